@@ -0,0 +1,75 @@
+use crate::commands::{sqlite_task, AppState};
+use crate::error::AppError;
+use crate::models::AuditEntry;
+use std::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+fn try_audit<E: Display>(
+    state: &State<'_, AppState>,
+    command: &str,
+    connection_id: &str,
+    database: Option<&str>,
+    sql: &str,
+    outcome: &Result<u64, E>,
+) -> Result<(), AppError> {
+    if !state.settings.lock()?.load()?.audit.enabled {
+        return Ok(());
+    }
+
+    let executed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    let entry = AuditEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        connection_id: connection_id.to_string(),
+        database: database.map(str::to_string),
+        command: command.to_string(),
+        sql: sql.to_string(),
+        rows_affected: outcome.as_ref().ok().copied(),
+        success: outcome.is_ok(),
+        error_message: outcome.as_ref().err().map(|e| e.to_string()),
+        executed_at,
+    };
+    let audit = state.audit.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let written = audit
+            .lock()
+            .map_err(AppError::from)
+            .and_then(|mut audit| audit.record(&entry).map_err(AppError::from));
+        if let Err(e) = written {
+            tracing::warn!(command = %entry.command, error = %e, "Failed to record audit entry");
+        }
+    });
+    Ok(())
+}
+
+/// Record a data-modifying statement when auditing is enabled. Auditing is
+/// best effort: a failed write is logged and never fails the command. The
+/// entry is written on the blocking pool, without holding up the command.
+pub(crate) fn record_audit<E: Display>(
+    state: &State<'_, AppState>,
+    command: &str,
+    connection_id: &str,
+    database: Option<&str>,
+    sql: &str,
+    outcome: &Result<u64, E>,
+) {
+    if let Err(e) = try_audit(state, command, connection_id, database, sql, outcome) {
+        tracing::warn!(command, error = %e, "Failed to record audit entry");
+    }
+}
+
+/// Recorded data-modifying statements, newest first
+#[tauri::command]
+pub async fn get_audit_log(
+    connection_id: Option<String>,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<AuditEntry>, AppError> {
+    sqlite_task(&state.audit, move |audit| {
+        audit.list(connection_id.as_deref(), limit)
+    })
+    .await
+}
@@ -0,0 +1,57 @@
+use crate::commands::{apply_auto_lock, sqlite_task, store_task, AppState};
+use crate::db::query_cache::QueryCache;
+use crate::error::AppError;
+use crate::storage::app_backup::{open_archive, seal_archive};
+use crate::storage::migrations::LATEST_VERSION;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// Write every connection, group, history entry, setting and workspace to
+/// one archive encrypted with `passphrase`, e.g. to move to another machine
+#[tauri::command]
+pub async fn backup_app_data(
+    path: String,
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::InvalidInput(
+            "A passphrase is required to encrypt the backup".to_string(),
+        ));
+    }
+    let database = store_task(&state, |store| store.export_database()).await?;
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    let contents = seal_archive(&database, LATEST_VERSION, created_at, &passphrase)?;
+    std::fs::write(&path, contents)?;
+    Ok(())
+}
+
+/// Replace all app data with an archive from `backup_app_data`. Returns
+/// where the data it replaced was backed up.
+#[tauri::command]
+pub async fn restore_app_data(
+    path: String,
+    passphrase: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let contents = std::fs::read_to_string(&path)?;
+    let database = open_archive(&contents, &passphrase)?;
+
+    let previous = store_task(&state, move |store| store.restore_database(&database)).await?;
+
+    // Settings came back with the data, so apply them as at startup
+    let settings = sqlite_task(&state.settings, |settings| settings.reload()).await?;
+    state.logs.set_level(settings.log_level)?;
+    state.pools.secrets.configure(&settings.vault);
+    apply_auto_lock(&settings, &state.connection_store)?;
+
+    // Cached pools, schemas and results belong to the replaced connections
+    state.pools.clear().await;
+    state.schemas.clear().await;
+    *state.query_cache.lock()? = QueryCache::default();
+    Ok(previous.display().to_string())
+}
@@ -1,6 +1,18 @@
-use crate::models::Connection;
+use crate::commands::{sqlite_task, store_task};
+use crate::db::pool_cache::CONNECTION_STATUS_EVENT;
+use crate::error::AppError;
+use crate::models::connection::SSHConfig;
+use crate::models::{
+    Connection, ConnectionGroup, ConnectionSort, ConnectionStatus, ConnectionStatusEvent,
+    ConnectionTree, ExternalTool, ImportStrategy, ImportSummary, StoreStatus,
+};
+use crate::storage::connection_transfer::{open_bundle, seal_bundle, ConnectionBundle};
+use crate::storage::importers;
+use std::path::Path;
 use crate::AppState;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::storage::connection_store::NewKey;
 
 #[cfg(test)]
 use crate::storage::connection_store::ConnectionStore;
@@ -9,67 +21,314 @@ use crate::storage::connection_store::ConnectionStore;
 pub async fn save_connection(
     connection: Connection,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .save_connection(&connection)
-        .map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    let saved = connection.clone();
+    store_task(&state, move |store| store.save_connection(&saved)).await?;
+
+    // Settings may have changed, so the next command reconnects
+    state.pools.invalidate(&connection.id).await;
+    state.schemas.invalidate(&connection.id).await;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn load_connection(
     id: String,
     state: State<'_, AppState>,
-) -> Result<Option<Connection>, String> {
-    state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&id)
-        .map_err(|e| e.to_string())
+) -> Result<Option<Connection>, AppError> {
+    store_task(&state, move |store| store.load_connection(&id)).await
 }
 
 #[tauri::command]
-pub async fn list_connections(state: State<'_, AppState>) -> Result<Vec<Connection>, String> {
-    state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .list_connections()
-        .map_err(|e| e.to_string())
+pub async fn duplicate_connection(
+    id: String,
+    new_name: String,
+    state: State<'_, AppState>,
+) -> Result<Connection, AppError> {
+    store_task(&state, move |store| store.duplicate_connection(&id, &new_name)).await
 }
 
 #[tauri::command]
-pub async fn delete_connection(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .delete_connection(&id)
-        .map_err(|e| e.to_string())
+pub async fn list_connections(
+    sort: Option<ConnectionSort>,
+    state: State<'_, AppState>,
+) -> Result<ConnectionTree, AppError> {
+    store_task(&state, move |store| {
+        store.list_connection_tree(sort.unwrap_or_default())
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn test_connection(connection: Connection) -> Result<String, String> {
-    if connection.host.is_empty() {
-        return Err("Host is required".to_string());
+pub async fn delete_connection(id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    let deleted = id.clone();
+    store_task(&state, move |store| store.delete_connection(&deleted)).await?;
+    let deleted = id.clone();
+    sqlite_task(&state.workspaces, move |workspaces| workspaces.delete(&deleted)).await?;
+
+    state.schedules.lock()?.remove_connection(&id);
+
+    state.pools.invalidate(&id).await;
+    state.schemas.invalidate(&id).await;
+    Ok(())
+}
+
+/// Roll back and close a connection's sessions, then close its pool and SSH
+/// tunnel. The next command on the connection connects again.
+async fn disconnect_connection(
+    connection_id: &str,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<(), AppError> {
+    for session in state.sessions.remove_connection(connection_id).await? {
+        // The connection is closed either way, which also ends the transaction
+        if let Err(e) = session.lock().await.rollback().await {
+            tracing::warn!(connection_id, error = %e, "Failed to roll back session");
+        }
+    }
+    state.pools.invalidate(connection_id).await;
+    tracing::info!(connection_id, "Disconnected");
+
+    let event = ConnectionStatusEvent {
+        connection_id: connection_id.to_string(),
+        status: ConnectionStatus::Disconnected,
+        error: None,
+        reason: None,
+    };
+    if let Err(e) = app.emit(CONNECTION_STATUS_EVENT, event) {
+        tracing::warn!(error = %e, "Failed to emit connection status");
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn disconnect(
+    connection_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    disconnect_connection(&connection_id, &app, &state).await
+}
+
+/// Disconnect every connection with a pool or an open session
+#[tauri::command]
+pub async fn disconnect_all(app: AppHandle, state: State<'_, AppState>) -> Result<(), AppError> {
+    let mut connection_ids = state.pools.connection_ids().await;
+    for id in state.sessions.connection_ids().await? {
+        if !connection_ids.contains(&id) {
+            connection_ids.push(id);
+        }
+    }
+    for connection_id in connection_ids {
+        disconnect_connection(&connection_id, &app, &state).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_connection_group(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<ConnectionGroup, AppError> {
+    store_task(&state, move |store| store.create_group(&name)).await
+}
+
+#[tauri::command]
+pub async fn rename_connection_group(
+    id: String,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    store_task(&state, move |store| store.rename_group(&id, &name)).await
+}
+
+#[tauri::command]
+pub async fn delete_connection_group(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    store_task(&state, move |store| store.delete_group(&id)).await
+}
+
+#[tauri::command]
+pub async fn move_connection_to_group(
+    connection_id: String,
+    group_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    store_task(&state, move |store| {
+        store.move_connection(&connection_id, group_id.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn export_connections(
+    path: String,
+    passphrase: String,
+    connection_ids: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    let bundle =
+        store_task(&state, move |store| store.export_bundle(connection_ids.as_deref())).await?;
+
+    let contents = seal_bundle(&bundle, &passphrase)?;
+    std::fs::write(&path, contents)?;
+
+    Ok(bundle.connections.len())
+}
+
+#[tauri::command]
+pub async fn import_connections(
+    path: String,
+    passphrase: String,
+    strategy: ImportStrategy,
+    state: State<'_, AppState>,
+) -> Result<ImportSummary, AppError> {
+    let contents = std::fs::read_to_string(&path)?;
+    let bundle = open_bundle(&contents, &passphrase)?;
+
+    store_task(&state, move |store| store.import_bundle(&bundle, strategy)).await
+}
+
+#[tauri::command]
+pub async fn import_external_connections(
+    tool: ExternalTool,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<ImportSummary, AppError> {
+    let connections = importers::import_from(tool, Path::new(&path))?;
+    let bundle = ConnectionBundle {
+        groups: vec![],
+        connections,
+    };
+
+    store_task(&state, move |store| {
+        store.import_bundle(&bundle, ImportStrategy::Merge)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_store_status(state: State<'_, AppState>) -> Result<StoreStatus, AppError> {
+    store_task(&state, |store| store.status()).await
+}
+
+/// Protect stored passwords with a master password, replacing any previous one
+#[tauri::command]
+pub async fn set_master_password(
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    if password.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Master password must not be empty".to_string(),
+        ));
+    }
+    store_task(&state, move |store| store.set_master_password(&password)).await?;
+    Ok(())
+}
+
+/// Re-encrypt the stored passwords with a key derived from a new master
+/// password, or with a random per-install key when `password` is None.
+/// Returns where the previous store file was backed up.
+#[tauri::command]
+pub async fn rotate_encryption_key(
+    password: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let new_key = match password {
+        Some(password) if password.is_empty() => {
+            return Err(AppError::InvalidInput(
+                "Master password must not be empty".to_string(),
+            ))
+        }
+        Some(password) => NewKey::Password(password),
+        None => NewKey::Random,
+    };
+    let backup = store_task(&state, |store| store.rotate_encryption_key(new_key)).await?;
+    Ok(backup.display().to_string())
+}
+
+#[tauri::command]
+pub async fn unlock_store(password: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    store_task(&state, move |store| store.unlock(&password)).await
+}
+
+/// Forget the master key now rather than waiting for auto-lock. Returns
+/// whether the store locked; without a master password it stays open.
+#[tauri::command]
+pub async fn lock_store(state: State<'_, AppState>) -> Result<bool, AppError> {
+    let locked = store_task(&state, |store| store.lock()).await?;
+    if locked {
+        state.pools.secrets.clear();
+    }
+    Ok(locked)
+}
+
+#[tauri::command]
+pub async fn parse_connection_url(url: String) -> Result<Connection, AppError> {
+    Connection::from_url(&url).map_err(AppError::InvalidInput)
+}
+
+/// Show what an SSH host resolves to once ~/.ssh/config is applied
+#[tauri::command]
+pub async fn resolve_ssh_config(ssh_config: SSHConfig) -> Result<SSHConfig, AppError> {
+    crate::ssh::config::resolve(&ssh_config).map_err(AppError::from)
+}
+
+/// Show what a connection resolves to once ~/.my.cnf or ~/.pgpass is applied
+#[tauri::command]
+pub async fn resolve_client_config(connection: Connection) -> Result<Connection, AppError> {
+    crate::db::client_config::resolve(&connection).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn test_connection(connection: Connection) -> Result<String, AppError> {
+    let connection = crate::db::placeholders::resolve(&connection)
+        .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    let connection = crate::db::client_config::resolve(&connection)?;
+    // Over SSH the socket is on the remote host, so it can't be checked here
+    if let (Some(socket), None) = (connection.socket_file(), &connection.ssh_config) {
+        if !is_socket(&socket) {
+            return Err(AppError::InvalidInput(format!(
+                "No socket at {}",
+                socket.display()
+            )));
+        }
+    } else if connection.host.is_empty() {
+        return Err(AppError::InvalidInput("Host is required".to_string()));
     }
     if connection.username.is_empty() {
-        return Err("Username is required".to_string());
+        return Err(AppError::InvalidInput("Username is required".to_string()));
     }
 
+    let address = match &connection.options.socket_path {
+        Some(socket) => socket.clone(),
+        None => format!("{}:{}", connection.host, connection.port),
+    };
     Ok(format!(
-        "Connection test successful to {}@{}:{}",
-        connection.username, connection.host, connection.port
+        "Connection test successful to {}@{}",
+        connection.username, address
     ))
 }
 
+#[cfg(unix)]
+fn is_socket(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket())
+}
+
+#[cfg(not(unix))]
+fn is_socket(path: &Path) -> bool {
+    path.exists()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::DatabaseType;
+    use crate::models::connection::ConnectionOptions;
+    use crate::models::{DatabaseType, Environment};
     use tempfile::TempDir;
 
     fn setup_test_store() -> (ConnectionStore, TempDir) {
@@ -82,7 +341,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_save_and_load_connection_via_store() {
-        let (mut store, _temp) = setup_test_store();
+        let (store, _temp) = setup_test_store();
 
         let connection = Connection {
             id: "test-1".to_string(),
@@ -96,6 +355,12 @@ mod tests {
             database: Some("test_db".to_string()),
             ssh_config: None,
             ssl_config: None,
+            group_id: None,
+            read_only: false,
+            environment: Environment::Dev,
+            options: ConnectionOptions::default(),
+            last_used_at: None,
+            use_count: 0,
         };
 
         // Save connection
@@ -112,7 +377,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_list_connections_via_store() {
-        let (mut store, _temp) = setup_test_store();
+        let (store, _temp) = setup_test_store();
 
         // Create 3 connections
         for i in 1..=3 {
@@ -128,6 +393,12 @@ mod tests {
                 database: None,
                 ssh_config: None,
                 ssl_config: None,
+                group_id: None,
+                read_only: false,
+                environment: Environment::Dev,
+                options: ConnectionOptions::default(),
+                last_used_at: None,
+                use_count: 0,
             };
             store.save_connection(&conn).unwrap();
         }
@@ -139,7 +410,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_delete_connection_via_store() {
-        let (mut store, _temp) = setup_test_store();
+        let (store, _temp) = setup_test_store();
 
         let connection = Connection {
             id: "test-delete".to_string(),
@@ -153,6 +424,12 @@ mod tests {
             database: None,
             ssh_config: None,
             ssl_config: None,
+            group_id: None,
+            read_only: false,
+            environment: Environment::Dev,
+            options: ConnectionOptions::default(),
+            last_used_at: None,
+            use_count: 0,
         };
 
         store.save_connection(&connection).unwrap();
@@ -179,23 +456,43 @@ mod tests {
             database: None,
             ssh_config: None,
             ssl_config: None,
+            group_id: None,
+            read_only: false,
+            environment: Environment::Dev,
+            options: ConnectionOptions::default(),
+            last_used_at: None,
+            use_count: 0,
         };
 
         // Should fail with empty host
         let result = test_connection(connection.clone()).await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Host is required"));
+        assert!(result.unwrap_err().to_string().contains("Host is required"));
 
         // Should fail with empty username
         connection.host = "localhost".to_string();
         connection.username = "".to_string();
         let result = test_connection(connection.clone()).await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Username is required"));
+        assert!(result.unwrap_err().to_string().contains("Username is required"));
 
         // Should succeed with valid data
         connection.username = "root".to_string();
-        let result = test_connection(connection).await;
+        let result = test_connection(connection.clone()).await;
         assert!(result.is_ok());
+
+        // A socket must exist and be a socket
+        let temp = TempDir::new().unwrap();
+        let socket = temp.path().join("mysqld.sock");
+        connection.options.socket_path = Some(socket.display().to_string());
+        let result = test_connection(connection.clone()).await;
+        assert!(result.unwrap_err().to_string().contains("No socket at"));
+
+        #[cfg(unix)]
+        {
+            let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+            connection.host = String::new();
+            assert!(test_connection(connection).await.is_ok());
+        }
     }
 }
@@ -1,32 +1,94 @@
-use crate::commands::AppState;
-use crate::db::MySQLAdapter;
+use crate::commands::{find_connection, record_audit, AppState};
+use crate::db::fixtures::{load_fixture, Fixture};
+use crate::db::sql::{self, StatementKind};
+use crate::error::AppError;
+use crate::models::{ServerInfo, SlowQuery};
 use tauri::State;
 
 #[tauri::command]
-pub async fn list_databases(connection_id: String, state: State<'_, AppState>) -> Result<Vec<String>, String> {
-    // Load connection from store
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", connection_id))?;
-
-    // Create adapter
-    let adapter = MySQLAdapter::new(&connection)
-        .await
-        .map_err(|e| e.to_string())?;
+pub async fn list_databases(connection_id: String, state: State<'_, AppState>) -> Result<Vec<String>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+
+    // Get the cached adapter
+    let adapter = state.pools.get_or_connect(&connection).await?;
 
     // Get list of databases
-    let databases = adapter
-        .list_databases()
-        .await
-        .map_err(|e| e.to_string())?;
+    let databases = adapter.list_databases().await?;
 
     Ok(databases)
 }
 
+/// Schemas of a database, for servers whose databases hold schemas; empty
+/// when tables belong to the database directly, as on MySQL
+#[tauri::command]
+pub async fn list_schemas(
+    connection_id: String,
+    database: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    Ok(adapter.list_schemas(&database).await?)
+}
+
+/// Server version and time zones, so TIMESTAMP values can be read unambiguously
+#[tauri::command]
+pub async fn get_server_info(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<ServerInfo, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    Ok(adapter.server_info().await?)
+}
+
+/// The `limit` statements the server spent the most time on in total, from
+/// its statement digests, with their calls, latencies and rows examined.
+/// Needs performance_schema and SELECT on it.
+#[tauri::command]
+pub async fn get_slow_queries(
+    connection_id: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<SlowQuery>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    Ok(adapter.slow_queries(limit).await?)
+}
+
+/// Create (or reset) a sample shop database to explore the app with
+#[tauri::command]
+pub async fn create_demo_database(
+    connection_id: String,
+    database: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    let fixture = Fixture::default();
+    let outcome = load_fixture(&adapter, &database, &fixture).await;
+
+    // Audit only the schema changes; the generated rows would swamp the log
+    let ddl: Vec<String> = fixture
+        .statements(&database)
+        .into_iter()
+        .filter(|statement| sql::classify(statement) == StatementKind::Ddl)
+        .collect();
+    record_audit(
+        &state,
+        "create_demo_database",
+        &connection_id,
+        Some(&database),
+        &ddl.join(";\n"),
+        &outcome.as_ref().map(|_| 0),
+    );
+    outcome?;
+
+    state.schemas.invalidate(&connection_id).await;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
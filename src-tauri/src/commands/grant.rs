@@ -0,0 +1,124 @@
+use crate::commands::{find_connection, record_audit, AppState};
+use crate::db::grants::{
+    create_user_statements, privilege_statements, show_grants_query, GrantAction,
+};
+use crate::db::sql;
+use crate::error::AppError;
+use crate::models::{AdminStatements, Connection, CreateUserRequest, PrivilegeRequest};
+use tauri::State;
+
+/// Execute generated statements unless only a preview was requested.
+/// `audited_sql` is what the audit log records for them.
+async fn run_statements(
+    state: &State<'_, AppState>,
+    connection: &Connection,
+    command: &str,
+    statements: Vec<String>,
+    audited_sql: String,
+    preview: bool,
+) -> Result<AdminStatements, AppError> {
+    if !preview {
+        let adapter = state.pools.get_or_connect(connection).await?;
+        let outcome = adapter.execute_statements(&statements).await;
+        record_audit(
+            state,
+            command,
+            &connection.id,
+            None,
+            &audited_sql,
+            &outcome.as_ref().map(|_| 0),
+        );
+        outcome?;
+    }
+
+    Ok(AdminStatements {
+        statements,
+        executed: !preview,
+    })
+}
+
+#[tauri::command]
+pub async fn create_database_user(
+    request: CreateUserRequest,
+    state: State<'_, AppState>,
+) -> Result<AdminStatements, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    let statements =
+        create_user_statements(&connection.db_type, &request).map_err(AppError::InvalidInput)?;
+    // Keep the new user's password out of the audit log
+    let audited_sql = statements
+        .iter()
+        .map(|statement| sql::redact_literals(statement))
+        .collect::<Vec<_>>()
+        .join(";\n");
+    run_statements(
+        &state,
+        &connection,
+        "create_database_user",
+        statements,
+        audited_sql,
+        request.preview,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn grant_privileges(
+    request: PrivilegeRequest,
+    state: State<'_, AppState>,
+) -> Result<AdminStatements, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    let statements = privilege_statements(&connection.db_type, GrantAction::Grant, &request)
+        .map_err(AppError::InvalidInput)?;
+    let audited_sql = statements.join(";\n");
+    run_statements(
+        &state,
+        &connection,
+        "grant_privileges",
+        statements,
+        audited_sql,
+        request.preview,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn revoke_privileges(
+    request: PrivilegeRequest,
+    state: State<'_, AppState>,
+) -> Result<AdminStatements, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    let statements = privilege_statements(&connection.db_type, GrantAction::Revoke, &request)
+        .map_err(AppError::InvalidInput)?;
+    let audited_sql = statements.join(";\n");
+    run_statements(
+        &state,
+        &connection,
+        "revoke_privileges",
+        statements,
+        audited_sql,
+        request.preview,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn show_grants(
+    connection_id: String,
+    username: String,
+    host: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    adapter
+        .fetch_strings(&show_grants_query(
+            &connection.db_type,
+            &username,
+            host.as_deref(),
+        ))
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,30 @@
+use crate::commands::{sqlite_task, AppState};
+use crate::error::AppError;
+use crate::models::{LogEntry, LogLevel};
+use tauri::State;
+
+/// Recent application log entries at `level` or more severe, newest first
+#[tauri::command]
+pub async fn get_app_logs(
+    level: Option<LogLevel>,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<LogEntry>, AppError> {
+    state
+        .logs
+        .read(level.unwrap_or(LogLevel::Trace), limit)
+        .map_err(AppError::from)
+}
+
+/// Change how much is logged, and remember it for the next start
+#[tauri::command]
+pub async fn set_log_level(level: LogLevel, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.logs.set_level(level)?;
+
+    sqlite_task(&state.settings, move |settings| {
+        let mut current = settings.load()?;
+        current.log_level = level;
+        settings.save(&current)
+    })
+    .await
+}
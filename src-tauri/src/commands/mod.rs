@@ -1,14 +1,127 @@
+pub mod audit;
+pub mod backup;
 pub mod connection;
 pub mod database;
+pub mod grant;
+pub mod logs;
 pub mod query;
+pub mod schedule;
 pub mod schema;
+pub mod settings;
 pub mod table;
+pub mod task;
+pub mod template;
+pub mod window;
+pub mod workspace;
 
+pub use audit::*;
+pub use backup::*;
 pub use connection::*;
 pub use database::*;
+pub use grant::*;
+pub use logs::*;
 pub use query::*;
+pub use schedule::*;
 pub use schema::*;
+pub use settings::*;
 pub use table::*;
+pub use task::*;
+pub use template::*;
+pub use window::*;
+pub use workspace::*;
 
 // Re-export AppState from main
 pub use crate::AppState;
+
+use crate::error::AppError;
+
+use crate::models::{Connection, EnvironmentPolicy};
+use crate::storage::connection_store::{ConnectionStore, StoreError};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// Load a stored connection by id, failing when it doesn't exist. Counts as a
+/// use of the connection for recency sorting. A connection whose environment
+/// policy is read-only comes back read-only.
+pub(crate) async fn find_connection(
+    connection_id: &str,
+    state: &State<'_, AppState>,
+) -> Result<Connection, AppError> {
+    let id = connection_id.to_string();
+    let mut connection = store_task(state, move |store| store.load_connection(&id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {}", connection_id)))?;
+
+    let used_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    // Usage stats are best effort; they must not fail the command
+    let id = connection_id.to_string();
+    let recorded = store_task(state, move |store| store.record_connection_use(&id, used_at));
+    if let Err(e) = recorded.await {
+        tracing::warn!(connection_id, error = %e, "Failed to record connection use");
+    }
+
+    connection.read_only |= environment_policy(&connection, state)?.read_only;
+    Ok(connection)
+}
+
+/// Run a connection store call on the blocking pool. Every call waits on
+/// SQLite, and some on much more: deriving a key from a password,
+/// re-encrypting the passwords or copying the database file.
+pub(crate) async fn store_task<T, F>(state: &AppState, task: F) -> Result<T, AppError>
+where
+    F: FnOnce(&ConnectionStore) -> Result<T, StoreError> + Send + 'static,
+    T: Send + 'static,
+{
+    let store = state.connection_store.clone();
+    tauri::async_runtime::spawn_blocking(move || task(&store))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map_err(AppError::from)
+}
+
+/// Run a call on one of the other SQLite stores (history, audit, settings,
+/// workspaces) on the blocking pool, like `store_task`
+pub(crate) async fn sqlite_task<S, T, F>(store: &Arc<Mutex<S>>, task: F) -> Result<T, AppError>
+where
+    S: Send + 'static,
+    F: FnOnce(&mut S) -> Result<T, StoreError> + Send + 'static,
+    T: Send + 'static,
+{
+    let store = store.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut store = store.lock()?;
+        task(&mut store).map_err(AppError::from)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+}
+
+/// The guardrails of a connection's environment
+pub(crate) fn environment_policy(
+    connection: &Connection,
+    state: &State<'_, AppState>,
+) -> Result<EnvironmentPolicy, AppError> {
+    let guardrails = state.settings.lock()?.load()?.guardrails;
+    Ok(guardrails.policy(connection.environment))
+}
+
+/// Fail a write to a connection whose environment wants its name typed as
+/// confirmation, unless `confirmation` is that name. Read-only connections
+/// reject the write anyway, so they aren't asked.
+pub(crate) fn check_typed_confirmation(
+    connection: &Connection,
+    state: &State<'_, AppState>,
+    confirmation: Option<&str>,
+) -> Result<(), AppError> {
+    if !connection.read_only
+        && environment_policy(connection, state)?.confirm_writes
+        && confirmation.map(str::trim) != Some(connection.name.as_str())
+    {
+        return Err(AppError::TypedConfirmation(connection.name.clone()));
+    }
+    Ok(())
+}
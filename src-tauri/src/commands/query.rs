@@ -1,43 +1,764 @@
-use crate::commands::AppState;
-use crate::db::MySQLAdapter;
-use crate::models::{QueryRequest, QueryResult};
-use tauri::State;
+use crate::commands::{
+    check_typed_confirmation, environment_policy, find_connection, record_audit, sqlite_task,
+    start_task, AppState,
+};
+use crate::db::execution_queue::QUERY_QUEUE_EVENT;
+use crate::db::masking::{self, MaskRules};
+use crate::db::mysql_adapter::{DatabaseError, MySQLAdapter, ResultLimits};
+use crate::db::result_cache::{ResultError, IPC_PAGE_SIZE};
+use crate::db::session::{Session, TransactionTracker};
+use crate::db::sql::{self, StatementKind};
+use crate::db::{diff, export, query_stats};
+use crate::db::{validate, values};
+use crate::error::AppError;
+use crate::models::{
+    DestructiveRisk, DiffRequest, DiffSide, ExportFormat, ExportRequest, GroupedResult,
+    LimitGuardMode, MaskedCell, ProcedureParameter, ProcedureResult, QueryHistoryEntry,
+    QueryRequest, QueryResult, QuerySearchMatch, QueryStats, ResultDiff, ResultViewRequest,
+    SessionState, SortOrder, TaskKind, TimeRange, ValidationResult, ValidationSource,
+    WatchdogConfig,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, State};
+
+/// Number of past parameter sets offered for a statement
+const PARAMETER_SUGGESTION_LIMIT: usize = 10;
+
+/// History matches returned when the caller doesn't ask for a number
+const DEFAULT_SEARCH_RESULTS: usize = 50;
 
 #[tauri::command]
 pub async fn execute_query(
     request: QueryRequest,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<QueryResult, String> {
-    // Load connection from store
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&request.connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", request.connection_id))?;
-
-    // Create adapter
-    let adapter = MySQLAdapter::new(&connection)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    // Execute query with optional pagination and database selection
-    let result = if let (Some(page), Some(page_size)) = (request.page, request.page_size) {
-        adapter
-            .execute_paginated(&request.sql, page, page_size)
-            .await
-            .map_err(|e| e.to_string())?
+) -> Result<QueryResult, AppError> {
+    let executed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    let connection = find_connection(&request.connection_id, &state).await?;
+    let masking = MaskRules::new(&connection.options.masked_columns);
+
+    // Only plain reads outside sessions are served from the cache; a session
+    // may see its own uncommitted changes
+    let kind = sql::classify_script(&request.sql);
+    let cacheable = request.session_id.is_none() && sql::is_cacheable(&request.sql);
+    if cacheable && !request.force_refresh {
+        let cached = state.query_cache.lock()?.get(&request);
+        if let Some(result) = cached {
+            return deliver(&state, result, &masking);
+        }
+    }
+
+    // Schema changes and writes are held to the environment's guardrails
+    let policy = environment_policy(&connection, &state)?;
+    if policy.block_ddl && kind == StatementKind::Ddl {
+        return Err(AppError::Guardrail(format!(
+            "Schema changes are blocked on {} connections",
+            connection.environment
+        )));
+    }
+    if matches!(kind, StatementKind::Dml | StatementKind::Ddl) {
+        check_typed_confirmation(&connection, &state, request.confirmation.as_deref())?;
+    }
+
+    // Statements that may lose data only run once the user confirmed them;
+    // a read-only connection rejects them without asking
+    if !request.confirmed && !connection.read_only {
+        if let Some(risk) = sql::destructive_risk(&request.sql, &connection.db_type) {
+            return Err(AppError::NeedsConfirmation(risk));
+        }
+    }
+
+    // Keep a SELECT of a whole table from flooding the app; paginated
+    // requests already have a LIMIT. An environment may force the LIMIT on.
+    let guard = state.settings.lock()?.load()?.limit_guard;
+    let mode = if policy.force_limit {
+        LimitGuardMode::Append
     } else {
-        adapter
-            .execute_query_with_database(&request.sql, request.database.as_deref())
+        guard.mode
+    };
+    let unbounded = mode != LimitGuardMode::Off
+        && request.page.is_none()
+        && kind == StatementKind::Query
+        && sql::is_unbounded_select(&request.sql, &connection.db_type);
+    let auto_limit = (unbounded && mode == LimitGuardMode::Append).then_some(guard.default_limit);
+    let statement = match auto_limit {
+        Some(limit) => sql::append_limit(&request.sql, limit),
+        None => request.sql.clone(),
+    };
+
+    // Statements beyond the connection's limit wait for a running one to finish
+    let permit = state
+        .queue
+        .acquire(
+            &connection.id,
+            request.request_id.clone(),
+            connection.options.max_concurrent_queries,
+            move |position| {
+                if let Err(e) = app.emit(QUERY_QUEUE_EVENT, position) {
+                    tracing::warn!(error = %e, "Failed to emit queue position");
+                }
+            },
+        )
+        .await;
+    let start = Instant::now();
+
+    let outcome = if let Some(session_id) = &request.session_id {
+        // Statements in a session share one connection so transactions span calls
+        let session = state.sessions.get(session_id)?;
+        let mut session = session.lock().await;
+        session
+            .execute(&statement, request.database.as_deref(), &request.params)
             .await
-            .map_err(|e| e.to_string())?
+    } else {
+        let adapter = state.pools.get_or_connect(&connection).await?;
+
+        // Execute query with optional pagination and database selection
+        if let (Some(page), Some(page_size)) = (request.page, request.page_size) {
+            adapter
+                .execute_paginated(
+                    &statement,
+                    request.database.as_deref(),
+                    page,
+                    page_size,
+                    &request.params,
+                )
+                .await
+        } else {
+            adapter
+                .execute_query_with_database(&statement, request.database.as_deref(), &request.params)
+                .await
+        }
+    };
+    drop(permit);
+    let outcome = outcome.map(|mut result| {
+        if let Some(limit) = auto_limit {
+            result.auto_limit = Some(limit);
+            // Hitting the limit means there may have been more rows
+            result.truncated |= result.total_rows >= limit as usize;
+        }
+        result.unbounded = unbounded && auto_limit.is_none();
+        result
+    });
+
+    let entry = QueryHistoryEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        connection_id: request.connection_id.clone(),
+        database: request.database.clone(),
+        sql: request.sql.clone(),
+        fingerprint: sql::fingerprint(&request.sql),
+        params: request.params.clone(),
+        executed_at,
+        execution_time_ms: start.elapsed().as_millis() as u64,
+        success: outcome.is_ok(),
+        error_message: outcome.as_ref().err().map(|e| e.to_string()),
+    };
+    // History is best effort; a failed write must not fail the query itself
+    let recorded = entry.clone();
+    let written = sqlite_task(&state.query_history, move |history| history.record(&recorded));
+    if let Err(e) = written.await {
+        tracing::warn!(error = %e, "Failed to record query history");
+    }
+
+    let statement = sql::redact_literals(&entry.fingerprint);
+    match &outcome {
+        Ok(_) => tracing::info!(
+            connection_id = %request.connection_id,
+            elapsed_ms = entry.execution_time_ms,
+            %statement,
+            "Executed statement"
+        ),
+        Err(e) => tracing::warn!(
+            connection_id = %request.connection_id,
+            elapsed_ms = entry.execution_time_ms,
+            %statement,
+            error = %e,
+            "Statement failed"
+        ),
+    }
+
+    if matches!(kind, StatementKind::Dml | StatementKind::Ddl) {
+        record_audit(
+            &state,
+            "execute_query",
+            &request.connection_id,
+            request.database.as_deref(),
+            &request.sql,
+            &outcome.as_ref().map(|result| result.rows_affected),
+        );
+    }
+
+    let result = outcome?;
+
+    // Cached schemas no longer match once tables change
+    if kind == StatementKind::Ddl {
+        state.schemas.invalidate(&request.connection_id).await;
+    }
+    if cacheable {
+        state
+            .query_cache
+            .lock()?
+            .insert(&request, &result, executed_at);
+    } else if kind != StatementKind::Query {
+        // Anything else may have changed data behind cached results
+        state.query_cache.lock()?.invalidate(&request.connection_id);
+    }
+
+    deliver(&state, result, &masking)
+}
+
+/// Parameters of a stored procedure, for building a call form
+#[tauri::command]
+pub async fn get_procedure_parameters(
+    connection_id: String,
+    database: String,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ProcedureParameter>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    adapter
+        .procedure_parameters(&database, &name)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Procedure {}", name)))
+}
+
+/// Call a stored procedure with values for its parameters by name, returning
+/// its result sets and the values of its OUT and INOUT parameters
+#[tauri::command]
+pub async fn call_procedure(
+    connection_id: String,
+    database: String,
+    name: String,
+    params: HashMap<String, serde_json::Value>,
+    confirmation: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ProcedureResult, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    check_typed_confirmation(&connection, &state, confirmation.as_deref())?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let parameters = adapter
+        .procedure_parameters(&database, &name)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Procedure {}", name)))?;
+
+    let outcome = adapter
+        .call_procedure(&database, &name, &parameters, &params)
+        .await;
+    record_audit(
+        &state,
+        "call_procedure",
+        &connection_id,
+        Some(&database),
+        &MySQLAdapter::call_procedure_sql(&database, &name, &parameters),
+        &outcome.as_ref().map(|called| called.result.rows_affected),
+    );
+    // The procedure may have changed anything
+    state.query_cache.lock()?.invalidate(&connection_id);
+
+    let mut called = outcome?;
+    let masking = MaskRules::new(&connection.options.masked_columns);
+    called.result = deliver(&state, called.result, &masking)?;
+    Ok(called)
+}
+
+/// Keep the full result for paging and send the first page of it, and of
+/// every further result set, with masked columns hidden
+fn deliver(
+    state: &AppState,
+    mut result: QueryResult,
+    masking: &MaskRules,
+) -> Result<QueryResult, AppError> {
+    let max_cell_length = state.settings.lock()?.load()?.max_cell_length;
+    masking.flag(&mut result.column_types, None);
+    for set in &mut result.more_results {
+        masking.flag(&mut set.column_types, None);
+    }
+
+    let mut cache = state.result_cache.lock()?;
+    let result_id = cache.insert(&result);
+    for set in &mut result.more_results {
+        set.result_id = Some(cache.insert_set(set));
+    }
+
+    // Keep IPC messages small; the UI pages through the rest from the cache.
+    // Part of the first page may already be on disk.
+    if result.spilled.is_some() {
+        result.rows = cache.get(&result_id)?.page(&result_id, 0, IPC_PAGE_SIZE)?.rows;
+        result.page_size = Some(IPC_PAGE_SIZE);
+    } else if result.rows.len() > IPC_PAGE_SIZE {
+        result.rows.truncate(IPC_PAGE_SIZE);
+        result.page_size = Some(IPC_PAGE_SIZE);
+    }
+    for set in &mut result.more_results {
+        if set.rows.len() > IPC_PAGE_SIZE {
+            set.rows.truncate(IPC_PAGE_SIZE);
+            set.page_size = Some(IPC_PAGE_SIZE);
+        }
+    }
+
+    result.result_id = Some(result_id);
+    masking::mask_rows(&mut result.rows, &result.column_types);
+    for set in &mut result.more_results {
+        masking::mask_rows(&mut set.rows, &set.column_types);
+    }
+    truncate_cells(&mut result, max_cell_length);
+    Ok(result)
+}
+
+/// Cut long text and JSON cells of a result sent to the grid; the cached
+/// result keeps them whole for paging and exports
+fn truncate_cells(result: &mut QueryResult, max_chars: Option<usize>) {
+    let Some(max_chars) = max_chars else {
+        return;
+    };
+    values::truncate_rows(&mut result.rows, &mut result.column_types, max_chars);
+    for set in &mut result.more_results {
+        values::truncate_rows(&mut set.rows, &mut set.column_types, max_chars);
+    }
+}
+
+/// Check a script without running it, so the editor can mark problems. Each
+/// statement is parsed; with `server_check` the server also plans queries and
+/// prepares DML, and its verdict wins over the parser's, which doesn't know
+/// every dialect extension.
+#[tauri::command]
+pub async fn validate_query(
+    sql: String,
+    connection_id: String,
+    database: Option<String>,
+    server_check: bool,
+    state: State<'_, AppState>,
+) -> Result<ValidationResult, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let statements = match validate::statements(&sql, &connection.db_type) {
+        Ok(statements) => statements,
+        Err(issue) => {
+            return Ok(ValidationResult {
+                valid: false,
+                issues: vec![issue],
+            })
+        }
+    };
+    let adapter = if server_check {
+        Some(state.pools.get_or_connect(&connection).await?)
+    } else {
+        None
+    };
+
+    let mut issues = Vec::new();
+    for range in statements {
+        if let Some(adapter) = &adapter {
+            let statement = &sql[range.clone()];
+            match adapter.validate_statement(statement, database.as_deref()).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(DatabaseError::Server {
+                    message,
+                    number,
+                    position,
+                    ..
+                }) => {
+                    issues.push(validate::issue(
+                        &sql,
+                        message,
+                        ValidationSource::Server,
+                        position.map(|offset| range.start + offset),
+                        &range,
+                        Some(number),
+                    ));
+                    continue;
+                }
+                Err(e @ DatabaseError::PermissionDenied { .. }) => {
+                    issues.push(validate::issue(
+                        &sql,
+                        e.to_string(),
+                        ValidationSource::Server,
+                        None,
+                        &range,
+                        None,
+                    ));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        issues.extend(validate::check_syntax(&sql, range, &connection.db_type));
+    }
+
+    Ok(ValidationResult {
+        valid: issues.is_empty(),
+        issues,
+    })
+}
+
+/// Pin a connection for a sequence of statements, returning the session id
+#[tauri::command]
+pub async fn open_session(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let conn = adapter.acquire().await?;
+
+    state
+        .sessions
+        .insert(Session {
+            connection_id,
+            username: connection.username.clone(),
+            read_only: connection.read_only,
+            limits: ResultLimits::from_options(&connection.options),
+            conn,
+            transaction: TransactionTracker::default(),
+        })
+        .map_err(AppError::from)
+}
+
+/// Release a session's connection. MySQL rolls back any transaction still open.
+#[tauri::command]
+pub async fn close_session(session_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    let session = state.sessions.remove(&session_id)?;
+    if let Some(session) = session {
+        session.lock().await.rollback().await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_session_state(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<SessionState, AppError> {
+    let session = state.sessions.get(&session_id)?;
+    let session = session.lock().await;
+    Ok(session.state(&session_id))
+}
+
+#[tauri::command]
+pub async fn get_watchdog_config(state: State<'_, AppState>) -> Result<WatchdogConfig, AppError> {
+    Ok(state.watchdog.lock()?.clone())
+}
+
+#[tauri::command]
+pub async fn update_watchdog_config(
+    config: WatchdogConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    *state.watchdog.lock()? = config;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn group_result(
+    result_id: String,
+    columns: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<GroupedResult, AppError> {
+    state
+        .result_cache
+        .lock()?
+        .get(&result_id)
+        .and_then(|result| result.group_by(&columns))
+        .map_err(AppError::from)
+}
+
+/// Run two queries, or one query on two connections, and compare their rows
+#[tauri::command]
+pub async fn diff_results(
+    request: DiffRequest,
+    state: State<'_, AppState>,
+) -> Result<ResultDiff, AppError> {
+    let left = run_diff_side(&request.left, &state).await?;
+    let right = run_diff_side(&request.right, &state).await?;
+    let mut diff = diff::compare(&left, &right, &request.key_columns)?;
+
+    // Compared in full, but a column masked on either side stays hidden
+    let masked_on = |result: &QueryResult, column: &String| {
+        result
+            .column_types
+            .iter()
+            .any(|t| t.masked && &t.name == column)
     };
+    let flags = |columns: &[String]| -> Vec<bool> {
+        columns
+            .iter()
+            .map(|column| masked_on(&left, column) || masked_on(&right, column))
+            .collect()
+    };
+    let (masked, masked_keys) = (flags(&diff.columns), flags(&diff.key_columns));
+    for row in diff.added.iter_mut().chain(&mut diff.removed) {
+        masking::mask_row(row, &masked);
+    }
+    for change in &mut diff.changed {
+        masking::mask_row(&mut change.left, &masked);
+        masking::mask_row(&mut change.right, &masked);
+        masking::mask_row(&mut change.key, &masked_keys);
+    }
+    Ok(diff)
+}
+
+async fn run_diff_side(
+    side: &DiffSide,
+    state: &State<'_, AppState>,
+) -> Result<QueryResult, AppError> {
+    // Comparing must never change either side
+    if sql::classify(&side.sql) != StatementKind::Query {
+        return Err(AppError::InvalidInput("Only queries can be compared".to_string()));
+    }
+    let connection = find_connection(&side.connection_id, state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let mut result = adapter
+        .execute_query_with_database(&side.sql, side.database.as_deref(), &side.params)
+        .await?;
+    MaskRules::new(&connection.options.masked_columns).flag(&mut result.column_types, None);
+    match result.spilled {
+        Some(_) => Err(ResultError::Spilled("compare").into()),
+        None => Ok(result),
+    }
+}
 
+#[tauri::command]
+pub async fn fetch_result_page(
+    result_id: String,
+    page: u32,
+    state: State<'_, AppState>,
+) -> Result<QueryResult, AppError> {
+    let max_cell_length = state.settings.lock()?.load()?.max_cell_length;
+    let mut result = state
+        .result_cache
+        .lock()?
+        .get(&result_id)
+        .and_then(|result| result.page(&result_id, page, IPC_PAGE_SIZE))?;
+    truncate_cells(&mut result, max_cell_length);
     Ok(result)
 }
 
+/// The full value of a cell its connection's masking rules hide. Asks for
+/// confirmation first, so a value isn't shown by accident on a shared screen.
+#[tauri::command]
+pub async fn reveal_masked_cell(
+    cell: MaskedCell,
+    confirmed: bool,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, AppError> {
+    if !confirmed {
+        return Err(AppError::NeedsConfirmation(DestructiveRisk::RevealMasked));
+    }
+    match cell {
+        MaskedCell::Result {
+            result_id,
+            row,
+            column,
+        } => {
+            let value = state.result_cache.lock()?.get(&result_id)?.reveal(row, &column)?;
+            tracing::info!(%result_id, row, %column, "Revealed masked cell");
+            Ok(value)
+        }
+        MaskedCell::Table {
+            connection_id,
+            database,
+            table,
+            primary_key,
+            column,
+        } => {
+            let connection = find_connection(&connection_id, &state).await?;
+            let adapter = state.pools.get_or_connect(&connection).await?;
+            let field = adapter
+                .get_cell_value(&database, &table, &primary_key, &column)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Row of {}", table)))?;
+            tracing::info!(%connection_id, %database, %table, %column, "Revealed masked cell");
+            Ok(field.value)
+        }
+    }
+}
+
+/// Free a cached result once the UI no longer shows it
+#[tauri::command]
+pub async fn release_result(result_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state
+        .result_cache
+        .lock()?
+        .remove(&result_id);
+    Ok(())
+}
+
+/// Sort and filter a cached result without going back to the server, and
+/// return one page of the outcome. Spilled rows are included; the sort and
+/// filter set with `sort_result` and `filter_result` aren't changed.
+#[tauri::command]
+pub async fn view_result(
+    request: ResultViewRequest,
+    state: State<'_, AppState>,
+) -> Result<QueryResult, AppError> {
+    let max_cell_length = state.settings.lock()?.load()?.max_cell_length;
+    let mut result = state
+        .result_cache
+        .lock()?
+        .get(&request.result_id)
+        .and_then(|result| result.view(&request))?;
+    truncate_cells(&mut result, max_cell_length);
+    Ok(result)
+}
+
+/// Sort a cached result by one column, spilled rows included, and return
+/// its first page. No column restores the fetched order; a filter set with
+/// `filter_result` still applies.
+#[tauri::command]
+pub async fn sort_result(
+    result_id: String,
+    column: Option<String>,
+    order: SortOrder,
+    state: State<'_, AppState>,
+) -> Result<QueryResult, AppError> {
+    let max_cell_length = state.settings.lock()?.load()?.max_cell_length;
+    let mut cache = state.result_cache.lock()?;
+    let cached = cache.get_mut(&result_id)?;
+    cached.sort(column.as_deref(), order)?;
+    let mut result = cached.page(&result_id, 0, IPC_PAGE_SIZE)?;
+    truncate_cells(&mut result, max_cell_length);
+    Ok(result)
+}
+
+/// Keep the rows of a cached result matching a SQL expression such as
+/// `status = 'paid' AND total > 100`, and return the first page of them. A
+/// blank expression shows every row again.
+#[tauri::command]
+pub async fn filter_result(
+    result_id: String,
+    expression: String,
+    state: State<'_, AppState>,
+) -> Result<QueryResult, AppError> {
+    let max_cell_length = state.settings.lock()?.load()?.max_cell_length;
+    let mut cache = state.result_cache.lock()?;
+    let cached = cache.get_mut(&result_id)?;
+    cached.filter(&expression)?;
+    let mut result = cached.page(&result_id, 0, IPC_PAGE_SIZE)?;
+    truncate_cells(&mut result, max_cell_length);
+    Ok(result)
+}
+
+/// Write the rows of a cached result to a file, not just the pages the UI
+/// has shown. Runs as a background task; returns its id.
+#[tauri::command]
+pub async fn export_result(
+    request: ExportRequest,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let result = state
+        .result_cache
+        .lock()?
+        .get(&request.result_id)?
+        .project(&request.columns, request.row_limit)?;
+    let path = PathBuf::from(&request.path);
+    let task = start_task(
+        &app,
+        &state,
+        TaskKind::Export,
+        request.path.clone(),
+        Some(result.row_count() as u64),
+    )?;
+
+    // Large results take a while to encode; keep that off the async workers
+    let worker = task.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut progress = |rows: usize, bytes| worker.progress(rows as u64, bytes);
+        let outcome = export::write(&result, request.format, &path, &mut progress);
+        if let Err(e) = &outcome {
+            tracing::warn!(path = %path.display(), error = %e, "Export failed");
+        }
+        worker.finish(outcome.map(|rows| rows as u64).map_err(|e| e.to_string()));
+    });
+    Ok(task.id())
+}
+
+/// A cached result as a Markdown or HTML table, for copying to the clipboard
+#[tauri::command]
+pub async fn render_result(
+    result_id: String,
+    format: ExportFormat,
+    columns: Option<Vec<String>>,
+    row_limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let result = state
+        .result_cache
+        .lock()?
+        .get(&result_id)?
+        .project(&columns.unwrap_or_default(), row_limit)?;
+    export::render(&result, format)
+        .ok_or_else(|| AppError::InvalidInput(format!("{:?} is not a text format", format)))
+}
+
+#[tauri::command]
+pub async fn get_query_history(
+    connection_id: String,
+    limit: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<QueryHistoryEntry>, AppError> {
+    sqlite_task(&state.query_history, move |history| history.list(&connection_id, limit)).await
+}
+
+/// Statements from the history whose text has every word of `text`, best
+/// match first, with the matching part highlighted. Searches every
+/// connection when `connection_id` is None.
+#[tauri::command]
+pub async fn search_queries(
+    text: String,
+    connection_id: Option<String>,
+    date_range: Option<TimeRange>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<QuerySearchMatch>, AppError> {
+    sqlite_task(&state.query_history, move |history| {
+        history.search(
+            &text,
+            connection_id.as_deref(),
+            &date_range.unwrap_or_default(),
+            limit.unwrap_or(DEFAULT_SEARCH_RESULTS),
+        )
+    })
+    .await
+}
+
+/// Slowest and most frequent statements, error rate and time per table,
+/// from the executions recorded for a connection within `time_range`
+#[tauri::command]
+pub async fn get_query_stats(
+    connection_id: String,
+    time_range: TimeRange,
+    state: State<'_, AppState>,
+) -> Result<QueryStats, AppError> {
+    let totals = sqlite_task(&state.query_history, move |history| {
+        history.fingerprint_totals(&connection_id, &time_range)
+    })
+    .await?;
+    Ok(query_stats::summarize(totals, query_stats::TOP_QUERIES))
+}
+
+#[tauri::command]
+pub async fn get_parameter_suggestions(
+    connection_id: String,
+    sql: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Vec<serde_json::Value>>, AppError> {
+    sqlite_task(&state.query_history, move |history| {
+        history.parameter_suggestions(
+            &connection_id,
+            &sql::fingerprint(&sql),
+            PARAMETER_SUGGESTION_LIMIT,
+        )
+    })
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,6 +771,12 @@ mod tests {
             database: Some("test_db".to_string()),
             page: None,
             page_size: None,
+            params: vec![],
+            session_id: None,
+            force_refresh: false,
+            confirmed: false,
+            confirmation: None,
+            request_id: None,
         };
         assert_eq!(request.connection_id, "test");
         assert_eq!(request.sql, "SELECT 1");
@@ -63,6 +790,12 @@ mod tests {
             database: Some("test_db".to_string()),
             page: Some(0),
             page_size: Some(10),
+            params: vec![],
+            session_id: None,
+            force_refresh: false,
+            confirmed: false,
+            confirmation: None,
+            request_id: None,
         };
         assert_eq!(request.page, Some(0));
         assert_eq!(request.page_size, Some(10));
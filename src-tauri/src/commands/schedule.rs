@@ -0,0 +1,158 @@
+use crate::commands::{environment_policy, store_task, AppState};
+use crate::db::masking::{self, MaskRules};
+use crate::db::result_cache::IPC_PAGE_SIZE;
+use crate::db::scheduler::{self, MIN_SCHEDULE_INTERVAL_SECS, SCHEDULED_RESULT_EVENT};
+use crate::error::AppError;
+use crate::models::{
+    QueryResult, ScheduleDelivery, ScheduleRequest, ScheduledQuery, ScheduledResult,
+};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Run a query every `interval_secs` and push its results as
+/// `SCHEDULED_RESULT_EVENT`s
+#[tauri::command]
+pub async fn schedule_query(
+    request: ScheduleRequest,
+    state: State<'_, AppState>,
+) -> Result<ScheduledQuery, AppError> {
+    // Running a statement that changes data over and over is never wanted
+    if !scheduler::is_schedulable(&request.sql) {
+        return Err(AppError::InvalidInput(
+            "Only queries can be scheduled".to_string(),
+        ));
+    }
+    if request.interval_secs < MIN_SCHEDULE_INTERVAL_SECS {
+        return Err(AppError::InvalidInput(format!(
+            "Queries can run at most every {} seconds",
+            MIN_SCHEDULE_INTERVAL_SECS
+        )));
+    }
+    let connection_id = request.connection_id.clone();
+    if store_task(&state, move |store| store.load_connection(&connection_id))
+        .await?
+        .is_none()
+    {
+        return Err(AppError::NotFound(format!(
+            "Connection {}",
+            request.connection_id
+        )));
+    }
+
+    let query = ScheduledQuery {
+        id: uuid::Uuid::new_v4().to_string(),
+        connection_id: request.connection_id,
+        database: request.database,
+        sql: request.sql,
+        params: request.params,
+        interval_secs: request.interval_secs,
+        delivery: request.delivery,
+        last_run_at: None,
+        last_error: None,
+    };
+    state.schedules.lock()?.register(query.clone());
+    Ok(query)
+}
+
+#[tauri::command]
+pub async fn unschedule_query(
+    schedule_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    if !state.schedules.lock()?.unregister(&schedule_id) {
+        return Err(AppError::NotFound(format!("Schedule {}", schedule_id)));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_scheduled_queries(
+    connection_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ScheduledQuery>, AppError> {
+    Ok(state.schedules.lock()?.list(connection_id.as_deref()))
+}
+
+/// Start every due query, each in its own task so a slow one doesn't hold
+/// up the rest
+pub(crate) fn run_due_queries(handle: &AppHandle) {
+    let state = handle.state::<AppState>();
+    let due = match state.schedules.lock() {
+        Ok(mut schedules) => schedules.take_due(Instant::now()),
+        Err(_) => return,
+    };
+    for query in due {
+        let handle = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            run_scheduled_query(&handle, query).await;
+        });
+    }
+}
+
+async fn run_scheduled_query(handle: &AppHandle, query: ScheduledQuery) {
+    let state = handle.state::<AppState>();
+    let outcome = execute_scheduled(&state, &query).await;
+    let ran_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    let changed = match state.schedules.lock() {
+        Ok(mut schedules) => schedules.finish(
+            &query.id,
+            Instant::now(),
+            ran_at,
+            outcome.as_ref().map_err(|e| e.to_string()),
+        ),
+        Err(_) => return,
+    };
+    if outcome.is_ok() && query.delivery == ScheduleDelivery::OnChange && !changed {
+        return;
+    }
+
+    let (mut result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(e) => {
+            tracing::warn!(schedule_id = %query.id, error = %e, "Scheduled query failed");
+            (None, Some(e.to_string()))
+        }
+    };
+    // Dashboards show a summary; keep the event within one IPC page
+    if let Some(result) = &mut result {
+        if result.rows.len() > IPC_PAGE_SIZE {
+            result.rows.truncate(IPC_PAGE_SIZE);
+            result.truncated = true;
+        }
+        masking::mask_rows(&mut result.rows, &result.column_types);
+    }
+
+    let payload = ScheduledResult {
+        schedule_id: query.id.clone(),
+        ran_at,
+        result,
+        error,
+        changed,
+    };
+    if let Err(e) = handle.emit(SCHEDULED_RESULT_EVENT, payload) {
+        tracing::warn!(error = %e, "Failed to emit scheduled query result");
+    }
+}
+
+async fn execute_scheduled(
+    state: &State<'_, AppState>,
+    query: &ScheduledQuery,
+) -> Result<QueryResult, AppError> {
+    // Looked up directly: scheduled runs shouldn't count as uses of the connection
+    let connection_id = query.connection_id.clone();
+    let mut connection = store_task(state, move |store| store.load_connection(&connection_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Connection {}", query.connection_id)))?;
+    // The pool is shared with the editor, so it must honour the environment too
+    connection.read_only |= environment_policy(&connection, state)?.read_only;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let mut result = adapter
+        .execute_query_with_database(&query.sql, query.database.as_deref(), &query.params)
+        .await?;
+    MaskRules::new(&connection.options.masked_columns).flag(&mut result.column_types, None);
+    Ok(result)
+}
@@ -1,64 +1,171 @@
-use crate::commands::AppState;
-use crate::db::MySQLAdapter;
-use crate::models::{AutocompleteData, Schema};
-use tauri::State;
+use crate::commands::{find_connection, AppState};
+use crate::db::completion;
+use crate::db::schema_cache::{
+    interpretations, schema_contains, split_reference, AUTOCOMPLETE_UPDATED_EVENT,
+};
+use crate::error::AppError;
+use crate::models::{
+    AutocompleteData, AutocompleteUpdate, ColumnMatch, Completion, ResolvedReference, Schema,
+};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, State};
 
 #[tauri::command]
-pub async fn get_schema(connection_id: String, state: State<'_, AppState>) -> Result<Schema, String> {
-    // Load connection from store
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", connection_id))?;
+pub async fn get_schema(connection_id: String, state: State<'_, AppState>) -> Result<Schema, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
 
     // Get database name
     let database = connection
         .database
         .as_ref()
-        .ok_or_else(|| "No database specified".to_string())?;
+        .ok_or_else(|| AppError::InvalidInput("No database specified".to_string()))?;
 
-    // Create adapter and get schema
-    let adapter = MySQLAdapter::new(&connection)
-        .await
-        .map_err(|e| e.to_string())?;
+    // Get the cached adapter and schema
+    let adapter = state.pools.get_or_connect(&connection).await?;
 
-    let schema = adapter
-        .get_schema(database)
-        .await
-        .map_err(|e| e.to_string())?;
+    let schema = adapter.get_schema(database).await?;
 
     Ok(schema)
 }
 
+/// Columns of every database on the server whose names match `pattern`,
+/// e.g. `user_id` or `*_at`
+#[tauri::command]
+pub async fn find_columns(
+    connection_id: String,
+    pattern: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ColumnMatch>, AppError> {
+    if pattern.trim().is_empty() {
+        return Err(AppError::InvalidInput("Enter a column name to search for".to_string()));
+    }
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    Ok(adapter.find_columns(&pattern).await?)
+}
+
 #[tauri::command]
 pub async fn get_autocomplete_data(
     connection_id: String,
     database: String,
     state: State<'_, AppState>,
-) -> Result<AutocompleteData, String> {
-    // Load connection from store
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", connection_id))?;
-
-    // Create adapter and get schema
-    let adapter = MySQLAdapter::new(&connection)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let schema = adapter
-        .get_schema(&database)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    Ok(AutocompleteData::from_schema(&schema))
+) -> Result<AutocompleteData, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+
+    // Get the cached adapter and schema
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    let schema = state
+        .schemas
+        .get_or_load(&connection_id, &database, &adapter)
+        .await?;
+
+    let mut data = adapter.autocomplete_data(&schema);
+    data.databases = adapter.list_databases().await?;
+    Ok(data)
+}
+
+/// Re-read only the tables that changed since the completion data of
+/// `database` was cached, emitting `autocomplete://updated` if any did
+#[tauri::command]
+pub async fn refresh_autocomplete_data(
+    connection_id: String,
+    database: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AutocompleteData, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    let (schema, changes) = state
+        .schemas
+        .refresh(&connection_id, &database, &adapter)
+        .await?;
+    if !changes.is_empty() {
+        tracing::debug!(connection_id, database, "Autocomplete data changed");
+        let update = AutocompleteUpdate {
+            connection_id: connection_id.clone(),
+            database: database.clone(),
+            added: changes.added,
+            changed: changes.changed,
+            removed: changes.removed,
+        };
+        if let Err(e) = app.emit(AUTOCOMPLETE_UPDATED_EVENT, update) {
+            tracing::warn!(error = %e, "Failed to emit autocomplete update");
+        }
+    }
+
+    let mut data = adapter.autocomplete_data(&schema);
+    data.databases = adapter.list_databases().await?;
+    Ok(data)
+}
+
+/// Ranked suggestions for the word at `cursor_offset` (in characters), based
+/// on the clause it's in and the tables and aliases the statement references
+#[tauri::command]
+pub async fn get_completions(
+    sql: String,
+    cursor_offset: usize,
+    connection_id: String,
+    database: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Completion>, AppError> {
+    let Some(context) = completion::analyze(&sql, cursor_offset) else {
+        return Ok(Vec::new());
+    };
+
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let databases = adapter.list_databases().await?;
+
+    let mut schemas = HashMap::new();
+    for name in context.databases(&database, &databases) {
+        let schema = state
+            .schemas
+            .get_or_load(&connection_id, &name, &adapter)
+            .await?;
+        schemas.insert(name, schema);
+    }
+
+    Ok(completion::complete(
+        &context,
+        &connection.db_type,
+        &database,
+        &schemas,
+        &databases,
+    ))
+}
+
+/// Resolve a `table`, `table.column`, `db.table` or `db.table.column`
+/// reference, loading other databases' schemas on first use. Returns None
+/// when nothing on the connection matches.
+#[tauri::command]
+pub async fn resolve_reference(
+    connection_id: String,
+    database: String,
+    reference: String,
+    state: State<'_, AppState>,
+) -> Result<Option<ResolvedReference>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let databases = adapter.list_databases().await?;
+
+    let parts = split_reference(&reference);
+    for candidate in interpretations(&parts, &database) {
+        // Don't try to load schemas of databases that don't exist
+        if candidate.database != database && !databases.contains(&candidate.database) {
+            continue;
+        }
+        let schema = state
+            .schemas
+            .get_or_load(&connection_id, &candidate.database, &adapter)
+            .await?;
+        if schema_contains(&schema, &candidate) {
+            return Ok(Some(candidate));
+        }
+    }
+
+    Ok(None)
 }
 
 #[cfg(test)]
@@ -66,7 +173,10 @@ mod tests {
     #[test]
     fn test_autocomplete_data_contains_keywords() {
         use crate::models::{AutocompleteData, Schema};
-        let schema = Schema { tables: vec![] };
+        let schema = Schema {
+            tables: vec![],
+            search_path: vec![],
+        };
         let data = AutocompleteData::from_schema(&schema);
         assert!(!data.keywords.is_empty());
         assert!(data.keywords.contains(&"SELECT".to_string()));
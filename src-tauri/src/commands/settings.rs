@@ -0,0 +1,87 @@
+use crate::commands::{sqlite_task, AppState};
+use crate::error::AppError;
+use crate::models::AppSettings;
+use crate::storage::audit_store::AuditStore;
+use crate::storage::connection_store::{ConnectionStore, StoreError};
+use crate::storage::query_history::QueryHistoryStore;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Unix millis `days` days before now
+fn retention_cutoff(days: u32) -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    now - i64::from(days) * DAY_MS
+}
+
+/// Drop history entries older than the configured retention
+pub(crate) fn apply_history_retention(
+    settings: &AppSettings,
+    history: &mut QueryHistoryStore,
+) -> Result<(), StoreError> {
+    let Some(days) = settings.history_retention_days else {
+        return Ok(());
+    };
+
+    history.prune_before(retention_cutoff(days)).map(|_| ())
+}
+
+/// Drop audit entries older than the configured retention
+pub(crate) fn apply_audit_retention(
+    settings: &AppSettings,
+    audit: &mut AuditStore,
+) -> Result<(), StoreError> {
+    let Some(days) = settings.audit.retention_days else {
+        return Ok(());
+    };
+
+    audit.prune_before(retention_cutoff(days)).map(|_| ())
+}
+
+/// Give the connection store the configured auto-lock timeout
+pub(crate) fn apply_auto_lock(
+    settings: &AppSettings,
+    store: &ConnectionStore,
+) -> Result<(), StoreError> {
+    let timeout = settings
+        .auto_lock_minutes
+        .map(|minutes| Duration::from_secs(u64::from(minutes) * 60));
+    store.set_auto_lock(timeout)
+}
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, AppError> {
+    state.settings.lock()?.load().map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn update_settings(
+    settings: AppSettings,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let previous = state.settings.lock()?.load()?;
+    let saved = settings.clone();
+    sqlite_task(&state.settings, move |store| store.save(&saved)).await?;
+    // Open pools were made read-only or not under the old guardrails
+    if previous.guardrails != settings.guardrails {
+        state.pools.clear().await;
+    }
+    state.logs.set_level(settings.log_level)?;
+    state.pools.secrets.configure(&settings.vault);
+    apply_auto_lock(&settings, &state.connection_store)?;
+
+    // A shorter retention takes effect right away
+    let retained = settings.clone();
+    sqlite_task(&state.query_history, move |history| {
+        apply_history_retention(&retained, history)
+    })
+    .await?;
+    sqlite_task(&state.audit, move |audit| {
+        apply_audit_retention(&settings, audit)
+    })
+    .await
+}
@@ -1,10 +1,22 @@
-use crate::commands::AppState;
-use crate::db::MySQLAdapter;
+use crate::commands::{
+    check_typed_confirmation, environment_policy, find_connection, record_audit, start_task,
+    AppState,
+};
+use crate::db::json_transfer::{self, ExportPlan, ImportPlan};
+use crate::db::masking::{self, MaskRules};
+use crate::db::result_cache::ResultError;
+use crate::db::table_copy::{self, CopyPlan, DEFAULT_COPY_BATCH_SIZE};
+use crate::db::{data_compare, values, MySQLAdapter};
+use crate::error::AppError;
 use crate::models::{
-    TableData, TableDataRequest, TableSchema, InsertRowRequest,
-    UpdateRowRequest, DeleteRowRequest,
+    ColumnProfile, Connection, CopyContent, CopyTableRequest, SeedTableRequest, TableData, TableDataRequest,
+    TableSchema, InsertRowRequest, UpdateRowRequest, DeleteRowRequest, CellFileRequest, DestructiveRisk, RowField, SyncMode,
+    TableCompareRequest, TableDataDiff, TableRowCount, TableRowTotal, TaskKind, JsonExportRequest,
+    JsonImportReport, JsonImportRequest,
 };
-use tauri::State;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[tauri::command]
 pub async fn get_table_structure(
@@ -12,139 +24,663 @@ pub async fn get_table_structure(
     database: String,
     table: String,
     state: State<'_, AppState>,
-) -> Result<TableSchema, String> {
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", connection_id))?;
+) -> Result<TableSchema, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
 
-    let adapter = MySQLAdapter::new(&connection)
-        .await
-        .map_err(|e| e.to_string())?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
 
-    let schema = adapter
-        .get_schema(&database)
-        .await
-        .map_err(|e| e.to_string())?;
+    let schema = adapter.get_schema(&database).await?;
 
     schema
         .tables
         .into_iter()
         .find(|t| t.name == table)
-        .ok_or_else(|| format!("Table not found: {}", table))
+        .ok_or_else(|| AppError::NotFound(format!("Table {}", table)))
 }
 
+/// Row counts of a database's tables for the sidebar, estimated unless the
+/// table is listed in `exact`, in one round trip
+#[tauri::command]
+pub async fn get_table_row_counts(
+    connection_id: String,
+    database: String,
+    tables: Vec<String>,
+    exact: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TableRowTotal>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    Ok(adapter
+        .table_row_counts(&database, &tables, &exact.unwrap_or_default())
+        .await?)
+}
+
+/// Event carrying the exact row count of a table page, see `get_table_data`
+pub const TABLE_COUNT_EVENT: &str = "table-data://count";
+
+/// Page of a table, returned without waiting for COUNT(*). When the count is
+/// needed the page has `total_rows_pending` set and the count follows in a
+/// `TABLE_COUNT_EVENT` with the page's `request_id`.
 #[tauri::command]
 pub async fn get_table_data(
     request: TableDataRequest,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<TableData, String> {
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&request.connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", request.connection_id))?;
+) -> Result<TableData, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
 
-    let adapter = MySQLAdapter::new(&connection)
-        .await
-        .map_err(|e| e.to_string())?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
 
-    adapter
-        .get_table_data(&request)
-        .await
-        .map_err(|e| e.to_string())
+    let mut data = adapter.get_table_page(&request).await?;
+    MaskRules::new(&connection.options.masked_columns)
+        .flag(&mut data.column_types, Some(&request.table));
+    for column in data.column_types.iter().filter(|c| c.masked) {
+        for row in &mut data.rows {
+            if let Some(value) = row.get_mut(&column.name) {
+                masking::mask_value(value);
+            }
+        }
+    }
+    if let Some(max_chars) = state.settings.lock()?.load()?.max_cell_length {
+        for column in data.column_types.iter_mut().filter(|c| values::is_truncatable(c)) {
+            for row in &mut data.rows {
+                if let Some(value) = row.get_mut(&column.name) {
+                    column.truncated |= values::truncate_value(value, max_chars);
+                }
+            }
+        }
+    }
+    if data.total_rows_pending {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        data.request_id = Some(request_id.clone());
+        tauri::async_runtime::spawn(async move {
+            let outcome = adapter.count_table_rows(&request).await;
+            if let Err(e) = &outcome {
+                tracing::warn!(table = %request.table, error = %e, "Counting table rows failed");
+            }
+            let count = TableRowCount {
+                request_id,
+                total_rows: outcome.as_ref().ok().copied(),
+                error: outcome.err().map(|e| e.to_string()),
+            };
+            if let Err(e) = app.emit(TABLE_COUNT_EVENT, count) {
+                tracing::warn!(error = %e, "Failed to emit table row count");
+            }
+        });
+    }
+
+    Ok(data)
 }
 
 #[tauri::command]
 pub async fn insert_table_row(
     request: InsertRowRequest,
+    confirmation: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&request.connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", request.connection_id))?;
+) -> Result<(), AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    check_typed_confirmation(&connection, &state, confirmation.as_deref())?;
 
-    let adapter = MySQLAdapter::new(&connection)
-        .await
-        .map_err(|e| e.to_string())?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
 
-    adapter
-        .insert_row(&request)
-        .await
-        .map_err(|e| e.to_string())
+    let outcome = adapter.insert_row(&request).await;
+    record_audit(
+        &state,
+        "insert_table_row",
+        &request.connection_id,
+        Some(&request.database),
+        &MySQLAdapter::insert_row_sql(&request),
+        &outcome,
+    );
+    // Cached query results may include the changed rows
+    state.query_cache.lock()?.invalidate(&request.connection_id);
+
+    outcome.map(|_| ()).map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn update_table_row(
-    request: UpdateRowRequest,
-    state: State<'_, AppState>,
-) -> Result<u64, String> {
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&request.connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", request.connection_id))?;
-
-    let adapter = MySQLAdapter::new(&connection)
-        .await
-        .map_err(|e| e.to_string())?;
+    mut request: UpdateRowRequest,
+    confirmation: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<u64, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    check_typed_confirmation(&connection, &state, confirmation.as_deref())?;
 
-    adapter
-        .update_row(&request)
-        .await
-        .map_err(|e| e.to_string())
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    if let Some(expected) = request.expected_values.as_mut() {
+        retain_comparable(&connection, &adapter, &state, &request.database, &request.table, expected)
+            .await?;
+    }
+    let outcome = adapter.update_row(&request).await;
+    record_audit(
+        &state,
+        "update_table_row",
+        &request.connection_id,
+        Some(&request.database),
+        &MySQLAdapter::update_row_sql(&request),
+        &outcome,
+    );
+    state.query_cache.lock()?.invalidate(&request.connection_id);
+
+    outcome.map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn delete_table_rows(
-    request: DeleteRowRequest,
-    state: State<'_, AppState>,
-) -> Result<u64, String> {
-    let connection = state
-        .connection_store
-        .lock()
-        .map_err(|e| e.to_string())?
-        .load_connection(&request.connection_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Connection not found: {}", request.connection_id))?;
-
-    let adapter = MySQLAdapter::new(&connection)
+    mut request: DeleteRowRequest,
+    confirmation: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<u64, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    check_typed_confirmation(&connection, &state, confirmation.as_deref())?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    if let Some(expected) = request.expected_values.as_mut() {
+        retain_comparable(&connection, &adapter, &state, &request.database, &request.table, expected)
+            .await?;
+    }
+    let outcome = adapter.delete_rows(&request).await;
+    record_audit(
+        &state,
+        "delete_table_rows",
+        &request.connection_id,
+        Some(&request.database),
+        &MySQLAdapter::delete_rows_sql(&request),
+        &outcome,
+    );
+    state.query_cache.lock()?.invalidate(&request.connection_id);
+
+    outcome.map_err(AppError::from)
+}
+
+/// Drop the expected values of an edit that the listing didn't show as
+/// stored: masked ones, cut ones, and those the adapter can't compare
+async fn retain_comparable(
+    connection: &Connection,
+    adapter: &MySQLAdapter,
+    state: &AppState,
+    database: &str,
+    table: &str,
+    expected: &mut HashMap<String, serde_json::Value>,
+) -> Result<(), AppError> {
+    let rules = MaskRules::new(&connection.options.masked_columns);
+    expected.retain(|column, _| !rules.is_masked(Some(table), column));
+    let max_chars = state.settings.lock()?.load()?.max_cell_length;
+    adapter
+        .retain_comparable(database, table, expected, max_chars)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(AppError::from)
+}
+
+/// Write a cell's value, such as an image or PDF in a BLOB column, to a file.
+/// Returns the number of bytes written.
+#[tauri::command]
+pub async fn export_cell_to_file(
+    request: CellFileRequest,
+    state: State<'_, AppState>,
+) -> Result<u64, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
 
     adapter
-        .delete_rows(&request)
+        .stream_cell_to_file(&request)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Replace a cell's value with the contents of a file, returning the number
+/// of rows changed
+#[tauri::command]
+pub async fn update_cell_from_file(
+    request: CellFileRequest,
+    confirmation: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<u64, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    check_typed_confirmation(&connection, &state, confirmation.as_deref())?;
+    let contents = tokio::fs::read(&request.path).await?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    let outcome = adapter.update_cell(&request, contents).await;
+    record_audit(
+        &state,
+        "update_cell_from_file",
+        &request.connection_id,
+        Some(&request.database),
+        &MySQLAdapter::update_cell_sql(&request),
+        &outcome,
+    );
+    state.query_cache.lock()?.invalidate(&request.connection_id);
+
+    outcome.map_err(AppError::from)
+}
+
+/// One row located by its primary key, with long text, JSON and binary
+/// values in full for a record detail view. Returns None when no row matches.
+#[tauri::command]
+pub async fn get_row(
+    connection_id: String,
+    database: String,
+    table: String,
+    pk_values: HashMap<String, serde_json::Value>,
+    state: State<'_, AppState>,
+) -> Result<Option<Vec<RowField>>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    let mut fields = adapter.get_row(&database, &table, &pk_values).await?;
+    let masking = MaskRules::new(&connection.options.masked_columns);
+    for field in fields.iter_mut().flatten() {
+        if masking.is_masked(Some(&table), &field.name) {
+            masking::mask_value(&mut field.value);
+        }
+    }
+    Ok(fields)
+}
+
+/// The full value of a cell that `get_table_data` or a query result cut to
+/// the cell length limit. Returns None when no row matches.
+#[tauri::command]
+pub async fn get_cell_value(
+    connection_id: String,
+    database: String,
+    table: String,
+    pk_values: HashMap<String, serde_json::Value>,
+    column: String,
+    state: State<'_, AppState>,
+) -> Result<Option<RowField>, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    let mut field = adapter
+        .get_cell_value(&database, &table, &pk_values, &column)
+        .await?;
+    let masking = MaskRules::new(&connection.options.masked_columns);
+    if let Some(field) = field.as_mut().filter(|_| masking.is_masked(Some(&table), &column)) {
+        masking::mask_value(&mut field.value);
+    }
+    Ok(field)
+}
+
+/// Null, distinct and most common values of a column, for a column
+/// statistics popover. Of a masked column only the counts come back.
+#[tauri::command]
+pub async fn profile_column(
+    connection_id: String,
+    database: String,
+    table: String,
+    column: String,
+    state: State<'_, AppState>,
+) -> Result<ColumnProfile, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
+
+    let mut profile = adapter.profile_column(&database, &table, &column).await?;
+    if MaskRules::new(&connection.options.masked_columns).is_masked(Some(&table), &column) {
+        masking::mask_profile(&mut profile);
+    }
+    Ok(profile)
+}
+
+/// Fill a table with made-up rows, e.g. to try out an empty development
+/// database. Runs as a background task; returns its id.
+#[tauri::command]
+pub async fn seed_table(
+    request: SeedTableRequest,
+    confirmation: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    check_typed_confirmation(&connection, &state, confirmation.as_deref())?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let task = start_task(
+        &app,
+        &state,
+        TaskKind::Seed,
+        format!("{}.{}", request.database, request.table),
+        Some(request.row_count as u64),
+    )?;
+
+    let worker = task.clone();
+    tauri::async_runtime::spawn(async move {
+        let progress = |rows| worker.progress(rows, 0);
+        let outcome = adapter.seed_table(&request, &progress).await;
+
+        let state = app.state::<AppState>();
+        // The generated rows would swamp the log; record what was asked for
+        record_audit(
+            &state,
+            "seed_table",
+            &request.connection_id,
+            Some(&request.database),
+            &format!(
+                "-- {} generated rows\nINSERT INTO `{}`.`{}`",
+                request.row_count, request.database, request.table
+            ),
+            &outcome,
+        );
+        if let Ok(mut cache) = state.query_cache.lock() {
+            cache.invalidate(&request.connection_id);
+        }
+        worker.finish(outcome.map_err(|e| e.to_string()));
+    });
+    Ok(task.id())
+}
+
+/// Copy a table to another database or connection: its structure, its rows
+/// or both. The structure is created right away; rows are copied in a
+/// background task, whose id is returned.
+#[tauri::command]
+pub async fn copy_table(
+    request: CopyTableRequest,
+    confirmation: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, AppError> {
+    let CopyTableRequest {
+        source_connection_id,
+        source_database,
+        table,
+        target_connection_id,
+        target_database,
+        options,
+    } = request;
+    let source = find_connection(&source_connection_id, &state).await?;
+    let target = find_connection(&target_connection_id, &state).await?;
+    let copies_structure = options.content != CopyContent::DataOnly;
+    if copies_structure && environment_policy(&target, &state)?.block_ddl {
+        return Err(AppError::Guardrail(format!(
+            "Creating tables is blocked on {} connections",
+            target.environment
+        )));
+    }
+    check_typed_confirmation(&target, &state, confirmation.as_deref())?;
+
+    let source_adapter = state.pools.get_or_connect(&source).await?;
+    let target_adapter = state.pools.get_or_connect(&target).await?;
+    let schema = source_adapter
+        .get_table_schema(&source_database, &table)
+        .await?;
+    if schema.columns.is_empty() {
+        return Err(AppError::NotFound(format!("Table {}.{}", source_database, table)));
+    }
+    let target_table = options.target_table.clone().unwrap_or_else(|| table.clone());
+
+    if copies_structure {
+        let create = if target.db_type == source.db_type {
+            let ddl = source_adapter
+                .show_create_table(&source_database, &table)
+                .await?;
+            table_copy::retarget_create_table(&ddl, &target_database, &target_table)
+                .map_err(AppError::InvalidInput)?
+        } else {
+            table_copy::translate_create_table(
+                &schema,
+                &target.db_type,
+                &target_database,
+                &target_table,
+            )
+        };
+        let outcome = target_adapter
+            .execute_statements(std::slice::from_ref(&create))
+            .await;
+        record_audit(
+            &state,
+            "copy_table",
+            &target.id,
+            Some(&target_database),
+            &create,
+            &outcome.as_ref().map(|_| 0),
+        );
+        outcome?;
+        state.schemas.invalidate(&target.id).await;
+    }
+    if options.content == CopyContent::StructureOnly {
+        return Ok(None);
+    }
+
+    let plan = CopyPlan::new(&schema, &source_database, &target_database, &target_table);
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_COPY_BATCH_SIZE);
+    let total = source_adapter
+        .estimated_row_count(&source_database, &table)
+        .await?;
+    let task = start_task(
+        &app,
+        &state,
+        TaskKind::Copy,
+        format!(
+            "{}.{} to {}.{}",
+            source_database, table, target_database, target_table
+        ),
+        total,
+    )?;
+
+    let worker = task.clone();
+    tauri::async_runtime::spawn(async move {
+        let progress = |rows| worker.progress(rows, 0);
+        let outcome = source_adapter
+            .copy_rows(&target_adapter, &plan, batch_size, &progress)
+            .await;
+
+        let state = app.state::<AppState>();
+        // The copied rows would swamp the log; record where they went
+        record_audit(
+            &state,
+            "copy_table",
+            &target.id,
+            Some(&target_database),
+            &format!(
+                "-- rows copied from {} ({}.{})\nINSERT INTO `{}`.`{}`",
+                source.name, source_database, table, target_database, target_table
+            ),
+            &outcome,
+        );
+        if let Ok(mut cache) = state.query_cache.lock() {
+            cache.invalidate(&target.id);
+        }
+        worker.finish(outcome.map_err(|e| e.to_string()));
+    });
+    Ok(Some(task.id()))
+}
+
+/// Write every row of a table to a JSON file whose values keep their types,
+/// as one document or as NDJSON. Runs as a background task; returns its id.
+#[tauri::command]
+pub async fn export_table_json(
+    request: JsonExportRequest,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let schema = adapter
+        .get_table_schema(&request.database, &request.table)
+        .await?;
+    if schema.columns.is_empty() {
+        return Err(AppError::NotFound(format!("Table {}.{}", request.database, request.table)));
+    }
+    let plan = ExportPlan::new(&schema, &request.database);
+    let total = adapter
+        .estimated_row_count(&request.database, &request.table)
+        .await?;
+    let task = start_task(&app, &state, TaskKind::Export, request.path.clone(), total)?;
+
+    let worker = task.clone();
+    tauri::async_runtime::spawn(async move {
+        let progress = |rows, bytes| worker.progress(rows, bytes);
+        let outcome = adapter
+            .export_table_json(&plan, request.layout, Path::new(&request.path), &progress)
+            .await;
+        if let Err(e) = &outcome {
+            tracing::warn!(path = %request.path, error = %e, "Export failed");
+        }
+        worker.finish(outcome.map_err(|e| e.to_string()));
+    });
+    Ok(task.id())
+}
+
+/// Insert the rows of a file from `export_table_json` into a table. The
+/// file's columns are checked against the table before anything is written;
+/// rows that don't fit are skipped and listed in the report.
+#[tauri::command]
+pub async fn import_table_json(
+    request: JsonImportRequest,
+    confirmation: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<JsonImportReport, AppError> {
+    let connection = find_connection(&request.connection_id, &state).await?;
+    check_typed_confirmation(&connection, &state, confirmation.as_deref())?;
+
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let schema = adapter
+        .get_table_schema(&request.database, &request.table)
+        .await?;
+    if schema.columns.is_empty() {
+        return Err(AppError::NotFound(format!("Table {}.{}", request.database, request.table)));
+    }
+    // A document is parsed whole; keep that off the async workers
+    let path = PathBuf::from(&request.path);
+    let (header, rows) = tauri::async_runtime::spawn_blocking(move || json_transfer::open(&path))
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map_err(AppError::InvalidInput)?;
+    let plan =
+        ImportPlan::new(&header, &schema, &request.database).map_err(AppError::InvalidInput)?;
+
+    let batch_size = request.batch_size.unwrap_or(DEFAULT_COPY_BATCH_SIZE);
+    let outcome = adapter.import_json_rows(&plan, rows, batch_size).await;
+    // The imported rows would swamp the log; record where they came from
+    record_audit(
+        &state,
+        "import_table_json",
+        &request.connection_id,
+        Some(&request.database),
+        &format!(
+            "-- rows imported from {}\nINSERT INTO `{}`.`{}`",
+            request.path, request.database, request.table
+        ),
+        &outcome.as_ref().map(|report| report.imported),
+    );
+    if let Ok(mut cache) = state.query_cache.lock() {
+        cache.invalidate(&request.connection_id);
+    }
+    Ok(outcome?)
+}
+
+/// Compare a table's rows on two connections as the changes that would bring
+/// the target in line with the source. Those changes can also be returned as
+/// statements, or applied to the target once confirmed.
+#[tauri::command]
+pub async fn compare_table_data(
+    request: TableCompareRequest,
+    state: State<'_, AppState>,
+) -> Result<TableDataDiff, AppError> {
+    let source = find_connection(&request.source.connection_id, &state).await?;
+    let target = find_connection(&request.target.connection_id, &state).await?;
+    if request.mode == SyncMode::Apply {
+        if !request.confirmed {
+            return Err(AppError::NeedsConfirmation(DestructiveRisk::SyncTable));
+        }
+        check_typed_confirmation(&target, &state, request.confirmation.as_deref())?;
+    }
+
+    let source_adapter = state.pools.get_or_connect(&source).await?;
+    let target_adapter = state.pools.get_or_connect(&target).await?;
+    let source_schema = source_adapter
+        .get_table_schema(&request.source.database, &request.table)
+        .await?;
+    let target_schema = target_adapter
+        .get_table_schema(&request.target.database, &request.table)
+        .await?;
+    let sides = [(&source_schema, &request.source), (&target_schema, &request.target)];
+    for (schema, location) in sides {
+        if schema.columns.is_empty() {
+            return Err(AppError::NotFound(format!(
+                "Table {}.{}",
+                location.database, request.table
+            )));
+        }
+    }
+    let plan = data_compare::plan(&source_schema, &target_schema, &request.key_columns)
+        .map_err(AppError::InvalidInput)?;
+
+    // Masked columns are compared, but their values never leave the backend
+    let source_rules = MaskRules::new(&source.options.masked_columns);
+    let target_rules = MaskRules::new(&target.options.masked_columns);
+    let is_masked = |column: &String| {
+        source_rules.is_masked(Some(&request.table), column)
+            || target_rules.is_masked(Some(&request.table), column)
+    };
+    if request.mode == SyncMode::Generate {
+        if let Some(column) = plan.column_names().into_iter().find(|c| is_masked(c)) {
+            return Err(ResultError::Masked(column).into());
+        }
+    }
+
+    let outcome = data_compare::compare(
+        &plan,
+        (&source_adapter, &request.source.database),
+        (&target_adapter, &request.target.database),
+        request.mode,
+    )
+    .await;
+    if request.mode == SyncMode::Apply {
+        record_audit(
+            &state,
+            "compare_table_data",
+            &target.id,
+            Some(&request.target.database),
+            &format!(
+                "-- Sync `{}`.`{}` from connection {} ({})",
+                request.target.database, request.table, source.name, request.source.database
+            ),
+            &outcome.as_ref().map(|diff| diff.rows_affected.unwrap_or_default()),
+        );
+        state.query_cache.lock()?.invalidate(&target.id);
+    }
+
+    let mut diff = outcome?;
+    let masked: Vec<bool> = diff.columns.iter().map(is_masked).collect();
+    let masked_keys: Vec<bool> = diff.key_columns.iter().map(is_masked).collect();
+    for row in diff.inserted.iter_mut().chain(&mut diff.deleted) {
+        masking::mask_row(row, &masked);
+    }
+    for change in &mut diff.updated {
+        masking::mask_row(&mut change.left, &masked);
+        masking::mask_row(&mut change.right, &masked);
+        masking::mask_row(&mut change.key, &masked_keys);
+    }
+    Ok(diff)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::models::{CellValue, RowCountMode};
 
     #[test]
     fn test_table_data_request_creation() {
         let request = TableDataRequest {
             connection_id: "test".to_string(),
             database: "test_db".to_string(),
+            schema: None,
             table: "users".to_string(),
             page: 0,
             page_size: 10,
             filters: None,
             sort_by: None,
             sort_order: None,
+            row_count: RowCountMode::Auto,
         };
         assert_eq!(request.table, "users");
         assert_eq!(request.page_size, 10);
@@ -153,8 +689,8 @@ mod tests {
     #[test]
     fn test_insert_row_request_creation() {
         let mut data = HashMap::new();
-        data.insert("name".to_string(), serde_json::Value::String("John".to_string()));
-        data.insert("age".to_string(), serde_json::Value::Number(30.into()));
+        data.insert("name".to_string(), CellValue::Literal(serde_json::Value::String("John".to_string())));
+        data.insert("age".to_string(), CellValue::Literal(serde_json::Value::Number(30.into())));
 
         let request = InsertRowRequest {
             connection_id: "test".to_string(),
@@ -169,7 +705,7 @@ mod tests {
     #[test]
     fn test_update_row_request_creation() {
         let mut data = HashMap::new();
-        data.insert("name".to_string(), serde_json::Value::String("Jane".to_string()));
+        data.insert("name".to_string(), CellValue::Literal(serde_json::Value::String("Jane".to_string())));
 
         let mut where_clause = HashMap::new();
         where_clause.insert("id".to_string(), serde_json::Value::Number(1.into()));
@@ -180,6 +716,7 @@ mod tests {
             table: "users".to_string(),
             data,
             where_clause,
+            expected_values: None,
         };
         assert_eq!(request.table, "users");
         assert_eq!(request.data.len(), 1);
@@ -196,8 +733,10 @@ mod tests {
             database: "test_db".to_string(),
             table: "users".to_string(),
             where_clause,
+            expected_values: None,
         };
         assert_eq!(request.table, "users");
         assert_eq!(request.where_clause.len(), 1);
     }
 }
+
@@ -0,0 +1,41 @@
+use crate::commands::AppState;
+use crate::db::tasks::{Task, TASK_PROGRESS_EVENT};
+use crate::error::AppError;
+use crate::models::{TaskInfo, TaskKind};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
+
+/// Register a background task whose progress is emitted to every window
+pub(crate) fn start_task(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+    kind: TaskKind,
+    description: String,
+    total_rows: Option<u64>,
+) -> Result<Arc<Task>, AppError> {
+    let handle = app.clone();
+    Ok(state
+        .tasks
+        .lock()?
+        .start(kind, description, total_rows, move |info| {
+            if let Err(e) = handle.emit(TASK_PROGRESS_EVENT, info) {
+                tracing::warn!(error = %e, "Failed to emit task progress");
+            }
+        }))
+}
+
+/// Running and recently finished background tasks, newest first
+#[tauri::command]
+pub async fn list_tasks(state: State<'_, AppState>) -> Result<Vec<TaskInfo>, AppError> {
+    Ok(state.tasks.lock()?.list())
+}
+
+/// Ask a running task to stop. It stops at its next progress report and
+/// then reports itself as cancelled.
+#[tauri::command]
+pub async fn cancel_task(task_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    if !state.tasks.lock()?.cancel(&task_id) {
+        return Err(AppError::NotFound(format!("Running task {}", task_id)));
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use crate::commands::{find_connection, AppState};
+use crate::db::templates::{builtin_templates, find_template, table_template};
+use crate::error::AppError;
+use crate::models::{DatabaseType, QueryTemplateKind, StatementTemplate};
+use std::collections::HashMap;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_templates() -> Result<Vec<StatementTemplate>, AppError> {
+    Ok(builtin_templates()
+        .into_iter()
+        .map(|definition| definition.template)
+        .collect())
+}
+
+#[tauri::command]
+pub async fn render_template(
+    template_id: String,
+    db_type: DatabaseType,
+    params: HashMap<String, String>,
+) -> Result<String, AppError> {
+    find_template(&template_id)
+        .ok_or_else(|| AppError::NotFound(format!("Template {}", template_id)))?
+        .render(&db_type, &params)
+        .map_err(AppError::InvalidInput)
+}
+
+/// A SELECT, INSERT, UPDATE or DELETE against a table, listing its columns,
+/// for the editor to fill in
+#[tauri::command]
+pub async fn generate_query_template(
+    connection_id: String,
+    database: String,
+    table: String,
+    kind: QueryTemplateKind,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let connection = find_connection(&connection_id, &state).await?;
+    let adapter = state.pools.get_or_connect(&connection).await?;
+    let schema = adapter.get_table_schema(&database, &table).await?;
+    table_template(&schema, &connection.db_type, &database, kind).map_err(AppError::InvalidInput)
+}
@@ -0,0 +1,75 @@
+use crate::commands::{store_task, AppState};
+use crate::error::AppError;
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder};
+
+/// Label of the window dedicated to a connection. Labels only allow
+/// alphanumerics, `-`, `/`, `:` and `_`, so anything else becomes `_`.
+fn connection_window_label(connection_id: &str) -> String {
+    let id: String = connection_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    format!("connection-{}", id)
+}
+
+/// Open another window sharing the app's connections and pools. With a
+/// connection the window is dedicated to it, and asking again focuses the
+/// existing one. Returns the window's label.
+#[tauri::command]
+pub async fn new_window(
+    connection_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let (label, title, url) = match &connection_id {
+        Some(connection_id) => {
+            let label = connection_window_label(connection_id);
+            if let Some(window) = app.get_webview_window(&label) {
+                window
+                    .set_focus()
+                    .map_err(|e| AppError::Internal(e.to_string()))?;
+                return Ok(label);
+            }
+
+            // Looked up directly: opening a window isn't a use of the connection
+            let id = connection_id.clone();
+            let connection = store_task(&state, move |store| store.load_connection(&id))
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("Connection {}", connection_id)))?;
+            let url = format!(
+                "index.html?connection={}",
+                percent_encoding::utf8_percent_encode(
+                    connection_id,
+                    percent_encoding::NON_ALPHANUMERIC
+                )
+            );
+            (label, format!("{} - DBClient", connection.name), url)
+        }
+        None => (
+            format!("window-{}", uuid::Uuid::new_v4()),
+            "DBClient".to_string(),
+            "index.html".to_string(),
+        ),
+    };
+
+    WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()))
+        .title(title)
+        .inner_size(1280.0, 800.0)
+        .build()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_window_label() {
+        assert_eq!(
+            connection_window_label("0b6e-41f2"),
+            "connection-0b6e-41f2"
+        );
+        assert_eq!(connection_window_label("a b.c"), "connection-a_b_c");
+    }
+}
@@ -0,0 +1,146 @@
+use crate::commands::{sqlite_task, AppState};
+use crate::error::AppError;
+use crate::models::{PinnedTable, RecentObject, RecentObjectKind, Workspace};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{State, WebviewWindow};
+
+/// Recent objects returned when the caller doesn't ask for a number
+const DEFAULT_RECENT_OBJECTS: usize = 20;
+
+/// Save the tabs a window has open on a connection
+#[tauri::command]
+pub async fn save_workspace(
+    workspace: Workspace,
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    let label = window.label().to_string();
+    sqlite_task(&state.workspaces, move |workspaces| {
+        workspaces.save(&workspace, &label, saved_at)
+    })
+    .await
+}
+
+/// The tabs the calling window last saved for a connection, if any
+#[tauri::command]
+pub async fn load_workspace(
+    connection_id: String,
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+) -> Result<Option<Workspace>, AppError> {
+    let label = window.label().to_string();
+    sqlite_task(&state.workspaces, move |workspaces| {
+        workspaces.load(&connection_id, &label)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn pin_table(
+    connection_id: String,
+    database: String,
+    table: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let pinned_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    let pinned = PinnedTable {
+        connection_id,
+        database,
+        table,
+        pinned_at,
+    };
+    sqlite_task(&state.workspaces, move |workspaces| {
+        workspaces.pin_table(&pinned)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn unpin_table(
+    connection_id: String,
+    database: String,
+    table: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    sqlite_task(&state.workspaces, move |workspaces| {
+        workspaces.unpin_table(&connection_id, &database, &table)
+    })
+    .await
+}
+
+/// Tables pinned to the top of a connection's sidebar, oldest pin first
+#[tauri::command]
+pub async fn list_pinned_tables(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<PinnedTable>, AppError> {
+    sqlite_task(&state.workspaces, move |workspaces| {
+        workspaces.list_pinned_tables(&connection_id)
+    })
+    .await
+}
+
+/// Note that the UI opened a database, table or view, for the recent list
+/// and quick switcher. `name` is None for a database.
+#[tauri::command]
+pub async fn record_recent_object(
+    connection_id: String,
+    kind: RecentObjectKind,
+    database: String,
+    name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    if kind != RecentObjectKind::Database && name.is_none() {
+        return Err(AppError::InvalidInput(format!("A {} needs a name", kind)));
+    }
+    let opened_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    let object = RecentObject {
+        connection_id,
+        kind,
+        database,
+        name: name.filter(|_| kind != RecentObjectKind::Database),
+        opened_at,
+        open_count: 1,
+    };
+    sqlite_task(&state.workspaces, move |workspaces| {
+        workspaces.record_recent_object(&object)
+    })
+    .await
+}
+
+/// Objects recently opened on a connection, latest first
+#[tauri::command]
+pub async fn get_recent_objects(
+    connection_id: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<RecentObject>, AppError> {
+    sqlite_task(&state.workspaces, move |workspaces| {
+        workspaces.list_recent_objects(&connection_id, limit.unwrap_or(DEFAULT_RECENT_OBJECTS))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn clear_recent_objects(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    sqlite_task(&state.workspaces, move |workspaces| {
+        workspaces.clear_recent_objects(&connection_id)
+    })
+    .await
+}
@@ -0,0 +1,131 @@
+use base64::{engine::general_purpose, Engine as _};
+
+/// Largest BLOB value that is inlined as an image preview in results
+pub const IMAGE_PREVIEW_MAX_BYTES: usize = 256 * 1024;
+
+/// Detect common image formats from their magic bytes
+pub fn detect_image_content_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if bytes.starts_with(b"BM") && bytes.len() >= 14 {
+        Some("image/bmp")
+    } else if bytes.starts_with(&[0x00, 0x00, 0x01, 0x00]) {
+        Some("image/x-icon")
+    } else {
+        None
+    }
+}
+
+/// Leading bytes of a binary value shown as hex in results
+pub const HEX_PREVIEW_BYTES: usize = 32;
+
+/// `{type: "blob", size, preview_hex}` describing a binary value
+fn blob_json(bytes: &[u8]) -> serde_json::Value {
+    let preview_hex: String = bytes
+        .iter()
+        .take(HEX_PREVIEW_BYTES)
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    serde_json::json!({
+        "type": "blob",
+        "size": bytes.len(),
+        "preview_hex": preview_hex,
+    })
+}
+
+/// Build the JSON value returned for a binary cell: a blob description, with
+/// the `content_type` of a recognised image and its base64 `data` when it's
+/// small enough to preview
+pub fn binary_to_json(bytes: &[u8]) -> serde_json::Value {
+    let mut value = blob_json(bytes);
+    if let Some(content_type) = detect_image_content_type(bytes) {
+        value["content_type"] = content_type.into();
+        if bytes.len() <= IMAGE_PREVIEW_MAX_BYTES {
+            value["data"] = general_purpose::STANDARD.encode(bytes).into();
+        }
+    }
+    value
+}
+
+/// Build the JSON value of a binary cell shown on its own: text is returned
+/// as a string, and images and other bytes as a blob with all of its `data`
+pub fn binary_detail_json(bytes: &[u8]) -> serde_json::Value {
+    let content_type = match detect_image_content_type(bytes) {
+        Some(content_type) => content_type,
+        None => match std::str::from_utf8(bytes) {
+            Ok(text) => return serde_json::Value::String(text.to_string()),
+            Err(_) => "application/octet-stream",
+        },
+    };
+    let mut value = blob_json(bytes);
+    value["content_type"] = content_type.into();
+    value["data"] = general_purpose::STANDARD.encode(bytes).into();
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_png_and_jpeg() {
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00];
+        assert_eq!(detect_image_content_type(&png), Some("image/png"));
+
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0];
+        assert_eq!(detect_image_content_type(&jpeg), Some("image/jpeg"));
+
+        assert_eq!(detect_image_content_type(b"plain text"), None);
+    }
+
+    #[test]
+    fn test_binary_to_json_previews_small_images() {
+        let gif = b"GIF89a\x01\x00\x01\x00";
+        let value = binary_to_json(gif);
+        assert_eq!(value["type"], "blob");
+        assert_eq!(value["content_type"], "image/gif");
+        assert_eq!(value["size"], gif.len());
+        assert!(value["data"].is_string());
+    }
+
+    #[test]
+    fn test_binary_to_json_describes_other_bytes() {
+        let value = binary_to_json(b"hello");
+        assert_eq!(
+            value,
+            serde_json::json!({"type": "blob", "size": 5, "preview_hex": "68656c6c6f"})
+        );
+
+        let mut large_png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        large_png.resize(IMAGE_PREVIEW_MAX_BYTES + 1, 0);
+        let value = binary_to_json(&large_png);
+        assert_eq!(value["content_type"], "image/png");
+        assert!(value.get("data").is_none());
+        assert_eq!(
+            value["preview_hex"].as_str().unwrap().len(),
+            HEX_PREVIEW_BYTES * 2
+        );
+    }
+
+    #[test]
+    fn test_binary_detail_json_keeps_whole_values() {
+        let mut large_png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        large_png.resize(IMAGE_PREVIEW_MAX_BYTES + 1, 0);
+        let value = binary_detail_json(&large_png);
+        assert_eq!(value["type"], "blob");
+        assert_eq!(value["content_type"], "image/png");
+        assert_eq!(value["size"], large_png.len());
+
+        assert_eq!(binary_detail_json(b"hello"), serde_json::json!("hello"));
+        assert_eq!(
+            binary_detail_json(&[0xFF, 0x00, 0xFE])["content_type"],
+            "application/octet-stream"
+        );
+    }
+}
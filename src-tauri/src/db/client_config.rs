@@ -0,0 +1,374 @@
+//! Credentials from the files command-line clients read: MySQL's ~/.my.cnf
+//! and PostgreSQL's ~/.pgpass. A connection that opts in is completed from
+//! them each time it connects, so the app never keeps its own copy.
+
+use crate::models::{Connection, DatabaseType};
+use crate::ssh::config::home_dir;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Option file groups read for MySQL, later ones overriding earlier ones
+const MY_CNF_GROUPS: [&str; 2] = ["client", "mysql"];
+
+#[derive(Error, Debug)]
+pub enum ClientConfigError {
+    #[error("Could not determine the home directory")]
+    NoHomeDirectory,
+
+    #[error("Failed to read {path}: {message}")]
+    Io { path: String, message: String },
+
+    #[error("Invalid port in {path}: {value}")]
+    InvalidPort { path: String, value: String },
+}
+
+pub type Result<T> = std::result::Result<T, ClientConfigError>;
+
+/// Values ~/.my.cnf sets for the client
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MyCnfEntry {
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<String>,
+    pub socket: Option<String>,
+    pub database: Option<String>,
+}
+
+/// Collect the client settings from option file contents. Like the mysql
+/// client, the last value of an option wins; `!include` isn't supported.
+pub fn lookup_my_cnf(contents: &str) -> MyCnfEntry {
+    let mut entry = MyCnfEntry::default();
+    let mut active = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            active = MY_CNF_GROUPS.contains(&group.trim().to_lowercase().as_str());
+            continue;
+        }
+        if !active {
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), unquote(value.trim())),
+            None => (line, String::new()),
+        };
+        let slot = match key.to_lowercase().replace('-', "_").as_str() {
+            "user" => &mut entry.user,
+            "password" => &mut entry.password,
+            "host" => &mut entry.host,
+            "port" => &mut entry.port,
+            "socket" => &mut entry.socket,
+            "database" => &mut entry.database,
+            _ => continue,
+        };
+        *slot = Some(value);
+    }
+
+    entry
+}
+
+/// Strip matching quotes, handling the escapes option files allow inside them
+fn unquote(value: &str) -> String {
+    let quoted = value.len() >= 2
+        && (value.starts_with('"') && value.ends_with('"')
+            || value.starts_with('\'') && value.ends_with('\''));
+    if !quoted {
+        // An unquoted value ends at a comment
+        return value
+            .split_once(" #")
+            .map_or(value, |(value, _)| value)
+            .trim_end()
+            .to_string();
+    }
+
+    let mut unquoted = String::new();
+    let mut chars = value[1..value.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unquoted.push('\n'),
+            Some('t') => unquoted.push('\t'),
+            Some('s') => unquoted.push(' '),
+            Some(other) => unquoted.push(other),
+            None => unquoted.push('\\'),
+        }
+    }
+    unquoted
+}
+
+/// The password ~/.pgpass holds for a server, database and user: the first
+/// line whose fields all match, `*` matching anything
+pub fn lookup_pgpass(
+    contents: &str,
+    host: &str,
+    port: u16,
+    database: &str,
+    username: &str,
+) -> Option<String> {
+    let port = port.to_string();
+    let wanted = [host, port.as_str(), database, username];
+
+    contents
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .map(pgpass_fields)
+        .find(|fields| {
+            fields.len() == 5
+                && fields
+                    .iter()
+                    .zip(wanted)
+                    .all(|(field, wanted)| field == "*" || field == wanted)
+        })
+        .map(|mut fields| fields.remove(4))
+}
+
+/// Split a .pgpass line on `:`, where `\:` and `\\` are literal
+fn pgpass_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    fields.last_mut().unwrap().push(escaped);
+                }
+            }
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Fill what a connection leaves empty from ~/.my.cnf: the user, the
+/// password, the database, and the server when the host is empty
+pub fn apply_my_cnf(
+    connection: &Connection,
+    entry: &MyCnfEntry,
+    path: &Path,
+) -> Result<Connection> {
+    let mut resolved = connection.clone();
+    let fill = |slot: &mut String, value: &Option<String>| {
+        if slot.is_empty() {
+            if let Some(value) = value {
+                *slot = value.clone();
+            }
+        }
+    };
+    fill(&mut resolved.username, &entry.user);
+    fill(&mut resolved.password, &entry.password);
+    if resolved.database.is_none() {
+        resolved.database = entry.database.clone();
+    }
+
+    if connection.host.is_empty() {
+        fill(&mut resolved.host, &entry.host);
+        if let Some(port) = &entry.port {
+            resolved.port = port.parse().map_err(|_| ClientConfigError::InvalidPort {
+                path: path.display().to_string(),
+                value: port.clone(),
+            })?;
+        }
+        if resolved.options.socket_path.is_none() {
+            resolved.options.socket_path = entry.socket.clone();
+        }
+        // No host at all means the local server, as for the mysql client
+        if resolved.host.is_empty() {
+            resolved.host = "localhost".to_string();
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Fill an empty password from ~/.pgpass
+pub fn apply_pgpass(connection: &Connection, contents: &str) -> Connection {
+    let mut resolved = connection.clone();
+    if resolved.password.is_empty() {
+        // libpq looks up socket connections as `localhost`
+        let host = match &connection.options.socket_path {
+            Some(_) => "localhost",
+            None => connection.host.as_str(),
+        };
+        let database = connection
+            .database
+            .as_deref()
+            .unwrap_or(&connection.username);
+        if let Some(password) = lookup_pgpass(
+            contents,
+            host,
+            connection.port,
+            database,
+            &connection.username,
+        ) {
+            resolved.password = password;
+        }
+    }
+    resolved
+}
+
+/// The client config file for a database type: ~/.my.cnf, or ~/.pgpass
+/// unless PGPASSFILE names another one
+pub fn config_path(db_type: &DatabaseType) -> Result<PathBuf> {
+    if let (DatabaseType::PostgreSQL, Some(path)) = (db_type, std::env::var_os("PGPASSFILE")) {
+        return Ok(PathBuf::from(path));
+    }
+    let home = home_dir().ok_or(ClientConfigError::NoHomeDirectory)?;
+    Ok(match db_type {
+        DatabaseType::MySQL => home.join(".my.cnf"),
+        DatabaseType::PostgreSQL => home.join(".pgpass"),
+    })
+}
+
+/// Complete a connection from its client config file when it opted in
+pub fn resolve(connection: &Connection) -> Result<Connection> {
+    if !connection.options.use_client_config {
+        return Ok(connection.clone());
+    }
+
+    let path = config_path(&connection.db_type)?;
+    if !path.exists() {
+        return Ok(connection.clone());
+    }
+    // libpq ignores a password file others can read, and so do we
+    if connection.db_type == DatabaseType::PostgreSQL && readable_by_others(&path) {
+        tracing::warn!(path = %path.display(), "Ignoring password file readable by others");
+        return Ok(connection.clone());
+    }
+
+    let contents = std::fs::read_to_string(&path).map_err(|e| ClientConfigError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+
+    match connection.db_type {
+        DatabaseType::MySQL => apply_my_cnf(connection, &lookup_my_cnf(&contents), &path),
+        DatabaseType::PostgreSQL => Ok(apply_pgpass(connection, &contents)),
+    }
+}
+
+#[cfg(unix)]
+fn readable_by_others(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o077 != 0)
+}
+
+#[cfg(not(unix))]
+fn readable_by_others(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MY_CNF: &str = r#"
+[mysqld]
+user = mysql
+
+[client]
+user = app
+password = "p#ss \"word\""
+port = 3307
+socket = /var/run/mysqld/mysqld.sock
+
+[mysql]
+database = orders # default schema
+"#;
+
+    const PGPASS: &str = "\
+# comment
+db.internal:5432:reports:reader:first
+*:*:*:reader:fallback
+local\\:host:*:*:admin:c\\:olon
+";
+
+    fn connection(db_type: DatabaseType, host: &str, username: &str) -> Connection {
+        let port = db_type.default_port();
+        Connection::new(
+            "local".to_string(),
+            "#3b82f6".to_string(),
+            db_type,
+            host.to_string(),
+            port,
+            username.to_string(),
+            String::new(),
+        )
+    }
+
+    #[test]
+    fn test_lookup_my_cnf_reads_client_groups() {
+        let entry = lookup_my_cnf(MY_CNF);
+        assert_eq!(entry.user.as_deref(), Some("app"));
+        assert_eq!(entry.password.as_deref(), Some("p#ss \"word\""));
+        assert_eq!(entry.port.as_deref(), Some("3307"));
+        assert_eq!(entry.database.as_deref(), Some("orders"));
+        assert!(entry.host.is_none());
+    }
+
+    #[test]
+    fn test_apply_my_cnf_fills_blanks() {
+        let entry = lookup_my_cnf(MY_CNF);
+        let path = Path::new("/home/me/.my.cnf");
+
+        let resolved =
+            apply_my_cnf(&connection(DatabaseType::MySQL, "", ""), &entry, path).unwrap();
+        assert_eq!(resolved.username, "app");
+        assert_eq!(resolved.password, "p#ss \"word\"");
+        assert_eq!(resolved.host, "localhost");
+        assert_eq!(resolved.port, 3307);
+        assert_eq!(
+            resolved.options.socket_path.as_deref(),
+            Some("/var/run/mysqld/mysqld.sock")
+        );
+
+        // Explicit values win, and a given host keeps its own port
+        let explicit = connection(DatabaseType::MySQL, "db.internal", "root");
+        let resolved = apply_my_cnf(&explicit, &entry, path).unwrap();
+        assert_eq!(resolved.username, "root");
+        assert_eq!(resolved.port, 3306);
+        assert!(resolved.options.socket_path.is_none());
+    }
+
+    #[test]
+    fn test_lookup_pgpass_first_match_and_escapes() {
+        assert_eq!(
+            lookup_pgpass(PGPASS, "db.internal", 5432, "reports", "reader").as_deref(),
+            Some("first")
+        );
+        assert_eq!(
+            lookup_pgpass(PGPASS, "db.internal", 5432, "other", "reader").as_deref(),
+            Some("fallback")
+        );
+        assert_eq!(
+            lookup_pgpass(PGPASS, "local:host", 5432, "x", "admin").as_deref(),
+            Some("c:olon")
+        );
+        assert!(lookup_pgpass(PGPASS, "db.internal", 5432, "reports", "nobody").is_none());
+    }
+
+    #[test]
+    fn test_apply_pgpass_keeps_explicit_password() {
+        let mut conn = connection(DatabaseType::PostgreSQL, "db.internal", "reader");
+        conn.database = Some("reports".to_string());
+        assert_eq!(apply_pgpass(&conn, PGPASS).password, "first");
+
+        conn.password = "typed".to_string();
+        assert_eq!(apply_pgpass(&conn, PGPASS).password, "typed");
+    }
+
+    #[test]
+    fn test_resolve_without_opt_in_is_unchanged() {
+        let conn = connection(DatabaseType::MySQL, "", "");
+        assert!(resolve(&conn).unwrap().username.is_empty());
+    }
+}
@@ -0,0 +1,687 @@
+//! Context-aware completion for the statement being edited. That statement
+//! is usually incomplete and won't parse, so this works on sqlparser tokens:
+//! table references and their aliases come from FROM, JOIN, UPDATE and INTO,
+//! and the keyword before the cursor decides what kind of name comes next.
+
+use crate::db::dialect;
+use crate::models::{Completion, CompletionKind, DatabaseType, Schema, TableSchema};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Location, Token, Tokenizer, Whitespace, Word};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Keywords followed by a table name
+const TABLE_KEYWORDS: &[Keyword] = &[
+    Keyword::FROM,
+    Keyword::JOIN,
+    Keyword::UPDATE,
+    Keyword::INTO,
+    Keyword::TABLE,
+];
+
+/// Keywords followed by an expression over the tables in scope
+const EXPRESSION_KEYWORDS: &[Keyword] = &[
+    Keyword::SELECT,
+    Keyword::WHERE,
+    Keyword::ON,
+    Keyword::SET,
+    Keyword::BY,
+    Keyword::HAVING,
+    Keyword::AND,
+    Keyword::OR,
+    Keyword::NOT,
+    Keyword::CASE,
+    Keyword::WHEN,
+    Keyword::THEN,
+    Keyword::ELSE,
+];
+
+/// Keywords after which neither tables nor columns make sense
+const OTHER_KEYWORDS: &[Keyword] = &[Keyword::LIMIT, Keyword::OFFSET];
+
+/// A table referenced by the statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRef {
+    pub database: Option<String>,
+    pub table: String,
+    pub alias: Option<String>,
+}
+
+/// What the statement expects at the cursor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clause {
+    /// A table name, e.g. right after FROM or JOIN
+    Table,
+    /// An expression: the SELECT list, WHERE, ON, SET, ...
+    Expression,
+    /// Anything else, e.g. the start of a statement or after a table name
+    Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionContext {
+    pub clause: Clause,
+    /// The name before a `.` at the cursor, e.g. `u` in `u.na`
+    pub qualifier: Option<String>,
+    /// The part of the word typed so far
+    pub partial: String,
+    /// Tables referenced anywhere in the statement, including after the cursor
+    pub tables: Vec<TableRef>,
+}
+
+impl CompletionContext {
+    /// Databases whose schemas `complete` may look into
+    pub fn databases(&self, active_database: &str, databases: &[String]) -> Vec<String> {
+        let mut needed = vec![active_database.to_string()];
+        let referenced = self
+            .tables
+            .iter()
+            .filter_map(|table| table.database.as_ref())
+            .chain(self.qualifier.as_ref());
+        for database in referenced {
+            if databases.contains(database) && !needed.contains(database) {
+                needed.push(database.clone());
+            }
+        }
+        needed
+    }
+}
+
+/// Tokenizer position of a character offset
+fn location_at(sql: &str, offset: usize) -> Location {
+    let mut location = Location::new(1, 1);
+    for ch in sql.chars().take(offset) {
+        if ch == '\n' {
+            location.line += 1;
+            location.column = 1;
+        } else {
+            location.column += 1;
+        }
+    }
+    location
+}
+
+fn word(token: &Token) -> Option<&Word> {
+    match token {
+        Token::Word(word) => Some(word),
+        _ => None,
+    }
+}
+
+/// The keyword of an unquoted word
+fn keyword(token: &Token) -> Option<Keyword> {
+    word(token)
+        .filter(|word| word.quote_style.is_none() && word.keyword != Keyword::NoKeyword)
+        .map(|word| word.keyword)
+}
+
+fn is_clause_keyword(keyword: Keyword) -> bool {
+    TABLE_KEYWORDS.contains(&keyword)
+        || EXPRESSION_KEYWORDS.contains(&keyword)
+        || OTHER_KEYWORDS.contains(&keyword)
+}
+
+/// Read `[db.]table [[AS] alias]` starting at `start`, returning the
+/// reference and the index after it
+fn table_ref(tokens: &[&Token], start: usize) -> Option<(TableRef, usize)> {
+    let first = word(tokens.get(start)?)?;
+    if keyword(tokens[start]).is_some_and(is_clause_keyword) {
+        return None;
+    }
+
+    let mut i = start + 1;
+    let (database, table) = match (tokens.get(i), tokens.get(i + 1).and_then(|t| word(t))) {
+        (Some(Token::Period), Some(second)) => {
+            i += 2;
+            (Some(first.value.clone()), second.value.clone())
+        }
+        _ => (None, first.value.clone()),
+    };
+
+    if tokens.get(i).and_then(|t| keyword(t)) == Some(Keyword::AS) {
+        i += 1;
+    }
+    let alias = match tokens.get(i).and_then(|t| word(t)) {
+        Some(alias) if alias.keyword == Keyword::NoKeyword || alias.quote_style.is_some() => {
+            i += 1;
+            Some(alias.value.clone())
+        }
+        _ => None,
+    };
+
+    Some((
+        TableRef {
+            database,
+            table,
+            alias,
+        },
+        i,
+    ))
+}
+
+fn table_refs(tokens: &[&Token]) -> Vec<TableRef> {
+    let mut refs = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let trigger = keyword(tokens[i]);
+        i += 1;
+        let Some(trigger) = trigger.filter(|k| TABLE_KEYWORDS.contains(k)) else {
+            continue;
+        };
+
+        while let Some((table, next)) = table_ref(tokens, i) {
+            refs.push(table);
+            i = next;
+            // Only FROM takes a comma-separated list of tables
+            if trigger == Keyword::FROM && tokens.get(i) == Some(&&Token::Comma) {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+    }
+    refs
+}
+
+/// Decide the clause from the tokens before the cursor
+fn clause_at(before: &[&Token]) -> Clause {
+    let mut depth = 0;
+    for (i, token) in before.iter().enumerate().rev() {
+        match token {
+            Token::RParen => depth += 1,
+            // Inside parentheses: function arguments, a column list or a subquery
+            Token::LParen if depth == 0 => return Clause::Expression,
+            Token::LParen => depth -= 1,
+            _ if depth > 0 => {}
+            _ => {
+                let Some(keyword) = keyword(token) else {
+                    continue;
+                };
+                if TABLE_KEYWORDS.contains(&keyword) {
+                    let last = i + 1 == before.len();
+                    let after_comma =
+                        keyword == Keyword::FROM && before.last() == Some(&&Token::Comma);
+                    return if last || after_comma {
+                        Clause::Table
+                    } else {
+                        Clause::Keyword
+                    };
+                }
+                if EXPRESSION_KEYWORDS.contains(&keyword) {
+                    return Clause::Expression;
+                }
+                if OTHER_KEYWORDS.contains(&keyword) {
+                    return Clause::Keyword;
+                }
+            }
+        }
+    }
+    Clause::Keyword
+}
+
+/// Work out what to complete at `cursor` (a character offset into `sql`).
+/// Returns None inside a string literal or comment, where nothing applies.
+pub fn analyze(sql: &str, cursor: usize) -> Option<CompletionContext> {
+    let cursor = location_at(sql, cursor);
+    let tokens = Tokenizer::new(&MySqlDialect {}, sql)
+        .tokenize_with_location()
+        .ok()?;
+
+    // Only the statement the cursor is in matters
+    let start = tokens
+        .iter()
+        .rposition(|t| t.token == Token::SemiColon && t.span.end <= cursor)
+        .map_or(0, |i| i + 1);
+    let end = tokens[start..]
+        .iter()
+        .position(|t| t.token == Token::SemiColon)
+        .map_or(tokens.len(), |i| start + i);
+    let statement = &tokens[start..end];
+
+    let mut before: Vec<_> = statement
+        .iter()
+        .take_while(|t| t.span.start < cursor)
+        .collect();
+
+    let mut partial = String::new();
+    if let Some(last) = before.last() {
+        if last.span.end >= cursor {
+            match &last.token {
+                Token::Word(word) => {
+                    let mut typed = (cursor.column - last.span.start.column) as usize;
+                    if word.quote_style.is_some() {
+                        typed = typed.saturating_sub(1);
+                    }
+                    partial = word.value.chars().take(typed).collect();
+                    before.pop();
+                }
+                Token::Whitespace(Whitespace::Space | Whitespace::Tab | Whitespace::Newline) => {}
+                // Inside a literal, comment or operator
+                _ if last.span.end > cursor => return None,
+                _ => {}
+            }
+        }
+    }
+
+    let mut before: Vec<&Token> = before
+        .into_iter()
+        .map(|t| &t.token)
+        .filter(|t| !matches!(t, Token::Whitespace(_)))
+        .collect();
+
+    let mut qualifier = None;
+    if let [.., Token::Word(name), Token::Period] = before.as_slice() {
+        qualifier = Some(name.value.clone());
+        before.truncate(before.len() - 2);
+    }
+
+    let significant: Vec<&Token> = statement
+        .iter()
+        .map(|t| &t.token)
+        .filter(|t| !matches!(t, Token::Whitespace(_)))
+        .collect();
+
+    Some(CompletionContext {
+        clause: clause_at(&before),
+        qualifier,
+        partial,
+        tables: table_refs(&significant),
+    })
+}
+
+/// Tables referenced anywhere in `sql`, which may hold several statements
+pub fn referenced_tables(sql: &str) -> Vec<TableRef> {
+    let Ok(tokens) = Tokenizer::new(&MySqlDialect {}, sql).tokenize() else {
+        return Vec::new();
+    };
+    let significant: Vec<&Token> = tokens
+        .iter()
+        .filter(|t| !matches!(t, Token::Whitespace(_)))
+        .collect();
+    table_refs(&significant)
+}
+
+/// A table named without its schema: the one in the earliest schema of the
+/// search path, where the database has schemas
+fn find_table<'a>(schema: &'a Schema, name: &str) -> Option<&'a TableSchema> {
+    schema
+        .tables
+        .iter()
+        .filter(|table| table.name.eq_ignore_ascii_case(name))
+        .filter_map(|table| match &table.schema {
+            None => Some((0, table)),
+            Some(namespace) => schema
+                .search_path
+                .iter()
+                .position(|s| s == namespace)
+                .map(|position| (position, table)),
+        })
+        .min_by_key(|(position, _)| *position)
+        .map(|(_, table)| table)
+}
+
+/// A table of the given schema within a database
+fn find_in_namespace<'a>(
+    schema: &'a Schema,
+    namespace: &str,
+    name: &str,
+) -> Option<&'a TableSchema> {
+    schema.tables.iter().find(|table| {
+        table.name.eq_ignore_ascii_case(name)
+            && table
+                .schema
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case(namespace))
+    })
+}
+
+/// The schema of a referenced table, if it was loaded. A qualifier that
+/// isn't a loaded database is taken for a schema of the active one.
+fn lookup<'a>(
+    table: &TableRef,
+    active_database: &str,
+    schemas: &'a HashMap<String, Arc<Schema>>,
+) -> Option<&'a TableSchema> {
+    match &table.database {
+        Some(database) => match schemas.get(database) {
+            Some(schema) => find_table(schema, &table.table),
+            None => find_in_namespace(schemas.get(active_database)?, database, &table.table),
+        },
+        None => find_table(schemas.get(active_database)?, &table.table),
+    }
+}
+
+fn column_completions<'a>(
+    table: &'a TableSchema,
+    source: &str,
+) -> impl Iterator<Item = Completion> + 'a {
+    let source = source.to_string();
+    table.columns.iter().map(move |column| Completion {
+        label: column.name.clone(),
+        kind: CompletionKind::Column,
+        detail: Some(format!("{}: {}", source, column.data_type)),
+    })
+}
+
+fn table_completions<'a>(
+    schema: &'a Schema,
+    database: &str,
+) -> impl Iterator<Item = Completion> + 'a {
+    let database = database.to_string();
+    schema.tables.iter().map(move |table| Completion {
+        label: schema.qualified_name(table),
+        kind: CompletionKind::Table,
+        detail: Some(database.clone()),
+    })
+}
+
+/// Tables of one schema of a database, after `schema.`
+fn namespace_completions<'a>(
+    schema: &'a Schema,
+    namespace: &'a str,
+) -> impl Iterator<Item = Completion> + 'a {
+    schema
+        .tables
+        .iter()
+        .filter(move |table| {
+            table
+                .schema
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case(namespace))
+        })
+        .map(move |table| Completion {
+            label: table.name.clone(),
+            kind: CompletionKind::Table,
+            detail: Some(namespace.to_string()),
+        })
+}
+
+fn keyword_completions(db_type: &DatabaseType) -> impl Iterator<Item = Completion> {
+    dialect::keywords(db_type)
+        .into_iter()
+        .map(|keyword| Completion {
+            label: keyword,
+            kind: CompletionKind::Keyword,
+            detail: None,
+        })
+}
+
+fn function_completions(db_type: &DatabaseType) -> impl Iterator<Item = Completion> {
+    dialect::functions(db_type)
+        .into_iter()
+        .map(|function| Completion {
+            label: function.name,
+            kind: CompletionKind::Function,
+            detail: Some(function.signature),
+        })
+}
+
+/// Suggestions for a context, best match first. `schemas` holds the loaded
+/// schemas of `context.databases()`.
+pub fn complete(
+    context: &CompletionContext,
+    db_type: &DatabaseType,
+    active_database: &str,
+    schemas: &HashMap<String, Arc<Schema>>,
+    databases: &[String],
+) -> Vec<Completion> {
+    let mut items: Vec<Completion> = Vec::new();
+
+    if let Some(qualifier) = &context.qualifier {
+        // An alias first, then a table named in the statement, then any table
+        let referenced = context
+            .tables
+            .iter()
+            .find(|t| {
+                t.alias
+                    .as_deref()
+                    .is_some_and(|a| a.eq_ignore_ascii_case(qualifier))
+            })
+            .or_else(|| {
+                context
+                    .tables
+                    .iter()
+                    .find(|t| t.table.eq_ignore_ascii_case(qualifier))
+            })
+            .and_then(|t| lookup(t, active_database, schemas));
+        let table = referenced.or_else(|| {
+            schemas
+                .get(active_database)
+                .and_then(|schema| find_table(schema, qualifier))
+        });
+
+        if let Some(table) = table {
+            items.extend(column_completions(table, qualifier));
+        } else if let Some(schema) = schemas.get(qualifier.as_str()) {
+            items.extend(table_completions(schema, qualifier));
+        } else if let Some(schema) = schemas.get(active_database) {
+            items.extend(namespace_completions(schema, qualifier));
+        }
+    } else {
+        match context.clause {
+            Clause::Table => {
+                if let Some(schema) = schemas.get(active_database) {
+                    items.extend(table_completions(schema, active_database));
+                }
+                items.extend(databases.iter().map(|database| Completion {
+                    label: database.clone(),
+                    kind: CompletionKind::Database,
+                    detail: None,
+                }));
+            }
+            Clause::Expression => {
+                for table in &context.tables {
+                    if let Some(schema) = lookup(table, active_database, schemas) {
+                        let source = table.alias.as_deref().unwrap_or(&table.table);
+                        items.extend(column_completions(schema, source));
+                    }
+                }
+                for table in &context.tables {
+                    items.push(match &table.alias {
+                        Some(alias) => Completion {
+                            label: alias.clone(),
+                            kind: CompletionKind::Alias,
+                            detail: Some(table.table.clone()),
+                        },
+                        None => Completion {
+                            label: table.table.clone(),
+                            kind: CompletionKind::Table,
+                            detail: table.database.clone(),
+                        },
+                    });
+                }
+                items.extend(function_completions(db_type));
+                items.extend(keyword_completions(db_type));
+            }
+            Clause::Keyword => items.extend(keyword_completions(db_type)),
+        }
+    }
+
+    let partial = context.partial.to_lowercase();
+    items.retain(|item| item.label.to_lowercase().starts_with(&partial));
+    let mut seen = Vec::new();
+    items.retain(|item| {
+        let key = (item.label.clone(), item.kind, item.detail.clone());
+        let new = !seen.contains(&key);
+        if new {
+            seen.push(key);
+        }
+        new
+    });
+    // An exact match beats a longer name of the same rank
+    items.sort_by_key(|item| item.label.len() != context.partial.len());
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ColumnSchema;
+
+    fn table(name: &str, columns: &[&str]) -> TableSchema {
+        TableSchema {
+            name: name.to_string(),
+            schema: None,
+            columns: columns
+                .iter()
+                .map(|column| ColumnSchema {
+                    name: column.to_string(),
+                    data_type: "int".to_string(),
+                    is_nullable: false,
+                    default_value: None,
+                    max_length: None,
+                    is_auto_increment: false,
+                    is_generated: false,
+                    extra: String::new(),
+                    allowed_values: None,
+                })
+                .collect(),
+            primary_keys: vec![],
+            foreign_keys: vec![],
+        }
+    }
+
+    fn schemas() -> HashMap<String, Arc<Schema>> {
+        let mut schemas = HashMap::new();
+        schemas.insert(
+            "shop".to_string(),
+            Arc::new(Schema {
+                tables: vec![
+                    table("users", &["id", "name", "email"]),
+                    table("orders", &["id", "user_id", "total"]),
+                ],
+                search_path: vec![],
+            }),
+        );
+        schemas.insert(
+            "archive".to_string(),
+            Arc::new(Schema {
+                tables: vec![table("old_orders", &["id", "note"])],
+                search_path: vec![],
+            }),
+        );
+        schemas
+    }
+
+    fn labels(sql: &str) -> Vec<String> {
+        let cursor = sql.find('|').expect("cursor marker");
+        let sql = sql.replace('|', "");
+        let context = analyze(&sql, cursor).expect("context");
+        let databases = vec!["shop".to_string(), "archive".to_string()];
+        complete(
+            &context,
+            &DatabaseType::MySQL,
+            "shop",
+            &schemas(),
+            &databases,
+        )
+        .into_iter()
+        .map(|c| c.label)
+        .collect()
+    }
+
+    #[test]
+    fn test_alias_columns_after_dot() {
+        assert_eq!(
+            labels("SELECT o.| FROM orders o JOIN users u ON u.id = o.user_id"),
+            vec!["id", "user_id", "total"]
+        );
+        assert_eq!(labels("SELECT u.na| FROM users AS u"), vec!["name"]);
+        assert_eq!(labels("SELECT users.e| FROM users"), vec!["email"]);
+    }
+
+    #[test]
+    fn test_tables_after_from_and_join() {
+        assert_eq!(
+            labels("SELECT * FROM |"),
+            vec!["users", "orders", "shop", "archive"]
+        );
+        assert_eq!(labels("SELECT * FROM users u JOIN o|"), vec!["orders"]);
+        assert_eq!(labels("SELECT * FROM users, or|"), vec!["orders"]);
+        assert_eq!(labels("SELECT * FROM archive.|"), vec!["old_orders"]);
+    }
+
+    #[test]
+    fn test_columns_in_expressions() {
+        let items = labels("SELECT * FROM users u WHERE |");
+        assert_eq!(&items[..3], ["id", "name", "email"]);
+        assert!(items.contains(&"u".to_string()));
+        assert!(items.contains(&"AND".to_string()));
+
+        assert_eq!(
+            labels("SELECT * FROM archive.old_orders WHERE no|"),
+            vec!["note", "NOW", "NOT"]
+        );
+        assert_eq!(labels("INSERT INTO orders (user|"), vec!["user_id"]);
+        assert_eq!(labels("SELECT date_f|"), vec!["DATE_FORMAT"]);
+    }
+
+    #[test]
+    fn test_schemas_and_search_path() {
+        let mut audit_users = table("users", &["id", "changed_at"]);
+        audit_users.schema = Some("audit".to_string());
+        let mut public_users = table("users", &["id", "name"]);
+        public_users.schema = Some("public".to_string());
+        let mut events = table("events", &["id", "payload"]);
+        events.schema = Some("audit".to_string());
+        let mut schemas = HashMap::new();
+        schemas.insert(
+            "app".to_string(),
+            Arc::new(Schema {
+                tables: vec![audit_users, public_users, events],
+                search_path: vec!["public".to_string()],
+            }),
+        );
+        let labels = |sql: &str| -> Vec<String> {
+            let cursor = sql.find('|').expect("cursor marker");
+            let context = analyze(&sql.replace('|', ""), cursor).expect("context");
+            complete(&context, &DatabaseType::PostgreSQL, "app", &schemas, &[])
+                .into_iter()
+                .map(|c| c.label)
+                .collect()
+        };
+
+        // Tables off the search path are offered qualified
+        assert_eq!(
+            labels("SELECT * FROM |"),
+            vec!["audit.users", "users", "audit.events"]
+        );
+        assert_eq!(labels("SELECT * FROM audit.|"), vec!["users", "events"]);
+        // An unqualified name resolves through the search path
+        assert_eq!(labels("SELECT * FROM users WHERE na|"), vec!["name"]);
+        assert_eq!(
+            labels("SELECT * FROM audit.users WHERE cha|"),
+            vec!["changed_at"]
+        );
+    }
+
+    #[test]
+    fn test_keywords_otherwise() {
+        assert_eq!(labels("SEL|"), vec!["SELECT"]);
+        assert_eq!(labels("SELECT * FROM users u WH|"), vec!["WHERE"]);
+        assert_eq!(labels("SELECT 1; DEL|"), vec!["DELETE"]);
+    }
+
+    #[test]
+    fn test_only_the_statement_at_the_cursor() {
+        let sql = "SELECT * FROM users u; SELECT u.id FROM orders u WHERE ";
+        let context = analyze(sql, sql.len()).unwrap();
+        assert_eq!(context.clause, Clause::Expression);
+        assert_eq!(
+            context.tables,
+            vec![TableRef {
+                database: None,
+                table: "orders".to_string(),
+                alias: Some("u".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_no_completion_inside_literals() {
+        let sql = "SELECT * FROM users WHERE name = 'jo' ";
+        assert!(analyze(sql, sql.find("jo").unwrap() + 1).is_none());
+        assert!(analyze("SELECT 'unterminated", 20).is_none());
+    }
+}
@@ -0,0 +1,726 @@
+//! Comparing a table's rows on two connections and syncing the target to the
+//! source. Both sides are read in chunks ordered by key: each chunk of the
+//! source bounds a key range, and the target's rows in that range are read
+//! in chunks of their own, so neither table is ever held in memory whole.
+//! Rows are paired by equal keys; ranges line up as long as both servers
+//! sort the keys alike, i.e. with the same collation.
+
+use crate::db::mysql_adapter::{MySQLAdapter, Result};
+use crate::db::sql::quote_identifier;
+use crate::db::values;
+use crate::models::{
+    ColumnSchema, DatabaseType, RowChange, SyncMode, TableDataDiff, TableSchema, ValueKind,
+};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+
+/// Rows read from either side per round trip
+pub const COMPARE_CHUNK_SIZE: usize = 1000;
+
+/// Rows of each kind listed in a diff; the counts go on past it
+pub const REPORTED_ROWS_LIMIT: usize = 1000;
+
+/// Spatial types, stored as binary; `DATA_TYPE` names them one by one
+const SPATIAL_TYPES: &[&str] = &[
+    "GEOMETRY",
+    "POINT",
+    "LINESTRING",
+    "POLYGON",
+    "MULTIPOINT",
+    "MULTILINESTRING",
+    "MULTIPOLYGON",
+    "GEOMETRYCOLLECTION",
+    "GEOMCOLLECTION",
+];
+
+/// How a column's values are read and written, so every type survives the
+/// trip from one server to the other
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Plain,
+    /// Binary and spatial values, as hex text
+    Hex,
+    /// JSON documents, as their text
+    Json,
+}
+
+/// A column as read from one server and written to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncColumn {
+    pub name: String,
+    pub encoding: Encoding,
+}
+
+impl SyncColumn {
+    pub fn from_schema(column: &ColumnSchema) -> Self {
+        let data_type = column.data_type.to_uppercase();
+        let encoding = match values::value_kind(&data_type) {
+            ValueKind::Binary | ValueKind::Geometry => Encoding::Hex,
+            ValueKind::Json => Encoding::Json,
+            _ if SPATIAL_TYPES.contains(&data_type.as_str()) => Encoding::Hex,
+            _ => Encoding::Plain,
+        };
+        Self {
+            name: column.name.clone(),
+            encoding,
+        }
+    }
+
+    pub fn quoted(&self) -> String {
+        quote_identifier(&DatabaseType::MySQL, &self.name)
+    }
+
+    /// Expression reading the column's value in its encoding
+    pub fn select_expr(&self) -> String {
+        match self.encoding {
+            Encoding::Plain => self.quoted(),
+            Encoding::Hex => format!("HEX({}) AS {}", self.quoted(), self.quoted()),
+            Encoding::Json => format!("CAST({} AS CHAR) AS {}", self.quoted(), self.quoted()),
+        }
+    }
+
+    /// Placeholder writing a value in the column's encoding
+    pub fn placeholder(&self) -> &'static str {
+        match self.encoding {
+            Encoding::Plain => "?",
+            Encoding::Hex => "UNHEX(?)",
+            Encoding::Json => "CAST(? AS JSON)",
+        }
+    }
+}
+
+/// A statement with `?` placeholders and the values bound to them
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundStatement {
+    pub sql: String,
+    pub params: Vec<Value>,
+}
+
+impl BoundStatement {
+    /// The statement with its values written out as literals, for a script
+    pub fn render(&self) -> String {
+        let mut text = String::with_capacity(self.sql.len());
+        let mut params = self.params.iter();
+        let mut quoted = false;
+        for c in self.sql.chars() {
+            match c {
+                '`' => {
+                    quoted = !quoted;
+                    text.push(c);
+                }
+                '?' if !quoted => {
+                    text.push_str(&params.next().map(literal).unwrap_or_default());
+                }
+                _ => text.push(c),
+            }
+        }
+        text
+    }
+}
+
+/// A value as a MySQL literal; backslashes escape in MySQL strings
+fn literal(value: &Value) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\\', "\\\\").replace('\'', "''"));
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(true) => "TRUE".to_string(),
+        Value::Bool(false) => "FALSE".to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => quote(s),
+        _ => quote(&value.to_string()),
+    }
+}
+
+/// Which columns are compared and how rows are told apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparePlan {
+    table: String,
+    /// Columns both tables have, in the source's order; generated columns
+    /// can't be written, so they are left out
+    columns: Vec<SyncColumn>,
+    /// Indexes of the key columns in `columns`
+    keys: Vec<usize>,
+    source_only: Vec<String>,
+    target_only: Vec<String>,
+}
+
+/// Plan the comparison of two tables' rows, keyed by `key_columns` or the
+/// source's primary key
+pub fn plan(
+    source: &TableSchema,
+    target: &TableSchema,
+    key_columns: &[String],
+) -> std::result::Result<ComparePlan, String> {
+    let writable = |table: &TableSchema| -> Vec<ColumnSchema> {
+        table
+            .columns
+            .iter()
+            .filter(|column| !column.is_generated)
+            .cloned()
+            .collect()
+    };
+    let (source_columns, target_columns) = (writable(source), writable(target));
+    let has = |columns: &[ColumnSchema], name: &str| columns.iter().any(|c| c.name == name);
+    let only_in = |columns: &[ColumnSchema], other: &[ColumnSchema]| -> Vec<String> {
+        columns
+            .iter()
+            .filter(|column| !has(other, &column.name))
+            .map(|column| column.name.clone())
+            .collect()
+    };
+
+    let columns: Vec<SyncColumn> = source_columns
+        .iter()
+        .filter(|column| has(&target_columns, &column.name))
+        .map(SyncColumn::from_schema)
+        .collect();
+
+    let key_columns = if key_columns.is_empty() {
+        &source.primary_keys
+    } else {
+        key_columns
+    };
+    if key_columns.is_empty() {
+        return Err(format!(
+            "Table {} has no primary key; choose the columns that identify a row",
+            source.name
+        ));
+    }
+    let keys = key_columns
+        .iter()
+        .map(|key| {
+            columns
+                .iter()
+                .position(|column| &column.name == key)
+                .ok_or_else(|| format!("Key column {} isn't in both tables", key))
+        })
+        .collect::<std::result::Result<_, _>>()?;
+
+    Ok(ComparePlan {
+        table: source.name.clone(),
+        source_only: only_in(&source_columns, &target_columns),
+        target_only: only_in(&target_columns, &source_columns),
+        columns,
+        keys,
+    })
+}
+
+impl ComparePlan {
+    pub fn column_names(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|column| column.name.clone())
+            .collect()
+    }
+
+    pub fn key_names(&self) -> Vec<String> {
+        self.keys
+            .iter()
+            .map(|&i| self.columns[i].name.clone())
+            .collect()
+    }
+
+    fn key_of(&self, row: &[Value]) -> Vec<Value> {
+        self.keys.iter().map(|&i| row[i].clone()).collect()
+    }
+
+    fn table_name(&self, database: &str) -> String {
+        format!(
+            "{}.{}",
+            quote_identifier(&DatabaseType::MySQL, database),
+            quote_identifier(&DatabaseType::MySQL, &self.table)
+        )
+    }
+
+    /// `(k1, k2) <op> (?, ?)` over the key columns
+    fn key_comparison(&self, operator: &str) -> String {
+        let keys = self.keys.iter().map(|&i| &self.columns[i]);
+        let names: Vec<String> = keys.clone().map(SyncColumn::quoted).collect();
+        let placeholders: Vec<&str> = keys.map(SyncColumn::placeholder).collect();
+        format!(
+            "({}) {} ({})",
+            names.join(", "),
+            operator,
+            placeholders.join(", ")
+        )
+    }
+
+    /// `k1 <=> ? AND k2 <=> ?`, locating one row
+    fn key_match(&self) -> String {
+        self.keys
+            .iter()
+            .map(|&i| &self.columns[i])
+            .map(|column| format!("{} <=> {}", column.quoted(), column.placeholder()))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    /// Up to `limit` rows in key order, with keys past `after` and up to and
+    /// including `through`
+    pub fn range_query(
+        &self,
+        database: &str,
+        after: Option<&[Value]>,
+        through: Option<&[Value]>,
+        limit: usize,
+    ) -> BoundStatement {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(after) = after {
+            conditions.push(self.key_comparison(">"));
+            params.extend_from_slice(after);
+        }
+        if let Some(through) = through {
+            conditions.push(self.key_comparison("<="));
+            params.extend_from_slice(through);
+        }
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        // Qualified, so binary keys sort by their bytes rather than their hex
+        let order: Vec<String> = self
+            .keys
+            .iter()
+            .map(|&i| {
+                format!(
+                    "{}.{}",
+                    quote_identifier(&DatabaseType::MySQL, &self.table),
+                    self.columns[i].quoted()
+                )
+            })
+            .collect();
+
+        BoundStatement {
+            sql: format!(
+                "SELECT {} FROM {}{} ORDER BY {} LIMIT {}",
+                self.columns
+                    .iter()
+                    .map(SyncColumn::select_expr)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.table_name(database),
+                filter,
+                order.join(", "),
+                limit
+            ),
+            params,
+        }
+    }
+
+    fn insert(&self, database: &str, row: &[Value]) -> BoundStatement {
+        BoundStatement {
+            sql: format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                self.table_name(database),
+                self.columns
+                    .iter()
+                    .map(SyncColumn::quoted)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.columns
+                    .iter()
+                    .map(SyncColumn::placeholder)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            params: row.to_vec(),
+        }
+    }
+
+    /// Set the columns of the target row that differ to the source's values
+    fn update(&self, database: &str, source: &[Value], target: &[Value]) -> BoundStatement {
+        let changed: Vec<usize> = (0..self.columns.len())
+            .filter(|&i| source[i] != target[i])
+            .collect();
+        let assignments: Vec<String> = changed
+            .iter()
+            .map(|&i| {
+                let column = &self.columns[i];
+                format!("{} = {}", column.quoted(), column.placeholder())
+            })
+            .collect();
+        let mut params: Vec<Value> = changed.iter().map(|&i| source[i].clone()).collect();
+        params.extend(self.key_of(target));
+        BoundStatement {
+            sql: format!(
+                "UPDATE {} SET {} WHERE {}",
+                self.table_name(database),
+                assignments.join(", "),
+                self.key_match()
+            ),
+            params,
+        }
+    }
+
+    fn delete(&self, database: &str, row: &[Value]) -> BoundStatement {
+        BoundStatement {
+            sql: format!(
+                "DELETE FROM {} WHERE {}",
+                self.table_name(database),
+                self.key_match()
+            ),
+            params: self.key_of(row),
+        }
+    }
+}
+
+/// Rows of one source chunk waiting for the target rows with their keys
+struct PendingRows {
+    by_key: HashMap<String, VecDeque<Vec<Value>>>,
+    /// Keys in source order, so rows the target lacks are inserted in order
+    order: Vec<String>,
+}
+
+impl PendingRows {
+    fn new(plan: &ComparePlan, rows: Vec<Vec<Value>>) -> Self {
+        let mut pending = Self {
+            by_key: HashMap::new(),
+            order: Vec::new(),
+        };
+        for row in rows {
+            let key = Value::Array(plan.key_of(&row)).to_string();
+            pending.order.push(key.clone());
+            pending.by_key.entry(key).or_default().push_back(row);
+        }
+        pending
+    }
+
+    fn take(&mut self, key: &str) -> Option<Vec<Value>> {
+        self.by_key.get_mut(key).and_then(VecDeque::pop_front)
+    }
+
+    fn into_rest(mut self) -> Vec<Vec<Value>> {
+        let mut rest = Vec::new();
+        for key in &self.order {
+            if let Some(row) = self.by_key.get_mut(key).and_then(VecDeque::pop_front) {
+                rest.push(row);
+            }
+        }
+        rest
+    }
+}
+
+/// Differences found so far, with the statements that resolve them
+struct Comparison<'a> {
+    plan: &'a ComparePlan,
+    target_database: &'a str,
+    diff: TableDataDiff,
+    /// Statements of the current chunk, deletes before updates before
+    /// inserts so unique values can move between rows
+    deletes: Vec<BoundStatement>,
+    updates: Vec<BoundStatement>,
+    inserts: Vec<BoundStatement>,
+}
+
+impl Comparison<'_> {
+    fn pair(&mut self, source: Vec<Value>, target: Vec<Value>) {
+        let changed_columns: Vec<String> = self
+            .plan
+            .columns
+            .iter()
+            .zip(source.iter().zip(&target))
+            .filter(|(_, (a, b))| a != b)
+            .map(|(column, _)| column.name.clone())
+            .collect();
+        if changed_columns.is_empty() {
+            self.diff.unchanged += 1;
+            return;
+        }
+
+        self.updates
+            .push(self.plan.update(self.target_database, &source, &target));
+        self.diff.updated_count += 1;
+        if self.diff.updated.len() < REPORTED_ROWS_LIMIT {
+            self.diff.updated.push(RowChange {
+                key: self.plan.key_of(&target),
+                left: source,
+                right: target,
+                changed_columns,
+            });
+        } else {
+            self.diff.truncated = true;
+        }
+    }
+
+    fn insert(&mut self, source: Vec<Value>) {
+        self.inserts
+            .push(self.plan.insert(self.target_database, &source));
+        self.diff.inserted_count += 1;
+        if self.diff.inserted.len() < REPORTED_ROWS_LIMIT {
+            self.diff.inserted.push(source);
+        } else {
+            self.diff.truncated = true;
+        }
+    }
+
+    fn delete(&mut self, target: Vec<Value>) {
+        self.deletes
+            .push(self.plan.delete(self.target_database, &target));
+        self.diff.deleted_count += 1;
+        if self.diff.deleted.len() < REPORTED_ROWS_LIMIT {
+            self.diff.deleted.push(target);
+        } else {
+            self.diff.truncated = true;
+        }
+    }
+
+    fn take_statements(&mut self) -> Vec<BoundStatement> {
+        let mut statements = std::mem::take(&mut self.deletes);
+        statements.append(&mut self.updates);
+        statements.append(&mut self.inserts);
+        statements
+    }
+}
+
+/// Compare the planned table of `source_database` on `source` with that of
+/// `target_database` on `target`. `Generate` collects the statements that
+/// sync the target; `Apply` runs them as each chunk is compared, so a
+/// failure leaves the chunks before it synced.
+pub async fn compare(
+    plan: &ComparePlan,
+    (source, source_database): (&MySQLAdapter, &str),
+    (target, target_database): (&MySQLAdapter, &str),
+    mode: SyncMode,
+) -> Result<TableDataDiff> {
+    let mut comparison = Comparison {
+        plan,
+        target_database,
+        diff: TableDataDiff {
+            columns: plan.column_names(),
+            key_columns: plan.key_names(),
+            source_only_columns: plan.source_only.clone(),
+            target_only_columns: plan.target_only.clone(),
+            rows_affected: (mode == SyncMode::Apply).then_some(0),
+            ..TableDataDiff::default()
+        },
+        deletes: Vec::new(),
+        updates: Vec::new(),
+        inserts: Vec::new(),
+    };
+
+    let mut after: Option<Vec<Value>> = None;
+    loop {
+        let query = plan.range_query(source_database, after.as_deref(), None, COMPARE_CHUNK_SIZE);
+        let rows = source.fetch_bound(&query).await?;
+        // The last chunk's range runs to the end of the target
+        let through = rows
+            .last()
+            .filter(|_| rows.len() == COMPARE_CHUNK_SIZE)
+            .map(|row| plan.key_of(row));
+        let mut pending = PendingRows::new(plan, rows);
+
+        let mut cursor = after.clone();
+        loop {
+            let query = plan.range_query(
+                target_database,
+                cursor.as_deref(),
+                through.as_deref(),
+                COMPARE_CHUNK_SIZE,
+            );
+            let rows = target.fetch_bound(&query).await?;
+            let full = rows.len() == COMPARE_CHUNK_SIZE;
+            cursor = rows.last().map(|row| plan.key_of(row));
+            for row in rows {
+                let key = Value::Array(plan.key_of(&row)).to_string();
+                match pending.take(&key) {
+                    Some(source_row) => comparison.pair(source_row, row),
+                    None => comparison.delete(row),
+                }
+            }
+            if !full {
+                break;
+            }
+        }
+        for row in pending.into_rest() {
+            comparison.insert(row);
+        }
+
+        let statements = comparison.take_statements();
+        match mode {
+            SyncMode::Report => {}
+            SyncMode::Generate => comparison
+                .diff
+                .statements
+                .extend(statements.iter().map(BoundStatement::render)),
+            SyncMode::Apply => {
+                let affected = target.apply_bound(&statements).await?;
+                *comparison.diff.rows_affected.get_or_insert(0) += affected;
+            }
+        }
+
+        match through {
+            Some(last) => after = Some(last),
+            None => break,
+        }
+    }
+    Ok(comparison.diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(name: &str, data_type: &str) -> ColumnSchema {
+        ColumnSchema {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: true,
+            default_value: None,
+            max_length: None,
+            is_auto_increment: false,
+            is_generated: false,
+            extra: String::new(),
+            allowed_values: None,
+        }
+    }
+
+    fn table(columns: Vec<ColumnSchema>, primary_keys: &[&str]) -> TableSchema {
+        TableSchema {
+            name: "users".to_string(),
+            schema: None,
+            columns,
+            primary_keys: primary_keys.iter().map(|key| key.to_string()).collect(),
+            foreign_keys: vec![],
+        }
+    }
+
+    fn users_plan() -> ComparePlan {
+        let mut full_name = column("full_name", "varchar");
+        full_name.is_generated = true;
+        let source = table(
+            vec![
+                column("id", "int"),
+                column("avatar", "blob"),
+                column("prefs", "json"),
+                column("legacy", "int"),
+                full_name,
+            ],
+            &["id"],
+        );
+        let target = table(
+            vec![
+                column("prefs", "json"),
+                column("id", "int"),
+                column("avatar", "blob"),
+                column("nickname", "varchar"),
+            ],
+            &["id"],
+        );
+        plan(&source, &target, &[]).unwrap()
+    }
+
+    #[test]
+    fn test_plan_columns_and_keys() {
+        let plan = users_plan();
+        assert_eq!(plan.column_names(), vec!["id", "avatar", "prefs"]);
+        assert_eq!(plan.key_names(), vec!["id"]);
+        assert_eq!(plan.source_only, vec!["legacy"]);
+        assert_eq!(plan.target_only, vec!["nickname"]);
+
+        let keyless = table(vec![column("id", "int")], &[]);
+        assert!(plan_err(&keyless, &[]).contains("no primary key"));
+        assert!(plan_err(&keyless, &["email"]).contains("email"));
+    }
+
+    fn plan_err(table: &TableSchema, keys: &[&str]) -> String {
+        let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        plan(table, table, &keys).unwrap_err()
+    }
+
+    #[test]
+    fn test_range_query_binds_key_bounds() {
+        let plan = users_plan();
+        let query = plan.range_query("app", Some(&[json!(10)]), Some(&[json!(20)]), 500);
+        assert_eq!(
+            query.sql,
+            "SELECT `id`, HEX(`avatar`) AS `avatar`, CAST(`prefs` AS CHAR) AS `prefs` \
+             FROM `app`.`users` WHERE (`id`) > (?) AND (`id`) <= (?) \
+             ORDER BY `users`.`id` LIMIT 500"
+        );
+        assert_eq!(query.params, vec![json!(10), json!(20)]);
+    }
+
+    #[test]
+    fn test_sync_statements_render_as_literals() {
+        let plan = users_plan();
+        let source = vec![
+            json!(1),
+            json!("CAFE"),
+            json!("{\"theme\": \"it's\\\\dark\"}"),
+        ];
+        let target = vec![json!(1), json!("CAFE"), json!("{}")];
+
+        assert_eq!(
+            plan.insert("app", &source).render(),
+            "INSERT INTO `app`.`users` (`id`, `avatar`, `prefs`) VALUES \
+             (1, UNHEX('CAFE'), CAST('{\"theme\": \"it''s\\\\\\\\dark\"}' AS JSON))"
+        );
+        let update = plan.update("app", &source, &target);
+        assert_eq!(
+            update.sql,
+            "UPDATE `app`.`users` SET `prefs` = CAST(? AS JSON) WHERE `id` <=> ?"
+        );
+        assert_eq!(update.params, vec![source[2].clone(), json!(1)]);
+        assert_eq!(
+            plan.delete("app", &target).render(),
+            "DELETE FROM `app`.`users` WHERE `id` <=> 1"
+        );
+    }
+
+    #[test]
+    fn test_question_marks_in_identifiers_are_kept() {
+        let statement = BoundStatement {
+            sql: "DELETE FROM `why?` WHERE `id` <=> ?".to_string(),
+            params: vec![json!("a")],
+        };
+        assert_eq!(statement.render(), "DELETE FROM `why?` WHERE `id` <=> 'a'");
+    }
+
+    #[test]
+    fn test_pairs_rows_by_key() {
+        let plan = users_plan();
+        let mut comparison = Comparison {
+            plan: &plan,
+            target_database: "app",
+            diff: TableDataDiff::default(),
+            deletes: Vec::new(),
+            updates: Vec::new(),
+            inserts: Vec::new(),
+        };
+        let row = |id: i64, prefs: &str| vec![json!(id), Value::Null, json!(prefs)];
+        let mut pending = PendingRows::new(&plan, vec![row(1, "{}"), row(2, "{}"), row(3, "{}")]);
+        for target in [row(2, "{}"), row(3, "[]"), row(4, "{}")] {
+            let key = Value::Array(plan.key_of(&target)).to_string();
+            match pending.take(&key) {
+                Some(source) => comparison.pair(source, target),
+                None => comparison.delete(target),
+            }
+        }
+        for source in pending.into_rest() {
+            comparison.insert(source);
+        }
+
+        let diff = &comparison.diff;
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.inserted, vec![row(1, "{}")]);
+        assert_eq!(diff.deleted, vec![row(4, "{}")]);
+        assert_eq!(diff.updated[0].changed_columns, vec!["prefs"]);
+        let statements: Vec<String> = comparison
+            .take_statements()
+            .iter()
+            .map(BoundStatement::render)
+            .collect();
+        assert_eq!(
+            statements,
+            vec![
+                "DELETE FROM `app`.`users` WHERE `id` <=> 4",
+                "UPDATE `app`.`users` SET `prefs` = CAST('{}' AS JSON) WHERE `id` <=> 3",
+                "INSERT INTO `app`.`users` (`id`, `avatar`, `prefs`) VALUES \
+                 (1, UNHEX(NULL), CAST('{}' AS JSON))",
+            ]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Keywords and built-in functions of each SQL dialect, for autocomplete
+
+use crate::models::schema::SQL_KEYWORDS;
+use crate::models::{DatabaseType, FunctionInfo};
+
+const MYSQL_KEYWORDS: &[&str] = &[
+    "SHOW",
+    "DESCRIBE",
+    "EXPLAIN",
+    "USE",
+    "REPLACE",
+    "IGNORE",
+    "STRAIGHT_JOIN",
+    "DUPLICATE",
+    "REGEXP",
+    "RLIKE",
+    "AUTO_INCREMENT",
+    "ENGINE",
+    "CHARSET",
+    "COLLATE",
+    "UNSIGNED",
+    "ZEROFILL",
+    "LOCK",
+    "UNLOCK",
+    "TABLES",
+    "PROCEDURE",
+    "SEPARATOR",
+    "INTERVAL",
+];
+
+const POSTGRES_KEYWORDS: &[&str] = &[
+    "RETURNING",
+    "ILIKE",
+    "SIMILAR",
+    "LATERAL",
+    "CONFLICT",
+    "DO",
+    "NOTHING",
+    "SERIAL",
+    "BIGSERIAL",
+    "JSONB",
+    "SCHEMA",
+    "SEQUENCE",
+    "MATERIALIZED",
+    "CONCURRENTLY",
+    "VACUUM",
+    "ANALYZE",
+    "INTERVAL",
+];
+
+/// (name, signature, description) of functions every dialect has
+const COMMON_FUNCTIONS: &[(&str, &str, &str)] = &[
+    (
+        "COUNT",
+        "COUNT(expr)",
+        "Number of rows, or of non-NULL values of expr",
+    ),
+    ("SUM", "SUM(expr)", "Sum of the values"),
+    ("AVG", "AVG(expr)", "Average of the values"),
+    ("MIN", "MIN(expr)", "Smallest value"),
+    ("MAX", "MAX(expr)", "Largest value"),
+    (
+        "COALESCE",
+        "COALESCE(value, ...)",
+        "First argument that isn't NULL",
+    ),
+    (
+        "NULLIF",
+        "NULLIF(a, b)",
+        "NULL when a equals b, otherwise a",
+    ),
+    ("CONCAT", "CONCAT(str, ...)", "Concatenate strings"),
+    ("LOWER", "LOWER(str)", "Lowercase a string"),
+    ("UPPER", "UPPER(str)", "Uppercase a string"),
+    ("TRIM", "TRIM(str)", "Remove leading and trailing spaces"),
+    (
+        "REPLACE",
+        "REPLACE(str, from, to)",
+        "Replace every occurrence of a substring",
+    ),
+    (
+        "ROUND",
+        "ROUND(x[, digits])",
+        "Round to a number of decimal places",
+    ),
+    ("ABS", "ABS(x)", "Absolute value"),
+    (
+        "CAST",
+        "CAST(expr AS type)",
+        "Convert a value to another type",
+    ),
+];
+
+const MYSQL_FUNCTIONS: &[(&str, &str, &str)] = &[
+    ("NOW", "NOW()", "Current date and time"),
+    ("CURDATE", "CURDATE()", "Current date"),
+    (
+        "DATE_FORMAT",
+        "DATE_FORMAT(date, format)",
+        "Format a date, e.g. '%Y-%m-%d'",
+    ),
+    (
+        "STR_TO_DATE",
+        "STR_TO_DATE(str, format)",
+        "Parse a string into a date",
+    ),
+    (
+        "DATE_ADD",
+        "DATE_ADD(date, INTERVAL expr unit)",
+        "Add an interval to a date",
+    ),
+    ("DATEDIFF", "DATEDIFF(end, start)", "Days between two dates"),
+    (
+        "UNIX_TIMESTAMP",
+        "UNIX_TIMESTAMP([date])",
+        "Seconds since the Unix epoch",
+    ),
+    (
+        "FROM_UNIXTIME",
+        "FROM_UNIXTIME(seconds[, format])",
+        "Date from a Unix timestamp",
+    ),
+    (
+        "CONCAT_WS",
+        "CONCAT_WS(separator, str, ...)",
+        "Concatenate with a separator",
+    ),
+    (
+        "SUBSTRING",
+        "SUBSTRING(str, pos[, len])",
+        "Part of a string, counting from 1",
+    ),
+    ("CHAR_LENGTH", "CHAR_LENGTH(str)", "Length in characters"),
+    ("LENGTH", "LENGTH(str)", "Length in bytes"),
+    (
+        "IFNULL",
+        "IFNULL(expr, fallback)",
+        "fallback when expr is NULL",
+    ),
+    (
+        "IF",
+        "IF(condition, then, else)",
+        "Choose a value by condition",
+    ),
+    (
+        "GROUP_CONCAT",
+        "GROUP_CONCAT(expr [ORDER BY ...] [SEPARATOR sep])",
+        "Join a group's values into one string",
+    ),
+    (
+        "FIND_IN_SET",
+        "FIND_IN_SET(str, list)",
+        "Position of str in a comma-separated list",
+    ),
+    (
+        "JSON_EXTRACT",
+        "JSON_EXTRACT(doc, path[, path] ...)",
+        "Values at JSON paths, e.g. '$.name'",
+    ),
+    (
+        "JSON_UNQUOTE",
+        "JSON_UNQUOTE(json)",
+        "Unquote a JSON string value",
+    ),
+    (
+        "JSON_OBJECT",
+        "JSON_OBJECT(key, value[, ...])",
+        "Build a JSON object",
+    ),
+    (
+        "JSON_ARRAYAGG",
+        "JSON_ARRAYAGG(expr)",
+        "Aggregate values into a JSON array",
+    ),
+    (
+        "JSON_CONTAINS",
+        "JSON_CONTAINS(target, candidate[, path])",
+        "Whether a JSON document contains another",
+    ),
+    ("UUID", "UUID()", "A new version 1 UUID"),
+    (
+        "LAST_INSERT_ID",
+        "LAST_INSERT_ID()",
+        "AUTO_INCREMENT value of the last insert",
+    ),
+];
+
+const POSTGRES_FUNCTIONS: &[(&str, &str, &str)] = &[
+    ("now", "now()", "Current date and time"),
+    (
+        "to_char",
+        "to_char(value, format)",
+        "Format a date or number, e.g. 'YYYY-MM-DD'",
+    ),
+    ("to_date", "to_date(text, format)", "Parse text into a date"),
+    (
+        "to_timestamp",
+        "to_timestamp(text, format)",
+        "Parse text into a timestamp",
+    ),
+    (
+        "date_trunc",
+        "date_trunc(field, source)",
+        "Truncate a timestamp, e.g. to 'day'",
+    ),
+    (
+        "extract",
+        "extract(field FROM source)",
+        "A part of a date, e.g. year",
+    ),
+    (
+        "age",
+        "age(timestamp[, timestamp])",
+        "Interval between timestamps",
+    ),
+    (
+        "substring",
+        "substring(str FROM start FOR count)",
+        "Part of a string, counting from 1",
+    ),
+    ("length", "length(str)", "Length in characters"),
+    (
+        "split_part",
+        "split_part(str, delimiter, n)",
+        "The nth field of a delimited string",
+    ),
+    (
+        "regexp_replace",
+        "regexp_replace(source, pattern, replacement[, flags])",
+        "Replace regular expression matches",
+    ),
+    (
+        "string_agg",
+        "string_agg(expr, delimiter)",
+        "Join a group's values into one string",
+    ),
+    (
+        "array_agg",
+        "array_agg(expr)",
+        "Aggregate values into an array",
+    ),
+    (
+        "jsonb_extract_path",
+        "jsonb_extract_path(from_json, VARIADIC path)",
+        "Value at a JSON path",
+    ),
+    (
+        "jsonb_build_object",
+        "jsonb_build_object(VARIADIC args)",
+        "Build a JSON object from key/value pairs",
+    ),
+    (
+        "jsonb_agg",
+        "jsonb_agg(expr)",
+        "Aggregate values into a JSON array",
+    ),
+    (
+        "jsonb_set",
+        "jsonb_set(target, path, new_value[, create_missing])",
+        "Replace a value in a JSON document",
+    ),
+    (
+        "jsonb_array_elements",
+        "jsonb_array_elements(jsonb)",
+        "Expand a JSON array into rows",
+    ),
+    ("to_jsonb", "to_jsonb(value)", "Convert a value to JSON"),
+    (
+        "generate_series",
+        "generate_series(start, stop[, step])",
+        "A set of values from start to stop",
+    ),
+    (
+        "gen_random_uuid",
+        "gen_random_uuid()",
+        "A new version 4 UUID",
+    ),
+    (
+        "greatest",
+        "greatest(value, ...)",
+        "Largest of the arguments",
+    ),
+    ("least", "least(value, ...)", "Smallest of the arguments"),
+];
+
+/// Standard keywords plus the ones only `db_type` understands
+pub fn keywords(db_type: &DatabaseType) -> Vec<String> {
+    let specific = match db_type {
+        DatabaseType::MySQL => MYSQL_KEYWORDS,
+        DatabaseType::PostgreSQL => POSTGRES_KEYWORDS,
+    };
+    SQL_KEYWORDS
+        .iter()
+        .chain(specific)
+        .map(|keyword| keyword.to_string())
+        .collect()
+}
+
+/// Built-in functions of `db_type`
+pub fn functions(db_type: &DatabaseType) -> Vec<FunctionInfo> {
+    let specific = match db_type {
+        DatabaseType::MySQL => MYSQL_FUNCTIONS,
+        DatabaseType::PostgreSQL => POSTGRES_FUNCTIONS,
+    };
+    COMMON_FUNCTIONS
+        .iter()
+        .chain(specific)
+        .map(|&(name, signature, description)| FunctionInfo {
+            name: name.to_string(),
+            signature: signature.to_string(),
+            description: description.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(db_type: &DatabaseType) -> Vec<String> {
+        functions(db_type).into_iter().map(|f| f.name).collect()
+    }
+
+    #[test]
+    fn test_functions_per_dialect() {
+        let mysql = names(&DatabaseType::MySQL);
+        assert!(mysql.contains(&"DATE_FORMAT".to_string()));
+        assert!(mysql.contains(&"COUNT".to_string()));
+        assert!(!mysql.contains(&"to_char".to_string()));
+
+        let postgres = names(&DatabaseType::PostgreSQL);
+        assert!(postgres.contains(&"to_char".to_string()));
+        assert!(postgres.contains(&"jsonb_set".to_string()));
+        assert!(!postgres.contains(&"JSON_EXTRACT".to_string()));
+    }
+
+    #[test]
+    fn test_keywords_are_unique_per_dialect() {
+        for db_type in [DatabaseType::MySQL, DatabaseType::PostgreSQL] {
+            let mut keywords = keywords(&db_type);
+            let count = keywords.len();
+            keywords.sort();
+            keywords.dedup();
+            assert_eq!(keywords.len(), count, "duplicate keyword for {}", db_type);
+        }
+        assert!(keywords(&DatabaseType::MySQL).contains(&"REGEXP".to_string()));
+        assert!(keywords(&DatabaseType::PostgreSQL).contains(&"RETURNING".to_string()));
+    }
+}
@@ -0,0 +1,216 @@
+//! Comparing two result sets row by row
+
+use crate::db::result_cache::{Result, ResultError};
+use crate::models::{QueryResult, ResultDiff, RowChange};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+
+/// Rows of `right` missing from `left` are added, rows of `left` missing from
+/// `right` removed, and rows with the same key but other values changed.
+/// Rows are matched on `key_columns`, or on every column when there are none,
+/// and repeated keys are paired in fetched order. Only the columns both
+/// results have are compared.
+pub fn compare(
+    left: &QueryResult,
+    right: &QueryResult,
+    key_columns: &[String],
+) -> Result<ResultDiff> {
+    let columns: Vec<String> = left
+        .columns
+        .iter()
+        .filter(|column| right.columns.contains(column))
+        .cloned()
+        .collect();
+    let only_in = |result: &QueryResult, other: &QueryResult| -> Vec<String> {
+        result
+            .columns
+            .iter()
+            .filter(|column| !other.columns.contains(column))
+            .cloned()
+            .collect()
+    };
+
+    let keys: Vec<usize> = if key_columns.is_empty() {
+        (0..columns.len()).collect()
+    } else {
+        key_columns
+            .iter()
+            .map(|key| {
+                columns
+                    .iter()
+                    .position(|column| column == key)
+                    .ok_or_else(|| ResultError::UnknownColumn(key.clone()))
+            })
+            .collect::<Result<_>>()?
+    };
+    let key_of = |row: &[Value]| -> Vec<Value> { keys.iter().map(|&i| row[i].clone()).collect() };
+
+    let left_rows = project(left, &columns);
+    let right_rows = project(right, &columns);
+
+    // Indexes of right rows not yet paired, by key
+    let mut unpaired: HashMap<String, VecDeque<usize>> = HashMap::new();
+    for (index, row) in right_rows.iter().enumerate() {
+        let key = Value::Array(key_of(row)).to_string();
+        unpaired.entry(key).or_default().push_back(index);
+    }
+
+    let mut paired = vec![false; right_rows.len()];
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    let mut unchanged = 0;
+    for row in left_rows {
+        let key = key_of(&row);
+        let Some(index) = unpaired
+            .get_mut(&Value::Array(key.clone()).to_string())
+            .and_then(VecDeque::pop_front)
+        else {
+            removed.push(row);
+            continue;
+        };
+        paired[index] = true;
+
+        let other = &right_rows[index];
+        let changed_columns: Vec<String> = columns
+            .iter()
+            .zip(row.iter().zip(other))
+            .filter(|(_, (a, b))| a != b)
+            .map(|(column, _)| column.clone())
+            .collect();
+        if changed_columns.is_empty() {
+            unchanged += 1;
+        } else {
+            changed.push(RowChange {
+                key,
+                left: row,
+                right: other.clone(),
+                changed_columns,
+            });
+        }
+    }
+
+    let added = right_rows
+        .into_iter()
+        .zip(paired)
+        .filter(|(_, paired)| !paired)
+        .map(|(row, _)| row)
+        .collect();
+
+    Ok(ResultDiff {
+        key_columns: keys.iter().map(|&i| columns[i].clone()).collect(),
+        left_only_columns: only_in(left, right),
+        right_only_columns: only_in(right, left),
+        columns,
+        added,
+        removed,
+        changed,
+        unchanged,
+        truncated: left.truncated || right.truncated,
+    })
+}
+
+/// Rows of `result` holding only `columns`, in that order
+fn project(result: &QueryResult, columns: &[String]) -> Vec<Vec<Value>> {
+    let indexes: Vec<usize> = columns
+        .iter()
+        .filter_map(|column| result.columns.iter().position(|c| c == column))
+        .collect();
+    result
+        .rows
+        .iter()
+        .map(|row| {
+            indexes
+                .iter()
+                .map(|&i| row.get(i).cloned().unwrap_or(Value::Null))
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(columns: &[&str], rows: Vec<Vec<Value>>) -> QueryResult {
+        QueryResult {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            total_rows: rows.len(),
+            rows,
+            execution_time_ms: 0,
+            result_id: None,
+            truncated: false,
+            page_size: None,
+            rows_affected: 0,
+            column_types: vec![],
+            cached: false,
+            cached_at: None,
+            auto_limit: None,
+            unbounded: false,
+            has_more: false,
+            dml: false,
+            last_insert_id: None,
+            warnings: vec![],
+            more_results: vec![],
+            spilled: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_by_key() {
+        let left = result(
+            &["id", "name", "legacy"],
+            vec![
+                vec![json!(1), json!("a"), json!(0)],
+                vec![json!(2), json!("b"), json!(0)],
+                vec![json!(3), json!("c"), json!(0)],
+            ],
+        );
+        let right = result(
+            &["name", "id"],
+            vec![
+                vec![json!("a"), json!(1)],
+                vec![json!("B"), json!(2)],
+                vec![json!("d"), json!(4)],
+            ],
+        );
+
+        let diff = compare(&left, &right, &["id".to_string()]).unwrap();
+        assert_eq!(diff.columns, vec!["id", "name"]);
+        assert_eq!(diff.left_only_columns, vec!["legacy"]);
+        assert!(diff.right_only_columns.is_empty());
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.removed, vec![vec![json!(3), json!("c")]]);
+        assert_eq!(diff.added, vec![vec![json!(4), json!("d")]]);
+        assert_eq!(
+            diff.changed,
+            vec![RowChange {
+                key: vec![json!(2)],
+                left: vec![json!(2), json!("b")],
+                right: vec![json!(2), json!("B")],
+                changed_columns: vec!["name".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_compare_whole_rows_with_duplicates() {
+        let left = result(&["n"], vec![vec![json!(1)], vec![json!(1)], vec![json!(2)]]);
+        let right = result(&["n"], vec![vec![json!(1)], vec![json!(2)], vec![json!(2)]]);
+
+        let diff = compare(&left, &right, &[]).unwrap();
+        assert_eq!(diff.key_columns, vec!["n"]);
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(diff.removed, vec![vec![json!(1)]]);
+        assert_eq!(diff.added, vec![vec![json!(2)]]);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_key_column() {
+        let left = result(&["id"], vec![]);
+        let right = result(&["id", "extra"], vec![]);
+        let err = compare(&left, &right, &["extra".to_string()]).unwrap_err();
+        assert!(matches!(err, ResultError::UnknownColumn(column) if column == "extra"));
+    }
+}
@@ -0,0 +1,278 @@
+//! Caps how many statements run at once on each connection, so several
+//! heavy queries started from different tabs can't all land on a small
+//! server at the same time. Statements beyond the cap wait in line, first
+//! come first served, and learn their place through a callback.
+
+use crate::models::QueuePosition;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Event emitted with a `QueuePosition` while a statement waits and once it
+/// starts
+pub const QUERY_QUEUE_EVENT: &str = "query://queue";
+
+type Reporter = Box<dyn Fn(QueuePosition) + Send + Sync>;
+
+struct Waiter {
+    id: u64,
+    request_id: Option<String>,
+    start: oneshot::Sender<()>,
+    report: Reporter,
+}
+
+#[derive(Default)]
+struct ConnectionQueue {
+    running: usize,
+    /// Most recent cap asked for; unlimited when None
+    limit: Option<usize>,
+    waiting: VecDeque<Waiter>,
+}
+
+impl ConnectionQueue {
+    fn has_room(&self) -> bool {
+        self.limit.is_none_or(|limit| self.running < limit.max(1))
+    }
+
+    /// Start waiters while there is room, then tell the rest their places
+    fn advance(&mut self, connection_id: &str) {
+        while self.has_room() {
+            let Some(waiter) = self.waiting.pop_front() else {
+                break;
+            };
+            // A waiter whose caller gave up can't take the slot
+            if waiter.start.send(()).is_ok() {
+                self.running += 1;
+                (waiter.report)(position(connection_id, &waiter.request_id, 0));
+            }
+        }
+        for (index, waiter) in self.waiting.iter().enumerate() {
+            (waiter.report)(position(connection_id, &waiter.request_id, index + 1));
+        }
+    }
+}
+
+fn position(connection_id: &str, request_id: &Option<String>, position: usize) -> QueuePosition {
+    QueuePosition {
+        connection_id: connection_id.to_string(),
+        request_id: request_id.clone(),
+        position,
+    }
+}
+
+#[derive(Default)]
+struct Queues {
+    connections: HashMap<String, ConnectionQueue>,
+    next_id: u64,
+}
+
+/// Statement slots of every connection
+#[derive(Clone, Default)]
+pub struct ExecutionQueue {
+    queues: Arc<Mutex<Queues>>,
+}
+
+/// A running statement's slot, given back when dropped
+pub struct QueuePermit {
+    queues: Arc<Mutex<Queues>>,
+    connection_id: String,
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        if let Ok(mut queues) = self.queues.lock() {
+            if let Some(queue) = queues.connections.get_mut(&self.connection_id) {
+                queue.running = queue.running.saturating_sub(1);
+                queue.advance(&self.connection_id);
+                if queue.running == 0 && queue.waiting.is_empty() {
+                    queues.connections.remove(&self.connection_id);
+                }
+            }
+        }
+    }
+}
+
+/// Takes a waiter out of line when its caller stops waiting, e.g. because
+/// the command was dropped, and gives back a slot it was handed but never
+/// took
+struct WaitGuard<'a> {
+    queues: &'a Mutex<Queues>,
+    connection_id: &'a str,
+    id: u64,
+    started: oneshot::Receiver<()>,
+    taken: bool,
+}
+
+impl Drop for WaitGuard<'_> {
+    fn drop(&mut self) {
+        if self.taken {
+            return;
+        }
+        let handed_slot = self.started.try_recv().is_ok();
+        if let Ok(mut queues) = self.queues.lock() {
+            if let Some(queue) = queues.connections.get_mut(self.connection_id) {
+                if handed_slot {
+                    queue.running = queue.running.saturating_sub(1);
+                }
+                queue.waiting.retain(|waiter| waiter.id != self.id);
+                queue.advance(self.connection_id);
+            }
+        }
+    }
+}
+
+impl ExecutionQueue {
+    /// Wait for a slot to run a statement on `connection_id`, with at most
+    /// `limit` running at once. `report` is told the statement's place in
+    /// line while it waits, and position 0 when it starts; a statement that
+    /// doesn't have to wait isn't reported.
+    pub async fn acquire(
+        &self,
+        connection_id: &str,
+        request_id: Option<String>,
+        limit: Option<usize>,
+        report: impl Fn(QueuePosition) + Send + Sync + 'static,
+    ) -> QueuePermit {
+        let permit = || QueuePermit {
+            queues: self.queues.clone(),
+            connection_id: connection_id.to_string(),
+        };
+
+        let (start, started) = oneshot::channel();
+        let id = {
+            let Ok(mut queues) = self.queues.lock() else {
+                // Without the queue's state, run unthrottled rather than not at all
+                return permit();
+            };
+            queues.next_id += 1;
+            let id = queues.next_id;
+            let queue = queues
+                .connections
+                .entry(connection_id.to_string())
+                .or_default();
+            queue.limit = limit;
+            if queue.waiting.is_empty() && queue.has_room() {
+                queue.running += 1;
+                return permit();
+            }
+
+            let place = queue.waiting.len() + 1;
+            report(position(connection_id, &request_id, place));
+            queue.waiting.push_back(Waiter {
+                id,
+                request_id,
+                start,
+                report: Box::new(report),
+            });
+            id
+        };
+
+        let mut guard = WaitGuard {
+            queues: &self.queues,
+            connection_id,
+            id,
+            started,
+            taken: false,
+        };
+        // The sender is only dropped unsent when the queue itself is gone
+        let _ = (&mut guard.started).await;
+        guard.taken = true;
+        permit()
+    }
+
+    /// Statements running and waiting on a connection
+    pub fn load(&self, connection_id: &str) -> (usize, usize) {
+        self.queues
+            .lock()
+            .ok()
+            .and_then(|queues| {
+                queues
+                    .connections
+                    .get(connection_id)
+                    .map(|queue| (queue.running, queue.waiting.len()))
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn recorder() -> (
+        Arc<Mutex<Vec<usize>>>,
+        impl Fn(QueuePosition) + Send + Sync + 'static,
+    ) {
+        let positions = Arc::new(Mutex::new(Vec::new()));
+        let sink = positions.clone();
+        (positions, move |event: QueuePosition| {
+            sink.lock().unwrap().push(event.position)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_statements_beyond_limit_wait_in_order() {
+        let queue = ExecutionQueue::default();
+        let first = queue.acquire("replica", None, Some(1), |_| {}).await;
+
+        let (second_positions, report) = recorder();
+        let waiting = queue.clone();
+        let second = tokio::spawn(async move {
+            let _permit = waiting
+                .acquire("replica", Some("b".to_string()), Some(1), report)
+                .await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let (third_positions, report) = recorder();
+        let waiting = queue.clone();
+        let third = tokio::spawn(async move {
+            waiting
+                .acquire("replica", Some("c".to_string()), Some(1), report)
+                .await;
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(queue.load("replica"), (1, 2));
+
+        // Other connections have slots of their own
+        let _other = queue.acquire("primary", None, Some(1), |_| {}).await;
+
+        drop(first);
+        second.await.unwrap();
+        third.await.unwrap();
+        assert_eq!(*second_positions.lock().unwrap(), vec![1, 0]);
+        assert_eq!(*third_positions.lock().unwrap(), vec![2, 1, 0]);
+        assert_eq!(queue.load("replica"), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiter_leaves_the_line() {
+        let queue = ExecutionQueue::default();
+        let first = queue.acquire("replica", None, Some(1), |_| {}).await;
+
+        let waiting = queue.clone();
+        let abandoned = tokio::spawn(async move {
+            waiting.acquire("replica", None, Some(1), |_| {}).await;
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        abandoned.abort();
+        let _ = abandoned.await;
+        assert_eq!(queue.load("replica"), (1, 0));
+
+        drop(first);
+        let _next = queue.acquire("replica", None, Some(1), |_| {}).await;
+        assert_eq!(queue.load("replica"), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_connection_never_waits() {
+        let queue = ExecutionQueue::default();
+        let mut permits = Vec::new();
+        for _ in 0..10 {
+            permits.push(queue.acquire("local", None, None, |_| {}).await);
+        }
+        assert_eq!(queue.load("local"), (10, 0));
+    }
+}
@@ -0,0 +1,716 @@
+//! Writing cached results to files for use in other tools. Parquet files keep
+//! each column's type, so pandas or DuckDB read numbers, decimals and dates
+//! without the guessing a CSV round trip needs; Markdown and HTML tables are
+//! for pasting results into issues, wikis and emails.
+
+use crate::db::result_cache::CachedResult;
+use crate::models::{ColumnTypeInfo, ExportFormat, ValueKind};
+use arrow_array::builder::{BinaryBuilder, BooleanBuilder, PrimitiveBuilder, StringBuilder};
+use arrow_array::types::{
+    Date32Type, Decimal128Type, DurationMicrosecondType, Float32Type, Float64Type, Int16Type,
+    Int32Type, Int64Type, Int8Type, TimestampMicrosecondType, UInt16Type, UInt32Type, UInt64Type,
+    UInt8Type,
+};
+use arrow_array::{ArrayRef, ArrowPrimitiveType, RecordBatch, RecordBatchOptions};
+use arrow_schema::{ArrowError, Field, Schema};
+use base64::{engine::general_purpose, Engine as _};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Largest precision of an Arrow `Decimal128`
+const MAX_DECIMAL_PRECISION: u8 = 38;
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+
+    #[error("Export cancelled")]
+    Cancelled,
+}
+
+pub type Result<T> = std::result::Result<T, ExportError>;
+
+/// Style of HTML header cells; inline, since email clients drop style sheets
+const HTML_HEADER_STYLE: &str =
+    "border: 1px solid #d0d7de; padding: 4px 8px; background: #f6f8fa; text-align: left";
+
+const HTML_CELL_STYLE: &str = "border: 1px solid #d0d7de; padding: 4px 8px";
+
+/// Rows encoded between two progress reports of a Parquet export
+const PROGRESS_ROWS: usize = 10_000;
+
+/// Bytes written between two progress reports of a text export
+const PROGRESS_BYTES: usize = 1024 * 1024;
+
+/// Write every row of a result to `path` in `format`, returning the number
+/// of rows written. `progress` is called with the rows and bytes written so
+/// far as the export goes. When `progress` returns false the export stops and
+/// the partial file is removed.
+pub fn write(
+    result: &CachedResult,
+    format: ExportFormat,
+    path: &Path,
+    progress: &mut dyn FnMut(usize, u64) -> bool,
+) -> Result<usize> {
+    let outcome = match render(result, format) {
+        Some(text) => write_text(&text, result.row_count(), path, progress),
+        None => write_parquet(result, path, progress),
+    };
+    if matches!(outcome, Err(ExportError::Cancelled)) {
+        let _ = std::fs::remove_file(path);
+    }
+    outcome
+}
+
+/// Write rendered text in chunks. A chunk boundary can fall anywhere in a
+/// row, so the rows written are estimated from the share of the text written.
+fn write_text(
+    text: &str,
+    rows: usize,
+    path: &Path,
+    progress: &mut dyn FnMut(usize, u64) -> bool,
+) -> Result<usize> {
+    let mut file = File::create(path)?;
+    let mut written = 0u64;
+    for chunk in text.as_bytes().chunks(PROGRESS_BYTES) {
+        file.write_all(chunk)?;
+        written += chunk.len() as u64;
+        let done = (rows as u64 * written / text.len() as u64) as usize;
+        if !progress(done, written) {
+            return Err(ExportError::Cancelled);
+        }
+    }
+    Ok(rows)
+}
+
+/// The result in a text format, or None for Parquet
+pub fn render(result: &CachedResult, format: ExportFormat) -> Option<String> {
+    match format {
+        ExportFormat::Parquet => None,
+        ExportFormat::Markdown => Some(render_markdown(result)),
+        ExportFormat::Html => Some(render_html(result)),
+    }
+}
+
+/// Write every row of a result to a Parquet file, returning the number of
+/// rows written
+pub fn write_parquet(
+    result: &CachedResult,
+    path: &Path,
+    progress: &mut dyn FnMut(usize, u64) -> bool,
+) -> Result<usize> {
+    let batch = record_batch(result)?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))?;
+    let mut offset = 0;
+    while offset < batch.num_rows() {
+        let length = PROGRESS_ROWS.min(batch.num_rows() - offset);
+        writer.write(&batch.slice(offset, length))?;
+        offset += length;
+        if !progress(offset, writer.bytes_written() as u64) {
+            return Err(ExportError::Cancelled);
+        }
+    }
+    writer.close()?;
+    Ok(batch.num_rows())
+}
+
+/// The result as Arrow columns. A column is only nullable when it holds a
+/// NULL; one whose values don't all fit its Arrow type is written as text.
+pub fn record_batch(result: &CachedResult) -> Result<RecordBatch> {
+    let mut fields = Vec::with_capacity(result.columns.len());
+    let mut arrays = Vec::with_capacity(result.columns.len());
+    for (index, name) in result.columns.iter().enumerate() {
+        let values = result.column_values(index);
+        let array = match result.column_types().get(index) {
+            Some(column_type) => typed_array(column_type, values),
+            None => inferred_array(values),
+        }
+        .unwrap_or_else(|| text_array(values));
+
+        let mut field = Field::new(
+            name.clone(),
+            array.data_type().clone(),
+            values.iter().any(Value::is_null),
+        );
+        if result
+            .column_types()
+            .get(index)
+            .is_some_and(|column_type| column_type.kind == ValueKind::Json)
+        {
+            field = field.with_metadata(HashMap::from([(
+                "ARROW:extension:name".to_string(),
+                "arrow.json".to_string(),
+            )]));
+        }
+        fields.push(field);
+        arrays.push(array);
+    }
+
+    let options = RecordBatchOptions::new().with_row_count(Some(result.row_count()));
+    Ok(RecordBatch::try_new_with_options(
+        Arc::new(Schema::new(fields)),
+        arrays,
+        &options,
+    )?)
+}
+
+/// The column as the Arrow type matching its server type, or None when a
+/// value doesn't convert, e.g. a `0000-00-00` date
+fn typed_array(column_type: &ColumnTypeInfo, values: &[Value]) -> Option<ArrayRef> {
+    match column_type.kind {
+        ValueKind::Boolean => boolean_array(values),
+        ValueKind::Integer => integer_array(&column_type.type_name, values),
+        ValueKind::Float if column_type.type_name == "FLOAT" => {
+            primitive_array::<Float32Type>(values, |value| Some(value.as_f64()? as f32))
+        }
+        ValueKind::Float => primitive_array::<Float64Type>(values, Value::as_f64),
+        ValueKind::Decimal => decimal_array(values),
+        ValueKind::Date => primitive_array::<Date32Type>(values, |value| {
+            parse_date(value.as_str()?)?.try_into().ok()
+        }),
+        ValueKind::DateTime => primitive_array::<TimestampMicrosecondType>(values, |value| {
+            parse_datetime(value.as_str()?)
+        }),
+        // TIME values can be negative or exceed a day, so they're durations
+        ValueKind::Time => {
+            primitive_array::<DurationMicrosecondType>(values, |value| parse_time(value.as_str()?))
+        }
+        ValueKind::Year => {
+            primitive_array::<UInt16Type>(values, |value| value.as_u64()?.try_into().ok())
+        }
+        ValueKind::Bit => primitive_array::<UInt64Type>(values, Value::as_u64),
+        ValueKind::Binary | ValueKind::Geometry => binary_array(values),
+        ValueKind::Text | ValueKind::Enum | ValueKind::Json | ValueKind::Other => None,
+    }
+}
+
+/// A column without type information, typed from its values
+fn inferred_array(values: &[Value]) -> Option<ArrayRef> {
+    primitive_array::<Int64Type>(values, Value::as_i64)
+        .or_else(|| primitive_array::<Float64Type>(values, Value::as_f64))
+        .or_else(|| boolean_array(values))
+}
+
+fn primitive_array<T: ArrowPrimitiveType>(
+    values: &[Value],
+    convert: impl Fn(&Value) -> Option<T::Native>,
+) -> Option<ArrayRef> {
+    let mut builder = PrimitiveBuilder::<T>::with_capacity(values.len());
+    for value in values {
+        match value {
+            Value::Null => builder.append_null(),
+            value => builder.append_value(convert(value)?),
+        }
+    }
+    Some(Arc::new(builder.finish()))
+}
+
+fn integer_array(type_name: &str, values: &[Value]) -> Option<ArrayRef> {
+    match type_name {
+        "TINYINT" => primitive_array::<Int8Type>(values, |value| value.as_i64()?.try_into().ok()),
+        "SMALLINT" => primitive_array::<Int16Type>(values, |value| value.as_i64()?.try_into().ok()),
+        "MEDIUMINT" | "INT" => {
+            primitive_array::<Int32Type>(values, |value| value.as_i64()?.try_into().ok())
+        }
+        "TINYINT UNSIGNED" => {
+            primitive_array::<UInt8Type>(values, |value| value.as_u64()?.try_into().ok())
+        }
+        "SMALLINT UNSIGNED" => {
+            primitive_array::<UInt16Type>(values, |value| value.as_u64()?.try_into().ok())
+        }
+        "MEDIUMINT UNSIGNED" | "INT UNSIGNED" => {
+            primitive_array::<UInt32Type>(values, |value| value.as_u64()?.try_into().ok())
+        }
+        "BIGINT UNSIGNED" => primitive_array::<UInt64Type>(values, Value::as_u64),
+        _ => primitive_array::<Int64Type>(values, Value::as_i64),
+    }
+}
+
+fn boolean_array(values: &[Value]) -> Option<ArrayRef> {
+    let mut builder = BooleanBuilder::with_capacity(values.len());
+    for value in values {
+        match value {
+            Value::Null => builder.append_null(),
+            Value::Bool(value) => builder.append_value(*value),
+            value => builder.append_value(value.as_i64()? != 0),
+        }
+    }
+    Some(Arc::new(builder.finish()))
+}
+
+/// DECIMAL values as `Decimal128`, scaled to the most fractional digits in
+/// the column
+fn decimal_array(values: &[Value]) -> Option<ArrayRef> {
+    let mut parts = Vec::with_capacity(values.len());
+    for value in values {
+        parts.push(match value {
+            Value::Null => None,
+            value => Some(decimal_parts(value.as_str()?)?),
+        });
+    }
+
+    let scale = parts
+        .iter()
+        .flatten()
+        .map(|(_, _, fraction)| fraction.len())
+        .max()
+        .unwrap_or(0);
+    let digits = parts
+        .iter()
+        .flatten()
+        .map(|(_, integer, _)| integer.len())
+        .max()
+        .unwrap_or(0);
+    if digits + scale > MAX_DECIMAL_PRECISION as usize {
+        return None;
+    }
+
+    let mut builder = PrimitiveBuilder::<Decimal128Type>::with_capacity(values.len())
+        .with_precision_and_scale(MAX_DECIMAL_PRECISION, scale as i8)
+        .ok()?;
+    for part in parts {
+        match part {
+            None => builder.append_null(),
+            Some((negative, integer, fraction)) => {
+                let unscaled: i128 = format!("{}{:0<scale$}", integer, fraction).parse().ok()?;
+                builder.append_value(if negative { -unscaled } else { unscaled });
+            }
+        }
+    }
+    Some(Arc::new(builder.finish()))
+}
+
+/// Sign, integer digits and fraction digits of a decimal like `-12.50`
+fn decimal_parts(text: &str) -> Option<(bool, &str, &str)> {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+    if integer.is_empty() && fraction.is_empty() || !digits(integer) || !digits(fraction) {
+        return None;
+    }
+    Some((negative, integer, fraction))
+}
+
+/// Binary values as bytes. Results only carry the bytes of small images, so
+/// other blobs are written as their description instead.
+fn binary_array(values: &[Value]) -> Option<ArrayRef> {
+    let mut builder = BinaryBuilder::with_capacity(values.len(), 0);
+    for value in values {
+        match value {
+            Value::Null => builder.append_null(),
+            value => {
+                let bytes = general_purpose::STANDARD
+                    .decode(value.get("data")?.as_str()?)
+                    .ok()?;
+                builder.append_value(bytes);
+            }
+        }
+    }
+    Some(Arc::new(builder.finish()))
+}
+
+fn text_array(values: &[Value]) -> ArrayRef {
+    let mut builder = StringBuilder::with_capacity(values.len(), 0);
+    for value in values {
+        match value {
+            Value::Null => builder.append_null(),
+            Value::String(text) => builder.append_value(text),
+            value => builder.append_value(value.to_string()),
+        }
+    }
+    Arc::new(builder.finish())
+}
+
+/// Days since 1970-01-01 of a `YYYY-MM-DD` date
+fn parse_date(text: &str) -> Option<i64> {
+    let mut parts = text.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days from civil, counting years from March so leap days come last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// Microseconds of a `HH:MM:SS[.ffffff]` time of day or duration
+fn parse_clock(text: &str) -> Option<i64> {
+    let (clock, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let mut parts = clock.splitn(3, ':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next()?.parse().ok()?;
+    let seconds: i64 = parts.next()?.parse().ok()?;
+    if fraction.len() > 6 || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let micros: i64 = format!("{:0<6}", fraction).parse().ok()?;
+    Some(((hours * 60 + minutes) * 60 + seconds) * MICROS_PER_SECOND + micros)
+}
+
+/// Microseconds since the epoch of a `YYYY-MM-DD HH:MM:SS[.ffffff]` value
+fn parse_datetime(text: &str) -> Option<i64> {
+    let (date, time) = text.split_once(' ').unwrap_or((text, "00:00:00"));
+    Some(parse_date(date)? * 86_400 * MICROS_PER_SECOND + parse_clock(time)?)
+}
+
+/// Microseconds of a `[-]HH:MM:SS[.ffffff]` TIME value
+fn parse_time(text: &str) -> Option<i64> {
+    match text.strip_prefix('-') {
+        Some(time) => Some(-parse_clock(time)?),
+        None => parse_clock(text),
+    }
+}
+
+/// A GitHub-flavored Markdown table, with numbers aligned right
+pub fn render_markdown(result: &CachedResult) -> String {
+    let escape = |text: &str| {
+        text.replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>")
+    };
+    let mut markdown = String::new();
+
+    let header: Vec<String> = result.columns.iter().map(|c| escape(c)).collect();
+    markdown.push_str(&format!("| {} |\n", header.join(" | ")));
+    let separators: Vec<&str> = (0..result.columns.len())
+        .map(|index| {
+            if is_numeric(result, index) {
+                "---:"
+            } else {
+                "---"
+            }
+        })
+        .collect();
+    markdown.push_str(&format!("| {} |\n", separators.join(" | ")));
+
+    for row in 0..result.row_count() {
+        let cells: Vec<String> = (0..result.columns.len())
+            .map(|column| match &result.column_values(column)[row] {
+                Value::Null => "*NULL*".to_string(),
+                value => escape(&cell_text(value)),
+            })
+            .collect();
+        markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    markdown
+}
+
+/// A table with inline styles, so it keeps its look when pasted into an email
+pub fn render_html(result: &CachedResult) -> String {
+    let mut html = String::from(
+        "<table style=\"border-collapse: collapse; font-family: sans-serif; font-size: 13px\">\n",
+    );
+
+    html.push_str("  <thead>\n    <tr>\n");
+    for column in &result.columns {
+        html.push_str(&format!(
+            "      <th style=\"{}\">{}</th>\n",
+            HTML_HEADER_STYLE,
+            escape_html(column)
+        ));
+    }
+    html.push_str("    </tr>\n  </thead>\n  <tbody>\n");
+
+    for row in 0..result.row_count() {
+        html.push_str("    <tr>\n");
+        for column in 0..result.columns.len() {
+            let align = if is_numeric(result, column) {
+                "; text-align: right"
+            } else {
+                ""
+            };
+            let cell = match &result.column_values(column)[row] {
+                Value::Null => format!(
+                    "<td style=\"{}; color: #999; font-style: italic\">NULL</td>",
+                    HTML_CELL_STYLE
+                ),
+                value => format!(
+                    "<td style=\"{}{}\">{}</td>",
+                    HTML_CELL_STYLE,
+                    align,
+                    escape_html(&cell_text(value))
+                ),
+            };
+            html.push_str(&format!("      {}\n", cell));
+        }
+        html.push_str("    </tr>\n");
+    }
+    html.push_str("  </tbody>\n</table>\n");
+    html
+}
+
+fn is_numeric(result: &CachedResult, column: usize) -> bool {
+    result
+        .column_types()
+        .get(column)
+        .is_some_and(|column_type| {
+            matches!(
+                column_type.kind,
+                ValueKind::Integer | ValueKind::Float | ValueKind::Decimal | ValueKind::Bit
+            )
+        })
+}
+
+/// A non-NULL value as the results grid shows it
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Object(object) if object.get("type") == Some(&Value::from("blob")) => {
+            let kind = object
+                .get("content_type")
+                .and_then(Value::as_str)
+                .unwrap_or("BLOB");
+            let size = object.get("size").cloned().unwrap_or(Value::from(0));
+            format!("{} ({} bytes)", kind, size)
+        }
+        value => value.to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::result_cache::ResultCache;
+    use crate::models::QueryResult;
+    use arrow_array::cast::AsArray;
+    use arrow_array::Array;
+    use arrow_schema::DataType;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+
+    fn column(name: &str, type_name: &str, kind: ValueKind) -> ColumnTypeInfo {
+        ColumnTypeInfo {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            kind,
+            truncated: false,
+            masked: false,
+        }
+    }
+
+    fn cached(column_types: Vec<ColumnTypeInfo>, rows: Vec<Vec<Value>>) -> CachedResult {
+        let result = QueryResult {
+            columns: column_types.iter().map(|c| c.name.clone()).collect(),
+            total_rows: rows.len(),
+            rows,
+            execution_time_ms: 1,
+            result_id: None,
+            truncated: false,
+            page_size: None,
+            rows_affected: 0,
+            column_types,
+            cached: false,
+            cached_at: None,
+            auto_limit: None,
+            unbounded: false,
+            has_more: false,
+            dml: false,
+            last_insert_id: None,
+            warnings: vec![],
+            more_results: vec![],
+            spilled: None,
+        };
+        let mut cache = ResultCache::new(1);
+        let id = cache.insert(&result);
+        cache.get(&id).unwrap().clone()
+    }
+
+    #[test]
+    fn test_maps_server_types() {
+        let result = cached(
+            vec![
+                column("id", "BIGINT UNSIGNED", ValueKind::Integer),
+                column("price", "DECIMAL", ValueKind::Decimal),
+                column("born", "DATE", ValueKind::Date),
+                column("seen", "DATETIME", ValueKind::DateTime),
+                column("took", "TIME", ValueKind::Time),
+                column("name", "VARCHAR", ValueKind::Text),
+            ],
+            vec![
+                vec![
+                    json!(1),
+                    json!("12.5"),
+                    json!("1970-01-02"),
+                    json!("1970-01-01 00:00:01.5"),
+                    json!("-25:00:00"),
+                    json!("a"),
+                ],
+                vec![
+                    json!(u64::MAX),
+                    json!("-0.25"),
+                    json!("2000-03-01"),
+                    Value::Null,
+                    json!("00:00:01"),
+                    json!("b"),
+                ],
+            ],
+        );
+        let batch = record_batch(&result).unwrap();
+        let schema = batch.schema();
+
+        assert_eq!(schema.field(0).data_type(), &DataType::UInt64);
+        assert!(!schema.field(0).is_nullable());
+        assert_eq!(schema.field(1).data_type(), &DataType::Decimal128(38, 2));
+        assert_eq!(schema.field(2).data_type(), &DataType::Date32);
+        assert!(schema.field(3).is_nullable());
+        assert_eq!(schema.field(5).data_type(), &DataType::Utf8);
+
+        let prices = batch.column(1).as_primitive::<Decimal128Type>();
+        assert_eq!((prices.value(0), prices.value(1)), (1250, -25));
+        let dates = batch.column(2).as_primitive::<Date32Type>();
+        assert_eq!((dates.value(0), dates.value(1)), (1, 11_017));
+        let seen = batch.column(3).as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(seen.value(0), 1_500_000);
+        let took = batch.column(4).as_primitive::<DurationMicrosecondType>();
+        assert_eq!(took.value(0), -90_000 * MICROS_PER_SECOND);
+    }
+
+    #[test]
+    fn test_unconvertible_values_fall_back_to_text() {
+        let result = cached(
+            vec![
+                column("d", "DATE", ValueKind::Date),
+                column("blob", "BLOB", ValueKind::Binary),
+            ],
+            vec![vec![
+                json!("0000-00-00"),
+                json!({"type": "blob", "size": 40000, "preview_hex": "00"}),
+            ]],
+        );
+        let batch = record_batch(&result).unwrap();
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Utf8);
+        assert_eq!(batch.column(0).as_string::<i32>().value(0), "0000-00-00");
+        assert_eq!(batch.schema().field(1).data_type(), &DataType::Utf8);
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let result = cached(
+            vec![
+                column("id", "INT", ValueKind::Integer),
+                column("note", "TEXT", ValueKind::Text),
+            ],
+            vec![
+                vec![json!(1), json!("a|b\nc")],
+                vec![json!(22), Value::Null],
+            ],
+        );
+        assert_eq!(
+            render_markdown(&result),
+            "| id | note |\n| ---: | --- |\n| 1 | a\\|b<br>c |\n| 22 | *NULL* |\n"
+        );
+    }
+
+    #[test]
+    fn test_render_html_escapes_values() {
+        let result = cached(
+            vec![
+                column("name", "VARCHAR", ValueKind::Text),
+                column("logo", "BLOB", ValueKind::Binary),
+            ],
+            vec![vec![
+                json!("<b>&</b>"),
+                json!({"type": "blob", "size": 12, "preview_hex": "89"}),
+            ]],
+        );
+        let html = render_html(&result);
+        assert!(html.contains(">&lt;b&gt;&amp;&lt;/b&gt;</td>"));
+        assert!(html.contains(">BLOB (12 bytes)</td>"));
+        assert!(html.contains(">name</th>"));
+    }
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let result = cached(
+            vec![
+                column("n", "INT", ValueKind::Integer),
+                column("doc", "JSON", ValueKind::Json),
+            ],
+            vec![
+                vec![json!(7), json!({"a": 1})],
+                vec![Value::Null, json!([1, 2])],
+            ],
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.parquet");
+        assert_eq!(write_parquet(&result, &path, &mut |_, _| true).unwrap(), 2);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        let numbers = batches[0].column(0).as_primitive::<Int32Type>();
+        assert_eq!(numbers.value(0), 7);
+        assert!(numbers.is_null(1));
+        assert_eq!(
+            batches[0].column(1).as_string::<i32>().value(0),
+            r#"{"a":1}"#
+        );
+    }
+
+    #[test]
+    fn test_cancelled_export_removes_file() {
+        let rows = (0..PROGRESS_ROWS as i64 * 2 + 1)
+            .map(|n| vec![json!(n)])
+            .collect();
+        let result = cached(vec![column("n", "BIGINT", ValueKind::Integer)], rows);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.parquet");
+
+        let mut reports = Vec::new();
+        let written = write(&result, ExportFormat::Parquet, &path, &mut |rows, _| {
+            reports.push(rows);
+            true
+        });
+        assert_eq!(written.unwrap(), PROGRESS_ROWS * 2 + 1);
+        assert_eq!(
+            reports,
+            vec![PROGRESS_ROWS, PROGRESS_ROWS * 2, PROGRESS_ROWS * 2 + 1]
+        );
+
+        let cancelled = write(&result, ExportFormat::Markdown, &path, &mut |_, _| false);
+        assert!(matches!(cancelled, Err(ExportError::Cancelled)));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::db::mysql_adapter::{MySQLAdapter, Result};
+
+/// Rows per INSERT statement when filling fixture tables
+const INSERT_BATCH_SIZE: usize = 500;
+
+/// A small shop schema (customers, products, orders) filled with
+/// deterministic rows. Covers the column types the adapter converts, so it
+/// backs both the integration tests and the demo database.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    pub customers: usize,
+    pub products: usize,
+    pub orders_per_customer: usize,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            customers: 50,
+            products: 20,
+            orders_per_customer: 3,
+        }
+    }
+}
+
+impl Fixture {
+    /// Statements that (re)create the fixture tables inside `database`
+    pub fn statements(&self, database: &str) -> Vec<String> {
+        let db = database.replace('`', "``");
+        let mut statements = vec![
+            format!("CREATE DATABASE IF NOT EXISTS `{}`", db),
+            format!("DROP TABLE IF EXISTS `{db}`.`orders`, `{db}`.`products`, `{db}`.`customers`"),
+            format!(
+                "CREATE TABLE `{}`.`customers` (
+                    id INT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL,
+                    email VARCHAR(255),
+                    active BOOLEAN NOT NULL DEFAULT TRUE,
+                    created_at DATETIME NOT NULL
+                )",
+                db
+            ),
+            format!(
+                "CREATE TABLE `{}`.`products` (
+                    id INT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL,
+                    price DOUBLE NOT NULL,
+                    description TEXT,
+                    thumbnail BLOB
+                )",
+                db
+            ),
+            format!(
+                "CREATE TABLE `{db}`.`orders` (
+                    id BIGINT PRIMARY KEY,
+                    customer_id INT NOT NULL,
+                    product_id INT NOT NULL,
+                    quantity SMALLINT NOT NULL,
+                    ordered_on DATE NOT NULL,
+                    FOREIGN KEY (customer_id) REFERENCES `{db}`.`customers` (id),
+                    FOREIGN KEY (product_id) REFERENCES `{db}`.`products` (id)
+                )"
+            ),
+        ];
+
+        let customers: Vec<String> = (1..=self.customers)
+            .map(|id| {
+                // Every fifth customer has no email, to exercise NULLs
+                let email = if id % 5 == 0 {
+                    "NULL".to_string()
+                } else {
+                    format!("'customer{}@example.com'", id)
+                };
+                format!(
+                    "({id}, 'Customer {id}', {email}, {active}, \
+                     TIMESTAMP('2024-01-01 09:00:00') + INTERVAL {id} DAY)",
+                    active = id % 7 != 0
+                )
+            })
+            .collect();
+        push_inserts(
+            &mut statements,
+            &db,
+            "customers (id, name, email, active, created_at)",
+            customers,
+        );
+
+        let products: Vec<String> = (1..=self.products)
+            .map(|id| {
+                format!(
+                    "({id}, 'Product {id}', {price:.2}, 'Description of product {id}', X'{thumb:02X}')",
+                    price = id as f64 * 2.5,
+                    thumb = id % 256
+                )
+            })
+            .collect();
+        push_inserts(
+            &mut statements,
+            &db,
+            "products (id, name, price, description, thumbnail)",
+            products,
+        );
+
+        if self.products > 0 {
+            let orders: Vec<String> = (0..self.customers * self.orders_per_customer)
+                .map(|i| {
+                    format!(
+                        "({id}, {customer}, {product}, {quantity}, \
+                         DATE('2024-02-01') + INTERVAL {day} DAY)",
+                        id = i + 1,
+                        customer = i / self.orders_per_customer + 1,
+                        product = i % self.products + 1,
+                        quantity = i % 4 + 1,
+                        day = i % 365
+                    )
+                })
+                .collect();
+            push_inserts(
+                &mut statements,
+                &db,
+                "orders (id, customer_id, product_id, quantity, ordered_on)",
+                orders,
+            );
+        }
+
+        statements
+    }
+}
+
+fn push_inserts(statements: &mut Vec<String>, db: &str, target: &str, rows: Vec<String>) {
+    for batch in rows.chunks(INSERT_BATCH_SIZE) {
+        statements.push(format!(
+            "INSERT INTO `{}`.{} VALUES {}",
+            db,
+            target,
+            batch.join(", ")
+        ));
+    }
+}
+
+/// Create the fixture tables in `database`, replacing any earlier copy
+pub async fn load_fixture(adapter: &MySQLAdapter, database: &str, fixture: &Fixture) -> Result<()> {
+    adapter
+        .execute_statements(&fixture.statements(database))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statements_batch_inserts() {
+        let fixture = Fixture {
+            customers: 1200,
+            products: 2,
+            orders_per_customer: 0,
+        };
+        let statements = fixture.statements("demo");
+
+        let customer_inserts: Vec<&String> = statements
+            .iter()
+            .filter(|s| s.starts_with("INSERT INTO `demo`.customers"))
+            .collect();
+        assert_eq!(customer_inserts.len(), 3);
+        assert!(statements.iter().all(|s| !s.contains("`demo`.orders (")));
+    }
+
+    #[test]
+    fn test_statements_escape_database_name() {
+        let statements = Fixture::default().statements("we`ird");
+        assert_eq!(statements[0], "CREATE DATABASE IF NOT EXISTS `we``ird`");
+    }
+}
@@ -0,0 +1,485 @@
+//! GRANT/REVOKE and user management statement builders for each dialect.
+
+use crate::db::sql::{quote_identifier, quote_literal};
+use crate::db::sql::leading_keywords;
+use crate::models::{CreateUserRequest, DatabaseType, MissingPrivilege, PrivilegeRequest};
+
+const MYSQL_PRIVILEGES: &[&str] = &[
+    "ALL PRIVILEGES",
+    "ALTER",
+    "CREATE",
+    "CREATE VIEW",
+    "DELETE",
+    "DROP",
+    "EVENT",
+    "EXECUTE",
+    "INDEX",
+    "INSERT",
+    "LOCK TABLES",
+    "REFERENCES",
+    "SELECT",
+    "SHOW VIEW",
+    "TRIGGER",
+    "UPDATE",
+];
+
+const POSTGRES_TABLE_PRIVILEGES: &[&str] = &[
+    "ALL PRIVILEGES",
+    "DELETE",
+    "INSERT",
+    "REFERENCES",
+    "SELECT",
+    "TRIGGER",
+    "TRUNCATE",
+    "UPDATE",
+];
+
+const POSTGRES_DATABASE_PRIVILEGES: &[&str] = &["CONNECT", "CREATE", "TEMPORARY"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GrantAction {
+    Grant,
+    Revoke,
+}
+
+fn mysql_account(username: &str, host: Option<&str>) -> String {
+    format!(
+        "{}@{}",
+        quote_literal(username),
+        quote_literal(host.unwrap_or("%"))
+    )
+}
+
+fn normalize_privileges(privileges: &[String]) -> Result<Vec<String>, String> {
+    let normalized: Vec<String> = privileges
+        .iter()
+        .map(|p| {
+            p.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_uppercase()
+        })
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    if normalized.is_empty() {
+        return Err("At least one privilege is required".to_string());
+    }
+    Ok(normalized)
+}
+
+pub fn create_user_statements(
+    db_type: &DatabaseType,
+    request: &CreateUserRequest,
+) -> Result<Vec<String>, String> {
+    if request.username.trim().is_empty() {
+        return Err("Username is required".to_string());
+    }
+
+    Ok(match db_type {
+        DatabaseType::MySQL => vec![format!(
+            "CREATE USER {} IDENTIFIED BY {}",
+            mysql_account(&request.username, request.host.as_deref()),
+            quote_literal(&request.password)
+        )],
+        DatabaseType::PostgreSQL => vec![format!(
+            "CREATE ROLE {} WITH LOGIN PASSWORD {}",
+            quote_identifier(db_type, &request.username),
+            quote_literal(&request.password)
+        )],
+    })
+}
+
+pub fn privilege_statements(
+    db_type: &DatabaseType,
+    action: GrantAction,
+    request: &PrivilegeRequest,
+) -> Result<Vec<String>, String> {
+    let privileges = normalize_privileges(&request.privileges)?;
+    let (verb, preposition) = match action {
+        GrantAction::Grant => ("GRANT", "TO"),
+        GrantAction::Revoke => ("REVOKE", "FROM"),
+    };
+
+    match db_type {
+        DatabaseType::MySQL => {
+            if let Some(unknown) = privileges
+                .iter()
+                .find(|p| !MYSQL_PRIVILEGES.contains(&p.as_str()))
+            {
+                return Err(format!("Unsupported MySQL privilege: {}", unknown));
+            }
+
+            let object = format!(
+                "{}.{}",
+                quote_identifier(db_type, &request.database),
+                request
+                    .table
+                    .as_deref()
+                    .map(|t| quote_identifier(db_type, t))
+                    .unwrap_or_else(|| "*".to_string())
+            );
+
+            Ok(vec![format!(
+                "{} {} ON {} {} {}",
+                verb,
+                privileges.join(", "),
+                object,
+                preposition,
+                mysql_account(&request.username, request.host.as_deref())
+            )])
+        }
+        DatabaseType::PostgreSQL => {
+            let role = quote_identifier(db_type, &request.username);
+            let (database_level, table_level): (Vec<String>, Vec<String>) = privileges
+                .into_iter()
+                .partition(|p| POSTGRES_DATABASE_PRIVILEGES.contains(&p.as_str()));
+
+            if let Some(unknown) = table_level
+                .iter()
+                .find(|p| !POSTGRES_TABLE_PRIVILEGES.contains(&p.as_str()))
+            {
+                return Err(format!("Unsupported PostgreSQL privilege: {}", unknown));
+            }
+
+            let mut statements = Vec::new();
+            if !database_level.is_empty() {
+                statements.push(format!(
+                    "{} {} ON DATABASE {} {} {}",
+                    verb,
+                    database_level.join(", "),
+                    quote_identifier(db_type, &request.database),
+                    preposition,
+                    role
+                ));
+            }
+            if !table_level.is_empty() {
+                let object = match &request.table {
+                    Some(table) => format!("TABLE {}", quote_identifier(db_type, table)),
+                    None => "ALL TABLES IN SCHEMA public".to_string(),
+                };
+                statements.push(format!(
+                    "{} {} ON {} {} {}",
+                    verb,
+                    table_level.join(", "),
+                    object,
+                    preposition,
+                    role
+                ));
+            }
+            Ok(statements)
+        }
+    }
+}
+
+/// Query listing the effective grants of a user
+pub fn show_grants_query(db_type: &DatabaseType, username: &str, host: Option<&str>) -> String {
+    match db_type {
+        DatabaseType::MySQL => format!("SHOW GRANTS FOR {}", mysql_account(username, host)),
+        DatabaseType::PostgreSQL => format!(
+            "SELECT privilege_type || ' ON ' || table_schema || '.' || table_name \
+             FROM information_schema.role_table_grants WHERE grantee = {} \
+             ORDER BY table_schema, table_name, privilege_type",
+            quote_literal(username)
+        ),
+    }
+}
+
+// MySQL server error numbers for privilege failures
+const ER_DBACCESS_DENIED_ERROR: u16 = 1044;
+const ER_TABLEACCESS_DENIED_ERROR: u16 = 1142;
+const ER_COLUMNACCESS_DENIED_ERROR: u16 = 1143;
+const ER_SPECIFIC_ACCESS_DENIED_ERROR: u16 = 1227;
+const ER_PROCACCESS_DENIED_ERROR: u16 = 1370;
+
+/// Strip the quotes MySQL puts around names in error messages
+fn unquote(name: &str) -> String {
+    name.trim()
+        .trim_matches(|c| c == '\'' || c == '`')
+        .to_string()
+}
+
+/// Split `'db.table'` or `` `db`.`table` `` into its parts
+fn split_qualified(name: &str) -> (Option<String>, String) {
+    let cleaned: String = name.chars().filter(|c| *c != '\'' && *c != '`').collect();
+    match cleaned.split_once('.') {
+        Some((db, object)) => (Some(db.trim().to_string()), object.trim().to_string()),
+        None => (None, cleaned.trim().to_string()),
+    }
+}
+
+/// Parse the `'user'@'host'` account out of "... to user 'bob'@'%' ..."
+fn parse_account(message: &str) -> (Option<String>, Option<String>) {
+    let Some(rest) = message.split_once(" user ").map(|(_, r)| r) else {
+        return (None, None);
+    };
+    let account = rest.split_whitespace().next().unwrap_or("");
+    match account.split_once('@') {
+        Some((user, host)) => (Some(unquote(user)), Some(unquote(host))),
+        None => (Some(unquote(account)), None),
+    }
+}
+
+/// Privilege needed to run `sql`, used when the server only says "access denied"
+fn privilege_for_statement(sql: &str) -> Option<&'static str> {
+    let keyword = leading_keywords(sql, 1).pop()?;
+    Some(match keyword.as_str() {
+        "SELECT" | "SHOW" | "DESCRIBE" | "DESC" | "EXPLAIN" | "WITH" | "USE" => "SELECT",
+        "INSERT" | "REPLACE" => "INSERT",
+        "UPDATE" => "UPDATE",
+        "DELETE" => "DELETE",
+        "CREATE" => "CREATE",
+        "ALTER" => "ALTER",
+        "DROP" | "TRUNCATE" => "DROP",
+        "CALL" => "EXECUTE",
+        _ => return None,
+    })
+}
+
+/// Work out which privilege a MySQL error says is missing. `sql` is the
+/// statement that failed, used when the message doesn't name the privilege.
+pub fn explain_denied(error_number: u16, message: &str, sql: &str) -> Option<MissingPrivilege> {
+    let (username, host) = parse_account(message);
+    let mut missing = MissingPrivilege {
+        privilege: String::new(),
+        database: None,
+        table: None,
+        column: None,
+        routine: None,
+        username,
+        host,
+        grant_statement: None,
+    };
+
+    match error_number {
+        // "SELECT command denied to user 'bob'@'%' for table 'orders'"
+        ER_TABLEACCESS_DENIED_ERROR => {
+            missing.privilege = message.split(" command denied").next()?.trim().to_uppercase();
+            let (database, table) = split_qualified(message.split_once(" for table ")?.1);
+            missing.database = database;
+            missing.table = Some(table);
+        }
+        // "SELECT command denied to user 'bob'@'%' for column 'email' in table 'users'"
+        ER_COLUMNACCESS_DENIED_ERROR => {
+            missing.privilege = message.split(" command denied").next()?.trim().to_uppercase();
+            let (column, table) = message.split_once(" for column ")?.1.split_once(" in table ")?;
+            let (database, table) = split_qualified(table);
+            missing.column = Some(unquote(column));
+            missing.database = database;
+            missing.table = Some(table);
+        }
+        // "execute command denied to user 'bob'@'%' for routine 'shop.refresh'"
+        ER_PROCACCESS_DENIED_ERROR => {
+            missing.privilege = message.split(" command denied").next()?.trim().to_uppercase();
+            let (database, routine) = split_qualified(message.split_once(" for routine ")?.1);
+            missing.database = database;
+            missing.routine = Some(routine);
+        }
+        // "Access denied for user 'bob'@'%' to database 'shop'"
+        ER_DBACCESS_DENIED_ERROR => {
+            missing.privilege = privilege_for_statement(sql)?.to_string();
+            missing.database = Some(unquote(message.split_once(" to database ")?.1));
+        }
+        // "Access denied; you need (at least one of) the PROCESS privilege(s) for this operation"
+        ER_SPECIFIC_ACCESS_DENIED_ERROR => {
+            let privileges = message.split_once(" the ")?.1.split(" privilege").next()?;
+            missing.privilege = privileges.split(" or ").next()?.trim().to_uppercase();
+        }
+        _ => return None,
+    }
+
+    missing.grant_statement = grant_statement(&missing);
+    Some(missing)
+}
+
+/// Fill in what the error message left out (the current database, or the
+/// account for global privilege errors) and rebuild the GRANT statement
+pub fn complete_missing_privilege(
+    missing: &mut MissingPrivilege,
+    database: Option<&str>,
+    username: &str,
+) {
+    let needs_database = missing.table.is_some() || missing.routine.is_some();
+    if needs_database && missing.database.is_none() {
+        missing.database = database.map(String::from);
+    }
+    if missing.username.is_none() {
+        missing.username = Some(username.to_string());
+    }
+    missing.grant_statement = grant_statement(missing);
+}
+
+fn grant_statement(missing: &MissingPrivilege) -> Option<String> {
+    let db_type = DatabaseType::MySQL;
+    let username = missing.username.as_deref()?;
+    let quote = |name: &str| quote_identifier(&db_type, name);
+
+    let object = match (&missing.database, &missing.table, &missing.routine) {
+        (Some(db), Some(table), _) => format!("{}.{}", quote(db), quote(table)),
+        (Some(db), None, Some(routine)) => format!("PROCEDURE {}.{}", quote(db), quote(routine)),
+        (Some(db), None, None) => format!("{}.*", quote(db)),
+        (None, None, None) => "*.*".to_string(),
+        // A table or routine without a known database can't be granted precisely
+        (None, _, _) => return None,
+    };
+    let privilege = match &missing.column {
+        Some(column) => format!("{} ({})", missing.privilege, quote(column)),
+        None => missing.privilege.clone(),
+    };
+
+    Some(format!(
+        "GRANT {} ON {} TO {}",
+        privilege,
+        object,
+        mysql_account(username, missing.host.as_deref())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn privilege_request(privileges: &[&str], table: Option<&str>) -> PrivilegeRequest {
+        PrivilegeRequest {
+            connection_id: "conn".to_string(),
+            username: "report".to_string(),
+            host: None,
+            privileges: privileges.iter().map(|p| p.to_string()).collect(),
+            database: "app".to_string(),
+            table: table.map(String::from),
+            preview: true,
+        }
+    }
+
+    #[test]
+    fn test_mysql_grant_and_revoke() {
+        let request = privilege_request(&["select", "insert"], Some("orders"));
+
+        let grant =
+            privilege_statements(&DatabaseType::MySQL, GrantAction::Grant, &request).unwrap();
+        assert_eq!(
+            grant,
+            vec!["GRANT SELECT, INSERT ON `app`.`orders` TO 'report'@'%'"]
+        );
+
+        let revoke =
+            privilege_statements(&DatabaseType::MySQL, GrantAction::Revoke, &request).unwrap();
+        assert_eq!(
+            revoke,
+            vec!["REVOKE SELECT, INSERT ON `app`.`orders` FROM 'report'@'%'"]
+        );
+    }
+
+    #[test]
+    fn test_postgres_splits_database_and_table_privileges() {
+        let request = privilege_request(&["CONNECT", "SELECT"], None);
+        let statements =
+            privilege_statements(&DatabaseType::PostgreSQL, GrantAction::Grant, &request).unwrap();
+
+        assert_eq!(
+            statements,
+            vec![
+                "GRANT CONNECT ON DATABASE \"app\" TO \"report\"",
+                "GRANT SELECT ON ALL TABLES IN SCHEMA public TO \"report\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_privileges() {
+        let request = privilege_request(&["SELECT; DROP TABLE users"], None);
+        assert!(privilege_statements(&DatabaseType::MySQL, GrantAction::Grant, &request).is_err());
+        assert!(privilege_statements(
+            &DatabaseType::MySQL,
+            GrantAction::Grant,
+            &privilege_request(&[], None)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_create_user_statements() {
+        let request = CreateUserRequest {
+            connection_id: "conn".to_string(),
+            username: "report".to_string(),
+            password: "it's".to_string(),
+            host: Some("10.0.0.%".to_string()),
+            preview: false,
+        };
+        assert_eq!(
+            create_user_statements(&DatabaseType::MySQL, &request).unwrap(),
+            vec!["CREATE USER 'report'@'10.0.0.%' IDENTIFIED BY 'it''s'"]
+        );
+    }
+
+    #[test]
+    fn test_explain_table_access_denied() {
+        let missing = explain_denied(
+            1142,
+            "SELECT command denied to user 'report'@'10.0.0.%' for table 'orders'",
+            "SELECT * FROM orders",
+        )
+        .unwrap();
+        assert_eq!(missing.privilege, "SELECT");
+        assert_eq!(missing.table.as_deref(), Some("orders"));
+        // The database isn't in the message, so no statement yet
+        assert_eq!(missing.grant_statement, None);
+
+        let mut missing = missing;
+        complete_missing_privilege(&mut missing, Some("shop"), "ignored");
+        assert_eq!(
+            missing.grant_statement.as_deref(),
+            Some("GRANT SELECT ON `shop`.`orders` TO 'report'@'10.0.0.%'")
+        );
+    }
+
+    #[test]
+    fn test_explain_column_and_qualified_table() {
+        let missing = explain_denied(
+            1143,
+            "UPDATE command denied to user 'app'@'%' for column 'email' in table 'shop.users'",
+            "UPDATE users SET email = ''",
+        )
+        .unwrap();
+        assert_eq!(
+            missing.grant_statement.as_deref(),
+            Some("GRANT UPDATE (`email`) ON `shop`.`users` TO 'app'@'%'")
+        );
+    }
+
+    #[test]
+    fn test_explain_database_access_uses_statement() {
+        let missing = explain_denied(
+            1044,
+            "Access denied for user 'app'@'%' to database 'billing'",
+            "insert into invoices values (1)",
+        )
+        .unwrap();
+        assert_eq!(
+            missing.grant_statement.as_deref(),
+            Some("GRANT INSERT ON `billing`.* TO 'app'@'%'")
+        );
+    }
+
+    #[test]
+    fn test_explain_global_privilege() {
+        let mut missing = explain_denied(
+            1227,
+            "Access denied; you need (at least one of) the PROCESS privilege(s) for this operation",
+            "SHOW ENGINE INNODB STATUS",
+        )
+        .unwrap();
+        assert_eq!(missing.privilege, "PROCESS");
+
+        complete_missing_privilege(&mut missing, Some("shop"), "app");
+        assert_eq!(
+            missing.grant_statement.as_deref(),
+            Some("GRANT PROCESS ON *.* TO 'app'@'%'")
+        );
+    }
+
+    #[test]
+    fn test_explain_ignores_other_errors() {
+        assert!(explain_denied(1064, "You have an error in your SQL syntax", "SELEC 1").is_none());
+    }
+}
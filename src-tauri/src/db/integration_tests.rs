@@ -0,0 +1,767 @@
+//! Runs the adapter against a real MySQL server in a container.
+//!
+//! Requires Docker; enable with `cargo test --features integration-tests`.
+
+use crate::db::fixtures::{load_fixture, Fixture};
+use crate::db::mysql_adapter::DatabaseError;
+use crate::db::table_copy::CopyPlan;
+use crate::db::MySQLAdapter;
+use crate::models::connection::ConnectionOptions;
+use crate::models::{
+    CellFileRequest, CellValue, Connection, DatabaseType, DeleteRowRequest, Environment,
+    InsertRowRequest, ParameterMode, RowCountMode, SeedTableRequest, TableDataRequest,
+    UpdateRowRequest, ValueKind,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use testcontainers::runners::AsyncRunner;
+use testcontainers::ContainerAsync;
+use testcontainers_modules::mysql::Mysql;
+
+const DATABASE: &str = "shop";
+
+/// A running server with the default fixture loaded. The container stops
+/// when this is dropped.
+struct MySqlHarness {
+    _container: ContainerAsync<Mysql>,
+    connection: Connection,
+    adapter: MySQLAdapter,
+}
+
+async fn start_mysql() -> MySqlHarness {
+    let container = Mysql::default()
+        .start()
+        .await
+        .expect("Failed to start MySQL container");
+    let host = container.get_host().await.unwrap().to_string();
+    let port = container.get_host_port_ipv4(3306).await.unwrap();
+
+    let connection = Connection {
+        id: "integration".to_string(),
+        name: "Integration".to_string(),
+        color: "#ef4444".to_string(),
+        db_type: DatabaseType::MySQL,
+        host,
+        port,
+        username: "root".to_string(),
+        password: String::new(),
+        database: Some("test".to_string()),
+        ssh_config: None,
+        ssl_config: None,
+        group_id: None,
+        read_only: false,
+        environment: Environment::Dev,
+        options: ConnectionOptions::default(),
+        last_used_at: None,
+        use_count: 0,
+    };
+
+    let adapter = MySQLAdapter::new(&connection).await.unwrap();
+    load_fixture(&adapter, DATABASE, &Fixture::default())
+        .await
+        .unwrap();
+
+    MySqlHarness {
+        _container: container,
+        connection,
+        adapter,
+    }
+}
+
+fn table_request(table: &str, page: u32, page_size: u32) -> TableDataRequest {
+    TableDataRequest {
+        connection_id: "integration".to_string(),
+        database: DATABASE.to_string(),
+        schema: None,
+        table: table.to_string(),
+        page,
+        page_size,
+        filters: None,
+        sort_by: Some("id".to_string()),
+        sort_order: None,
+        row_count: RowCountMode::Exact,
+    }
+}
+
+#[tokio::test]
+async fn test_schema_introspection() {
+    let harness = start_mysql().await;
+
+    let databases = harness.adapter.list_databases().await.unwrap();
+    assert!(databases.contains(&DATABASE.to_string()));
+
+    let schema = harness.adapter.get_schema(DATABASE).await.unwrap();
+    let mut tables: Vec<&str> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+    tables.sort_unstable();
+    assert_eq!(tables, vec!["customers", "orders", "products"]);
+
+    let orders = schema.tables.iter().find(|t| t.name == "orders").unwrap();
+    assert_eq!(orders.primary_keys, vec!["id".to_string()]);
+    assert_eq!(orders.foreign_keys.len(), 2);
+    let email = schema
+        .tables
+        .iter()
+        .find(|t| t.name == "customers")
+        .and_then(|t| t.columns.iter().find(|c| c.name == "email"))
+        .unwrap();
+    assert!(email.is_nullable);
+}
+
+#[tokio::test]
+async fn test_row_crud() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+
+    let mut data = HashMap::new();
+    data.insert("id".to_string(), CellValue::Literal(json!(1000)));
+    data.insert("name".to_string(), CellValue::Literal(json!("New product")));
+    data.insert("price".to_string(), CellValue::Literal(json!(9.5)));
+    data.insert("description".to_string(), CellValue::Default);
+    adapter
+        .insert_row(&InsertRowRequest {
+            connection_id: "integration".to_string(),
+            database: DATABASE.to_string(),
+            table: "products".to_string(),
+            data,
+        })
+        .await
+        .unwrap();
+
+    let mut key = HashMap::new();
+    key.insert("id".to_string(), json!(1000));
+    let mut changes = HashMap::new();
+    changes.insert("price".to_string(), CellValue::Expression("price + 2.5".to_string()));
+    let updated = adapter
+        .update_row(&UpdateRowRequest {
+            connection_id: "integration".to_string(),
+            database: DATABASE.to_string(),
+            table: "products".to_string(),
+            data: changes,
+            where_clause: key.clone(),
+            expected_values: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(updated, 1);
+
+    let result = adapter
+        .execute_query_with_database(
+            "SELECT price FROM products WHERE id = 1000",
+            Some(DATABASE),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.rows, vec![vec![json!(12.0)]]);
+
+    let row = adapter
+        .get_row(DATABASE, "products", &key)
+        .await
+        .unwrap()
+        .unwrap();
+    let name = row.iter().find(|field| field.name == "name").unwrap();
+    assert_eq!(name.value, json!("New product"));
+    assert_eq!(name.size, Some(11));
+
+    // A delete expecting the price from before the update is a conflict
+    let delete = |price: f64| DeleteRowRequest {
+        connection_id: "integration".to_string(),
+        database: DATABASE.to_string(),
+        table: "products".to_string(),
+        where_clause: key.clone(),
+        expected_values: Some(HashMap::from([("price".to_string(), json!(price))])),
+    };
+    let stale = adapter.delete_rows(&delete(9.5)).await;
+    assert!(matches!(stale, Err(DatabaseError::Conflict(_))));
+
+    let deleted = adapter.delete_rows(&delete(12.0)).await.unwrap();
+    assert_eq!(deleted, 1);
+    assert!(adapter
+        .get_row(DATABASE, "products", &key)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_approximate_values_are_left_out_of_the_check() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    adapter
+        .execute_statements(&[
+            format!("CREATE TABLE `{}`.rates (id INT PRIMARY KEY, ratio FLOAT, note TEXT)", DATABASE),
+            format!("INSERT INTO `{}`.rates VALUES (1, 0.1, 'a long note')", DATABASE),
+        ])
+        .await
+        .unwrap();
+
+    // As listed: the FLOAT read back as decimal text, the note cut short
+    let mut expected = HashMap::from([
+        ("ratio".to_string(), json!(0.1)),
+        ("note".to_string(), json!("a lo")),
+    ]);
+    adapter
+        .retain_comparable(DATABASE, "rates", &mut expected, Some(4))
+        .await
+        .unwrap();
+    assert!(expected.is_empty());
+
+    let deleted = adapter
+        .delete_rows(&DeleteRowRequest {
+            connection_id: harness.connection.id.clone(),
+            database: DATABASE.to_string(),
+            table: "rates".to_string(),
+            where_clause: HashMap::from([("id".to_string(), json!(1))]),
+            expected_values: Some(expected),
+        })
+        .await
+        .unwrap();
+    assert_eq!(deleted, 1);
+}
+
+#[tokio::test]
+async fn test_database_stays_with_its_statement() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    adapter
+        .execute_statements(&[
+            "CREATE DATABASE archive".to_string(),
+            "CREATE TABLE archive.notes (id INT PRIMARY KEY, body TEXT)".to_string(),
+            "CREATE TABLE shop.notes (id INT PRIMARY KEY, body TEXT)".to_string(),
+            "INSERT INTO archive.notes VALUES (1, 'archive')".to_string(),
+            "INSERT INTO shop.notes VALUES (1, 'shop')".to_string(),
+        ])
+        .await
+        .unwrap();
+
+    // Two connections in the pool, one of them left on the other database
+    let mut first = adapter.acquire().await.unwrap();
+    let second = adapter.acquire().await.unwrap();
+    sqlx::Executor::execute(&mut *first, "USE archive").await.unwrap();
+    drop(first);
+    drop(second);
+
+    let body = |database: &'static str| async move {
+        adapter
+            .execute_query_with_database("SELECT body FROM notes", Some(database), &[])
+            .await
+            .unwrap()
+            .rows
+    };
+    for _ in 0..4 {
+        assert_eq!(body(DATABASE).await, vec![vec![json!("shop")]]);
+        assert_eq!(body("archive").await, vec![vec![json!("archive")]]);
+    }
+
+    for _ in 0..2 {
+        let mut changes = HashMap::new();
+        changes.insert("body".to_string(), CellValue::Literal(json!("edited")));
+        let updated = adapter
+            .update_row(&UpdateRowRequest {
+                connection_id: "integration".to_string(),
+                database: DATABASE.to_string(),
+                table: "notes".to_string(),
+                data: changes,
+                where_clause: HashMap::from([("id".to_string(), json!(1))]),
+                expected_values: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated, 1);
+    }
+    assert_eq!(body(DATABASE).await, vec![vec![json!("edited")]]);
+    assert_eq!(body("archive").await, vec![vec![json!("archive")]]);
+
+    // Connections go back to the pool on the connection's own database
+    let mut held = Vec::new();
+    for _ in 0..2 {
+        held.push(adapter.acquire().await.unwrap());
+    }
+    for conn in &mut held {
+        let (current,): (String,) = sqlx::query_as("SELECT DATABASE()")
+            .fetch_one(&mut **conn)
+            .await
+            .unwrap();
+        assert_eq!(current, "test");
+    }
+}
+
+#[tokio::test]
+async fn test_pagination() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+
+    let page = adapter
+        .get_table_data(&table_request("orders", 1, 40))
+        .await
+        .unwrap();
+    let fixture = Fixture::default();
+    assert_eq!(
+        page.total_rows as usize,
+        fixture.customers * fixture.orders_per_customer
+    );
+    assert_eq!(page.rows.len(), 40);
+    assert_eq!(page.rows[0]["id"], json!(41));
+
+    let result = adapter
+        .execute_paginated("SELECT id FROM customers ORDER BY id", Some(DATABASE), 2, 10, &[])
+        .await
+        .unwrap();
+    assert_eq!(result.rows.first(), Some(&vec![json!(21)]));
+    assert_eq!(result.rows.len(), 10);
+    assert!(result.has_more);
+
+    // A query with its own LIMIT keeps it, and the last page has no more rows
+    let result = adapter
+        .execute_paginated(
+            "SELECT id FROM customers ORDER BY id LIMIT 25;",
+            Some(DATABASE),
+            2,
+            10,
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.rows.len(), 5);
+    assert!(!result.has_more);
+}
+
+#[tokio::test]
+async fn test_type_extraction() {
+    let harness = start_mysql().await;
+
+    let result = harness
+        .adapter
+        .execute_query_with_database(
+            "SELECT c.id, c.name, c.email, p.price, p.thumbnail, o.quantity, o.id AS order_id
+             FROM customers c
+             JOIN orders o ON o.customer_id = c.id
+             JOIN products p ON p.id = o.product_id
+             WHERE c.id = 5
+             ORDER BY o.id
+             LIMIT 1",
+            Some(DATABASE),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let row = &result.rows[0];
+    assert_eq!(row[0], json!(5));
+    assert_eq!(row[1], json!("Customer 5"));
+    // Every fifth customer has no email
+    assert_eq!(row[2], json!(null));
+    assert!(row[3].is_f64());
+    assert_eq!(row[4]["type"], json!("blob"));
+    assert!(row[5].is_i64());
+    assert!(row[6].is_i64());
+}
+
+#[tokio::test]
+async fn test_value_type_matrix() {
+    let harness = start_mysql().await;
+
+    let result = harness
+        .adapter
+        .execute_query_with_database(
+            "SELECT CAST('12345678901234567890.12' AS DECIMAL(30,2)) AS amount,
+                    CAST('2024-02-29 13:05:09.25' AS DATETIME(2)) AS happened_at,
+                    DATE '2024-02-29' AS day,
+                    TIME '-51:04:05' AS elapsed,
+                    CAST(18446744073709551615 AS UNSIGNED) AS big,
+                    b'101' AS flags",
+            Some(DATABASE),
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let row = &result.rows[0];
+    assert_eq!(row[0], json!("12345678901234567890.12"));
+    assert_eq!(row[1], json!("2024-02-29 13:05:09.250000"));
+    assert_eq!(row[2], json!("2024-02-29"));
+    assert_eq!(row[3], json!("-51:04:05"));
+    assert_eq!(row[4], json!(u64::MAX));
+    assert_eq!(row[5], json!(5));
+
+    let kinds: Vec<ValueKind> = result.column_types.iter().map(|c| c.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ValueKind::Decimal,
+            ValueKind::DateTime,
+            ValueKind::Date,
+            ValueKind::Time,
+            ValueKind::Integer,
+            ValueKind::Bit,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_dml_metadata() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    adapter
+        .execute_statements(&[format!(
+            "CREATE TABLE `{}`.notes (id INT AUTO_INCREMENT PRIMARY KEY, body VARCHAR(20))",
+            DATABASE
+        )])
+        .await
+        .unwrap();
+
+    let result = adapter
+        .execute_query_with_database(
+            "INSERT INTO notes (body) VALUES ('a'), ('b')",
+            Some(DATABASE),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(result.dml);
+    assert_eq!(result.rows_affected, 2);
+    // MySQL reports the id of the first row of a multi-row insert
+    assert_eq!(result.last_insert_id, Some(1));
+    assert!(result.warnings.is_empty());
+
+    let result = adapter
+        .execute_query_with_database(
+            "INSERT IGNORE INTO notes (id, body) VALUES (1, 'c')",
+            Some(DATABASE),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected, 0);
+    assert_eq!(result.warnings.len(), 1);
+    assert_eq!(result.warnings[0].code, 1062);
+    assert_eq!(result.warnings[0].level, "Warning");
+}
+
+#[tokio::test]
+async fn test_multiple_result_sets() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+
+    let result = adapter
+        .execute_query_with_database("SELECT 1 AS a; SELECT 2 AS b, 3 AS c", Some(DATABASE), &[])
+        .await
+        .unwrap();
+    assert_eq!(result.columns, vec!["a"]);
+    assert_eq!(result.more_results.len(), 1);
+    assert_eq!(result.more_results[0].columns, vec!["b", "c"]);
+    assert_eq!(result.more_results[0].rows, vec![vec![json!(2), json!(3)]]);
+
+    adapter
+        .execute_statements(&[format!(
+            "CREATE PROCEDURE `{}`.two_sets() BEGIN SELECT 1 AS x; SELECT 'y' AS y; END",
+            DATABASE
+        )])
+        .await
+        .unwrap();
+    let result = adapter
+        .execute_query_with_database("CALL two_sets()", Some(DATABASE), &[])
+        .await
+        .unwrap();
+    // The status that ends the call isn't a result set of its own
+    assert_eq!(result.columns, vec!["x"]);
+    assert_eq!(result.more_results.len(), 1);
+    assert_eq!(result.more_results[0].rows, vec![vec![json!("y")]]);
+
+    // A statement after a query keeps its status
+    let result = adapter
+        .execute_query_with_database("SELECT 1 AS a; SET @x = 1", Some(DATABASE), &[])
+        .await
+        .unwrap();
+    assert_eq!(result.columns, vec!["a"]);
+    assert_eq!(result.more_results.len(), 1);
+    assert!(result.more_results[0].columns.is_empty());
+}
+
+#[tokio::test]
+async fn test_call_procedure_with_out_parameters() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    adapter
+        .execute_statements(&[format!(
+            "CREATE PROCEDURE `{}`.scale(IN factor INT, INOUT amount DECIMAL(10,2), \
+             OUT label VARCHAR(20)) \
+             BEGIN SET amount = amount * factor; SET label = 'scaled'; SELECT factor AS f; END",
+            DATABASE
+        )])
+        .await
+        .unwrap();
+
+    assert!(adapter
+        .procedure_parameters(DATABASE, "missing")
+        .await
+        .unwrap()
+        .is_none());
+    let parameters = adapter
+        .procedure_parameters(DATABASE, "scale")
+        .await
+        .unwrap()
+        .unwrap();
+    let modes: Vec<ParameterMode> = parameters.iter().map(|p| p.mode).collect();
+    assert_eq!(
+        modes,
+        vec![ParameterMode::In, ParameterMode::InOut, ParameterMode::Out]
+    );
+
+    let values = HashMap::from([
+        ("factor".to_string(), json!(3)),
+        ("amount".to_string(), json!("2.50")),
+    ]);
+    let called = adapter
+        .call_procedure(DATABASE, "scale", &parameters, &values)
+        .await
+        .unwrap();
+    assert_eq!(called.result.rows, vec![vec![json!(3)]]);
+    assert_eq!(called.outputs.len(), 2);
+    assert_eq!(called.outputs[0].name, "amount");
+    assert_eq!(called.outputs[0].value, json!("7.50"));
+    assert_eq!(called.outputs[1].value, json!("scaled"));
+}
+
+#[tokio::test]
+async fn test_profile_column() {
+    let harness = start_mysql().await;
+    let fixture = Fixture::default();
+
+    let profile = harness
+        .adapter
+        .profile_column(DATABASE, "orders", "customer_id")
+        .await
+        .unwrap();
+    assert_eq!(
+        profile.total_rows as usize,
+        fixture.customers * fixture.orders_per_customer
+    );
+    assert_eq!(profile.null_count, 0);
+    assert_eq!(profile.distinct_count as usize, fixture.customers);
+    assert_eq!(profile.min, json!(1));
+    assert_eq!(profile.max, json!(fixture.customers));
+    assert_eq!(profile.top_values.len(), 10);
+    assert!(profile
+        .top_values
+        .iter()
+        .all(|top| top.count as usize == fixture.orders_per_customer));
+}
+
+#[tokio::test]
+async fn test_seed_table() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    let fixture = Fixture::default();
+    let existing = fixture.customers * fixture.orders_per_customer;
+
+    let request = SeedTableRequest {
+        connection_id: "integration".to_string(),
+        database: DATABASE.to_string(),
+        table: "orders".to_string(),
+        row_count: 25,
+        batch_size: Some(10),
+    };
+    let batches = std::sync::Mutex::new(Vec::new());
+    let progress = |rows| {
+        batches.lock().unwrap().push(rows);
+        true
+    };
+    assert_eq!(adapter.seed_table(&request, &progress).await.unwrap(), 25);
+    assert_eq!(*batches.lock().unwrap(), vec![10, 20, 25]);
+
+    let page = adapter
+        .get_table_data(&table_request("orders", 0, 100))
+        .await
+        .unwrap();
+    assert_eq!(page.total_rows as usize, existing + 25);
+
+    // New keys follow the existing ones; foreign keys point at real rows
+    let result = adapter
+        .execute_query_with_database(
+            "SELECT MIN(o.id), COUNT(c.id) FROM orders o
+             JOIN customers c ON c.id = o.customer_id
+             WHERE o.id > ?",
+            Some(DATABASE),
+            &[json!(existing)],
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.rows[0], vec![json!(existing + 1), json!(25)]);
+}
+
+#[tokio::test]
+async fn test_cell_file_roundtrip() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    let dir = tempfile::TempDir::new().unwrap();
+
+    let mut key = HashMap::new();
+    key.insert("id".to_string(), json!(3));
+    let mut request = CellFileRequest {
+        connection_id: "integration".to_string(),
+        database: DATABASE.to_string(),
+        table: "products".to_string(),
+        column: "thumbnail".to_string(),
+        primary_key: key,
+        path: String::new(),
+    };
+
+    let pdf = b"%PDF-1.4 not really a pdf \x00\xff".to_vec();
+    assert_eq!(adapter.update_cell(&request, pdf.clone()).await.unwrap(), 1);
+
+    request.path = dir.path().join("thumbnail.bin").display().to_string();
+    let written = adapter.stream_cell_to_file(&request).await.unwrap();
+    assert_eq!(written, pdf.len() as u64);
+    assert_eq!(std::fs::read(&request.path).unwrap(), pdf);
+}
+
+#[tokio::test]
+async fn test_json_columns() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    adapter
+        .execute_statements(&[format!(
+            "CREATE TABLE `{}`.docs (id INT PRIMARY KEY, body JSON)",
+            DATABASE
+        )])
+        .await
+        .unwrap();
+
+    let body = json!({"tags": ["a", "b"], "note": "it's \"quoted\"\\n"});
+    let mut data = HashMap::new();
+    data.insert("id".to_string(), CellValue::Literal(json!(1)));
+    data.insert("body".to_string(), CellValue::Literal(body.clone()));
+    adapter
+        .insert_row(&InsertRowRequest {
+            connection_id: "integration".to_string(),
+            database: DATABASE.to_string(),
+            table: "docs".to_string(),
+            data,
+        })
+        .await
+        .unwrap();
+
+    let page = adapter
+        .get_table_data(&table_request("docs", 0, 10))
+        .await
+        .unwrap();
+    assert_eq!(page.rows[0]["body"], body);
+}
+
+#[tokio::test]
+async fn test_table_row_counts() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    adapter
+        .execute_statements(&[format!(
+            "CREATE TABLE `{}`.drafts (id INT PRIMARY KEY)",
+            DATABASE
+        )])
+        .await
+        .unwrap();
+
+    let tables: Vec<String> = ["orders", "drafts", "missing"]
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+    let totals = adapter
+        .table_row_counts(DATABASE, &tables, &["orders".to_string()])
+        .await
+        .unwrap();
+
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals[0].table, "orders");
+    assert_eq!(totals[0].rows, Some(150));
+    assert!(totals[0].exact && !totals[0].empty);
+    assert_eq!(totals[1].table, "drafts");
+    assert!(!totals[1].exact && totals[1].empty);
+}
+
+#[tokio::test]
+async fn test_server_info() {
+    let harness = start_mysql().await;
+    let info = harness.adapter.server_info().await.unwrap();
+    assert!(!info.version.is_empty());
+    assert_eq!(info.session_time_zone, info.server_time_zone);
+}
+
+#[tokio::test]
+async fn test_slow_queries() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+    adapter
+        .execute_query("SELECT SLEEP(0.01), 'digest probe'")
+        .await
+        .unwrap();
+
+    let queries = adapter.slow_queries(1000).await.unwrap();
+    let probe = queries
+        .iter()
+        .find(|q| q.digest_text.contains("SLEEP"))
+        .expect("statement digest recorded");
+    assert!(probe.calls >= 1);
+    assert!(probe.total_latency_ms >= 10.0);
+    assert!(probe.mean_latency_ms <= probe.max_latency_ms);
+}
+
+#[tokio::test]
+async fn test_session_setup() {
+    let harness = start_mysql().await;
+    let mut connection = harness.connection.clone();
+    connection.options.time_zone = Some("+05:30".to_string());
+    connection.options.session_variables =
+        [("sql_mode".to_string(), "ANSI_QUOTES".to_string())].into();
+    connection.options.init_sql =
+        Some("SET @app = 'dbclient'; SET SESSION sort_buffer_size = 1048576".to_string());
+
+    let adapter = MySQLAdapter::new(&connection).await.unwrap();
+    let info = adapter.server_info().await.unwrap();
+    assert_eq!(info.session_time_zone, "+05:30");
+    assert_eq!(info.utc_offset_minutes, 330);
+
+    connection.options.init_sql = Some("SET ROLE missing_role".to_string());
+    match MySQLAdapter::new(&connection).await {
+        Err(DatabaseError::Connection(message)) => assert!(message.contains("SET ROLE")),
+        other => panic!("expected a connection error, got {:?}", other.err()),
+    }
+}
+
+#[tokio::test]
+async fn test_validate_runs_nothing() {
+    let harness = start_mysql().await;
+    let adapter = &harness.adapter;
+
+    assert!(adapter
+        .validate_statement("SELECT name FROM customers", Some(DATABASE))
+        .await
+        .unwrap());
+    // The tokenizer sees one statement; the server would see two
+    let hidden = "SELECT 1 /*!50000 ; DROP TABLE products */";
+    assert!(adapter
+        .validate_statement(hidden, Some(DATABASE))
+        .await
+        .is_err());
+
+    let tables = adapter.get_schema(DATABASE).await.unwrap().tables;
+    assert!(tables.iter().any(|table| table.name == "products"));
+}
+
+#[tokio::test]
+async fn test_copy_within_a_single_connection_pool() {
+    let harness = start_mysql().await;
+    let mut connection = harness.connection.clone();
+    connection.options.pool_size = 1;
+    let adapter = MySQLAdapter::new(&connection).await.unwrap();
+
+    let schema = adapter.get_table_schema(DATABASE, "products").await.unwrap();
+    let plan = CopyPlan::new(&schema, DATABASE, DATABASE, "products_copy");
+    // Streaming the source holds the only connection the inserts would need
+    let outcome = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        adapter.copy_rows(&adapter, &plan, 100, &|_| true),
+    )
+    .await
+    .expect("copy waited on its own connection");
+    assert!(matches!(outcome, Err(DatabaseError::Query(_))));
+}
@@ -0,0 +1,655 @@
+//! Table rows as JSON files that can be imported back without losing types.
+//!
+//! Every value carries a type tag, e.g. `{"type":"decimal","value":"12.50"}`,
+//! so DECIMALs keep their digits, temporal values stay text rather than
+//! becoming numbers or dates in some time zone, and binary values travel as
+//! hex. A file starts with a header naming the table and its columns; rows
+//! are arrays of values in that order. As a document the rows are an array
+//! under `rows`; as NDJSON the header is the first line and each row follows
+//! on its own line, so neither side has to hold a large table in memory.
+
+use crate::db::data_compare::{BoundStatement, SyncColumn};
+use crate::db::sql::quote_identifier;
+use crate::db::table_copy::CopyPlan;
+use crate::db::values;
+use crate::models::{
+    ColumnSchema, DatabaseType, JsonImportReport, JsonLayout, JsonRowError, TableSchema, ValueKind,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Identifies a file written by `export_table_json`
+pub const JSON_FORMAT: &str = "dabba-table";
+
+const JSON_FORMAT_VERSION: u32 = 1;
+
+/// Failed rows whose errors an import report lists; the rest are counted
+const MAX_REPORTED_ERRORS: usize = 1000;
+
+/// A value with its type, as stored in a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum TypedValue {
+    Null,
+    Boolean(bool),
+    Integer(Number),
+    Float(f64),
+    /// Kept as text, so no digits are lost
+    Decimal(String),
+    Text(String),
+    Json(Value),
+    /// Hex digits
+    Binary(String),
+    Date(String),
+    Time(String),
+    DateTime(String),
+    Year(u16),
+    Bit(u64),
+}
+
+impl TypedValue {
+    /// Tag a value read from a column of `kind`. Binary columns are read as
+    /// hex and JSON columns as text (see `SyncColumn::select_expr`).
+    pub fn tag(kind: ValueKind, value: Value) -> Self {
+        let text = |value: Value| match value {
+            Value::String(text) => text,
+            other => other.to_string(),
+        };
+        match (kind, value) {
+            (_, Value::Null) => TypedValue::Null,
+            (_, Value::Bool(flag)) => TypedValue::Boolean(flag),
+            (ValueKind::Year, Value::Number(n)) if n.as_u64().is_some_and(|y| y <= 9999) => {
+                TypedValue::Year(n.as_u64().unwrap_or_default() as u16)
+            }
+            (ValueKind::Bit, Value::Number(n)) if n.is_u64() => {
+                TypedValue::Bit(n.as_u64().unwrap_or_default())
+            }
+            (ValueKind::Float, Value::Number(n)) => TypedValue::Float(n.as_f64().unwrap_or(0.0)),
+            (_, Value::Number(n)) => TypedValue::Integer(n),
+            (ValueKind::Decimal, value) => TypedValue::Decimal(text(value)),
+            (ValueKind::Binary | ValueKind::Geometry, value) => TypedValue::Binary(text(value)),
+            (ValueKind::Date, value) => TypedValue::Date(text(value)),
+            (ValueKind::Time, value) => TypedValue::Time(text(value)),
+            (ValueKind::DateTime, value) => TypedValue::DateTime(text(value)),
+            (ValueKind::Json, Value::String(document)) => match serde_json::from_str(&document) {
+                Ok(json) => TypedValue::Json(json),
+                Err(_) => TypedValue::Text(document),
+            },
+            (ValueKind::Json, json) => TypedValue::Json(json),
+            (_, value) => TypedValue::Text(text(value)),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            TypedValue::Null => "null",
+            TypedValue::Boolean(_) => "boolean",
+            TypedValue::Integer(_) => "integer",
+            TypedValue::Float(_) => "float",
+            TypedValue::Decimal(_) => "decimal",
+            TypedValue::Text(_) => "text",
+            TypedValue::Json(_) => "json",
+            TypedValue::Binary(_) => "binary",
+            TypedValue::Date(_) => "date",
+            TypedValue::Time(_) => "time",
+            TypedValue::DateTime(_) => "datetime",
+            TypedValue::Year(_) => "year",
+            TypedValue::Bit(_) => "bit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonColumn {
+    pub name: String,
+    /// The column's type on the table it was exported from
+    #[serde(rename = "type")]
+    pub data_type: String,
+}
+
+/// What a file holds, written before its rows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonHeader {
+    pub format: String,
+    pub version: u32,
+    pub table: String,
+    pub columns: Vec<JsonColumn>,
+}
+
+#[derive(Deserialize)]
+struct JsonDocument {
+    #[serde(flatten)]
+    header: JsonHeader,
+    rows: Vec<Value>,
+}
+
+/// How the rows of a table are read for export, and the file's header
+pub struct ExportPlan {
+    pub header: JsonHeader,
+    kinds: Vec<ValueKind>,
+    select: String,
+}
+
+impl ExportPlan {
+    /// Every column, generated ones included, so the file shows the table
+    /// as it was; importing leaves those to the target to compute
+    pub fn new(schema: &TableSchema, database: &str) -> Self {
+        let columns: Vec<SyncColumn> = schema.columns.iter().map(SyncColumn::from_schema).collect();
+        let select = format!(
+            "SELECT {} FROM {}.{}",
+            columns
+                .iter()
+                .map(SyncColumn::select_expr)
+                .collect::<Vec<_>>()
+                .join(", "),
+            quote_identifier(&DatabaseType::MySQL, database),
+            quote_identifier(&DatabaseType::MySQL, &schema.name)
+        );
+        Self {
+            header: JsonHeader {
+                format: JSON_FORMAT.to_string(),
+                version: JSON_FORMAT_VERSION,
+                table: schema.name.clone(),
+                columns: schema
+                    .columns
+                    .iter()
+                    .map(|column| JsonColumn {
+                        name: column.name.clone(),
+                        data_type: column.data_type.clone(),
+                    })
+                    .collect(),
+            },
+            kinds: schema.columns.iter().map(kind).collect(),
+            select,
+        }
+    }
+
+    pub fn select(&self) -> &str {
+        &self.select
+    }
+
+    /// What goes before the first row
+    pub fn start(&self, layout: JsonLayout) -> String {
+        let header = serde_json::to_string(&self.header).unwrap_or_default();
+        match layout {
+            // Leave the header object open for the rows that follow
+            JsonLayout::Document => {
+                let open = header.strip_suffix('}').unwrap_or(&header);
+                format!("{},\"rows\":[\n", open)
+            }
+            JsonLayout::Ndjson => format!("{}\n", header),
+        }
+    }
+
+    /// A row read with `select`, as its line in the file
+    pub fn row(&self, layout: JsonLayout, index: u64, row: Vec<Value>) -> String {
+        let values: Vec<TypedValue> = self
+            .kinds
+            .iter()
+            .zip(row)
+            .map(|(kind, value)| TypedValue::tag(*kind, value))
+            .collect();
+        let line = serde_json::to_string(&values).unwrap_or_default();
+        match layout {
+            JsonLayout::Document if index > 0 => format!(",\n{}", line),
+            JsonLayout::Document => line,
+            JsonLayout::Ndjson => line + "\n",
+        }
+    }
+
+    /// What goes after the last row
+    pub fn end(&self, layout: JsonLayout) -> &'static str {
+        match layout {
+            JsonLayout::Document => "\n]}\n",
+            JsonLayout::Ndjson => "",
+        }
+    }
+}
+
+fn kind(column: &ColumnSchema) -> ValueKind {
+    values::value_kind(&column.data_type.to_uppercase())
+}
+
+/// A row that couldn't be imported; `row` counts from 1
+pub fn row_error(row: u64, column: Option<&str>, message: impl Into<String>) -> JsonRowError {
+    JsonRowError {
+        row,
+        column: column.map(str::to_string),
+        message: message.into(),
+    }
+}
+
+/// Count a row that wasn't imported, keeping its error if there's room
+pub fn record_failure(report: &mut JsonImportReport, error: JsonRowError) {
+    report.failed += 1;
+    if report.errors.len() < MAX_REPORTED_ERRORS {
+        report.errors.push(error);
+    }
+}
+
+/// The rows of a file with their positions, read as they're needed when
+/// it's NDJSON
+pub type JsonRows = Box<dyn Iterator<Item = (u64, Result<Vec<TypedValue>, JsonRowError>)> + Send>;
+
+/// Open a file from `export_table_json` in either layout
+pub fn open(path: &Path) -> Result<(JsonHeader, JsonRows), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(file);
+    let mut first = String::new();
+    reader.read_line(&mut first).map_err(|e| e.to_string())?;
+
+    // An NDJSON header is a whole object on the first line; a document's
+    // first line is left open for its rows
+    if let Ok(header) = serde_json::from_str::<JsonHeader>(&first) {
+        check_header(&header)?;
+        let rows = reader.lines().enumerate().filter_map(|(index, line)| {
+            let row = index as u64 + 1;
+            match line {
+                Ok(line) if line.trim().is_empty() => None,
+                Ok(line) => Some((row, parse_row(row, serde_json::from_str(&line)))),
+                Err(e) => Some((row, Err(row_error(row, None, e.to_string())))),
+            }
+        });
+        return Ok((header, Box::new(rows)));
+    }
+
+    reader
+        .read_to_string(&mut first)
+        .map_err(|e| e.to_string())?;
+    let document: JsonDocument =
+        serde_json::from_str(&first).map_err(|e| format!("Not a table JSON file: {}", e))?;
+    check_header(&document.header)?;
+    let rows = document.rows.into_iter().enumerate().map(|(index, row)| {
+        let number = index as u64 + 1;
+        (number, parse_row(number, Ok(row)))
+    });
+    Ok((document.header, Box::new(rows)))
+}
+
+fn check_header(header: &JsonHeader) -> Result<(), String> {
+    if header.format != JSON_FORMAT {
+        return Err(format!(
+            "Not a table JSON file: format is {}",
+            header.format
+        ));
+    }
+    if header.version > JSON_FORMAT_VERSION {
+        return Err(format!(
+            "The file is from a newer version (format version {})",
+            header.version
+        ));
+    }
+    Ok(())
+}
+
+fn parse_row(row: u64, value: serde_json::Result<Value>) -> Result<Vec<TypedValue>, JsonRowError> {
+    let value = value.map_err(|e| row_error(row, None, format!("Invalid JSON: {}", e)))?;
+    let Value::Array(cells) = value else {
+        return Err(row_error(row, None, "A row must be an array of values"));
+    };
+    cells
+        .into_iter()
+        .map(|cell| {
+            serde_json::from_value(cell)
+                .map_err(|e| row_error(row, None, format!("Invalid value: {}", e)))
+        })
+        .collect()
+}
+
+/// Where each value of a file's rows goes in the target table
+pub struct ImportPlan {
+    /// The target column of each file column; None for generated columns,
+    /// which the target computes
+    targets: Vec<Option<ColumnSchema>>,
+    names: Vec<String>,
+    insert: CopyPlan,
+}
+
+impl ImportPlan {
+    /// Check a file's columns against the target table: every one must exist
+    /// there, and every target column that needs a value must be in the file
+    pub fn new(header: &JsonHeader, schema: &TableSchema, database: &str) -> Result<Self, String> {
+        let mut targets = Vec::with_capacity(header.columns.len());
+        for column in &header.columns {
+            let target = schema
+                .columns
+                .iter()
+                .find(|c| c.name.eq_ignore_ascii_case(&column.name))
+                .ok_or_else(|| format!("Column {} isn't in table {}", column.name, schema.name))?;
+            targets.push((!target.is_generated).then(|| target.clone()));
+        }
+
+        let missing: Vec<&str> = schema
+            .columns
+            .iter()
+            .filter(|c| {
+                !c.is_nullable
+                    && c.default_value.is_none()
+                    && !c.is_auto_increment
+                    && !c.is_generated
+            })
+            .filter(|c| {
+                !header
+                    .columns
+                    .iter()
+                    .any(|f| f.name.eq_ignore_ascii_case(&c.name))
+            })
+            .map(|c| c.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "The file has no values for required columns: {}",
+                missing.join(", ")
+            ));
+        }
+
+        let written = TableSchema {
+            columns: targets.iter().flatten().cloned().collect(),
+            ..schema.clone()
+        };
+        if written.columns.is_empty() {
+            return Err("The file has no columns that can be written".to_string());
+        }
+        Ok(Self {
+            names: header.columns.iter().map(|c| c.name.clone()).collect(),
+            targets,
+            insert: CopyPlan::new(&written, database, database, &schema.name),
+        })
+    }
+
+    /// Rows per INSERT, kept under the placeholder limit
+    pub fn batch_size(&self, requested: usize) -> usize {
+        self.insert.batch_size(requested)
+    }
+
+    pub fn insert(&self, rows: Vec<Vec<Value>>) -> BoundStatement {
+        self.insert.insert(rows)
+    }
+
+    /// Values to bind for row number `row` of the file, or why it can't be
+    /// written to the target
+    pub fn bind(&self, row: u64, values: Vec<TypedValue>) -> Result<Vec<Value>, JsonRowError> {
+        if values.len() != self.targets.len() {
+            return Err(row_error(
+                row,
+                None,
+                format!(
+                    "Expected {} values, found {}",
+                    self.targets.len(),
+                    values.len()
+                ),
+            ));
+        }
+        let mut bound = Vec::with_capacity(values.len());
+        for ((target, name), value) in self.targets.iter().zip(&self.names).zip(values) {
+            let Some(target) = target else {
+                continue;
+            };
+            bound.push(convert(target, value).map_err(|e| row_error(row, Some(name), e))?);
+        }
+        Ok(bound)
+    }
+}
+
+/// A value checked against the column it's written to, as it's bound
+fn convert(column: &ColumnSchema, value: TypedValue) -> Result<Value, String> {
+    let mismatch = |value: &TypedValue| {
+        format!(
+            "A {} value can't be written to a {} column",
+            value.type_name(),
+            column.data_type
+        )
+    };
+    let kind = kind(column);
+    Ok(match (kind, value) {
+        (_, TypedValue::Null) if column.is_nullable || column.is_auto_increment => Value::Null,
+        (_, TypedValue::Null) => return Err("The column doesn't allow NULL".to_string()),
+
+        (ValueKind::Integer | ValueKind::Bit | ValueKind::Boolean, TypedValue::Boolean(b)) => {
+            Value::from(b as u8)
+        }
+        (ValueKind::Integer | ValueKind::Bit | ValueKind::Year, TypedValue::Integer(n)) => {
+            Value::Number(n)
+        }
+        (ValueKind::Integer | ValueKind::Bit, TypedValue::Bit(bits)) => Value::from(bits),
+        (ValueKind::Integer | ValueKind::Year, TypedValue::Year(year)) => Value::from(year),
+        (ValueKind::Float, TypedValue::Integer(n)) => Value::Number(n),
+        (ValueKind::Float, TypedValue::Float(f)) => Value::from(f),
+        (ValueKind::Float | ValueKind::Decimal, TypedValue::Decimal(d)) => {
+            if d.trim().parse::<f64>().is_err() {
+                return Err(format!("{} isn't a number", d));
+            }
+            Value::String(d)
+        }
+        (ValueKind::Decimal, TypedValue::Integer(n)) => Value::String(n.to_string()),
+        (ValueKind::Decimal, TypedValue::Float(f)) => Value::String(f.to_string()),
+
+        (ValueKind::Text | ValueKind::Other, TypedValue::Text(text)) => {
+            if let Some(max) = column.max_length {
+                let length = text.chars().count();
+                if length as i64 > max {
+                    return Err(format!(
+                        "{} characters is longer than the column's {}",
+                        length, max
+                    ));
+                }
+            }
+            Value::String(text)
+        }
+        (ValueKind::Enum, TypedValue::Text(text)) => {
+            let allowed = column.allowed_values.as_deref().unwrap_or_default();
+            // A SET value lists members separated by commas
+            let members = if column.data_type.eq_ignore_ascii_case("set") {
+                text.split(',').filter(|m| !m.is_empty()).collect()
+            } else {
+                vec![text.as_str()]
+            };
+            if let Some(unknown) = members.iter().find(|m| !allowed.iter().any(|a| a == *m)) {
+                return Err(format!("{} isn't one of the column's values", unknown));
+            }
+            Value::String(text)
+        }
+        (ValueKind::Json, TypedValue::Json(json)) => Value::String(json.to_string()),
+        (ValueKind::Binary | ValueKind::Geometry, TypedValue::Binary(hex)) => {
+            if hex.len() % 2 != 0 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err("Binary values must be hex digits".to_string());
+            }
+            Value::String(hex)
+        }
+        (ValueKind::Date, TypedValue::Date(text))
+        | (ValueKind::Time, TypedValue::Time(text))
+        | (ValueKind::DateTime, TypedValue::DateTime(text) | TypedValue::Date(text)) => {
+            Value::String(text)
+        }
+        (_, value) => return Err(mismatch(&value)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(name: &str, data_type: &str) -> ColumnSchema {
+        ColumnSchema {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: false,
+            default_value: None,
+            max_length: None,
+            is_auto_increment: false,
+            is_generated: false,
+            extra: String::new(),
+            allowed_values: None,
+        }
+    }
+
+    fn payments() -> TableSchema {
+        let mut id = column("id", "bigint");
+        id.is_auto_increment = true;
+        let mut note = column("note", "varchar");
+        note.is_nullable = true;
+        note.max_length = Some(5);
+        let mut total_cents = column("total_cents", "bigint");
+        total_cents.is_generated = true;
+        TableSchema {
+            name: "payments".to_string(),
+            schema: None,
+            columns: vec![
+                id,
+                column("total", "decimal"),
+                column("paid_at", "datetime"),
+                column("receipt", "blob"),
+                note,
+                total_cents,
+            ],
+            primary_keys: vec!["id".to_string()],
+            foreign_keys: vec![],
+        }
+    }
+
+    #[test]
+    fn test_tagged_values_round_trip() {
+        let values = vec![
+            TypedValue::tag(ValueKind::Integer, json!(7)),
+            TypedValue::tag(ValueKind::Decimal, json!("12.50")),
+            TypedValue::tag(ValueKind::DateTime, json!("2024-03-01 10:00:00.250000")),
+            TypedValue::tag(ValueKind::Binary, json!("00FF")),
+            TypedValue::tag(ValueKind::Json, json!("{\"a\": [1, 2]}")),
+            TypedValue::tag(ValueKind::Text, Value::Null),
+        ];
+        let line = serde_json::to_string(&values).unwrap();
+        assert!(line.contains(r#"{"type":"decimal","value":"12.50"}"#));
+        assert!(line.contains(r#"{"type":"json","value":{"a":[1,2]}}"#));
+        assert!(line.contains(r#"{"type":"null"}"#));
+        let parsed: Vec<TypedValue> = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed, values);
+    }
+
+    #[test]
+    fn test_both_layouts_read_back() {
+        let plan = ExportPlan::new(&payments(), "shop");
+        assert_eq!(
+            plan.select(),
+            "SELECT `id`, `total`, `paid_at`, HEX(`receipt`) AS `receipt`, `note`, \
+             `total_cents` FROM `shop`.`payments`"
+        );
+        let row = |id: u64| {
+            vec![
+                json!(id),
+                json!("9.99"),
+                json!("2024-03-01 10:00:00"),
+                json!("CAFE"),
+                Value::Null,
+                json!(999),
+            ]
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        for layout in [JsonLayout::Document, JsonLayout::Ndjson] {
+            let mut contents = plan.start(layout);
+            for index in 0..2 {
+                contents.push_str(&plan.row(layout, index, row(index + 1)));
+            }
+            contents.push_str(plan.end(layout));
+
+            let path = dir.path().join("payments.json");
+            std::fs::write(&path, &contents).unwrap();
+            let (header, rows) = open(&path).unwrap();
+            assert_eq!(header, plan.header);
+            let rows: Vec<_> = rows.map(|(_, row)| row.unwrap()).collect();
+            assert_eq!(rows.len(), 2);
+            assert_eq!(rows[1][0], TypedValue::Integer(2.into()));
+            assert_eq!(rows[1][1], TypedValue::Decimal("9.99".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_import_checks_rows_against_target() {
+        let schema = payments();
+        let header = ExportPlan::new(&schema, "shop").header;
+        let plan = ImportPlan::new(&header, &schema, "shop").unwrap();
+        let row = |total: TypedValue, note: TypedValue| {
+            vec![
+                TypedValue::Null,
+                total,
+                TypedValue::DateTime("2024-03-01 10:00:00".to_string()),
+                TypedValue::Binary("CAFE".to_string()),
+                note,
+                TypedValue::Integer(999.into()),
+            ]
+        };
+
+        // The generated column is left to the target
+        assert_eq!(
+            plan.bind(
+                1,
+                row(TypedValue::Decimal("9.99".to_string()), TypedValue::Null)
+            )
+            .unwrap(),
+            vec![
+                Value::Null,
+                json!("9.99"),
+                json!("2024-03-01 10:00:00"),
+                json!("CAFE"),
+                Value::Null
+            ]
+        );
+        assert!(plan
+            .insert(vec![vec![Value::Null; 5]])
+            .sql
+            .contains("UNHEX(?)"));
+
+        let error = plan
+            .bind(
+                2,
+                row(TypedValue::Text("nine".to_string()), TypedValue::Null),
+            )
+            .unwrap_err();
+        assert_eq!(error.row, 2);
+        assert_eq!(error.column.as_deref(), Some("total"));
+        assert_eq!(
+            error.message,
+            "A text value can't be written to a decimal column"
+        );
+
+        let error = plan
+            .bind(
+                3,
+                row(
+                    TypedValue::Integer(1.into()),
+                    TypedValue::Text("too long".into()),
+                ),
+            )
+            .unwrap_err();
+        assert_eq!(error.column.as_deref(), Some("note"));
+
+        let error = plan
+            .bind(4, row(TypedValue::Null, TypedValue::Null))
+            .unwrap_err();
+        assert_eq!(error.message, "The column doesn't allow NULL");
+    }
+
+    #[test]
+    fn test_import_needs_matching_columns() {
+        let schema = payments();
+        let mut header = ExportPlan::new(&schema, "shop").header;
+        header.columns.retain(|c| c.name != "total");
+        assert_eq!(
+            ImportPlan::new(&header, &schema, "shop").err().unwrap(),
+            "The file has no values for required columns: total"
+        );
+
+        header.columns.push(JsonColumn {
+            name: "tax".to_string(),
+            data_type: "decimal".to_string(),
+        });
+        assert_eq!(
+            ImportPlan::new(&header, &schema, "shop").err().unwrap(),
+            "Column tax isn't in table payments"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Masking of sensitive columns, e.g. while screen-sharing against
+//! production data. A connection's rules name columns by wildcard pattern;
+//! values of matching columns are replaced before results leave the
+//! backend, and only `reveal_masked_cell` hands one out in full.
+
+use crate::models::{ColumnProfile, ColumnTypeInfo};
+use serde_json::Value;
+
+/// What a masked value is replaced with; NULLs stay NULL
+pub const MASKED_VALUE: &str = "••••••";
+
+/// Whether `text` matches `pattern`, where `*` matches any run of characters
+/// and `?` a single one, ignoring case
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A connection's masking rules. A rule is a column pattern such as
+/// `*password*`, or `table.column` with patterns on both sides.
+pub struct MaskRules<'a> {
+    rules: &'a [String],
+}
+
+impl<'a> MaskRules<'a> {
+    pub fn new(rules: &'a [String]) -> Self {
+        Self { rules }
+    }
+
+    /// Whether a column is masked. Query results don't say which table a
+    /// column came from, so with `table` None a `table.column` rule masks
+    /// every column of that name.
+    pub fn is_masked(&self, table: Option<&str>, column: &str) -> bool {
+        self.rules
+            .iter()
+            .map(|rule| rule.trim())
+            .any(|rule| match rule.split_once('.') {
+                Some((table_pattern, column_pattern)) => {
+                    wildcard_match(column_pattern, column)
+                        && table.is_none_or(|table| wildcard_match(table_pattern, table))
+                }
+                None => !rule.is_empty() && wildcard_match(rule, column),
+            })
+    }
+
+    /// Flag the masked columns of a result of `table`, or of a query
+    pub fn flag(&self, columns: &mut [ColumnTypeInfo], table: Option<&str>) {
+        for column in columns {
+            column.masked = self.is_masked(table, &column.name);
+        }
+    }
+}
+
+/// Which of `columns` are flagged as masked
+pub fn masked_flags(columns: &[ColumnTypeInfo]) -> Vec<bool> {
+    columns.iter().map(|column| column.masked).collect()
+}
+
+pub fn mask_value(value: &mut Value) {
+    if !value.is_null() {
+        *value = Value::String(MASKED_VALUE.to_string());
+    }
+}
+
+/// Mask the values of a row in the columns flagged in `masked`
+pub fn mask_row(row: &mut [Value], masked: &[bool]) {
+    for (value, _) in row.iter_mut().zip(masked).filter(|(_, &masked)| masked) {
+        mask_value(value);
+    }
+}
+
+/// Mask the values a profile of a masked column quotes. The counts stay; the
+/// average length goes too, as it tells a lot about values like card numbers.
+pub fn mask_profile(profile: &mut ColumnProfile) {
+    mask_value(&mut profile.min);
+    mask_value(&mut profile.max);
+    profile.avg_length = None;
+    for frequency in &mut profile.top_values {
+        mask_value(&mut frequency.value);
+    }
+}
+
+/// Mask the values of the columns flagged as masked
+pub fn mask_rows(rows: &mut [Vec<Value>], columns: &[ColumnTypeInfo]) {
+    let masked = masked_flags(columns);
+    if masked.contains(&true) {
+        for row in rows {
+            mask_row(row, &masked);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rules_match_columns_and_tables() {
+        let rules = vec![
+            "*password*".to_string(),
+            "users.ssn".to_string(),
+            "pay*.card_?".to_string(),
+        ];
+        let rules = MaskRules::new(&rules);
+
+        assert!(rules.is_masked(Some("accounts"), "Password_Hash"));
+        assert!(rules.is_masked(Some("users"), "ssn"));
+        assert!(!rules.is_masked(Some("employees"), "ssn"));
+        assert!(rules.is_masked(Some("payments"), "card_1"));
+        assert!(!rules.is_masked(Some("payments"), "card_10"));
+        // Without a table, a table rule masks every column of its name
+        assert!(rules.is_masked(None, "ssn"));
+        assert!(!rules.is_masked(None, "email"));
+    }
+
+    #[test]
+    fn test_mask_rows_keeps_nulls() {
+        let rules = vec!["secret".to_string()];
+        let column = |name: &str| ColumnTypeInfo {
+            name: name.to_string(),
+            type_name: "TEXT".to_string(),
+            kind: crate::models::ValueKind::Text,
+            truncated: false,
+            masked: false,
+        };
+        let mut columns = vec![column("id"), column("secret")];
+        MaskRules::new(&rules).flag(&mut columns, None);
+
+        let mut rows = vec![
+            vec![json!(1), json!("hunter2")],
+            vec![json!(2), json!(null)],
+        ];
+        mask_rows(&mut rows, &columns);
+        assert_eq!(
+            rows,
+            vec![
+                vec![json!(1), json!(MASKED_VALUE)],
+                vec![json!(2), json!(null)]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mask_profile_keeps_counts() {
+        let mut profile = ColumnProfile {
+            column: "ssn".to_string(),
+            total_rows: 3,
+            null_count: 1,
+            distinct_count: 2,
+            min: json!("078-05-1120"),
+            max: json!("219-09-9999"),
+            avg_length: Some(11.0),
+            top_values: vec![
+                crate::models::ValueFrequency {
+                    value: json!("078-05-1120"),
+                    count: 1,
+                },
+                crate::models::ValueFrequency {
+                    value: json!(null),
+                    count: 1,
+                },
+            ],
+        };
+        mask_profile(&mut profile);
+
+        assert_eq!(profile.min, json!(MASKED_VALUE));
+        assert_eq!(profile.max, json!(MASKED_VALUE));
+        assert_eq!(profile.avg_length, None);
+        assert_eq!(profile.top_values[0].value, json!(MASKED_VALUE));
+        assert_eq!(profile.top_values[0].count, 1);
+        assert_eq!(profile.top_values[1].value, json!(null));
+        assert_eq!((profile.null_count, profile.distinct_count), (1, 2));
+    }
+}
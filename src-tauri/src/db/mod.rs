@@ -1,3 +1,37 @@
+pub mod binary;
+pub mod client_config;
+pub mod completion;
+pub mod data_compare;
+pub mod dialect;
+pub mod diff;
+pub mod execution_queue;
+pub mod export;
+pub mod fixtures;
+pub mod grants;
+pub mod json_transfer;
+pub mod masking;
 pub mod mysql_adapter;
+pub mod placeholders;
+pub mod pool_cache;
+pub mod query_stats;
+pub mod query_cache;
+pub mod result_cache;
+pub mod result_filter;
+pub mod scheduler;
+pub mod schema_cache;
+pub mod seed;
+pub mod session;
+pub mod spill;
+pub mod sql;
+pub mod table_copy;
+pub mod table_queries;
+pub mod tasks;
+pub mod templates;
+pub mod validate;
+pub mod values;
+pub mod watchdog;
+
+#[cfg(all(test, feature = "integration-tests"))]
+mod integration_tests;
 
 pub use mysql_adapter::MySQLAdapter;
@@ -0,0 +1,210 @@
+//! `${NAME}` placeholders in connection fields, replaced with environment
+//! variables when the connection opens, so secrets can stay out of the
+//! connection store. `$${` stands for a literal `${`.
+
+use crate::models::connection::SSHAuth;
+use crate::models::Connection;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PlaceholderError {
+    #[error("Environment variable {name} used in {field} is not set")]
+    Unset { field: String, name: String },
+}
+
+pub type Result<T> = std::result::Result<T, PlaceholderError>;
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replace the placeholders in `value`; `field` names it in errors
+pub fn expand(
+    value: &str,
+    field: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start..];
+        if let Some(escaped) = after.strip_prefix("$${") {
+            expanded.push_str("${");
+            rest = escaped;
+        } else if let Some(placeholder) = after.strip_prefix("${") {
+            let name = placeholder.find('}').map(|end| &placeholder[..end]);
+            let Some(name) = name.filter(|name| is_name(name)) else {
+                // Not a placeholder, e.g. a password that happens to hold `${`
+                expanded.push_str("${");
+                rest = placeholder;
+                continue;
+            };
+            let resolved = lookup(name).ok_or_else(|| PlaceholderError::Unset {
+                field: field.to_string(),
+                name: name.to_string(),
+            })?;
+            expanded.push_str(&resolved);
+            rest = &placeholder[name.len() + 1..];
+        } else {
+            expanded.push('$');
+            rest = &after[1..];
+        }
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// The connection with placeholders in its server, credentials, socket,
+/// SSH and TLS settings replaced, looking names up with `lookup`
+pub fn resolve_with(
+    connection: &Connection,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Connection> {
+    let lookup = &lookup;
+    let expand_in = |slot: &mut String, field: &str| -> Result<()> {
+        *slot = expand(slot, field, lookup)?;
+        Ok(())
+    };
+    let expand_optional = |slot: &mut Option<String>, field: &str| -> Result<()> {
+        if let Some(value) = slot {
+            *value = expand(value, field, lookup)?;
+        }
+        Ok(())
+    };
+    let expand_auth = |auth: &mut SSHAuth, field: &str| -> Result<()> {
+        match auth {
+            SSHAuth::Password(password) => expand_in(password, &format!("{} password", field)),
+            SSHAuth::PrivateKey {
+                key_path,
+                passphrase,
+            } => {
+                expand_in(key_path, &format!("{} key path", field))?;
+                expand_optional(passphrase, &format!("{} key passphrase", field))
+            }
+            SSHAuth::Agent => Ok(()),
+        }
+    };
+
+    let mut resolved = connection.clone();
+    expand_in(&mut resolved.host, "host")?;
+    expand_in(&mut resolved.username, "username")?;
+    expand_in(&mut resolved.password, "password")?;
+    expand_optional(&mut resolved.database, "database")?;
+    expand_optional(&mut resolved.options.socket_path, "socket path")?;
+
+    if let Some(ssh) = &mut resolved.ssh_config {
+        expand_in(&mut ssh.host, "SSH host")?;
+        expand_in(&mut ssh.username, "SSH username")?;
+        expand_auth(&mut ssh.auth, "SSH")?;
+        for hop in &mut ssh.jump_hosts {
+            expand_in(&mut hop.host, "jump host")?;
+            expand_in(&mut hop.username, "jump host username")?;
+            expand_auth(&mut hop.auth, "jump host")?;
+        }
+    }
+    if let Some(ssl) = &mut resolved.ssl_config {
+        expand_optional(&mut ssl.ca_cert, "CA certificate")?;
+        expand_optional(&mut ssl.client_cert, "client certificate")?;
+        expand_optional(&mut ssl.client_key, "client key")?;
+    }
+
+    Ok(resolved)
+}
+
+/// The connection with placeholders replaced from the process environment
+pub fn resolve(connection: &Connection) -> Result<Connection> {
+    resolve_with(connection, |name| std::env::var(name).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::connection::SSHConfig;
+    use crate::models::DatabaseType;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "DB_PASSWORD" => Some("s3cret".to_string()),
+            "DB_HOST" => Some("db.internal".to_string()),
+            "HOME" => Some("/home/ci".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand("${DB_HOST}", "host", &lookup).unwrap(),
+            "db.internal"
+        );
+        assert_eq!(
+            expand("pre-${DB_PASSWORD}-$5", "password", &lookup).unwrap(),
+            "pre-s3cret-$5"
+        );
+        assert_eq!(
+            expand("$${DB_HOST}", "host", &lookup).unwrap(),
+            "${DB_HOST}"
+        );
+        assert_eq!(
+            expand("a${not a name}", "password", &lookup).unwrap(),
+            "a${not a name}"
+        );
+
+        assert_eq!(
+            expand("${MISSING}", "password", &lookup),
+            Err(PlaceholderError::Unset {
+                field: "password".to_string(),
+                name: "MISSING".to_string(),
+            })
+        );
+        assert_eq!(expand("${DB_HOST", "host", &lookup).unwrap(), "${DB_HOST");
+    }
+
+    #[test]
+    fn test_resolve_connection_fields() {
+        let mut connection = Connection::new(
+            "ci".to_string(),
+            "#3b82f6".to_string(),
+            DatabaseType::MySQL,
+            "${DB_HOST}".to_string(),
+            3306,
+            "ci".to_string(),
+            "${DB_PASSWORD}".to_string(),
+        );
+        connection.ssh_config = Some(SSHConfig {
+            host: "bastion".to_string(),
+            port: 22,
+            username: "ci".to_string(),
+            auth: SSHAuth::PrivateKey {
+                key_path: "${HOME}/.ssh/id_ed25519".to_string(),
+                passphrase: None,
+            },
+            use_ssh_config: false,
+            proxy_jump: None,
+            jump_hosts: vec![],
+        });
+
+        let resolved = resolve_with(&connection, lookup).unwrap();
+        assert_eq!(resolved.host, "db.internal");
+        assert_eq!(resolved.password, "s3cret");
+        match resolved.ssh_config.unwrap().auth {
+            SSHAuth::PrivateKey { key_path, .. } => {
+                assert_eq!(key_path, "/home/ci/.ssh/id_ed25519")
+            }
+            other => panic!("unexpected auth: {:?}", other),
+        }
+
+        connection.username = "${CI_USER}".to_string();
+        assert_eq!(
+            resolve_with(&connection, lookup).unwrap_err().to_string(),
+            "Environment variable CI_USER used in username is not set"
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Connection pools are expensive to open (TCP + auth, possibly through a
+//! tunnel), so adapters are kept per connection id and shared by all commands.
+
+use crate::db::mysql_adapter::{DatabaseError, Result};
+use crate::db::MySQLAdapter;
+use crate::models::{Connection, ConnectionStatus, ConnectionStatusEvent, LostReason};
+use crate::secrets::SecretResolver;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// How often cached pools are pinged
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Event emitted whenever a cached pool changes status
+pub const CONNECTION_STATUS_EVENT: &str = "connection://status";
+
+/// Event emitted when a cached pool stops responding, with the reason
+pub const CONNECTION_LOST_EVENT: &str = "connection://lost";
+
+/// Event emitted when a lost pool was reconnected
+pub const CONNECTION_RESTORED_EVENT: &str = "connection://restored";
+
+struct CachedPool {
+    connection: Connection,
+    adapter: Arc<MySQLAdapter>,
+}
+
+#[derive(Default)]
+pub struct PoolCache {
+    pools: Mutex<HashMap<String, CachedPool>>,
+    /// Credentials of connections that keep them in a secret store
+    pub secrets: SecretResolver,
+}
+
+impl PoolCache {
+    /// Open a pool, reading the connection's credentials from its secret
+    /// store first. A secret the server rejected is fetched again next time.
+    async fn open(&self, connection: &Connection) -> Result<MySQLAdapter> {
+        let resolved = self
+            .secrets
+            .resolve(connection)
+            .await
+            .map_err(|e| DatabaseError::Connection(e.to_string()))?;
+        let adapter = MySQLAdapter::new(&resolved).await;
+        if adapter.is_err() {
+            self.secrets.forget(connection);
+        }
+        adapter
+    }
+
+    /// Return the cached adapter for a connection, opening a pool on first use
+    pub async fn get_or_connect(&self, connection: &Connection) -> Result<Arc<MySQLAdapter>> {
+        if let Some(cached) = self.pools.lock().await.get(&connection.id) {
+            return Ok(cached.adapter.clone());
+        }
+
+        // Connect without holding the lock so other connections aren't blocked
+        tracing::info!(
+            connection_id = %connection.id,
+            host = %connection.host,
+            port = connection.port,
+            username = %connection.username,
+            ssh = connection.ssh_config.is_some(),
+            "Connecting"
+        );
+        let adapter = match self.open(connection).await {
+            Ok(adapter) => Arc::new(adapter),
+            Err(e) => {
+                tracing::warn!(connection_id = %connection.id, error = %e, "Connection failed");
+                return Err(e);
+            }
+        };
+        tracing::info!(connection_id = %connection.id, "Connected");
+
+        let mut pools = self.pools.lock().await;
+        let cached = pools
+            .entry(connection.id.clone())
+            .or_insert_with(|| CachedPool {
+                connection: connection.clone(),
+                adapter,
+            });
+        Ok(cached.adapter.clone())
+    }
+
+    /// Drop the pool for a connection, e.g. after it was edited or deleted
+    pub async fn invalidate(&self, connection_id: &str) {
+        if let Some(cached) = self.pools.lock().await.remove(connection_id) {
+            cached.adapter.close().await;
+        }
+    }
+
+    /// Ids of the connections with a cached pool
+    pub async fn connection_ids(&self) -> Vec<String> {
+        self.pools.lock().await.keys().cloned().collect()
+    }
+
+    /// Drop every pool, e.g. after the stored connections were replaced
+    pub async fn clear(&self) {
+        let pools: Vec<CachedPool> = self.pools.lock().await.drain().map(|(_, p)| p).collect();
+        for cached in pools {
+            cached.adapter.close().await;
+        }
+        self.secrets.clear();
+    }
+
+    /// Check every cached pool and rebuild the ones that stopped responding.
+    /// `on_status` is called for each pool that was lost and is reconnecting,
+    /// came back or had to be dropped.
+    pub async fn keepalive<F>(&self, on_status: F)
+    where
+        F: Fn(ConnectionStatusEvent),
+    {
+        let snapshot: Vec<(Connection, Arc<MySQLAdapter>)> = self
+            .pools
+            .lock()
+            .await
+            .values()
+            .map(|cached| (cached.connection.clone(), cached.adapter.clone()))
+            .collect();
+
+        for (connection, adapter) in snapshot {
+            // A dead tunnel fails the ping too, but says more about why
+            let (reason, error) = if adapter.tunnel_exited() {
+                (LostReason::TunnelDied, "The SSH tunnel exited".to_string())
+            } else {
+                match adapter.ping().await {
+                    Ok(()) => continue,
+                    Err(e) => (lost_reason(&e), e.to_string()),
+                }
+            };
+            tracing::warn!(
+                connection_id = %connection.id,
+                reason = ?reason,
+                error = %error,
+                "Connection lost, reconnecting"
+            );
+
+            on_status(ConnectionStatusEvent {
+                connection_id: connection.id.clone(),
+                status: ConnectionStatus::Reconnecting,
+                error: Some(error),
+                reason: Some(reason),
+            });
+            adapter.close().await;
+
+            match self.open(&connection).await {
+                Ok(fresh) => {
+                    let mut pools = self.pools.lock().await;
+                    // Skip if the connection was invalidated while reconnecting
+                    if let Some(cached) = pools.get_mut(&connection.id) {
+                        cached.adapter = Arc::new(fresh);
+                    }
+                    on_status(ConnectionStatusEvent {
+                        connection_id: connection.id,
+                        status: ConnectionStatus::Connected,
+                        error: None,
+                        reason: None,
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!(connection_id = %connection.id, error = %e, "Reconnect failed");
+                    self.pools.lock().await.remove(&connection.id);
+                    on_status(ConnectionStatusEvent {
+                        connection_id: connection.id,
+                        status: ConnectionStatus::Disconnected,
+                        reason: Some(lost_reason(&e)),
+                        error: Some(e.to_string()),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Why a pool stopped responding, from the error pinging or reopening it
+fn lost_reason(error: &DatabaseError) -> LostReason {
+    match error {
+        // Access denied, password expired or must be reset
+        DatabaseError::Server {
+            number: 1045 | 1820 | 1862,
+            ..
+        } => LostReason::AuthExpired,
+        // Server gone away, lost connection, shutting down or killed
+        DatabaseError::Server {
+            number: 1053 | 1927 | 2006 | 2013,
+            ..
+        } => LostReason::ServerGone,
+        DatabaseError::Connection(_) | DatabaseError::Io(_) => LostReason::ServerGone,
+        DatabaseError::Timeout(_) => LostReason::Timeout,
+        _ => LostReason::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(number: u16) -> DatabaseError {
+        DatabaseError::Server {
+            message: String::new(),
+            number,
+            sqlstate: None,
+            position: None,
+        }
+    }
+
+    #[test]
+    fn test_lost_reason() {
+        assert_eq!(lost_reason(&server(1045)), LostReason::AuthExpired);
+        assert_eq!(lost_reason(&server(1862)), LostReason::AuthExpired);
+        assert_eq!(lost_reason(&server(2013)), LostReason::ServerGone);
+        assert_eq!(
+            lost_reason(&DatabaseError::Connection("broken pipe".to_string())),
+            LostReason::ServerGone
+        );
+        assert_eq!(
+            lost_reason(&DatabaseError::Timeout("ping".to_string())),
+            LostReason::Timeout
+        );
+        assert_eq!(lost_reason(&server(1064)), LostReason::Other);
+    }
+}
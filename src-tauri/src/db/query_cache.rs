@@ -0,0 +1,203 @@
+//! Results of read-only queries kept for a short while, so switching back to
+//! a tab shows its result without running an expensive query again. Entries
+//! expire after a TTL and are dropped when a connection changes data.
+
+use crate::db::sql;
+use crate::models::{QueryRequest, QueryResult};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Number of query results kept for re-runs
+pub const DEFAULT_QUERY_CACHE_CAPACITY: usize = 20;
+
+/// How long a cached result is served before the query runs again
+pub const DEFAULT_QUERY_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// What makes two runs the same query: where it runs, its normalized text,
+/// its parameters and the page asked for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    connection_id: String,
+    database: Option<String>,
+    sql: String,
+    params: String,
+    page: Option<(u32, u32)>,
+}
+
+impl CacheKey {
+    fn new(request: &QueryRequest) -> Self {
+        Self {
+            connection_id: request.connection_id.clone(),
+            database: request.database.clone(),
+            sql: sql::fingerprint(&request.sql),
+            params: serde_json::Value::from(request.params.clone()).to_string(),
+            page: request.page.zip(request.page_size),
+        }
+    }
+}
+
+struct CacheEntry {
+    result: QueryResult,
+    stored: Instant,
+}
+
+/// Bounded store of recent query results, evicting the oldest first
+pub struct QueryCache {
+    entries: HashMap<CacheKey, CacheEntry>,
+    order: VecDeque<CacheKey>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl Default for QueryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUERY_CACHE_CAPACITY, DEFAULT_QUERY_CACHE_TTL)
+    }
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+            ttl,
+        }
+    }
+
+    /// The cached result of the request, unless it has expired
+    pub fn get(&mut self, request: &QueryRequest) -> Option<QueryResult> {
+        let key = CacheKey::new(request);
+        let entry = self.entries.get(&key)?;
+        if entry.stored.elapsed() < self.ttl {
+            return Some(entry.result.clone());
+        }
+        self.remove(&key);
+        None
+    }
+
+    /// Remember the result of a request, marked as cached
+    pub fn insert(&mut self, request: &QueryRequest, result: &QueryResult, cached_at: i64) {
+        let key = CacheKey::new(request);
+        self.remove(&key);
+
+        while self.order.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+
+        let mut result = result.clone();
+        result.cached = true;
+        result.cached_at = Some(cached_at);
+        result.result_id = None;
+        self.order.push_back(key.clone());
+        self.entries.insert(
+            key,
+            CacheEntry {
+                result,
+                stored: Instant::now(),
+            },
+        );
+    }
+
+    /// Forget every result of a connection, e.g. after it changed data
+    pub fn invalidate(&mut self, connection_id: &str) {
+        self.order.retain(|key| key.connection_id != connection_id);
+        self.entries
+            .retain(|key, _| key.connection_id != connection_id);
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if self.entries.remove(key).is_some() {
+            self.order.retain(|cached| cached != key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(connection_id: &str, sql: &str) -> QueryRequest {
+        QueryRequest {
+            connection_id: connection_id.to_string(),
+            sql: sql.to_string(),
+            database: Some("shop".to_string()),
+            page: None,
+            page_size: None,
+            params: vec![],
+            session_id: None,
+            force_refresh: false,
+            confirmed: false,
+            confirmation: None,
+            request_id: None,
+        }
+    }
+
+    fn result(value: i64) -> QueryResult {
+        QueryResult {
+            columns: vec!["n".to_string()],
+            rows: vec![vec![json!(value)]],
+            total_rows: 1,
+            execution_time_ms: 120,
+            result_id: Some("result".to_string()),
+            truncated: false,
+            page_size: None,
+            rows_affected: 0,
+            column_types: vec![],
+            cached: false,
+            cached_at: None,
+            auto_limit: None,
+            unbounded: false,
+            has_more: false,
+            dml: false,
+            last_insert_id: None,
+            warnings: vec![],
+            more_results: vec![],
+            spilled: None,
+        }
+    }
+
+    #[test]
+    fn test_hit_ignores_whitespace() {
+        let mut cache = QueryCache::default();
+        cache.insert(&request("a", "SELECT  1;"), &result(1), 1_000);
+
+        let hit = cache.get(&request("a", "SELECT 1")).unwrap();
+        assert!(hit.cached);
+        assert_eq!(hit.cached_at, Some(1_000));
+        assert_eq!(hit.result_id, None);
+        assert!(cache.get(&request("b", "SELECT 1")).is_none());
+
+        let mut other_params = request("a", "SELECT 1");
+        other_params.params = vec![json!(5)];
+        assert!(cache.get(&other_params).is_none());
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let mut cache = QueryCache::new(10, Duration::ZERO);
+        cache.insert(&request("a", "SELECT 1"), &result(1), 0);
+        assert!(cache.get(&request("a", "SELECT 1")).is_none());
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn test_evicts_oldest_and_invalidates_connection() {
+        let mut cache = QueryCache::new(2, DEFAULT_QUERY_CACHE_TTL);
+        cache.insert(&request("a", "SELECT 1"), &result(1), 0);
+        cache.insert(&request("a", "SELECT 2"), &result(2), 0);
+        cache.insert(&request("b", "SELECT 3"), &result(3), 0);
+        assert!(cache.get(&request("a", "SELECT 1")).is_none());
+        assert!(cache.get(&request("a", "SELECT 2")).is_some());
+
+        cache.invalidate("a");
+        assert!(cache.get(&request("a", "SELECT 2")).is_none());
+        assert!(cache.get(&request("b", "SELECT 3")).is_some());
+    }
+}
@@ -0,0 +1,156 @@
+//! Where your own time goes, from the executions recorded in query history:
+//! the slowest and most frequent statements, how often they fail, and the
+//! time spent per table. Nothing here needs access to the server's logs.
+
+use crate::db::completion;
+use crate::models::{QueryStat, QueryStats, TableTime};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Statements listed as slowest and as most frequent
+pub const TOP_QUERIES: usize = 20;
+
+/// Rank the per-fingerprint totals of a connection's history, keeping the
+/// `top` statements of each list
+pub fn summarize(queries: Vec<QueryStat>, top: usize) -> QueryStats {
+    let executions: u64 = queries.iter().map(|query| query.executions).sum();
+    let failures: u64 = queries.iter().map(|query| query.failures).sum();
+    let total_time_ms = queries.iter().map(|query| query.total_time_ms).sum();
+
+    let mut tables: HashMap<(Option<String>, String), TableTime> = HashMap::new();
+    for query in &queries {
+        let mut seen = Vec::new();
+        for table in completion::referenced_tables(&query.fingerprint) {
+            let key = (
+                table.database.or_else(|| query.database.clone()),
+                table.table,
+            );
+            // A self-join is still one statement against the table
+            if seen.contains(&key) {
+                continue;
+            }
+            let time = tables.entry(key.clone()).or_insert_with(|| TableTime {
+                database: key.0.clone(),
+                table: key.1.clone(),
+                executions: 0,
+                total_time_ms: 0,
+            });
+            time.executions += query.executions;
+            time.total_time_ms += query.total_time_ms;
+            seen.push(key);
+        }
+    }
+    let mut time_by_table: Vec<TableTime> = tables.into_values().collect();
+    time_by_table.sort_by(|a, b| {
+        b.total_time_ms
+            .cmp(&a.total_time_ms)
+            .then_with(|| a.table.cmp(&b.table))
+    });
+
+    let mut slowest = queries.clone();
+    slowest.sort_by_key(|query| (Reverse(query.average_time_ms), Reverse(query.max_time_ms)));
+    slowest.truncate(top);
+
+    let mut most_frequent = queries;
+    most_frequent.sort_by_key(|query| (Reverse(query.executions), Reverse(query.total_time_ms)));
+    most_frequent.truncate(top);
+
+    QueryStats {
+        executions,
+        failures,
+        error_rate: if executions == 0 {
+            0.0
+        } else {
+            failures as f64 / executions as f64
+        },
+        total_time_ms,
+        slowest,
+        most_frequent,
+        time_by_table,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(fingerprint: &str, executions: u64, failures: u64, total_time_ms: u64) -> QueryStat {
+        QueryStat {
+            fingerprint: fingerprint.to_string(),
+            database: Some("shop".to_string()),
+            executions,
+            failures,
+            total_time_ms,
+            average_time_ms: total_time_ms / executions,
+            max_time_ms: total_time_ms,
+            last_executed_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_rankings_and_error_rate() {
+        let stats = summarize(
+            vec![
+                stat("SELECT * FROM users WHERE id = ?", 90, 0, 900),
+                stat("SELECT COUNT(*) FROM orders", 2, 1, 4000),
+                stat("DELETE FROM sessions", 8, 3, 160),
+            ],
+            2,
+        );
+
+        assert_eq!(stats.executions, 100);
+        assert_eq!(stats.failures, 4);
+        assert_eq!(stats.error_rate, 0.04);
+        assert_eq!(stats.total_time_ms, 5060);
+        let fingerprints = |queries: &[QueryStat]| -> Vec<String> {
+            queries.iter().map(|q| q.fingerprint.clone()).collect()
+        };
+        assert_eq!(
+            fingerprints(&stats.slowest),
+            vec!["SELECT COUNT(*) FROM orders", "DELETE FROM sessions"]
+        );
+        assert_eq!(
+            fingerprints(&stats.most_frequent),
+            vec!["SELECT * FROM users WHERE id = ?", "DELETE FROM sessions"]
+        );
+    }
+
+    #[test]
+    fn test_time_by_table() {
+        let stats = summarize(
+            vec![
+                stat(
+                    "SELECT * FROM orders o JOIN users u ON u.id = o.user_id",
+                    10,
+                    0,
+                    500,
+                ),
+                stat("UPDATE users SET name = ? WHERE id = ?", 5, 0, 50),
+                stat(
+                    "SELECT * FROM archive.orders a JOIN archive.orders b",
+                    1,
+                    0,
+                    7,
+                ),
+            ],
+            10,
+        );
+
+        let tables: Vec<(Option<&str>, &str, u64, u64)> = stats
+            .time_by_table
+            .iter()
+            .map(|t| {
+                let database = t.database.as_deref();
+                (database, t.table.as_str(), t.executions, t.total_time_ms)
+            })
+            .collect();
+        assert_eq!(
+            tables,
+            vec![
+                (Some("shop"), "users", 15, 550),
+                (Some("shop"), "orders", 10, 500),
+                (Some("archive"), "orders", 1, 7),
+            ]
+        );
+    }
+}
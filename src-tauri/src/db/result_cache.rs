@@ -0,0 +1,896 @@
+use crate::db::masking;
+use crate::db::result_filter::{self, Predicate};
+use crate::db::spill::{SpillError, SpilledRows, SPILL_BATCH_SIZE};
+use crate::models::{
+    ColumnTypeInfo, GroupedResult, QueryResult, ResultGroup, ResultSet, ResultViewRequest,
+    SortOrder,
+};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+use thiserror::Error;
+
+/// Number of query results kept in memory for post-processing
+pub const DEFAULT_RESULT_CACHE_CAPACITY: usize = 20;
+
+/// Results with more rows than this are sent to the UI one page at a time
+pub const IPC_PAGE_SIZE: usize = 1000;
+
+#[derive(Error, Debug)]
+pub enum ResultError {
+    #[error("Result not found: {0}")]
+    NotFound(String),
+
+    #[error("Unknown column: {0}")]
+    UnknownColumn(String),
+
+    /// The result outgrew its memory budget, so only its pages can be read
+    #[error("Result is too large to {0}; rerun the query with a narrower selection")]
+    Spilled(&'static str),
+
+    #[error(transparent)]
+    Spill(#[from] SpillError),
+
+    #[error("Column {0} is masked")]
+    Masked(String),
+
+    #[error("{0}")]
+    InvalidFilter(String),
+}
+
+pub type Result<T> = std::result::Result<T, ResultError>;
+
+/// A fetched result set kept around so it can be re-shaped without re-querying.
+/// Values are stored column by column so sorting and filtering only touch the
+/// columns involved. Values of columns flagged as masked are kept whole but
+/// only handed out masked, except by `reveal`.
+///
+/// A sort and filter set with `sort` and `filter` reorder the rows every
+/// page is read from, spilled ones included; `view` applies its own to a
+/// single page.
+#[derive(Debug, Clone)]
+pub struct CachedResult {
+    pub columns: Vec<String>,
+    column_types: Vec<ColumnTypeInfo>,
+    data: Vec<Vec<Value>>,
+    row_count: usize,
+    masked: Vec<bool>,
+    /// Rows past the ones in `data`, kept on disk
+    spill: Option<SpilledRows>,
+    sort: Vec<(usize, SortOrder)>,
+    filter: Option<Predicate>,
+    /// Fetched positions of the rows left by `sort` and `filter`, in order
+    arranged: Option<Vec<usize>>,
+}
+
+impl CachedResult {
+    fn from_result(result: &QueryResult) -> Self {
+        Self {
+            spill: result.spilled.clone(),
+            ..Self::new(&result.columns, &result.column_types, &result.rows)
+        }
+    }
+
+    fn new(columns: &[String], column_types: &[ColumnTypeInfo], rows: &[Vec<Value>]) -> Self {
+        let mut data: Vec<Vec<Value>> = columns
+            .iter()
+            .map(|_| Vec::with_capacity(rows.len()))
+            .collect();
+        for row in rows {
+            for (column, value) in data.iter_mut().zip(row) {
+                column.push(value.clone());
+            }
+        }
+
+        Self {
+            columns: columns.to_vec(),
+            column_types: column_types.to_vec(),
+            data,
+            row_count: rows.len(),
+            masked: masking::masked_flags(column_types),
+            spill: None,
+            sort: vec![],
+            filter: None,
+            arranged: None,
+        }
+    }
+
+    pub fn column_types(&self) -> &[ColumnTypeInfo] {
+        &self.column_types
+    }
+
+    /// Every value of the column at `index`
+    pub fn column_values(&self, index: usize) -> &[Value] {
+        &self.data[index]
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// Rows in memory and on disk
+    pub fn total_rows(&self) -> usize {
+        self.row_count + self.spill.as_ref().map_or(0, SpilledRows::len)
+    }
+
+    /// Rows left by the filter, or every row
+    pub fn visible_rows(&self) -> usize {
+        self.arranged
+            .as_ref()
+            .map_or_else(|| self.total_rows(), Vec::len)
+    }
+
+    /// Order rows by `column`, or restore the fetched order with None. Rows
+    /// that compare equal keep their fetched order.
+    pub fn sort(&mut self, column: Option<&str>, order: SortOrder) -> Result<()> {
+        self.sort = match column {
+            Some(column) => vec![(self.unmasked_column_index(column)?, order)],
+            None => vec![],
+        };
+        self.arranged = self.arrange(&self.sort, self.filter.as_ref())?;
+        Ok(())
+    }
+
+    /// Keep only the rows matching a SQL expression such as
+    /// `status = 'paid' AND total > 100`; a blank one shows every row again
+    pub fn filter(&mut self, expression: &str) -> Result<()> {
+        self.filter = self.parse_filter(expression)?;
+        self.arranged = self.arrange(&self.sort, self.filter.as_ref())?;
+        Ok(())
+    }
+
+    /// The predicate of a filter expression, or None for a blank one
+    fn parse_filter(&self, expression: &str) -> Result<Option<Predicate>> {
+        if expression.trim().is_empty() {
+            return Ok(None);
+        }
+        let column = |name: &str| {
+            self.columns
+                .iter()
+                .position(|c| c.eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("Unknown column: {}", name))
+                .and_then(|index| match self.is_masked(index) {
+                    true => Err(format!("Column {} is masked", self.columns[index])),
+                    false => Ok(index),
+                })
+        };
+        result_filter::parse(expression, &column)
+            .map(Some)
+            .map_err(ResultError::InvalidFilter)
+    }
+
+    /// Fetched positions of the rows `filter` keeps, in `sort` order, or
+    /// None when there's neither. Spilled rows are read from disk a batch
+    /// at a time, keeping only their sort values.
+    fn arrange(
+        &self,
+        sort: &[(usize, SortOrder)],
+        filter: Option<&Predicate>,
+    ) -> Result<Option<Vec<usize>>> {
+        if sort.is_empty() && filter.is_none() {
+            return Ok(None);
+        }
+
+        let mut keyed: Vec<(usize, Vec<Value>)> = Vec::new();
+        for row in 0..self.row_count {
+            if let Some(key) = sort_key(sort, filter, &|column| &self.data[column][row]) {
+                keyed.push((row, key));
+            }
+        }
+        if let Some(spill) = &self.spill {
+            let mut offset = 0;
+            loop {
+                let rows = spill.read(offset, SPILL_BATCH_SIZE)?;
+                for (i, row) in rows.iter().enumerate() {
+                    if let Some(key) = sort_key(sort, filter, &|column| &row[column]) {
+                        keyed.push((self.row_count + offset + i, key));
+                    }
+                }
+                if rows.len() < SPILL_BATCH_SIZE {
+                    break;
+                }
+                offset += rows.len();
+            }
+        }
+
+        if !sort.is_empty() {
+            // Stable, so rows that compare equal keep their fetched order;
+            // later columns break ties of earlier ones
+            keyed.sort_by(|(_, a), (_, b)| {
+                sort.iter()
+                    .zip(a.iter().zip(b))
+                    .map(|((_, order), (a, b))| match order {
+                        SortOrder::Asc => result_filter::sort_order(a, b),
+                        SortOrder::Desc => result_filter::sort_order(b, a),
+                    })
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+        Ok(Some(
+            keyed.into_iter().map(|(position, _)| position).collect(),
+        ))
+    }
+
+    /// Fetched position of the row shown at `row`
+    fn position(&self, row: usize) -> Option<usize> {
+        match &self.arranged {
+            Some(arranged) => arranged.get(row).copied(),
+            None => Some(row),
+        }
+    }
+
+    /// Fail when some rows are on disk, for operations that need them all
+    /// in memory
+    fn ensure_in_memory(&self, action: &'static str) -> Result<()> {
+        match self.spill {
+            Some(_) => Err(ResultError::Spilled(action)),
+            None => Ok(()),
+        }
+    }
+
+    /// The given columns, in that order, of the first `row_limit` rows; every
+    /// column when `columns` is empty
+    pub fn project(&self, columns: &[String], row_limit: Option<usize>) -> Result<CachedResult> {
+        self.ensure_in_memory("export")?;
+        let indexes = if columns.is_empty() {
+            (0..self.columns.len()).collect()
+        } else {
+            columns
+                .iter()
+                .map(|column| self.column_index(column))
+                .collect::<Result<Vec<_>>>()?
+        };
+        let row_count = row_limit.map_or(self.row_count, |limit| limit.min(self.row_count));
+
+        Ok(Self {
+            columns: indexes.iter().map(|&i| self.columns[i].clone()).collect(),
+            column_types: indexes
+                .iter()
+                .filter_map(|&i| self.column_types.get(i).cloned())
+                .collect(),
+            data: indexes
+                .iter()
+                .map(|&i| {
+                    let mut values = self.data[i][..row_count].to_vec();
+                    if self.is_masked(i) {
+                        values.iter_mut().for_each(masking::mask_value);
+                    }
+                    values
+                })
+                .collect(),
+            row_count,
+            // Already masked
+            masked: vec![],
+            spill: None,
+            sort: vec![],
+            filter: None,
+            arranged: None,
+        })
+    }
+
+    fn is_masked(&self, index: usize) -> bool {
+        self.masked.get(index).copied().unwrap_or_default()
+    }
+
+    fn row(&self, index: usize) -> Vec<Value> {
+        let mut row: Vec<Value> = self
+            .data
+            .iter()
+            .map(|column| column[index].clone())
+            .collect();
+        masking::mask_row(&mut row, &self.masked);
+        row
+    }
+
+    fn column_index(&self, column: &str) -> Result<usize> {
+        self.columns
+            .iter()
+            .position(|c| c == column)
+            .ok_or_else(|| ResultError::UnknownColumn(column.to_string()))
+    }
+
+    /// Index of a column rows are filtered, sorted or grouped by; masked
+    /// ones can't be, since the outcome would give their values away
+    fn unmasked_column_index(&self, column: &str) -> Result<usize> {
+        let index = self.column_index(column)?;
+        if self.is_masked(index) {
+            return Err(ResultError::Masked(column.to_string()));
+        }
+        Ok(index)
+    }
+
+    /// A value in full, masked or not
+    pub fn reveal(&self, row: usize, column: &str) -> Result<Value> {
+        let index = self.column_index(column)?;
+        let not_found = || ResultError::NotFound(format!("Row {}", row));
+        let row = self.position(row).ok_or_else(not_found)?;
+        if row < self.row_count {
+            return Ok(self.data[index][row].clone());
+        }
+        self.spill
+            .as_ref()
+            .map(|spill| spill.read(row - self.row_count, 1))
+            .transpose()?
+            .and_then(|rows| rows.into_iter().next())
+            .and_then(|mut values| values.get_mut(index).map(Value::take))
+            .ok_or_else(not_found)
+    }
+
+    /// Group rows by the given columns, most frequent combinations first
+    pub fn group_by(&self, columns: &[String]) -> Result<GroupedResult> {
+        self.ensure_in_memory("group")?;
+        let indexes = columns
+            .iter()
+            .map(|c| self.unmasked_column_index(c))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut groups: Vec<ResultGroup> = Vec::new();
+
+        for row in 0..self.row_count {
+            let values: Vec<Value> = indexes.iter().map(|&i| self.data[i][row].clone()).collect();
+            let key = serde_json::Value::Array(values.clone()).to_string();
+
+            match positions.get(&key) {
+                Some(&position) => groups[position].count += 1,
+                None => {
+                    positions.insert(key, groups.len());
+                    groups.push(ResultGroup { values, count: 1 });
+                }
+            }
+        }
+
+        // Stable sort keeps first-seen order among equal counts
+        groups.sort_by_key(|g| std::cmp::Reverse(g.count));
+
+        Ok(GroupedResult {
+            columns: columns.to_vec(),
+            groups,
+            total_rows: self.row_count,
+        })
+    }
+
+    /// One page of the rows, sorted and filtered if set, read from disk past
+    /// the rows in memory; pages are numbered from zero
+    pub fn page(&self, result_id: &str, page: u32, page_size: usize) -> Result<QueryResult> {
+        let total_rows = self.visible_rows();
+        let start = (page as usize).saturating_mul(page_size).min(total_rows);
+        let end = start.saturating_add(page_size).min(total_rows);
+        let rows = self.rows_between(self.arranged.as_deref(), start, end)?;
+        Ok(self.rows_result(result_id, rows, total_rows, Some(page_size)))
+    }
+
+    /// Rows `start..end` of an arrangement, or of the fetched order, masked
+    fn rows_between(
+        &self,
+        arranged: Option<&[usize]>,
+        start: usize,
+        end: usize,
+    ) -> Result<Vec<Vec<Value>>> {
+        if let Some(arranged) = arranged {
+            return self.rows_at(&arranged[start..end]);
+        }
+        let mut rows: Vec<Vec<Value>> = (start..end.min(self.row_count))
+            .map(|row| self.row(row))
+            .collect();
+        if let Some(spill) = self.spill.as_ref().filter(|_| end > self.row_count) {
+            let offset = start.max(self.row_count) - self.row_count;
+            for mut row in spill.read(offset, end - start - rows.len())? {
+                masking::mask_row(&mut row, &self.masked);
+                rows.push(row);
+            }
+        }
+        Ok(rows)
+    }
+
+    fn rows_result(
+        &self,
+        result_id: &str,
+        rows: Vec<Vec<Value>>,
+        total_rows: usize,
+        page_size: Option<usize>,
+    ) -> QueryResult {
+        QueryResult {
+            columns: self.columns.clone(),
+            rows,
+            total_rows,
+            execution_time_ms: 0,
+            result_id: Some(result_id.to_string()),
+            truncated: false,
+            page_size,
+            rows_affected: 0,
+            column_types: self.column_types.clone(),
+            cached: false,
+            cached_at: None,
+            auto_limit: None,
+            unbounded: false,
+            has_more: false,
+            dml: false,
+            last_insert_id: None,
+            warnings: vec![],
+            more_results: vec![],
+            spilled: None,
+        }
+    }
+
+    /// The rows at the given fetched positions, masked
+    fn rows_at(&self, positions: &[usize]) -> Result<Vec<Vec<Value>>> {
+        let spilled: Vec<usize> = positions
+            .iter()
+            .filter(|&&position| position >= self.row_count)
+            .map(|position| position - self.row_count)
+            .collect();
+        let mut spilled = match (&self.spill, spilled.is_empty()) {
+            (Some(spill), false) => spill.read_positions(&spilled)?.into_iter(),
+            _ => Vec::new().into_iter(),
+        };
+
+        positions
+            .iter()
+            .map(|&position| {
+                if position < self.row_count {
+                    return Ok(self.row(position));
+                }
+                let mut row = spilled
+                    .next()
+                    .ok_or_else(|| ResultError::NotFound(format!("Row {}", position)))?;
+                masking::mask_row(&mut row, &self.masked);
+                Ok(row)
+            })
+            .collect()
+    }
+
+    /// Filter and sort the cached rows, spilled ones included, returning one
+    /// page of the outcome. Unlike `sort` and `filter`, this leaves the
+    /// result's own arrangement alone. `total_rows` of the returned result
+    /// counts every matching row.
+    pub fn view(&self, request: &ResultViewRequest) -> Result<QueryResult> {
+        let start = Instant::now();
+
+        let sort = request
+            .sort
+            .iter()
+            .map(|s| Ok((self.unmasked_column_index(&s.column)?, s.order)))
+            .collect::<Result<Vec<_>>>()?;
+        let filters = request
+            .filters
+            .iter()
+            .map(|f| {
+                let column = self.unmasked_column_index(&f.column)?;
+                Ok(result_filter::from_table_filter(f, column))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let expression = self.parse_filter(request.expression.as_deref().unwrap_or_default())?;
+        let filter = filters
+            .into_iter()
+            .chain(expression)
+            .reduce(|a, b| Predicate::And(Box::new(a), Box::new(b)));
+
+        let arranged = self.arrange(&sort, filter.as_ref())?;
+        let total_rows = arranged
+            .as_ref()
+            .map_or_else(|| self.total_rows(), Vec::len);
+        let first = request.offset.min(total_rows);
+        let last = first.saturating_add(request.limit).min(total_rows);
+        let rows = self.rows_between(arranged.as_deref(), first, last)?;
+
+        let mut result = self.rows_result(&request.result_id, rows, total_rows, None);
+        result.execution_time_ms = start.elapsed().as_millis() as u64;
+        Ok(result)
+    }
+}
+
+/// The values a row is sorted by, or None when `filter` drops it
+fn sort_key<'a>(
+    sort: &[(usize, SortOrder)],
+    filter: Option<&'a Predicate>,
+    value: &impl Fn(usize) -> &'a Value,
+) -> Option<Vec<Value>> {
+    if filter.is_some_and(|filter| !filter.matches(value)) {
+        return None;
+    }
+    Some(
+        sort.iter()
+            .map(|(column, _)| value(*column).clone())
+            .collect(),
+    )
+}
+
+/// Bounded store of recent results, evicting the oldest first
+pub struct ResultCache {
+    entries: HashMap<String, CachedResult>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Cache a copy of the result's rows and return the id it is stored under
+    pub fn insert(&mut self, result: &QueryResult) -> String {
+        self.store(CachedResult::from_result(result))
+    }
+
+    /// Cache a copy of one of several result sets, like `insert`
+    pub fn insert_set(&mut self, set: &ResultSet) -> String {
+        self.store(CachedResult::new(
+            &set.columns,
+            &set.column_types,
+            &set.rows,
+        ))
+    }
+
+    fn store(&mut self, result: CachedResult) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        while self.order.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+
+        self.entries.insert(id.clone(), result);
+        self.order.push_back(id.clone());
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Result<&CachedResult> {
+        self.entries
+            .get(id)
+            .ok_or_else(|| ResultError::NotFound(id.to_string()))
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Result<&mut CachedResult> {
+        self.entries
+            .get_mut(id)
+            .ok_or_else(|| ResultError::NotFound(id.to_string()))
+    }
+
+    /// Drop a cached result, returning whether it was still cached
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.order.retain(|cached| cached != id);
+        self.entries.remove(id).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::spill::SpillFile;
+    use crate::models::query::ResultSort;
+    use crate::models::{FilterOperator, TableFilter};
+    use serde_json::json;
+
+    fn sample_result() -> QueryResult {
+        QueryResult {
+            columns: vec!["id".to_string(), "status".to_string()],
+            rows: vec![
+                vec![json!(1), json!("active")],
+                vec![json!(2), json!("inactive")],
+                vec![json!(3), json!("active")],
+                vec![json!(4), json!(null)],
+            ],
+            total_rows: 4,
+            execution_time_ms: 1,
+            result_id: None,
+            truncated: false,
+            page_size: None,
+            rows_affected: 0,
+            column_types: vec![],
+            cached: false,
+            cached_at: None,
+            auto_limit: None,
+            unbounded: false,
+            has_more: false,
+            dml: false,
+            last_insert_id: None,
+            warnings: vec![],
+            more_results: vec![],
+            spilled: None,
+        }
+    }
+
+    #[test]
+    fn test_group_by_counts_values() {
+        let mut cache = ResultCache::new(DEFAULT_RESULT_CACHE_CAPACITY);
+        let id = cache.insert(&sample_result());
+
+        let grouped = cache
+            .get(&id)
+            .unwrap()
+            .group_by(&["status".to_string()])
+            .unwrap();
+
+        assert_eq!(grouped.total_rows, 4);
+        assert_eq!(grouped.groups.len(), 3);
+        assert_eq!(grouped.groups[0].values, vec![json!("active")]);
+        assert_eq!(grouped.groups[0].count, 2);
+    }
+
+    #[test]
+    fn test_group_by_unknown_column() {
+        let mut cache = ResultCache::new(DEFAULT_RESULT_CACHE_CAPACITY);
+        let id = cache.insert(&sample_result());
+
+        let result = cache.get(&id).unwrap().group_by(&["missing".to_string()]);
+        assert!(matches!(result, Err(ResultError::UnknownColumn(_))));
+    }
+
+    fn view_request(filters: Vec<TableFilter>, sort: Vec<ResultSort>) -> ResultViewRequest {
+        ResultViewRequest {
+            result_id: "r".to_string(),
+            filters,
+            sort,
+            expression: None,
+            offset: 0,
+            limit: 100,
+        }
+    }
+
+    #[test]
+    fn test_view_filters_and_sorts() {
+        let cached = CachedResult::from_result(&sample_result());
+        let request = view_request(
+            vec![TableFilter {
+                column: "status".to_string(),
+                operator: FilterOperator::Like,
+                value: "%ACTIVE".to_string(),
+            }],
+            vec![ResultSort {
+                column: "id".to_string(),
+                order: SortOrder::Desc,
+            }],
+        );
+
+        let view = cached.view(&request).unwrap();
+        assert_eq!(view.total_rows, 3);
+        let ids: Vec<Value> = view.rows.iter().map(|r| r[0].clone()).collect();
+        assert_eq!(ids, vec![json!(3), json!(2), json!(1)]);
+    }
+
+    #[test]
+    fn test_view_numeric_comparison_and_paging() {
+        let cached = CachedResult::from_result(&sample_result());
+        let mut request = view_request(
+            vec![TableFilter {
+                column: "id".to_string(),
+                operator: FilterOperator::GreaterThanOrEqual,
+                value: "2".to_string(),
+            }],
+            vec![],
+        );
+        request.offset = 1;
+        request.limit = 1;
+
+        let view = cached.view(&request).unwrap();
+        assert_eq!(view.total_rows, 3);
+        assert_eq!(view.rows, vec![vec![json!(3), json!("active")]]);
+    }
+
+    #[test]
+    fn test_sort_puts_nulls_first() {
+        let cached = CachedResult::from_result(&sample_result());
+        let request = view_request(
+            vec![],
+            vec![ResultSort {
+                column: "status".to_string(),
+                order: SortOrder::Asc,
+            }],
+        );
+
+        let view = cached.view(&request).unwrap();
+        assert_eq!(view.rows[0][1], json!(null));
+        assert_eq!(view.rows[3][1], json!("inactive"));
+    }
+
+    #[test]
+    fn test_page_splits_rows() {
+        let cached = CachedResult::from_result(&sample_result());
+
+        let first = cached.page("r", 0, 3).unwrap();
+        assert_eq!(first.rows.len(), 3);
+        assert_eq!(first.total_rows, 4);
+        assert_eq!(first.result_id.as_deref(), Some("r"));
+
+        let last = cached.page("r", 1, 3).unwrap();
+        assert_eq!(last.rows, vec![vec![json!(4), json!(null)]]);
+        assert!(cached.page("r", 5, 3).unwrap().rows.is_empty());
+    }
+
+    #[test]
+    fn test_page_reads_spilled_rows() {
+        let mut file = SpillFile::create().unwrap();
+        let spilled: Vec<Vec<Value>> = (5..9)
+            .map(|id| vec![json!(id), json!("archived")])
+            .collect();
+        file.append(&spilled).unwrap();
+        let mut result = sample_result();
+        result.spilled = Some(SpilledRows::new(file));
+        let cached = CachedResult::from_result(&result);
+        assert_eq!(cached.total_rows(), 8);
+
+        // The second page starts in memory and ends on disk
+        let page = cached.page("r", 1, 3).unwrap();
+        let ids: Vec<Value> = page.rows.iter().map(|r| r[0].clone()).collect();
+        assert_eq!(ids, vec![json!(4), json!(5), json!(6)]);
+        assert_eq!(page.total_rows, 8);
+        assert_eq!(cached.page("r", 2, 3).unwrap().rows, spilled[2..].to_vec());
+
+        assert!(matches!(
+            cached.group_by(&["status".to_string()]),
+            Err(ResultError::Spilled(_))
+        ));
+    }
+
+    #[test]
+    fn test_sort_and_filter_reach_spilled_rows() {
+        let mut file = SpillFile::create().unwrap();
+        file.append(&[
+            vec![json!(7), json!("active")],
+            vec![json!(0), json!("active")],
+        ])
+        .unwrap();
+        let mut result = sample_result();
+        result.spilled = Some(SpilledRows::new(file));
+        let mut cached = CachedResult::from_result(&result);
+        let ids = |cached: &CachedResult| -> Vec<Value> {
+            let page = cached.page("r", 0, 10).unwrap();
+            page.rows.iter().map(|r| r[0].clone()).collect()
+        };
+
+        cached.sort(Some("id"), SortOrder::Desc).unwrap();
+        assert_eq!(
+            ids(&cached),
+            vec![json!(7), json!(4), json!(3), json!(2), json!(1), json!(0)]
+        );
+
+        // Sort and filter compose, and the page counts only matching rows
+        cached.filter("status = 'active' AND id > 0").unwrap();
+        assert_eq!(ids(&cached), vec![json!(7), json!(3), json!(1)]);
+        assert_eq!(cached.page("r", 0, 10).unwrap().total_rows, 3);
+        assert_eq!(cached.reveal(0, "status").unwrap(), json!("active"));
+
+        cached.sort(None, SortOrder::Asc).unwrap();
+        assert_eq!(ids(&cached), vec![json!(1), json!(3), json!(7)]);
+        cached.filter(" ").unwrap();
+        assert_eq!(cached.visible_rows(), 6);
+        assert!(cached.arranged.is_none());
+
+        assert!(matches!(
+            cached.filter("missing = 1"),
+            Err(ResultError::InvalidFilter(_))
+        ));
+    }
+
+    #[test]
+    fn test_view_reads_spilled_rows() {
+        let mut file = SpillFile::create().unwrap();
+        file.append(&[
+            vec![json!(7), json!("active")],
+            vec![json!(0), json!("archived")],
+        ])
+        .unwrap();
+        let mut result = sample_result();
+        result.spilled = Some(SpilledRows::new(file));
+        let mut cached = CachedResult::from_result(&result);
+        cached.filter("id > 2").unwrap();
+
+        let mut request = view_request(
+            vec![TableFilter {
+                column: "status".to_string(),
+                operator: FilterOperator::IsNotNull,
+                value: String::new(),
+            }],
+            vec![
+                ResultSort {
+                    column: "status".to_string(),
+                    order: SortOrder::Asc,
+                },
+                ResultSort {
+                    column: "id".to_string(),
+                    order: SortOrder::Desc,
+                },
+            ],
+        );
+        request.expression = Some("id < 7 OR status = 'active'".to_string());
+        let view = cached.view(&request).unwrap();
+        let ids: Vec<Value> = view.rows.iter().map(|r| r[0].clone()).collect();
+        assert_eq!(ids, vec![json!(7), json!(3), json!(1), json!(0), json!(2)]);
+        assert_eq!(view.total_rows, 5);
+
+        // The result's own filter is left as it was
+        assert_eq!(cached.visible_rows(), 3);
+
+        request.expression = Some("missing = 1".to_string());
+        assert!(matches!(
+            cached.view(&request),
+            Err(ResultError::InvalidFilter(_))
+        ));
+    }
+
+    #[test]
+    fn test_project_selects_columns_and_rows() {
+        let mut cache = ResultCache::new(1);
+        let id = cache.insert(&sample_result());
+        let result = cache.get(&id).unwrap();
+
+        let projected = result.project(&["status".to_string()], Some(2)).unwrap();
+        assert_eq!(projected.columns, vec!["status"]);
+        assert_eq!(projected.row_count(), 2);
+        assert_eq!(
+            projected.column_values(0),
+            &[json!("active"), json!("inactive")]
+        );
+
+        assert_eq!(result.project(&[], Some(10)).unwrap().row_count(), 4);
+        assert!(matches!(
+            result.project(&["missing".to_string()], None),
+            Err(ResultError::UnknownColumn(_))
+        ));
+    }
+
+    #[test]
+    fn test_masked_column_is_hidden_until_revealed() {
+        let mut result = sample_result();
+        result.column_types = ["id", "status"]
+            .iter()
+            .map(|name| ColumnTypeInfo {
+                name: name.to_string(),
+                type_name: "TEXT".to_string(),
+                kind: crate::models::ValueKind::Text,
+                truncated: false,
+                masked: *name == "status",
+            })
+            .collect();
+        let cached = CachedResult::from_result(&result);
+
+        let page = cached.page("r", 0, 2).unwrap();
+        assert_eq!(page.rows[0], vec![json!(1), json!(masking::MASKED_VALUE)]);
+        let projected = cached.project(&[], None).unwrap();
+        assert_eq!(projected.column_values(1)[3], json!(null));
+        assert_eq!(projected.column_values(1)[0], json!(masking::MASKED_VALUE));
+        assert!(matches!(
+            cached.group_by(&["status".to_string()]),
+            Err(ResultError::Masked(_))
+        ));
+
+        assert_eq!(cached.reveal(1, "status").unwrap(), json!("inactive"));
+        assert!(matches!(
+            cached.reveal(9, "status"),
+            Err(ResultError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_remove_releases_result() {
+        let mut cache = ResultCache::new(2);
+        let id = cache.insert(&sample_result());
+
+        assert!(cache.remove(&id));
+        assert!(!cache.remove(&id));
+        assert!(cache.get(&id).is_err());
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let mut cache = ResultCache::new(2);
+        let first = cache.insert(&sample_result());
+        let second = cache.insert(&sample_result());
+        let third = cache.insert(&sample_result());
+
+        assert!(matches!(cache.get(&first), Err(ResultError::NotFound(_))));
+        assert!(cache.get(&second).is_ok());
+        assert!(cache.get(&third).is_ok());
+    }
+}
@@ -0,0 +1,434 @@
+//! Filter expressions over the rows of a cached result, such as
+//! `status = 'paid' AND total > 100`. The expression is parsed as SQL and
+//! checked against each row in the backend, without going back to the
+//! server. Like SQL, a comparison with NULL never matches. The filters of
+//! the table grid's filter bar are checked as predicates too.
+
+use crate::models::{FilterOperator, TableFilter};
+use serde_json::Value;
+use sqlparser::ast::{BinaryOperator, Expr, UnaryOperator, Value as SqlValue};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::parser::Parser;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Column(usize),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering == Ordering::Equal,
+            Comparison::NotEq => ordering != Ordering::Equal,
+            Comparison::Lt => ordering == Ordering::Less,
+            Comparison::LtEq => ordering != Ordering::Greater,
+            Comparison::Gt => ordering == Ordering::Greater,
+            Comparison::GtEq => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// A parsed filter expression with its columns resolved to indexes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+    Compare(Operand, Comparison, Operand),
+    IsNull(Operand, bool),
+    Like {
+        operand: Operand,
+        pattern: String,
+        negated: bool,
+    },
+    In {
+        operand: Operand,
+        list: Vec<Operand>,
+        negated: bool,
+    },
+    Between {
+        operand: Operand,
+        low: Operand,
+        high: Operand,
+        negated: bool,
+    },
+}
+
+/// Parse `expression`, resolving column names with `column`, which returns
+/// the index of a column or why it can't be filtered on
+pub fn parse(
+    expression: &str,
+    column: &impl Fn(&str) -> Result<usize, String>,
+) -> Result<Predicate, String> {
+    let expr = Parser::new(&MySqlDialect {})
+        .try_with_sql(expression)
+        .and_then(|mut parser| parser.parse_expr())
+        .map_err(|e| format!("Invalid filter: {}", e))?;
+    predicate(&expr, column)
+}
+
+/// The predicate of one filter-bar filter on the column at `column`. The
+/// value is compared as a number when it reads as one; IN takes a
+/// comma-separated list.
+pub fn from_table_filter(filter: &TableFilter, column: usize) -> Predicate {
+    let operand = Operand::Column(column);
+    let value = || Operand::Literal(Value::String(filter.value.clone()));
+    let compare = |comparison| Predicate::Compare(Operand::Column(column), comparison, value());
+    let list = || {
+        filter
+            .value
+            .split(',')
+            .map(|item| Operand::Literal(Value::String(item.trim().to_string())))
+            .collect()
+    };
+    match filter.operator {
+        FilterOperator::Equals => compare(Comparison::Eq),
+        FilterOperator::NotEquals => compare(Comparison::NotEq),
+        FilterOperator::GreaterThan => compare(Comparison::Gt),
+        FilterOperator::LessThan => compare(Comparison::Lt),
+        FilterOperator::GreaterThanOrEqual => compare(Comparison::GtEq),
+        FilterOperator::LessThanOrEqual => compare(Comparison::LtEq),
+        FilterOperator::Like | FilterOperator::NotLike => Predicate::Like {
+            operand,
+            pattern: filter.value.clone(),
+            negated: filter.operator == FilterOperator::NotLike,
+        },
+        FilterOperator::In | FilterOperator::NotIn => Predicate::In {
+            operand,
+            list: list(),
+            negated: filter.operator == FilterOperator::NotIn,
+        },
+        FilterOperator::IsNull => Predicate::IsNull(operand, false),
+        FilterOperator::IsNotNull => Predicate::IsNull(operand, true),
+    }
+}
+
+fn predicate(
+    expr: &Expr,
+    column: &impl Fn(&str) -> Result<usize, String>,
+) -> Result<Predicate, String> {
+    let operand = |expr: &Expr| operand(expr, column);
+    let boxed = |expr: &Expr| predicate(expr, column).map(Box::new);
+
+    Ok(match expr {
+        Expr::Nested(inner) => predicate(inner, column)?,
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => Predicate::Not(boxed(expr)?),
+        Expr::BinaryOp { left, op, right } => {
+            let comparison = match op {
+                BinaryOperator::And => return Ok(Predicate::And(boxed(left)?, boxed(right)?)),
+                BinaryOperator::Or => return Ok(Predicate::Or(boxed(left)?, boxed(right)?)),
+                BinaryOperator::Eq => Comparison::Eq,
+                BinaryOperator::NotEq => Comparison::NotEq,
+                BinaryOperator::Lt => Comparison::Lt,
+                BinaryOperator::LtEq => Comparison::LtEq,
+                BinaryOperator::Gt => Comparison::Gt,
+                BinaryOperator::GtEq => Comparison::GtEq,
+                other => return Err(format!("Unsupported operator in filter: {}", other)),
+            };
+            Predicate::Compare(operand(left)?, comparison, operand(right)?)
+        }
+        Expr::IsNull(expr) => Predicate::IsNull(operand(expr)?, false),
+        Expr::IsNotNull(expr) => Predicate::IsNull(operand(expr)?, true),
+        Expr::Like {
+            negated,
+            expr,
+            pattern,
+            ..
+        }
+        | Expr::ILike {
+            negated,
+            expr,
+            pattern,
+            ..
+        } => match operand(pattern)? {
+            Operand::Literal(Value::String(pattern)) => Predicate::Like {
+                operand: operand(expr)?,
+                pattern,
+                negated: *negated,
+            },
+            _ => return Err("LIKE needs a quoted pattern".to_string()),
+        },
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => Predicate::In {
+            operand: operand(expr)?,
+            list: list.iter().map(operand).collect::<Result<_, _>>()?,
+            negated: *negated,
+        },
+        Expr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => Predicate::Between {
+            operand: operand(expr)?,
+            low: operand(low)?,
+            high: operand(high)?,
+            negated: *negated,
+        },
+        other => return Err(format!("Unsupported filter: {}", other)),
+    })
+}
+
+fn operand(
+    expr: &Expr,
+    column: &impl Fn(&str) -> Result<usize, String>,
+) -> Result<Operand, String> {
+    Ok(match expr {
+        Expr::Nested(inner) => operand(inner, column)?,
+        Expr::Identifier(ident) => Operand::Column(column(&ident.value)?),
+        // `alias.column`: results only know the column's own name
+        Expr::CompoundIdentifier(parts) => match parts.last() {
+            Some(ident) => Operand::Column(column(&ident.value)?),
+            None => return Err("Empty column name in filter".to_string()),
+        },
+        Expr::Value(value) => Operand::Literal(literal(value)?),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match operand(expr, column)? {
+            Operand::Literal(Value::Number(n)) => {
+                let negated = format!("-{}", n);
+                Operand::Literal(serde_json::from_str(&negated).map_err(|e| e.to_string())?)
+            }
+            _ => return Err("Only numbers can be negated in a filter".to_string()),
+        },
+        other => return Err(format!("Unsupported value in filter: {}", other)),
+    })
+}
+
+fn literal(value: &SqlValue) -> Result<Value, String> {
+    Ok(match value {
+        SqlValue::Number(number, _) => serde_json::from_str(number)
+            .map_err(|_| format!("Invalid number in filter: {}", number))?,
+        SqlValue::SingleQuotedString(text) | SqlValue::DoubleQuotedString(text) => {
+            Value::String(text.clone())
+        }
+        SqlValue::Boolean(flag) => Value::Bool(*flag),
+        SqlValue::Null => Value::Null,
+        other => return Err(format!("Unsupported value in filter: {}", other)),
+    })
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        // DECIMAL columns arrive as text
+        Value::String(s) => s.trim().parse().ok(),
+        Value::Bool(b) => Some(*b as u8 as f64),
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Order two values, numerically when both are numbers; None with a NULL
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    if a.is_null() || b.is_null() {
+        return None;
+    }
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => x.partial_cmp(&y),
+        _ => Some(text(a).cmp(&text(b))),
+    }
+}
+
+/// Order two values for sorting: as filters compare them, with NULLs first
+pub fn sort_order(a: &Value, b: &Value) -> Ordering {
+    match (a.is_null(), b.is_null()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => compare(a, b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Case-insensitive SQL LIKE, where `%` matches any run and `_` one character
+pub fn like(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+
+    let (mut t, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '_' || pattern[p] == text[t]) {
+            t += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '%' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '%')
+}
+
+impl Predicate {
+    /// Whether the row whose values `value` looks up by column index matches
+    pub fn matches<'a>(&'a self, value: &impl Fn(usize) -> &'a Value) -> bool {
+        let resolve = |operand: &'a Operand| -> &'a Value {
+            match operand {
+                Operand::Column(index) => value(*index),
+                Operand::Literal(literal) => literal,
+            }
+        };
+        match self {
+            Predicate::And(left, right) => left.matches(value) && right.matches(value),
+            Predicate::Or(left, right) => left.matches(value) || right.matches(value),
+            Predicate::Not(inner) => !inner.matches(value),
+            Predicate::Compare(left, comparison, right) => {
+                compare(resolve(left), resolve(right)).is_some_and(|o| comparison.holds(o))
+            }
+            Predicate::IsNull(operand, negated) => resolve(operand).is_null() != *negated,
+            Predicate::Like {
+                operand,
+                pattern,
+                negated,
+            } => {
+                let value = resolve(operand);
+                !value.is_null() && like(&text(value), pattern) != *negated
+            }
+            Predicate::In {
+                operand,
+                list,
+                negated,
+            } => {
+                let value = resolve(operand);
+                let found = list
+                    .iter()
+                    .any(|item| compare(value, resolve(item)) == Some(Ordering::Equal));
+                !value.is_null() && found != *negated
+            }
+            Predicate::Between {
+                operand,
+                low,
+                high,
+                negated,
+            } => {
+                let value = resolve(operand);
+                match (compare(value, resolve(low)), compare(value, resolve(high))) {
+                    (Some(low), Some(high)) => {
+                        (low != Ordering::Less && high != Ordering::Greater) != *negated
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(expression: &str, row: &[Value]) -> bool {
+        let columns = ["id", "status", "total", "note"];
+        let column = |name: &str| {
+            columns
+                .iter()
+                .position(|c| c.eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("Unknown column: {}", name))
+        };
+        parse(expression, &column)
+            .unwrap()
+            .matches(&|index| &row[index])
+    }
+
+    #[test]
+    fn test_expressions_match_rows() {
+        let row = [json!(7), json!("paid"), json!("120.50"), Value::Null];
+
+        assert!(check("status = 'paid' AND total > 100", &row));
+        assert!(!check("status = 'paid' AND total > 200", &row));
+        assert!(check("o.id IN (1, 7) OR note IS NOT NULL", &row));
+        assert!(check("total BETWEEN 100 AND 200", &row));
+        assert!(!check("total NOT BETWEEN -5 AND 200", &row));
+        assert!(check("status LIKE 'PA%' AND note IS NULL", &row));
+        assert!(check("NOT (id < -1)", &row));
+        // Comparisons with NULL never match, either way
+        assert!(!check("note = 'x'", &row));
+        assert!(!check("note <> 'x'", &row));
+    }
+
+    #[test]
+    fn test_table_filters_match_like_expressions() {
+        let row = [json!(7), json!("paid"), json!("120.50"), Value::Null];
+        let filter = |column: &str, operator, value: &str| TableFilter {
+            column: column.to_string(),
+            operator,
+            value: value.to_string(),
+        };
+        let check = |filter: TableFilter, column| {
+            from_table_filter(&filter, column).matches(&|index| &row[index])
+        };
+
+        assert!(check(
+            filter("total", FilterOperator::GreaterThan, "99.5"),
+            2
+        ));
+        assert!(check(filter("status", FilterOperator::Like, "%AI%"), 1));
+        assert!(check(filter("id", FilterOperator::In, "1, 7"), 0));
+        assert!(!check(filter("note", FilterOperator::NotEquals, "x"), 3));
+        assert!(check(filter("note", FilterOperator::IsNull, ""), 3));
+    }
+
+    #[test]
+    fn test_sort_order_puts_nulls_first() {
+        assert_eq!(sort_order(&Value::Null, &json!(1)), Ordering::Less);
+        assert_eq!(sort_order(&json!("10.5"), &json!(9)), Ordering::Greater);
+        assert_eq!(sort_order(&json!("b"), &json!("a")), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_like_wildcards() {
+        assert!(like("Inactive", "in%"));
+        assert!(like("abc", "a_c"));
+        assert!(like("abc", "%"));
+        assert!(!like("abc", "a_"));
+        assert!(like("aXbXc", "%x%c"));
+    }
+
+    #[test]
+    fn test_rejects_unknown_columns_and_syntax() {
+        let column = |name: &str| match name {
+            "id" => Ok(0),
+            _ => Err(format!("Unknown column: {}", name)),
+        };
+        assert_eq!(
+            parse("missing = 1", &column).unwrap_err(),
+            "Unknown column: missing"
+        );
+        assert!(parse("id = (SELECT 1)", &column).is_err());
+        assert!(parse("id = ", &column)
+            .unwrap_err()
+            .starts_with("Invalid filter"));
+    }
+}
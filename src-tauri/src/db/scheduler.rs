@@ -0,0 +1,218 @@
+//! Queries registered to run again on an interval, such as a dashboard's
+//! "pending jobs" count. The scheduler only tracks what is due; the runner
+//! in `commands::schedule` executes due queries and emits their results.
+
+use crate::db::sql::{self, StatementKind};
+use crate::models::{QueryResult, ScheduledQuery};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// How often the runner looks for due queries
+pub const SCHEDULER_TICK: Duration = Duration::from_secs(1);
+
+/// Shortest interval a query can be scheduled at
+pub const MIN_SCHEDULE_INTERVAL_SECS: u64 = 5;
+
+/// Event emitted with a `ScheduledResult` after each delivered run
+pub const SCHEDULED_RESULT_EVENT: &str = "schedule://result";
+
+struct Entry {
+    query: ScheduledQuery,
+    next_run: Instant,
+    /// Set while the query runs, so a slow query isn't started twice
+    running: bool,
+    /// Hash of the previous run's rows
+    last_rows: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    entries: HashMap<String, Entry>,
+}
+
+/// Whether `sql` can be scheduled: the whole script is sent every run, so
+/// each of its statements must be a query, and none may hide in an
+/// executable comment
+pub fn is_schedulable(sql: &str) -> bool {
+    sql::classify_script(sql) == StatementKind::Query && !sql::has_executable_comment(sql)
+}
+
+fn rows_hash(result: &QueryResult) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    result.columns.hash(&mut hasher);
+    serde_json::Value::from(result.rows.clone())
+        .to_string()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Scheduler {
+    /// Add a query, due right away
+    pub fn register(&mut self, query: ScheduledQuery) {
+        self.entries.insert(
+            query.id.clone(),
+            Entry {
+                query,
+                next_run: Instant::now(),
+                running: false,
+                last_rows: None,
+            },
+        );
+    }
+
+    /// Stop running a query; false when there was no such schedule
+    pub fn unregister(&mut self, id: &str) -> bool {
+        self.entries.remove(id).is_some()
+    }
+
+    /// Schedules of a connection, or all of them
+    pub fn list(&self, connection_id: Option<&str>) -> Vec<ScheduledQuery> {
+        self.entries
+            .values()
+            .map(|entry| &entry.query)
+            .filter(|query| connection_id.is_none_or(|id| query.connection_id == id))
+            .cloned()
+            .collect()
+    }
+
+    /// Forget every schedule of a connection, e.g. when it's deleted
+    pub fn remove_connection(&mut self, connection_id: &str) {
+        self.entries
+            .retain(|_, entry| entry.query.connection_id != connection_id);
+    }
+
+    /// Queries due at `now` that aren't already running, marked as running
+    pub fn take_due(&mut self, now: Instant) -> Vec<ScheduledQuery> {
+        self.entries
+            .values_mut()
+            .filter(|entry| !entry.running && entry.next_run <= now)
+            .map(|entry| {
+                entry.running = true;
+                entry.query.clone()
+            })
+            .collect()
+    }
+
+    /// Record a finished run and schedule the next one. Returns whether the
+    /// rows differ from the previous run; a failed run never counts as a
+    /// change. False as well when the query was unregistered meanwhile.
+    pub fn finish(
+        &mut self,
+        id: &str,
+        now: Instant,
+        ran_at: i64,
+        outcome: Result<&QueryResult, String>,
+    ) -> bool {
+        let Some(entry) = self.entries.get_mut(id) else {
+            return false;
+        };
+        entry.running = false;
+        entry.next_run = now + Duration::from_secs(entry.query.interval_secs);
+        entry.query.last_run_at = Some(ran_at);
+
+        match outcome {
+            Ok(result) => {
+                entry.query.last_error = None;
+                let rows = rows_hash(result);
+                let changed = entry.last_rows != Some(rows);
+                entry.last_rows = Some(rows);
+                changed
+            }
+            Err(error) => {
+                entry.query.last_error = Some(error);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ScheduleDelivery;
+    use serde_json::json;
+
+    fn query(id: &str, interval_secs: u64) -> ScheduledQuery {
+        ScheduledQuery {
+            id: id.to_string(),
+            connection_id: "local".to_string(),
+            database: None,
+            sql: "SELECT COUNT(*) FROM jobs".to_string(),
+            params: vec![],
+            interval_secs,
+            delivery: ScheduleDelivery::OnChange,
+            last_run_at: None,
+            last_error: None,
+        }
+    }
+
+    fn result(count: i64) -> QueryResult {
+        QueryResult {
+            columns: vec!["count".to_string()],
+            rows: vec![vec![json!(count)]],
+            total_rows: 1,
+            execution_time_ms: 3,
+            result_id: None,
+            truncated: false,
+            page_size: None,
+            rows_affected: 0,
+            column_types: vec![],
+            cached: false,
+            cached_at: None,
+            auto_limit: None,
+            unbounded: false,
+            has_more: false,
+            dml: false,
+            last_insert_id: None,
+            warnings: vec![],
+            more_results: vec![],
+            spilled: None,
+        }
+    }
+
+    #[test]
+    fn test_only_queries_are_schedulable() {
+        assert!(is_schedulable("SELECT COUNT(*) FROM jobs"));
+        assert!(is_schedulable("SELECT 1; SHOW PROCESSLIST"));
+        assert!(!is_schedulable("SELECT 1; DELETE FROM jobs"));
+        assert!(!is_schedulable("SELECT 1 /*!50000 ; DELETE FROM jobs */"));
+        assert!(!is_schedulable("EXPLAIN ANALYZE DELETE FROM jobs"));
+    }
+
+    #[test]
+    fn test_due_queries_run_once_at_a_time() {
+        let mut scheduler = Scheduler::default();
+        scheduler.register(query("a", 10));
+        let now = Instant::now();
+
+        assert_eq!(scheduler.take_due(now).len(), 1);
+        // Still running, so not handed out again
+        assert!(scheduler.take_due(now).is_empty());
+
+        scheduler.finish("a", now, 1_000, Ok(&result(1)));
+        assert!(scheduler.take_due(now + Duration::from_secs(5)).is_empty());
+        assert_eq!(scheduler.take_due(now + Duration::from_secs(10)).len(), 1);
+    }
+
+    #[test]
+    fn test_finish_reports_changes() {
+        let mut scheduler = Scheduler::default();
+        scheduler.register(query("a", 10));
+        let now = Instant::now();
+
+        assert!(scheduler.finish("a", now, 1, Ok(&result(4))));
+        assert!(!scheduler.finish("a", now, 2, Ok(&result(4))));
+        assert!(!scheduler.finish("a", now, 3, Err("gone away".to_string())));
+        assert_eq!(
+            scheduler.list(None)[0].last_error.as_deref(),
+            Some("gone away")
+        );
+        assert!(scheduler.finish("a", now, 4, Ok(&result(5))));
+        assert_eq!(scheduler.list(Some("local"))[0].last_run_at, Some(4));
+
+        assert!(scheduler.unregister("a"));
+        assert!(!scheduler.finish("a", now, 5, Ok(&result(6))));
+    }
+}
@@ -0,0 +1,299 @@
+//! Schemas are loaded per database on first use and kept until the
+//! connection changes or DDL runs, so completion and reference resolution can
+//! look across every database of a connection without re-querying each time.
+//! A cached schema can also be refreshed in place, re-reading only the tables
+//! whose version changed since it was loaded.
+
+use crate::db::mysql_adapter::Result;
+use crate::db::MySQLAdapter;
+use crate::models::{ResolvedReference, Schema};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Emitted with an `AutocompleteUpdate` when a refresh finds tables added,
+/// changed or dropped
+pub const AUTOCOMPLETE_UPDATED_EVENT: &str = "autocomplete://updated";
+
+/// What tells whether a table changed since its schema was read
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableVersion {
+    pub created: Option<String>,
+    pub updated: Option<String>,
+    /// Checksum of the table's column definitions
+    pub columns: u64,
+}
+
+/// Tables that differ between two sets of versions, each sorted by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersionChanges {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl VersionChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Compare the versions a schema was loaded at with the current ones
+pub fn diff_versions(
+    cached: &HashMap<String, TableVersion>,
+    current: &HashMap<String, TableVersion>,
+) -> VersionChanges {
+    let mut changes = VersionChanges::default();
+    for (table, version) in current {
+        match cached.get(table) {
+            None => changes.added.push(table.clone()),
+            Some(previous) if previous != version => changes.changed.push(table.clone()),
+            Some(_) => {}
+        }
+    }
+    changes.removed = cached
+        .keys()
+        .filter(|table| !current.contains_key(*table))
+        .cloned()
+        .collect();
+
+    changes.added.sort();
+    changes.changed.sort();
+    changes.removed.sort();
+    changes
+}
+
+struct CachedSchema {
+    schema: Arc<Schema>,
+    versions: HashMap<String, TableVersion>,
+}
+
+#[derive(Default)]
+pub struct SchemaCache {
+    schemas: Mutex<HashMap<(String, String), CachedSchema>>,
+}
+
+impl SchemaCache {
+    /// Return the schema of `database`, loading it the first time it's needed
+    pub async fn get_or_load(
+        &self,
+        connection_id: &str,
+        database: &str,
+        adapter: &MySQLAdapter,
+    ) -> Result<Arc<Schema>> {
+        let key = (connection_id.to_string(), database.to_string());
+        if let Some(cached) = self.schemas.lock().await.get(&key) {
+            return Ok(cached.schema.clone());
+        }
+
+        // Versions are read first, so a change made while the schema loads is
+        // picked up by the next refresh rather than missed
+        let versions = adapter.table_versions(database).await?;
+        let schema = Arc::new(adapter.get_schema(database).await?);
+        self.schemas.lock().await.insert(
+            key,
+            CachedSchema {
+                schema: schema.clone(),
+                versions,
+            },
+        );
+        Ok(schema)
+    }
+
+    /// Bring the cached schema of `database` up to date, re-reading only the
+    /// tables whose version changed. Loads the whole schema if none is cached.
+    pub async fn refresh(
+        &self,
+        connection_id: &str,
+        database: &str,
+        adapter: &MySQLAdapter,
+    ) -> Result<(Arc<Schema>, VersionChanges)> {
+        let key = (connection_id.to_string(), database.to_string());
+        let cached = self
+            .schemas
+            .lock()
+            .await
+            .get(&key)
+            .map(|cached| (cached.schema.clone(), cached.versions.clone()));
+        let Some((schema, versions)) = cached else {
+            let schema = self.get_or_load(connection_id, database, adapter).await?;
+            return Ok((schema, VersionChanges::default()));
+        };
+
+        let current = adapter.table_versions(database).await?;
+        let changes = diff_versions(&versions, &current);
+        if changes.is_empty() {
+            return Ok((schema, changes));
+        }
+
+        let mut updated = Schema::clone(&schema);
+        updated.tables.retain(|table| {
+            current.contains_key(&table.name) && !changes.changed.contains(&table.name)
+        });
+        for table in changes.changed.iter().chain(&changes.added) {
+            updated
+                .tables
+                .push(adapter.get_table_schema(database, table).await?);
+        }
+        updated.tables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let updated = Arc::new(updated);
+        self.schemas.lock().await.insert(
+            key,
+            CachedSchema {
+                schema: updated.clone(),
+                versions: current,
+            },
+        );
+        Ok((updated, changes))
+    }
+
+    /// Forget every cached schema of a connection
+    pub async fn invalidate(&self, connection_id: &str) {
+        self.schemas
+            .lock()
+            .await
+            .retain(|(cached_connection, _), _| cached_connection != connection_id);
+    }
+
+    pub async fn clear(&self) {
+        self.schemas.lock().await.clear();
+    }
+}
+
+/// Split `db.table.column` into its parts, dropping identifier quotes
+pub fn split_reference(reference: &str) -> Vec<String> {
+    reference
+        .split('.')
+        .map(|part| {
+            part.trim()
+                .trim_matches(|c| c == '`' || c == '"')
+                .to_string()
+        })
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// The ways a dotted reference can be read, most likely first:
+/// `table`, `table.column` or `db.table`, and `db.table.column`
+pub fn interpretations(parts: &[String], active_database: &str) -> Vec<ResolvedReference> {
+    let reference = |database: &str, table: &str, column: Option<&String>| ResolvedReference {
+        database: database.to_string(),
+        table: table.to_string(),
+        column: column.cloned(),
+    };
+
+    match parts {
+        [table] => vec![reference(active_database, table, None)],
+        [first, second] => vec![
+            reference(active_database, first, Some(second)),
+            reference(first, second, None),
+        ],
+        [database, table, column] => vec![reference(database, table, Some(column))],
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `schema` has the table (and column, if any) of a reference
+pub fn schema_contains(schema: &Schema, reference: &ResolvedReference) -> bool {
+    let Some(table) = schema
+        .tables
+        .iter()
+        .find(|t| t.name.eq_ignore_ascii_case(&reference.table))
+    else {
+        return false;
+    };
+
+    match &reference.column {
+        Some(column) => table
+            .columns
+            .iter()
+            .any(|c| c.name.eq_ignore_ascii_case(column)),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ColumnSchema, TableSchema};
+
+    fn schema(table: &str, columns: &[&str]) -> Schema {
+        Schema {
+            tables: vec![TableSchema {
+                name: table.to_string(),
+                schema: None,
+                columns: columns
+                    .iter()
+                    .map(|name| ColumnSchema {
+                        name: name.to_string(),
+                        data_type: "int".to_string(),
+                        is_nullable: false,
+                        default_value: None,
+                        max_length: None,
+                        is_auto_increment: false,
+                        is_generated: false,
+                        extra: String::new(),
+                        allowed_values: None,
+                    })
+                    .collect(),
+                primary_keys: vec![],
+                foreign_keys: vec![],
+            }],
+            search_path: vec![],
+        }
+    }
+
+    #[test]
+    fn test_diff_versions() {
+        let version = |updated: &str, columns: u64| TableVersion {
+            created: Some("2024-01-01 00:00:00".to_string()),
+            updated: Some(updated.to_string()),
+            columns,
+        };
+        let cached = HashMap::from([
+            ("orders".to_string(), version("2024-02-01 10:00:00", 7)),
+            ("users".to_string(), version("2024-02-01 10:00:00", 3)),
+            ("legacy".to_string(), version("2024-01-05 09:00:00", 1)),
+        ]);
+        let current = HashMap::from([
+            ("orders".to_string(), version("2024-02-01 10:00:00", 7)),
+            ("users".to_string(), version("2024-02-01 10:00:00", 4)),
+            ("invoices".to_string(), version("2024-02-02 08:00:00", 5)),
+        ]);
+
+        let changes = diff_versions(&cached, &current);
+        assert_eq!(changes.added, vec!["invoices"]);
+        assert_eq!(changes.changed, vec!["users"]);
+        assert_eq!(changes.removed, vec!["legacy"]);
+        assert!(diff_versions(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_split_reference_strips_quotes() {
+        assert_eq!(
+            split_reference("`billing`.invoices.`total`"),
+            vec!["billing", "invoices", "total"]
+        );
+    }
+
+    #[test]
+    fn test_two_part_reference_prefers_active_table() {
+        let parts = split_reference("orders.id");
+        let candidates = interpretations(&parts, "shop");
+        assert_eq!(candidates[0].database, "shop");
+        assert_eq!(candidates[0].column.as_deref(), Some("id"));
+        assert_eq!(candidates[1].database, "orders");
+        assert_eq!(candidates[1].table, "id");
+    }
+
+    #[test]
+    fn test_schema_contains_column() {
+        let billing = schema("invoices", &["id", "total"]);
+        let candidates = interpretations(&split_reference("billing.invoices.TOTAL"), "shop");
+        assert!(schema_contains(&billing, &candidates[0]));
+
+        let missing = interpretations(&split_reference("billing.invoices.tax"), "shop");
+        assert!(!schema_contains(&billing, &missing[0]));
+    }
+}
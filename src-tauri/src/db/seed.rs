@@ -0,0 +1,465 @@
+//! Made-up rows for filling empty development tables.
+//!
+//! Values are picked from a column's type and, for text, from its name, so
+//! an `email` column gets addresses and a `first_name` column gets names.
+//! Foreign key columns take values sampled from the referenced table.
+
+use crate::db::sql::quote_literal;
+use crate::models::{ColumnSchema, TableSchema};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Rows per INSERT statement when the request doesn't say
+pub const DEFAULT_SEED_BATCH_SIZE: usize = 500;
+
+/// Share of generated values that are NULL in nullable columns
+const NULL_PROBABILITY: f64 = 0.1;
+
+const FIRST_NAMES: &[&str] = &[
+    "Alice", "Bruno", "Chen", "Dana", "Elif", "Farah", "Gustavo", "Hana", "Ivan", "Jonas", "Keiko",
+    "Liam", "Maya", "Nikhil", "Olga", "Priya", "Quentin", "Rosa", "Sven", "Tariq",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Anders", "Baker", "Costa", "Dubois", "Eriksen", "Fischer", "Garcia", "Haddad", "Ito",
+    "Jensen", "Kumar", "Lopez", "Moreau", "Nakamura", "Okafor", "Petrov", "Rossi", "Singh",
+];
+
+const CITIES: &[&str] = &[
+    "Amsterdam",
+    "Bangalore",
+    "Chicago",
+    "Dublin",
+    "Edinburgh",
+    "Florence",
+    "Geneva",
+    "Helsinki",
+    "Istanbul",
+    "Jakarta",
+    "Kyoto",
+    "Lisbon",
+    "Montreal",
+    "Nairobi",
+    "Oslo",
+    "Porto",
+];
+
+const WORDS: &[&str] = &[
+    "alpha", "bright", "copper", "delta", "ember", "forest", "garden", "harbor", "island",
+    "jasper", "kernel", "lumen", "meadow", "north", "orbit", "pepper", "quartz", "river", "signal",
+    "timber", "umber", "velvet", "willow", "yonder", "zephyr",
+];
+
+/// How the values of a column are made up
+#[derive(Debug, Clone, PartialEq)]
+enum Generator {
+    /// Consecutive integers, for a primary key the server doesn't number
+    Sequence(i64),
+    /// Random hex, for a text primary key
+    Token,
+    /// One of the values sampled from a referenced table
+    Reference(Vec<Value>),
+    Boolean,
+    Integer(i64, i64),
+    Decimal,
+    Date,
+    DateTime,
+    Time,
+    Year,
+    Choice(Vec<String>),
+    Set(Vec<String>),
+    Json,
+    Binary,
+    Email,
+    FirstName,
+    LastName,
+    FullName,
+    Phone,
+    City,
+    Url,
+    Text,
+}
+
+/// A column filled by the seeder
+#[derive(Debug, Clone)]
+pub struct SeedColumn {
+    name: String,
+    nullable: bool,
+    max_length: Option<usize>,
+    generator: Generator,
+}
+
+/// Columns of `table` to fill and how. Auto-increment and generated
+/// columns are left to the server, as are columns of types with no
+/// generator that have a default or allow NULL.
+///
+/// `references` holds sampled values of each foreign key column, and
+/// `next_key` the first value for an integer primary key the server
+/// doesn't number.
+pub fn plan(
+    table: &TableSchema,
+    references: &HashMap<String, Vec<Value>>,
+    next_key: i64,
+) -> Result<Vec<SeedColumn>, String> {
+    let single_key = match table.primary_keys.as_slice() {
+        [key] => Some(key.as_str()),
+        _ => None,
+    };
+
+    let mut columns = Vec::new();
+    for column in &table.columns {
+        if column.is_auto_increment || column.is_generated {
+            continue;
+        }
+        let is_key = single_key == Some(column.name.as_str());
+        let generator = match references.get(&column.name) {
+            Some(sample) if sample.is_empty() && column.is_nullable => continue,
+            Some(sample) if sample.is_empty() => {
+                return Err(format!(
+                    "Column {} references a table with no rows",
+                    column.name
+                ))
+            }
+            Some(sample) => Some(Generator::Reference(sample.clone())),
+            None if is_key && is_integer(&column.data_type) => Some(Generator::Sequence(next_key)),
+            None if is_key && is_text(&column.data_type) => Some(Generator::Token),
+            None => generator_for(column),
+        };
+
+        match generator {
+            Some(generator) => columns.push(SeedColumn {
+                name: column.name.clone(),
+                nullable: column.is_nullable && !is_key,
+                max_length: column.max_length.map(|length| length.max(0) as usize),
+                generator,
+            }),
+            None if column.is_nullable || column.default_value.is_some() => {}
+            None => {
+                return Err(format!(
+                    "Can't generate values for column {} of type {}",
+                    column.name, column.data_type
+                ))
+            }
+        }
+    }
+    Ok(columns)
+}
+
+fn is_integer(data_type: &str) -> bool {
+    matches!(
+        data_type,
+        "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint"
+    )
+}
+
+fn is_text(data_type: &str) -> bool {
+    matches!(
+        data_type,
+        "char" | "varchar" | "tinytext" | "text" | "mediumtext" | "longtext"
+    )
+}
+
+fn generator_for(column: &ColumnSchema) -> Option<Generator> {
+    let name = column.name.to_lowercase();
+    let data_type = column.data_type.to_lowercase();
+    let flag = name.starts_with("is_")
+        || name.starts_with("has_")
+        || matches!(name.as_str(), "active" | "enabled" | "deleted" | "verified");
+
+    let generator = match data_type.as_str() {
+        "bool" | "boolean" | "bit" => Generator::Boolean,
+        "tinyint" if flag => Generator::Boolean,
+        "tinyint" => Generator::Integer(0, 100),
+        "smallint" => Generator::Integer(0, 1000),
+        _ if is_integer(&data_type) && (name == "age" || name.ends_with("_age")) => {
+            Generator::Integer(18, 90)
+        }
+        _ if is_integer(&data_type) => Generator::Integer(1, 100_000),
+        "decimal" | "numeric" | "float" | "double" | "real" => Generator::Decimal,
+        "date" => Generator::Date,
+        "datetime" | "timestamp" => Generator::DateTime,
+        "time" => Generator::Time,
+        "year" => Generator::Year,
+        "enum" => Generator::Choice(column.allowed_values.clone()?),
+        "set" => Generator::Set(column.allowed_values.clone()?),
+        "json" => Generator::Json,
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => {
+            Generator::Binary
+        }
+        _ if is_text(&data_type) => {
+            if name.contains("email") {
+                Generator::Email
+            } else if name.contains("first") && name.contains("name") {
+                Generator::FirstName
+            } else if (name.contains("last") && name.contains("name")) || name.contains("surname") {
+                Generator::LastName
+            } else if name.contains("name") {
+                Generator::FullName
+            } else if name.contains("phone") {
+                Generator::Phone
+            } else if name.contains("city") {
+                Generator::City
+            } else if name.contains("url") || name.contains("website") {
+                Generator::Url
+            } else {
+                Generator::Text
+            }
+        }
+        _ => return None,
+    };
+    Some(generator)
+}
+
+fn pick<'a, R: Rng>(rng: &mut R, values: &[&'a str]) -> &'a str {
+    values.choose(rng).copied().unwrap_or_default()
+}
+
+fn date<R: Rng>(rng: &mut R) -> String {
+    format!(
+        "{:04}-{:02}-{:02}",
+        rng.gen_range(2020..=2025),
+        rng.gen_range(1..=12),
+        rng.gen_range(1..=28)
+    )
+}
+
+fn time<R: Rng>(rng: &mut R) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        rng.gen_range(0..24),
+        rng.gen_range(0..60),
+        rng.gen_range(0..60)
+    )
+}
+
+/// Cut text to a column's maximum length in characters
+fn fit(text: String, max_length: Option<usize>) -> String {
+    match max_length {
+        Some(length) if text.chars().count() > length => text.chars().take(length).collect(),
+        _ => text,
+    }
+}
+
+/// SQL literal of the value of `column` in the `index`th generated row
+fn value<R: Rng>(column: &SeedColumn, index: usize, rng: &mut R) -> String {
+    if column.nullable && rng.gen_bool(NULL_PROBABILITY) {
+        return "NULL".to_string();
+    }
+
+    let text = |text: String| quote_literal(&fit(text, column.max_length));
+    match &column.generator {
+        Generator::Sequence(start) => (start + index as i64).to_string(),
+        Generator::Token => text(format!("{:032x}", rng.gen::<u128>())),
+        Generator::Reference(sample) => match sample.choose(rng) {
+            Some(Value::String(value)) => quote_literal(value),
+            Some(Value::Null) | None => "NULL".to_string(),
+            Some(value) => value.to_string(),
+        },
+        Generator::Boolean => rng.gen_range(0..=1).to_string(),
+        Generator::Integer(min, max) => rng.gen_range(*min..=*max).to_string(),
+        Generator::Decimal => format!("{:.2}", rng.gen_range(0.0..1000.0)),
+        Generator::Date => quote_literal(&date(rng)),
+        Generator::DateTime => quote_literal(&format!("{} {}", date(rng), time(rng))),
+        Generator::Time => quote_literal(&time(rng)),
+        Generator::Year => rng.gen_range(1990..=2030).to_string(),
+        Generator::Choice(values) => quote_literal(values.choose(rng).map_or("", |v| v.as_str())),
+        Generator::Set(values) => {
+            let chosen: Vec<&str> = values
+                .iter()
+                .filter(|_| rng.gen_bool(0.5))
+                .map(String::as_str)
+                .collect();
+            quote_literal(&chosen.join(","))
+        }
+        Generator::Json => quote_literal(
+            &serde_json::json!({ "seed": index, "tag": pick(rng, WORDS) }).to_string(),
+        ),
+        Generator::Binary => {
+            let length = column.max_length.unwrap_or(16).clamp(1, 16);
+            let bytes: String = (0..length)
+                .map(|_| format!("{:02X}", rng.gen::<u8>()))
+                .collect();
+            format!("X'{}'", bytes)
+        }
+        Generator::Email => text(format!(
+            "{}.{}{}@example.com",
+            pick(rng, FIRST_NAMES).to_lowercase(),
+            pick(rng, LAST_NAMES).to_lowercase(),
+            rng.gen_range(1..100_000)
+        )),
+        Generator::FirstName => text(pick(rng, FIRST_NAMES).to_string()),
+        Generator::LastName => text(pick(rng, LAST_NAMES).to_string()),
+        Generator::FullName => text(format!(
+            "{} {}",
+            pick(rng, FIRST_NAMES),
+            pick(rng, LAST_NAMES)
+        )),
+        Generator::Phone => text(format!(
+            "+1-555-{:03}-{:04}",
+            rng.gen_range(0..1000),
+            rng.gen_range(0..10_000)
+        )),
+        Generator::City => text(pick(rng, CITIES).to_string()),
+        Generator::Url => text(format!(
+            "https://{}.example.com/{}",
+            pick(rng, WORDS),
+            pick(rng, WORDS)
+        )),
+        Generator::Text => {
+            let words: Vec<&str> = (0..rng.gen_range(2..=8))
+                .map(|_| pick(rng, WORDS))
+                .collect();
+            text(words.join(" "))
+        }
+    }
+}
+
+/// INSERT statements adding `rows` generated rows to `database`.`table`,
+/// `batch_size` rows each
+pub fn insert_statements<R: Rng>(
+    database: &str,
+    table: &str,
+    columns: &[SeedColumn],
+    rows: usize,
+    batch_size: usize,
+    rng: &mut R,
+) -> Vec<String> {
+    let names: Vec<String> = columns
+        .iter()
+        .map(|column| format!("`{}`", column.name.replace('`', "``")))
+        .collect();
+    let target = format!(
+        "INSERT INTO `{}`.`{}` ({}) VALUES ",
+        database.replace('`', "``"),
+        table.replace('`', "``"),
+        names.join(", ")
+    );
+
+    let mut statements = Vec::new();
+    let mut start = 0;
+    while start < rows {
+        let end = (start + batch_size.max(1)).min(rows);
+        let values: Vec<String> = (start..end)
+            .map(|index| {
+                let row: Vec<String> = columns
+                    .iter()
+                    .map(|column| value(column, index, rng))
+                    .collect();
+                format!("({})", row.join(", "))
+            })
+            .collect();
+        statements.push(format!("{}{}", target, values.join(", ")));
+        start = end;
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ForeignKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    fn column(name: &str, data_type: &str, nullable: bool) -> ColumnSchema {
+        ColumnSchema {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: nullable,
+            default_value: None,
+            max_length: None,
+            is_auto_increment: false,
+            is_generated: false,
+            extra: String::new(),
+            allowed_values: None,
+        }
+    }
+
+    fn users() -> TableSchema {
+        let mut id = column("id", "int", false);
+        id.is_auto_increment = true;
+        let mut email = column("email", "varchar", false);
+        email.max_length = Some(20);
+        TableSchema {
+            name: "users".to_string(),
+            schema: None,
+            columns: vec![
+                id,
+                email,
+                column("first_name", "varchar", false),
+                column("team_id", "int", false),
+                column("location", "point", true),
+                column("is_admin", "tinyint", false),
+            ],
+            primary_keys: vec!["id".to_string()],
+            foreign_keys: vec![ForeignKey {
+                column_name: "team_id".to_string(),
+                referenced_table: "teams".to_string(),
+                referenced_column: "id".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_plan_picks_generators() {
+        let references = HashMap::from([("team_id".to_string(), vec![json!(7), json!(9)])]);
+        let columns = plan(&users(), &references, 1).unwrap();
+
+        let generators: Vec<(&str, &Generator)> = columns
+            .iter()
+            .map(|column| (column.name.as_str(), &column.generator))
+            .collect();
+        assert_eq!(
+            generators,
+            vec![
+                ("email", &Generator::Email),
+                ("first_name", &Generator::FirstName),
+                ("team_id", &Generator::Reference(vec![json!(7), json!(9)])),
+                ("is_admin", &Generator::Boolean),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_needs_referenced_rows() {
+        let references = HashMap::from([("team_id".to_string(), vec![])]);
+        let err = plan(&users(), &references, 1).unwrap_err();
+        assert!(err.contains("team_id"));
+    }
+
+    #[test]
+    fn test_insert_statements_batch_rows() {
+        let mut table = users();
+        table.columns[0].is_auto_increment = false;
+        let references = HashMap::from([("team_id".to_string(), vec![json!(7)])]);
+        let columns = plan(&table, &references, 41).unwrap();
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let statements = insert_statements("dev", "users", &columns, 5, 2, &mut rng);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].starts_with(
+            "INSERT INTO `dev`.`users` (`id`, `email`, `first_name`, `team_id`, `is_admin`) VALUES (41, '"
+        ));
+        assert!(statements[2].contains("(45, '"));
+        assert!(statements.iter().all(|s| s.contains(", 7, ")));
+    }
+
+    #[test]
+    fn test_values_fit_column_length() {
+        let columns = plan(
+            &users(),
+            &HashMap::from([("team_id".to_string(), vec![json!(1)])]),
+            1,
+        )
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(2);
+        for index in 0..50 {
+            let email = value(&columns[0], index, &mut rng);
+            // Quotes around at most 20 characters
+            assert!(email.chars().count() <= 22, "{}", email);
+        }
+    }
+}
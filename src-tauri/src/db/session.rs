@@ -0,0 +1,255 @@
+//! Sessions pin a single server connection so statements issued across several
+//! commands (e.g. BEGIN ... COMMIT) run on the same connection.
+
+use crate::db::mysql_adapter::{ensure_statement_allowed, query_error, MySQLAdapter, ResultLimits};
+use crate::db::mysql_adapter::{DatabaseError, Result};
+use crate::db::sql::{classify, quote_identifier, StatementKind};
+use crate::models::{DatabaseType, QueryResult, SessionState};
+use sqlx::mysql::MySql;
+use sqlx::pool::PoolConnection;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone)]
+struct OpenTransaction {
+    started: Instant,
+    started_at: i64,
+    statements: Vec<String>,
+    /// Whether the watchdog already warned about this transaction
+    flagged: bool,
+}
+
+/// Tracks whether statements run on a connection have left a transaction open
+#[derive(Debug, Default)]
+pub struct TransactionTracker {
+    open: Option<OpenTransaction>,
+}
+
+impl TransactionTracker {
+    /// Update transaction state after `sql` executed successfully
+    pub fn record(&mut self, sql: &str) {
+        match classify(sql) {
+            StatementKind::Begin => {
+                self.open = Some(OpenTransaction {
+                    started: Instant::now(),
+                    started_at: now_millis(),
+                    statements: Vec::new(),
+                    flagged: false,
+                });
+            }
+            // MySQL commits implicitly before and after DDL
+            StatementKind::Commit | StatementKind::Rollback | StatementKind::Ddl => {
+                self.open = None;
+            }
+            _ => {
+                if let Some(open) = &mut self.open {
+                    open.statements.push(sql.trim().to_string());
+                }
+            }
+        }
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// How long the current transaction has been open
+    pub fn open_for(&self) -> Option<Duration> {
+        self.open.as_ref().map(|open| open.started.elapsed())
+    }
+
+    /// Mark the open transaction as reported, returning false if it already was
+    pub fn flag(&mut self) -> bool {
+        match &mut self.open {
+            Some(open) if !open.flagged => {
+                open.flagged = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub struct Session {
+    pub connection_id: String,
+    pub username: String,
+    pub read_only: bool,
+    pub limits: ResultLimits,
+    pub conn: PoolConnection<MySql>,
+    pub transaction: TransactionTracker,
+}
+
+impl Session {
+    /// Run a statement on the pinned connection and track its effect on the
+    /// open transaction
+    pub async fn execute(
+        &mut self,
+        sql: &str,
+        database: Option<&str>,
+        params: &[serde_json::Value],
+    ) -> Result<QueryResult> {
+        ensure_statement_allowed(self.read_only, sql)?;
+
+        if let Some(db) = database {
+            let use_query = format!("USE {}", quote_identifier(&DatabaseType::MySQL, db));
+            sqlx::query(&use_query)
+                .execute(&mut *self.conn)
+                .await
+                .map_err(|e| query_error(e, &use_query).with_context(Some(db), &self.username))?;
+        }
+
+        let result = MySQLAdapter::fetch_result(&mut self.conn, sql, params, self.limits)
+            .await
+            .map_err(|e| e.with_context(database, &self.username))?;
+        self.transaction.record(sql);
+        Ok(result)
+    }
+
+    /// Roll back the open transaction, if any, so the connection goes back
+    /// to the pool clean
+    pub async fn rollback(&mut self) -> Result<()> {
+        if self.transaction.in_transaction() {
+            sqlx::query("ROLLBACK")
+                .execute(&mut *self.conn)
+                .await
+                .map_err(|e| query_error(e, "ROLLBACK"))?;
+            self.transaction.record("ROLLBACK");
+        }
+        Ok(())
+    }
+
+    pub fn state(&self, session_id: &str) -> SessionState {
+        let open = self.transaction.open.as_ref();
+        SessionState {
+            session_id: session_id.to_string(),
+            connection_id: self.connection_id.clone(),
+            in_transaction: open.is_some(),
+            transaction_started_at: open.map(|o| o.started_at),
+            transaction_duration_ms: open.map(|o| o.started.elapsed().as_millis() as u64),
+            statements_in_transaction: open.map(|o| o.statements.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+pub type SharedSession = Arc<tokio::sync::Mutex<Session>>;
+
+/// Open sessions by id
+#[derive(Default)]
+pub struct SessionManager {
+    sessions: Mutex<HashMap<String, SharedSession>>,
+}
+
+impl SessionManager {
+    pub fn insert(&self, session: Session) -> Result<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.sessions
+            .lock()
+            .map_err(|e| DatabaseError::Connection(e.to_string()))?
+            .insert(id.clone(), Arc::new(tokio::sync::Mutex::new(session)));
+        Ok(id)
+    }
+
+    pub fn get(&self, session_id: &str) -> Result<SharedSession> {
+        self.sessions
+            .lock()
+            .map_err(|e| DatabaseError::Connection(e.to_string()))?
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| DatabaseError::Connection(format!("Session not found: {}", session_id)))
+    }
+
+    /// All open sessions with their ids
+    pub fn list(&self) -> Result<Vec<(String, SharedSession)>> {
+        Ok(self
+            .sessions
+            .lock()
+            .map_err(|e| DatabaseError::Connection(e.to_string()))?
+            .iter()
+            .map(|(id, session)| (id.clone(), session.clone()))
+            .collect())
+    }
+
+    pub fn remove(&self, session_id: &str) -> Result<Option<SharedSession>> {
+        Ok(self
+            .sessions
+            .lock()
+            .map_err(|e| DatabaseError::Connection(e.to_string()))?
+            .remove(session_id))
+    }
+
+    /// Remove every session of a connection. Waits for statements running
+    /// in them to finish.
+    pub async fn remove_connection(&self, connection_id: &str) -> Result<Vec<SharedSession>> {
+        let mut removed = Vec::new();
+        for (id, session) in self.list()? {
+            if session.lock().await.connection_id == connection_id {
+                self.remove(&id)?;
+                removed.push(session);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Ids of the connections with open sessions
+    pub async fn connection_ids(&self) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        for (_, session) in self.list()? {
+            let id = session.lock().await.connection_id.clone();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_follows_transaction_lifecycle() {
+        let mut tracker = TransactionTracker::default();
+        tracker.record("SELECT 1");
+        assert!(!tracker.in_transaction());
+
+        tracker.record("START TRANSACTION");
+        tracker.record("UPDATE accounts SET balance = 0");
+        tracker.record("SELECT * FROM accounts");
+        assert!(tracker.in_transaction());
+        assert_eq!(tracker.open.as_ref().unwrap().statements.len(), 2);
+
+        tracker.record("COMMIT");
+        assert!(!tracker.in_transaction());
+    }
+
+    #[test]
+    fn test_tracker_flags_each_transaction_once() {
+        let mut tracker = TransactionTracker::default();
+        assert!(!tracker.flag());
+
+        tracker.record("BEGIN");
+        assert!(tracker.flag());
+        assert!(!tracker.flag());
+
+        tracker.record("ROLLBACK");
+        tracker.record("BEGIN");
+        assert!(tracker.flag());
+    }
+
+    #[test]
+    fn test_tracker_ddl_implicitly_commits() {
+        let mut tracker = TransactionTracker::default();
+        tracker.record("BEGIN");
+        tracker.record("ALTER TABLE t ADD COLUMN c INT");
+        assert!(!tracker.in_transaction());
+    }
+}
@@ -0,0 +1,180 @@
+//! Rows of a result past its connection's memory budget, kept in a temporary
+//! SQLite database instead of memory. SQLite writes the pages it can't cache
+//! to a file of its own, removed when the store is dropped, so a giant result
+//! costs disk space rather than bringing the app down.
+
+use rusqlite::{params, Connection as SqliteConnection};
+use serde_json::Value;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// Rows written to the store in one transaction
+pub const SPILL_BATCH_SIZE: usize = 1000;
+
+#[derive(Error, Debug)]
+pub enum SpillError {
+    #[error("Temporary result store error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("Temporary result store holds an invalid row: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Temporary result store is unavailable")]
+    Poisoned,
+}
+
+pub type Result<T> = std::result::Result<T, SpillError>;
+
+/// Rough number of bytes a value takes in memory, to hold results to a budget
+pub fn value_size(value: &Value) -> usize {
+    const SLOT: usize = std::mem::size_of::<Value>();
+    match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => SLOT,
+        Value::String(text) => SLOT + text.len(),
+        Value::Array(items) => SLOT + items.iter().map(value_size).sum::<usize>(),
+        Value::Object(fields) => {
+            SLOT + fields
+                .iter()
+                .map(|(key, value)| key.len() + value_size(value))
+                .sum::<usize>()
+        }
+    }
+}
+
+/// Rough number of bytes a row takes in memory
+pub fn row_size(row: &[Value]) -> usize {
+    row.iter().map(value_size).sum()
+}
+
+/// Rows appended in order and read back by position
+pub struct SpillFile {
+    db: SqliteConnection,
+    len: usize,
+}
+
+impl SpillFile {
+    pub fn create() -> Result<Self> {
+        // An empty path opens a private database SQLite deletes on close
+        let db = SqliteConnection::open("")?;
+        db.execute_batch("CREATE TABLE rows (position INTEGER PRIMARY KEY, data TEXT NOT NULL)")?;
+        Ok(Self { db, len: 0 })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn append(&mut self, rows: &[Vec<Value>]) -> Result<()> {
+        let tx = self.db.transaction()?;
+        {
+            let mut insert =
+                tx.prepare_cached("INSERT INTO rows (position, data) VALUES (?1, ?2)")?;
+            for (offset, row) in rows.iter().enumerate() {
+                insert.execute(params![
+                    (self.len + offset) as i64,
+                    serde_json::to_string(row)?
+                ])?;
+            }
+        }
+        tx.commit()?;
+        self.len += rows.len();
+        Ok(())
+    }
+
+    /// Up to `limit` rows starting at `offset`
+    pub fn read(&self, offset: usize, limit: usize) -> Result<Vec<Vec<Value>>> {
+        let mut select = self.db.prepare_cached(
+            "SELECT data FROM rows WHERE position >= ?1 ORDER BY position LIMIT ?2",
+        )?;
+        let rows = select
+            .query_map(params![offset as i64, limit as i64], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.iter()
+            .map(|data| serde_json::from_str(data).map_err(SpillError::from))
+            .collect()
+    }
+
+    /// The rows at the given positions, in that order
+    pub fn read_positions(&self, positions: &[usize]) -> Result<Vec<Vec<Value>>> {
+        let mut select = self
+            .db
+            .prepare_cached("SELECT data FROM rows WHERE position = ?1")?;
+        positions
+            .iter()
+            .map(|&position| {
+                let data: String = select.query_row([position as i64], |row| row.get(0))?;
+                Ok(serde_json::from_str(&data)?)
+            })
+            .collect()
+    }
+}
+
+/// A spilled store shared by the query cache and result cache entries of
+/// one result
+#[derive(Clone)]
+pub struct SpilledRows(Arc<Mutex<SpillFile>>);
+
+impl SpilledRows {
+    pub fn new(file: SpillFile) -> Self {
+        Self(Arc::new(Mutex::new(file)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().map(|file| file.len()).unwrap_or_default()
+    }
+
+    pub fn read(&self, offset: usize, limit: usize) -> Result<Vec<Vec<Value>>> {
+        self.0
+            .lock()
+            .map_err(|_| SpillError::Poisoned)?
+            .read(offset, limit)
+    }
+
+    pub fn read_positions(&self, positions: &[usize]) -> Result<Vec<Vec<Value>>> {
+        self.0
+            .lock()
+            .map_err(|_| SpillError::Poisoned)?
+            .read_positions(positions)
+    }
+}
+
+impl fmt::Debug for SpilledRows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpilledRows")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rows_read_back_in_order() {
+        let mut file = SpillFile::create().unwrap();
+        let rows: Vec<Vec<Value>> = (0..25)
+            .map(|n| vec![json!(n), json!(format!("row {}", n)), Value::Null])
+            .collect();
+        file.append(&rows[..10]).unwrap();
+        file.append(&rows[10..]).unwrap();
+        assert_eq!(file.len(), 25);
+
+        let spilled = SpilledRows::new(file);
+        assert_eq!(spilled.read(8, 4).unwrap(), rows[8..12].to_vec());
+        assert_eq!(spilled.read(20, 100).unwrap(), rows[20..].to_vec());
+        assert!(spilled.read(25, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_value_size_grows_with_content() {
+        let short = vec![json!("a"), json!(1)];
+        let long = vec![json!("a".repeat(1000)), json!(1)];
+        assert!(row_size(&long) >= row_size(&short) + 999);
+        assert!(value_size(&json!({"k": [1, 2]})) > value_size(&json!(1)));
+    }
+}
@@ -0,0 +1,963 @@
+//! Helpers for inspecting SQL text without a round trip to the server.
+
+use crate::db::validate;
+use crate::models::{DatabaseType, DestructiveRisk};
+use sqlparser::ast::{Delete, Query, SetExpr, Statement};
+use sqlparser::dialect::MySqlDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer, Whitespace};
+
+/// Quote an identifier for the given dialect, escaping embedded quote characters
+pub fn quote_identifier(db_type: &DatabaseType, name: &str) -> String {
+    match db_type {
+        DatabaseType::MySQL => format!("`{}`", name.replace('`', "``")),
+        DatabaseType::PostgreSQL => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+/// Quote a string literal, escaping embedded single quotes
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// A LIKE pattern for a name pattern with `*` and `?` wildcards; without
+/// wildcards, names containing the text match. LIKE's own wildcards are
+/// escaped, so `user_id` doesn't also match `userxid`.
+pub fn like_pattern(pattern: &str) -> String {
+    let escaped: String = pattern
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '\\' | '%' | '_' => format!("\\{}", c),
+            '*' => "%".to_string(),
+            '?' => "_".to_string(),
+            c => c.to_string(),
+        })
+        .collect();
+    if pattern.contains(['*', '?']) {
+        escaped
+    } else {
+        format!("%{}%", escaped)
+    }
+}
+
+/// Statement setting the session time zone, e.g. `+00:00` or `Europe/Berlin`
+pub fn set_time_zone(db_type: &DatabaseType, time_zone: &str) -> String {
+    match db_type {
+        DatabaseType::MySQL => format!("SET time_zone = {}", quote_literal(time_zone)),
+        DatabaseType::PostgreSQL => format!("SET TIME ZONE {}", quote_literal(time_zone)),
+    }
+}
+
+/// Statement setting a session variable, or None when `name` isn't a plain
+/// variable name. Numbers and `DEFAULT` are kept as they are, other values
+/// become string literals; PostgreSQL lists like `search_path` are quoted
+/// item by item.
+pub fn set_session_variable(db_type: &DatabaseType, name: &str, value: &str) -> Option<String> {
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if !valid_name {
+        return None;
+    }
+
+    let value = value.trim();
+    let is_number = value.parse::<i64>().is_ok() || value.parse::<f64>().is_ok_and(f64::is_finite);
+    let value = if is_number || value.eq_ignore_ascii_case("DEFAULT") {
+        value.to_string()
+    } else {
+        match db_type {
+            DatabaseType::MySQL => quote_literal(&value.replace('\\', "\\\\")),
+            DatabaseType::PostgreSQL => value
+                .split(',')
+                .map(|item| quote_literal(item.trim()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    };
+    Some(match db_type {
+        DatabaseType::MySQL => format!("SET SESSION {} = {}", name, value),
+        DatabaseType::PostgreSQL => format!("SET {} = {}", name, value),
+    })
+}
+
+/// A JSON document as a literal cast to the dialect's JSON type
+pub fn json_literal(db_type: &DatabaseType, value: &serde_json::Value) -> String {
+    let text = value.to_string();
+    match db_type {
+        // Backslashes escape in MySQL strings, and JSON text is full of them
+        DatabaseType::MySQL => format!(
+            "CAST('{}' AS JSON)",
+            text.replace('\\', "\\\\").replace('\'', "''")
+        ),
+        DatabaseType::PostgreSQL => format!("{}::jsonb", quote_literal(&text)),
+    }
+}
+
+/// Broad category of a statement, decided from its leading keywords
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    /// SELECT, SHOW, DESCRIBE, EXPLAIN and other read-only statements
+    Query,
+    /// INSERT, UPDATE, DELETE, REPLACE
+    Dml,
+    /// CREATE, ALTER, DROP, TRUNCATE, RENAME
+    Ddl,
+    Begin,
+    Commit,
+    Rollback,
+    /// Anything else (SET, USE, CALL, GRANT, ...)
+    Other,
+}
+
+/// Strip leading whitespace, comments and opening parentheses
+fn skip_leading_noise(sql: &str) -> &str {
+    let mut rest = sql;
+    loop {
+        let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '(');
+        if let Some(comment) = trimmed
+            .strip_prefix("--")
+            .or_else(|| trimmed.strip_prefix('#'))
+        {
+            rest = comment.split_once('\n').map(|(_, r)| r).unwrap_or("");
+        } else if let Some(comment) = trimmed.strip_prefix("/*") {
+            rest = comment.split_once("*/").map(|(_, r)| r).unwrap_or("");
+        } else {
+            return trimmed;
+        }
+    }
+}
+
+/// Upper-cased leading words of a statement, ignoring comments
+pub fn leading_keywords(sql: &str, count: usize) -> Vec<String> {
+    skip_leading_noise(sql)
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .take(count)
+        .map(str::to_uppercase)
+        .collect()
+}
+
+/// The statement a leading WITH clause belongs to, e.g. the DELETE of
+/// `WITH c AS (SELECT 1) DELETE FROM t`. None when `sql` doesn't start with
+/// WITH or the statement after its common table expressions can't be found.
+pub fn after_with_clause(sql: &str) -> Option<&str> {
+    let dialect = validate::dialect(&DatabaseType::MySQL);
+    let tokens = Tokenizer::new(dialect.as_ref(), sql)
+        .tokenize_with_location()
+        .ok()?;
+    let mut tokens = tokens
+        .iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_)));
+    match &tokens.next()?.token {
+        Token::Word(word) if word.keyword == Keyword::WITH => {}
+        _ => return None,
+    }
+
+    // The statement starts at the first of its keywords outside the
+    // expressions' parentheses, or at a parenthesis right after one closes
+    let mut depth = 0usize;
+    let mut after_body = false;
+    for token in tokens {
+        let starts = match &token.token {
+            Token::Word(word) if depth == 0 => matches!(
+                word.keyword,
+                Keyword::SELECT
+                    | Keyword::INSERT
+                    | Keyword::UPDATE
+                    | Keyword::DELETE
+                    | Keyword::REPLACE
+                    | Keyword::TABLE
+                    | Keyword::VALUES
+            ),
+            Token::LParen => depth == 0 && after_body,
+            _ => false,
+        };
+        if starts {
+            return Some(&sql[validate::byte_offset(sql, token.span.start)..]);
+        }
+        match token.token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+        after_body = depth == 0 && token.token == Token::RParen;
+    }
+    None
+}
+
+/// Whether a statement holds a `/*! ... */` comment, whose text MySQL runs
+/// as part of the statement
+pub fn has_executable_comment(sql: &str) -> bool {
+    let dialect = validate::dialect(&DatabaseType::MySQL);
+    match Tokenizer::new(dialect.as_ref(), sql).tokenize() {
+        Ok(tokens) => tokens.iter().any(|token| match token {
+            Token::Whitespace(Whitespace::MultiLineComment(text)) => text.starts_with('!'),
+            _ => false,
+        }),
+        Err(_) => sql.contains("/*!"),
+    }
+}
+
+pub fn classify(sql: &str) -> StatementKind {
+    let words = leading_keywords(sql, 2);
+    let first = words.first().map(String::as_str).unwrap_or("");
+    let second = words.get(1).map(String::as_str).unwrap_or("");
+
+    match first {
+        // MySQL also runs UPDATE and DELETE after common table expressions
+        "WITH" => match after_with_clause(sql) {
+            Some(statement) => classify(statement),
+            None => StatementKind::Other,
+        },
+        "SELECT" | "SHOW" | "DESCRIBE" | "DESC" | "EXPLAIN" | "VALUES" | "TABLE" => {
+            StatementKind::Query
+        }
+        "INSERT" | "UPDATE" | "DELETE" | "REPLACE" | "MERGE" => StatementKind::Dml,
+        "CREATE" | "ALTER" | "DROP" | "TRUNCATE" | "RENAME" => StatementKind::Ddl,
+        "BEGIN" => StatementKind::Begin,
+        "START" if second == "TRANSACTION" => StatementKind::Begin,
+        "COMMIT" | "END" => StatementKind::Commit,
+        "ROLLBACK" if second == "TO" => StatementKind::Other,
+        "ROLLBACK" | "ABORT" => StatementKind::Rollback,
+        _ => StatementKind::Other,
+    }
+}
+
+/// The statements of a MySQL script, or the whole text when it can't be
+/// split, e.g. for an unterminated string the server will reject anyway
+pub fn split_statements(sql: &str) -> Vec<&str> {
+    match validate::statements(sql, &DatabaseType::MySQL) {
+        Ok(ranges) if !ranges.is_empty() => ranges.into_iter().map(|range| &sql[range]).collect(),
+        _ => vec![sql],
+    }
+}
+
+/// Kind of a script: that of its first statement that isn't a query, or
+/// `Query` when every statement is one
+pub fn classify_script(sql: &str) -> StatementKind {
+    split_statements(sql)
+        .into_iter()
+        .map(statement_kind)
+        .find(|kind| *kind != StatementKind::Query)
+        .unwrap_or(StatementKind::Query)
+}
+
+/// `sql` with each `/*! ... */` comment replaced by the text MySQL runs
+/// from it, i.e. without the comment markers and version number
+pub fn open_executable_comments(sql: &str) -> String {
+    let dialect = validate::dialect(&DatabaseType::MySQL);
+    let Ok(tokens) = Tokenizer::new(dialect.as_ref(), sql).tokenize_with_location() else {
+        return sql.to_string();
+    };
+    let mut offsets = validate::Offsets::new(sql);
+    let mut opened = String::with_capacity(sql.len());
+    let mut copied = 0;
+    for token in &tokens {
+        let Token::Whitespace(Whitespace::MultiLineComment(text)) = &token.token else {
+            continue;
+        };
+        let Some(body) = text.strip_prefix('!') else {
+            continue;
+        };
+        let start = offsets.at(token.span.start);
+        opened.push_str(&sql[copied..start]);
+        opened.push(' ');
+        opened.push_str(body.trim_start_matches(|c: char| c.is_ascii_digit()));
+        opened.push(' ');
+        copied = offsets.at(token.span.end);
+    }
+    opened.push_str(&sql[copied..]);
+    opened
+}
+
+/// Kind of a statement as sqlparser reads it once its `/*! ... */` comments
+/// are opened. Statements it can't parse, like MySQL's WITH ... DELETE, are
+/// classified by their leading keywords.
+pub fn statement_kind(sql: &str) -> StatementKind {
+    let sql = open_executable_comments(sql);
+    match Parser::parse_sql(&MySqlDialect {}, &sql).as_deref() {
+        Ok([statement]) => parsed_kind(statement),
+        _ => classify(&sql),
+    }
+}
+
+fn parsed_kind(statement: &Statement) -> StatementKind {
+    match statement {
+        Statement::Query(query) => match query.body.as_ref() {
+            SetExpr::Insert(_) | SetExpr::Update(_) => StatementKind::Dml,
+            _ => StatementKind::Query,
+        },
+        Statement::Insert(_)
+        | Statement::Update { .. }
+        | Statement::Delete(_)
+        | Statement::Merge { .. }
+        | Statement::LoadData { .. } => StatementKind::Dml,
+        Statement::CreateView { .. }
+        | Statement::CreateTable(_)
+        | Statement::CreateIndex(_)
+        | Statement::CreateSchema { .. }
+        | Statement::CreateDatabase { .. }
+        | Statement::CreateFunction(_)
+        | Statement::CreateProcedure { .. }
+        | Statement::CreateTrigger { .. }
+        | Statement::AlterTable { .. }
+        | Statement::AlterIndex { .. }
+        | Statement::AlterView { .. }
+        | Statement::Drop { .. }
+        | Statement::DropFunction { .. }
+        | Statement::DropProcedure { .. }
+        | Statement::DropTrigger { .. }
+        | Statement::Truncate { .. } => StatementKind::Ddl,
+        Statement::StartTransaction { .. } => StatementKind::Begin,
+        Statement::Commit { .. } => StatementKind::Commit,
+        Statement::Rollback {
+            savepoint: None, ..
+        } => StatementKind::Rollback,
+        // EXPLAIN ANALYZE runs the statement it explains
+        Statement::Explain {
+            analyze: true,
+            statement,
+            ..
+        } => parsed_kind(statement),
+        Statement::Explain { .. }
+        | Statement::ExplainTable { .. }
+        | Statement::ShowFunctions { .. }
+        | Statement::ShowVariable { .. }
+        | Statement::ShowStatus { .. }
+        | Statement::ShowVariables { .. }
+        | Statement::ShowCreate { .. }
+        | Statement::ShowColumns { .. }
+        | Statement::ShowDatabases { .. }
+        | Statement::ShowSchemas { .. }
+        | Statement::ShowTables { .. }
+        | Statement::ShowViews { .. }
+        | Statement::ShowCollation { .. } => StatementKind::Query,
+        _ => StatementKind::Other,
+    }
+}
+
+/// Whether running a script again may return a stored result: every
+/// statement has to be a plain SELECT (without a locking clause or INTO),
+/// SHOW, DESCRIBE, or an EXPLAIN that doesn't run its statement
+pub fn is_cacheable(sql: &str) -> bool {
+    split_statements(sql).into_iter().all(|statement| {
+        if has_executable_comment(statement) {
+            return false;
+        }
+        match Parser::parse_sql(&MySqlDialect {}, statement).as_deref() {
+            Ok([Statement::Query(query)]) => is_plain_query(query),
+            Ok([statement]) => {
+                parsed_kind(statement) == StatementKind::Query
+                    && !matches!(statement, Statement::Explain { analyze: true, .. })
+            }
+            _ => false,
+        }
+    })
+}
+
+fn is_plain_query(query: &Query) -> bool {
+    fn plain_body(body: &SetExpr) -> bool {
+        match body {
+            SetExpr::Select(select) => select.into.is_none(),
+            SetExpr::Query(query) => is_plain_query(query),
+            SetExpr::SetOperation { left, right, .. } => plain_body(left) && plain_body(right),
+            SetExpr::Values(_) | SetExpr::Table(_) => true,
+            SetExpr::Insert(_) | SetExpr::Update(_) => false,
+        }
+    }
+    let plain_ctes = query
+        .with
+        .as_ref()
+        .is_none_or(|with| with.cte_tables.iter().all(|cte| is_plain_query(&cte.query)));
+    query.locks.is_empty() && plain_ctes && plain_body(&query.body)
+}
+
+/// Whether a statement is safe to run on a read-only connection. Transaction
+/// control and session-level SET/USE are allowed; anything that may write is not.
+/// The server enforces read-only connections too (see `MySQLAdapter::new`);
+/// this check reports what was refused before a statement is sent, and
+/// keeps statements from lifting the server's read-only mode.
+pub fn is_read_only(sql: &str) -> bool {
+    if has_executable_comment(sql) {
+        return false;
+    }
+    match statement_kind(sql) {
+        StatementKind::Query | StatementKind::Commit | StatementKind::Rollback => true,
+        // START TRANSACTION READ WRITE overrides the session's read-only mode
+        StatementKind::Begin => !is_read_write(&words(sql)),
+        StatementKind::Dml | StatementKind::Ddl => false,
+        StatementKind::Other => match leading_keywords(sql, 1).first().map(String::as_str) {
+            Some("USE") | Some("SAVEPOINT") | Some("RELEASE") | Some("ROLLBACK") => true,
+            Some("SET") => is_session_set(sql),
+            _ => false,
+        },
+    }
+}
+
+/// Whether a SET only changes variables of this session. Every assignment
+/// counts, so `SET a = 1, GLOBAL b = 2` doesn't pass, and neither does one
+/// taking back the session's read-only transaction mode.
+fn is_session_set(sql: &str) -> bool {
+    let words = words(sql);
+    let changes_server = words.iter().any(|word| {
+        matches!(
+            word.as_str(),
+            "GLOBAL"
+                | "PERSIST"
+                | "PERSIST_ONLY"
+                | "PASSWORD"
+                | "TRANSACTION_READ_ONLY"
+                | "TX_READ_ONLY"
+        )
+    });
+    let sets_role = words
+        .windows(2)
+        .any(|pair| pair[0] == "DEFAULT" && pair[1] == "ROLE");
+    !changes_server && !sets_role && !is_read_write(&words)
+}
+
+/// The upper-cased words of a statement outside its string literals
+fn words(sql: &str) -> Vec<String> {
+    redact_literals(sql)
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .map(str::to_uppercase)
+        .collect()
+}
+
+/// Whether a statement asks for a READ WRITE transaction
+fn is_read_write(words: &[String]) -> bool {
+    words
+        .windows(2)
+        .any(|pair| pair[0] == "READ" && pair[1] == "WRITE")
+}
+
+/// Normalize a statement so repeated runs of the same query share a fingerprint.
+/// Whitespace outside string literals is collapsed and trailing semicolons dropped.
+pub fn fingerprint(sql: &str) -> String {
+    let mut normalized = String::with_capacity(sql.len());
+    let mut quote: Option<char> = None;
+    let mut pending_space = false;
+
+    for ch in sql.trim().chars() {
+        match quote {
+            Some(q) => {
+                normalized.push(ch);
+                if ch == q {
+                    quote = None;
+                }
+            }
+            None if ch.is_whitespace() => pending_space = true,
+            None => {
+                if pending_space && !normalized.is_empty() {
+                    normalized.push(' ');
+                }
+                pending_space = false;
+                if matches!(ch, '\'' | '"' | '`') {
+                    quote = Some(ch);
+                }
+                normalized.push(ch);
+            }
+        }
+    }
+
+    normalized
+        .trim_end_matches(|c: char| c == ';' || c.is_whitespace())
+        .to_string()
+}
+
+/// Replace string and numeric literals with `?`, so a statement can be logged
+/// without the values it carried. Quoted identifiers are left alone.
+pub fn redact_literals(sql: &str) -> String {
+    let mut redacted = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    // Digits directly after an identifier character are part of the name
+    let mut in_word = false;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\'' | '"' => {
+                while let Some(next) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == ch {
+                        // A doubled quote is an escaped quote, not the end
+                        if chars.peek() == Some(&ch) {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                redacted.push('?');
+                in_word = false;
+            }
+            '`' => {
+                redacted.push(ch);
+                for next in chars.by_ref() {
+                    redacted.push(next);
+                    if next == '`' {
+                        break;
+                    }
+                }
+                in_word = false;
+            }
+            c if c.is_ascii_digit() && !in_word => {
+                while chars
+                    .peek()
+                    .is_some_and(|&next| next.is_ascii_alphanumeric() || next == '.')
+                {
+                    chars.next();
+                }
+                redacted.push('?');
+            }
+            c => {
+                in_word = c.is_alphanumeric() || c == '_' || c == '$';
+                redacted.push(c);
+            }
+        }
+    }
+
+    redacted
+}
+
+/// Whether `sql` is a single SELECT reading from tables without a LIMIT or
+/// FETCH, so it could return every row of a huge table. False when it
+/// doesn't parse, or when a LIMIT can't simply be appended, e.g. before
+/// FOR UPDATE.
+pub fn is_unbounded_select(sql: &str, db_type: &DatabaseType) -> bool {
+    let Ok(statements) = Parser::parse_sql(validate::dialect(db_type).as_ref(), sql) else {
+        return false;
+    };
+    let [Statement::Query(query)] = statements.as_slice() else {
+        return false;
+    };
+    query.limit.is_none()
+        && query.fetch.is_none()
+        && query.locks.is_empty()
+        && query.for_clause.is_none()
+        && reads_tables(&query.body)
+}
+
+fn reads_tables(body: &SetExpr) -> bool {
+    match body {
+        SetExpr::Select(select) => select.into.is_none() && !select.from.is_empty(),
+        SetExpr::Query(query) => query.limit.is_none() && reads_tables(&query.body),
+        SetExpr::SetOperation { left, right, .. } => reads_tables(left) || reads_tables(right),
+        _ => false,
+    }
+}
+
+/// `sql` with a LIMIT clause appended after any trailing semicolons are
+/// dropped. It goes on its own line in case the statement ends in a comment.
+pub fn append_limit(sql: &str, limit: u32) -> String {
+    let statement = sql.trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+    format!("{}\nLIMIT {}", statement, limit)
+}
+
+/// `sql` restricted to `limit` rows after skipping `offset`, or None when it
+/// isn't a single query. A query without a LIMIT gets one appended; one that
+/// already limits or locks its rows is wrapped, so its own clauses apply
+/// before the page is taken.
+pub fn paginate(sql: &str, db_type: &DatabaseType, limit: u64, offset: u64) -> Option<String> {
+    let statements = Parser::parse_sql(validate::dialect(db_type).as_ref(), sql).ok()?;
+    let [Statement::Query(query)] = statements.as_slice() else {
+        return None;
+    };
+    // The statement's text without trailing semicolons and comments
+    let ranges = validate::statements(sql, db_type).ok()?;
+    let [range] = ranges.as_slice() else {
+        return None;
+    };
+    let statement = &sql[range.clone()];
+
+    let page = format!("LIMIT {} OFFSET {}", limit, offset);
+    if query.limit.is_none()
+        && query.offset.is_none()
+        && query.fetch.is_none()
+        && query.locks.is_empty()
+        && query.for_clause.is_none()
+    {
+        Some(format!("{} {}", statement, page))
+    } else {
+        Some(format!("SELECT * FROM ({}) AS sub {}", statement, page))
+    }
+}
+
+/// The first statement of a script that may lose data: an UPDATE or DELETE
+/// without a WHERE clause, a DROP or a TRUNCATE
+pub fn destructive_risk(sql: &str, db_type: &DatabaseType) -> Option<DestructiveRisk> {
+    // A script that can't be split is rejected by the server anyway
+    let ranges = validate::statements(sql, db_type).ok()?;
+    ranges
+        .into_iter()
+        .find_map(|range| statement_risk(&sql[range], db_type))
+}
+
+fn statement_risk(statement: &str, db_type: &DatabaseType) -> Option<DestructiveRisk> {
+    let statement = open_executable_comments(statement);
+    // Every kind of DROP counts, including those sqlparser can't parse
+    match leading_keywords(&statement, 1).first().map(String::as_str) {
+        Some("DROP") => return Some(DestructiveRisk::Drop),
+        Some("TRUNCATE") => return Some(DestructiveRisk::Truncate),
+        _ => {}
+    }
+    let dialect = validate::dialect(db_type);
+    // sqlparser doesn't take a DELETE after a WITH clause, so that one is
+    // parsed on its own
+    let parsed = Parser::parse_sql(dialect.as_ref(), &statement)
+        .or_else(|e| match after_with_clause(&statement) {
+            Some(body) => Parser::parse_sql(dialect.as_ref(), body),
+            None => Err(e),
+        })
+        .ok()?;
+    parsed_risk(parsed.first()?)
+}
+
+fn parsed_risk(statement: &Statement) -> Option<DestructiveRisk> {
+    match statement {
+        Statement::Update {
+            selection: None, ..
+        } => Some(DestructiveRisk::UpdateWithoutWhere),
+        Statement::Delete(Delete {
+            selection: None, ..
+        }) => Some(DestructiveRisk::DeleteWithoutWhere),
+        // A WITH clause wraps the UPDATE it belongs to in a query
+        Statement::Query(query) => match query.body.as_ref() {
+            SetExpr::Update(statement) => parsed_risk(statement),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts_split_and_classify() {
+        assert_eq!(
+            split_statements("SELECT 1; SELECT ';'"),
+            vec!["SELECT 1", "SELECT ';'"]
+        );
+        assert_eq!(split_statements("SELECT 'a"), vec!["SELECT 'a"]);
+        assert_eq!(
+            classify_script("SELECT 1; SHOW TABLES"),
+            StatementKind::Query
+        );
+        assert_eq!(
+            classify_script("SELECT 1; DELETE FROM t; DROP TABLE t"),
+            StatementKind::Dml
+        );
+    }
+
+    #[test]
+    fn test_scripts_classify_what_mysql_runs() {
+        assert_eq!(
+            classify_script("WITH c AS (SELECT 1) DELETE FROM t"),
+            StatementKind::Dml
+        );
+        assert_eq!(
+            classify_script("WITH c AS (SELECT 1) UPDATE t SET a = 1"),
+            StatementKind::Dml
+        );
+        assert_eq!(
+            classify_script("/*!DELETE FROM t WHERE id IN */ (SELECT 1)"),
+            StatementKind::Dml
+        );
+        assert_eq!(
+            classify_script("/*!40101 DROP TABLE t */"),
+            StatementKind::Ddl
+        );
+        assert_eq!(
+            classify_script("EXPLAIN ANALYZE SELECT 1; DESCRIBE t"),
+            StatementKind::Query
+        );
+        assert_eq!(
+            open_executable_comments("SELECT 1 /*!50100 + 1 */ /* note */"),
+            "SELECT 1   + 1   /* note */"
+        );
+    }
+
+    #[test]
+    fn test_is_cacheable() {
+        assert!(is_cacheable("SELECT * FROM t; SHOW TABLES"));
+        assert!(is_cacheable("WITH c AS (SELECT 1) SELECT * FROM c"));
+        assert!(is_cacheable("DESCRIBE t"));
+        assert!(is_cacheable("EXPLAIN SELECT * FROM t"));
+        assert!(!is_cacheable("WITH c AS (SELECT 1) DELETE FROM t"));
+        assert!(!is_cacheable("/*!DELETE FROM t WHERE id IN */ (SELECT 1)"));
+        assert!(!is_cacheable("SELECT * FROM t FOR UPDATE"));
+        assert!(!is_cacheable("SELECT id INTO @id FROM t"));
+        assert!(!is_cacheable("EXPLAIN ANALYZE SELECT * FROM t"));
+        assert!(!is_cacheable("SELECT 1; SET @a = 1"));
+    }
+
+    #[test]
+    fn test_classify_statements() {
+        assert_eq!(classify("select 1"), StatementKind::Query);
+        assert_eq!(
+            classify("  -- note\n/* x */ (SELECT 1)"),
+            StatementKind::Query
+        );
+        assert_eq!(classify("INSERT INTO t VALUES (1)"), StatementKind::Dml);
+        assert_eq!(classify("drop table t"), StatementKind::Ddl);
+        assert_eq!(classify("START TRANSACTION"), StatementKind::Begin);
+        assert_eq!(classify("begin;"), StatementKind::Begin);
+        assert_eq!(classify("COMMIT"), StatementKind::Commit);
+        assert_eq!(classify("ROLLBACK"), StatementKind::Rollback);
+        assert_eq!(classify("ROLLBACK TO SAVEPOINT a"), StatementKind::Other);
+        assert_eq!(classify("SET autocommit = 0"), StatementKind::Other);
+        assert_eq!(
+            classify("WITH c AS (SELECT 1) SELECT * FROM c"),
+            StatementKind::Query
+        );
+        assert_eq!(
+            classify("with c as (select 1) delete from t"),
+            StatementKind::Dml
+        );
+        assert_eq!(classify("WITH c AS (SELECT 1"), StatementKind::Other);
+    }
+
+    #[test]
+    fn test_is_read_only() {
+        assert!(is_read_only("SELECT * FROM users"));
+        assert!(is_read_only("SET NAMES utf8mb4"));
+        assert!(is_read_only("USE shop"));
+        assert!(!is_read_only("UPDATE users SET name = 'x'"));
+        assert!(!is_read_only("/* sneaky */ DROP TABLE users"));
+        assert!(!is_read_only("SET GLOBAL max_connections = 10"));
+        assert!(!is_read_only("GRANT SELECT ON *.* TO 'a'@'%'"));
+        assert!(!is_read_only("CALL cleanup()"));
+    }
+
+    #[test]
+    fn test_is_read_only_looks_past_the_first_keyword() {
+        assert!(is_read_only("WITH c AS (SELECT 1) SELECT * FROM c"));
+        assert!(is_read_only("WITH c (n) AS (SELECT 1) (SELECT n FROM c)"));
+        assert!(!is_read_only("WITH c AS (SELECT 1) DELETE FROM t"));
+        assert!(!is_read_only(
+            "WITH RECURSIVE c AS (SELECT 1), d AS (SELECT 2) UPDATE t SET a = 1"
+        ));
+        assert!(!is_read_only("/*!DELETE FROM t WHERE id IN */ (SELECT 1)"));
+        assert!(!is_read_only("SELECT 1 /*!50100 INTO OUTFILE '/tmp/x' */"));
+        assert!(is_read_only("SELECT '/*!' AS text /* note */"));
+        assert!(is_read_only("SET SESSION sql_mode = 'ANSI', @a = 'GLOBAL'"));
+        assert!(!is_read_only("SET SESSION a=1, GLOBAL b=2"));
+        assert!(!is_read_only("SET @@global.max_connections = 10"));
+        assert!(!is_read_only("SET DEFAULT ROLE ALL TO 'app'@'%'"));
+        assert!(!is_read_only("SET SESSION TRANSACTION READ WRITE"));
+        assert!(!is_read_only("SET transaction_read_only = 0"));
+        assert!(is_read_only("START TRANSACTION READ ONLY"));
+        assert!(is_read_only("BEGIN"));
+        assert!(!is_read_only("START TRANSACTION READ WRITE"));
+        assert!(!is_read_only(
+            "START TRANSACTION WITH CONSISTENT SNAPSHOT, READ WRITE"
+        ));
+        // EXPLAIN ANALYZE runs the statement it explains
+        assert!(is_read_only("EXPLAIN SELECT * FROM t"));
+        assert!(!is_read_only("EXPLAIN ANALYZE DELETE FROM t WHERE id = 1"));
+    }
+
+    #[test]
+    fn test_quote_identifier_per_dialect() {
+        assert_eq!(quote_identifier(&DatabaseType::MySQL, "a`b"), "`a``b`");
+        assert_eq!(
+            quote_identifier(&DatabaseType::PostgreSQL, "a\"b"),
+            "\"a\"\"b\""
+        );
+        assert_eq!(quote_literal("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn test_like_pattern() {
+        assert_eq!(like_pattern("user_id"), "%user\\_id%");
+        assert_eq!(like_pattern(" *_at "), "%\\_at");
+        assert_eq!(like_pattern("id?"), "id_");
+        assert_eq!(like_pattern("100%"), "%100\\%%");
+    }
+
+    #[test]
+    fn test_fingerprint_collapses_whitespace() {
+        assert_eq!(
+            fingerprint("  SELECT *\n  FROM users\tWHERE id = ? ;"),
+            "SELECT * FROM users WHERE id = ?"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_preserves_string_literals() {
+        assert_eq!(
+            fingerprint("SELECT 'a   b'  FROM t"),
+            "SELECT 'a   b' FROM t"
+        );
+    }
+
+    #[test]
+    fn test_set_time_zone_per_dialect() {
+        assert_eq!(
+            set_time_zone(&DatabaseType::MySQL, "+02:00"),
+            "SET time_zone = '+02:00'"
+        );
+        assert_eq!(
+            set_time_zone(&DatabaseType::PostgreSQL, "O'Higgins"),
+            "SET TIME ZONE 'O''Higgins'"
+        );
+    }
+
+    #[test]
+    fn test_set_session_variable_per_dialect() {
+        let mysql = DatabaseType::MySQL;
+        assert_eq!(
+            set_session_variable(&mysql, "sql_mode", "STRICT_ALL_TABLES,ANSI_QUOTES").unwrap(),
+            "SET SESSION sql_mode = 'STRICT_ALL_TABLES,ANSI_QUOTES'"
+        );
+        assert_eq!(
+            set_session_variable(&mysql, "max_execution_time", "5000").unwrap(),
+            "SET SESSION max_execution_time = 5000"
+        );
+        assert_eq!(
+            set_session_variable(&mysql, "sql_mode", "default").unwrap(),
+            "SET SESSION sql_mode = default"
+        );
+        assert_eq!(
+            set_session_variable(&mysql, "init", "a\\' b").unwrap(),
+            "SET SESSION init = 'a\\\\'' b'"
+        );
+        assert_eq!(
+            set_session_variable(&DatabaseType::PostgreSQL, "search_path", "app, public").unwrap(),
+            "SET search_path = 'app', 'public'"
+        );
+        assert!(set_session_variable(&mysql, "x = 1; DROP TABLE t", "1").is_none());
+        assert!(set_session_variable(&mysql, "", "1").is_none());
+    }
+
+    #[test]
+    fn test_json_literal_per_dialect() {
+        let value = serde_json::json!({"name": "O'Brien", "note": "a\"b"});
+        assert_eq!(
+            json_literal(&DatabaseType::MySQL, &value),
+            r#"CAST('{"name":"O''Brien","note":"a\\"b"}' AS JSON)"#
+        );
+        assert_eq!(
+            json_literal(&DatabaseType::PostgreSQL, &value),
+            r#"'{"name":"O''Brien","note":"a\"b"}'::jsonb"#
+        );
+    }
+
+    #[test]
+    fn test_redact_literals() {
+        assert_eq!(
+            redact_literals("SELECT * FROM t1 WHERE name = 'O''Brien' AND age > 42.5"),
+            "SELECT * FROM t1 WHERE name = ? AND age > ?"
+        );
+        assert_eq!(
+            redact_literals("INSERT INTO `log 2` VALUES (\"a\\\"b\", 0x1F, -3)"),
+            "INSERT INTO `log 2` VALUES (?, ?, -?)"
+        );
+        assert_eq!(
+            redact_literals("SELECT col_1 FROM t WHERE id = ?"),
+            "SELECT col_1 FROM t WHERE id = ?"
+        );
+    }
+
+    #[test]
+    fn test_unbounded_selects() {
+        let mysql = DatabaseType::MySQL;
+        assert!(is_unbounded_select("SELECT * FROM events", &mysql));
+        assert!(is_unbounded_select(
+            "SELECT id FROM a UNION SELECT id FROM b ORDER BY id;",
+            &mysql
+        ));
+        assert!(!is_unbounded_select(
+            "SELECT * FROM events LIMIT 10",
+            &mysql
+        ));
+        assert!(!is_unbounded_select(
+            "SELECT * FROM events LIMIT 5, 10",
+            &mysql
+        ));
+        assert!(!is_unbounded_select("SELECT NOW()", &mysql));
+        assert!(!is_unbounded_select("SELECT * FROM t FOR UPDATE", &mysql));
+        assert!(!is_unbounded_select("DELETE FROM events", &mysql));
+        assert!(!is_unbounded_select("SELECT 1; SELECT * FROM t", &mysql));
+    }
+
+    #[test]
+    fn test_append_limit() {
+        assert_eq!(
+            append_limit("SELECT * FROM t -- all rows\n ;; ", 500),
+            "SELECT * FROM t -- all rows\nLIMIT 500"
+        );
+    }
+
+    #[test]
+    fn test_destructive_risk() {
+        let risk = |sql| destructive_risk(sql, &DatabaseType::MySQL);
+        assert_eq!(
+            risk("UPDATE t SET a = 1"),
+            Some(DestructiveRisk::UpdateWithoutWhere)
+        );
+        assert_eq!(risk("update t set a = 1 where id = 2"), None);
+        assert_eq!(
+            risk("DELETE FROM t;"),
+            Some(DestructiveRisk::DeleteWithoutWhere)
+        );
+        assert_eq!(risk("DELETE FROM t WHERE id IN (1, 2)"), None);
+        assert_eq!(risk("SELECT 1; DROP TABLE t"), Some(DestructiveRisk::Drop));
+        assert_eq!(
+            risk("-- cleanup\nTRUNCATE TABLE t"),
+            Some(DestructiveRisk::Truncate)
+        );
+        assert_eq!(
+            risk("DROP PROCEDURE IF EXISTS p"),
+            Some(DestructiveRisk::Drop)
+        );
+        assert_eq!(risk("SELECT * FROM t"), None);
+        assert_eq!(risk("ALTER TABLE t ADD COLUMN c INT"), None);
+
+        assert_eq!(
+            risk("WITH x AS (SELECT 1) DELETE FROM users"),
+            Some(DestructiveRisk::DeleteWithoutWhere)
+        );
+        assert_eq!(
+            risk("WITH x AS (SELECT 1) DELETE FROM users WHERE id IN (SELECT * FROM x)"),
+            None
+        );
+        assert_eq!(
+            risk("WITH x AS (SELECT 1) UPDATE users SET a = 1"),
+            Some(DestructiveRisk::UpdateWithoutWhere)
+        );
+        assert_eq!(
+            risk("/*!DELETE FROM users */"),
+            Some(DestructiveRisk::DeleteWithoutWhere)
+        );
+    }
+
+    #[test]
+    fn test_paginate() {
+        let page = |sql| paginate(sql, &DatabaseType::MySQL, 11, 20);
+        assert_eq!(
+            page("SELECT id FROM t ORDER BY id;\n").as_deref(),
+            Some("SELECT id FROM t ORDER BY id LIMIT 11 OFFSET 20")
+        );
+        assert_eq!(
+            page("SELECT * FROM t -- all").as_deref(),
+            Some("SELECT * FROM t LIMIT 11 OFFSET 20")
+        );
+        assert_eq!(
+            page("SELECT id FROM t LIMIT 100").as_deref(),
+            Some("SELECT * FROM (SELECT id FROM t LIMIT 100) AS sub LIMIT 11 OFFSET 20")
+        );
+        assert_eq!(
+            page("SELECT * FROM (SELECT id FROM t ORDER BY id LIMIT 5) x").as_deref(),
+            Some("SELECT * FROM (SELECT id FROM t ORDER BY id LIMIT 5) x LIMIT 11 OFFSET 20")
+        );
+        assert_eq!(page("SHOW TABLES"), None);
+        assert_eq!(page("SELECT 1; SELECT 2"), None);
+    }
+}
@@ -0,0 +1,283 @@
+//! Copying a table to another database or connection: its structure as a
+//! CREATE TABLE for the target, and its rows as multi-row INSERTs.
+//!
+//! Between servers of the same kind the source's own `SHOW CREATE TABLE` is
+//! replayed, so indexes and table options survive. Foreign keys are left
+//! out, as the tables they reference may not exist on the target. For
+//! another dialect the statement is built from the column definitions.
+
+use crate::db::data_compare::{BoundStatement, SyncColumn};
+use crate::db::sql::quote_identifier;
+use crate::models::{ColumnSchema, DatabaseType, TableSchema};
+use serde_json::Value;
+
+/// Rows per INSERT statement when the options don't say
+pub const DEFAULT_COPY_BATCH_SIZE: usize = 500;
+
+/// Most placeholders MySQL accepts in one prepared statement
+const MAX_PLACEHOLDERS: usize = 65_535;
+
+/// Point a `SHOW CREATE TABLE` statement at `database.table`, without its
+/// foreign keys
+pub fn retarget_create_table(ddl: &str, database: &str, table: &str) -> Result<String, String> {
+    let not_a_table = || "Only base tables can be copied".to_string();
+    let rest = ddl.strip_prefix("CREATE TABLE `").ok_or_else(not_a_table)?;
+
+    // Skip the source's name; a doubled backtick is part of it
+    let mut chars = rest.char_indices().peekable();
+    let name_end = loop {
+        match chars.next() {
+            Some((_, '`')) if chars.next_if(|&(_, c)| c == '`').is_none() => match chars.peek() {
+                Some(&(i, _)) => break i,
+                None => return Err(not_a_table()),
+            },
+            Some(_) => {}
+            None => return Err(not_a_table()),
+        }
+    };
+
+    let mut lines: Vec<&str> = rest[name_end..]
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            !(line.starts_with("CONSTRAINT ") && line.contains(" FOREIGN KEY "))
+        })
+        .collect();
+    // The definition before the closing line may have lost its successor
+    if let Some(close) = lines.iter().rposition(|line| line.starts_with(')')) {
+        if close > 0 {
+            lines[close - 1] = lines[close - 1].trim_end_matches(',');
+        }
+    }
+
+    Ok(format!(
+        "CREATE TABLE {}.{}{}",
+        quote_identifier(&DatabaseType::MySQL, database),
+        quote_identifier(&DatabaseType::MySQL, table),
+        lines.join("\n")
+    ))
+}
+
+/// A PostgreSQL type for a MySQL column
+fn postgres_type(column: &ColumnSchema) -> String {
+    let sized = |name: &str| match column.max_length {
+        Some(length) => format!("{}({})", name, length),
+        None => name.to_string(),
+    };
+    let data_type = column.data_type.to_lowercase();
+    let base = match data_type.as_str() {
+        "tinyint" | "smallint" | "year" => "SMALLINT".to_string(),
+        "mediumint" | "int" | "integer" => "INTEGER".to_string(),
+        "bigint" | "bit" => "BIGINT".to_string(),
+        "float" => "REAL".to_string(),
+        "double" | "real" => "DOUBLE PRECISION".to_string(),
+        "decimal" | "numeric" => "NUMERIC".to_string(),
+        "char" => sized("CHAR"),
+        "varchar" => sized("VARCHAR"),
+        "json" => "JSONB".to_string(),
+        "date" => "DATE".to_string(),
+        "time" => "TIME".to_string(),
+        "datetime" | "timestamp" => "TIMESTAMP".to_string(),
+        "binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob" => {
+            "BYTEA".to_string()
+        }
+        "boolean" | "bool" => "BOOLEAN".to_string(),
+        _ => "TEXT".to_string(),
+    };
+    if column.is_auto_increment {
+        format!("{} GENERATED BY DEFAULT AS IDENTITY", base)
+    } else {
+        base
+    }
+}
+
+/// A CREATE TABLE for `database.table` in `db_type`'s dialect, from the
+/// column definitions of a MySQL table. Defaults and indexes other than the
+/// primary key aren't carried over.
+pub fn translate_create_table(
+    schema: &TableSchema,
+    db_type: &DatabaseType,
+    database: &str,
+    table: &str,
+) -> String {
+    let quote = |name: &str| quote_identifier(db_type, name);
+    let mut definitions: Vec<String> = schema
+        .columns
+        .iter()
+        .filter(|column| !column.is_generated)
+        .map(|column| {
+            let data_type = match db_type {
+                DatabaseType::PostgreSQL => postgres_type(column),
+                DatabaseType::MySQL => column.data_type.to_uppercase(),
+            };
+            let null = if column.is_nullable { "" } else { " NOT NULL" };
+            format!("  {} {}{}", quote(&column.name), data_type, null)
+        })
+        .collect();
+    if !schema.primary_keys.is_empty() {
+        let keys: Vec<String> = schema.primary_keys.iter().map(|key| quote(key)).collect();
+        definitions.push(format!("  PRIMARY KEY ({})", keys.join(", ")));
+    }
+
+    format!(
+        "CREATE TABLE {}.{} (\n{}\n)",
+        quote(database),
+        quote(table),
+        definitions.join(",\n")
+    )
+}
+
+/// How rows are read from the source table and written to the copy
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyPlan {
+    /// Generated columns are computed by the target, so they aren't copied
+    columns: Vec<SyncColumn>,
+    source: String,
+    target: String,
+}
+
+impl CopyPlan {
+    pub fn new(
+        schema: &TableSchema,
+        source_database: &str,
+        target_database: &str,
+        target_table: &str,
+    ) -> Self {
+        let qualified = |database: &str, table: &str| {
+            format!(
+                "{}.{}",
+                quote_identifier(&DatabaseType::MySQL, database),
+                quote_identifier(&DatabaseType::MySQL, table)
+            )
+        };
+        Self {
+            columns: schema
+                .columns
+                .iter()
+                .filter(|column| !column.is_generated)
+                .map(SyncColumn::from_schema)
+                .collect(),
+            source: qualified(source_database, &schema.name),
+            target: qualified(target_database, target_table),
+        }
+    }
+
+    /// Every row of the source table
+    pub fn select(&self) -> BoundStatement {
+        let columns: Vec<String> = self.columns.iter().map(SyncColumn::select_expr).collect();
+        BoundStatement {
+            sql: format!("SELECT {} FROM {}", columns.join(", "), self.source),
+            params: vec![],
+        }
+    }
+
+    /// Rows per INSERT, kept under the placeholder limit
+    pub fn batch_size(&self, requested: usize) -> usize {
+        requested
+            .min(MAX_PLACEHOLDERS / self.columns.len().max(1))
+            .max(1)
+    }
+
+    /// One INSERT of `rows` into the copy
+    pub fn insert(&self, rows: Vec<Vec<Value>>) -> BoundStatement {
+        let placeholders: Vec<&str> = self.columns.iter().map(SyncColumn::placeholder).collect();
+        let row = format!("({})", placeholders.join(", "));
+        let columns: Vec<String> = self.columns.iter().map(SyncColumn::quoted).collect();
+        BoundStatement {
+            sql: format!(
+                "INSERT INTO {} ({}) VALUES {}",
+                self.target,
+                columns.join(", "),
+                vec![row; rows.len()].join(", ")
+            ),
+            params: rows.into_iter().flatten().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(name: &str, data_type: &str, max_length: Option<i64>) -> ColumnSchema {
+        ColumnSchema {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: false,
+            default_value: None,
+            max_length,
+            is_auto_increment: false,
+            is_generated: false,
+            extra: String::new(),
+            allowed_values: None,
+        }
+    }
+
+    fn orders() -> TableSchema {
+        let mut id = column("id", "bigint", None);
+        id.is_auto_increment = true;
+        let mut note = column("note", "varchar", Some(200));
+        note.is_nullable = true;
+        TableSchema {
+            name: "orders".to_string(),
+            schema: None,
+            columns: vec![id, note, column("receipt", "blob", None)],
+            primary_keys: vec!["id".to_string()],
+            foreign_keys: vec![],
+        }
+    }
+
+    #[test]
+    fn test_retarget_drops_foreign_keys() {
+        let ddl = "CREATE TABLE `orders` (\n  `id` int NOT NULL,\n  `customer_id` int,\n  \
+                   PRIMARY KEY (`id`),\n  \
+                   CONSTRAINT `fk_customer` FOREIGN KEY (`customer_id`) REFERENCES `customers` (`id`)\n\
+                   ) ENGINE=InnoDB";
+        assert_eq!(
+            retarget_create_table(ddl, "archive", "orders_2024").unwrap(),
+            "CREATE TABLE `archive`.`orders_2024` (\n  `id` int NOT NULL,\n  `customer_id` int,\n  \
+             PRIMARY KEY (`id`)\n) ENGINE=InnoDB"
+        );
+
+        let odd = "CREATE TABLE `a``b` (\n  `id` int\n)";
+        assert_eq!(
+            retarget_create_table(odd, "db", "c").unwrap(),
+            "CREATE TABLE `db`.`c` (\n  `id` int\n)"
+        );
+        assert!(retarget_create_table("CREATE VIEW `v` AS SELECT 1", "db", "v").is_err());
+    }
+
+    #[test]
+    fn test_translate_to_postgres() {
+        assert_eq!(
+            translate_create_table(&orders(), &DatabaseType::PostgreSQL, "public", "orders"),
+            "CREATE TABLE \"public\".\"orders\" (\n  \
+             \"id\" BIGINT GENERATED BY DEFAULT AS IDENTITY NOT NULL,\n  \
+             \"note\" VARCHAR(200),\n  \
+             \"receipt\" BYTEA NOT NULL,\n  \
+             PRIMARY KEY (\"id\")\n)"
+        );
+    }
+
+    #[test]
+    fn test_batched_inserts() {
+        let plan = CopyPlan::new(&orders(), "shop", "archive", "orders");
+        assert_eq!(
+            plan.select().sql,
+            "SELECT `id`, `note`, HEX(`receipt`) AS `receipt` FROM `shop`.`orders`"
+        );
+
+        let insert = plan.insert(vec![
+            vec![json!(1), json!("gift"), json!("CAFE")],
+            vec![json!(2), Value::Null, Value::Null],
+        ]);
+        assert_eq!(
+            insert.sql,
+            "INSERT INTO `archive`.`orders` (`id`, `note`, `receipt`) VALUES \
+             (?, ?, UNHEX(?)), (?, ?, UNHEX(?))"
+        );
+        assert_eq!(insert.params.len(), 6);
+        assert_eq!(plan.batch_size(100_000), MAX_PLACEHOLDERS / 3);
+    }
+}
@@ -0,0 +1,306 @@
+//! Statements behind the table browser's pages and row counts. Filter values,
+//! the page size and the offset are bound as parameters, so every page of a
+//! table with the same kinds of filters and sort has the same statement
+//! text: it is built once and kept in a small LRU, and each pooled
+//! connection prepares it once in sqlx's statement cache.
+
+use crate::models::{FilterOperator, SortOrder, TableDataRequest, TableFilter};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Number of table statement shapes kept
+pub const TABLE_QUERY_CACHE_CAPACITY: usize = 64;
+
+/// What a table statement's text depends on; the filter values don't
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct QueryShape {
+    /// The table's database, or its schema where databases hold schemas
+    namespace: String,
+    table: String,
+    /// Column, operator and number of values of each filter
+    filters: Vec<(String, FilterOperator, usize)>,
+    sort: Option<(String, SortOrder)>,
+}
+
+/// Page and count statements of one shape
+#[derive(Debug, PartialEq)]
+pub struct TableStatements {
+    /// Ends with `LIMIT ? OFFSET ?`, bound after the filter values
+    pub page: String,
+    pub count: String,
+    /// Whether the statements filter rows at all
+    pub filtered: bool,
+}
+
+fn quote(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
+}
+
+/// The items of an IN list as typed in the filter box, e.g. `1, 2` or
+/// `'a', 'b,c'`; quotes around an item are removed
+pub fn list_items(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut chars = value.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut item = String::new();
+        match chars.peek() {
+            None => break,
+            Some(&quote) if quote == '\'' || quote == '"' => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == quote {
+                        // A doubled quote stands for the quote itself
+                        if chars.next_if_eq(&quote).is_none() {
+                            break;
+                        }
+                    }
+                    item.push(c);
+                }
+                while chars.next_if(|&c| c != ',').is_some() {}
+            }
+            Some(_) => {
+                while let Some(c) = chars.next_if(|&c| c != ',') {
+                    item.push(c);
+                }
+                item = item.trim_end().to_string();
+            }
+        }
+        items.push(item);
+        if chars.next().is_none() {
+            break;
+        }
+    }
+    items
+}
+
+/// The values a filter binds, in order
+pub fn filter_params(filter: &TableFilter) -> Vec<String> {
+    match filter.operator {
+        FilterOperator::IsNull | FilterOperator::IsNotNull => vec![],
+        FilterOperator::In | FilterOperator::NotIn => list_items(&filter.value),
+        FilterOperator::Like | FilterOperator::NotLike => vec![format!("%{}%", filter.value)],
+        _ => vec![filter.value.clone()],
+    }
+}
+
+/// Filter values of a request in the order its statements bind them
+pub fn params(request: &TableDataRequest) -> Vec<String> {
+    request
+        .filters
+        .iter()
+        .flatten()
+        .flat_map(filter_params)
+        .collect()
+}
+
+fn condition(column: &str, operator: FilterOperator, values: usize) -> String {
+    let column = quote(column);
+    let list = || vec!["?"; values.max(1)].join(", ");
+    match operator {
+        FilterOperator::Equals => format!("{} = ?", column),
+        FilterOperator::NotEquals => format!("{} != ?", column),
+        FilterOperator::GreaterThan => format!("{} > ?", column),
+        FilterOperator::LessThan => format!("{} < ?", column),
+        FilterOperator::GreaterThanOrEqual => format!("{} >= ?", column),
+        FilterOperator::LessThanOrEqual => format!("{} <= ?", column),
+        FilterOperator::Like => format!("{} LIKE ?", column),
+        FilterOperator::NotLike => format!("{} NOT LIKE ?", column),
+        // An empty list matches nothing, or everything when negated
+        FilterOperator::In if values == 0 => "FALSE".to_string(),
+        FilterOperator::NotIn if values == 0 => "TRUE".to_string(),
+        FilterOperator::In => format!("{} IN ({})", column, list()),
+        FilterOperator::NotIn => format!("{} NOT IN ({})", column, list()),
+        FilterOperator::IsNull => format!("{} IS NULL", column),
+        FilterOperator::IsNotNull => format!("{} IS NOT NULL", column),
+    }
+}
+
+fn build(shape: &QueryShape) -> TableStatements {
+    let table = format!("{}.{}", quote(&shape.namespace), quote(&shape.table));
+    let conditions: Vec<String> = shape
+        .filters
+        .iter()
+        .map(|(column, operator, values)| condition(column, *operator, *values))
+        .collect();
+    let filter = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    let order = match &shape.sort {
+        Some((column, SortOrder::Desc)) => format!(" ORDER BY {} DESC", quote(column)),
+        Some((column, SortOrder::Asc)) => format!(" ORDER BY {} ASC", quote(column)),
+        None => String::new(),
+    };
+    TableStatements {
+        page: format!(
+            "SELECT * FROM {}{}{} LIMIT ? OFFSET ?",
+            table, filter, order
+        ),
+        count: format!("SELECT COUNT(*) as count FROM {}{}", table, filter),
+        filtered: !conditions.is_empty(),
+    }
+}
+
+/// Recently used table statements, evicting the least recently used
+pub struct TableQueryCache {
+    entries: HashMap<QueryShape, Arc<TableStatements>>,
+    order: VecDeque<QueryShape>,
+    capacity: usize,
+}
+
+impl Default for TableQueryCache {
+    fn default() -> Self {
+        Self::new(TABLE_QUERY_CACHE_CAPACITY)
+    }
+}
+
+impl TableQueryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// The statements for a request, built on first use
+    pub fn get(&mut self, request: &TableDataRequest) -> Arc<TableStatements> {
+        let shape = QueryShape {
+            namespace: request
+                .schema
+                .clone()
+                .unwrap_or_else(|| request.database.clone()),
+            table: request.table.clone(),
+            filters: request
+                .filters
+                .iter()
+                .flatten()
+                .map(|filter| {
+                    let values = filter_params(filter).len();
+                    (filter.column.clone(), filter.operator, values)
+                })
+                .collect(),
+            sort: request
+                .sort_by
+                .clone()
+                .map(|column| (column, request.sort_order.unwrap_or(SortOrder::Asc))),
+        };
+
+        if let Some(statements) = self.entries.get(&shape).cloned() {
+            self.order.retain(|key| key != &shape);
+            self.order.push_back(shape);
+            return statements;
+        }
+
+        let statements = Arc::new(build(&shape));
+        if self.entries.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.order.push_back(shape.clone());
+        self.entries.insert(shape, statements.clone());
+        statements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RowCountMode;
+
+    fn filter(column: &str, operator: FilterOperator, value: &str) -> TableFilter {
+        TableFilter {
+            column: column.to_string(),
+            operator,
+            value: value.to_string(),
+        }
+    }
+
+    fn request(table: &str, filters: Vec<TableFilter>) -> TableDataRequest {
+        TableDataRequest {
+            connection_id: "test".to_string(),
+            database: "shop".to_string(),
+            schema: None,
+            table: table.to_string(),
+            page: 0,
+            page_size: 50,
+            filters: Some(filters),
+            sort_by: Some("id".to_string()),
+            sort_order: Some(SortOrder::Desc),
+            row_count: RowCountMode::Auto,
+        }
+    }
+
+    #[test]
+    fn test_list_items() {
+        assert_eq!(list_items("1, 2,3"), vec!["1", "2", "3"]);
+        assert_eq!(
+            list_items("'a', \"b,c\", 'it''s'"),
+            vec!["a", "b,c", "it's"]
+        );
+        assert_eq!(list_items(" "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_values_are_bound_not_inlined() {
+        let mut cache = TableQueryCache::default();
+        let request = request(
+            "orders",
+            vec![
+                filter("status", FilterOperator::Equals, "x' OR '1'='1"),
+                filter("id", FilterOperator::In, "1, 2, 3"),
+                filter("note", FilterOperator::Like, "gift"),
+                filter("shipped_at", FilterOperator::IsNull, ""),
+            ],
+        );
+
+        let statements = cache.get(&request);
+        assert_eq!(
+            statements.page,
+            "SELECT * FROM `shop`.`orders` WHERE `status` = ? AND `id` IN (?, ?, ?) \
+             AND `note` LIKE ? AND `shipped_at` IS NULL ORDER BY `id` DESC LIMIT ? OFFSET ?"
+        );
+        assert!(statements
+            .count
+            .starts_with("SELECT COUNT(*) as count FROM `shop`.`orders` WHERE"));
+        assert!(statements.filtered);
+        assert_eq!(
+            params(&request),
+            vec!["x' OR '1'='1", "1", "2", "3", "%gift%"]
+        );
+    }
+
+    #[test]
+    fn test_same_shape_shares_statements() {
+        let mut cache = TableQueryCache::new(2);
+        let first = cache.get(&request(
+            "orders",
+            vec![filter("status", FilterOperator::Equals, "paid")],
+        ));
+        let second = cache.get(&request(
+            "orders",
+            vec![filter("status", FilterOperator::Equals, "refunded")],
+        ));
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // A different number of IN values is a different statement
+        cache.get(&request(
+            "orders",
+            vec![filter("id", FilterOperator::In, "1")],
+        ));
+        cache.get(&request(
+            "orders",
+            vec![filter("id", FilterOperator::In, "1, 2")],
+        ));
+        assert_eq!(cache.entries.len(), 2);
+        let rebuilt = cache.get(&request(
+            "orders",
+            vec![filter("status", FilterOperator::Equals, "paid")],
+        ));
+        assert!(!Arc::ptr_eq(&first, &rebuilt));
+    }
+}
@@ -0,0 +1,278 @@
+//! Long-running operations, such as exports and seeding, run as background
+//! tasks: the command returns a task id right away, progress follows in
+//! `TASK_PROGRESS_EVENT`s, and the UI can cancel the task or list every
+//! task for a background jobs panel.
+
+use crate::models::{TaskInfo, TaskKind, TaskStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Event emitted with a `TaskInfo` as a task makes progress and when it ends
+pub const TASK_PROGRESS_EVENT: &str = "task://progress";
+
+/// Least time between two progress events of one task
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Finished tasks kept for the jobs panel; older ones are dropped
+pub const FINISHED_TASKS_KEPT: usize = 50;
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Time left for a task that did `done` of `total` units in `elapsed`,
+/// assuming it keeps its pace so far
+fn eta(elapsed: Duration, done: u64, total: u64) -> Option<Duration> {
+    if done == 0 || total == 0 {
+        return None;
+    }
+    let remaining = total.saturating_sub(done);
+    Some(elapsed.mul_f64(remaining as f64 / done as f64))
+}
+
+type Reporter = Box<dyn Fn(&TaskInfo) + Send + Sync>;
+
+/// A running task, shared by the code doing the work and the manager that
+/// lists and cancels it
+pub struct Task {
+    info: Mutex<TaskInfo>,
+    started: Instant,
+    last_report: Mutex<Option<Instant>>,
+    cancelled: AtomicBool,
+    report: Reporter,
+}
+
+impl Task {
+    pub fn id(&self) -> String {
+        self.info().id
+    }
+
+    pub fn info(&self) -> TaskInfo {
+        self.info
+            .lock()
+            .map(|info| info.clone())
+            .unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn is_running(&self) -> bool {
+        self.info().status == TaskStatus::Running
+    }
+
+    /// Record the rows and bytes processed so far, reporting them unless
+    /// the previous report was very recent. Returns false once the task is
+    /// cancelled, so the work can stop at the next convenient point.
+    pub fn progress(&self, rows: u64, bytes: u64) -> bool {
+        let info = match self.info.lock() {
+            Ok(mut info) => {
+                info.rows_processed = rows;
+                info.bytes_processed = bytes;
+                let elapsed = self.started.elapsed();
+                info.eta_ms = info
+                    .total_rows
+                    .and_then(|total| eta(elapsed, rows, total))
+                    .map(|left| left.as_millis() as u64);
+                info.clone()
+            }
+            Err(_) => return !self.is_cancelled(),
+        };
+
+        let due = match self.last_report.lock() {
+            Ok(mut last) => {
+                let due = last.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL);
+                if due {
+                    *last = Some(Instant::now());
+                }
+                due
+            }
+            Err(_) => false,
+        };
+        if due {
+            (self.report)(&info);
+        }
+        !self.is_cancelled()
+    }
+
+    /// Record how the task ended, with the rows it processed on success,
+    /// and report it. A task stopped after a cancel counts as cancelled
+    /// however its work ended.
+    pub fn finish(&self, outcome: Result<u64, String>) {
+        let info = match self.info.lock() {
+            Ok(mut info) => {
+                info.finished_at = Some(now_millis());
+                info.eta_ms = None;
+                match outcome {
+                    _ if self.is_cancelled() => info.status = TaskStatus::Cancelled,
+                    Ok(rows) => {
+                        info.status = TaskStatus::Completed;
+                        info.rows_processed = rows;
+                    }
+                    Err(error) => {
+                        info.status = TaskStatus::Failed;
+                        info.error = Some(error);
+                    }
+                }
+                info.clone()
+            }
+            Err(_) => return,
+        };
+        (self.report)(&info);
+    }
+}
+
+/// Running tasks and recently finished ones, oldest first
+#[derive(Default)]
+pub struct TaskManager {
+    tasks: Vec<Arc<Task>>,
+}
+
+impl TaskManager {
+    /// Register a new running task. `report` is called with its progress;
+    /// the caller does the work and calls `Task::finish`.
+    pub fn start(
+        &mut self,
+        kind: TaskKind,
+        description: String,
+        total_rows: Option<u64>,
+        report: impl Fn(&TaskInfo) + Send + Sync + 'static,
+    ) -> Arc<Task> {
+        let finished = self.tasks.iter().filter(|task| !task.is_running()).count();
+        let mut excess = finished.saturating_sub(FINISHED_TASKS_KEPT - 1);
+        self.tasks.retain(|task| {
+            if excess > 0 && !task.is_running() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+
+        let task = Arc::new(Task {
+            info: Mutex::new(TaskInfo {
+                id: uuid::Uuid::new_v4().to_string(),
+                kind,
+                description,
+                status: TaskStatus::Running,
+                rows_processed: 0,
+                total_rows,
+                bytes_processed: 0,
+                eta_ms: None,
+                started_at: now_millis(),
+                finished_at: None,
+                error: None,
+            }),
+            started: Instant::now(),
+            last_report: Mutex::new(None),
+            cancelled: AtomicBool::new(false),
+            report: Box::new(report),
+        });
+        self.tasks.push(task.clone());
+        task
+    }
+
+    /// Ask a running task to stop; false when there is no such running task
+    pub fn cancel(&self, id: &str) -> bool {
+        match self
+            .tasks
+            .iter()
+            .find(|task| task.is_running() && task.id() == id)
+        {
+            Some(task) => {
+                task.cancelled.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Every task, newest first
+    pub fn list(&self) -> Vec<TaskInfo> {
+        self.tasks.iter().rev().map(|task| task.info()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta() {
+        let elapsed = Duration::from_secs(10);
+        assert_eq!(eta(elapsed, 250, 1000), Some(Duration::from_secs(30)));
+        assert_eq!(eta(elapsed, 1000, 1000), Some(Duration::ZERO));
+        assert_eq!(eta(elapsed, 0, 1000), None);
+        assert_eq!(eta(elapsed, 10, 0), None);
+    }
+
+    #[test]
+    fn test_task_lifecycle() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let mut manager = TaskManager::default();
+        let task = manager.start(
+            TaskKind::Export,
+            "orders.parquet".to_string(),
+            Some(100),
+            move |info| sink.lock().unwrap().push(info.status),
+        );
+
+        // The second update comes too soon after the first to be reported
+        assert!(task.progress(10, 512));
+        assert!(task.progress(20, 1024));
+        assert_eq!(reports.lock().unwrap().len(), 1);
+        let info = &manager.list()[0];
+        assert_eq!(info.rows_processed, 20);
+        assert_eq!(info.bytes_processed, 1024);
+        assert!(info.eta_ms.is_some());
+
+        task.finish(Ok(100));
+        let info = &manager.list()[0];
+        assert_eq!(info.status, TaskStatus::Completed);
+        assert_eq!(info.rows_processed, 100);
+        assert!(info.finished_at.is_some());
+        assert_eq!(
+            *reports.lock().unwrap(),
+            vec![TaskStatus::Running, TaskStatus::Completed]
+        );
+
+        // Only running tasks can be cancelled
+        assert!(!manager.cancel(&task.id()));
+    }
+
+    #[test]
+    fn test_cancel_stops_task() {
+        let mut manager = TaskManager::default();
+        let task = manager.start(TaskKind::Seed, "users".to_string(), None, |_| {});
+        assert!(manager.cancel(&task.id()));
+        assert!(!task.progress(5, 0));
+
+        task.finish(Err("interrupted".to_string()));
+        let info = &manager.list()[0];
+        assert_eq!(info.status, TaskStatus::Cancelled);
+        assert_eq!(info.error, None);
+        assert!(!manager.cancel("missing"));
+    }
+
+    #[test]
+    fn test_old_finished_tasks_are_dropped() {
+        let mut manager = TaskManager::default();
+        let running = manager.start(TaskKind::Seed, "running".to_string(), None, |_| {});
+        for index in 0..FINISHED_TASKS_KEPT + 5 {
+            manager
+                .start(TaskKind::Export, index.to_string(), None, |_| {})
+                .finish(Ok(0));
+        }
+
+        let tasks = manager.list();
+        assert_eq!(tasks.len(), FINISHED_TASKS_KEPT + 1);
+        assert_eq!(tasks[0].description, (FINISHED_TASKS_KEPT + 4).to_string());
+        assert!(tasks.iter().any(|task| task.id == running.id()));
+    }
+}
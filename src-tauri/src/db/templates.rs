@@ -0,0 +1,494 @@
+//! Built-in catalog of admin statement templates. Each template declares its
+//! parameters and a render function that emits dialect-specific SQL; adding a
+//! template means adding an entry to [`builtin_templates`].
+//!
+//! [`table_template`] instead writes a statement against one table from its
+//! schema, for the editor to fill in.
+
+use crate::db::sql::{quote_identifier, quote_literal};
+use crate::models::{
+    DatabaseType, QueryTemplateKind, StatementTemplate, TableSchema, TemplateParam,
+};
+use std::collections::HashMap;
+
+type RenderFn = fn(&DatabaseType, &TemplateArgs) -> Result<String, String>;
+
+pub struct TemplateDefinition {
+    pub template: StatementTemplate,
+    render: RenderFn,
+}
+
+impl TemplateDefinition {
+    pub fn render(
+        &self,
+        db_type: &DatabaseType,
+        params: &HashMap<String, String>,
+    ) -> Result<String, String> {
+        let args = TemplateArgs::resolve(&self.template.params, params)?;
+        (self.render)(db_type, &args)
+    }
+}
+
+/// Parameter values after defaults are applied and required ones are checked
+pub struct TemplateArgs(HashMap<String, String>);
+
+impl TemplateArgs {
+    fn resolve(spec: &[TemplateParam], values: &HashMap<String, String>) -> Result<Self, String> {
+        let mut resolved = HashMap::new();
+        for param in spec {
+            let value = values
+                .get(&param.name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .or_else(|| param.default_value.clone());
+
+            match value {
+                Some(value) => {
+                    resolved.insert(param.name.clone(), value);
+                }
+                None if param.required => {
+                    return Err(format!("Missing required parameter: {}", param.label))
+                }
+                None => {}
+            }
+        }
+        Ok(Self(resolved))
+    }
+
+    fn get(&self, name: &str) -> &str {
+        self.0.get(name).map(String::as_str).unwrap_or_default()
+    }
+
+    fn optional(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    fn number(&self, name: &str) -> Result<u64, String> {
+        self.get(name)
+            .parse()
+            .map_err(|_| format!("Parameter {} must be a whole number", name))
+    }
+
+    /// Split a comma separated list parameter into trimmed, non-empty items
+    fn list(&self, name: &str) -> Vec<&str> {
+        self.get(name)
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
+fn param(name: &str, label: &str, required: bool, default_value: Option<&str>) -> TemplateParam {
+    TemplateParam {
+        name: name.to_string(),
+        label: label.to_string(),
+        required,
+        default_value: default_value.map(String::from),
+    }
+}
+
+fn template(
+    id: &str,
+    name: &str,
+    description: &str,
+    params: Vec<TemplateParam>,
+) -> StatementTemplate {
+    StatementTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        params,
+    }
+}
+
+pub fn builtin_templates() -> Vec<TemplateDefinition> {
+    vec![
+        TemplateDefinition {
+            template: template(
+                "create_user_with_grants",
+                "Create user with grants",
+                "Create a login and grant it privileges on a database",
+                vec![
+                    param("username", "Username", true, None),
+                    param("password", "Password", true, None),
+                    param("database", "Database", true, None),
+                    param("privileges", "Privileges", true, Some("SELECT")),
+                    param("host", "Host (MySQL)", false, Some("%")),
+                ],
+            ),
+            render: render_create_user,
+        },
+        TemplateDefinition {
+            template: template(
+                "add_index_concurrently",
+                "Add index without blocking writes",
+                "Build an index while the table stays writable",
+                vec![
+                    param("table", "Table", true, None),
+                    param("index_name", "Index name", true, None),
+                    param("columns", "Columns (comma separated)", true, None),
+                ],
+            ),
+            render: render_add_index,
+        },
+        TemplateDefinition {
+            template: template(
+                "rename_column_safely",
+                "Rename column safely",
+                "Rename a column with a short lock timeout so it fails fast instead of queueing behind long transactions",
+                vec![
+                    param("table", "Table", true, None),
+                    param("old_name", "Current name", true, None),
+                    param("new_name", "New name", true, None),
+                    param("lock_timeout_seconds", "Lock timeout (seconds)", false, Some("5")),
+                ],
+            ),
+            render: render_rename_column,
+        },
+        TemplateDefinition {
+            template: template(
+                "kill_sleeping_connections",
+                "Kill sleeping connections",
+                "Terminate sessions that have been idle longer than a threshold",
+                vec![
+                    param("idle_seconds", "Idle for at least (seconds)", true, Some("600")),
+                    param("username", "Only for user", false, None),
+                ],
+            ),
+            render: render_kill_sleeping,
+        },
+    ]
+}
+
+pub fn find_template(id: &str) -> Option<TemplateDefinition> {
+    builtin_templates()
+        .into_iter()
+        .find(|definition| definition.template.id == id)
+}
+
+fn render_create_user(db_type: &DatabaseType, args: &TemplateArgs) -> Result<String, String> {
+    let privileges = args.list("privileges").join(", ").to_uppercase();
+    let database = quote_identifier(db_type, args.get("database"));
+
+    Ok(match db_type {
+        DatabaseType::MySQL => {
+            let account = format!(
+                "{}@{}",
+                quote_literal(args.get("username")),
+                quote_literal(args.get("host"))
+            );
+            format!(
+                "CREATE USER {account} IDENTIFIED BY {};\nGRANT {privileges} ON {database}.* TO {account};",
+                quote_literal(args.get("password")),
+            )
+        }
+        DatabaseType::PostgreSQL => {
+            let role = quote_identifier(db_type, args.get("username"));
+            format!(
+                "CREATE ROLE {role} WITH LOGIN PASSWORD {};\nGRANT CONNECT ON DATABASE {database} TO {role};\nGRANT {privileges} ON ALL TABLES IN SCHEMA public TO {role};",
+                quote_literal(args.get("password")),
+            )
+        }
+    })
+}
+
+fn render_add_index(db_type: &DatabaseType, args: &TemplateArgs) -> Result<String, String> {
+    let table = quote_identifier(db_type, args.get("table"));
+    let index = quote_identifier(db_type, args.get("index_name"));
+    let columns = args
+        .list("columns")
+        .iter()
+        .map(|c| quote_identifier(db_type, c))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Ok(match db_type {
+        DatabaseType::MySQL => format!(
+            "ALTER TABLE {table} ADD INDEX {index} ({columns}), ALGORITHM=INPLACE, LOCK=NONE;"
+        ),
+        DatabaseType::PostgreSQL => {
+            format!("CREATE INDEX CONCURRENTLY {index} ON {table} ({columns});")
+        }
+    })
+}
+
+fn render_rename_column(db_type: &DatabaseType, args: &TemplateArgs) -> Result<String, String> {
+    let timeout = args.number("lock_timeout_seconds")?;
+    let table = quote_identifier(db_type, args.get("table"));
+    let old_name = quote_identifier(db_type, args.get("old_name"));
+    let new_name = quote_identifier(db_type, args.get("new_name"));
+
+    Ok(match db_type {
+        DatabaseType::MySQL => format!(
+            "SET SESSION lock_wait_timeout = {timeout};\nALTER TABLE {table} RENAME COLUMN {old_name} TO {new_name};"
+        ),
+        DatabaseType::PostgreSQL => format!(
+            "BEGIN;\nSET LOCAL lock_timeout = '{timeout}s';\nALTER TABLE {table} RENAME COLUMN {old_name} TO {new_name};\nCOMMIT;"
+        ),
+    })
+}
+
+fn render_kill_sleeping(db_type: &DatabaseType, args: &TemplateArgs) -> Result<String, String> {
+    let idle_seconds = args.number("idle_seconds")?;
+
+    Ok(match db_type {
+        DatabaseType::MySQL => {
+            let user_filter = args
+                .optional("username")
+                .map(|u| format!(" AND USER = {}", quote_literal(u)))
+                .unwrap_or_default();
+            format!(
+                "SELECT CONCAT('KILL ', ID, ';') AS kill_statement\nFROM information_schema.PROCESSLIST\nWHERE COMMAND = 'Sleep' AND TIME > {idle_seconds}{user_filter};"
+            )
+        }
+        DatabaseType::PostgreSQL => {
+            let user_filter = args
+                .optional("username")
+                .map(|u| format!(" AND usename = {}", quote_literal(u)))
+                .unwrap_or_default();
+            format!(
+                "SELECT pg_terminate_backend(pid)\nFROM pg_stat_activity\nWHERE state = 'idle' AND state_change < now() - interval '{idle_seconds} seconds' AND pid <> pg_backend_pid(){user_filter};"
+            )
+        }
+    })
+}
+
+/// Rows a generated SELECT asks for
+const TEMPLATE_SELECT_LIMIT: u32 = 100;
+
+/// Numbers the value placeholders of a statement in the dialect's style
+struct Placeholders<'a> {
+    db_type: &'a DatabaseType,
+    count: usize,
+}
+
+impl Placeholders<'_> {
+    fn next(&mut self) -> String {
+        self.count += 1;
+        match self.db_type {
+            DatabaseType::MySQL => "?".to_string(),
+            DatabaseType::PostgreSQL => format!("${}", self.count),
+        }
+    }
+}
+
+/// A statement of `kind` against `database.table`, listing every column,
+/// with placeholders for values. UPDATE and DELETE match rows on the
+/// primary key.
+pub fn table_template(
+    schema: &TableSchema,
+    db_type: &DatabaseType,
+    database: &str,
+    kind: QueryTemplateKind,
+) -> Result<String, String> {
+    let quote = |name: &str| quote_identifier(db_type, name);
+    let table = format!("{}.{}", quote(database), quote(&schema.name));
+    let mut placeholders = Placeholders { db_type, count: 0 };
+    let key_condition = |placeholders: &mut Placeholders| {
+        if schema.primary_keys.is_empty() {
+            return Err(format!(
+                "{} has no primary key; write the WHERE clause by hand",
+                schema.name
+            ));
+        }
+        let conditions: Vec<String> = schema
+            .primary_keys
+            .iter()
+            .map(|key| format!("{} = {}", quote(key), placeholders.next()))
+            .collect();
+        Ok(conditions.join(" AND "))
+    };
+
+    Ok(match kind {
+        QueryTemplateKind::Select => {
+            let columns: Vec<String> = schema.columns.iter().map(|c| quote(&c.name)).collect();
+            format!(
+                "SELECT {}\nFROM {}\nLIMIT {};",
+                columns.join(", "),
+                table,
+                TEMPLATE_SELECT_LIMIT
+            )
+        }
+        QueryTemplateKind::Insert => {
+            // Auto-increment columns are left to the server
+            let columns: Vec<String> = schema
+                .columns
+                .iter()
+                .filter(|column| !column.is_generated && !column.is_auto_increment)
+                .map(|column| quote(&column.name))
+                .collect();
+            let values: Vec<String> = columns.iter().map(|_| placeholders.next()).collect();
+            format!(
+                "INSERT INTO {} ({})\nVALUES ({});",
+                table,
+                columns.join(", "),
+                values.join(", ")
+            )
+        }
+        QueryTemplateKind::Update => {
+            // Generated columns can't be written
+            let assignments: Vec<String> = schema
+                .columns
+                .iter()
+                .filter(|column| {
+                    !column.is_generated && !schema.primary_keys.contains(&column.name)
+                })
+                .map(|column| format!("{} = {}", quote(&column.name), placeholders.next()))
+                .collect();
+            if assignments.is_empty() {
+                return Err(format!(
+                    "{} has no columns to update besides its primary key",
+                    schema.name
+                ));
+            }
+            format!(
+                "UPDATE {}\nSET {}\nWHERE {};",
+                table,
+                assignments.join(", "),
+                key_condition(&mut placeholders)?
+            )
+        }
+        QueryTemplateKind::Delete => {
+            format!(
+                "DELETE FROM {}\nWHERE {};",
+                table,
+                key_condition(&mut placeholders)?
+            )
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ColumnSchema;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_template_ids_are_unique() {
+        let templates = builtin_templates();
+        let mut ids: Vec<_> = templates.iter().map(|t| t.template.id.clone()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), templates.len());
+    }
+
+    #[test]
+    fn test_create_user_applies_defaults_per_dialect() {
+        let template = find_template("create_user_with_grants").unwrap();
+        let values = params(&[
+            ("username", "report"),
+            ("password", "pw"),
+            ("database", "app"),
+        ]);
+
+        let mysql = template.render(&DatabaseType::MySQL, &values).unwrap();
+        assert_eq!(
+            mysql,
+            "CREATE USER 'report'@'%' IDENTIFIED BY 'pw';\nGRANT SELECT ON `app`.* TO 'report'@'%';"
+        );
+
+        let postgres = template.render(&DatabaseType::PostgreSQL, &values).unwrap();
+        assert!(postgres.starts_with("CREATE ROLE \"report\" WITH LOGIN PASSWORD 'pw';"));
+    }
+
+    #[test]
+    fn test_add_index_concurrently() {
+        let template = find_template("add_index_concurrently").unwrap();
+        let values = params(&[
+            ("table", "orders"),
+            ("index_name", "idx_user"),
+            ("columns", "user_id, created_at"),
+        ]);
+
+        assert_eq!(
+            template.render(&DatabaseType::PostgreSQL, &values).unwrap(),
+            "CREATE INDEX CONCURRENTLY \"idx_user\" ON \"orders\" (\"user_id\", \"created_at\");"
+        );
+        assert!(template
+            .render(&DatabaseType::MySQL, &values)
+            .unwrap()
+            .ends_with("ALGORITHM=INPLACE, LOCK=NONE;"));
+    }
+
+    fn users() -> TableSchema {
+        let column = |name: &str, is_auto_increment: bool, is_generated: bool| ColumnSchema {
+            name: name.to_string(),
+            data_type: "varchar".to_string(),
+            is_nullable: false,
+            default_value: None,
+            max_length: None,
+            is_auto_increment,
+            is_generated,
+            extra: String::new(),
+            allowed_values: None,
+        };
+        TableSchema {
+            name: "users".to_string(),
+            schema: None,
+            columns: vec![
+                column("id", true, false),
+                column("email", false, false),
+                column("domain", false, true),
+            ],
+            primary_keys: vec!["id".to_string()],
+            foreign_keys: vec![],
+        }
+    }
+
+    #[test]
+    fn test_table_templates() {
+        let mysql = |kind| table_template(&users(), &DatabaseType::MySQL, "shop", kind).unwrap();
+        assert_eq!(
+            mysql(QueryTemplateKind::Select),
+            "SELECT `id`, `email`, `domain`\nFROM `shop`.`users`\nLIMIT 100;"
+        );
+        assert_eq!(
+            mysql(QueryTemplateKind::Insert),
+            "INSERT INTO `shop`.`users` (`email`)\nVALUES (?);"
+        );
+        assert_eq!(
+            mysql(QueryTemplateKind::Delete),
+            "DELETE FROM `shop`.`users`\nWHERE `id` = ?;"
+        );
+
+        let update = table_template(
+            &users(),
+            &DatabaseType::PostgreSQL,
+            "public",
+            QueryTemplateKind::Update,
+        );
+        assert_eq!(
+            update.unwrap(),
+            "UPDATE \"public\".\"users\"\nSET \"email\" = $1\nWHERE \"id\" = $2;"
+        );
+
+        let mut keyless = users();
+        keyless.primary_keys.clear();
+        let delete = table_template(
+            &keyless,
+            &DatabaseType::MySQL,
+            "shop",
+            QueryTemplateKind::Delete,
+        );
+        assert!(delete.unwrap_err().contains("no primary key"));
+    }
+
+    #[test]
+    fn test_missing_and_invalid_params() {
+        let template = find_template("kill_sleeping_connections").unwrap();
+        let result = template.render(&DatabaseType::MySQL, &params(&[("idle_seconds", "soon")]));
+        assert!(result.unwrap_err().contains("whole number"));
+
+        let template = find_template("rename_column_safely").unwrap();
+        let result = template.render(&DatabaseType::MySQL, &params(&[("table", "t")]));
+        assert!(result.unwrap_err().contains("Missing required parameter"));
+    }
+}
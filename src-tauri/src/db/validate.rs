@@ -0,0 +1,249 @@
+//! Finding problems in SQL before it runs
+
+use crate::models::{DatabaseType, ValidationIssue, ValidationSource};
+use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect};
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Location, Token, Tokenizer, Whitespace};
+use std::ops::Range;
+
+pub(crate) fn dialect(db_type: &DatabaseType) -> Box<dyn Dialect> {
+    match db_type {
+        DatabaseType::MySQL => Box::new(MySqlDialect {}),
+        DatabaseType::PostgreSQL => Box::new(PostgreSqlDialect {}),
+    }
+}
+
+/// Byte offset of a tokenizer location in `sql`
+pub(crate) fn byte_offset(sql: &str, location: Location) -> usize {
+    Offsets::new(sql).at(location)
+}
+
+/// Byte offsets of tokenizer locations in one text. Locations asked for in
+/// order, as a token stream's are, take a single pass over the text.
+pub(crate) struct Offsets<'a> {
+    sql: &'a str,
+    offset: usize,
+    line: u64,
+    column: u64,
+}
+
+impl<'a> Offsets<'a> {
+    pub(crate) fn new(sql: &'a str) -> Self {
+        Self {
+            sql,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub(crate) fn at(&mut self, location: Location) -> usize {
+        if (location.line, location.column) < (self.line, self.column) {
+            *self = Self::new(self.sql);
+        }
+        let sql = self.sql;
+        for ch in sql[self.offset..].chars() {
+            if self.line == location.line && self.column == location.column {
+                return self.offset;
+            }
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+            self.offset += ch.len_utf8();
+        }
+        sql.len()
+    }
+}
+
+/// 1-based line and column of a byte offset, counted in characters
+fn line_column(sql: &str, offset: usize) -> (usize, usize) {
+    let before = sql.get(..offset).unwrap_or(sql);
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+/// Split a parser message into its text and the ` at Line: 1, Column: 8`
+/// location sqlparser appends to it
+fn split_location(message: &str) -> (&str, Option<Location>) {
+    let Some((text, location)) = message.rsplit_once(" at Line: ") else {
+        return (message, None);
+    };
+    let location = location
+        .split_once(", Column: ")
+        .and_then(|(line, column)| Some(Location::new(line.parse().ok()?, column.parse().ok()?)));
+    match location {
+        Some(location) => (text, Some(location)),
+        None => (message, None),
+    }
+}
+
+/// A problem in the statement at `statement` of `sql`, located at the byte
+/// offset `position` when it's known
+pub fn issue(
+    sql: &str,
+    message: String,
+    source: ValidationSource,
+    position: Option<usize>,
+    statement: &Range<usize>,
+    server_code: Option<u16>,
+) -> ValidationIssue {
+    let (line, column) = match position.map(|offset| line_column(sql, offset)) {
+        Some((line, column)) => (Some(line), Some(column)),
+        None => (None, None),
+    };
+    ValidationIssue {
+        message,
+        source,
+        position,
+        line,
+        column,
+        statement_start: statement.start,
+        statement_end: statement.end,
+        server_code,
+    }
+}
+
+/// Byte ranges of the statements in a script, without the separating
+/// semicolons and the whitespace and comments around them. `/*! ... */`
+/// comments stay, since MySQL runs their text. Fails when the script can't
+/// be tokenized, e.g. for an unterminated string.
+pub fn statements(sql: &str, db_type: &DatabaseType) -> Result<Vec<Range<usize>>, ValidationIssue> {
+    let dialect = dialect(db_type);
+    let tokens = Tokenizer::new(dialect.as_ref(), sql)
+        .tokenize_with_location()
+        .map_err(|e| {
+            let position = byte_offset(sql, e.location);
+            issue(
+                sql,
+                e.message,
+                ValidationSource::Parser,
+                Some(position),
+                &(0..sql.len()),
+                None,
+            )
+        })?;
+
+    let mut offsets = Offsets::new(sql);
+    let mut ranges = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    for token in &tokens {
+        let in_statement = match &token.token {
+            Token::SemiColon | Token::EOF => {
+                ranges.extend(current.take());
+                false
+            }
+            Token::Whitespace(Whitespace::MultiLineComment(text)) => text.starts_with('!'),
+            Token::Whitespace(_) => false,
+            _ => true,
+        };
+        if in_statement {
+            let start = offsets.at(token.span.start);
+            let end = offsets.at(token.span.end);
+            match &mut current {
+                Some(range) => range.end = end,
+                None => current = Some(start..end),
+            }
+        }
+    }
+    ranges.extend(current);
+    Ok(ranges)
+}
+
+/// Parse the statement at `statement` of `sql`, returning the syntax error
+/// if it doesn't parse
+pub fn check_syntax(
+    sql: &str,
+    statement: Range<usize>,
+    db_type: &DatabaseType,
+) -> Option<ValidationIssue> {
+    let text = &sql[statement.clone()];
+    let error = Parser::parse_sql(dialect(db_type).as_ref(), text).err()?;
+    let message = match error {
+        ParserError::TokenizerError(message) | ParserError::ParserError(message) => message,
+        ParserError::RecursionLimitExceeded => "Statement is nested too deeply".to_string(),
+    };
+
+    let (text_message, location) = split_location(&message);
+    let position = match location {
+        Some(location) => Some(statement.start + byte_offset(text, location)),
+        // The statement ended while more was expected
+        None if message.ends_with("found: EOF") => Some(statement.end),
+        None => None,
+    };
+    Some(issue(
+        sql,
+        text_message.to_string(),
+        ValidationSource::Parser,
+        position,
+        &statement,
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(sql: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|range| &sql[range.clone()]).collect()
+    }
+
+    #[test]
+    fn test_statements_split_on_semicolons() {
+        let sql = "SELECT 1;\n-- next\nSELECT 'a;b' FROM t ;\n\n";
+        let ranges = statements(sql, &DatabaseType::MySQL).unwrap();
+        assert_eq!(texts(sql, &ranges), vec!["SELECT 1", "SELECT 'a;b' FROM t"]);
+
+        let sql = "/* note */ /*!40101 SET NAMES utf8 */; /*!DELETE FROM t */";
+        let ranges = statements(sql, &DatabaseType::MySQL).unwrap();
+        assert_eq!(
+            texts(sql, &ranges),
+            vec!["/*!40101 SET NAMES utf8 */", "/*!DELETE FROM t */"]
+        );
+    }
+
+    #[test]
+    fn test_offsets_in_any_order() {
+        let sql = "SELECT 'é';\nSELECT 2";
+        let mut offsets = Offsets::new(sql);
+        assert_eq!(offsets.at(Location::new(1, 8)), 7);
+        assert_eq!(offsets.at(Location::new(2, 8)), 20);
+        assert_eq!(offsets.at(Location::new(1, 11)), 11);
+        assert_eq!(offsets.at(Location::new(3, 1)), sql.len());
+
+        // A long script splits in one pass rather than one per token
+        let script = "SELECT 1;\n".repeat(20_000);
+        let ranges = statements(&script, &DatabaseType::MySQL).unwrap();
+        assert_eq!(ranges.len(), 20_000);
+        assert_eq!(ranges[19_999], script.len() - 10..script.len() - 2);
+    }
+
+    #[test]
+    fn test_unterminated_string_is_an_issue() {
+        let sql = "SELECT 'abc";
+        let issue = statements(sql, &DatabaseType::MySQL).unwrap_err();
+        assert_eq!(issue.source, ValidationSource::Parser);
+        assert_eq!(issue.position, Some(7));
+    }
+
+    #[test]
+    fn test_syntax_error_position() {
+        let sql = "SELECT 1;\nSELECT id FROM users WHERE";
+        let ranges = statements(sql, &DatabaseType::MySQL).unwrap();
+        assert!(check_syntax(sql, ranges[0].clone(), &DatabaseType::MySQL).is_none());
+
+        let issue = check_syntax(sql, ranges[1].clone(), &DatabaseType::MySQL).unwrap();
+        assert_eq!(issue.position, Some(sql.len()));
+        assert_eq!((issue.line, issue.column), (Some(2), Some(27)));
+        assert_eq!(issue.statement_start, 10);
+
+        let sql = "SELECT id FROM users WHERE id = = 1";
+        let issue = check_syntax(sql, 0..sql.len(), &DatabaseType::MySQL).unwrap();
+        assert_eq!(issue.position, Some(32));
+        assert!(!issue.message.contains("Line:"));
+    }
+}
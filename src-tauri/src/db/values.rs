@@ -0,0 +1,308 @@
+//! Converting MySQL values into JSON for results.
+//!
+//! Rows come back in the binary protocol, where temporal values are packed
+//! structs and DECIMALs are text. Values are decoded by column type instead
+//! of through sqlx's typed decoders, which reject some collations and don't
+//! cover TIME values outside a day.
+
+use crate::db::binary::{binary_detail_json, binary_to_json};
+use crate::models::{ColumnTypeInfo, ValueKind};
+use serde_json::Value;
+use sqlx::mysql::MySqlRow;
+use sqlx::{Column, Row, TypeInfo, ValueRef};
+
+pub fn value_kind(type_name: &str) -> ValueKind {
+    match type_name {
+        "BOOLEAN" => ValueKind::Boolean,
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "TINYINT UNSIGNED"
+        | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED" | "BIGINT UNSIGNED" => {
+            ValueKind::Integer
+        }
+        "FLOAT" | "DOUBLE" => ValueKind::Float,
+        "DECIMAL" => ValueKind::Decimal,
+        "CHAR" | "VARCHAR" | "TINYTEXT" | "TEXT" | "MEDIUMTEXT" | "LONGTEXT" => ValueKind::Text,
+        "ENUM" | "SET" => ValueKind::Enum,
+        "JSON" => ValueKind::Json,
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+            ValueKind::Binary
+        }
+        "DATE" => ValueKind::Date,
+        "TIME" => ValueKind::Time,
+        "DATETIME" | "TIMESTAMP" => ValueKind::DateTime,
+        "YEAR" => ValueKind::Year,
+        "BIT" => ValueKind::Bit,
+        "GEOMETRY" => ValueKind::Geometry,
+        _ => ValueKind::Other,
+    }
+}
+
+/// Name and type of each column of `row`
+pub fn column_types(row: &MySqlRow) -> Vec<ColumnTypeInfo> {
+    row.columns()
+        .iter()
+        .map(|column| {
+            let type_name = column.type_info().name();
+            ColumnTypeInfo {
+                name: column.name().to_string(),
+                type_name: type_name.to_string(),
+                kind: value_kind(type_name),
+                truncated: false,
+                masked: false,
+            }
+        })
+        .collect()
+}
+
+fn raw_bytes(row: &MySqlRow, index: usize) -> Option<&[u8]> {
+    row.try_get_unchecked::<Option<&[u8]>, _>(index)
+        .ok()
+        .flatten()
+}
+
+fn text(row: &MySqlRow, index: usize) -> Option<String> {
+    raw_bytes(row, index).map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn is_null(row: &MySqlRow, index: usize) -> bool {
+    row.try_get_raw(index).map_or(true, |value| value.is_null())
+}
+
+/// A packed binary-protocol value: a length byte followed by that many
+/// bytes. Returns None for anything else, such as a text-protocol value.
+fn packed(bytes: &[u8], lengths: &[usize]) -> Option<Vec<u8>> {
+    let (&len, rest) = bytes.split_first()?;
+    (len as usize == rest.len() && lengths.contains(&rest.len())).then(|| rest.to_vec())
+}
+
+fn fraction(micros: u32) -> String {
+    if micros == 0 {
+        String::new()
+    } else {
+        format!(".{:06}", micros)
+    }
+}
+
+fn micros_at(fields: &[u8], start: usize) -> u32 {
+    fields
+        .get(start..start + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .unwrap_or(0)
+}
+
+/// Format a DATE, DATETIME or TIMESTAMP value; `with_time` adds the time of day
+pub fn format_date(bytes: &[u8], with_time: bool) -> String {
+    let Some(fields) = packed(bytes, &[0, 4, 7, 11]) else {
+        return String::from_utf8_lossy(bytes).into_owned();
+    };
+    let field = |i: usize| fields.get(i).copied().unwrap_or(0);
+    let year = u16::from_le_bytes([field(0), field(1)]);
+    let date = format!("{:04}-{:02}-{:02}", year, field(2), field(3));
+    if !with_time {
+        return date;
+    }
+    format!(
+        "{} {:02}:{:02}:{:02}{}",
+        date,
+        field(4),
+        field(5),
+        field(6),
+        fraction(micros_at(&fields, 7))
+    )
+}
+
+/// Format a TIME value, which is a duration of up to 838 hours either way
+pub fn format_time(bytes: &[u8]) -> String {
+    let Some(fields) = packed(bytes, &[0, 8, 12]) else {
+        return String::from_utf8_lossy(bytes).into_owned();
+    };
+    let field = |i: usize| fields.get(i).copied().unwrap_or(0);
+    let days = micros_at(&fields, 1);
+    let hours = days * 24 + field(5) as u32;
+    format!(
+        "{}{:02}:{:02}:{:02}{}",
+        if field(0) == 1 { "-" } else { "" },
+        hours,
+        field(6),
+        field(7),
+        fraction(micros_at(&fields, 8))
+    )
+}
+
+/// Value of the column at `index` as JSON, NULL when it can't be read
+pub fn extract_value(row: &MySqlRow, index: usize) -> Value {
+    if is_null(row, index) {
+        return Value::Null;
+    }
+    let type_name = row.column(index).type_info().name();
+
+    let value = match value_kind(type_name) {
+        ValueKind::Boolean => row.try_get::<bool, _>(index).ok().map(Value::from),
+        ValueKind::Integer if type_name.ends_with("UNSIGNED") => {
+            row.try_get::<u64, _>(index).ok().map(Value::from)
+        }
+        ValueKind::Integer => row.try_get::<i64, _>(index).ok().map(Value::from),
+        ValueKind::Year => row.try_get::<u16, _>(index).ok().map(Value::from),
+        // FLOATs arrive as 4 bytes; going through their shortest text keeps
+        // 1.1 from turning into 1.100000023841858
+        ValueKind::Float if type_name == "FLOAT" => row
+            .try_get::<f32, _>(index)
+            .ok()
+            .and_then(|f| f.to_string().parse::<f64>().ok())
+            .map(Value::from),
+        ValueKind::Float => row.try_get::<f64, _>(index).ok().map(Value::from),
+        ValueKind::Json => {
+            text(row, index).map(|text| serde_json::from_str(&text).unwrap_or(Value::String(text)))
+        }
+        ValueKind::Binary | ValueKind::Geometry => raw_bytes(row, index).map(binary_to_json),
+        ValueKind::Date => raw_bytes(row, index).map(|bytes| format_date(bytes, false).into()),
+        ValueKind::DateTime => raw_bytes(row, index).map(|bytes| format_date(bytes, true).into()),
+        ValueKind::Time => raw_bytes(row, index).map(|bytes| format_time(bytes).into()),
+        ValueKind::Bit => raw_bytes(row, index).map(|bytes| {
+            Value::from(
+                bytes
+                    .iter()
+                    .fold(0u64, |number, &byte| (number << 8) | byte as u64),
+            )
+        }),
+        ValueKind::Decimal | ValueKind::Text | ValueKind::Enum | ValueKind::Other => {
+            text(row, index).map(Value::String)
+        }
+    };
+
+    value.unwrap_or(Value::Null)
+}
+
+/// Like `extract_value`, but keeping binary values whole; also returns the
+/// size of text, JSON and binary values in bytes
+pub fn extract_full_value(row: &MySqlRow, index: usize) -> (Value, Option<u64>) {
+    match value_kind(row.column(index).type_info().name()) {
+        ValueKind::Binary | ValueKind::Geometry => match raw_bytes(row, index) {
+            Some(bytes) => (binary_detail_json(bytes), Some(bytes.len() as u64)),
+            None => (Value::Null, None),
+        },
+        ValueKind::Json => {
+            let size = raw_bytes(row, index).map(|bytes| bytes.len() as u64);
+            (extract_value(row, index), size)
+        }
+        _ => {
+            let value = extract_value(row, index);
+            let size = value.as_str().map(|text| text.len() as u64);
+            (value, size)
+        }
+    }
+}
+
+/// Cut a text value, or a JSON document as its text, to its first
+/// `max_chars` characters. Returns whether it was cut.
+pub fn truncate_value(value: &mut Value, max_chars: usize) -> bool {
+    let cut = |text: &str| {
+        text.char_indices()
+            .nth(max_chars)
+            .map(|(end, _)| text[..end].to_string())
+    };
+    let cut = match value {
+        Value::String(text) => cut(text),
+        Value::Array(_) | Value::Object(_) => cut(&value.to_string()),
+        _ => None,
+    };
+    match cut {
+        Some(text) => {
+            *value = Value::String(text);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether values of a column can be long enough to cut for listings
+pub fn is_truncatable(column: &ColumnTypeInfo) -> bool {
+    matches!(column.kind, ValueKind::Text | ValueKind::Json)
+}
+
+/// Cut the long text and JSON values of `rows`, flagging the columns that
+/// had any cut
+pub fn truncate_rows(rows: &mut [Vec<Value>], columns: &mut [ColumnTypeInfo], max_chars: usize) {
+    for (index, column) in columns.iter_mut().enumerate() {
+        if !is_truncatable(column) {
+            continue;
+        }
+        for row in rows.iter_mut() {
+            if let Some(value) = row.get_mut(index) {
+                column.truncated |= truncate_value(value, max_chars);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_kinds() {
+        assert_eq!(value_kind("BIGINT UNSIGNED"), ValueKind::Integer);
+        assert_eq!(value_kind("DECIMAL"), ValueKind::Decimal);
+        assert_eq!(value_kind("TIMESTAMP"), ValueKind::DateTime);
+        assert_eq!(value_kind("LONGBLOB"), ValueKind::Binary);
+        assert_eq!(value_kind("MEDIUMTEXT"), ValueKind::Text);
+        assert_eq!(value_kind("SET"), ValueKind::Enum);
+        assert_eq!(value_kind("NULL"), ValueKind::Other);
+    }
+
+    #[test]
+    fn test_format_date() {
+        // 2024-02-29 13:05:09.000250
+        let datetime = [11, 0xE8, 0x07, 2, 29, 13, 5, 9, 0xFA, 0, 0, 0];
+        assert_eq!(format_date(&datetime, true), "2024-02-29 13:05:09.000250");
+
+        let date = [4, 0xE8, 0x07, 2, 29];
+        assert_eq!(format_date(&date, false), "2024-02-29");
+        assert_eq!(format_date(&date, true), "2024-02-29 00:00:00");
+        assert_eq!(format_date(&[0], false), "0000-00-00");
+
+        // Text-protocol values pass through
+        assert_eq!(format_date(b"2024-02-29", false), "2024-02-29");
+    }
+
+    #[test]
+    fn test_format_time_beyond_a_day() {
+        // -2 days 3:04:05 is -51:04:05
+        let time = [8, 1, 2, 0, 0, 0, 3, 4, 5];
+        assert_eq!(format_time(&time), "-51:04:05");
+
+        let time = [12, 0, 0, 0, 0, 0, 23, 59, 59, 0x90, 0xD0, 0x03, 0];
+        assert_eq!(format_time(&time), "23:59:59.250000");
+        assert_eq!(format_time(&[0]), "00:00:00");
+    }
+
+    #[test]
+    fn test_truncate_rows() {
+        let column = |name: &str, kind| ColumnTypeInfo {
+            name: name.to_string(),
+            type_name: String::new(),
+            kind,
+            truncated: false,
+            masked: false,
+        };
+        let mut columns = vec![
+            column("id", ValueKind::Integer),
+            column("body", ValueKind::Text),
+            column("doc", ValueKind::Json),
+            column("title", ValueKind::Text),
+        ];
+        let mut rows = vec![vec![
+            Value::from(123456),
+            Value::from("héllo wörld"),
+            serde_json::json!({"key": "value"}),
+            Value::from("short"),
+        ]];
+
+        truncate_rows(&mut rows, &mut columns, 5);
+        assert_eq!(rows[0][0], Value::from(123456));
+        assert_eq!(rows[0][1], Value::from("héllo"));
+        assert_eq!(rows[0][2], Value::from("{\"key"));
+        assert_eq!(rows[0][3], Value::from("short"));
+        let truncated: Vec<bool> = columns.iter().map(|c| c.truncated).collect();
+        assert_eq!(truncated, vec![false, true, true, false]);
+    }
+}
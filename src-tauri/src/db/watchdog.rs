@@ -0,0 +1,73 @@
+//! Watches sessions for transactions left open too long, the usual cause of
+//! a table staying locked overnight.
+
+use crate::db::session::SessionManager;
+use crate::models::{IdleTransactionWarning, WatchdogConfig};
+use std::time::Duration;
+
+/// How often open sessions are inspected
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Event emitted for each transaction that crossed the threshold
+pub const IDLE_TRANSACTION_EVENT: &str = "session://idle-transaction";
+
+/// Inspect every session and report transactions open longer than the
+/// configured threshold. Each transaction is reported once; with
+/// `auto_rollback` it is rolled back instead of left open.
+pub async fn check_sessions(
+    sessions: &SessionManager,
+    config: &WatchdogConfig,
+) -> Vec<IdleTransactionWarning> {
+    let mut warnings = Vec::new();
+    if !config.enabled {
+        return warnings;
+    }
+    let threshold = Duration::from_secs(config.threshold_secs);
+
+    let sessions = match sessions.list() {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            tracing::warn!(error = %e, "Transaction watchdog could not list sessions");
+            return warnings;
+        }
+    };
+
+    for (session_id, session) in sessions {
+        // A busy session is running a statement right now, so it isn't idle
+        let Ok(mut session) = session.try_lock() else {
+            continue;
+        };
+        let open_for = match session.transaction.open_for() {
+            Some(open_for) if open_for >= threshold => open_for,
+            _ => continue,
+        };
+
+        let state = session.state(&session_id);
+        let rolled_back = if config.auto_rollback {
+            match session.execute("ROLLBACK", None, &[]).await {
+                Ok(_) => true,
+                Err(e) => {
+                    tracing::warn!(session_id = %session_id, error = %e, "Failed to roll back idle session");
+                    false
+                }
+            }
+        } else {
+            false
+        };
+
+        // Without a rollback the transaction stays open; only warn about it once
+        if !rolled_back && !session.transaction.flag() {
+            continue;
+        }
+
+        warnings.push(IdleTransactionWarning {
+            session_id,
+            connection_id: state.connection_id,
+            open_for_ms: open_for.as_millis() as u64,
+            statements_in_transaction: state.statements_in_transaction,
+            rolled_back,
+        });
+    }
+
+    warnings
+}
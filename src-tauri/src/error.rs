@@ -1,28 +1,317 @@
+use crate::db::client_config::ClientConfigError;
+use crate::db::export::ExportError;
+use crate::db::mysql_adapter::DatabaseError;
+use crate::db::result_cache::ResultError;
+use crate::logging::LogError;
+use crate::models::DestructiveRisk;
+use crate::ssh::config::SshConfigError;
+use crate::storage::connection_store::StoreError;
+use crate::storage::importers::ImportError;
+use serde::{Deserialize, Serialize, Serializer};
+use std::sync::PoisonError;
 use thiserror::Error;
 
+/// Error returned by every command. Serializes to an `ErrorPayload` so the UI
+/// can react to the kind of failure instead of matching on messages.
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Storage error: {0}")]
-    Storage(#[from] crate::storage::connection_store::StoreError),
+    Storage(#[from] StoreError),
 
     #[error("Encryption error: {0}")]
     Encryption(#[from] crate::storage::encryption::EncryptionError),
 
-    #[error("Database error: {0}")]
-    Database(String),
+    #[error("{0}")]
+    Database(#[from] DatabaseError),
 
-    #[error("Connection error: {0}")]
-    Connection(String),
+    #[error("{0}")]
+    Result(#[from] ResultError),
+
+    #[error("{0}")]
+    Import(#[from] ImportError),
+
+    #[error("{0}")]
+    Export(#[from] ExportError),
+
+    #[error("{0}")]
+    SshConfig(#[from] SshConfigError),
+
+    #[error("{0}")]
+    ClientConfig(#[from] ClientConfigError),
+
+    #[error("{0}")]
+    Log(#[from] LogError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
 
     #[error("Not found: {0}")]
     NotFound(String),
+
+    #[error("{0}")]
+    InvalidInput(String),
+
+    #[error("{}", .0.description())]
+    NeedsConfirmation(DestructiveRisk),
+
+    /// The connection's environment policy forbids the statement
+    #[error("{0}")]
+    Guardrail(String),
+
+    /// The connection's environment requires its name typed to run a write
+    #[error("Type the connection name \"{0}\" to confirm this change")]
+    TypedConfirmation(String),
+
+    #[error("Internal error: {0}")]
+    Internal(String),
 }
 
 #[allow(dead_code)]
 pub type Result<T> = std::result::Result<T, AppError>;
 
-impl From<AppError> for String {
-    fn from(error: AppError) -> Self {
-        error.to_string()
+/// What kind of problem an error is, for choosing how the UI presents it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ErrorCategory {
+    /// Wrong credentials or authentication method
+    Auth,
+    /// The server can't be reached or the connection dropped
+    Network,
+    Syntax,
+    /// A unique, foreign key or NOT NULL constraint rejected the change
+    Constraint,
+    Timeout,
+    /// The user lacks a privilege, or the connection is read-only
+    Permission,
+    NotFound,
+    Validation,
+    /// The data changed since it was read
+    Conflict,
+    Internal,
+}
+
+/// Serialized form of an `AppError`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ErrorPayload {
+    /// Stable identifier of the error kind, e.g. `DATABASE_SERVER`
+    pub code: String,
+    pub category: ErrorCategory,
+    pub message: String,
+    pub sqlstate: Option<String>,
+    /// Server error number, e.g. 1062 for a duplicate key in MySQL
+    pub server_code: Option<u16>,
+    /// Byte offset into the statement where the error was found
+    pub position: Option<usize>,
+    /// Extra context, such as a statement that would fix the problem
+    pub detail: Option<String>,
+    /// Why the statement needs confirmation before it runs
+    pub risk: Option<DestructiveRisk>,
+}
+
+/// Category of a MySQL server error, from its error number and SQLSTATE
+fn server_category(number: u16, sqlstate: Option<&str>) -> ErrorCategory {
+    match number {
+        // Access denied for user, plugin or password problems
+        1045 | 1251 | 1698 | 1862 | 2049 => ErrorCategory::Auth,
+        // Unknown database, table or column
+        1049 | 1051 | 1054 | 1146 => ErrorCategory::NotFound,
+        // Statement or lock wait timeouts
+        1205 | 3024 => ErrorCategory::Timeout,
+        // Server gone away or lost during query
+        1053 | 2002 | 2003 | 2006 | 2013 => ErrorCategory::Network,
+        // Server in read-only mode
+        1290 | 1792 => ErrorCategory::Permission,
+        _ => match sqlstate.map(|state| state.get(..2).unwrap_or(state)) {
+            Some("23") => ErrorCategory::Constraint,
+            Some("42") => ErrorCategory::Syntax,
+            Some("08") => ErrorCategory::Network,
+            Some("28") => ErrorCategory::Auth,
+            _ => ErrorCategory::Internal,
+        },
+    }
+}
+
+impl AppError {
+    pub fn payload(&self) -> ErrorPayload {
+        let mut payload = ErrorPayload {
+            code: String::new(),
+            category: ErrorCategory::Internal,
+            message: self.to_string(),
+            sqlstate: None,
+            server_code: None,
+            position: None,
+            detail: None,
+            risk: None,
+        };
+
+        let (code, category) = match self {
+            AppError::Database(error) => match error {
+                DatabaseError::Connection(_) => ("DATABASE_CONNECTION", ErrorCategory::Network),
+                DatabaseError::Query(_) => ("DATABASE_QUERY", ErrorCategory::Internal),
+                DatabaseError::Server {
+                    message,
+                    number,
+                    sqlstate,
+                    position,
+                } => {
+                    payload.message = message.clone();
+                    payload.server_code = Some(*number);
+                    payload.sqlstate = sqlstate.clone();
+                    payload.position = *position;
+                    (
+                        "DATABASE_SERVER",
+                        server_category(*number, sqlstate.as_deref()),
+                    )
+                }
+                DatabaseError::Timeout(_) => ("DATABASE_TIMEOUT", ErrorCategory::Timeout),
+                DatabaseError::Io(_) => ("IO", ErrorCategory::Internal),
+                DatabaseError::ReadOnly(_) => ("READ_ONLY", ErrorCategory::Permission),
+                DatabaseError::Conflict(_) => ("ROW_CONFLICT", ErrorCategory::Conflict),
+                DatabaseError::PermissionDenied { message, missing } => {
+                    payload.message = message.clone();
+                    payload.detail = missing.grant_statement.clone();
+                    ("PERMISSION_DENIED", ErrorCategory::Permission)
+                }
+            },
+            AppError::Storage(StoreError::NotFound(_) | StoreError::GroupNotFound(_)) => {
+                ("NOT_FOUND", ErrorCategory::NotFound)
+            }
+            AppError::Storage(StoreError::Locked) => ("STORE_LOCKED", ErrorCategory::Auth),
+            AppError::Storage(StoreError::WrongPassword) => ("WRONG_PASSWORD", ErrorCategory::Auth),
+            AppError::Storage(_) => ("STORAGE", ErrorCategory::Internal),
+            AppError::Encryption(_) => ("ENCRYPTION", ErrorCategory::Internal),
+            AppError::Result(ResultError::NotFound(_)) => ("NOT_FOUND", ErrorCategory::NotFound),
+            AppError::Result(ResultError::UnknownColumn(_)) => {
+                ("INVALID_INPUT", ErrorCategory::Validation)
+            }
+            AppError::Result(ResultError::Spilled(_)) => {
+                ("RESULT_TOO_LARGE", ErrorCategory::Validation)
+            }
+            AppError::Result(ResultError::Spill(_)) => ("IO", ErrorCategory::Internal),
+            AppError::Result(ResultError::Masked(_)) => ("MASKED", ErrorCategory::Validation),
+            AppError::Result(ResultError::InvalidFilter(_)) => {
+                ("INVALID_FILTER", ErrorCategory::Validation)
+            }
+            AppError::Import(_) => ("IMPORT", ErrorCategory::Validation),
+            AppError::Export(_) => ("EXPORT", ErrorCategory::Internal),
+            AppError::SshConfig(_) => ("SSH_CONFIG", ErrorCategory::Validation),
+            AppError::ClientConfig(_) => ("CLIENT_CONFIG", ErrorCategory::Validation),
+            AppError::Log(_) => ("LOG", ErrorCategory::Internal),
+            AppError::Io(_) => ("IO", ErrorCategory::Internal),
+            AppError::NotFound(_) => ("NOT_FOUND", ErrorCategory::NotFound),
+            AppError::InvalidInput(_) => ("INVALID_INPUT", ErrorCategory::Validation),
+            AppError::NeedsConfirmation(risk) => {
+                payload.risk = Some(*risk);
+                ("NEEDS_CONFIRMATION", ErrorCategory::Validation)
+            }
+            AppError::Guardrail(_) => ("GUARDRAIL", ErrorCategory::Permission),
+            AppError::TypedConfirmation(_) => {
+                ("NEEDS_TYPED_CONFIRMATION", ErrorCategory::Validation)
+            }
+            AppError::Internal(_) => ("INTERNAL", ErrorCategory::Internal),
+        };
+
+        payload.code = code.to_string();
+        payload.category = category;
+        payload
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.payload().serialize(serializer)
+    }
+}
+
+impl<T> From<PoisonError<T>> for AppError {
+    fn from(error: PoisonError<T>) -> Self {
+        AppError::Internal(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MissingPrivilege;
+
+    #[test]
+    fn test_server_error_payload() {
+        let error = AppError::from(DatabaseError::Server {
+            message: "Duplicate entry '1' for key 'PRIMARY'".to_string(),
+            number: 1062,
+            sqlstate: Some("23000".to_string()),
+            position: None,
+        });
+
+        let payload = error.payload();
+        assert_eq!(payload.code, "DATABASE_SERVER");
+        assert_eq!(payload.category, ErrorCategory::Constraint);
+        assert_eq!(payload.server_code, Some(1062));
+        assert_eq!(payload.sqlstate.as_deref(), Some("23000"));
+        assert_eq!(payload.message, "Duplicate entry '1' for key 'PRIMARY'");
+    }
+
+    #[test]
+    fn test_server_categories() {
+        assert_eq!(server_category(1045, Some("28000")), ErrorCategory::Auth);
+        assert_eq!(server_category(1064, Some("42000")), ErrorCategory::Syntax);
+        assert_eq!(
+            server_category(1146, Some("42S02")),
+            ErrorCategory::NotFound
+        );
+        assert_eq!(server_category(3024, Some("HY000")), ErrorCategory::Timeout);
+        assert_eq!(
+            server_category(1452, Some("23000")),
+            ErrorCategory::Constraint
+        );
+        assert_eq!(server_category(9999, None), ErrorCategory::Internal);
+    }
+
+    #[test]
+    fn test_permission_denied_carries_grant() {
+        let error = AppError::from(DatabaseError::PermissionDenied {
+            message: "DELETE command denied".to_string(),
+            missing: Box::new(MissingPrivilege {
+                privilege: "DELETE".to_string(),
+                database: Some("shop".to_string()),
+                table: Some("orders".to_string()),
+                column: None,
+                routine: None,
+                username: Some("app".to_string()),
+                host: Some("%".to_string()),
+                grant_statement: Some("GRANT DELETE ON `shop`.`orders` TO 'app'@'%'".to_string()),
+            }),
+        });
+
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["category"], "Permission");
+        assert_eq!(json["code"], "PERMISSION_DENIED");
+        assert_eq!(
+            json["detail"],
+            "GRANT DELETE ON `shop`.`orders` TO 'app'@'%'"
+        );
+    }
+
+    #[test]
+    fn test_needs_confirmation_carries_risk() {
+        let json =
+            serde_json::to_value(AppError::NeedsConfirmation(DestructiveRisk::Truncate)).unwrap();
+        assert_eq!(json["code"], "NEEDS_CONFIRMATION");
+        assert_eq!(json["risk"], "Truncate");
+        assert_eq!(json["message"], "TRUNCATE removes every row of the table");
+    }
+
+    #[test]
+    fn test_not_found_from_store() {
+        let error = AppError::from(StoreError::NotFound("abc".to_string()));
+        let payload = error.payload();
+        assert_eq!(payload.category, ErrorCategory::NotFound);
+        assert_eq!(payload.message, "Storage error: Connection not found: abc");
+    }
+
+    #[test]
+    fn test_store_locked_payload() {
+        let payload = AppError::from(StoreError::Locked).payload();
+        assert_eq!(payload.code, "STORE_LOCKED");
+        assert_eq!(payload.category, ErrorCategory::Auth);
     }
 }
@@ -0,0 +1,238 @@
+//! Structured application log, written as JSON lines to daily files in the
+//! app data directory so users can see why a connection or query failed.
+
+use crate::models::{LogEntry, LogLevel};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+const LOG_FILE_PREFIX: &str = "dbclient";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Daily files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+#[derive(Error, Debug)]
+pub enum LogError {
+    #[error("Failed to open log file: {0}")]
+    Appender(#[from] tracing_appender::rolling::InitError),
+
+    #[error("Failed to install logger: {0}")]
+    Install(#[from] tracing_subscriber::util::TryInitError),
+
+    #[error("Failed to change log level: {0}")]
+    Reload(#[from] reload::Error),
+
+    #[error("Failed to read logs: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, LogError>;
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Events from this app at `level`, and only warnings from dependencies.
+/// sqlx is silenced because it logs statements with their literals.
+fn targets(level: LogLevel) -> Targets {
+    Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), LevelFilter::from(level))
+        .with_target("sqlx", LevelFilter::OFF)
+        .with_default(LevelFilter::WARN)
+}
+
+/// Handle to the installed logger
+pub struct LogHandle {
+    dir: PathBuf,
+    filter: reload::Handle<Targets, Registry>,
+}
+
+/// Install the global logger, writing to rotating files in `dir`
+pub fn init(dir: &Path, level: LogLevel) -> Result<LogHandle> {
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(dir)?;
+
+    let (filter, handle) = reload::Layer::new(targets(level));
+    let file_layer = fmt::layer()
+        .json()
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_writer(appender);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .try_init()?;
+
+    Ok(LogHandle {
+        dir: dir.to_path_buf(),
+        filter: handle,
+    })
+}
+
+impl LogHandle {
+    pub fn set_level(&self, level: LogLevel) -> Result<()> {
+        self.filter.reload(targets(level)).map_err(LogError::from)
+    }
+
+    /// See `read_logs`
+    pub fn read(&self, level: LogLevel, limit: usize) -> Result<Vec<LogEntry>> {
+        read_logs(&self.dir, level, limit)
+    }
+}
+
+/// A line as written by the JSON formatter
+#[derive(Deserialize)]
+struct RawEntry {
+    timestamp: String,
+    level: String,
+    target: String,
+    #[serde(default)]
+    fields: Map<String, Value>,
+}
+
+fn parse_level(level: &str) -> Option<LogLevel> {
+    match level {
+        "ERROR" => Some(LogLevel::Error),
+        "WARN" => Some(LogLevel::Warn),
+        "INFO" => Some(LogLevel::Info),
+        "DEBUG" => Some(LogLevel::Debug),
+        "TRACE" => Some(LogLevel::Trace),
+        _ => None,
+    }
+}
+
+fn parse_line(line: &str) -> Option<LogEntry> {
+    let raw: RawEntry = serde_json::from_str(line).ok()?;
+    let mut fields = raw.fields;
+    let message = match fields.remove("message") {
+        Some(Value::String(message)) => message,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+
+    Some(LogEntry {
+        timestamp: raw.timestamp,
+        level: parse_level(&raw.level)?,
+        target: raw.target,
+        message,
+        fields,
+    })
+}
+
+/// The newest `limit` entries at `level` or more severe, newest first.
+/// Lines that aren't log entries are skipped.
+pub fn read_logs(dir: &Path, level: LogLevel, limit: usize) -> Result<Vec<LogEntry>> {
+    let prefix = format!("{}.", LOG_FILE_PREFIX);
+    let suffix = format!(".{}", LOG_FILE_SUFFIX);
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(&suffix))
+        })
+        .collect();
+    // File names end in the date, so name order is chronological
+    files.sort();
+
+    let mut entries = Vec::new();
+    for file in files.iter().rev() {
+        let contents = fs::read_to_string(file)?;
+        for entry in contents.lines().rev().filter_map(parse_line) {
+            if entries.len() >= limit {
+                return Ok(entries);
+            }
+            if entry.level <= level {
+                entries.push(entry);
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn line(timestamp: &str, level: &str, message: &str) -> String {
+        format!(
+            r#"{{"timestamp":"{}","level":"{}","fields":{{"message":"{}","connection_id":"c1"}},"target":"dbclient::db::pool_cache"}}"#,
+            timestamp, level, message
+        )
+    }
+
+    #[test]
+    fn test_read_logs_newest_first_across_files() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("dbclient.2026-01-01.log"),
+            [
+                line("2026-01-01T10:00:00Z", "INFO", "first"),
+                line("2026-01-01T11:00:00Z", "WARN", "second"),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("dbclient.2026-01-02.log"),
+            format!(
+                "{}\nnot json\n",
+                line("2026-01-02T09:00:00Z", "ERROR", "third")
+            ),
+        )
+        .unwrap();
+        fs::write(temp.path().join("other.txt"), "ignored").unwrap();
+
+        let entries = read_logs(temp.path(), LogLevel::Trace, 10).unwrap();
+        let messages: Vec<&str> = entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["third", "second", "first"]);
+        assert_eq!(entries[0].level, LogLevel::Error);
+        assert_eq!(entries[0].fields["connection_id"], "c1");
+        assert!(!entries[0].fields.contains_key("message"));
+    }
+
+    #[test]
+    fn test_read_logs_filters_level_and_limits() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("dbclient.2026-01-01.log"),
+            [
+                line("2026-01-01T10:00:00Z", "DEBUG", "noise"),
+                line("2026-01-01T10:01:00Z", "WARN", "slow"),
+                line("2026-01-01T10:02:00Z", "ERROR", "failed"),
+                line("2026-01-01T10:03:00Z", "INFO", "connected"),
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let entries = read_logs(temp.path(), LogLevel::Warn, 10).unwrap();
+        let messages: Vec<&str> = entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["failed", "slow"]);
+
+        let entries = read_logs(temp.path(), LogLevel::Trace, 1).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].message, "connected");
+    }
+}
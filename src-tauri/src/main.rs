@@ -4,20 +4,61 @@
 mod commands;
 mod db;
 mod error;
+mod logging;
 mod models;
+mod secrets;
+mod ssh;
 mod storage;
 
-use storage::connection_store::ConnectionStore;
-use std::sync::Mutex;
-use tauri::Manager;
+use db::execution_queue::ExecutionQueue;
+use db::query_cache::QueryCache;
+use db::result_cache::{ResultCache, DEFAULT_RESULT_CACHE_CAPACITY};
+use db::pool_cache::{
+    PoolCache, CONNECTION_LOST_EVENT, CONNECTION_RESTORED_EVENT, CONNECTION_STATUS_EVENT,
+    KEEPALIVE_INTERVAL,
+};
+use db::scheduler::{Scheduler, SCHEDULER_TICK};
+use db::schema_cache::SchemaCache;
+use db::session::SessionManager;
+use db::tasks::TaskManager;
+use db::watchdog::{check_sessions, IDLE_TRANSACTION_EVENT, WATCHDOG_INTERVAL};
+use logging::LogHandle;
+use models::{AppSettings, ConnectionStatus, WatchdogConfig};
+use storage::audit_store::AuditStore;
+use storage::connection_store::{ConnectionStore, AUTO_LOCK_CHECK_INTERVAL, STORE_LOCKED_EVENT};
+use storage::query_history::QueryHistoryStore;
+use storage::settings_store::SettingsStore;
+use storage::workspace_store::WorkspaceStore;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
 
 pub struct AppState {
-    pub connection_store: Mutex<ConnectionStore>,
+    /// Shared by every window and locked inside, per call; slow calls go
+    /// through `commands::store_task`
+    pub connection_store: Arc<ConnectionStore>,
+    pub result_cache: Mutex<ResultCache>,
+    pub query_cache: Mutex<QueryCache>,
+    /// SQLite stores sharing the app file; calls that reach SQLite go
+    /// through `commands::sqlite_task`, while settings are served from memory
+    pub query_history: Arc<Mutex<QueryHistoryStore>>,
+    pub audit: Arc<Mutex<AuditStore>>,
+    pub settings: Arc<Mutex<SettingsStore>>,
+    pub workspaces: Arc<Mutex<WorkspaceStore>>,
+    pub sessions: SessionManager,
+    pub pools: PoolCache,
+    pub queue: ExecutionQueue,
+    pub schemas: SchemaCache,
+    pub watchdog: Mutex<WatchdogConfig>,
+    pub schedules: Mutex<Scheduler>,
+    pub tasks: Mutex<TaskManager>,
+    pub logs: LogHandle,
 }
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             // Get the app data directory
             let app_dir = app
@@ -35,9 +76,146 @@ fn main() {
 
             let connection_store = ConnectionStore::new(&db_path, encryption_key)
                 .expect("Failed to initialize connection store");
+            let mut query_history =
+                QueryHistoryStore::new(&db_path).expect("Failed to initialize query history");
+            let mut audit = AuditStore::new(&db_path).expect("Failed to initialize audit log");
+            let mut settings =
+                SettingsStore::new(&db_path).expect("Failed to initialize settings store");
+            let workspaces =
+                WorkspaceStore::new(&db_path).expect("Failed to initialize workspace store");
+
+            let (current, settings_error) = match settings.load() {
+                Ok(current) => (current, None),
+                Err(e) => (AppSettings::default(), Some(e)),
+            };
+            let logs = logging::init(&app_dir.join("logs"), current.log_level)
+                .expect("Failed to initialize logging");
+            if let Some(e) = settings_error {
+                tracing::error!(error = %e, "Failed to load settings");
+            }
+            if let Err(e) = commands::apply_history_retention(&current, &mut query_history) {
+                tracing::warn!(error = %e, "Failed to prune query history");
+            }
+            if let Err(e) = commands::apply_audit_retention(&current, &mut audit) {
+                tracing::warn!(error = %e, "Failed to prune audit log");
+            }
+            if let Err(e) = commands::apply_auto_lock(&current, &connection_store) {
+                tracing::warn!(error = %e, "Failed to set the auto-lock timeout");
+            }
+            let pools = PoolCache::default();
+            pools.secrets.configure(&current.vault);
 
             app.manage(AppState {
-                connection_store: Mutex::new(connection_store),
+                connection_store: Arc::new(connection_store),
+                result_cache: Mutex::new(ResultCache::new(DEFAULT_RESULT_CACHE_CAPACITY)),
+                query_cache: Mutex::new(QueryCache::default()),
+                query_history: Arc::new(Mutex::new(query_history)),
+                audit: Arc::new(Mutex::new(audit)),
+                settings: Arc::new(Mutex::new(settings)),
+                workspaces: Arc::new(Mutex::new(workspaces)),
+                sessions: SessionManager::default(),
+                pools,
+                queue: ExecutionQueue::default(),
+                schemas: SchemaCache::default(),
+                watchdog: Mutex::new(WatchdogConfig::default()),
+                schedules: Mutex::new(Scheduler::default()),
+                tasks: Mutex::new(TaskManager::default()),
+                logs,
+            });
+
+            // Keep cached pools alive and rebuild them after sleep or network loss
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(KEEPALIVE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    let state = handle.state::<AppState>();
+                    state
+                        .pools
+                        .keepalive(|event| {
+                            let transition = match event.status {
+                                ConnectionStatus::Reconnecting => Some(CONNECTION_LOST_EVENT),
+                                ConnectionStatus::Connected => Some(CONNECTION_RESTORED_EVENT),
+                                ConnectionStatus::Disconnected => None,
+                            };
+                            if let Some(name) = transition {
+                                if let Err(e) = handle.emit(name, event.clone()) {
+                                    tracing::warn!(error = %e, "Failed to emit connection status");
+                                }
+                            }
+                            if let Err(e) = handle.emit(CONNECTION_STATUS_EVENT, event) {
+                                tracing::warn!(error = %e, "Failed to emit connection status");
+                            }
+                        })
+                        .await;
+                }
+            });
+
+            // Re-run scheduled queries as they come due
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(SCHEDULER_TICK);
+                loop {
+                    interval.tick().await;
+                    commands::run_due_queries(&handle);
+                }
+            });
+
+            // Forget the master key once the store has been idle for a while
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(AUTO_LOCK_CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    let state = handle.state::<AppState>();
+                    let locked =
+                        commands::store_task(&state, |store| store.lock_if_idle()).await;
+                    match locked {
+                        Ok(true) => {
+                            tracing::info!("Connection store locked after inactivity");
+                            state.pools.secrets.clear();
+                            if let Err(e) = handle.emit(STORE_LOCKED_EVENT, ()) {
+                                tracing::warn!(error = %e, "Failed to emit store locked");
+                            }
+                        }
+                        Ok(false) => {}
+                        Err(e) => tracing::warn!(error = %e, "Failed to auto-lock the store"),
+                    }
+                }
+            });
+
+            // Warn about transactions left open in a session
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(WATCHDOG_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    let state = handle.state::<AppState>();
+                    let config = match state.watchdog.lock() {
+                        Ok(config) => config.clone(),
+                        Err(_) => continue,
+                    };
+                    for warning in check_sessions(&state.sessions, &config).await {
+                        let minutes = warning.open_for_ms / 60_000;
+                        let body = if warning.rolled_back {
+                            format!("A transaction open for {} min was rolled back", minutes)
+                        } else {
+                            format!("A transaction has been open for {} min", minutes)
+                        };
+                        if let Err(e) = handle
+                            .notification()
+                            .builder()
+                            .title("Open transaction")
+                            .body(body)
+                            .show()
+                        {
+                            tracing::warn!(error = %e, "Failed to show notification");
+                        }
+                        if let Err(e) = handle.emit(IDLE_TRANSACTION_EVENT, warning) {
+                            tracing::warn!(error = %e, "Failed to emit idle transaction warning");
+                        }
+                    }
+                }
             });
 
             Ok(())
@@ -47,16 +225,103 @@ fn main() {
             commands::load_connection,
             commands::list_connections,
             commands::delete_connection,
+            commands::disconnect,
+            commands::disconnect_all,
+            commands::duplicate_connection,
+            commands::create_connection_group,
+            commands::rename_connection_group,
+            commands::delete_connection_group,
+            commands::move_connection_to_group,
+            commands::export_connections,
+            commands::import_connections,
+            commands::import_external_connections,
+            commands::get_store_status,
+            commands::set_master_password,
+            commands::rotate_encryption_key,
+            commands::unlock_store,
+            commands::lock_store,
+            commands::parse_connection_url,
+            commands::resolve_ssh_config,
+            commands::resolve_client_config,
             commands::test_connection,
             commands::list_databases,
+            commands::list_schemas,
+            commands::get_server_info,
+            commands::get_slow_queries,
+            commands::create_demo_database,
             commands::get_schema,
+            commands::find_columns,
             commands::get_autocomplete_data,
+            commands::refresh_autocomplete_data,
+            commands::get_completions,
+            commands::resolve_reference,
             commands::execute_query,
+            commands::validate_query,
+            commands::group_result,
+            commands::diff_results,
+            commands::view_result,
+            commands::sort_result,
+            commands::filter_result,
+            commands::export_result,
+            commands::render_result,
+            commands::get_procedure_parameters,
+            commands::call_procedure,
+            commands::fetch_result_page,
+            commands::release_result,
+            commands::get_query_history,
+            commands::search_queries,
+            commands::get_query_stats,
+            commands::get_parameter_suggestions,
+            commands::open_session,
+            commands::close_session,
+            commands::get_session_state,
+            commands::get_watchdog_config,
+            commands::update_watchdog_config,
+            commands::schedule_query,
+            commands::unschedule_query,
+            commands::list_scheduled_queries,
+            commands::list_tasks,
+            commands::cancel_task,
+            commands::get_settings,
+            commands::update_settings,
+            commands::get_app_logs,
+            commands::get_audit_log,
+            commands::backup_app_data,
+            commands::restore_app_data,
+            commands::set_log_level,
+            commands::new_window,
+            commands::save_workspace,
+            commands::load_workspace,
+            commands::pin_table,
+            commands::unpin_table,
+            commands::list_pinned_tables,
+            commands::record_recent_object,
+            commands::get_recent_objects,
+            commands::clear_recent_objects,
             commands::get_table_structure,
+            commands::get_table_row_counts,
             commands::get_table_data,
             commands::insert_table_row,
             commands::update_table_row,
             commands::delete_table_rows,
+            commands::export_cell_to_file,
+            commands::update_cell_from_file,
+            commands::get_row,
+            commands::get_cell_value,
+            commands::reveal_masked_cell,
+            commands::profile_column,
+            commands::seed_table,
+            commands::copy_table,
+            commands::export_table_json,
+            commands::import_table_json,
+            commands::compare_table_data,
+            commands::list_templates,
+            commands::render_template,
+            commands::generate_query_template,
+            commands::create_database_user,
+            commands::grant_privileges,
+            commands::revoke_privileges,
+            commands::show_grants,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
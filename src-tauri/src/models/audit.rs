@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// A data-modifying statement issued through the app
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub id: String,
+    pub connection_id: String,
+    pub database: Option<String>,
+    /// Command that issued the statement, e.g. `update_table_row`
+    pub command: String,
+    pub sql: String,
+    /// None when the statement failed
+    pub rows_affected: Option<u64>,
+    pub success: bool,
+    pub error_message: Option<String>,
+    pub executed_at: i64,
+}
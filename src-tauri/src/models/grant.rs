@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateUserRequest {
+    pub connection_id: String,
+    pub username: String,
+    pub password: String,
+    /// MySQL account host; ignored for PostgreSQL
+    pub host: Option<String>,
+    #[serde(default)]
+    pub preview: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivilegeRequest {
+    pub connection_id: String,
+    pub username: String,
+    pub host: Option<String>,
+    pub privileges: Vec<String>,
+    pub database: String,
+    /// Limit the grant to one table; the whole database when None
+    pub table: Option<String>,
+    #[serde(default)]
+    pub preview: bool,
+}
+
+/// Statements generated for an admin action, and whether they were run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminStatements {
+    pub statements: Vec<String>,
+    pub executed: bool,
+}
+
+/// The privilege a failed statement was missing, with the statement an
+/// administrator could run to grant it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MissingPrivilege {
+    pub privilege: String,
+    /// None for global privileges such as PROCESS
+    pub database: Option<String>,
+    pub table: Option<String>,
+    pub column: Option<String>,
+    pub routine: Option<String>,
+    pub username: Option<String>,
+    pub host: Option<String>,
+    /// Only present once the account and object are fully known
+    pub grant_statement: Option<String>,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Verbosity of the application log, from most to least severe
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+/// One line of the application log
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogEntry {
+    /// RFC 3339 time the event was recorded
+    pub timestamp: String,
+    pub level: LogLevel,
+    /// Module that emitted the event, e.g. `dbclient::db::pool_cache`
+    pub target: String,
+    pub message: String,
+    /// Structured fields attached to the event, such as `connection_id`
+    pub fields: Map<String, Value>,
+}
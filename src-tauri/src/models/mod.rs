@@ -1,11 +1,43 @@
+pub mod audit;
 pub mod connection;
+pub mod grant;
+pub mod log;
 pub mod query;
+pub mod schedule;
 pub mod schema;
+pub mod settings;
+pub mod task;
+pub mod template;
+pub mod workspace;
 
-pub use connection::{Connection, DatabaseType};
-pub use query::{QueryRequest, QueryResult};
+pub use audit::AuditEntry;
+pub use connection::{
+    Connection, ConnectionGroup, ConnectionGroupNode, ConnectionSort, ConnectionStatus, ConnectionStatusEvent,
+    ConnectionTree, DatabaseType, Environment, ExternalTool, ImportStrategy, ImportSummary, LostReason,
+    SecretRef, ServerInfo, StoreStatus,
+};
+pub use grant::{AdminStatements, CreateUserRequest, MissingPrivilege, PrivilegeRequest};
+pub use log::{LogEntry, LogLevel};
+pub use query::{
+    ColumnTypeInfo, DestructiveRisk, DiffRequest, DiffSide, ExportFormat, ExportRequest,
+    GroupedResult, IdleTransactionWarning, MaskedCell, ParameterMode, ProcedureOutput, ProcedureParameter,
+    ProcedureResult, QueryHistoryEntry, QueryRequest, QueuePosition, QueryResult, QuerySearchMatch,
+    QueryStat, QueryStats, QueryWarning, ResultDiff, ResultGroup, ResultSet,
+    ResultViewRequest,
+    RowChange, SessionState, SlowQuery, SyncMode, TableCompareRequest, TableDataDiff, TableTime,
+    TimeRange,
+    ValidationIssue, ValidationResult, ValidationSource, ValueKind,
+    WatchdogConfig,
+};
+pub use schedule::{ScheduleDelivery, ScheduleRequest, ScheduledQuery, ScheduledResult};
 pub use schema::{
-    AutocompleteData, ColumnSchema, DeleteRowRequest, FilterOperator, ForeignKey,
-    InsertRowRequest, Schema, SortOrder, TableData, TableDataRequest, TableSchema,
-    UpdateRowRequest,
+    AutocompleteData, ColumnMatch, ColumnProfile, ColumnSchema, Completion, CompletionKind, CopyContent,
+    AutocompleteUpdate, CopyTableRequest, DeleteRowRequest,
+    CellFileRequest, CellValue, FilterOperator, ForeignKey, FunctionInfo, InsertRowRequest, ResolvedReference, RowCountMode, RowField, Schema, SeedTableRequest, SortOrder, TableData, TableDataRequest, TableFilter, TableRowCount,
+    TableRowTotal, TableSchema, UpdateRowRequest, ValueFrequency,
+    JsonExportRequest, JsonImportReport, JsonImportRequest, JsonLayout, JsonRowError,
 };
+pub use settings::{AppSettings, EnvironmentPolicy, LimitGuardMode, VaultSettings};
+pub use task::{TaskInfo, TaskKind, TaskStatus};
+pub use template::{QueryTemplateKind, StatementTemplate, TemplateParam};
+pub use workspace::{PinnedTable, RecentObject, RecentObjectKind, Workspace};
@@ -1,4 +1,7 @@
+use super::schema::{SortOrder, TableFilter};
+use crate::db::spill::SpilledRows;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryRequest {
@@ -7,6 +10,102 @@ pub struct QueryRequest {
     pub database: Option<String>,
     pub page: Option<u32>,
     pub page_size: Option<u32>,
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    /// Run on a pinned session connection instead of a fresh pooled one
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Run the query even when a cached result is available
+    #[serde(default)]
+    pub force_refresh: bool,
+    /// The user agreed to run a statement that may lose data
+    #[serde(default)]
+    pub confirmed: bool,
+    /// The connection's name as typed by the user, for writes to an
+    /// environment that asks for it
+    #[serde(default)]
+    pub confirmation: Option<String>,
+    /// Chosen by the UI to match `query://queue` events to this request
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+/// Why a statement needs the user's confirmation before it runs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum DestructiveRisk {
+    UpdateWithoutWhere,
+    DeleteWithoutWhere,
+    Drop,
+    Truncate,
+    /// Not destructive, but shows a value masking hides, e.g. on a shared screen
+    RevealMasked,
+    /// Applying a data sync rewrites the target table's rows
+    SyncTable,
+}
+
+impl DestructiveRisk {
+    pub fn description(&self) -> &'static str {
+        match self {
+            DestructiveRisk::UpdateWithoutWhere => {
+                "UPDATE without a WHERE clause changes every row"
+            }
+            DestructiveRisk::DeleteWithoutWhere => {
+                "DELETE without a WHERE clause removes every row"
+            }
+            DestructiveRisk::Drop => "DROP removes the object and all of its data",
+            DestructiveRisk::Truncate => "TRUNCATE removes every row of the table",
+            DestructiveRisk::RevealMasked => "Revealing shows the masked value in full",
+            DestructiveRisk::SyncTable => {
+                "Syncing inserts, updates and deletes rows of the target table"
+            }
+        }
+    }
+}
+
+/// How a column's values are represented in results
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ValueKind {
+    Boolean,
+    /// Signed or unsigned integers, as numbers
+    Integer,
+    Float,
+    /// Exact numbers, as strings so no precision is lost
+    Decimal,
+    Text,
+    /// ENUM and SET values, as text
+    Enum,
+    Json,
+    /// Blob descriptions, see `binary_to_json`
+    Binary,
+    /// `YYYY-MM-DD`
+    Date,
+    /// `[-]HH:MM:SS[.ffffff]`, where hours may exceed 24
+    Time,
+    /// DATETIME and TIMESTAMP, as `YYYY-MM-DD HH:MM:SS[.ffffff]`
+    DateTime,
+    Year,
+    /// BIT values, as numbers
+    Bit,
+    /// Well-known binary, described like `Binary`
+    Geometry,
+    Other,
+}
+
+/// Type of a result column, so values can be shown and edited appropriately
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnTypeInfo {
+    pub name: String,
+    /// Server type, e.g. `BIGINT UNSIGNED` or `DECIMAL`
+    pub type_name: String,
+    pub kind: ValueKind,
+    /// Some values were cut to the cell length limit; `get_cell_value`
+    /// fetches one in full
+    #[serde(default)]
+    pub truncated: bool,
+    /// Values are hidden by the connection's masking rules;
+    /// `reveal_masked_cell` shows one
+    #[serde(default)]
+    pub masked: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,4 +114,478 @@ pub struct QueryResult {
     pub rows: Vec<Vec<serde_json::Value>>,
     pub total_rows: usize,
     pub execution_time_ms: u64,
+    pub result_id: Option<String>,
+    /// More rows were available than the connection's row limit allowed
+    #[serde(default)]
+    pub truncated: bool,
+    /// Set when `rows` holds only the first page; the rest are fetched by
+    /// `result_id` with `fetch_result_page`
+    #[serde(default)]
+    pub page_size: Option<usize>,
+    /// Rows changed by an INSERT, UPDATE or DELETE
+    #[serde(default)]
+    pub rows_affected: u64,
+    /// One entry per column in `columns`
+    #[serde(default)]
+    pub column_types: Vec<ColumnTypeInfo>,
+    /// Served from the query cache instead of running the query
+    #[serde(default)]
+    pub cached: bool,
+    /// When the cached result was fetched, in milliseconds since the epoch
+    #[serde(default)]
+    pub cached_at: Option<i64>,
+    /// LIMIT the limit guard appended to an unbounded SELECT
+    #[serde(default)]
+    pub auto_limit: Option<u32>,
+    /// The statement was an unbounded SELECT, run as written
+    #[serde(default)]
+    pub unbounded: bool,
+    /// A page of a paginated query was full and more rows follow it
+    #[serde(default)]
+    pub has_more: bool,
+    /// The statement was an INSERT, UPDATE, DELETE or REPLACE, described by
+    /// `rows_affected`, `last_insert_id` and `warnings` instead of rows
+    #[serde(default)]
+    pub dml: bool,
+    /// AUTO_INCREMENT value generated by an INSERT
+    #[serde(default)]
+    pub last_insert_id: Option<u64>,
+    /// Warnings the server reported for an INSERT, UPDATE or DELETE
+    #[serde(default)]
+    pub warnings: Vec<QueryWarning>,
+    /// Result sets after the first, which the fields above describe, from a
+    /// script of several statements or a stored procedure call.
+    /// `rows_affected` above counts the rows of every set.
+    #[serde(default)]
+    pub more_results: Vec<ResultSet>,
+    /// Rows past the connection's memory budget, kept on disk; `rows` holds
+    /// the ones before them and `total_rows` counts both
+    #[serde(skip)]
+    pub spilled: Option<SpilledRows>,
+}
+
+/// One of several result sets returned by a single execution
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub column_types: Vec<ColumnTypeInfo>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub total_rows: usize,
+    pub rows_affected: u64,
+    /// Cached full set, paged through with `fetch_result_page`
+    #[serde(default)]
+    pub result_id: Option<String>,
+    /// Set when `rows` holds only the first page
+    #[serde(default)]
+    pub page_size: Option<usize>,
+}
+
+/// A note or warning the server reported for a statement, from SHOW WARNINGS
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryWarning {
+    /// Note, Warning or Error
+    pub level: String,
+    pub code: u32,
+    pub message: String,
+}
+
+/// Direction of a stored procedure parameter
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ParameterMode {
+    In,
+    Out,
+    InOut,
+}
+
+/// A parameter of a stored procedure, from information_schema.PARAMETERS
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProcedureParameter {
+    pub name: String,
+    pub mode: ParameterMode,
+    /// Declared type, e.g. `decimal(10,2)`
+    pub data_type: String,
+}
+
+/// Value of an OUT or INOUT parameter after a call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcedureOutput {
+    pub name: String,
+    pub data_type: String,
+    pub value: serde_json::Value,
+}
+
+/// Everything a stored procedure call returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcedureResult {
+    /// The procedure's result sets, the first one in the top-level fields
+    pub result: QueryResult,
+    /// One entry per OUT and INOUT parameter, in declaration order
+    pub outputs: Vec<ProcedureOutput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultGroup {
+    pub values: Vec<serde_json::Value>,
+    pub count: usize,
+}
+
+/// Distinct value combinations of the selected columns with their row counts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupedResult {
+    pub columns: Vec<String>,
+    pub groups: Vec<ResultGroup>,
+    pub total_rows: usize,
+}
+
+/// One of the two queries compared by `diff_results`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffSide {
+    pub connection_id: String,
+    pub database: Option<String>,
+    pub sql: String,
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffRequest {
+    pub left: DiffSide,
+    pub right: DiffSide,
+    /// Columns identifying a row on both sides; every column when empty
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+}
+
+/// A cell hidden by masking rules, to reveal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source")]
+pub enum MaskedCell {
+    /// A cell of a cached query result, by row index and column name
+    Result {
+        result_id: String,
+        row: usize,
+        column: String,
+    },
+    /// A cell of a table row located by its primary key
+    Table {
+        connection_id: String,
+        database: String,
+        table: String,
+        primary_key: HashMap<String, serde_json::Value>,
+        column: String,
+    },
+}
+
+/// A row found on both sides with different values
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RowChange {
+    /// Values of the key columns
+    pub key: Vec<serde_json::Value>,
+    pub left: Vec<serde_json::Value>,
+    pub right: Vec<serde_json::Value>,
+    pub changed_columns: Vec<String>,
+}
+
+/// Row-level differences between two results. Rows hold the values of
+/// `columns`, the columns both sides have.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResultDiff {
+    pub columns: Vec<String>,
+    pub key_columns: Vec<String>,
+    pub left_only_columns: Vec<String>,
+    pub right_only_columns: Vec<String>,
+    /// Rows only the right side has
+    pub added: Vec<Vec<serde_json::Value>>,
+    /// Rows only the left side has
+    pub removed: Vec<Vec<serde_json::Value>>,
+    pub changed: Vec<RowChange>,
+    pub unchanged: usize,
+    /// A side hit the row limit, so rows past it weren't compared
+    pub truncated: bool,
+}
+
+/// A table's database on one of the connections `compare_table_data` reads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableLocation {
+    pub connection_id: String,
+    pub database: String,
+}
+
+/// What `compare_table_data` does with the differences it finds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum SyncMode {
+    /// Only report them
+    #[default]
+    Report,
+    /// Also return statements that bring the target in line with the source
+    Generate,
+    /// Run those statements on the target, a chunk per transaction
+    Apply,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableCompareRequest {
+    pub source: TableLocation,
+    pub target: TableLocation,
+    pub table: String,
+    /// Columns identifying a row on both sides; the source's primary key
+    /// when empty
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+    #[serde(default)]
+    pub mode: SyncMode,
+    /// The user agreed to change the target's rows
+    #[serde(default)]
+    pub confirmed: bool,
+    /// The target connection's name as typed by the user, for environments
+    /// that ask for it
+    #[serde(default)]
+    pub confirmation: Option<String>,
+}
+
+/// Row-level differences between a table on two connections, as changes to
+/// the target. Rows hold the values of `columns`, binary values as hex.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct TableDataDiff {
+    pub columns: Vec<String>,
+    pub key_columns: Vec<String>,
+    pub source_only_columns: Vec<String>,
+    pub target_only_columns: Vec<String>,
+    /// Source rows the target lacks
+    pub inserted: Vec<Vec<serde_json::Value>>,
+    /// Rows with other values on the target; `left` holds the source's
+    pub updated: Vec<RowChange>,
+    /// Target rows the source lacks
+    pub deleted: Vec<Vec<serde_json::Value>>,
+    pub inserted_count: u64,
+    pub updated_count: u64,
+    pub deleted_count: u64,
+    pub unchanged: u64,
+    /// Only the first rows of each kind are listed; the counts cover all
+    pub truncated: bool,
+    /// Statements syncing the target, with `SyncMode::Generate`
+    pub statements: Vec<String>,
+    /// Rows changed on the target, with `SyncMode::Apply`
+    pub rows_affected: Option<u64>,
+}
+
+/// A recorded execution, kept so past statements and their parameters can be reused
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryHistoryEntry {
+    pub id: String,
+    pub connection_id: String,
+    pub database: Option<String>,
+    pub sql: String,
+    pub fingerprint: String,
+    pub params: Vec<serde_json::Value>,
+    pub executed_at: i64,
+    pub execution_time_ms: u64,
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+/// A statement from the history that matches a search, from `search_queries`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuerySearchMatch {
+    /// The latest execution of the statement that matched
+    pub entry: QueryHistoryEntry,
+    /// The statement around the words that matched, which are wrapped in
+    /// `«` and `»`
+    pub snippet: String,
+    /// How well it matched; higher is better
+    pub score: f64,
+}
+
+/// A span of time in milliseconds since the epoch; a missing end is open
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct TimeRange {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+/// A statement, normalized by the server, with the totals it keeps for it,
+/// from `get_slow_queries`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SlowQuery {
+    /// Default database of the sessions that ran it
+    pub database: Option<String>,
+    /// Hash identifying the normalized statement
+    pub digest: Option<String>,
+    /// The statement with its literals replaced by `?`
+    pub digest_text: String,
+    pub calls: u64,
+    pub total_latency_ms: f64,
+    pub mean_latency_ms: f64,
+    pub max_latency_ms: f64,
+    pub rows_examined: u64,
+    pub rows_sent: u64,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+}
+
+/// Recorded executions of one statement fingerprint against one database
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryStat {
+    pub fingerprint: String,
+    pub database: Option<String>,
+    pub executions: u64,
+    pub failures: u64,
+    pub total_time_ms: u64,
+    pub average_time_ms: u64,
+    pub max_time_ms: u64,
+    pub last_executed_at: i64,
+}
+
+/// Time spent in statements that referenced a table
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableTime {
+    pub database: Option<String>,
+    pub table: String,
+    pub executions: u64,
+    pub total_time_ms: u64,
+}
+
+/// Where time went across a connection's recorded executions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryStats {
+    pub executions: u64,
+    pub failures: u64,
+    /// Share of executions that failed, from 0 to 1
+    pub error_rate: f64,
+    pub total_time_ms: u64,
+    /// Highest average time first
+    pub slowest: Vec<QueryStat>,
+    /// Most executions first
+    pub most_frequent: Vec<QueryStat>,
+    /// Most time first. A statement joining tables counts fully for each.
+    pub time_by_table: Vec<TableTime>,
+}
+
+/// Which check found a validation problem
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ValidationSource {
+    /// The statement doesn't parse
+    Parser,
+    /// The server rejected the statement when asked to plan or prepare it
+    Server,
+}
+
+/// A problem found in a statement without running it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub message: String,
+    pub source: ValidationSource,
+    /// Byte offset into the validated SQL where the problem was found
+    pub position: Option<usize>,
+    /// 1-based line and column of `position`, counted in characters
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Byte range of the statement the problem is in
+    pub statement_start: usize,
+    pub statement_end: usize,
+    /// Server error number, e.g. 1054 for an unknown column in MySQL
+    pub server_code: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationResult {
+    pub valid: bool,
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// Transaction status of a pinned session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionState {
+    pub session_id: String,
+    pub connection_id: String,
+    pub in_transaction: bool,
+    pub transaction_started_at: Option<i64>,
+    pub transaction_duration_ms: Option<u64>,
+    pub statements_in_transaction: Vec<String>,
+}
+
+/// Settings for warning about (and optionally rolling back) long-running
+/// transactions held open by a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogConfig {
+    pub enabled: bool,
+    pub threshold_secs: u64,
+    pub auto_rollback: bool,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_secs: 300,
+            auto_rollback: false,
+        }
+    }
+}
+
+/// Payload of the `session://idle-transaction` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdleTransactionWarning {
+    pub session_id: String,
+    pub connection_id: String,
+    pub open_for_ms: u64,
+    pub statements_in_transaction: Vec<String>,
+    pub rolled_back: bool,
+}
+
+/// Payload of the `query://queue` event: a statement's place in line for
+/// one of its connection's slots, 0 once it starts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueuePosition {
+    pub connection_id: String,
+    pub request_id: Option<String>,
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultSort {
+    pub column: String,
+    pub order: SortOrder,
+}
+
+/// Filter/sort a cached result and fetch one page of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultViewRequest {
+    pub result_id: String,
+    #[serde(default)]
+    pub filters: Vec<TableFilter>,
+    /// Applied in order; later entries break ties of earlier ones
+    #[serde(default)]
+    pub sort: Vec<ResultSort>,
+    /// A filter expression like `filter_result` takes, applied with `filters`
+    #[serde(default)]
+    pub expression: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// File format of an exported result
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ExportFormat {
+    /// Columnar, with SQL types mapped to Arrow types
+    Parquet,
+    /// GitHub-flavored Markdown table
+    Markdown,
+    /// Table with inline styles
+    Html,
+}
+
+/// Write the rows of a cached result to a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportRequest {
+    pub result_id: String,
+    pub path: String,
+    pub format: ExportFormat,
+    /// Columns to export, in this order; all of them when empty
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Export only the first rows; all of them when None
+    #[serde(default)]
+    pub row_limit: Option<usize>,
 }
@@ -0,0 +1,50 @@
+use super::query::QueryResult;
+use serde::{Deserialize, Serialize};
+
+/// When a scheduled query's result is pushed to the UI
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum ScheduleDelivery {
+    #[default]
+    EveryRun,
+    /// Only when the rows differ from the previous run
+    OnChange,
+}
+
+/// A query to run again every `interval_secs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRequest {
+    pub connection_id: String,
+    pub database: Option<String>,
+    pub sql: String,
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub delivery: ScheduleDelivery,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledQuery {
+    pub id: String,
+    pub connection_id: String,
+    pub database: Option<String>,
+    pub sql: String,
+    pub params: Vec<serde_json::Value>,
+    pub interval_secs: u64,
+    pub delivery: ScheduleDelivery,
+    /// When the query last finished, in milliseconds since the epoch
+    pub last_run_at: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+/// Outcome of one scheduled run, pushed to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledResult {
+    pub schedule_id: String,
+    /// When the run finished, in milliseconds since the epoch
+    pub ran_at: i64,
+    pub result: Option<QueryResult>,
+    pub error: Option<String>,
+    /// The rows differ from the previous run's
+    pub changed: bool,
+}
@@ -1,14 +1,36 @@
+use super::query::ColumnTypeInfo;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Schema {
     pub tables: Vec<TableSchema>,
+    /// Schemas an unqualified table name is looked up in, in order, like
+    /// PostgreSQL's `search_path`. Empty where databases don't hold schemas,
+    /// as on MySQL, whose databases are its schemas.
+    #[serde(default)]
+    pub search_path: Vec<String>,
+}
+
+impl Schema {
+    /// How a table is referred to without naming its database: by name when
+    /// it's on the search path, as `schema.table` otherwise
+    pub fn qualified_name(&self, table: &TableSchema) -> String {
+        match &table.schema {
+            Some(schema) if !self.search_path.contains(schema) => {
+                format!("{}.{}", schema, table.name)
+            }
+            _ => table.name.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableSchema {
     pub name: String,
+    /// Schema the table is in, on servers whose databases hold schemas
+    #[serde(default)]
+    pub schema: Option<String>,
     pub columns: Vec<ColumnSchema>,
     pub primary_keys: Vec<String>,
     pub foreign_keys: Vec<ForeignKey>,
@@ -21,6 +43,17 @@ pub struct ColumnSchema {
     pub is_nullable: bool,
     pub default_value: Option<String>,
     pub max_length: Option<i64>,
+    /// Filled in by the database when left out of an insert
+    #[serde(default)]
+    pub is_auto_increment: bool,
+    /// Computed from other columns; can't be written at all
+    #[serde(default)]
+    pub is_generated: bool,
+    /// Raw EXTRA of INFORMATION_SCHEMA.COLUMNS, e.g. `on update CURRENT_TIMESTAMP`
+    #[serde(default)]
+    pub extra: String,
+    /// Values an ENUM or SET column accepts, in definition order
+    pub allowed_values: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,60 +63,179 @@ pub struct ForeignKey {
     pub referenced_column: String,
 }
 
+/// Keywords every dialect understands
+pub const SQL_KEYWORDS: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "JOIN", "INNER", "LEFT", "RIGHT", "OUTER",
+    "ON", "AND", "OR", "NOT", "IN", "LIKE", "BETWEEN", "IS", "NULL",
+    "INSERT", "INTO", "VALUES", "UPDATE", "SET", "DELETE", "CREATE",
+    "TABLE", "DROP", "ALTER", "ADD", "COLUMN", "INDEX", "PRIMARY", "KEY",
+    "FOREIGN", "REFERENCES", "AS", "ORDER", "BY", "GROUP", "HAVING",
+    "LIMIT", "OFFSET", "DISTINCT", "COUNT", "SUM", "AVG", "MIN", "MAX",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutocompleteData {
     pub tables: Vec<String>,
     pub columns_by_table: HashMap<String, Vec<String>>,
     pub keywords: Vec<String>,
+    /// Every database on the connection, for `db.table` references
+    #[serde(default)]
+    pub databases: Vec<String>,
+    /// Built-in functions of the connection's dialect
+    #[serde(default)]
+    pub functions: Vec<FunctionInfo>,
+}
+
+/// Payload of the `autocomplete://updated` event, sent when a refresh finds
+/// tables added, changed or dropped since the completion data was loaded
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AutocompleteUpdate {
+    pub connection_id: String,
+    pub database: String,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// A built-in SQL function
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FunctionInfo {
+    pub name: String,
+    /// e.g. `DATE_FORMAT(date, format)`
+    pub signature: String,
+    pub description: String,
 }
 
 impl AutocompleteData {
     pub fn from_schema(schema: &Schema) -> Self {
-        let tables: Vec<String> = schema.tables.iter().map(|t| t.name.clone()).collect();
+        let tables: Vec<String> = schema
+            .tables
+            .iter()
+            .map(|t| schema.qualified_name(t))
+            .collect();
 
         let mut columns_by_table = HashMap::new();
         for table in &schema.tables {
             let columns: Vec<String> = table.columns.iter().map(|c| c.name.clone()).collect();
-            columns_by_table.insert(table.name.clone(), columns);
+            columns_by_table.insert(schema.qualified_name(table), columns);
         }
 
-        let keywords = vec![
-            "SELECT", "FROM", "WHERE", "JOIN", "INNER", "LEFT", "RIGHT", "OUTER",
-            "ON", "AND", "OR", "NOT", "IN", "LIKE", "BETWEEN", "IS", "NULL",
-            "INSERT", "INTO", "VALUES", "UPDATE", "SET", "DELETE", "CREATE",
-            "TABLE", "DROP", "ALTER", "ADD", "COLUMN", "INDEX", "PRIMARY", "KEY",
-            "FOREIGN", "REFERENCES", "AS", "ORDER", "BY", "GROUP", "HAVING",
-            "LIMIT", "OFFSET", "DISTINCT", "COUNT", "SUM", "AVG", "MIN", "MAX",
-        ]
-        .into_iter()
-        .map(String::from)
-        .collect();
+        let keywords = SQL_KEYWORDS.iter().map(|k| k.to_string()).collect();
 
         Self {
             tables,
             columns_by_table,
             keywords,
+            databases: Vec::new(),
+            functions: Vec::new(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CompletionKind {
+    Column,
+    Table,
+    /// An alias given to a table in the statement
+    Alias,
+    Database,
+    Function,
+    Keyword,
+}
+
+/// A suggestion for the word at the cursor
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// Where the suggestion comes from, e.g. the table and type of a column
+    pub detail: Option<String>,
+}
+
+/// A table or column reference resolved to the database it lives in
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResolvedReference {
+    pub database: String,
+    pub table: String,
+    pub column: Option<String>,
+}
+
+/// A column found by `find_columns`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnMatch {
+    pub database: String,
+    pub table: String,
+    pub column: String,
+    /// Full type, e.g. `int unsigned` or `varchar(255)`
+    pub column_type: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableData {
     pub columns: Vec<String>,
     pub rows: Vec<HashMap<String, serde_json::Value>>,
     pub total_rows: u64,
+    /// One entry per column in `columns`
+    #[serde(default)]
+    pub column_types: Vec<ColumnTypeInfo>,
+    /// Whether `total_rows` is the database's estimate rather than a count
+    #[serde(default)]
+    pub total_rows_estimated: bool,
+    /// The exact count is still running; it arrives in a `TableRowCount`
+    /// event with this page's `request_id`
+    #[serde(default)]
+    pub total_rows_pending: bool,
+    pub request_id: Option<String>,
+}
+
+/// Payload of the `table-data://count` event, the outcome of counting the
+/// rows of a table page returned earlier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableRowCount {
+    pub request_id: String,
+    pub total_rows: Option<u64>,
+    /// Why counting failed, when it did
+    pub error: Option<String>,
+}
+
+/// Rows of a table for the sidebar, from `get_table_row_counts`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableRowTotal {
+    pub table: String,
+    /// None when the server keeps no statistics for it, e.g. for a view
+    pub rows: Option<u64>,
+    /// Whether `rows` was counted rather than estimated
+    pub exact: bool,
+    /// Known to hold no rows. An estimate above zero is taken at its word.
+    pub empty: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableDataRequest {
     pub connection_id: String,
     pub database: String,
+    /// Schema of the table within `database`, where databases hold schemas
+    #[serde(default)]
+    pub schema: Option<String>,
     pub table: String,
     pub page: u32,
     pub page_size: u32,
     pub filters: Option<Vec<TableFilter>>,
     pub sort_by: Option<String>,
     pub sort_order: Option<SortOrder>,
+    #[serde(default)]
+    pub row_count: RowCountMode,
+}
+
+/// How `total_rows` of a table page is counted
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum RowCountMode {
+    /// Estimate for tables big enough that counting them is slow
+    #[default]
+    Auto,
+    Exact,
+    /// The database's statistics; filtered pages are still counted exactly
+    Estimate,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,7 +245,7 @@ pub struct TableFilter {
     pub value: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum FilterOperator {
     Equals,
     NotEquals,
@@ -109,18 +261,31 @@ pub enum FilterOperator {
     IsNotNull,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SortOrder {
     Asc,
     Desc,
 }
 
+/// A value written to a column by an insert or update, sent as
+/// `{"kind": "Literal", "value": ...}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", content = "value")]
+pub enum CellValue {
+    Null,
+    /// The column's default value
+    Default,
+    /// Raw SQL such as `NOW()`, written as is
+    Expression(String),
+    Literal(serde_json::Value),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InsertRowRequest {
     pub connection_id: String,
     pub database: String,
     pub table: String,
-    pub data: HashMap<String, serde_json::Value>,
+    pub data: HashMap<String, CellValue>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,8 +293,14 @@ pub struct UpdateRowRequest {
     pub connection_id: String,
     pub database: String,
     pub table: String,
-    pub data: HashMap<String, serde_json::Value>,
+    pub data: HashMap<String, CellValue>,
     pub where_clause: HashMap<String, serde_json::Value>,
+    /// The row's values as they were read. When given, the row only changes
+    /// if they still match, and a row changed by someone else since is a
+    /// conflict instead of being overwritten. Values that can't be compared
+    /// exactly, i.e. cut, masked, binary and FLOAT or DOUBLE ones, are left
+    /// out of the check.
+    pub expected_values: Option<HashMap<String, serde_json::Value>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -138,4 +309,149 @@ pub struct DeleteRowRequest {
     pub database: String,
     pub table: String,
     pub where_clause: HashMap<String, serde_json::Value>,
+    /// See `UpdateRowRequest::expected_values`
+    pub expected_values: Option<HashMap<String, serde_json::Value>>,
+}
+
+/// A cell located by its row's primary key, and the file its value is
+/// written to or read from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CellFileRequest {
+    pub connection_id: String,
+    pub database: String,
+    pub table: String,
+    pub column: String,
+    pub primary_key: HashMap<String, serde_json::Value>,
+    pub path: String,
+}
+
+/// Fill a table with made-up rows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedTableRequest {
+    pub connection_id: String,
+    pub database: String,
+    pub table: String,
+    pub row_count: usize,
+    /// Rows per INSERT statement
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+}
+
+/// What `copy_table` copies
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum CopyContent {
+    #[default]
+    StructureAndData,
+    StructureOnly,
+    /// Rows only, into a table that already exists on the target
+    DataOnly,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct CopyTableOptions {
+    pub content: CopyContent,
+    /// Name of the copy; the source table's when None
+    pub target_table: Option<String>,
+    /// Rows per INSERT statement
+    pub batch_size: Option<usize>,
+}
+
+/// Copy a table to another database, on the same connection or another one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopyTableRequest {
+    pub source_connection_id: String,
+    pub source_database: String,
+    pub table: String,
+    pub target_connection_id: String,
+    pub target_database: String,
+    #[serde(default)]
+    pub options: CopyTableOptions,
+}
+
+/// How `export_table_json` lays out a file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum JsonLayout {
+    /// One JSON document, with the rows in an array
+    #[default]
+    Document,
+    /// The header on the first line, then a row per line
+    Ndjson,
+}
+
+/// Write every row of a table to a JSON file with type-tagged values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonExportRequest {
+    pub connection_id: String,
+    pub database: String,
+    pub table: String,
+    pub path: String,
+    #[serde(default)]
+    pub layout: JsonLayout,
+}
+
+/// Insert the rows of a file from `export_table_json` into a table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonImportRequest {
+    pub connection_id: String,
+    pub database: String,
+    pub table: String,
+    pub path: String,
+    /// Rows per INSERT statement
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+}
+
+/// A row of a file that wasn't imported, and why
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JsonRowError {
+    /// Position of the row in the file, from 1
+    pub row: u64,
+    /// The column whose value was rejected, if it was one value
+    pub column: Option<String>,
+    pub message: String,
+}
+
+/// Outcome of `import_table_json`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct JsonImportReport {
+    pub imported: u64,
+    pub failed: u64,
+    /// Why rows failed, for the first of them
+    pub errors: Vec<JsonRowError>,
+}
+
+/// A value of a column and the number of rows holding it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValueFrequency {
+    pub value: serde_json::Value,
+    pub count: u64,
+}
+
+/// Statistics of the values in one column of a table
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnProfile {
+    pub column: String,
+    pub total_rows: u64,
+    pub null_count: u64,
+    pub distinct_count: u64,
+    pub min: serde_json::Value,
+    pub max: serde_json::Value,
+    /// Average length in characters of the non-NULL values as text
+    pub avg_length: Option<f64>,
+    /// Most common values, most frequent first
+    pub top_values: Vec<ValueFrequency>,
+}
+
+/// A column of a single fetched row, with its value in full. JSON columns
+/// hold parsed JSON; binary columns hold text, or a `type: "blob"` object
+/// with its `content_type` and base64 `data`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowField {
+    pub name: String,
+    /// Server type of the column, e.g. `LONGTEXT`
+    pub type_name: String,
+    pub value: serde_json::Value,
+    /// Size of a text or binary value in bytes
+    pub size: Option<u64>,
 }
@@ -0,0 +1,198 @@
+use super::log::LogLevel;
+use super::Environment;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum Theme {
+    /// Follow the operating system
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct EditorSettings {
+    pub font_size: u32,
+    pub tab_size: u32,
+    pub word_wrap: bool,
+    pub autocomplete: bool,
+}
+
+impl Default for EditorSettings {
+    fn default() -> Self {
+        Self {
+            font_size: 14,
+            tab_size: 2,
+            word_wrap: false,
+            autocomplete: true,
+        }
+    }
+}
+
+/// Which destructive actions ask for confirmation before running
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ConfirmSettings {
+    pub delete_rows: bool,
+    pub drop_objects: bool,
+    pub truncate_tables: bool,
+}
+
+impl Default for ConfirmSettings {
+    fn default() -> Self {
+        Self {
+            delete_rows: true,
+            drop_objects: true,
+            truncate_tables: true,
+        }
+    }
+}
+
+/// Recording of data-modifying statements to the local audit log
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AuditSettings {
+    pub enabled: bool,
+    /// Drop audit entries older than this many days; kept forever when None
+    pub retention_days: Option<u32>,
+}
+
+impl Default for AuditSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retention_days: Some(365),
+        }
+    }
+}
+
+/// What happens to a SELECT that reads tables without a LIMIT
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum LimitGuardMode {
+    Off,
+    /// Run it as written, flagging the result
+    Warn,
+    /// Append `LIMIT default_limit`
+    #[default]
+    Append,
+}
+
+/// Protection against accidentally fetching a whole table
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LimitGuardSettings {
+    pub mode: LimitGuardMode,
+    pub default_limit: u32,
+}
+
+impl Default for LimitGuardSettings {
+    fn default() -> Self {
+        Self {
+            mode: LimitGuardMode::default(),
+            default_limit: 1000,
+        }
+    }
+}
+
+/// Restrictions enforced on every connection of an environment
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct EnvironmentPolicy {
+    /// Treat the connections as read-only whatever their own flag says
+    pub read_only: bool,
+    /// Reject CREATE, ALTER, DROP and other schema changes
+    pub block_ddl: bool,
+    /// Append the limit guard's LIMIT to unbounded SELECTs whatever its mode
+    pub force_limit: bool,
+    /// Writes run only with the connection's name typed as confirmation
+    pub confirm_writes: bool,
+}
+
+/// Policies of the staging and production environments; development
+/// connections are unrestricted
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct GuardrailSettings {
+    pub staging: EnvironmentPolicy,
+    pub prod: EnvironmentPolicy,
+}
+
+impl Default for GuardrailSettings {
+    fn default() -> Self {
+        Self {
+            staging: EnvironmentPolicy::default(),
+            prod: EnvironmentPolicy {
+                read_only: false,
+                block_ddl: true,
+                force_limit: true,
+                confirm_writes: true,
+            },
+        }
+    }
+}
+
+impl GuardrailSettings {
+    pub fn policy(&self, environment: Environment) -> EnvironmentPolicy {
+        match environment {
+            Environment::Dev => EnvironmentPolicy::default(),
+            Environment::Staging => self.staging,
+            Environment::Prod => self.prod,
+        }
+    }
+}
+
+/// HashiCorp Vault server that connections' `SecretRef::Vault` secrets are
+/// read from. Empty values fall back to `VAULT_ADDR` and `VAULT_NAMESPACE`,
+/// like the vault CLI. The token is never stored with the settings: it's
+/// read from `VAULT_TOKEN` or the vault CLI's `~/.vault-token`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct VaultSettings {
+    pub address: Option<String>,
+    pub namespace: Option<String>,
+}
+
+/// User preferences persisted across restarts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AppSettings {
+    pub editor: EditorSettings,
+    pub default_page_size: usize,
+    /// Text and JSON cells in table pages and query results are cut to this
+    /// many characters; shown in full when None
+    pub max_cell_length: Option<usize>,
+    pub confirm: ConfirmSettings,
+    pub theme: Theme,
+    /// Drop query history older than this many days; kept forever when None
+    pub history_retention_days: Option<u32>,
+    /// Least severe level written to the application log
+    pub log_level: LogLevel,
+    pub audit: AuditSettings,
+    pub limit_guard: LimitGuardSettings,
+    pub guardrails: GuardrailSettings,
+    pub vault: VaultSettings,
+    /// Lock the connection store after this many idle minutes when it has a
+    /// master password; never when None
+    pub auto_lock_minutes: Option<u32>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            editor: EditorSettings::default(),
+            default_page_size: 100,
+            max_cell_length: Some(4096),
+            confirm: ConfirmSettings::default(),
+            theme: Theme::default(),
+            history_retention_days: Some(90),
+            log_level: LogLevel::default(),
+            audit: AuditSettings::default(),
+            limit_guard: LimitGuardSettings::default(),
+            guardrails: GuardrailSettings::default(),
+            vault: VaultSettings::default(),
+            auto_lock_minutes: Some(15),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// The long-running operation a background task performs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TaskKind {
+    Export,
+    Seed,
+    Copy,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TaskStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Progress of a background task, listed by `list_tasks` and pushed in
+/// `task://progress` events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub id: String,
+    pub kind: TaskKind,
+    /// What the task works on, e.g. the file an export writes
+    pub description: String,
+    pub status: TaskStatus,
+    pub rows_processed: u64,
+    /// Rows the task will process, when known up front
+    pub total_rows: Option<u64>,
+    /// Bytes written so far, for tasks that write a file
+    pub bytes_processed: u64,
+    /// Estimated time left, once enough progress was made to tell
+    pub eta_ms: Option<u64>,
+    /// When the task started, in milliseconds since the epoch
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateParam {
+    pub name: String,
+    pub label: String,
+    pub required: bool,
+    pub default_value: Option<String>,
+}
+
+/// A parameterized statement for a common admin task, rendered per dialect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub params: Vec<TemplateParam>,
+}
+
+/// Statement written by `generate_query_template` for a table
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum QueryTemplateKind {
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
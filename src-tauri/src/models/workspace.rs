@@ -0,0 +1,107 @@
+use super::schema::{SortOrder, TableFilter};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TabKind {
+    Query,
+    TableData,
+    TableStructure,
+}
+
+/// Where a table-data tab was: page, sort and filters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableBrowserState {
+    pub page: u32,
+    pub page_size: u32,
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    #[serde(default)]
+    pub sort_order: Option<SortOrder>,
+    #[serde(default)]
+    pub filters: Vec<TableFilter>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceTab {
+    pub id: String,
+    pub title: String,
+    pub kind: TabKind,
+    #[serde(default)]
+    pub database: Option<String>,
+    /// Editor contents of a query tab
+    #[serde(default)]
+    pub query_text: Option<String>,
+    #[serde(default)]
+    pub table: Option<String>,
+    #[serde(default)]
+    pub table_state: Option<TableBrowserState>,
+    /// Vertical scroll offset of the tab's main view, in pixels
+    #[serde(default)]
+    pub scroll_top: f64,
+}
+
+/// The tabs open on a connection, restored when the app starts again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub connection_id: String,
+    pub tabs: Vec<WorkspaceTab>,
+    #[serde(default)]
+    pub active_tab_id: Option<String>,
+}
+
+/// A table pinned to the top of a connection's sidebar
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PinnedTable {
+    pub connection_id: String,
+    pub database: String,
+    pub table: String,
+    pub pinned_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RecentObjectKind {
+    Database,
+    Table,
+    View,
+}
+
+impl fmt::Display for RecentObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecentObjectKind::Database => write!(f, "Database"),
+            RecentObjectKind::Table => write!(f, "Table"),
+            RecentObjectKind::View => write!(f, "View"),
+        }
+    }
+}
+
+impl FromStr for RecentObjectKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Database" => Ok(RecentObjectKind::Database),
+            "Table" => Ok(RecentObjectKind::Table),
+            "View" => Ok(RecentObjectKind::View),
+            _ => Err(format!("Invalid recent object kind: {}", s)),
+        }
+    }
+}
+
+/// A database, table or view recently opened on a connection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecentObject {
+    pub connection_id: String,
+    pub kind: RecentObjectKind,
+    pub database: String,
+    /// The table or view; None for a database
+    #[serde(default)]
+    pub name: Option<String>,
+    /// When it was last opened, in milliseconds since the epoch
+    pub opened_at: i64,
+    /// How many times it was opened
+    #[serde(default)]
+    pub open_count: u32,
+}
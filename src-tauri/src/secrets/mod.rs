@@ -0,0 +1,258 @@
+//! Credentials kept in an external secret store rather than the connection
+//! store. A connection's `SecretRef` is resolved when its pool opens, and
+//! fetched secrets are cached until their lease runs out.
+
+pub mod vault;
+
+use crate::models::{Connection, SecretRef, VaultSettings};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// How long a secret without a lease of its own is reused
+pub const DEFAULT_SECRET_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Error, Debug)]
+pub enum SecretError {
+    /// Vault has no address in the settings or the environment, or no token
+    #[error("Vault is not configured: {0}")]
+    NotConfigured(String),
+
+    #[error("Secret request to {path} failed: {message}")]
+    Request { path: String, message: String },
+
+    #[error("Vault denied access to {0}")]
+    Forbidden(String),
+
+    #[error("No secret at {0}")]
+    NotFound(String),
+
+    #[error("Secret {path} has no field {key}")]
+    MissingKey { path: String, key: String },
+}
+
+pub type Result<T> = std::result::Result<T, SecretError>;
+
+/// The fields of a secret and how long they may be reused
+#[derive(Debug, Clone, PartialEq)]
+pub struct Secret {
+    pub values: HashMap<String, String>,
+    pub ttl: Duration,
+}
+
+impl Secret {
+    fn get(&self, path: &str, key: &str) -> Result<String> {
+        self.values
+            .get(key)
+            .cloned()
+            .ok_or_else(|| SecretError::MissingKey {
+                path: path.to_string(),
+                key: key.to_string(),
+            })
+    }
+}
+
+/// A store secrets can be read from by path
+#[async_trait]
+pub trait SecretBackend: Send + Sync {
+    async fn fetch(&self, path: &str) -> Result<Secret>;
+}
+
+struct CachedSecret {
+    secret: Secret,
+    fetched: Instant,
+}
+
+/// Fills in connections' credentials from their secret stores
+#[derive(Default)]
+pub struct SecretResolver {
+    vault: Mutex<VaultSettings>,
+    cache: Mutex<HashMap<String, CachedSecret>>,
+}
+
+impl SecretResolver {
+    /// Use new Vault settings, forgetting secrets read with the old ones
+    pub fn configure(&self, vault: &VaultSettings) {
+        if let Ok(mut current) = self.vault.lock() {
+            if *current != *vault {
+                *current = vault.clone();
+                self.clear();
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.clear();
+        }
+    }
+
+    /// Forget a connection's secret, e.g. after it was rejected
+    pub fn forget(&self, connection: &Connection) {
+        if let (Some(path), Ok(mut cache)) = (secret_path(connection), self.cache.lock()) {
+            cache.remove(path);
+        }
+    }
+
+    /// The connection with its credentials read from its secret store, or
+    /// unchanged when it doesn't reference one
+    pub async fn resolve(&self, connection: &Connection) -> Result<Connection> {
+        match &connection.options.password_secret {
+            Some(SecretRef::Vault { .. }) => {
+                let settings = self
+                    .vault
+                    .lock()
+                    .map(|settings| settings.clone())
+                    .unwrap_or_default();
+                let backend = vault::VaultBackend::new(&settings)?;
+                self.resolve_with(connection, &backend).await
+            }
+            None => Ok(connection.clone()),
+        }
+    }
+
+    async fn resolve_with(
+        &self,
+        connection: &Connection,
+        backend: &dyn SecretBackend,
+    ) -> Result<Connection> {
+        let Some(SecretRef::Vault {
+            path,
+            key,
+            username_key,
+        }) = &connection.options.password_secret
+        else {
+            return Ok(connection.clone());
+        };
+
+        let secret = self.fetch(path, backend).await?;
+        let mut resolved = connection.clone();
+        resolved.password = secret.get(path, key)?;
+        if let Some(username_key) = username_key {
+            resolved.username = secret.get(path, username_key)?;
+        }
+        Ok(resolved)
+    }
+
+    async fn fetch(&self, path: &str, backend: &dyn SecretBackend) -> Result<Secret> {
+        if let Ok(cache) = self.cache.lock() {
+            if let Some(cached) = cache.get(path) {
+                if cached.fetched.elapsed() < cached.secret.ttl {
+                    return Ok(cached.secret.clone());
+                }
+            }
+        }
+
+        tracing::info!(path, "Fetching secret");
+        let secret = backend.fetch(path).await?;
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(
+                path.to_string(),
+                CachedSecret {
+                    secret: secret.clone(),
+                    fetched: Instant::now(),
+                },
+            );
+        }
+        Ok(secret)
+    }
+}
+
+fn secret_path(connection: &Connection) -> Option<&str> {
+    match &connection.options.password_secret {
+        Some(SecretRef::Vault { path, .. }) => Some(path),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DatabaseType;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct FakeBackend {
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SecretBackend for FakeBackend {
+        async fn fetch(&self, path: &str) -> Result<Secret> {
+            if path != "secret/data/prod/db" {
+                return Err(SecretError::NotFound(path.to_string()));
+            }
+            let count = self.fetches.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(Secret {
+                values: HashMap::from([
+                    ("username".to_string(), "app".to_string()),
+                    ("password".to_string(), format!("rotated-{}", count)),
+                ]),
+                ttl: DEFAULT_SECRET_TTL,
+            })
+        }
+    }
+
+    fn connection(path: &str, key: &str) -> Connection {
+        let mut connection = Connection::new(
+            "prod".to_string(),
+            "#ef4444".to_string(),
+            DatabaseType::MySQL,
+            "db.internal".to_string(),
+            3306,
+            "root".to_string(),
+            String::new(),
+        );
+        connection.options.password_secret = Some(SecretRef::Vault {
+            path: path.to_string(),
+            key: key.to_string(),
+            username_key: Some("username".to_string()),
+        });
+        connection
+    }
+
+    #[tokio::test]
+    async fn test_resolve_caches_until_forgotten() {
+        let resolver = SecretResolver::default();
+        let backend = FakeBackend {
+            fetches: AtomicUsize::new(0),
+        };
+        let conn = connection("secret/data/prod/db", "password");
+
+        let resolved = resolver.resolve_with(&conn, &backend).await.unwrap();
+        assert_eq!(resolved.username, "app");
+        assert_eq!(resolved.password, "rotated-1");
+
+        let again = resolver.resolve_with(&conn, &backend).await.unwrap();
+        assert_eq!(again.password, "rotated-1");
+
+        resolver.forget(&conn);
+        let fresh = resolver.resolve_with(&conn, &backend).await.unwrap();
+        assert_eq!(fresh.password, "rotated-2");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_reports_missing_secrets() {
+        let resolver = SecretResolver::default();
+        let backend = FakeBackend {
+            fetches: AtomicUsize::new(0),
+        };
+
+        let missing_key = connection("secret/data/prod/db", "pass");
+        assert_eq!(
+            resolver
+                .resolve_with(&missing_key, &backend)
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Secret secret/data/prod/db has no field pass"
+        );
+
+        let missing_path = connection("secret/data/other", "password");
+        assert!(matches!(
+            resolver.resolve_with(&missing_path, &backend).await,
+            Err(SecretError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+//! Reading secrets from HashiCorp Vault's HTTP API. Both KV engine versions
+//! are understood: version 2 nests the fields under `data.data`.
+
+use super::{Result, Secret, SecretBackend, SecretError, DEFAULT_SECRET_TTL};
+use crate::models::VaultSettings;
+use crate::ssh::config::home_dir;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long a request to Vault may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct VaultBackend {
+    client: reqwest::Client,
+    address: String,
+    token: String,
+    namespace: Option<String>,
+}
+
+/// A setting, or the environment variable the vault CLI reads instead
+fn setting_or_env(value: &Option<String>, variable: &str) -> Option<String> {
+    value
+        .clone()
+        .or_else(|| std::env::var(variable).ok())
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// The token of `VAULT_TOKEN`, or else the one `vault login` saved
+fn token() -> Option<String> {
+    let saved = || {
+        let token = std::fs::read_to_string(home_dir()?.join(".vault-token")).ok()?;
+        Some(token.trim().to_string()).filter(|token| !token.is_empty())
+    };
+    setting_or_env(&None, "VAULT_TOKEN").or_else(saved)
+}
+
+impl VaultBackend {
+    pub fn new(settings: &VaultSettings) -> Result<Self> {
+        let address = setting_or_env(&settings.address, "VAULT_ADDR")
+            .ok_or_else(|| SecretError::NotConfigured("no address".to_string()))?;
+        let token = token().ok_or_else(|| SecretError::NotConfigured("no token".to_string()))?;
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| SecretError::Request {
+                path: address.clone(),
+                message: e.to_string(),
+            })?;
+
+        Ok(Self {
+            client,
+            address: address.trim_end_matches('/').to_string(),
+            token,
+            namespace: setting_or_env(&settings.namespace, "VAULT_NAMESPACE"),
+        })
+    }
+}
+
+#[async_trait]
+impl SecretBackend for VaultBackend {
+    async fn fetch(&self, path: &str) -> Result<Secret> {
+        let request_error = |message: String| SecretError::Request {
+            path: path.to_string(),
+            message,
+        };
+
+        let url = format!("{}/v1/{}", self.address, path.trim_start_matches('/'));
+        let mut request = self.client.get(&url).header("X-Vault-Token", &self.token);
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| request_error(e.to_string()))?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        match status {
+            StatusCode::FORBIDDEN => return Err(SecretError::Forbidden(path.to_string())),
+            StatusCode::NOT_FOUND => return Err(SecretError::NotFound(path.to_string())),
+            status if !status.is_success() => {
+                return Err(request_error(error_message(status, &body)));
+            }
+            _ => {}
+        }
+
+        parse_secret(&body).ok_or_else(|| request_error("Unexpected response".to_string()))
+    }
+}
+
+/// Vault's `errors` list, or the status when there is none
+fn error_message(status: StatusCode, body: &Value) -> String {
+    let errors: Vec<&str> = body["errors"]
+        .as_array()
+        .map(|errors| errors.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if errors.is_empty() {
+        status.to_string()
+    } else {
+        errors.join("; ")
+    }
+}
+
+/// The fields and lease of a read response. Non-string fields are kept as
+/// their JSON text.
+fn parse_secret(body: &Value) -> Option<Secret> {
+    let data = &body["data"];
+    // KV version 2 wraps the fields with their metadata
+    let fields = match (&data["data"], &data["metadata"]) {
+        (Value::Object(fields), Value::Object(_)) => fields,
+        _ => data.as_object()?,
+    };
+
+    let values: HashMap<String, String> = fields
+        .iter()
+        .map(|(key, value)| {
+            let text = match value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            (key.clone(), text)
+        })
+        .collect();
+    let ttl = match body["lease_duration"].as_u64() {
+        Some(seconds) if seconds > 0 => Duration::from_secs(seconds),
+        _ => DEFAULT_SECRET_TTL,
+    };
+    Some(Secret { values, ttl })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_kv_versions() {
+        let v2 = json!({
+            "lease_duration": 0,
+            "data": {
+                "data": {"password": "s3cret", "port": 3306},
+                "metadata": {"version": 4}
+            }
+        });
+        let secret = parse_secret(&v2).unwrap();
+        assert_eq!(secret.values["password"], "s3cret");
+        assert_eq!(secret.values["port"], "3306");
+        assert_eq!(secret.ttl, DEFAULT_SECRET_TTL);
+
+        let v1 = json!({"lease_duration": 60, "data": {"password": "old"}});
+        let secret = parse_secret(&v1).unwrap();
+        assert_eq!(secret.values["password"], "old");
+        assert_eq!(secret.ttl, Duration::from_secs(60));
+
+        assert!(parse_secret(&json!({"errors": []})).is_none());
+    }
+
+    #[test]
+    fn test_error_message() {
+        let body = json!({"errors": ["permission denied", "1 error occurred"]});
+        assert_eq!(
+            error_message(StatusCode::BAD_REQUEST, &body),
+            "permission denied; 1 error occurred"
+        );
+        assert_eq!(
+            error_message(StatusCode::BAD_GATEWAY, &Value::Null),
+            "502 Bad Gateway"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Minimal ~/.ssh/config reader used to resolve host aliases the way OpenSSH does:
+//! every `Host` block whose patterns match contributes values, and the first
+//! value seen for a keyword wins. `Match` blocks and `Include` are not supported.
+
+use crate::models::connection::{SSHAuth, SSHConfig};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SshConfigError {
+    #[error("Could not determine the home directory")]
+    NoHomeDirectory,
+
+    #[error("Failed to read {path}: {message}")]
+    Io { path: String, message: String },
+
+    #[error("Invalid Port for host {host}: {value}")]
+    InvalidPort { host: String, value: String },
+}
+
+pub type Result<T> = std::result::Result<T, SshConfigError>;
+
+/// Values ~/.ssh/config defines for a host
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SshHostEntry {
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<String>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn expand_tilde(path: &str, home: &Path) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest).to_string_lossy().into_owned(),
+        None => path.to_string(),
+    }
+}
+
+/// Glob match supporting `*` and `?`, as used by `Host` patterns
+fn pattern_matches(pattern: &str, host: &str) -> bool {
+    fn matches(pattern: &[char], host: &[char]) -> bool {
+        match pattern.split_first() {
+            None => host.is_empty(),
+            Some(('*', rest)) => (0..=host.len()).any(|i| matches(rest, &host[i..])),
+            Some(('?', rest)) => !host.is_empty() && matches(rest, &host[1..]),
+            Some((c, rest)) => host.first() == Some(c) && matches(rest, &host[1..]),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let host: Vec<char> = host.to_lowercase().chars().collect();
+    matches(&pattern, &host)
+}
+
+fn host_line_matches(patterns: &[&str], host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if pattern_matches(negated, host) => return false,
+            Some(_) => {}
+            None => matched |= pattern_matches(pattern, host),
+        }
+    }
+    matched
+}
+
+/// Collect the settings that apply to `host` from config file contents
+pub fn lookup(contents: &str, host: &str) -> SshHostEntry {
+    let mut entry = SshHostEntry::default();
+    // Lines before the first Host block apply to every host
+    let mut active = true;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((keyword, value)) => (keyword, value.trim_start_matches([' ', '\t', '=']).trim()),
+            None => (line, ""),
+        };
+        let value = value.trim_matches('"');
+
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                let patterns: Vec<&str> = value.split_whitespace().collect();
+                active = host_line_matches(&patterns, host);
+            }
+            "match" => active = false,
+            _ if !active => {}
+            "hostname" => set_once(&mut entry.host_name, value),
+            "user" => set_once(&mut entry.user, value),
+            "port" => set_once(&mut entry.port, value),
+            "identityfile" => set_once(&mut entry.identity_file, value),
+            "proxyjump" => set_once(&mut entry.proxy_jump, value),
+            _ => {}
+        }
+    }
+
+    entry
+}
+
+fn set_once(slot: &mut Option<String>, value: &str) {
+    if slot.is_none() && !value.is_empty() {
+        *slot = Some(value.to_string());
+    }
+}
+
+/// Apply an entry to an SSH configuration. Values from ~/.ssh/config take
+/// precedence, except that an explicit password or key in the app is kept.
+pub fn apply(config: &SSHConfig, entry: &SshHostEntry, home: &Path) -> Result<SSHConfig> {
+    let mut resolved = config.clone();
+
+    if let Some(host_name) = &entry.host_name {
+        resolved.host = host_name.replace("%h", &config.host);
+    }
+    if let Some(user) = &entry.user {
+        resolved.username = user.clone();
+    }
+    if let Some(port) = &entry.port {
+        resolved.port = port.parse().map_err(|_| SshConfigError::InvalidPort {
+            host: config.host.clone(),
+            value: port.clone(),
+        })?;
+    }
+    if let (Some(identity_file), SSHAuth::Agent) = (&entry.identity_file, &config.auth) {
+        resolved.auth = SSHAuth::PrivateKey {
+            key_path: expand_tilde(identity_file, home),
+            passphrase: None,
+        };
+    }
+    if resolved.proxy_jump.is_none() {
+        resolved.proxy_jump = entry.proxy_jump.clone().filter(|j| j != "none");
+    }
+
+    Ok(resolved)
+}
+
+/// Resolve `config.host` against ~/.ssh/config when the connection opted in
+pub fn resolve(config: &SSHConfig) -> Result<SSHConfig> {
+    if !config.use_ssh_config {
+        return Ok(config.clone());
+    }
+
+    let home = home_dir().ok_or(SshConfigError::NoHomeDirectory)?;
+    let path = home.join(".ssh").join("config");
+    if !path.exists() {
+        return Ok(config.clone());
+    }
+
+    let contents = std::fs::read_to_string(&path).map_err(|e| SshConfigError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+
+    apply(config, &lookup(&contents, &config.host), &home)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Global defaults
+ServerAliveInterval 30
+
+Host prod-db
+    HostName db.internal.example.com
+    User deploy
+    Port 2222
+    IdentityFile ~/.ssh/prod_ed25519
+    ProxyJump bastion
+
+Host *.example.com !secret.example.com
+    User wildcard
+
+Host *
+    User fallback
+    IdentityFile ~/.ssh/id_rsa
+"#;
+
+    fn ssh_config(host: &str, auth: SSHAuth) -> SSHConfig {
+        SSHConfig {
+            host: host.to_string(),
+            port: 22,
+            username: String::new(),
+            auth,
+            use_ssh_config: true,
+            proxy_jump: None,
+            jump_hosts: vec![],
+        }
+    }
+
+    #[test]
+    fn test_lookup_first_value_wins() {
+        let entry = lookup(CONFIG, "prod-db");
+        assert_eq!(entry.host_name.as_deref(), Some("db.internal.example.com"));
+        assert_eq!(entry.user.as_deref(), Some("deploy"));
+        assert_eq!(entry.port.as_deref(), Some("2222"));
+        assert_eq!(entry.identity_file.as_deref(), Some("~/.ssh/prod_ed25519"));
+        assert_eq!(entry.proxy_jump.as_deref(), Some("bastion"));
+    }
+
+    #[test]
+    fn test_lookup_wildcards_and_negation() {
+        assert_eq!(
+            lookup(CONFIG, "api.example.com").user.as_deref(),
+            Some("wildcard")
+        );
+        assert_eq!(
+            lookup(CONFIG, "secret.example.com").user.as_deref(),
+            Some("fallback")
+        );
+        assert!(lookup(CONFIG, "other").host_name.is_none());
+    }
+
+    #[test]
+    fn test_apply_resolves_alias() {
+        let home = Path::new("/home/me");
+        let config = ssh_config("prod-db", SSHAuth::Agent);
+
+        let resolved = apply(&config, &lookup(CONFIG, "prod-db"), home).unwrap();
+        assert_eq!(resolved.host, "db.internal.example.com");
+        assert_eq!(resolved.port, 2222);
+        assert_eq!(resolved.username, "deploy");
+        assert_eq!(resolved.proxy_jump.as_deref(), Some("bastion"));
+        match resolved.auth {
+            SSHAuth::PrivateKey { key_path, .. } => {
+                assert_eq!(key_path, "/home/me/.ssh/prod_ed25519")
+            }
+            other => panic!("unexpected auth: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_apply_keeps_explicit_password() {
+        let config = ssh_config("prod-db", SSHAuth::Password("pw".to_string()));
+        let resolved = apply(&config, &lookup(CONFIG, "prod-db"), Path::new("/home/me")).unwrap();
+        assert!(matches!(resolved.auth, SSHAuth::Password(_)));
+    }
+
+    #[test]
+    fn test_resolve_without_opt_in_is_unchanged() {
+        let mut config = ssh_config("prod-db", SSHAuth::Agent);
+        config.use_ssh_config = false;
+        assert_eq!(resolve(&config).unwrap().host, "prod-db");
+    }
+}
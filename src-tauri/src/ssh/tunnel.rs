@@ -0,0 +1,566 @@
+//! SSH tunnels to a database host, opened with the system's OpenSSH client.
+//! Each jump host is reached through the one before it with `ssh -W`, so
+//! every hop keeps its own user, port and authentication, and a failure can
+//! be traced back to the hop that caused it.
+
+use crate::models::connection::{SSHAuth, SSHConfig, SSHHop};
+use crate::ssh::config::{self, SshConfigError};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+
+/// Address the local end of a tunnel listens on
+pub const LOCAL_HOST: &str = "127.0.0.1";
+
+/// How often a starting tunnel is checked for readiness
+const READY_POLL: Duration = Duration::from_millis(100);
+
+/// Lines of ssh's output kept to explain a failure
+const STDERR_LINES: usize = 50;
+
+/// Environment variable holding the password or passphrase of hop `n`
+const SECRET_VAR: &str = "DBCLIENT_SSH_SECRET";
+
+#[derive(Error, Debug)]
+pub enum TunnelError {
+    #[error("Invalid jump host: {0}")]
+    InvalidJumpHost(String),
+
+    #[error("Failed to start ssh: {0}")]
+    Spawn(String),
+
+    #[error("SSH hop {hop} of {hops} ({host}) failed: {message}")]
+    HopFailed {
+        /// 1-based position of the hop, the database's SSH host being last
+        hop: usize,
+        hops: usize,
+        host: String,
+        message: String,
+    },
+
+    #[error("SSH tunnel failed: {0}")]
+    Failed(String),
+
+    #[error("SSH tunnel was not ready after {0} seconds")]
+    Timeout(u64),
+
+    #[error(transparent)]
+    Config(#[from] SshConfigError),
+}
+
+pub type Result<T> = std::result::Result<T, TunnelError>;
+
+/// A running `ssh` process forwarding a local port to the database. The
+/// process is stopped when this is dropped.
+pub struct SshTunnel {
+    child: Child,
+    local_port: u16,
+    askpass: Option<PathBuf>,
+}
+
+impl SshTunnel {
+    /// Tunnel through every hop of `ssh_config` to `target`, a `host:port`
+    /// or a socket path on the last hop, waiting until the last hop accepts
+    /// forwarded connections
+    pub async fn open(ssh_config: &SSHConfig, target: &str, timeout: Duration) -> Result<Self> {
+        let hops = hops(ssh_config)?;
+        let local_port = free_port().await?;
+        let forward = format!("{}:{}:{}", LOCAL_HOST, local_port, target);
+        let common = common_options(&hops, timeout);
+        let args = hop_command(
+            &hops,
+            hops.len() - 1,
+            &common,
+            &["-N", "-o", "ExitOnForwardFailure=yes", "-L", &forward],
+        );
+
+        let mut command = Command::new(&args[0]);
+        command
+            .args(&args[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let askpass = match askpass_script(&hops) {
+            Some(script) => {
+                let path = write_askpass(&script)?;
+                command
+                    .env("SSH_ASKPASS", &path)
+                    .env("SSH_ASKPASS_REQUIRE", "force");
+                for (index, secret) in hops.iter().map(|hop| secret(&hop.auth)).enumerate() {
+                    if let Some(secret) = secret {
+                        command.env(format!("{}_{}", SECRET_VAR, index), secret);
+                    }
+                }
+                Some(path)
+            }
+            None => None,
+        };
+
+        tracing::info!(hops = hops.len(), local_port, "Opening SSH tunnel");
+        let mut child = command
+            .spawn()
+            .map_err(|e| TunnelError::Spawn(e.to_string()))?;
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let reader = collect_stderr(&mut child, stderr.clone());
+
+        let mut tunnel = Self {
+            child,
+            local_port,
+            askpass,
+        };
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Ok(Some(_)) = tunnel.child.try_wait() {
+                // Let the reader pick up ssh's last words
+                if let Some(reader) = reader {
+                    let _ = tokio::time::timeout(Duration::from_secs(1), reader).await;
+                }
+                let lines = stderr.lock().map(|lines| lines.clone()).unwrap_or_default();
+                return Err(hop_failure(&lines, &hops));
+            }
+            if TcpStream::connect((LOCAL_HOST, local_port)).await.is_ok() {
+                return Ok(tunnel);
+            }
+            if Instant::now() >= deadline {
+                return Err(TunnelError::Timeout(timeout.as_secs()));
+            }
+            tokio::time::sleep(READY_POLL).await;
+        }
+    }
+
+    /// Port on `LOCAL_HOST` forwarded to the database
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    /// Whether the ssh process is no longer running
+    pub fn has_exited(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        if let Some(path) = &self.askpass {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// The hops of a tunnel in order, ending with `ssh_config`'s own host. Hosts
+/// are resolved against ~/.ssh/config when the connection opted in.
+pub fn hops(ssh_config: &SSHConfig) -> Result<Vec<SSHHop>> {
+    let target = config::resolve(ssh_config)?;
+
+    let mut hops = if !ssh_config.jump_hosts.is_empty() {
+        ssh_config.jump_hosts.clone()
+    } else {
+        match &target.proxy_jump {
+            Some(spec) => parse_proxy_jump(spec)?,
+            None => vec![],
+        }
+    };
+    if ssh_config.use_ssh_config {
+        hops = hops
+            .into_iter()
+            .map(resolve_hop)
+            .collect::<std::result::Result<_, _>>()?;
+    }
+
+    hops.push(SSHHop {
+        host: target.host,
+        port: target.port,
+        username: target.username,
+        auth: target.auth,
+    });
+    Ok(hops)
+}
+
+fn resolve_hop(hop: SSHHop) -> std::result::Result<SSHHop, SshConfigError> {
+    let resolved = config::resolve(&SSHConfig {
+        host: hop.host,
+        port: hop.port,
+        username: hop.username,
+        auth: hop.auth,
+        use_ssh_config: true,
+        proxy_jump: None,
+        jump_hosts: vec![],
+    })?;
+    Ok(SSHHop {
+        host: resolved.host,
+        port: resolved.port,
+        username: resolved.username,
+        auth: resolved.auth,
+    })
+}
+
+/// Parse OpenSSH's `[user@]host[:port]` list, e.g. `ops@bastion:2222,jump`
+pub fn parse_proxy_jump(spec: &str) -> Result<Vec<SSHHop>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .map(|hop| {
+            let invalid = || TunnelError::InvalidJumpHost(hop.to_string());
+            let spec = hop.strip_prefix("ssh://").unwrap_or(hop);
+            let (username, address) = match spec.rsplit_once('@') {
+                Some((username, address)) => (username.to_string(), address),
+                None => (String::new(), spec),
+            };
+            let (host, port) = match address.strip_prefix('[') {
+                // [::1]:2222
+                Some(bracketed) => {
+                    let (host, rest) = bracketed.split_once(']').ok_or_else(invalid)?;
+                    (host, rest.strip_prefix(':'))
+                }
+                None => match address.split_once(':') {
+                    Some((host, port)) => (host, Some(port)),
+                    None => (address, None),
+                },
+            };
+            let port = match port {
+                Some(port) => port.parse().map_err(|_| invalid())?,
+                None => 22,
+            };
+            if host.is_empty() {
+                return Err(invalid());
+            }
+            Ok(SSHHop {
+                host: host.to_string(),
+                port,
+                username,
+                auth: SSHAuth::Agent,
+            })
+        })
+        .collect()
+}
+
+/// Options passed to the ssh process of every hop
+fn common_options(hops: &[SSHHop], timeout: Duration) -> Vec<String> {
+    let mut options = vec![
+        "-o".to_string(),
+        "StrictHostKeyChecking=accept-new".to_string(),
+        "-o".to_string(),
+        format!("ConnectTimeout={}", timeout.as_secs().max(1)),
+        "-o".to_string(),
+        "ServerAliveInterval=30".to_string(),
+    ];
+    // Without secrets to answer with, ssh must fail rather than prompt
+    if hops.iter().all(|hop| secret(&hop.auth).is_none()) {
+        options.extend(["-o".to_string(), "BatchMode=yes".to_string()]);
+    }
+    options
+}
+
+/// Command line of the ssh process logging in to `hops[index]`, reaching it
+/// through the hops before it. `tail` goes right before the host.
+fn hop_command(hops: &[SSHHop], index: usize, common: &[String], tail: &[&str]) -> Vec<String> {
+    let hop = &hops[index];
+    let mut args = vec!["ssh".to_string()];
+    args.extend(common.iter().cloned());
+    args.extend(["-p".to_string(), hop.port.to_string()]);
+    if !hop.username.is_empty() {
+        args.extend(["-l".to_string(), hop.username.clone()]);
+    }
+    match &hop.auth {
+        SSHAuth::PrivateKey { key_path, .. } => args.extend([
+            "-i".to_string(),
+            key_path.clone(),
+            "-o".to_string(),
+            "IdentitiesOnly=yes".to_string(),
+        ]),
+        SSHAuth::Password(_) => args.extend([
+            "-o".to_string(),
+            "PreferredAuthentications=password,keyboard-interactive".to_string(),
+        ]),
+        SSHAuth::Agent => {}
+    }
+    if index > 0 {
+        let stdio = host_port(&hop.host, hop.port);
+        let proxy = hop_command(hops, index - 1, common, &["-W", &stdio]);
+        // ssh expands `%` tokens in ProxyCommand, so literal ones are doubled
+        let proxy = proxy
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace('%', "%%");
+        args.extend(["-o".to_string(), format!("ProxyCommand={}", proxy)]);
+    }
+    args.extend(tail.iter().map(|arg| arg.to_string()));
+    args.push(hop.host.clone());
+    args
+}
+
+/// `host:port`, bracketing IPv6 addresses
+pub fn host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-[]".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// The password or key passphrase ssh will ask for, if any
+fn secret(auth: &SSHAuth) -> Option<&str> {
+    match auth {
+        SSHAuth::Password(password) => Some(password),
+        SSHAuth::PrivateKey {
+            passphrase: Some(passphrase),
+            ..
+        } => Some(passphrase),
+        _ => None,
+    }
+}
+
+/// SSH_ASKPASS program answering each hop's password or passphrase prompt
+/// from the environment, so secrets never appear on a command line. None
+/// when no hop has a secret.
+fn askpass_script(hops: &[SSHHop]) -> Option<String> {
+    let mut cases = String::new();
+    for (index, hop) in hops.iter().enumerate() {
+        // Prompts look like `user@host's password:`, `(user@host) Password:`
+        // and `Enter passphrase for key '/path':`
+        let pattern = match &hop.auth {
+            SSHAuth::Password(_) => format!("{}@{}", hop.username, hop.host),
+            SSHAuth::PrivateKey {
+                key_path,
+                passphrase: Some(_),
+            } => key_path.clone(),
+            _ => continue,
+        };
+        cases.push_str(&format!(
+            "  *{}*) printf '%s\\n' \"${}_{}\" ;;\n",
+            shell_quote(&pattern),
+            SECRET_VAR,
+            index
+        ));
+    }
+    if cases.is_empty() {
+        return None;
+    }
+    Some(format!("#!/bin/sh\ncase \"$1\" in\n{}esac\n", cases))
+}
+
+fn write_askpass(script: &str) -> Result<PathBuf> {
+    use std::io::Write;
+
+    let path = std::env::temp_dir().join(format!("dbclient-askpass-{}", uuid::Uuid::new_v4()));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o700);
+    }
+    let mut file = options
+        .open(&path)
+        .map_err(|e| TunnelError::Spawn(e.to_string()))?;
+    file.write_all(script.as_bytes())
+        .map_err(|e| TunnelError::Spawn(e.to_string()))?;
+    Ok(path)
+}
+
+async fn free_port() -> Result<u16> {
+    let listener = TcpListener::bind((LOCAL_HOST, 0))
+        .await
+        .map_err(|e| TunnelError::Spawn(e.to_string()))?;
+    listener
+        .local_addr()
+        .map(|addr| addr.port())
+        .map_err(|e| TunnelError::Spawn(e.to_string()))
+}
+
+/// Keep reading ssh's stderr, so a chatty process never blocks on a full
+/// pipe, remembering the last lines
+fn collect_stderr(child: &mut Child, lines: Arc<Mutex<Vec<String>>>) -> Option<JoinHandle<()>> {
+    let stderr = child.stderr.take()?;
+    Some(tokio::spawn(async move {
+        let mut reader = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = reader.next_line().await {
+            tracing::debug!(line = %line, "ssh");
+            if let Ok(mut lines) = lines.lock() {
+                if lines.len() == STDERR_LINES {
+                    lines.remove(0);
+                }
+                lines.push(line);
+            }
+        }
+    }))
+}
+
+/// Explain why ssh exited, naming the first hop its output mentions
+fn hop_failure(stderr: &[String], hops: &[SSHHop]) -> TunnelError {
+    let lines: Vec<&str> = stderr
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with("Warning:"))
+        .collect();
+
+    for line in &lines {
+        // The longest host that matches, in case one host contains another
+        let mentioned = hops
+            .iter()
+            .enumerate()
+            .filter(|(_, hop)| line.contains(hop.host.as_str()))
+            .max_by_key(|(_, hop)| hop.host.len());
+        if let Some((index, hop)) = mentioned {
+            return TunnelError::HopFailed {
+                hop: index + 1,
+                hops: hops.len(),
+                host: hop.host.clone(),
+                message: line.trim_start_matches("ssh: ").to_string(),
+            };
+        }
+    }
+
+    TunnelError::Failed(
+        lines
+            .last()
+            .map(|line| line.to_string())
+            .unwrap_or_else(|| "ssh exited without an error message".to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hop(host: &str, username: &str, auth: SSHAuth) -> SSHHop {
+        SSHHop {
+            host: host.to_string(),
+            port: 22,
+            username: username.to_string(),
+            auth,
+        }
+    }
+
+    #[test]
+    fn test_parse_proxy_jump() {
+        let hops = parse_proxy_jump("ops@bastion:2222, jump,[fd00::1]:23").unwrap();
+        assert_eq!(hops.len(), 3);
+        assert_eq!(
+            (hops[0].username.as_str(), hops[0].host.as_str()),
+            ("ops", "bastion")
+        );
+        assert_eq!(hops[0].port, 2222);
+        assert_eq!((hops[1].host.as_str(), hops[1].port), ("jump", 22));
+        assert_eq!((hops[2].host.as_str(), hops[2].port), ("fd00::1", 23));
+
+        assert!(parse_proxy_jump("bastion:ssh").is_err());
+        assert!(parse_proxy_jump("ops@").is_err());
+    }
+
+    #[test]
+    fn test_hops_end_with_the_ssh_host() {
+        let config = SSHConfig {
+            host: "db-host".to_string(),
+            port: 22,
+            username: "deploy".to_string(),
+            auth: SSHAuth::Agent,
+            use_ssh_config: false,
+            proxy_jump: Some("bastion".to_string()),
+            jump_hosts: vec![],
+        };
+        let names: Vec<String> = hops(&config).unwrap().into_iter().map(|h| h.host).collect();
+        assert_eq!(names, vec!["bastion", "db-host"]);
+
+        let config = SSHConfig {
+            jump_hosts: vec![hop("edge", "ops", SSHAuth::Agent)],
+            ..config
+        };
+        let names: Vec<String> = hops(&config).unwrap().into_iter().map(|h| h.host).collect();
+        assert_eq!(names, vec!["edge", "db-host"]);
+    }
+
+    #[test]
+    fn test_hop_command_chains_proxy_commands() {
+        let hops = vec![
+            hop("bastion", "ops", SSHAuth::Agent),
+            hop(
+                "jump",
+                "",
+                SSHAuth::PrivateKey {
+                    key_path: "/keys/my key".to_string(),
+                    passphrase: None,
+                },
+            ),
+            hop("db-host", "deploy", SSHAuth::Password("pw".to_string())),
+        ];
+        let args = hop_command(&hops, 2, &[], &["-N"]);
+        assert_eq!(args[..5], ["ssh", "-p", "22", "-l", "deploy"]);
+        assert_eq!(args[args.len() - 2..], ["-N", "db-host"]);
+
+        let proxy = args
+            .iter()
+            .find_map(|arg| arg.strip_prefix("ProxyCommand="))
+            .unwrap();
+        assert!(proxy.starts_with("ssh -p 22 -i '/keys/my key' -o IdentitiesOnly=yes"));
+        assert!(proxy.contains("-W db-host:22 jump"));
+        // The first hop is nested one level deeper
+        assert!(proxy.contains("'ProxyCommand=ssh -p 22 -l ops -W jump:22 bastion'"));
+        assert!(!proxy.contains("pw"));
+    }
+
+    #[test]
+    fn test_askpass_script_matches_prompts() {
+        let hops = vec![
+            hop("bastion", "ops", SSHAuth::Password("secret".to_string())),
+            hop("db-host", "deploy", SSHAuth::Agent),
+        ];
+        let script = askpass_script(&hops).unwrap();
+        assert!(script.contains("*ops@bastion*) printf '%s\\n' \"$DBCLIENT_SSH_SECRET_0\" ;;"));
+        assert!(!script.contains("secret\""));
+        assert!(askpass_script(&hops[1..]).is_none());
+    }
+
+    #[test]
+    fn test_hop_failure_names_the_hop() {
+        let hops = vec![
+            hop("bastion", "ops", SSHAuth::Agent),
+            hop("db-bastion", "ops", SSHAuth::Agent),
+        ];
+        let stderr = vec![
+            "Warning: Permanently added 'bastion' (ED25519) to the list of known hosts."
+                .to_string(),
+            "ops@db-bastion: Permission denied (publickey).".to_string(),
+        ];
+        match hop_failure(&stderr, &hops) {
+            TunnelError::HopFailed {
+                hop,
+                hops,
+                host,
+                message,
+            } => {
+                assert_eq!((hop, hops, host.as_str()), (2, 2, "db-bastion"));
+                assert_eq!(message, "ops@db-bastion: Permission denied (publickey).");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let stderr = vec!["kex_exchange_identification: read: Connection reset".to_string()];
+        assert!(matches!(
+            hop_failure(&stderr, &hops),
+            TunnelError::Failed(_)
+        ));
+    }
+}
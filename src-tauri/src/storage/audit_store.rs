@@ -0,0 +1,137 @@
+use crate::models::AuditEntry;
+use crate::storage::connection_store::Result;
+use crate::storage::migrations::migrate;
+use rusqlite::{params, Connection as SqliteConnection, Row};
+use std::path::Path;
+
+/// Persists data-modifying statements issued through the app, as evidence of
+/// who changed what in a shared database
+pub struct AuditStore {
+    db: SqliteConnection,
+}
+
+fn entry_from_row(row: &Row<'_>) -> rusqlite::Result<AuditEntry> {
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        connection_id: row.get(1)?,
+        database: row.get(2)?,
+        command: row.get(3)?,
+        sql: row.get(4)?,
+        rows_affected: row.get::<_, Option<i64>>(5)?.map(|rows| rows as u64),
+        success: row.get(6)?,
+        error_message: row.get(7)?,
+        executed_at: row.get(8)?,
+    })
+}
+
+impl AuditStore {
+    pub fn new(db_path: &Path) -> Result<Self> {
+        let mut db = SqliteConnection::open(db_path)?;
+        migrate(&mut db)?;
+
+        Ok(Self { db })
+    }
+
+    pub fn record(&mut self, entry: &AuditEntry) -> Result<()> {
+        self.db.execute(
+            "INSERT INTO audit_log
+            (id, connection_id, database, command, sql, rows_affected, success, error_message, executed_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                entry.id,
+                entry.connection_id,
+                entry.database,
+                entry.command,
+                entry.sql,
+                entry.rows_affected.map(|rows| rows as i64),
+                entry.success,
+                entry.error_message,
+                entry.executed_at,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Most recent entries, newest first, optionally for a single connection
+    pub fn list(&self, connection_id: Option<&str>, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.db.prepare(
+            "SELECT id, connection_id, database, command, sql, rows_affected, success, error_message, executed_at
+             FROM audit_log WHERE ?1 IS NULL OR connection_id = ?1
+             ORDER BY executed_at DESC LIMIT ?2",
+        )?;
+
+        let entries = stmt
+            .query_map(params![connection_id, limit as i64], entry_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Delete entries recorded before `cutoff` (unix millis), returning how many were removed
+    pub fn prune_before(&mut self, cutoff: i64) -> Result<usize> {
+        Ok(self.db.execute(
+            "DELETE FROM audit_log WHERE executed_at < ?1",
+            params![cutoff],
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_test_store() -> (AuditStore, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let store = AuditStore::new(&temp_dir.path().join("test.db")).unwrap();
+        (store, temp_dir)
+    }
+
+    fn entry(connection_id: &str, executed_at: i64) -> AuditEntry {
+        AuditEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            connection_id: connection_id.to_string(),
+            database: Some("shop".to_string()),
+            command: "update_table_row".to_string(),
+            sql: "UPDATE `orders` SET `status` = 'paid' WHERE `id` = 7".to_string(),
+            rows_affected: Some(1),
+            success: true,
+            error_message: None,
+            executed_at,
+        }
+    }
+
+    #[test]
+    fn test_record_and_list_entries() {
+        let (mut store, _temp) = setup_test_store();
+        store.record(&entry("a", 1)).unwrap();
+        store.record(&entry("b", 2)).unwrap();
+        let failed = AuditEntry {
+            rows_affected: None,
+            success: false,
+            error_message: Some("Table 'shop.orders' doesn't exist".to_string()),
+            ..entry("a", 3)
+        };
+        store.record(&failed).unwrap();
+
+        let all = store.list(None, 10).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0], failed);
+
+        let for_a = store.list(Some("a"), 10).unwrap();
+        let times: Vec<i64> = for_a.iter().map(|e| e.executed_at).collect();
+        assert_eq!(times, vec![3, 1]);
+        assert_eq!(for_a[1].rows_affected, Some(1));
+    }
+
+    #[test]
+    fn test_prune_before_cutoff() {
+        let (mut store, _temp) = setup_test_store();
+        store.record(&entry("a", 1)).unwrap();
+        store.record(&entry("a", 5)).unwrap();
+
+        assert_eq!(store.prune_before(5).unwrap(), 1);
+        assert_eq!(store.list(None, 10).unwrap().len(), 1);
+    }
+}
@@ -1,8 +1,34 @@
-use crate::models::{Connection, DatabaseType};
-use crate::storage::encryption::{decode_encrypted, decrypt, encode_encrypted, encrypt};
-use rusqlite::{params, Connection as SqliteConnection, Row};
-use std::path::Path;
+use crate::models::{
+    Connection, ConnectionGroup, ConnectionGroupNode, ConnectionSort, ConnectionTree,
+    DatabaseType, ImportStrategy, ImportSummary, StoreStatus,
+};
+use crate::storage::connection_transfer::ConnectionBundle;
+use crate::storage::encryption::{
+    decode_encrypted, decrypt, derive_key_from_password, encode_encrypted, encrypt, generate_key,
+};
+use crate::storage::migrations::migrate;
+use crate::storage::query_history::rebuild_search_index;
+use rand::RngCore;
+use rusqlite::backup::Backup;
+use rusqlite::{params, Connection as SqliteConnection, OpenFlags, OptionalExtension, Row};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Event emitted when the store locks itself after inactivity
+pub const STORE_LOCKED_EVENT: &str = "store://locked";
+
+/// How often the auto-lock timer checks for inactivity
+pub const AUTO_LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Read-only connections a store keeps next to the one it writes with
+const READERS: usize = 4;
+
+/// Known plaintext encrypted with the master key, to check a password against
+const MASTER_CHECK: &str = "dbclient-master-key";
 
 #[derive(Error, Debug)]
 pub enum StoreError {
@@ -17,10 +43,30 @@ pub enum StoreError {
 
     #[error("Connection not found: {0}")]
     NotFound(String),
+
+    #[error("Connection group not found: {0}")]
+    GroupNotFound(String),
+
+    #[error("The connection store is locked")]
+    Locked,
+
+    #[error("Incorrect master password")]
+    WrongPassword,
+
+    #[error("The connection store is unavailable")]
+    Poisoned,
+
+    #[error("The app database has schema version {0}, written by a newer version of the app")]
+    UnsupportedVersion(u32),
 }
 
 pub type Result<T> = std::result::Result<T, StoreError>;
 
+/// Columns read into a `RawConnectionRow`, in order
+const CONNECTION_COLUMNS: &str = "id, name, color, db_type, host, port, username, password, \
+    database, ssh_config, ssl_config, group_id, read_only, options, last_used_at, use_count, \
+    environment";
+
 /// Raw data extracted from a database row before decryption/parsing
 struct RawConnectionRow {
     id: String,
@@ -34,6 +80,12 @@ struct RawConnectionRow {
     database: Option<String>,
     ssh_config_json: Option<String>,
     ssl_config_json: Option<String>,
+    group_id: Option<String>,
+    read_only: bool,
+    options_json: Option<String>,
+    last_used_at: Option<i64>,
+    use_count: u64,
+    environment: String,
 }
 
 impl RawConnectionRow {
@@ -50,6 +102,12 @@ impl RawConnectionRow {
             database: row.get(8)?,
             ssh_config_json: row.get(9)?,
             ssl_config_json: row.get(10)?,
+            group_id: row.get(11)?,
+            read_only: row.get(12)?,
+            options_json: row.get(13)?,
+            last_used_at: row.get(14)?,
+            use_count: row.get(15)?,
+            environment: row.get(16)?,
         })
     }
 
@@ -73,6 +131,13 @@ impl RawConnectionRow {
             .transpose()
             .map_err(|e| StoreError::Serialization(e.to_string()))?;
 
+        let options = self
+            .options_json
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| StoreError::Serialization(e.to_string()))?
+            .unwrap_or_default();
+
         Ok(Connection {
             id: self.id,
             name: self.name,
@@ -85,6 +150,12 @@ impl RawConnectionRow {
             database: self.database,
             ssh_config,
             ssl_config,
+            group_id: self.group_id,
+            read_only: self.read_only,
+            environment: self.environment.parse().map_err(StoreError::Serialization)?,
+            options,
+            last_used_at: self.last_used_at,
+            use_count: self.use_count,
         })
     }
 }
@@ -94,61 +165,417 @@ fn parse_database_type(s: &str) -> Result<DatabaseType> {
         .map_err(|e: String| StoreError::Serialization(e))
 }
 
+/// Re-encrypt every stored password from `old_key` to `new_key`
+fn reencrypt_passwords(
+    db: &SqliteConnection,
+    old_key: &[u8; 32],
+    new_key: &[u8; 32],
+) -> Result<()> {
+    let rows: Vec<(String, String)> = db
+        .prepare("SELECT id, password FROM connections")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for (id, encoded) in rows {
+        let password = decode_encrypted(&encoded)
+            .and_then(|encrypted| decrypt(&encrypted, old_key))
+            .map(Zeroizing::new)
+            .map_err(|e| StoreError::Encryption(e.to_string()))?;
+        let encrypted =
+            encrypt(&password, new_key).map_err(|e| StoreError::Encryption(e.to_string()))?;
+        db.execute(
+            "UPDATE connections SET password = ?1 WHERE id = ?2",
+            params![encode_encrypted(&encrypted), id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Read a value from the store's key/value metadata
+fn read_meta(db: &SqliteConnection, name: &str) -> Result<Option<String>> {
+    Ok(db
+        .query_row(
+            "SELECT value FROM store_meta WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// A path next to the store file with the current time and `suffix`,
+/// e.g. `connections.db.1718000000000.bak`
+fn sibling_path(db: &SqliteConnection, suffix: &str) -> Result<PathBuf> {
+    let path = db
+        .path()
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| StoreError::Encryption("The store has no file to back up".to_string()))?;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    Ok(PathBuf::from(format!("{}.{}.{}", path, stamp, suffix)))
+}
+
+/// Copy the store file next to itself
+fn backup(db: &SqliteConnection) -> Result<PathBuf> {
+    let backup = sibling_path(db, "bak")?;
+    // VACUUM INTO writes a consistent copy even while others use the file
+    db.execute("VACUUM INTO ?1", params![backup.to_string_lossy()])?;
+    Ok(backup)
+}
+
+type Key = Zeroizing<[u8; 32]>;
+
+/// What `rotate_encryption_key` encrypts the stored passwords with next
+pub enum NewKey {
+    /// A key derived from a master password, which then unlocks the store
+    Password(String),
+    /// A random key kept in the store file. Unlike the built-in key it is
+    /// different for every install, and the store never locks.
+    Random,
+}
+
+/// The key passwords are currently encrypted with: none while a master
+/// password protects them, else a random key rotated in, else the built-in one
+fn stored_key(db: &SqliteConnection, builtin_key: &Key) -> Result<Option<Key>> {
+    if read_meta(db, "master_salt")?.is_some() {
+        return Ok(None);
+    }
+    let Some(data_key) = read_meta(db, "data_key")? else {
+        return Ok(Some(builtin_key.clone()));
+    };
+
+    let bytes = Zeroizing::new(
+        decode_encrypted(&data_key).map_err(|e| StoreError::Encryption(e.to_string()))?,
+    );
+    let data_key: [u8; 32] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| StoreError::Encryption("Stored key has the wrong length".to_string()))?;
+    Ok(Some(Zeroizing::new(data_key)))
+}
+
+/// Stored connections and their encrypted passwords. Every method takes
+/// `&self`: the SQLite connections and the key sit behind locks of their
+/// own, held only for the duration of a call, so one store is shared by
+/// every command without a lock around it. The file is in WAL mode, so reads
+/// go to a small set of read-only connections and run alongside each other
+/// and alongside a write. Calls block on SQLite; commands make them through
+/// `commands::store_task`.
 pub struct ConnectionStore {
-    db: SqliteConnection,
-    encryption_key: [u8; 32],
+    db: Mutex<SqliteConnection>,
+    readers: Vec<Mutex<SqliteConnection>>,
+    next_reader: AtomicUsize,
+    builtin_key: Key,
+    /// Key the passwords are encrypted with; wiped while the store is locked.
+    /// Taken after `db` or a reader by calls that hold both.
+    encryption_key: RwLock<Option<Key>>,
+    /// Lock after this long without using the key; never when None
+    auto_lock: RwLock<Option<Duration>>,
+    last_used: Mutex<Instant>,
 }
 
 impl ConnectionStore {
     /// Create a new connection store with the given database path and encryption key
     pub fn new(db_path: &Path, encryption_key: &str) -> Result<Self> {
-        let db = SqliteConnection::open(db_path)?;
+        let mut db = SqliteConnection::open(db_path)?;
 
         // Create encryption key from string (in production, derive this properly)
-        let mut key = [0u8; 32];
+        let mut key = Zeroizing::new([0u8; 32]);
         let key_bytes = encryption_key.as_bytes();
         let copy_len = std::cmp::min(key_bytes.len(), 32);
         key[..copy_len].copy_from_slice(&key_bytes[..copy_len]);
 
-        // Initialize database schema
-        db.execute(
-            "CREATE TABLE IF NOT EXISTS connections (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                color TEXT NOT NULL,
-                db_type TEXT NOT NULL,
-                host TEXT NOT NULL,
-                port INTEGER NOT NULL,
-                username TEXT NOT NULL,
-                password TEXT NOT NULL,
-                database TEXT,
-                ssh_config TEXT,
-                ssl_config TEXT,
-                created_at INTEGER DEFAULT (strftime('%s', 'now'))
-            )",
-            [],
-        )?;
+        migrate(&mut db)?;
+        db.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+        let readers = (0..READERS)
+            .map(|_| {
+                let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+                SqliteConnection::open_with_flags(db_path, flags).map(Mutex::new)
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // With a master password the key is only known once it's entered
+        let encryption_key = stored_key(&db, &key)?;
 
         Ok(Self {
-            db,
-            encryption_key: key,
+            db: Mutex::new(db),
+            readers,
+            next_reader: AtomicUsize::new(0),
+            builtin_key: key,
+            encryption_key: RwLock::new(encryption_key),
+            auto_lock: RwLock::new(None),
+            last_used: Mutex::new(Instant::now()),
+        })
+    }
+
+    fn db(&self) -> Result<MutexGuard<'_, SqliteConnection>> {
+        self.db.lock().map_err(|_| StoreError::Poisoned)
+    }
+
+    /// A read-only connection: a free one if there is one, otherwise the
+    /// next in turn once it's free
+    fn reader(&self) -> Result<MutexGuard<'_, SqliteConnection>> {
+        if let Some(reader) = self.readers.iter().find_map(|reader| reader.try_lock().ok()) {
+            return Ok(reader);
+        }
+        let next = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        self.readers[next].lock().map_err(|_| StoreError::Poisoned)
+    }
+
+    /// Run `read` with the key, held throughout so a key rotation can't land
+    /// between reading encrypted passwords and decrypting them
+    fn with_key<T>(&self, read: impl FnOnce(&Key) -> Result<T>) -> Result<T> {
+        let key = self.encryption_key.read().map_err(|_| StoreError::Poisoned)?;
+        let key = key.as_ref().ok_or(StoreError::Locked)?;
+        self.touch()?;
+        read(key)
+    }
+
+    fn set_key(&self, key: Option<Key>) -> Result<()> {
+        *self.encryption_key.write().map_err(|_| StoreError::Poisoned)? = key;
+        self.touch()
+    }
+
+    fn touch(&self) -> Result<()> {
+        *self.last_used.lock().map_err(|_| StoreError::Poisoned)? = Instant::now();
+        Ok(())
+    }
+
+    /// The key passwords are encrypted with, counting as activity for auto-lock
+    fn key(&self) -> Result<Key> {
+        let key = self
+            .encryption_key
+            .read()
+            .map_err(|_| StoreError::Poisoned)?
+            .clone()
+            .ok_or(StoreError::Locked)?;
+        self.touch()?;
+        Ok(key)
+    }
+
+    fn is_locked(&self) -> Result<bool> {
+        Ok(self
+            .encryption_key
+            .read()
+            .map_err(|_| StoreError::Poisoned)?
+            .is_none())
+    }
+
+    pub fn has_master_password(&self) -> Result<bool> {
+        Ok(read_meta(&*self.reader()?, "master_salt")?.is_some())
+    }
+
+    pub fn status(&self) -> Result<StoreStatus> {
+        Ok(StoreStatus {
+            has_master_password: self.has_master_password()?,
+            locked: self.is_locked()?,
         })
     }
 
+    /// Encrypt the stored passwords with a key derived from `password`. The
+    /// store must be unlocked; it stays unlocked with the new key.
+    pub fn set_master_password(&self, password: &str) -> Result<PathBuf> {
+        self.rotate_encryption_key(NewKey::Password(password.to_string()))
+    }
+
+    /// Re-encrypt every stored password with a new key. The store file is
+    /// copied first, and the passwords and key metadata change in a single
+    /// transaction, so a failure leaves the old key working. Returns the
+    /// path of the copy.
+    pub fn rotate_encryption_key(&self, new_key: NewKey) -> Result<PathBuf> {
+        // What store_meta should hold afterwards, None meaning removed
+        let (key, meta): (Key, [(&str, Option<String>); 3]) = match new_key {
+            NewKey::Password(password) => {
+                let mut salt = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut salt);
+                let key = Zeroizing::new(
+                    derive_key_from_password(&password, &salt)
+                        .map_err(|e| StoreError::Encryption(e.to_string()))?,
+                );
+                let check = encrypt(MASTER_CHECK, &key)
+                    .map_err(|e| StoreError::Encryption(e.to_string()))?;
+                let meta = [
+                    ("master_salt", Some(encode_encrypted(&salt))),
+                    ("master_check", Some(encode_encrypted(&check))),
+                    ("data_key", None),
+                ];
+                (key, meta)
+            }
+            NewKey::Random => {
+                let key = Zeroizing::new(generate_key());
+                let meta = [
+                    ("master_salt", None),
+                    ("master_check", None),
+                    ("data_key", Some(encode_encrypted(key.as_slice()))),
+                ];
+                (key, meta)
+            }
+        };
+
+        // Both held until the new key is in place, so no password is saved
+        // with the old key after the others were re-encrypted, and no read
+        // decrypts the new passwords with the old key
+        let mut db = self.db()?;
+        let mut current = self.encryption_key.write().map_err(|_| StoreError::Poisoned)?;
+        let old_key = current.clone().ok_or(StoreError::Locked)?;
+        let backup = backup(&db)?;
+
+        let tx = db.transaction()?;
+        reencrypt_passwords(&tx, &old_key, &key)?;
+        for (name, value) in meta {
+            match value {
+                Some(value) => tx.execute(
+                    "INSERT INTO store_meta (name, value) VALUES (?1, ?2)
+                    ON CONFLICT(name) DO UPDATE SET value = excluded.value",
+                    params![name, value],
+                )?,
+                None => tx.execute("DELETE FROM store_meta WHERE name = ?1", params![name])?,
+            };
+        }
+        tx.commit()?;
+        *current = Some(key);
+        drop(current);
+
+        tracing::info!(backup = %backup.display(), "Rotated the connection store key");
+        self.touch()?;
+        Ok(backup)
+    }
+
+    /// A consistent copy of the whole app database file: connections,
+    /// history, settings, workspaces and the audit log
+    pub fn export_database(&self) -> Result<Vec<u8>> {
+        let snapshot = backup(&*self.db()?)?;
+        let contents = std::fs::read(&snapshot);
+        let _ = std::fs::remove_file(&snapshot);
+        contents.map_err(|e| StoreError::Serialization(e.to_string()))
+    }
+
+    /// Replace the whole app database with a copy from `export_database`.
+    /// The copy is checked and migrated first, and the current file is backed
+    /// up; returns the path of that backup. Afterwards the store is locked if
+    /// the restored data has a master password.
+    pub fn restore_database(&self, contents: &[u8]) -> Result<PathBuf> {
+        let mut db = self.db()?;
+        let staged = sibling_path(&db, "restore")?;
+        let result = self.restore_from(&mut db, &staged, contents);
+        let _ = std::fs::remove_file(&staged);
+        result
+    }
+
+    fn restore_from(
+        &self,
+        db: &mut SqliteConnection,
+        staged: &Path,
+        contents: &[u8],
+    ) -> Result<PathBuf> {
+        std::fs::write(staged, contents).map_err(|e| StoreError::Serialization(e.to_string()))?;
+        let mut source = SqliteConnection::open(staged)?;
+        let integrity: String = source
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .map_err(|_| StoreError::Serialization("Not an app database".to_string()))?;
+        if integrity != "ok" {
+            return Err(StoreError::Serialization(format!(
+                "The backed up database is damaged: {}",
+                integrity
+            )));
+        }
+        migrate(&mut source)?;
+        // The copy was written by VACUUM INTO, which may renumber the rowids
+        // the history search index refers to
+        rebuild_search_index(&source)?;
+
+        let backup = backup(db)?;
+        // Readers wait for the key of the restored data rather than decrypt
+        // it with the current one
+        let mut current = self.encryption_key.write().map_err(|_| StoreError::Poisoned)?;
+        Backup::new(&source, db)?.run_to_completion(256, Duration::from_millis(10), None)?;
+        *current = stored_key(db, &self.builtin_key)?;
+        drop(current);
+        self.touch()?;
+        tracing::info!(backup = %backup.display(), "Restored the app database");
+        Ok(backup)
+    }
+
+    /// Derive the key from the master password, failing if it's wrong
+    pub fn unlock(&self, password: &str) -> Result<()> {
+        let (salt, check) = {
+            let db = self.reader()?;
+            (read_meta(&db, "master_salt")?, read_meta(&db, "master_check")?)
+        };
+        let (Some(salt), Some(check)) = (salt, check) else {
+            // Nothing to unlock without a master password
+            return Ok(());
+        };
+        let decode = |value: &str| {
+            decode_encrypted(value).map_err(|e| StoreError::Encryption(e.to_string()))
+        };
+
+        // Deriving the key is slow on purpose; nothing is locked meanwhile
+        let key = Zeroizing::new(
+            derive_key_from_password(password, &decode(&salt)?)
+                .map_err(|e| StoreError::Encryption(e.to_string()))?,
+        );
+        match decrypt(&decode(&check)?, &key) {
+            Ok(plaintext) if plaintext == MASTER_CHECK => {}
+            _ => return Err(StoreError::WrongPassword),
+        }
+
+        self.set_key(Some(key))
+    }
+
+    /// Wipe the key from memory until the master password is entered again.
+    /// Returns whether the store was locked; without a master password it
+    /// couldn't be unlocked, so it stays open.
+    pub fn lock(&self) -> Result<bool> {
+        if !self.has_master_password()? {
+            return Ok(false);
+        }
+        // Dropping the key zeroes it
+        *self.encryption_key.write().map_err(|_| StoreError::Poisoned)? = None;
+        Ok(true)
+    }
+
+    /// Lock after `timeout` without activity, or never when None
+    pub fn set_auto_lock(&self, timeout: Option<Duration>) -> Result<()> {
+        *self.auto_lock.write().map_err(|_| StoreError::Poisoned)? = timeout;
+        Ok(())
+    }
+
+    /// Lock if the store has been idle for longer than its auto-lock
+    /// timeout. Returns whether it locked just now.
+    pub fn lock_if_idle(&self) -> Result<bool> {
+        let timeout = *self.auto_lock.read().map_err(|_| StoreError::Poisoned)?;
+        let idle = self
+            .last_used
+            .lock()
+            .map_err(|_| StoreError::Poisoned)?
+            .elapsed();
+        match timeout {
+            Some(timeout) if !self.is_locked()? && idle >= timeout => self.lock(),
+            _ => Ok(false),
+        }
+    }
+
     /// Check if the store is initialized
     pub fn is_initialized(&self) -> bool {
-        self.db
-            .query_row(
-                "SELECT name FROM sqlite_master WHERE type='table' AND name='connections'",
-                [],
-                |_| Ok(()),
-            )
-            .is_ok()
+        self.reader()
+            .map(|db| {
+                db.query_row(
+                    "SELECT name FROM sqlite_master WHERE type='table' AND name='connections'",
+                    [],
+                    |_| Ok(()),
+                )
+                .is_ok()
+            })
+            .unwrap_or_default()
     }
 
     /// Save a connection to the database
-    pub fn save_connection(&mut self, connection: &Connection) -> Result<()> {
-        let encrypted_password = encrypt(&connection.password, &self.encryption_key)
+    pub fn save_connection(&self, connection: &Connection) -> Result<()> {
+        // Taken before the key, so a key rotation can't slip in between
+        let db = self.db()?;
+        let encrypted_password = encrypt(&connection.password, &*self.key()?)
             .map_err(|e| StoreError::Encryption(e.to_string()))?;
         let encoded_password = encode_encrypted(&encrypted_password);
 
@@ -166,10 +593,21 @@ impl ConnectionStore {
             .transpose()
             .map_err(|e| StoreError::Serialization(e.to_string()))?;
 
-        self.db.execute(
-            "INSERT OR REPLACE INTO connections
-            (id, name, color, db_type, host, port, username, password, database, ssh_config, ssl_config)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        let options_json = serde_json::to_string(&connection.options)
+            .map_err(|e| StoreError::Serialization(e.to_string()))?;
+
+        db.execute(
+            // Upsert rather than replace so usage stats survive edits
+            "INSERT INTO connections
+            (id, name, color, db_type, host, port, username, password, database, ssh_config, ssl_config, group_id, read_only, options, environment)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name, color = excluded.color, db_type = excluded.db_type,
+                host = excluded.host, port = excluded.port, username = excluded.username,
+                password = excluded.password, database = excluded.database,
+                ssh_config = excluded.ssh_config, ssl_config = excluded.ssl_config,
+                group_id = excluded.group_id, read_only = excluded.read_only,
+                options = excluded.options, environment = excluded.environment",
             params![
                 connection.id,
                 connection.name,
@@ -182,6 +620,10 @@ impl ConnectionStore {
                 connection.database,
                 ssh_config_json,
                 ssl_config_json,
+                connection.group_id,
+                connection.read_only,
+                options_json,
+                connection.environment.to_string(),
             ],
         )?;
 
@@ -190,47 +632,255 @@ impl ConnectionStore {
 
     /// Load a connection by ID
     pub fn load_connection(&self, id: &str) -> Result<Option<Connection>> {
-        let mut stmt = self.db.prepare(
-            "SELECT id, name, color, db_type, host, port, username, password, database, ssh_config, ssl_config
-             FROM connections WHERE id = ?1",
-        )?;
+        let db = self.reader()?;
+        self.with_key(|key| {
+            let mut stmt = db.prepare(&format!(
+                "SELECT {} FROM connections WHERE id = ?1",
+                CONNECTION_COLUMNS
+            ))?;
 
-        match stmt.query_row(params![id], RawConnectionRow::from_row) {
-            Ok(raw) => Ok(Some(raw.into_connection(&self.encryption_key)?)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(StoreError::Database(e)),
-        }
+            match stmt.query_row(params![id], RawConnectionRow::from_row) {
+                Ok(raw) => Ok(Some(raw.into_connection(key)?)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(StoreError::Database(e)),
+            }
+        })
     }
 
     /// List all connections
     pub fn list_connections(&self) -> Result<Vec<Connection>> {
-        let mut stmt = self.db.prepare(
-            "SELECT id, name, color, db_type, host, port, username, password, database, ssh_config, ssl_config
-             FROM connections ORDER BY name",
-        )?;
+        self.list_connections_sorted(ConnectionSort::Name)
+    }
 
-        let raw_connections: Vec<RawConnectionRow> = stmt
-            .query_map([], RawConnectionRow::from_row)?
-            .collect::<rusqlite::Result<Vec<_>>>()?;
+    /// List all connections in the given order
+    pub fn list_connections_sorted(&self, sort: ConnectionSort) -> Result<Vec<Connection>> {
+        let order_by = match sort {
+            ConnectionSort::Name => "name",
+            ConnectionSort::Recent => "last_used_at IS NULL, last_used_at DESC, name",
+        };
+        let db = self.reader()?;
+        self.with_key(|key| {
+            let mut stmt = db.prepare(&format!(
+                "SELECT {} FROM connections ORDER BY {}",
+                CONNECTION_COLUMNS, order_by
+            ))?;
 
-        raw_connections
-            .into_iter()
-            .map(|raw| raw.into_connection(&self.encryption_key))
-            .collect()
+            let raw_connections: Vec<RawConnectionRow> = stmt
+                .query_map([], RawConnectionRow::from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            raw_connections
+                .into_iter()
+                .map(|raw| raw.into_connection(key))
+                .collect()
+        })
+    }
+
+    /// Copy a stored connection under a new id and name. The password is
+    /// re-encrypted for the copy; usage stats start from zero.
+    pub fn duplicate_connection(&self, id: &str, new_name: &str) -> Result<Connection> {
+        let mut copy = self
+            .load_connection(id)?
+            .ok_or_else(|| StoreError::NotFound(id.to_string()))?;
+
+        copy.id = uuid::Uuid::new_v4().to_string();
+        copy.name = new_name.to_string();
+        copy.last_used_at = None;
+        copy.use_count = 0;
+
+        self.save_connection(&copy)?;
+        Ok(copy)
+    }
+
+    /// Record that a command used a connection
+    pub fn record_connection_use(&self, id: &str, used_at: i64) -> Result<()> {
+        self.db()?.execute(
+            "UPDATE connections SET last_used_at = ?1, use_count = use_count + 1 WHERE id = ?2",
+            params![used_at, id],
+        )?;
+        Ok(())
     }
 
     /// Delete a connection
-    pub fn delete_connection(&mut self, id: &str) -> Result<()> {
-        self.db
+    pub fn delete_connection(&self, id: &str) -> Result<()> {
+        self.db()?
             .execute("DELETE FROM connections WHERE id = ?1", params![id])?;
         Ok(())
     }
+
+    /// List all connections arranged by group
+    pub fn list_connection_tree(&self, sort: ConnectionSort) -> Result<ConnectionTree> {
+        let mut groups: Vec<ConnectionGroupNode> = self
+            .list_groups()?
+            .into_iter()
+            .map(|group| ConnectionGroupNode {
+                group,
+                connections: Vec::new(),
+            })
+            .collect();
+        let mut ungrouped = Vec::new();
+
+        for connection in self.list_connections_sorted(sort)? {
+            let node = connection
+                .group_id
+                .as_ref()
+                .and_then(|group_id| groups.iter_mut().find(|n| &n.group.id == group_id));
+
+            match node {
+                Some(node) => node.connections.push(connection),
+                None => ungrouped.push(connection),
+            }
+        }
+
+        Ok(ConnectionTree { groups, ungrouped })
+    }
+
+    /// List all connection groups
+    pub fn list_groups(&self) -> Result<Vec<ConnectionGroup>> {
+        let db = self.reader()?;
+        let mut stmt = db.prepare("SELECT id, name FROM connection_groups ORDER BY name")?;
+
+        let groups = stmt
+            .query_map([], |row| {
+                Ok(ConnectionGroup {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(groups)
+    }
+
+    /// Create a new connection group
+    pub fn create_group(&self, name: &str) -> Result<ConnectionGroup> {
+        let group = ConnectionGroup {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+        };
+
+        self.db()?.execute(
+            "INSERT INTO connection_groups (id, name) VALUES (?1, ?2)",
+            params![group.id, group.name],
+        )?;
+
+        Ok(group)
+    }
+
+    /// Rename an existing connection group
+    pub fn rename_group(&self, id: &str, name: &str) -> Result<()> {
+        let updated = self.db()?.execute(
+            "UPDATE connection_groups SET name = ?1 WHERE id = ?2",
+            params![name, id],
+        )?;
+
+        if updated == 0 {
+            return Err(StoreError::GroupNotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Delete a connection group, moving its connections back to the top level
+    pub fn delete_group(&self, id: &str) -> Result<()> {
+        let mut db = self.db()?;
+        let tx = db.transaction()?;
+        tx.execute(
+            "UPDATE connections SET group_id = NULL WHERE group_id = ?1",
+            params![id],
+        )?;
+        tx.execute("DELETE FROM connection_groups WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Move a connection into a group, or out of any group when `group_id` is None
+    pub fn move_connection(&self, connection_id: &str, group_id: Option<&str>) -> Result<()> {
+        let db = self.db()?;
+        if let Some(group_id) = group_id {
+            let exists = db
+                .query_row(
+                    "SELECT 1 FROM connection_groups WHERE id = ?1",
+                    params![group_id],
+                    |_| Ok(()),
+                )
+                .is_ok();
+            if !exists {
+                return Err(StoreError::GroupNotFound(group_id.to_string()));
+            }
+        }
+
+        let updated = db.execute(
+            "UPDATE connections SET group_id = ?1 WHERE id = ?2",
+            params![group_id, connection_id],
+        )?;
+
+        if updated == 0 {
+            return Err(StoreError::NotFound(connection_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Collect connections (all, or only the given ids) and their groups for export
+    pub fn export_bundle(&self, connection_ids: Option<&[String]>) -> Result<ConnectionBundle> {
+        let connections: Vec<Connection> = self
+            .list_connections()?
+            .into_iter()
+            .filter(|c| match connection_ids {
+                Some(ids) => ids.contains(&c.id),
+                None => true,
+            })
+            .collect();
+
+        let groups = self
+            .list_groups()?
+            .into_iter()
+            .filter(|g| connections.iter().any(|c| c.group_id.as_ref() == Some(&g.id)))
+            .collect();
+
+        Ok(ConnectionBundle {
+            groups,
+            connections,
+        })
+    }
+
+    /// Store connections from an imported bundle using the given strategy
+    pub fn import_bundle(
+        &self,
+        bundle: &ConnectionBundle,
+        strategy: ImportStrategy,
+    ) -> Result<ImportSummary> {
+        for group in &bundle.groups {
+            self.db()?.execute(
+                "INSERT OR IGNORE INTO connection_groups (id, name) VALUES (?1, ?2)",
+                params![group.id, group.name],
+            )?;
+        }
+
+        let mut summary = ImportSummary {
+            imported: 0,
+            skipped: 0,
+        };
+
+        for connection in &bundle.connections {
+            let exists = self.load_connection(&connection.id)?.is_some();
+            if exists && strategy == ImportStrategy::Merge {
+                summary.skipped += 1;
+                continue;
+            }
+
+            self.save_connection(connection)?;
+            summary.imported += 1;
+        }
+
+        Ok(summary)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::DatabaseType;
+    use crate::models::connection::{ConnectionOptions, SSHAuth, SSHConfig, SecretRef};
+    use crate::models::{DatabaseType, Environment};
     use tempfile::TempDir;
 
     fn setup_test_db() -> (ConnectionStore, TempDir) {
@@ -250,7 +900,7 @@ mod tests {
 
     #[test]
     fn test_save_and_load_connection() {
-        let (mut store, _temp) = setup_test_db();
+        let (store, _temp) = setup_test_db();
 
         let conn = Connection {
             id: "test-id".to_string(),
@@ -264,6 +914,12 @@ mod tests {
             database: Some("test_db".to_string()),
             ssh_config: None,
             ssl_config: None,
+            group_id: None,
+            read_only: false,
+            environment: Environment::Dev,
+            options: ConnectionOptions::default(),
+            last_used_at: None,
+            use_count: 0,
         };
 
         // Save
@@ -282,7 +938,7 @@ mod tests {
 
     #[test]
     fn test_password_encrypted_in_database() {
-        let (mut store, temp) = setup_test_db();
+        let (store, temp) = setup_test_db();
 
         let conn = Connection {
             id: "test-id".to_string(),
@@ -296,6 +952,12 @@ mod tests {
             database: Some("test_db".to_string()),
             ssh_config: None,
             ssl_config: None,
+            group_id: None,
+            read_only: false,
+            environment: Environment::Dev,
+            options: ConnectionOptions::default(),
+            last_used_at: None,
+            use_count: 0,
         };
 
         store.save_connection(&conn).unwrap();
@@ -318,7 +980,7 @@ mod tests {
 
     #[test]
     fn test_list_all_connections() {
-        let (mut store, _temp) = setup_test_db();
+        let (store, _temp) = setup_test_db();
 
         // Save 3 connections
         for i in 1..=3 {
@@ -334,6 +996,12 @@ mod tests {
                 database: None,
                 ssh_config: None,
                 ssl_config: None,
+                group_id: None,
+                read_only: false,
+                environment: Environment::Dev,
+                options: ConnectionOptions::default(),
+                last_used_at: None,
+                use_count: 0,
             };
             store.save_connection(&conn).unwrap();
         }
@@ -344,7 +1012,7 @@ mod tests {
 
     #[test]
     fn test_delete_connection() {
-        let (mut store, _temp) = setup_test_db();
+        let (store, _temp) = setup_test_db();
 
         let conn = Connection {
             id: "test-id".to_string(),
@@ -358,6 +1026,12 @@ mod tests {
             database: None,
             ssh_config: None,
             ssl_config: None,
+            group_id: None,
+            read_only: false,
+            environment: Environment::Dev,
+            options: ConnectionOptions::default(),
+            last_used_at: None,
+            use_count: 0,
         };
         store.save_connection(&conn).unwrap();
 
@@ -369,7 +1043,7 @@ mod tests {
 
     #[test]
     fn test_update_connection() {
-        let (mut store, _temp) = setup_test_db();
+        let (store, _temp) = setup_test_db();
 
         let mut conn = Connection {
             id: "test-id".to_string(),
@@ -383,6 +1057,12 @@ mod tests {
             database: None,
             ssh_config: None,
             ssl_config: None,
+            group_id: None,
+            read_only: false,
+            environment: Environment::Dev,
+            options: ConnectionOptions::default(),
+            last_used_at: None,
+            use_count: 0,
         };
         store.save_connection(&conn).unwrap();
 
@@ -394,4 +1074,391 @@ mod tests {
         assert_eq!(loaded.name, "Updated Name");
         assert_eq!(loaded.password, "new_password");
     }
+
+    fn create_test_connection(id: &str, group_id: Option<String>) -> Connection {
+        Connection {
+            id: id.to_string(),
+            name: format!("Connection {}", id),
+            color: "#ef4444".to_string(),
+            db_type: DatabaseType::MySQL,
+            host: "localhost".to_string(),
+            port: 3306,
+            username: "root".to_string(),
+            password: "password".to_string(),
+            database: None,
+            ssh_config: None,
+            ssl_config: None,
+            group_id,
+            read_only: false,
+            environment: Environment::Dev,
+            options: ConnectionOptions::default(),
+            last_used_at: None,
+            use_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_connection_options_round_trip() {
+        let (store, _temp) = setup_test_db();
+        let mut conn = create_test_connection("analytics", None);
+        conn.options = ConnectionOptions {
+            max_rows: None,
+            max_result_memory_mb: Some(64),
+            statement_timeout_ms: Some(30_000),
+            connect_timeout_secs: 5,
+            pool_size: 2,
+            max_concurrent_queries: Some(1),
+            time_zone: Some("Europe/Berlin".to_string()),
+            session_variables: [("sql_mode".to_string(), "ANSI_QUOTES".to_string())].into(),
+            init_sql: Some("SET ROLE reporting".to_string()),
+            socket_path: Some("/var/run/mysqld/mysqld.sock".to_string()),
+            use_client_config: true,
+            password_secret: Some(SecretRef::Vault {
+                path: "secret/data/analytics".to_string(),
+                key: "password".to_string(),
+                username_key: None,
+            }),
+            masked_columns: vec!["*password*".to_string(), "users.ssn".to_string()],
+        };
+        store.save_connection(&conn).unwrap();
+
+        let loaded = store.load_connection("analytics").unwrap().unwrap();
+        assert_eq!(loaded.options, conn.options);
+    }
+
+    #[test]
+    fn test_recent_sort_and_usage_survive_edits() {
+        let (store, _temp) = setup_test_db();
+        for id in ["a", "b", "c"] {
+            store.save_connection(&create_test_connection(id, None)).unwrap();
+        }
+        store.record_connection_use("c", 1_000).unwrap();
+        store.record_connection_use("b", 2_000).unwrap();
+        store.record_connection_use("b", 3_000).unwrap();
+
+        let ids: Vec<String> = store
+            .list_connections_sorted(ConnectionSort::Recent)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec!["b", "c", "a"]);
+
+        // Saving an edited connection keeps its usage stats
+        let mut edited = create_test_connection("b", None);
+        edited.name = "Renamed".to_string();
+        store.save_connection(&edited).unwrap();
+        let loaded = store.load_connection("b").unwrap().unwrap();
+        assert_eq!(loaded.name, "Renamed");
+        assert_eq!(loaded.last_used_at, Some(3_000));
+        assert_eq!(loaded.use_count, 2);
+    }
+
+    #[test]
+    fn test_duplicate_connection() {
+        let (store, _temp) = setup_test_db();
+        let mut original = create_test_connection("orig", None);
+        original.ssh_config = Some(SSHConfig {
+            host: "bastion".to_string(),
+            port: 22,
+            username: "deploy".to_string(),
+            auth: SSHAuth::Agent,
+            use_ssh_config: false,
+            proxy_jump: None,
+            jump_hosts: vec![],
+        });
+        store.save_connection(&original).unwrap();
+        store.record_connection_use("orig", 1_000).unwrap();
+
+        let copy = store.duplicate_connection("orig", "Copy of orig").unwrap();
+        assert_ne!(copy.id, "orig");
+        assert_eq!(copy.name, "Copy of orig");
+        assert_eq!(copy.use_count, 0);
+
+        let loaded = store.load_connection(&copy.id).unwrap().unwrap();
+        assert_eq!(loaded.password, original.password);
+        assert_eq!(loaded.ssh_config.unwrap().host, "bastion");
+        assert_eq!(store.list_connections().unwrap().len(), 2);
+
+        assert!(matches!(
+            store.duplicate_connection("missing", "x"),
+            Err(StoreError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_only_flag_round_trips() {
+        let (store, _temp) = setup_test_db();
+        let mut conn = create_test_connection("prod", None);
+        conn.read_only = true;
+        conn.environment = Environment::Prod;
+        store.save_connection(&conn).unwrap();
+
+        let loaded = store.load_connection("prod").unwrap().unwrap();
+        assert!(loaded.read_only);
+        assert_eq!(loaded.environment, Environment::Prod);
+    }
+
+    #[test]
+    fn test_create_and_rename_group() {
+        let (store, _temp) = setup_test_db();
+
+        let group = store.create_group("Production").unwrap();
+        store.rename_group(&group.id, "Prod").unwrap();
+
+        let groups = store.list_groups().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "Prod");
+
+        assert!(matches!(
+            store.rename_group("missing", "x"),
+            Err(StoreError::GroupNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_connection_tree_groups_connections() {
+        let (store, _temp) = setup_test_db();
+
+        let group = store.create_group("Staging").unwrap();
+        store
+            .save_connection(&create_test_connection("grouped", Some(group.id.clone())))
+            .unwrap();
+        store
+            .save_connection(&create_test_connection("loose", None))
+            .unwrap();
+
+        let tree = store.list_connection_tree(ConnectionSort::Name).unwrap();
+        assert_eq!(tree.groups.len(), 1);
+        assert_eq!(tree.groups[0].connections.len(), 1);
+        assert_eq!(tree.groups[0].connections[0].id, "grouped");
+        assert_eq!(tree.ungrouped.len(), 1);
+        assert_eq!(tree.ungrouped[0].id, "loose");
+    }
+
+    #[test]
+    fn test_move_connection_between_groups() {
+        let (store, _temp) = setup_test_db();
+
+        let group = store.create_group("Analytics").unwrap();
+        store
+            .save_connection(&create_test_connection("conn", None))
+            .unwrap();
+
+        store.move_connection("conn", Some(&group.id)).unwrap();
+        let loaded = store.load_connection("conn").unwrap().unwrap();
+        assert_eq!(loaded.group_id, Some(group.id.clone()));
+
+        store.move_connection("conn", None).unwrap();
+        let loaded = store.load_connection("conn").unwrap().unwrap();
+        assert_eq!(loaded.group_id, None);
+
+        assert!(matches!(
+            store.move_connection("conn", Some("missing")),
+            Err(StoreError::GroupNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_delete_group_ungroups_connections() {
+        let (store, _temp) = setup_test_db();
+
+        let group = store.create_group("Old").unwrap();
+        store
+            .save_connection(&create_test_connection("conn", Some(group.id.clone())))
+            .unwrap();
+
+        store.delete_group(&group.id).unwrap();
+
+        let tree = store.list_connection_tree(ConnectionSort::Name).unwrap();
+        assert!(tree.groups.is_empty());
+        assert_eq!(tree.ungrouped.len(), 1);
+        assert_eq!(tree.ungrouped[0].group_id, None);
+    }
+
+    #[test]
+    fn test_import_bundle_merge_and_overwrite() {
+        let (store, _temp) = setup_test_db();
+        store
+            .save_connection(&create_test_connection("existing", None))
+            .unwrap();
+
+        let mut incoming = create_test_connection("existing", None);
+        incoming.name = "Imported".to_string();
+        let bundle = ConnectionBundle {
+            groups: vec![],
+            connections: vec![incoming, create_test_connection("new", None)],
+        };
+
+        let summary = store.import_bundle(&bundle, ImportStrategy::Merge).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.skipped, 1);
+        let existing = store.load_connection("existing").unwrap().unwrap();
+        assert_eq!(existing.name, "Connection existing");
+
+        let summary = store
+            .import_bundle(&bundle, ImportStrategy::Overwrite)
+            .unwrap();
+        assert_eq!(summary.imported, 2);
+        let existing = store.load_connection("existing").unwrap().unwrap();
+        assert_eq!(existing.name, "Imported");
+    }
+
+    #[test]
+    fn test_export_bundle_includes_referenced_groups() {
+        let (store, _temp) = setup_test_db();
+        let group = store.create_group("Team").unwrap();
+        store.create_group("Unused").unwrap();
+        store
+            .save_connection(&create_test_connection("a", Some(group.id.clone())))
+            .unwrap();
+        store
+            .save_connection(&create_test_connection("b", None))
+            .unwrap();
+
+        let bundle = store.export_bundle(Some(&["a".to_string()])).unwrap();
+        assert_eq!(bundle.connections.len(), 1);
+        assert_eq!(bundle.groups.len(), 1);
+        assert_eq!(bundle.groups[0].name, "Team");
+    }
+
+    #[test]
+    fn test_master_password_lock_and_unlock() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let store = ConnectionStore::new(&db_path, "test_key_32_bytes_long_string!!").unwrap();
+        store
+            .save_connection(&create_test_connection("a", None))
+            .unwrap();
+
+        // Without a master password there is nothing to unlock with
+        assert!(!store.lock().unwrap());
+        store.set_master_password("hunter2").unwrap();
+        assert!(store.lock().unwrap());
+        assert!(matches!(store.load_connection("a"), Err(StoreError::Locked)));
+        assert!(matches!(
+            store.unlock("wrong"),
+            Err(StoreError::WrongPassword)
+        ));
+
+        store.unlock("hunter2").unwrap();
+        let password = store.load_connection("a").unwrap().unwrap().password;
+
+        // A reopened store starts locked
+        drop(store);
+        let store = ConnectionStore::new(&db_path, "test_key_32_bytes_long_string!!").unwrap();
+        assert_eq!(
+            store.status().unwrap(),
+            StoreStatus {
+                has_master_password: true,
+                locked: true,
+            }
+        );
+        store.unlock("hunter2").unwrap();
+        assert_eq!(store.load_connection("a").unwrap().unwrap().password, password);
+    }
+
+    #[test]
+    fn test_lock_if_idle() {
+        let (store, _temp) = setup_test_db();
+        store.set_master_password("hunter2").unwrap();
+
+        store.set_auto_lock(None).unwrap();
+        assert!(!store.lock_if_idle().unwrap());
+        store.set_auto_lock(Some(Duration::from_secs(3600))).unwrap();
+        assert!(!store.lock_if_idle().unwrap());
+        store.set_auto_lock(Some(Duration::ZERO)).unwrap();
+        assert!(store.lock_if_idle().unwrap());
+        assert!(store.status().unwrap().locked);
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let (store, _temp) = setup_test_db();
+        let store = std::sync::Arc::new(store);
+
+        let writers: Vec<_> = (0..4)
+            .map(|i| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    let id = format!("conn-{}", i);
+                    store.save_connection(&create_test_connection(&id, None)).unwrap();
+                    store.load_connection(&id).unwrap().unwrap()
+                })
+            })
+            .collect();
+        for writer in writers {
+            assert_eq!(writer.join().unwrap().password, "password");
+        }
+        assert_eq!(store.list_connections().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_reads_while_writing() {
+        let (store, _temp) = setup_test_db();
+        store.save_connection(&create_test_connection("conn-1", None)).unwrap();
+
+        // A write in progress holds the writer; reads use connections of their own
+        let writer = store.db().unwrap();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        let loaded = store.load_connection("conn-1").unwrap().unwrap();
+        assert_eq!(loaded.password, "password");
+        assert_eq!(store.list_connections().unwrap().len(), 1);
+        writer.execute_batch("ROLLBACK").unwrap();
+    }
+
+    #[test]
+    fn test_rotate_encryption_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let store = ConnectionStore::new(&db_path, "test_key_32_bytes_long_string!!").unwrap();
+        store
+            .save_connection(&create_test_connection("a", None))
+            .unwrap();
+        let password = store.load_connection("a").unwrap().unwrap().password;
+
+        let backup = store.rotate_encryption_key(NewKey::Random).unwrap();
+        assert!(backup.exists());
+        assert_eq!(store.load_connection("a").unwrap().unwrap().password, password);
+
+        // The backup still opens with the key it was written with
+        let old = ConnectionStore::new(&backup, "test_key_32_bytes_long_string!!").unwrap();
+        assert_eq!(old.load_connection("a").unwrap().unwrap().password, password);
+
+        // The random key replaces the built-in one once reopened
+        drop(store);
+        let store = ConnectionStore::new(&db_path, "some other built-in key").unwrap();
+        assert_eq!(store.load_connection("a").unwrap().unwrap().password, password);
+
+        store
+            .rotate_encryption_key(NewKey::Password("hunter2".to_string()))
+            .unwrap();
+        drop(store);
+        let store = ConnectionStore::new(&db_path, "some other built-in key").unwrap();
+        assert!(store.status().unwrap().locked);
+        store.unlock("hunter2").unwrap();
+        assert_eq!(store.load_connection("a").unwrap().unwrap().password, password);
+    }
+
+    #[test]
+    fn test_export_and_restore_database() {
+        let (store, _temp) = setup_test_db();
+        store
+            .save_connection(&create_test_connection("a", None))
+            .unwrap();
+        let exported = store.export_database().unwrap();
+
+        store.delete_connection("a").unwrap();
+        store.set_master_password("hunter2").unwrap();
+        assert!(store.restore_database(b"not a database").is_err());
+        assert!(store.has_master_password().unwrap());
+
+        let previous = store.restore_database(&exported).unwrap();
+        assert!(previous.exists());
+        assert!(!store.status().unwrap().locked);
+        assert_eq!(
+            store.load_connection("a").unwrap().unwrap().password,
+            "password"
+        );
+    }
 }
@@ -0,0 +1,137 @@
+use crate::models::{Connection, ConnectionGroup};
+use crate::storage::connection_store::{Result, StoreError};
+use crate::storage::encryption::{
+    decode_encrypted, decrypt, derive_key_from_password, encode_encrypted, encrypt,
+};
+use aes_gcm::aead::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+const BUNDLE_VERSION: u32 = 1;
+const SALT_SIZE: usize = 16;
+
+/// Connections (with their groups) serialized for moving between machines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionBundle {
+    pub groups: Vec<ConnectionGroup>,
+    pub connections: Vec<Connection>,
+}
+
+/// On-disk envelope: the bundle encrypted with a key derived from a passphrase
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedBundle {
+    version: u32,
+    salt: String,
+    data: String,
+}
+
+/// Encrypt a bundle with the given passphrase, returning the file contents
+pub fn seal_bundle(bundle: &ConnectionBundle, passphrase: &str) -> Result<String> {
+    let mut salt = [0u8; SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+
+    let key = derive_key_from_password(passphrase, &salt)
+        .map_err(|e| StoreError::Encryption(e.to_string()))?;
+
+    let plaintext =
+        serde_json::to_string(bundle).map_err(|e| StoreError::Serialization(e.to_string()))?;
+    let ciphertext =
+        encrypt(&plaintext, &key).map_err(|e| StoreError::Encryption(e.to_string()))?;
+
+    let envelope = EncryptedBundle {
+        version: BUNDLE_VERSION,
+        salt: encode_encrypted(&salt),
+        data: encode_encrypted(&ciphertext),
+    };
+
+    serde_json::to_string_pretty(&envelope).map_err(|e| StoreError::Serialization(e.to_string()))
+}
+
+/// Decrypt file contents produced by [`seal_bundle`]
+pub fn open_bundle(contents: &str, passphrase: &str) -> Result<ConnectionBundle> {
+    let envelope: EncryptedBundle =
+        serde_json::from_str(contents).map_err(|e| StoreError::Serialization(e.to_string()))?;
+
+    if envelope.version != BUNDLE_VERSION {
+        return Err(StoreError::Serialization(format!(
+            "Unsupported export version: {}",
+            envelope.version
+        )));
+    }
+
+    let salt =
+        decode_encrypted(&envelope.salt).map_err(|e| StoreError::Encryption(e.to_string()))?;
+    let ciphertext =
+        decode_encrypted(&envelope.data).map_err(|e| StoreError::Encryption(e.to_string()))?;
+
+    let key = derive_key_from_password(passphrase, &salt)
+        .map_err(|e| StoreError::Encryption(e.to_string()))?;
+    let plaintext = decrypt(&ciphertext, &key)
+        .map_err(|_| StoreError::Encryption("Wrong passphrase or corrupted file".to_string()))?;
+
+    serde_json::from_str(&plaintext).map_err(|e| StoreError::Serialization(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::connection::{ConnectionOptions, SSHAuth, SSHConfig};
+    use crate::models::{DatabaseType, Environment};
+
+    fn sample_bundle() -> ConnectionBundle {
+        ConnectionBundle {
+            groups: vec![ConnectionGroup {
+                id: "group-1".to_string(),
+                name: "Production".to_string(),
+            }],
+            connections: vec![Connection {
+                id: "conn-1".to_string(),
+                name: "Prod".to_string(),
+                color: "#ef4444".to_string(),
+                db_type: DatabaseType::MySQL,
+                host: "db.internal".to_string(),
+                port: 3306,
+                username: "app".to_string(),
+                password: "s3cret".to_string(),
+                database: Some("app".to_string()),
+                ssh_config: Some(SSHConfig {
+                    host: "bastion".to_string(),
+                    port: 22,
+                    username: "ops".to_string(),
+                    auth: SSHAuth::Agent,
+                    use_ssh_config: false,
+                    proxy_jump: None,
+                    jump_hosts: vec![],
+                }),
+                ssl_config: None,
+                group_id: Some("group-1".to_string()),
+                read_only: false,
+                environment: Environment::Dev,
+                options: ConnectionOptions::default(),
+                last_used_at: None,
+                use_count: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_seal_and_open_roundtrip() {
+        let sealed = seal_bundle(&sample_bundle(), "passphrase").unwrap();
+        assert!(!sealed.contains("s3cret"));
+
+        let opened = open_bundle(&sealed, "passphrase").unwrap();
+        assert_eq!(opened.connections.len(), 1);
+        assert_eq!(opened.connections[0].password, "s3cret");
+        assert!(opened.connections[0].ssh_config.is_some());
+        assert_eq!(opened.groups[0].name, "Production");
+    }
+
+    #[test]
+    fn test_open_with_wrong_passphrase_fails() {
+        let sealed = seal_bundle(&sample_bundle(), "passphrase").unwrap();
+        assert!(matches!(
+            open_bundle(&sealed, "wrong"),
+            Err(StoreError::Encryption(_))
+        ));
+    }
+}
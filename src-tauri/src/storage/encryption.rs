@@ -13,7 +13,6 @@ use thiserror::Error;
 const NONCE_SIZE: usize = 12;
 const KEY_SIZE: usize = 32;
 
-#[allow(dead_code)]
 const MIN_SALT_SIZE: usize = 16;
 
 #[derive(Error, Debug)]
@@ -34,7 +33,6 @@ pub enum EncryptionError {
 pub type Result<T> = std::result::Result<T, EncryptionError>;
 
 /// Generate a random 32-byte encryption key
-#[allow(dead_code)]
 pub fn generate_key() -> [u8; KEY_SIZE] {
     let mut key = [0u8; KEY_SIZE];
     OsRng.fill_bytes(&mut key);
@@ -42,7 +40,6 @@ pub fn generate_key() -> [u8; KEY_SIZE] {
 }
 
 /// Derive a key from a password using Argon2
-#[allow(dead_code)]
 pub fn derive_key_from_password(password: &str, salt: &[u8]) -> Result<[u8; KEY_SIZE]> {
     if salt.len() < MIN_SALT_SIZE {
         return Err(EncryptionError::KeyDerivation(
@@ -74,6 +71,11 @@ pub fn derive_key_from_password(password: &str, salt: &[u8]) -> Result<[u8; KEY_
 
 /// Encrypt data using AES-256-GCM
 pub fn encrypt(plaintext: &str, key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
+    encrypt_bytes(plaintext.as_bytes(), key)
+}
+
+/// Encrypt binary data using AES-256-GCM
+pub fn encrypt_bytes(plaintext: &[u8], key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
     let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
 
     let mut nonce_bytes = [0u8; NONCE_SIZE];
@@ -81,7 +83,7 @@ pub fn encrypt(plaintext: &str, key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
     let nonce = Nonce::from_slice(&nonce_bytes);
 
     let ciphertext = cipher
-        .encrypt(nonce, plaintext.as_bytes())
+        .encrypt(nonce, plaintext)
         .map_err(|e| EncryptionError::Encryption(e.to_string()))?;
 
     let mut result = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
@@ -93,6 +95,13 @@ pub fn encrypt(plaintext: &str, key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
 
 /// Decrypt data using AES-256-GCM
 pub fn decrypt(encrypted_data: &[u8], key: &[u8; KEY_SIZE]) -> Result<String> {
+    let plaintext = decrypt_bytes(encrypted_data, key)?;
+    String::from_utf8(plaintext)
+        .map_err(|e| EncryptionError::Decryption(format!("Invalid UTF-8: {}", e)))
+}
+
+/// Decrypt binary data using AES-256-GCM. Fails if the data was tampered with.
+pub fn decrypt_bytes(encrypted_data: &[u8], key: &[u8; KEY_SIZE]) -> Result<Vec<u8>> {
     if encrypted_data.len() < NONCE_SIZE {
         return Err(EncryptionError::Decryption(
             "Encrypted data too short".to_string(),
@@ -103,12 +112,9 @@ pub fn decrypt(encrypted_data: &[u8], key: &[u8; KEY_SIZE]) -> Result<String> {
     let nonce = Nonce::from_slice(&encrypted_data[..NONCE_SIZE]);
     let ciphertext = &encrypted_data[NONCE_SIZE..];
 
-    let plaintext = cipher
+    cipher
         .decrypt(nonce, ciphertext)
-        .map_err(|e| EncryptionError::Decryption(e.to_string()))?;
-
-    String::from_utf8(plaintext)
-        .map_err(|e| EncryptionError::Decryption(format!("Invalid UTF-8: {}", e)))
+        .map_err(|e| EncryptionError::Decryption(e.to_string()))
 }
 
 /// Encode encrypted data as base64 for storage
@@ -0,0 +1,212 @@
+//! DBeaver keeps connections in `data-sources.json` and their credentials in an
+//! AES-encrypted `credentials-config.json` next to it.
+
+use super::{
+    bool_field, new_connection, port_field, read_file, string_field, ImportError, Result,
+};
+use crate::models::connection::{SSHAuth, SSHConfig};
+use crate::models::{Connection, DatabaseType};
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use std::path::Path;
+
+/// Fixed key DBeaver uses to encrypt `credentials-config.json`
+const CREDENTIALS_KEY: [u8; 16] = [
+    0xba, 0xbb, 0x4a, 0x9f, 0x77, 0x4a, 0xb8, 0x53, 0xc9, 0x6c, 0x2d, 0x65, 0x3d, 0xfe, 0x54, 0x4a,
+];
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+/// Parse `data-sources.json`, picking up credentials from the sibling file when present
+pub fn parse_file(path: &Path) -> Result<Vec<Connection>> {
+    let data_sources = read_file(path)?;
+
+    let credentials_path = path.with_file_name("credentials-config.json");
+    let credentials = if credentials_path.exists() {
+        Some(decrypt_credentials(&read_file(&credentials_path)?)?)
+    } else {
+        None
+    };
+
+    parse(&data_sources, credentials.as_ref())
+}
+
+/// Decrypt `credentials-config.json`: a 16-byte IV followed by AES-128-CBC ciphertext
+pub fn decrypt_credentials(bytes: &[u8]) -> Result<serde_json::Value> {
+    if bytes.len() < 32 {
+        return Err(ImportError::Decryption("Credentials file too short".to_string()));
+    }
+
+    let (iv, ciphertext) = bytes.split_at(16);
+    let mut buffer = ciphertext.to_vec();
+    let plaintext = Aes128CbcDec::new(&CREDENTIALS_KEY.into(), iv.into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+        .map_err(|e| ImportError::Decryption(e.to_string()))?;
+
+    serde_json::from_slice(plaintext).map_err(|e| ImportError::Parse(e.to_string()))
+}
+
+pub fn parse(data_sources: &[u8], credentials: Option<&serde_json::Value>) -> Result<Vec<Connection>> {
+    let root: serde_json::Value =
+        serde_json::from_slice(data_sources).map_err(|e| ImportError::Parse(e.to_string()))?;
+
+    let Some(entries) = root.get("connections").and_then(|c| c.as_object()) else {
+        return Ok(Vec::new());
+    };
+
+    let mut connections = Vec::new();
+    for (id, entry) in entries {
+        let provider = string_field(entry, "provider").unwrap_or_default();
+        let db_type = match provider.as_str() {
+            "mysql" => DatabaseType::MySQL,
+            "postgresql" => DatabaseType::PostgreSQL,
+            _ => continue,
+        };
+
+        let name = string_field(entry, "name").unwrap_or_else(|| id.clone());
+        let mut connection = new_connection(name, db_type);
+        let secrets = credentials.and_then(|c| c.get(id));
+
+        if let Some(config) = entry.get("configuration") {
+            if let Some(host) = string_field(config, "host") {
+                connection.host = host;
+            }
+            if let Some(port) = port_field(config, "port") {
+                connection.port = port;
+            }
+            connection.database = string_field(config, "database");
+            connection.username = string_field(config, "user").unwrap_or_default();
+            connection.password = string_field(config, "password").unwrap_or_default();
+
+            if let Some(tunnel) = config.pointer("/handlers/ssh_tunnel") {
+                let tunnel_secrets = secrets.and_then(|s| s.get("network/ssh_tunnel"));
+                connection.ssh_config = parse_ssh_tunnel(tunnel, tunnel_secrets);
+            }
+        }
+
+        if let Some(login) = secrets.and_then(|s| s.get("#connection")) {
+            if let Some(user) = string_field(login, "user") {
+                connection.username = user;
+            }
+            if let Some(password) = string_field(login, "password") {
+                connection.password = password;
+            }
+        }
+
+        connections.push(connection);
+    }
+
+    Ok(connections)
+}
+
+fn parse_ssh_tunnel(
+    tunnel: &serde_json::Value,
+    secrets: Option<&serde_json::Value>,
+) -> Option<SSHConfig> {
+    if !bool_field(tunnel, "enabled") {
+        return None;
+    }
+
+    let properties = tunnel.get("properties")?;
+    let host = string_field(properties, "host")?;
+    let username = secrets
+        .and_then(|s| string_field(s, "user"))
+        .or_else(|| string_field(properties, "user"))
+        .unwrap_or_default();
+    let password = secrets.and_then(|s| string_field(s, "password"));
+
+    let auth = match string_field(properties, "authType").as_deref() {
+        Some("PUBLIC_KEY") => SSHAuth::PrivateKey {
+            key_path: string_field(properties, "keyPath").unwrap_or_default(),
+            passphrase: password,
+        },
+        Some("AGENT") => SSHAuth::Agent,
+        _ => SSHAuth::Password(password.unwrap_or_default()),
+    };
+
+    Some(SSHConfig {
+        host,
+        port: port_field(properties, "port").unwrap_or(22),
+        username,
+        auth,
+        use_ssh_config: false,
+        proxy_jump: None,
+        jump_hosts: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockEncryptMut;
+    use serde_json::json;
+
+    type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+
+    fn encrypt_credentials(value: &serde_json::Value) -> Vec<u8> {
+        let iv = [7u8; 16];
+        let plaintext = value.to_string().into_bytes();
+        let mut buffer = plaintext.clone();
+        buffer.resize(plaintext.len() + 16, 0);
+        let ciphertext = Aes128CbcEnc::new(&CREDENTIALS_KEY.into(), &iv.into())
+            .encrypt_padded_mut::<Pkcs7>(&mut buffer, plaintext.len())
+            .unwrap();
+
+        let mut bytes = iv.to_vec();
+        bytes.extend_from_slice(ciphertext);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_data_sources_with_credentials() {
+        let data_sources = json!({
+            "connections": {
+                "mysql8-1": {
+                    "provider": "mysql",
+                    "name": "Orders",
+                    "configuration": {
+                        "host": "orders.db",
+                        "port": "3307",
+                        "database": "orders",
+                        "handlers": {
+                            "ssh_tunnel": {
+                                "enabled": true,
+                                "properties": {
+                                    "host": "bastion",
+                                    "port": 2222,
+                                    "authType": "PUBLIC_KEY",
+                                    "keyPath": "/home/me/.ssh/id_ed25519"
+                                }
+                            }
+                        }
+                    }
+                },
+                "sqlite-1": { "provider": "sqlite", "name": "Local" }
+            }
+        });
+        let credentials = decrypt_credentials(&encrypt_credentials(&json!({
+            "mysql8-1": {
+                "#connection": { "user": "app", "password": "secret" },
+                "network/ssh_tunnel": { "user": "ops" }
+            }
+        })))
+        .unwrap();
+
+        let connections =
+            parse(data_sources.to_string().as_bytes(), Some(&credentials)).unwrap();
+
+        assert_eq!(connections.len(), 1);
+        let conn = &connections[0];
+        assert_eq!(conn.name, "Orders");
+        assert_eq!(conn.host, "orders.db");
+        assert_eq!(conn.port, 3307);
+        assert_eq!(conn.username, "app");
+        assert_eq!(conn.password, "secret");
+        assert_eq!(conn.database.as_deref(), Some("orders"));
+
+        let ssh = conn.ssh_config.as_ref().unwrap();
+        assert_eq!(ssh.host, "bastion");
+        assert_eq!(ssh.port, 2222);
+        assert_eq!(ssh.username, "ops");
+        assert!(matches!(ssh.auth, SSHAuth::PrivateKey { .. }));
+    }
+}
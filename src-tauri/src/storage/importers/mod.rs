@@ -0,0 +1,100 @@
+//! Parsers that turn other clients' saved connections into `Connection` records.
+
+pub mod dbeaver;
+pub mod sequel_ace;
+pub mod tableplus;
+
+use crate::models::{Connection, DatabaseType, ExternalTool};
+use std::path::Path;
+use thiserror::Error;
+
+/// Color given to imported connections, since other tools don't share our palette
+const IMPORTED_COLOR: &str = "#3b82f6";
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("Failed to read file: {0}")]
+    Io(String),
+
+    #[error("Failed to parse file: {0}")]
+    Parse(String),
+
+    #[error("Failed to decrypt credentials: {0}")]
+    Decryption(String),
+}
+
+pub type Result<T> = std::result::Result<T, ImportError>;
+
+/// Parse the connections stored by `tool` at `path`
+pub fn import_from(tool: ExternalTool, path: &Path) -> Result<Vec<Connection>> {
+    match tool {
+        ExternalTool::DBeaver => dbeaver::parse_file(path),
+        ExternalTool::TablePlus => tableplus::parse_file(path),
+        ExternalTool::SequelAce => sequel_ace::parse_file(path),
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| ImportError::Io(format!("{}: {}", path.display(), e)))
+}
+
+/// Parse an XML or binary property list into JSON so all importers share helpers
+fn plist_to_json(bytes: &[u8]) -> Result<serde_json::Value> {
+    let value: plist::Value =
+        plist::from_bytes(bytes).map_err(|e| ImportError::Parse(e.to_string()))?;
+    serde_json::to_value(value).map_err(|e| ImportError::Parse(e.to_string()))
+}
+
+fn string_field(value: &serde_json::Value, key: &str) -> Option<String> {
+    match value.get(key)? {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Ports are stored as numbers by some tools and strings by others
+fn port_field(value: &serde_json::Value, key: &str) -> Option<u16> {
+    match value.get(key)? {
+        serde_json::Value::Number(n) => n.as_u64().and_then(|p| u16::try_from(p).ok()),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn bool_field(value: &serde_json::Value, key: &str) -> bool {
+    match value.get(key) {
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(serde_json::Value::Number(n)) => n.as_i64().unwrap_or(0) != 0,
+        Some(serde_json::Value::String(s)) => matches!(s.as_str(), "1" | "true" | "YES"),
+        _ => false,
+    }
+}
+
+fn new_connection(name: String, db_type: DatabaseType) -> Connection {
+    let port = db_type.default_port();
+    Connection::new(
+        name,
+        IMPORTED_COLOR.to_string(),
+        db_type,
+        "localhost".to_string(),
+        port,
+        String::new(),
+        String::new(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_port_field_accepts_numbers_and_strings() {
+        let value = json!({"a": 3307, "b": "5433", "c": "nope"});
+        assert_eq!(port_field(&value, "a"), Some(3307));
+        assert_eq!(port_field(&value, "b"), Some(5433));
+        assert_eq!(port_field(&value, "c"), None);
+        assert_eq!(port_field(&value, "missing"), None);
+    }
+}
@@ -0,0 +1,141 @@
+//! Sequel Ace keeps favorites in `Favorites.plist` as a tree of groups. Passwords
+//! live in the macOS keychain and are not imported.
+
+use super::{bool_field, new_connection, plist_to_json, port_field, read_file, string_field, Result};
+use crate::models::connection::{SSHAuth, SSHConfig, SSLConfig};
+use crate::models::{Connection, DatabaseType};
+use std::path::Path;
+
+/// Favorite `type` value for connections made over an SSH tunnel
+const CONNECTION_TYPE_SSH: i64 = 2;
+
+pub fn parse_file(path: &Path) -> Result<Vec<Connection>> {
+    parse(&read_file(path)?)
+}
+
+pub fn parse(bytes: &[u8]) -> Result<Vec<Connection>> {
+    let root = plist_to_json(bytes)?;
+    let favorites_root = root.get("Favorites Root").unwrap_or(&root);
+
+    let mut connections = Vec::new();
+    collect_favorites(favorites_root, &mut connections);
+    Ok(connections)
+}
+
+fn collect_favorites(node: &serde_json::Value, connections: &mut Vec<Connection>) {
+    if let Some(children) = node.get("Children").and_then(|c| c.as_array()) {
+        for child in children {
+            collect_favorites(child, connections);
+        }
+        return;
+    }
+
+    if node.get("host").is_some() {
+        connections.push(parse_favorite(node));
+    }
+}
+
+fn parse_favorite(favorite: &serde_json::Value) -> Connection {
+    let name = string_field(favorite, "name").unwrap_or_else(|| "Imported".to_string());
+    let mut connection = new_connection(name, DatabaseType::MySQL);
+
+    if let Some(host) = string_field(favorite, "host") {
+        connection.host = host;
+    }
+    if let Some(port) = port_field(favorite, "port") {
+        connection.port = port;
+    }
+    connection.username = string_field(favorite, "user").unwrap_or_default();
+    connection.database = string_field(favorite, "database");
+
+    let connection_type = favorite.get("type").and_then(|t| t.as_i64()).unwrap_or(0);
+    if connection_type == CONNECTION_TYPE_SSH {
+        if let Some(host) = string_field(favorite, "sshHost") {
+            let auth = match string_field(favorite, "sshKeyLocation") {
+                Some(key_path) if bool_field(favorite, "sshKeyLocationEnabled") => {
+                    SSHAuth::PrivateKey {
+                        key_path,
+                        passphrase: None,
+                    }
+                }
+                _ => SSHAuth::Agent,
+            };
+
+            connection.ssh_config = Some(SSHConfig {
+                host,
+                port: port_field(favorite, "sshPort").unwrap_or(22),
+                username: string_field(favorite, "sshUser").unwrap_or_default(),
+                auth,
+                use_ssh_config: false,
+                proxy_jump: None,
+                jump_hosts: vec![],
+            });
+        }
+    }
+
+    if bool_field(favorite, "useSSL") {
+        connection.ssl_config = Some(SSLConfig {
+            ca_cert: string_field(favorite, "sslCACertFileLocation"),
+            client_cert: string_field(favorite, "sslCertificateFileLocation"),
+            client_key: string_field(favorite, "sslKeyFileLocation"),
+            verify: true,
+        });
+    }
+
+    connection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nested_favorites() {
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Favorites Root</key>
+    <dict>
+        <key>Children</key>
+        <array>
+            <dict>
+                <key>name</key><string>Local</string>
+                <key>host</key><string>127.0.0.1</string>
+                <key>port</key><string></string>
+                <key>user</key><string>root</string>
+                <key>type</key><integer>0</integer>
+            </dict>
+            <dict>
+                <key>Name</key><string>Work</string>
+                <key>Children</key>
+                <array>
+                    <dict>
+                        <key>name</key><string>Prod</string>
+                        <key>host</key><string>10.0.0.5</string>
+                        <key>port</key><string>3307</string>
+                        <key>user</key><string>app</string>
+                        <key>type</key><integer>2</integer>
+                        <key>sshHost</key><string>bastion</string>
+                        <key>sshUser</key><string>ops</string>
+                        <key>sshPort</key><string>22</string>
+                        <key>useSSL</key><integer>1</integer>
+                    </dict>
+                </array>
+            </dict>
+        </array>
+    </dict>
+</dict>
+</plist>"#;
+
+        let connections = parse(plist.as_bytes()).unwrap();
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[0].port, 3306);
+
+        let prod = &connections[1];
+        assert_eq!(prod.name, "Prod");
+        assert_eq!(prod.port, 3307);
+        assert_eq!(prod.ssh_config.as_ref().unwrap().username, "ops");
+        assert!(prod.ssl_config.is_some());
+    }
+}
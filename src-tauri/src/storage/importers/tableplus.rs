@@ -0,0 +1,115 @@
+//! TablePlus stores connections as an array of dictionaries, either in its
+//! `Connections.plist` or in a JSON export.
+
+use super::{
+    bool_field, new_connection, plist_to_json, port_field, read_file, string_field, ImportError,
+    Result,
+};
+use crate::models::connection::{SSHAuth, SSHConfig};
+use crate::models::{Connection, DatabaseType};
+use std::path::Path;
+
+pub fn parse_file(path: &Path) -> Result<Vec<Connection>> {
+    parse(&read_file(path)?)
+}
+
+pub fn parse(bytes: &[u8]) -> Result<Vec<Connection>> {
+    let root = match serde_json::from_slice::<serde_json::Value>(bytes) {
+        Ok(value) => value,
+        Err(_) => plist_to_json(bytes)?,
+    };
+
+    let entries = root
+        .as_array()
+        .ok_or_else(|| ImportError::Parse("Expected a list of connections".to_string()))?;
+
+    let mut connections = Vec::new();
+    for entry in entries {
+        let db_type = match string_field(entry, "Driver").as_deref() {
+            Some("MySQL") | Some("MariaDB") => DatabaseType::MySQL,
+            Some("PostgreSQL") => DatabaseType::PostgreSQL,
+            _ => continue,
+        };
+
+        let name = string_field(entry, "ConnectionName").unwrap_or_else(|| "Imported".to_string());
+        let mut connection = new_connection(name, db_type);
+
+        if let Some(host) = string_field(entry, "DatabaseHost") {
+            connection.host = host;
+        }
+        if let Some(port) = port_field(entry, "DatabasePort") {
+            connection.port = port;
+        }
+        connection.username = string_field(entry, "DatabaseUser").unwrap_or_default();
+        connection.password = string_field(entry, "DatabasePassword").unwrap_or_default();
+        connection.database = string_field(entry, "DatabaseName");
+
+        if bool_field(entry, "isOverSSH") {
+            if let Some(host) = string_field(entry, "ServerAddress") {
+                let auth = match string_field(entry, "ServerPrivateKeyName") {
+                    Some(key_path) => SSHAuth::PrivateKey {
+                        key_path,
+                        passphrase: None,
+                    },
+                    None => SSHAuth::Password(
+                        string_field(entry, "ServerPassword").unwrap_or_default(),
+                    ),
+                };
+
+                connection.ssh_config = Some(SSHConfig {
+                    host,
+                    port: port_field(entry, "ServerPort").unwrap_or(22),
+                    username: string_field(entry, "ServerUser").unwrap_or_default(),
+                    auth,
+                    use_ssh_config: false,
+                    proxy_jump: None,
+                    jump_hosts: vec![],
+                });
+            }
+        }
+
+        connections.push(connection);
+    }
+
+    Ok(connections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plist_export() {
+        let plist = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<array>
+    <dict>
+        <key>ConnectionName</key><string>Analytics</string>
+        <key>Driver</key><string>PostgreSQL</string>
+        <key>DatabaseHost</key><string>pg.internal</string>
+        <key>DatabasePort</key><string>6432</string>
+        <key>DatabaseUser</key><string>analyst</string>
+        <key>DatabaseName</key><string>warehouse</string>
+        <key>isOverSSH</key><true/>
+        <key>ServerAddress</key><string>jump.internal</string>
+        <key>ServerUser</key><string>ops</string>
+    </dict>
+    <dict>
+        <key>ConnectionName</key><string>Cache</string>
+        <key>Driver</key><string>Redis</string>
+    </dict>
+</array>
+</plist>"#;
+
+        let connections = parse(plist.as_bytes()).unwrap();
+        assert_eq!(connections.len(), 1);
+
+        let conn = &connections[0];
+        assert_eq!(conn.db_type, DatabaseType::PostgreSQL);
+        assert_eq!(conn.host, "pg.internal");
+        assert_eq!(conn.port, 6432);
+        assert_eq!(conn.database.as_deref(), Some("warehouse"));
+        assert_eq!(conn.ssh_config.as_ref().unwrap().host, "jump.internal");
+    }
+}
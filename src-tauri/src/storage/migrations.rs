@@ -0,0 +1,458 @@
+//! Versioned schema of the app's SQLite file. Every store shares the file,
+//! so one ordered list of migrations covers all their tables, and the
+//! file's `user_version` records how many have been applied.
+//!
+//! Installs from before versioning have `user_version` 0 and any subset of
+//! the tables, so migrations only create what is missing.
+
+use crate::storage::connection_store::{Result, StoreError};
+use rusqlite::{Connection as SqliteConnection, Transaction};
+
+/// One schema change, applied in a transaction together with the version bump
+struct Migration {
+    description: &'static str,
+    apply: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+/// Applied in order; migration `i` brings the file to version `i + 1`.
+/// Append new migrations, never edit or reorder released ones.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "connections",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS connections (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    color TEXT NOT NULL,
+                    db_type TEXT NOT NULL,
+                    host TEXT NOT NULL,
+                    port INTEGER NOT NULL,
+                    username TEXT NOT NULL,
+                    password TEXT NOT NULL,
+                    database TEXT,
+                    ssh_config TEXT,
+                    ssl_config TEXT,
+                    created_at INTEGER DEFAULT (strftime('%s', 'now'))
+                )",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "connection groups",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS connection_groups (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    created_at INTEGER DEFAULT (strftime('%s', 'now'))
+                )",
+                [],
+            )?;
+            add_column(tx, "connections", "group_id", "TEXT")
+        },
+    },
+    Migration {
+        description: "query history",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS query_history (
+                    id TEXT PRIMARY KEY,
+                    connection_id TEXT NOT NULL,
+                    database TEXT,
+                    sql TEXT NOT NULL,
+                    fingerprint TEXT NOT NULL,
+                    params TEXT,
+                    executed_at INTEGER NOT NULL,
+                    execution_time_ms INTEGER NOT NULL,
+                    success INTEGER NOT NULL,
+                    error_message TEXT
+                )",
+                [],
+            )?;
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_query_history_fingerprint
+                 ON query_history (connection_id, fingerprint)",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "read-only connections",
+        apply: |tx| add_column(tx, "connections", "read_only", "INTEGER NOT NULL DEFAULT 0"),
+    },
+    Migration {
+        description: "connection options",
+        apply: |tx| add_column(tx, "connections", "options", "TEXT"),
+    },
+    Migration {
+        description: "connection usage",
+        apply: |tx| {
+            add_column(tx, "connections", "last_used_at", "INTEGER")?;
+            add_column(tx, "connections", "use_count", "INTEGER NOT NULL DEFAULT 0")
+        },
+    },
+    Migration {
+        description: "settings",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS settings (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                )",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "workspaces",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS workspaces (
+                    connection_id TEXT PRIMARY KEY,
+                    workspace TEXT NOT NULL,
+                    saved_at INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "audit log",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS audit_log (
+                    id TEXT PRIMARY KEY,
+                    connection_id TEXT NOT NULL,
+                    database TEXT,
+                    command TEXT NOT NULL,
+                    sql TEXT NOT NULL,
+                    rows_affected INTEGER,
+                    success INTEGER NOT NULL,
+                    error_message TEXT,
+                    executed_at INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_audit_log_executed_at
+                 ON audit_log (executed_at)",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "pinned tables",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS pinned_tables (
+                    connection_id TEXT NOT NULL,
+                    database_name TEXT NOT NULL,
+                    table_name TEXT NOT NULL,
+                    pinned_at INTEGER NOT NULL,
+                    PRIMARY KEY (connection_id, database_name, table_name)
+                )",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "store metadata",
+        apply: |tx| {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS store_meta (
+                    name TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                )",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "window-scoped workspaces",
+        apply: |tx| {
+            // Rebuilt to key workspaces by window too; existing ones belong
+            // to the main window, the only one there was
+            tx.execute_batch(
+                "CREATE TABLE window_workspaces (
+                    connection_id TEXT NOT NULL,
+                    window_label TEXT NOT NULL,
+                    workspace TEXT NOT NULL,
+                    saved_at INTEGER NOT NULL,
+                    PRIMARY KEY (connection_id, window_label)
+                );
+                INSERT INTO window_workspaces (connection_id, window_label, workspace, saved_at)
+                SELECT connection_id, 'main', workspace, saved_at FROM workspaces;
+                DROP TABLE workspaces;
+                ALTER TABLE window_workspaces RENAME TO workspaces;",
+            )
+        },
+    },
+    Migration {
+        description: "connection environments",
+        apply: |tx| add_column(tx, "connections", "environment", "TEXT NOT NULL DEFAULT 'Dev'"),
+    },
+    Migration {
+        description: "recent objects",
+        apply: |tx| {
+            // A database has an empty object name, so it can be in the key
+            tx.execute_batch(
+                "CREATE TABLE recent_objects (
+                    connection_id TEXT NOT NULL,
+                    kind TEXT NOT NULL,
+                    database_name TEXT NOT NULL,
+                    object_name TEXT NOT NULL,
+                    opened_at INTEGER NOT NULL,
+                    open_count INTEGER NOT NULL DEFAULT 1,
+                    PRIMARY KEY (connection_id, kind, database_name, object_name)
+                );
+                CREATE INDEX idx_recent_objects_opened_at
+                ON recent_objects (connection_id, opened_at);",
+            )
+        },
+    },
+    Migration {
+        description: "query history search",
+        apply: |tx| {
+            // Indexes the statements of `query_history` by rowid, kept in
+            // step by triggers; the rows already recorded are indexed now
+            tx.execute_batch(
+                "CREATE VIRTUAL TABLE query_history_fts USING fts5(
+                    sql, content = 'query_history', content_rowid = 'rowid'
+                );
+                INSERT INTO query_history_fts (query_history_fts) VALUES ('rebuild');
+                CREATE TRIGGER query_history_fts_insert AFTER INSERT ON query_history BEGIN
+                    INSERT INTO query_history_fts (rowid, sql) VALUES (new.rowid, new.sql);
+                END;
+                CREATE TRIGGER query_history_fts_delete AFTER DELETE ON query_history BEGIN
+                    INSERT INTO query_history_fts (query_history_fts, rowid, sql)
+                    VALUES ('delete', old.rowid, old.sql);
+                END;
+                CREATE TRIGGER query_history_fts_update AFTER UPDATE ON query_history BEGIN
+                    INSERT INTO query_history_fts (query_history_fts, rowid, sql)
+                    VALUES ('delete', old.rowid, old.sql);
+                    INSERT INTO query_history_fts (rowid, sql) VALUES (new.rowid, new.sql);
+                END;",
+            )
+        },
+    },
+    Migration {
+        description: "drop stored vault token",
+        apply: |tx| {
+            // The token is read from the environment now; one saved with the
+            // settings was plain text
+            tx.execute(
+                "UPDATE settings SET value = json_remove(value, '$.token')
+                 WHERE key = 'vault' AND json_valid(value)",
+                [],
+            )?;
+            Ok(())
+        },
+    },
+];
+
+/// Schema version a fully migrated file has
+pub const LATEST_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Add a column unless an install from before versioning already has it
+fn add_column(
+    db: &SqliteConnection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    let mut stmt = db.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .iter()
+        .any(|name| name == column);
+
+    if !exists {
+        db.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn schema_version(db: &SqliteConnection) -> Result<u32> {
+    Ok(db.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Apply the migrations up to `target` the file doesn't have yet. Each runs
+/// in its own transaction with its version bump, so an interrupted upgrade
+/// resumes where it stopped.
+fn migrate_to(db: &mut SqliteConnection, target: u32) -> Result<()> {
+    let current = schema_version(db)?;
+    if current > LATEST_VERSION {
+        return Err(StoreError::UnsupportedVersion(current));
+    }
+
+    for (index, migration) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .take(target as usize)
+        .skip(current as usize)
+    {
+        let version = index as u32 + 1;
+        tracing::info!(
+            version,
+            migration = migration.description,
+            "Migrating app database"
+        );
+        let tx = db.transaction()?;
+        (migration.apply)(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// Bring the file to the latest schema
+pub fn migrate(db: &mut SqliteConnection) -> Result<()> {
+    migrate_to(db, LATEST_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    /// Every table's columns, to compare schemas however they were reached
+    fn schema(db: &SqliteConnection) -> Vec<(String, Vec<(String, String)>)> {
+        let tables: Vec<String> = db
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+
+        tables
+            .into_iter()
+            .map(|table| {
+                let mut columns: Vec<(String, String)> = db
+                    .prepare(&format!("PRAGMA table_info({})", table))
+                    .unwrap()
+                    .query_map([], |row| Ok((row.get(1)?, row.get(2)?)))
+                    .unwrap()
+                    .collect::<rusqlite::Result<_>>()
+                    .unwrap();
+                columns.sort();
+                (table, columns)
+            })
+            .collect()
+    }
+
+    fn fresh_schema() -> Vec<(String, Vec<(String, String)>)> {
+        let mut db = SqliteConnection::open_in_memory().unwrap();
+        migrate(&mut db).unwrap();
+        schema(&db)
+    }
+
+    #[test]
+    fn test_upgrade_from_each_version() {
+        let expected = fresh_schema();
+
+        for version in 0..=LATEST_VERSION {
+            let mut db = SqliteConnection::open_in_memory().unwrap();
+            migrate_to(&mut db, version).unwrap();
+            assert_eq!(schema_version(&db).unwrap(), version);
+            if version >= 1 {
+                db.execute(
+                    "INSERT INTO connections
+                    (id, name, color, db_type, host, port, username, password)
+                    VALUES ('a', 'A', '#fff', 'MySQL', 'localhost', 3306, 'root', 'x')",
+                    [],
+                )
+                .unwrap();
+            }
+
+            migrate(&mut db).unwrap();
+            assert_eq!(schema_version(&db).unwrap(), LATEST_VERSION);
+            assert_eq!(schema(&db), expected, "upgrading from version {}", version);
+
+            let count: i64 = db
+                .query_row("SELECT COUNT(*) FROM connections", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(count, i64::from(version >= 1));
+        }
+    }
+
+    #[test]
+    fn test_upgrade_unversioned_install() {
+        // A file from before versioning: some columns added, others not
+        let mut db = SqliteConnection::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE connections (
+                id TEXT PRIMARY KEY, name TEXT NOT NULL, color TEXT NOT NULL,
+                db_type TEXT NOT NULL, host TEXT NOT NULL, port INTEGER NOT NULL,
+                username TEXT NOT NULL, password TEXT NOT NULL, database TEXT,
+                ssh_config TEXT, ssl_config TEXT,
+                created_at INTEGER DEFAULT (strftime('%s', 'now')),
+                group_id TEXT, read_only INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+        )
+        .unwrap();
+        db.execute(
+            "INSERT INTO connections
+            (id, name, color, db_type, host, port, username, password, read_only)
+            VALUES (?1, 'A', '#fff', 'MySQL', 'localhost', 3306, 'root', 'x', 1)",
+            params!["a"],
+        )
+        .unwrap();
+
+        migrate(&mut db).unwrap();
+        assert_eq!(schema(&db), fresh_schema());
+        let (read_only, use_count): (bool, i64) = db
+            .query_row(
+                "SELECT read_only, use_count FROM connections WHERE id = 'a'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(read_only);
+        assert_eq!(use_count, 0);
+    }
+
+    #[test]
+    fn test_stored_vault_token_is_dropped() {
+        let mut db = SqliteConnection::open_in_memory().unwrap();
+        migrate_to(&mut db, LATEST_VERSION - 1).unwrap();
+        db.execute(
+            "INSERT INTO settings (key, value) VALUES ('vault', ?1)",
+            params![r#"{"address":"https://vault:8200","token":"hvs.s3cret"}"#],
+        )
+        .unwrap();
+
+        migrate(&mut db).unwrap();
+        let vault: String = db
+            .query_row("SELECT value FROM settings WHERE key = 'vault'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(vault, r#"{"address":"https://vault:8200"}"#);
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let mut db = SqliteConnection::open_in_memory().unwrap();
+        db.pragma_update(None, "user_version", LATEST_VERSION + 1)
+            .unwrap();
+        assert!(matches!(
+            migrate(&mut db),
+            Err(StoreError::UnsupportedVersion(_))
+        ));
+    }
+}
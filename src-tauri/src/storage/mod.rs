@@ -1,2 +1,10 @@
+pub mod app_backup;
+pub mod audit_store;
 pub mod encryption;
 pub mod connection_store;
+pub mod connection_transfer;
+pub mod importers;
+pub mod migrations;
+pub mod query_history;
+pub mod settings_store;
+pub mod workspace_store;
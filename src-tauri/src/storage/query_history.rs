@@ -0,0 +1,404 @@
+use crate::models::{QueryHistoryEntry, QuerySearchMatch, QueryStat, TimeRange};
+use crate::storage::connection_store::{Result, StoreError};
+use crate::storage::migrations::migrate;
+use rusqlite::{params, Connection as SqliteConnection, Row};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Words of a snippet either side of the ones that matched
+const SNIPPET_WORDS: i64 = 24;
+
+/// Persists executed statements (and the parameters they ran with) per connection
+pub struct QueryHistoryStore {
+    db: SqliteConnection,
+}
+
+fn entry_from_row(row: &Row<'_>) -> rusqlite::Result<(QueryHistoryEntry, Option<String>)> {
+    let entry = QueryHistoryEntry {
+        id: row.get(0)?,
+        connection_id: row.get(1)?,
+        database: row.get(2)?,
+        sql: row.get(3)?,
+        fingerprint: row.get(4)?,
+        params: Vec::new(),
+        executed_at: row.get(6)?,
+        execution_time_ms: row.get::<_, i64>(7)? as u64,
+        success: row.get(8)?,
+        error_message: row.get(9)?,
+    };
+    Ok((entry, row.get(5)?))
+}
+
+/// A full-text query finding statements with every word typed, each also
+/// as the start of a longer word. Words are quoted, so punctuation such as
+/// `orders.id` is matched as words instead of read as query syntax.
+fn match_expression(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Index the statements recorded so far again, e.g. after the file was
+/// copied in a way that can renumber rowids
+pub fn rebuild_search_index(db: &SqliteConnection) -> Result<()> {
+    db.execute(
+        "INSERT INTO query_history_fts (query_history_fts) VALUES ('rebuild')",
+        [],
+    )?;
+    Ok(())
+}
+
+fn parse_params(json: Option<String>) -> Result<Vec<serde_json::Value>> {
+    json.map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e| StoreError::Serialization(e.to_string()))
+}
+
+impl QueryHistoryStore {
+    pub fn new(db_path: &Path) -> Result<Self> {
+        let mut db = SqliteConnection::open(db_path)?;
+        migrate(&mut db)?;
+
+        Ok(Self { db })
+    }
+
+    /// Record a single execution
+    pub fn record(&mut self, entry: &QueryHistoryEntry) -> Result<()> {
+        let params_json = if entry.params.is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::to_string(&entry.params)
+                    .map_err(|e| StoreError::Serialization(e.to_string()))?,
+            )
+        };
+
+        self.db.execute(
+            "INSERT INTO query_history
+            (id, connection_id, database, sql, fingerprint, params, executed_at, execution_time_ms, success, error_message)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                entry.id,
+                entry.connection_id,
+                entry.database,
+                entry.sql,
+                entry.fingerprint,
+                params_json,
+                entry.executed_at,
+                entry.execution_time_ms as i64,
+                entry.success,
+                entry.error_message,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Most recent executions for a connection, newest first
+    pub fn list(&self, connection_id: &str, limit: usize) -> Result<Vec<QueryHistoryEntry>> {
+        let mut stmt = self.db.prepare(
+            "SELECT id, connection_id, database, sql, fingerprint, params, executed_at, execution_time_ms, success, error_message
+             FROM query_history WHERE connection_id = ?1
+             ORDER BY executed_at DESC LIMIT ?2",
+        )?;
+
+        let rows = stmt
+            .query_map(params![connection_id, limit as i64], entry_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(mut entry, params_json)| {
+                entry.params = parse_params(params_json)?;
+                Ok(entry)
+            })
+            .collect()
+    }
+
+    /// Totals per statement fingerprint and database of the executions
+    /// recorded within `range`
+    pub fn fingerprint_totals(
+        &self,
+        connection_id: &str,
+        range: &TimeRange,
+    ) -> Result<Vec<QueryStat>> {
+        let mut stmt = self.db.prepare(
+            "SELECT fingerprint, database, COUNT(*), SUM(CASE WHEN success THEN 0 ELSE 1 END),
+                    SUM(execution_time_ms), MAX(execution_time_ms), MAX(executed_at)
+             FROM query_history
+             WHERE connection_id = ?1 AND executed_at >= ?2 AND executed_at < ?3
+             GROUP BY fingerprint, database",
+        )?;
+
+        let start = range.start.unwrap_or(i64::MIN);
+        let end = range.end.unwrap_or(i64::MAX);
+        let rows = stmt
+            .query_map(params![connection_id, start, end], |row| {
+                let executions = row.get::<_, i64>(2)? as u64;
+                let total_time_ms = row.get::<_, i64>(4)? as u64;
+                Ok(QueryStat {
+                    fingerprint: row.get(0)?,
+                    database: row.get(1)?,
+                    executions,
+                    failures: row.get::<_, i64>(3)? as u64,
+                    total_time_ms,
+                    average_time_ms: total_time_ms / executions.max(1),
+                    max_time_ms: row.get::<_, i64>(5)? as u64,
+                    last_executed_at: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    }
+
+    /// Statements whose text has every word of `text`, best match first,
+    /// of one connection or all of them and executed within `range`. A
+    /// statement run many times is listed once, with its latest execution.
+    pub fn search(
+        &self,
+        text: &str,
+        connection_id: Option<&str>,
+        range: &TimeRange,
+        limit: usize,
+    ) -> Result<Vec<QuerySearchMatch>> {
+        let Some(expression) = match_expression(text) else {
+            return Ok(Vec::new());
+        };
+        let mut stmt = self.db.prepare(
+            "SELECT h.id, h.connection_id, h.database, h.sql, h.fingerprint, h.params,
+                    h.executed_at, h.execution_time_ms, h.success, h.error_message,
+                    snippet(query_history_fts, 0, '«', '»', '…', ?5),
+                    bm25(query_history_fts)
+             FROM query_history_fts
+             JOIN query_history h ON h.rowid = query_history_fts.rowid
+             WHERE query_history_fts MATCH ?1
+               AND (?2 IS NULL OR h.connection_id = ?2)
+               AND h.executed_at >= ?3 AND h.executed_at < ?4
+             ORDER BY bm25(query_history_fts), h.executed_at DESC",
+        )?;
+
+        let start = range.start.unwrap_or(i64::MIN);
+        let end = range.end.unwrap_or(i64::MAX);
+        let rows = stmt.query_map(
+            params![expression, connection_id, start, end, SNIPPET_WORDS],
+            |row| {
+                let (entry, params_json) = entry_from_row(row)?;
+                let snippet: String = row.get(10)?;
+                // bm25 is lower for better matches
+                let score = -row.get::<_, f64>(11)?;
+                Ok((entry, params_json, snippet, score))
+            },
+        )?;
+
+        // Runs of one statement score the same, and the latest sorts first
+        let mut seen = HashSet::new();
+        let mut matches = Vec::new();
+        for row in rows {
+            let (mut entry, params_json, snippet, score) = row?;
+            if !seen.insert((entry.connection_id.clone(), entry.fingerprint.clone())) {
+                continue;
+            }
+            entry.params = parse_params(params_json)?;
+            matches.push(QuerySearchMatch {
+                entry,
+                snippet,
+                score,
+            });
+            if matches.len() == limit {
+                break;
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Delete executions recorded before `cutoff` (unix millis), returning how many were removed
+    pub fn prune_before(&mut self, cutoff: i64) -> Result<usize> {
+        Ok(self
+            .db
+            .execute("DELETE FROM query_history WHERE executed_at < ?1", params![cutoff])?)
+    }
+
+    /// Distinct parameter sets previously used with a statement fingerprint, most recent first
+    pub fn parameter_suggestions(
+        &self,
+        connection_id: &str,
+        fingerprint: &str,
+        limit: usize,
+    ) -> Result<Vec<Vec<serde_json::Value>>> {
+        let mut stmt = self.db.prepare(
+            "SELECT params FROM query_history
+             WHERE connection_id = ?1 AND fingerprint = ?2 AND params IS NOT NULL AND success = 1
+             GROUP BY params
+             ORDER BY MAX(executed_at) DESC
+             LIMIT ?3",
+        )?;
+
+        let rows = stmt
+            .query_map(params![connection_id, fingerprint, limit as i64], |row| {
+                row.get::<_, Option<String>>(0)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter().map(parse_params).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn setup_test_store() -> (QueryHistoryStore, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let store = QueryHistoryStore::new(&temp_dir.path().join("test.db")).unwrap();
+        (store, temp_dir)
+    }
+
+    fn entry(executed_at: i64, params: Vec<serde_json::Value>) -> QueryHistoryEntry {
+        QueryHistoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            connection_id: "conn".to_string(),
+            database: Some("app".to_string()),
+            sql: "SELECT * FROM users WHERE id = ?".to_string(),
+            fingerprint: "SELECT * FROM users WHERE id = ?".to_string(),
+            params,
+            executed_at,
+            execution_time_ms: 5,
+            success: true,
+            error_message: None,
+        }
+    }
+
+    #[test]
+    fn test_record_and_list_history() {
+        let (mut store, _temp) = setup_test_store();
+        store.record(&entry(1, vec![])).unwrap();
+        store.record(&entry(2, vec![json!(42)])).unwrap();
+
+        let history = store.list("conn", 10).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].executed_at, 2);
+        assert_eq!(history[0].params, vec![json!(42)]);
+        assert!(history[1].params.is_empty());
+    }
+
+    #[test]
+    fn test_prune_before_cutoff() {
+        let (mut store, _temp) = setup_test_store();
+        store.record(&entry(1, vec![])).unwrap();
+        store.record(&entry(5, vec![])).unwrap();
+
+        assert_eq!(store.prune_before(5).unwrap(), 1);
+        let history = store.list("conn", 10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].executed_at, 5);
+    }
+
+    #[test]
+    fn test_fingerprint_totals_within_range() {
+        let (mut store, _temp) = setup_test_store();
+        for executed_at in 1..=4 {
+            let mut run = entry(executed_at, vec![]);
+            run.execution_time_ms = executed_at as u64 * 10;
+            run.success = executed_at != 3;
+            store.record(&run).unwrap();
+        }
+
+        let range = TimeRange {
+            start: Some(2),
+            end: Some(4),
+        };
+        let totals = store.fingerprint_totals("conn", &range).unwrap();
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].executions, 2);
+        assert_eq!(totals[0].failures, 1);
+        assert_eq!(totals[0].total_time_ms, 50);
+        assert_eq!(totals[0].average_time_ms, 25);
+        assert_eq!(totals[0].max_time_ms, 30);
+        assert_eq!(totals[0].last_executed_at, 3);
+        assert!(store
+            .fingerprint_totals("other", &TimeRange::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parameter_suggestions_are_distinct_and_recent_first() {
+        let (mut store, _temp) = setup_test_store();
+        store.record(&entry(1, vec![json!(1)])).unwrap();
+        store.record(&entry(2, vec![json!(2)])).unwrap();
+        store.record(&entry(3, vec![json!(1)])).unwrap();
+
+        let fingerprint = "SELECT * FROM users WHERE id = ?";
+        let suggestions = store.parameter_suggestions("conn", fingerprint, 10).unwrap();
+        assert_eq!(suggestions, vec![vec![json!(1)], vec![json!(2)]]);
+    }
+
+    fn run(connection_id: &str, executed_at: i64, sql: &str) -> QueryHistoryEntry {
+        QueryHistoryEntry {
+            connection_id: connection_id.to_string(),
+            sql: sql.to_string(),
+            fingerprint: sql.to_string(),
+            ..entry(executed_at, vec![])
+        }
+    }
+
+    #[test]
+    fn test_search_ranks_and_dedupes() {
+        let (mut store, _temp) = setup_test_store();
+        let join = "SELECT o.id, r.amount FROM orders o JOIN refunds r ON r.order_id = o.id";
+        store.record(&run("conn", 1, join)).unwrap();
+        store.record(&run("conn", 5, join)).unwrap();
+        store
+            .record(&run("conn", 2, "SELECT * FROM orders WHERE status = 'refunded'"))
+            .unwrap();
+        store.record(&run("other", 3, "DELETE FROM refunds")).unwrap();
+
+        let matches = store
+            .search("orders refund", Some("conn"), &TimeRange::default(), 10)
+            .unwrap();
+        assert_eq!(matches.len(), 2);
+        assert!(matches[0].score >= matches[1].score);
+        // The join is listed once, as its latest run
+        let joined = matches.iter().find(|m| m.entry.sql == join).unwrap();
+        assert_eq!(joined.entry.executed_at, 5);
+        assert!(joined.snippet.contains("«orders»"));
+
+        // Punctuation is matched as words rather than query syntax
+        let matches = store
+            .search("r.order_id", None, &TimeRange::default(), 10)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+
+        let range = TimeRange {
+            start: Some(3),
+            end: None,
+        };
+        let matches = store.search("refunds", None, &range, 10).unwrap();
+        let times: Vec<i64> = matches.iter().map(|m| m.entry.executed_at).collect();
+        assert_eq!(times.len(), 2);
+        assert!(times.contains(&3) && times.contains(&5));
+        assert!(store
+            .search("  \"* ", None, &TimeRange::default(), 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_search_index_follows_deletes() {
+        let (mut store, _temp) = setup_test_store();
+        store.record(&run("conn", 1, "SELECT * FROM invoices")).unwrap();
+        store.record(&run("conn", 9, "SELECT * FROM payments")).unwrap();
+        store.prune_before(5).unwrap();
+
+        let all = TimeRange::default();
+        assert!(store.search("invoices", None, &all, 10).unwrap().is_empty());
+        rebuild_search_index(&store.db).unwrap();
+        assert_eq!(store.search("payments", None, &all, 10).unwrap().len(), 1);
+    }
+}
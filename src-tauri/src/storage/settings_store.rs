@@ -0,0 +1,146 @@
+use crate::models::AppSettings;
+use crate::storage::connection_store::{Result, StoreError};
+use crate::storage::migrations::migrate;
+use rusqlite::{params, Connection as SqliteConnection};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Persists application settings as one row per top-level key, so settings
+/// added in later versions fall back to their defaults. Once read, the
+/// settings are kept in memory, so commands can check them without SQLite.
+pub struct SettingsStore {
+    db: SqliteConnection,
+    current: Option<AppSettings>,
+}
+
+impl SettingsStore {
+    pub fn new(db_path: &Path) -> Result<Self> {
+        let mut db = SqliteConnection::open(db_path)?;
+        migrate(&mut db)?;
+
+        Ok(Self { db, current: None })
+    }
+
+    pub fn load(&mut self) -> Result<AppSettings> {
+        if let Some(current) = &self.current {
+            return Ok(current.clone());
+        }
+        let settings = self.read()?;
+        self.current = Some(settings.clone());
+        Ok(settings)
+    }
+
+    /// Read the settings again, e.g. after the file was restored from a backup
+    pub fn reload(&mut self) -> Result<AppSettings> {
+        self.current = None;
+        self.load()
+    }
+
+    fn read(&self) -> Result<AppSettings> {
+        let mut stmt = self.db.prepare("SELECT key, value FROM settings")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut values = Map::new();
+        for (key, value) in rows {
+            let value = serde_json::from_str(&value)
+                .map_err(|e| StoreError::Serialization(e.to_string()))?;
+            values.insert(key, value);
+        }
+
+        serde_json::from_value(Value::Object(values))
+            .map_err(|e| StoreError::Serialization(e.to_string()))
+    }
+
+    pub fn save(&mut self, settings: &AppSettings) -> Result<()> {
+        let values = match serde_json::to_value(settings) {
+            Ok(Value::Object(values)) => values,
+            Ok(_) => {
+                return Err(StoreError::Serialization(
+                    "Settings must be an object".to_string(),
+                ))
+            }
+            Err(e) => return Err(StoreError::Serialization(e.to_string())),
+        };
+
+        let tx = self.db.transaction()?;
+        for (key, value) in values {
+            tx.execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![key, value.to_string()],
+            )?;
+        }
+        tx.commit()?;
+
+        self.current = Some(settings.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::settings::Theme;
+    use tempfile::TempDir;
+
+    fn setup_test_store() -> (SettingsStore, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let store = SettingsStore::new(&temp_dir.path().join("test.db")).unwrap();
+        (store, temp_dir)
+    }
+
+    #[test]
+    fn test_load_defaults_when_empty() {
+        let (mut store, _temp) = setup_test_store();
+        assert_eq!(store.load().unwrap(), AppSettings::default());
+    }
+
+    #[test]
+    fn test_save_and_load_settings() {
+        let (mut store, _temp) = setup_test_store();
+        let settings = AppSettings {
+            theme: Theme::Dark,
+            history_retention_days: None,
+            ..AppSettings::default()
+        };
+        store.save(&settings).unwrap();
+        assert_eq!(store.load().unwrap(), settings);
+    }
+
+    #[test]
+    fn test_missing_keys_use_defaults() {
+        let (mut store, _temp) = setup_test_store();
+        store
+            .db
+            .execute(
+                "INSERT INTO settings (key, value) VALUES ('default_page_size', '500')",
+                [],
+            )
+            .unwrap();
+
+        let settings = store.load().unwrap();
+        assert_eq!(settings.default_page_size, 500);
+        assert_eq!(settings.editor, AppSettings::default().editor);
+    }
+
+    #[test]
+    fn test_reload_reads_the_file_again() {
+        let (mut store, _temp) = setup_test_store();
+        assert_eq!(store.load().unwrap().default_page_size, 100);
+        store
+            .db
+            .execute(
+                "INSERT INTO settings (key, value) VALUES ('default_page_size', '250')",
+                [],
+            )
+            .unwrap();
+
+        // Served from memory until reloaded
+        assert_eq!(store.load().unwrap().default_page_size, 100);
+        assert_eq!(store.reload().unwrap().default_page_size, 250);
+    }
+}
@@ -0,0 +1,378 @@
+use crate::models::{PinnedTable, RecentObject, RecentObjectKind, Workspace};
+use crate::storage::connection_store::{Result, StoreError};
+use crate::storage::migrations::migrate;
+use rusqlite::{params, Connection as SqliteConnection, OptionalExtension};
+use std::path::Path;
+
+/// Objects kept in a connection's recent list; older ones are dropped
+const RECENT_OBJECTS_LIMIT: usize = 100;
+
+/// Persists the open tabs of each connection, one row per connection and
+/// window, the tables pinned in its sidebar and the objects recently opened
+pub struct WorkspaceStore {
+    db: SqliteConnection,
+}
+
+impl WorkspaceStore {
+    pub fn new(db_path: &Path) -> Result<Self> {
+        let mut db = SqliteConnection::open(db_path)?;
+        migrate(&mut db)?;
+
+        Ok(Self { db })
+    }
+
+    /// Replace the stored workspace of `workspace.connection_id` in a window
+    pub fn save(&mut self, workspace: &Workspace, window: &str, saved_at: i64) -> Result<()> {
+        let json = serde_json::to_string(workspace)
+            .map_err(|e| StoreError::Serialization(e.to_string()))?;
+
+        self.db.execute(
+            "INSERT INTO workspaces (connection_id, window_label, workspace, saved_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(connection_id, window_label) DO UPDATE SET
+                workspace = excluded.workspace,
+                saved_at = excluded.saved_at",
+            params![workspace.connection_id, window, json, saved_at],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn load(&self, connection_id: &str, window: &str) -> Result<Option<Workspace>> {
+        let json: Option<String> = self
+            .db
+            .query_row(
+                "SELECT workspace FROM workspaces WHERE connection_id = ?1 AND window_label = ?2",
+                params![connection_id, window],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        json.map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| StoreError::Serialization(e.to_string()))
+    }
+
+    /// Remove the workspaces, pinned tables and recent objects of a
+    /// connection, in every window
+    pub fn delete(&mut self, connection_id: &str) -> Result<()> {
+        self.db.execute(
+            "DELETE FROM workspaces WHERE connection_id = ?1",
+            params![connection_id],
+        )?;
+        self.db.execute(
+            "DELETE FROM pinned_tables WHERE connection_id = ?1",
+            params![connection_id],
+        )?;
+        self.clear_recent_objects(connection_id)
+    }
+
+    /// Pin a table; pinning it again keeps its original position
+    pub fn pin_table(&mut self, table: &PinnedTable) -> Result<()> {
+        self.db.execute(
+            "INSERT OR IGNORE INTO pinned_tables (connection_id, database_name, table_name, pinned_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![table.connection_id, table.database, table.table, table.pinned_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn unpin_table(&mut self, connection_id: &str, database: &str, table: &str) -> Result<()> {
+        self.db.execute(
+            "DELETE FROM pinned_tables
+             WHERE connection_id = ?1 AND database_name = ?2 AND table_name = ?3",
+            params![connection_id, database, table],
+        )?;
+        Ok(())
+    }
+
+    /// Pinned tables of a connection, in the order they were pinned
+    pub fn list_pinned_tables(&self, connection_id: &str) -> Result<Vec<PinnedTable>> {
+        let mut stmt = self.db.prepare(
+            "SELECT connection_id, database_name, table_name, pinned_at FROM pinned_tables
+             WHERE connection_id = ?1
+             ORDER BY pinned_at, database_name, table_name",
+        )?;
+
+        let tables = stmt
+            .query_map(params![connection_id], |row| {
+                Ok(PinnedTable {
+                    connection_id: row.get(0)?,
+                    database: row.get(1)?,
+                    table: row.get(2)?,
+                    pinned_at: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(tables)
+    }
+
+    /// Note that an object was opened, putting it at the top of the recent
+    /// list. Only the latest `RECENT_OBJECTS_LIMIT` are kept.
+    pub fn record_recent_object(&mut self, object: &RecentObject) -> Result<()> {
+        let tx = self.db.transaction()?;
+        tx.execute(
+            "INSERT INTO recent_objects
+                (connection_id, kind, database_name, object_name, opened_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(connection_id, kind, database_name, object_name) DO UPDATE SET
+                opened_at = MAX(opened_at, excluded.opened_at),
+                open_count = open_count + 1",
+            params![
+                object.connection_id,
+                object.kind.to_string(),
+                object.database,
+                object.name.as_deref().unwrap_or_default(),
+                object.opened_at
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM recent_objects WHERE connection_id = ?1 AND rowid NOT IN (
+                SELECT rowid FROM recent_objects WHERE connection_id = ?1
+                ORDER BY opened_at DESC LIMIT ?2
+             )",
+            params![object.connection_id, RECENT_OBJECTS_LIMIT as i64],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The objects a connection opened most recently, latest first
+    pub fn list_recent_objects(
+        &self,
+        connection_id: &str,
+        limit: usize,
+    ) -> Result<Vec<RecentObject>> {
+        let mut stmt = self.db.prepare(
+            "SELECT kind, database_name, object_name, opened_at, open_count FROM recent_objects
+             WHERE connection_id = ?1
+             ORDER BY opened_at DESC, database_name, object_name
+             LIMIT ?2",
+        )?;
+
+        let rows = stmt
+            .query_map(params![connection_id, limit as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, u32>(4)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(kind, database, name, opened_at, open_count)| {
+                Ok(RecentObject {
+                    connection_id: connection_id.to_string(),
+                    kind: kind
+                        .parse::<RecentObjectKind>()
+                        .map_err(StoreError::Serialization)?,
+                    database,
+                    name: (!name.is_empty()).then_some(name),
+                    opened_at,
+                    open_count,
+                })
+            })
+            .collect()
+    }
+
+    pub fn clear_recent_objects(&mut self, connection_id: &str) -> Result<()> {
+        self.db.execute(
+            "DELETE FROM recent_objects WHERE connection_id = ?1",
+            params![connection_id],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::workspace::{TabKind, TableBrowserState, WorkspaceTab};
+    use tempfile::TempDir;
+
+    fn setup_test_store() -> (WorkspaceStore, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let store = WorkspaceStore::new(&temp_dir.path().join("test.db")).unwrap();
+        (store, temp_dir)
+    }
+
+    fn workspace(query: &str) -> Workspace {
+        Workspace {
+            connection_id: "conn".to_string(),
+            tabs: vec![
+                WorkspaceTab {
+                    id: "tab-1".to_string(),
+                    title: "Query".to_string(),
+                    kind: TabKind::Query,
+                    database: Some("shop".to_string()),
+                    query_text: Some(query.to_string()),
+                    table: None,
+                    table_state: None,
+                    scroll_top: 120.0,
+                },
+                WorkspaceTab {
+                    id: "tab-2".to_string(),
+                    title: "orders".to_string(),
+                    kind: TabKind::TableData,
+                    database: Some("shop".to_string()),
+                    query_text: None,
+                    table: Some("orders".to_string()),
+                    table_state: Some(TableBrowserState {
+                        page: 3,
+                        page_size: 100,
+                        sort_by: Some("id".to_string()),
+                        sort_order: None,
+                        filters: vec![],
+                    }),
+                    scroll_top: 0.0,
+                },
+            ],
+            active_tab_id: Some("tab-2".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_workspace() {
+        let (mut store, _temp) = setup_test_store();
+        assert!(store.load("conn", "main").unwrap().is_none());
+
+        store.save(&workspace("SELECT 1"), "main", 1).unwrap();
+        let loaded = store.load("conn", "main").unwrap().unwrap();
+
+        assert_eq!(loaded.tabs.len(), 2);
+        assert_eq!(loaded.tabs[0].query_text.as_deref(), Some("SELECT 1"));
+        assert_eq!(loaded.tabs[0].scroll_top, 120.0);
+        assert_eq!(loaded.tabs[1].kind, TabKind::TableData);
+        assert_eq!(loaded.tabs[1].table_state.as_ref().unwrap().page, 3);
+        assert_eq!(loaded.active_tab_id.as_deref(), Some("tab-2"));
+    }
+
+    #[test]
+    fn test_save_replaces_and_delete_removes() {
+        let (mut store, _temp) = setup_test_store();
+        store.save(&workspace("SELECT 1"), "main", 1).unwrap();
+        store.save(&workspace("SELECT 2"), "main", 2).unwrap();
+
+        let loaded = store.load("conn", "main").unwrap().unwrap();
+        assert_eq!(loaded.tabs[0].query_text.as_deref(), Some("SELECT 2"));
+
+        store.delete("conn").unwrap();
+        assert!(store.load("conn", "main").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_workspaces_are_per_window() {
+        let (mut store, _temp) = setup_test_store();
+        store.save(&workspace("SELECT 1"), "main", 1).unwrap();
+        store
+            .save(&workspace("SELECT 2"), "connection-conn", 2)
+            .unwrap();
+
+        let main = store.load("conn", "main").unwrap().unwrap();
+        assert_eq!(main.tabs[0].query_text.as_deref(), Some("SELECT 1"));
+        let other = store.load("conn", "connection-conn").unwrap().unwrap();
+        assert_eq!(other.tabs[0].query_text.as_deref(), Some("SELECT 2"));
+        assert!(store.load("conn", "window-2").unwrap().is_none());
+
+        store.delete("conn").unwrap();
+        assert!(store.load("conn", "connection-conn").unwrap().is_none());
+    }
+
+    fn pinned(database: &str, table: &str, pinned_at: i64) -> PinnedTable {
+        PinnedTable {
+            connection_id: "conn".to_string(),
+            database: database.to_string(),
+            table: table.to_string(),
+            pinned_at,
+        }
+    }
+
+    #[test]
+    fn test_pin_and_unpin_tables() {
+        let (mut store, _temp) = setup_test_store();
+        store.pin_table(&pinned("shop", "orders", 2)).unwrap();
+        store.pin_table(&pinned("billing", "invoices", 1)).unwrap();
+        // Pinning again doesn't move the table
+        store.pin_table(&pinned("shop", "orders", 3)).unwrap();
+
+        let tables = store.list_pinned_tables("conn").unwrap();
+        assert_eq!(
+            tables,
+            vec![
+                pinned("billing", "invoices", 1),
+                pinned("shop", "orders", 2)
+            ]
+        );
+        assert!(store.list_pinned_tables("other").unwrap().is_empty());
+
+        store.unpin_table("conn", "billing", "invoices").unwrap();
+        assert_eq!(
+            store.list_pinned_tables("conn").unwrap(),
+            vec![pinned("shop", "orders", 2)]
+        );
+
+        store.delete("conn").unwrap();
+        assert!(store.list_pinned_tables("conn").unwrap().is_empty());
+    }
+
+    fn opened(kind: RecentObjectKind, name: Option<&str>, opened_at: i64) -> RecentObject {
+        RecentObject {
+            connection_id: "conn".to_string(),
+            kind,
+            database: "shop".to_string(),
+            name: name.map(str::to_string),
+            opened_at,
+            open_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_recent_objects() {
+        let (mut store, _temp) = setup_test_store();
+        store
+            .record_recent_object(&opened(RecentObjectKind::Table, Some("orders"), 1))
+            .unwrap();
+        store
+            .record_recent_object(&opened(RecentObjectKind::Database, None, 2))
+            .unwrap();
+        store
+            .record_recent_object(&opened(RecentObjectKind::View, Some("sales"), 3))
+            .unwrap();
+        // Opening it again moves it to the top and counts the visit
+        store
+            .record_recent_object(&opened(RecentObjectKind::Table, Some("orders"), 4))
+            .unwrap();
+
+        let recent = store.list_recent_objects("conn", 10).unwrap();
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[0].name.as_deref(), Some("orders"));
+        assert_eq!(recent[0].open_count, 2);
+        assert_eq!(recent[0].opened_at, 4);
+        assert_eq!(recent[2], opened(RecentObjectKind::Database, None, 2));
+        assert_eq!(store.list_recent_objects("conn", 1).unwrap().len(), 1);
+        assert!(store.list_recent_objects("other", 10).unwrap().is_empty());
+
+        store.clear_recent_objects("conn").unwrap();
+        assert!(store.list_recent_objects("conn", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recent_objects_are_capped() {
+        let (mut store, _temp) = setup_test_store();
+        for i in 0..RECENT_OBJECTS_LIMIT as i64 + 5 {
+            let name = format!("t{}", i);
+            store
+                .record_recent_object(&opened(RecentObjectKind::Table, Some(&name), i))
+                .unwrap();
+        }
+
+        let recent = store.list_recent_objects("conn", 1000).unwrap();
+        assert_eq!(recent.len(), RECENT_OBJECTS_LIMIT);
+        assert_eq!(recent[0].name.as_deref(), Some("t104"));
+        assert_eq!(recent.last().unwrap().name.as_deref(), Some("t5"));
+    }
+}
@@ -1,6 +1,7 @@
 import { useState } from 'react';
 import type { Connection, DatabaseType } from '../../types/connection';
 import { useConnectionStore } from '../../store/connectionStore';
+import { errorMessage } from '../../services/errors';
 
 interface ConnectionFormProps {
   onClose: () => void;
@@ -71,7 +72,7 @@ export default function ConnectionForm({ onClose, existingConnection }: Connecti
       await saveConnection(formData as Connection);
       onClose();
     } catch (error) {
-      setErrors({ submit: String(error) });
+      setErrors({ submit: errorMessage(error) });
     }
   }
 
@@ -85,7 +86,7 @@ export default function ConnectionForm({ onClose, existingConnection }: Connecti
       const message = await testConnection(formData as Connection);
       setTestResult({ success: true, message });
     } catch (error) {
-      setTestResult({ success: false, message: String(error) });
+      setTestResult({ success: false, message: errorMessage(error) });
     } finally {
       setTesting(false);
     }
@@ -1,4 +1,4 @@
-import { describe, it, expect } from 'vitest';
+import { describe, it, expect, vi } from 'vitest';
 import { createSQLCompletionProvider } from './AutocompleteProvider';
 import type { AutocompleteData } from '../../types/schema';
 
@@ -170,3 +170,49 @@ describe('AutocompleteProvider - Backspace Support', () => {
     expect(result?.suggestions.map(s => s.label)).toContain('id');
   });
 });
+
+describe('AutocompleteProvider - Cross-database references', () => {
+  const billingData: AutocompleteData = {
+    tables: ['invoices'],
+    columns_by_table: { invoices: ['id', 'total'] },
+    keywords: [],
+  };
+  const dataWithDatabases: AutocompleteData = {
+    ...mockAutocompleteData,
+    databases: ['app', 'billing'],
+  };
+
+  it('lazily loads tables of another database after "db."', async () => {
+    const loader = vi.fn().mockResolvedValue(billingData);
+    const provider = createSQLCompletionProvider(dataWithDatabases, loader);
+    const model = createMockModel('SELECT * FROM billing.', 22);
+
+    const result = await provider.provideCompletionItems(model as any, createMockPosition(22) as any, {} as any, {} as any);
+
+    expect(loader).toHaveBeenCalledWith('billing');
+    expect(result?.suggestions.map(s => s.label)).toEqual(['invoices']);
+  });
+
+  it('suggests columns for "db.table." and loads each database once', async () => {
+    const loader = vi.fn().mockResolvedValue(billingData);
+    const provider = createSQLCompletionProvider(dataWithDatabases, loader);
+    const model = createMockModel('SELECT billing.invoices.', 24);
+
+    await provider.provideCompletionItems(model as any, createMockPosition(24) as any, {} as any, {} as any);
+    const result = await provider.provideCompletionItems(model as any, createMockPosition(24) as any, {} as any, {} as any);
+
+    expect(loader).toHaveBeenCalledTimes(1);
+    expect(result?.suggestions.map(s => s.label)).toContain('total');
+  });
+
+  it('keeps table.column completion for tables of the active database', () => {
+    const loader = vi.fn();
+    const provider = createSQLCompletionProvider(dataWithDatabases, loader);
+    const model = createMockModel('SELECT users.', 13);
+
+    const result = provider.provideCompletionItems(model as any, createMockPosition(13) as any, {} as any, {} as any);
+
+    expect(loader).not.toHaveBeenCalled();
+    expect((result as any)?.suggestions.map((s: any) => s.label)).toContain('email');
+  });
+});
@@ -1,9 +1,29 @@
 import * as monaco from 'monaco-editor';
 import type { AutocompleteData } from '../../types/schema';
 
+/** Loads completion data for another database of the same connection */
+export type DatabaseLoader = (database: string) => Promise<AutocompleteData>;
+
 export function createSQLCompletionProvider(
-  autocompleteData: AutocompleteData
+  autocompleteData: AutocompleteData,
+  loadDatabase?: DatabaseLoader
 ): monaco.languages.CompletionItemProvider {
+  // Other databases are fetched on first reference and reused afterwards
+  const loadedDatabases = new Map<string, Promise<AutocompleteData>>();
+  const databases = autocompleteData.databases ?? [];
+
+  const loadOtherDatabase = (database: string): Promise<AutocompleteData> | null => {
+    if (!loadDatabase || !databases.includes(database)) return null;
+    let pending = loadedDatabases.get(database);
+    if (!pending) {
+      pending = loadDatabase(database);
+      // Retry on the next reference if loading failed
+      pending.catch(() => loadedDatabases.delete(database));
+      loadedDatabases.set(database, pending);
+    }
+    return pending;
+  };
+
   return {
     triggerCharacters: ['.', ' '],
 
@@ -23,10 +43,45 @@ export function createSQLCompletionProvider(
         endColumn: word.endColumn,
       };
 
+      // "db.table." - columns of a table in another database
+      const qualifiedMatch = textUntilPosition.match(/(\w+)\.(\w+)\.(\w*)$/);
+      if (qualifiedMatch) {
+        const [, database, tableName] = qualifiedMatch;
+        const pending = loadOtherDatabase(database);
+        if (pending) {
+          return pending.then((data) => ({
+            suggestions: (data.columns_by_table[tableName] || []).map((col) => ({
+              label: col,
+              kind: monaco.languages.CompletionItemKind.Field,
+              insertText: col,
+              range,
+              detail: `Column from ${database}.${tableName}`,
+            })),
+          }));
+        }
+      }
+
       // Check if we're after a table name with a dot (for column suggestions)
       const dotMatch = textUntilPosition.match(/(\w+)\.(\w*)$/);
       if (dotMatch) {
         const tableName = dotMatch[1];
+
+        // "db." - tables of another database, unless a table has the same name
+        const pending = autocompleteData.columns_by_table[tableName]
+          ? null
+          : loadOtherDatabase(tableName);
+        if (pending) {
+          return pending.then((data) => ({
+            suggestions: data.tables.map((table) => ({
+              label: table,
+              kind: monaco.languages.CompletionItemKind.Class,
+              insertText: table,
+              range,
+              detail: `Table in ${tableName}`,
+            })),
+          }));
+        }
+
         const columns = autocompleteData.columns_by_table[tableName] || [];
 
         return {
@@ -71,6 +126,18 @@ export function createSQLCompletionProvider(
         });
       });
 
+      // Add built-in functions
+      (autocompleteData.functions ?? []).forEach((fn) => {
+        suggestions.push({
+          label: fn.name,
+          kind: monaco.languages.CompletionItemKind.Function,
+          insertText: fn.name,
+          range,
+          detail: fn.signature,
+          documentation: fn.description,
+        });
+      });
+
       // Add tables
       autocompleteData.tables.forEach((table) => {
         suggestions.push({
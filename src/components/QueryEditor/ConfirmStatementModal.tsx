@@ -0,0 +1,51 @@
+import type { PendingConfirmation } from '../../store/queryStore';
+
+interface ConfirmStatementModalProps {
+  pending: PendingConfirmation;
+  onConfirm: () => void;
+  onCancel: () => void;
+}
+
+export default function ConfirmStatementModal({
+  pending,
+  onConfirm,
+  onCancel,
+}: ConfirmStatementModalProps) {
+  return (
+    <div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-[60] p-4">
+      <div className="bg-white rounded-lg shadow-xl max-w-lg w-full">
+        {/* Header */}
+        <div className="px-6 py-4 border-b border-gray-200 bg-red-50">
+          <h3 className="text-lg font-bold text-gray-900">Run Destructive Statement?</h3>
+        </div>
+
+        {/* Content */}
+        <div className="p-6">
+          <p className="text-sm text-gray-700">{pending.message}.</p>
+          <p className="text-sm text-gray-600 mt-2">This action cannot be undone.</p>
+          <pre className="mt-4 bg-gray-50 border border-gray-200 rounded-md p-3 max-h-40 overflow-auto text-xs text-gray-700 font-mono whitespace-pre-wrap">
+            {pending.sql}
+          </pre>
+        </div>
+
+        {/* Footer */}
+        <div className="px-6 py-4 border-t border-gray-200 bg-gray-50 flex justify-end gap-3">
+          <button
+            type="button"
+            onClick={onCancel}
+            className="px-4 py-2 border border-gray-300 rounded-md hover:bg-gray-50 text-gray-700"
+          >
+            Cancel
+          </button>
+          <button
+            type="button"
+            onClick={onConfirm}
+            className="px-4 py-2 bg-red-600 hover:bg-red-700 text-white rounded-md"
+          >
+            Run Anyway
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
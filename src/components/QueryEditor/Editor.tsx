@@ -2,6 +2,7 @@ import { useEffect, useRef } from 'react';
 import * as monaco from 'monaco-editor';
 import { createSQLCompletionProvider } from './AutocompleteProvider';
 import { useQueryStore } from '../../store/queryStore';
+import { schemaApi } from '../../services/tauriApi';
 
 interface EditorProps {
   value: string;
@@ -12,7 +13,7 @@ interface EditorProps {
 export default function Editor({ value, onChange, onExecute }: EditorProps): JSX.Element {
   const editorRef = useRef<HTMLDivElement>(null);
   const monacoEditorRef = useRef<monaco.editor.IStandaloneCodeEditor | null>(null);
-  const { autocompleteData } = useQueryStore();
+  const { autocompleteData, autocompleteConnectionId } = useQueryStore();
 
   // Initialize Monaco Editor
   useEffect(() => {
@@ -59,15 +60,19 @@ export default function Editor({ value, onChange, onExecute }: EditorProps): JSX
   useEffect(() => {
     if (!autocompleteData) return;
 
+    const loadDatabase = autocompleteConnectionId
+      ? (database: string) => schemaApi.getAutocompleteData(autocompleteConnectionId, database)
+      : undefined;
+
     const disposable = monaco.languages.registerCompletionItemProvider(
       'sql',
-      createSQLCompletionProvider(autocompleteData)
+      createSQLCompletionProvider(autocompleteData, loadDatabase)
     );
 
     return () => {
       disposable.dispose();
     };
-  }, [autocompleteData]);
+  }, [autocompleteData, autocompleteConnectionId]);
 
   // Handle Tab key for accepting suggestions
   useEffect(() => {
@@ -1,13 +1,31 @@
-import { useState, useCallback } from 'react';
+import { useState, useCallback, useEffect } from 'react';
 import { Panel, PanelGroup, PanelResizeHandle } from 'react-resizable-panels';
 import Editor from './Editor';
+import ConfirmStatementModal from './ConfirmStatementModal';
 import { useQueryStore } from '../../store/queryStore';
 import { useConnectionStore } from '../../store/connectionStore';
+import { formatCellValue } from '../../services/cellValue';
 
 export default function QueryEditor(): JSX.Element {
   const [sql, setSql] = useState('SELECT * FROM ');
   const { activeConnectionId, selectedDatabase } = useConnectionStore();
-  const { currentResult, isExecuting, error, executeQuery, cancelQuery, clearError } = useQueryStore();
+  const {
+    currentResult,
+    isExecuting,
+    error,
+    pendingConfirmation,
+    executeQuery,
+    cancelQuery,
+    clearError,
+    confirmPending,
+    cancelPending,
+  } = useQueryStore();
+
+  // Scripts and procedure calls can return several result sets
+  const [activeSet, setActiveSet] = useState(0);
+  useEffect(() => setActiveSet(0), [currentResult]);
+  const resultSets = currentResult ? [currentResult, ...(currentResult.more_results ?? [])] : [];
+  const shownSet = resultSets[Math.min(activeSet, resultSets.length - 1)];
 
   const handleExecute = useCallback(async () => {
     if (!activeConnectionId) {
@@ -28,8 +46,22 @@ export default function QueryEditor(): JSX.Element {
     await executeQuery(activeConnectionId, sql, selectedDatabase);
   }, [activeConnectionId, selectedDatabase, sql, executeQuery]);
 
+  // Run again, bypassing the cached result being shown
+  const handleRefresh = useCallback(async () => {
+    if (activeConnectionId && selectedDatabase && sql.trim()) {
+      await executeQuery(activeConnectionId, sql, selectedDatabase, undefined, undefined, true);
+    }
+  }, [activeConnectionId, selectedDatabase, sql, executeQuery]);
+
   return (
     <div className="h-full">
+      {pendingConfirmation && (
+        <ConfirmStatementModal
+          pending={pendingConfirmation}
+          onConfirm={confirmPending}
+          onCancel={cancelPending}
+        />
+      )}
       <PanelGroup direction="vertical">
         {/* Editor Section */}
         <Panel defaultSize={40} minSize={20} maxSize={80}>
@@ -108,17 +140,75 @@ export default function QueryEditor(): JSX.Element {
               <div className="text-sm text-gray-600">
                 {currentResult.total_rows} {currentResult.total_rows === 1 ? 'row' : 'rows'} in{' '}
                 {currentResult.execution_time_ms}ms
+                {currentResult.cached && (
+                  <span className="ml-2 text-gray-500">
+                    (cached
+                    {currentResult.cached_at &&
+                      ` ${new Date(currentResult.cached_at).toLocaleTimeString()}`}
+                    )
+                    <button
+                      onClick={handleRefresh}
+                      disabled={isExecuting}
+                      className="ml-2 text-blue-600 hover:text-blue-800 disabled:opacity-50"
+                    >
+                      Refresh
+                    </button>
+                  </span>
+                )}
+                {currentResult.auto_limit != null && (
+                  <span className="ml-2 text-amber-600">
+                    (limited to {currentResult.auto_limit} rows)
+                  </span>
+                )}
+                {currentResult.unbounded && (
+                  <span className="ml-2 text-amber-600">(no LIMIT)</span>
+                )}
               </div>
             </div>
 
-            {currentResult.total_rows === 0 ? (
+            {currentResult.warnings && currentResult.warnings.length > 0 && (
+              <ul className="mb-3 p-3 bg-amber-50 border border-amber-200 rounded-md text-sm text-amber-800">
+                {currentResult.warnings.map((warning, index) => (
+                  <li key={index}>
+                    {warning.level} {warning.code}: {warning.message}
+                  </li>
+                ))}
+              </ul>
+            )}
+
+            {resultSets.length > 1 && (
+              <div className="flex gap-1 mb-3 border-b border-gray-200">
+                {resultSets.map((set, index) => (
+                  <button
+                    key={index}
+                    onClick={() => setActiveSet(index)}
+                    className={`px-3 py-1 text-sm ${
+                      set === shownSet
+                        ? 'border-b-2 border-blue-600 text-blue-600'
+                        : 'text-gray-600 hover:text-gray-800'
+                    }`}
+                  >
+                    Result {index + 1}
+                  </button>
+                ))}
+              </div>
+            )}
+
+            {currentResult.dml && shownSet === currentResult ? (
+              <div className="text-center text-gray-500 py-8">
+                {currentResult.rows_affected ?? 0}{' '}
+                {currentResult.rows_affected === 1 ? 'row' : 'rows'} affected
+                {currentResult.last_insert_id != null &&
+                  `, last insert id ${currentResult.last_insert_id}`}
+              </div>
+            ) : shownSet.total_rows === 0 ? (
               <div className="text-center text-gray-500 py-8">No results</div>
             ) : (
               <div className="overflow-x-auto border border-gray-300 rounded-md">
                 <table className="min-w-full divide-y divide-gray-200">
                   <thead className="bg-gray-50">
                     <tr>
-                      {currentResult.columns.map((col, index) => (
+                      {shownSet.columns.map((col, index) => (
                         <th
                           key={index}
                           className="px-4 py-3 text-left text-xs font-medium text-gray-700 uppercase tracking-wider"
@@ -129,7 +219,7 @@ export default function QueryEditor(): JSX.Element {
                     </tr>
                   </thead>
                   <tbody className="bg-white divide-y divide-gray-200">
-                    {currentResult.rows.map((row, rowIndex) => (
+                    {shownSet.rows.map((row, rowIndex) => (
                       <tr key={rowIndex} className="hover:bg-gray-50">
                         {row.map((cell, cellIndex) => (
                           <td
@@ -138,10 +228,8 @@ export default function QueryEditor(): JSX.Element {
                           >
                             {cell === null ? (
                               <span className="text-gray-400 italic">NULL</span>
-                            ) : typeof cell === 'object' ? (
-                              JSON.stringify(cell)
                             ) : (
-                              String(cell)
+                              formatCellValue(cell)
                             )}
                           </td>
                         ))}
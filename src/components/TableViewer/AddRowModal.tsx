@@ -1,5 +1,15 @@
 import { useState } from 'react';
 import { tableApi } from '../../services/tauriApi';
+import { errorMessage } from '../../services/errors';
+import {
+  FIELD_MODES,
+  FIELD_MODE_LABELS,
+  toCellValue,
+  type FieldMode,
+} from '../../services/cellValue';
+import type { CellValue } from '../../types/table';
+import { useColumnInfo } from '../../hooks/useColumnInfo';
+import FieldInput from './FieldInput';
 
 interface AddRowModalProps {
   connectionId: string;
@@ -19,9 +29,12 @@ export default function AddRowModal({
   onSuccess,
 }: AddRowModalProps) {
   const [values, setValues] = useState<Record<string, string>>({});
-  const [nullColumns, setNullColumns] = useState<Set<string>>(new Set());
+  const [modes, setModes] = useState<Record<string, FieldMode>>({});
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const columnInfo = useColumnInfo(connectionId, database, table);
+
+  const isGenerated = (column: string) => columnInfo[column]?.is_generated === true;
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
@@ -29,21 +42,15 @@ export default function AddRowModal({
     setError(null);
 
     try {
-      const data: Record<string, any> = {};
+      const data: Record<string, CellValue> = {};
       for (const column of columns) {
-        if (nullColumns.has(column)) {
-          data[column] = null;
-        } else if (values[column] !== undefined && values[column] !== '') {
-          // Try to parse as number if it looks like one
-          const value = values[column];
-          if (/^-?\d+$/.test(value)) {
-            data[column] = parseInt(value, 10);
-          } else if (/^-?\d+\.\d+$/.test(value)) {
-            data[column] = parseFloat(value);
-          } else {
-            data[column] = value;
-          }
+        const mode = modes[column] ?? 'Value';
+        // Empty fields are left out so the server fills in the default
+        if (isGenerated(column) || (mode === 'Value' && !values[column])) {
+          continue;
         }
+        const format = columnInfo[column]?.allowed_values ? 'text' : 'auto';
+        data[column] = toCellValue(mode, values[column] ?? '', format);
       }
 
       await tableApi.insertRow({
@@ -56,7 +63,7 @@ export default function AddRowModal({
       onSuccess();
       onClose();
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to insert row');
+      setError(errorMessage(err, 'Failed to insert row'));
     } finally {
       setLoading(false);
     }
@@ -64,28 +71,15 @@ export default function AddRowModal({
 
   const handleValueChange = (column: string, value: string) => {
     setValues({ ...values, [column]: value });
-    // Remove from null set if value is entered
-    if (value) {
-      const newNullColumns = new Set(nullColumns);
-      newNullColumns.delete(column);
-      setNullColumns(newNullColumns);
-    }
   };
 
-  const toggleNull = (column: string) => {
-    const newNullColumns = new Set(nullColumns);
-    if (nullColumns.has(column)) {
-      newNullColumns.delete(column);
-    } else {
-      newNullColumns.add(column);
-      // Clear value when setting to NULL
-      const newValues = { ...values };
-      delete newValues[column];
-      setValues(newValues);
-    }
-    setNullColumns(newNullColumns);
+  const setMode = (column: string, mode: FieldMode) => {
+    setModes({ ...modes, [column]: mode });
   };
 
+  const hasText = (column: string) =>
+    (modes[column] ?? 'Value') === 'Value' || modes[column] === 'Expression';
+
   return (
     <div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-[60] p-4">
       <div className="bg-white rounded-lg shadow-xl max-w-2xl w-full max-h-[90vh] overflow-hidden flex flex-col">
@@ -117,26 +111,43 @@ export default function AddRowModal({
                 <div className="flex-1">
                   <label className="block text-sm font-medium text-gray-700 mb-1">
                     {column}
+                    {isGenerated(column) && (
+                      <span className="ml-2 text-xs text-gray-400">generated</span>
+                    )}
+                    {columnInfo[column]?.is_auto_increment && (
+                      <span className="ml-2 text-xs text-gray-400">auto increment</span>
+                    )}
                   </label>
-                  <input
-                    type="text"
+                  <FieldInput
+                    column={column}
                     value={values[column] || ''}
-                    onChange={(e) => handleValueChange(column, e.target.value)}
-                    disabled={nullColumns.has(column)}
+                    onChange={(value) => handleValueChange(column, value)}
+                    disabled={isGenerated(column) || !hasText(column)}
                     className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-green-500 disabled:bg-gray-100 disabled:text-gray-500"
-                    placeholder="Enter value..."
+                    placeholder={
+                      modes[column] === 'Expression'
+                        ? 'SQL expression, e.g. NOW()'
+                        : columnInfo[column]?.is_auto_increment
+                          ? 'Leave empty to generate'
+                          : 'Enter value...'
+                    }
+                    schema={modes[column] === 'Expression' ? undefined : columnInfo[column]}
                   />
                 </div>
-                <div className="pt-7">
-                  <label className="flex items-center gap-2 cursor-pointer">
-                    <input
-                      type="checkbox"
-                      checked={nullColumns.has(column)}
-                      onChange={() => toggleNull(column)}
-                      className="rounded border-gray-300"
-                    />
-                    <span className="text-sm text-gray-600">NULL</span>
-                  </label>
+                <div className="pt-6">
+                  <select
+                    value={modes[column] ?? 'Value'}
+                    onChange={(e) => setMode(column, e.target.value as FieldMode)}
+                    disabled={isGenerated(column)}
+                    className="px-2 py-2 border border-gray-300 rounded-md text-sm text-gray-600"
+                    aria-label={`How ${column} is written`}
+                  >
+                    {FIELD_MODES.map((mode) => (
+                      <option key={mode} value={mode}>
+                        {FIELD_MODE_LABELS[mode]}
+                      </option>
+                    ))}
+                  </select>
                 </div>
               </div>
             ))}
@@ -1,5 +1,6 @@
 import { useState } from 'react';
 import { tableApi } from '../../services/tauriApi';
+import { errorMessage } from '../../services/errors';
 
 interface DeleteConfirmModalProps {
   connectionId: string;
@@ -54,7 +55,7 @@ export default function DeleteConfirmModal({
       onSuccess();
       onClose();
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to delete rows');
+      setError(errorMessage(err, 'Failed to delete rows'));
     } finally {
       setLoading(false);
     }
@@ -1,5 +1,16 @@
 import { useState, useEffect } from 'react';
 import { tableApi } from '../../services/tauriApi';
+import { errorMessage } from '../../services/errors';
+import {
+  FIELD_MODES,
+  FIELD_MODE_LABELS,
+  isJsonDocument,
+  toCellValue,
+  type FieldMode,
+} from '../../services/cellValue';
+import type { CellValue } from '../../types/table';
+import { useColumnInfo } from '../../hooks/useColumnInfo';
+import FieldInput from './FieldInput';
 
 interface EditRowModalProps {
   connectionId: string;
@@ -21,25 +32,28 @@ export default function EditRowModal({
   onSuccess,
 }: EditRowModalProps) {
   const [values, setValues] = useState<Record<string, string>>({});
-  const [nullColumns, setNullColumns] = useState<Set<string>>(new Set());
+  const [modes, setModes] = useState<Record<string, FieldMode>>({});
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const columnInfo = useColumnInfo(connectionId, database, table);
 
   useEffect(() => {
     // Initialize form with existing row data
     const initialValues: Record<string, string> = {};
-    const initialNulls = new Set<string>();
+    const initialModes: Record<string, FieldMode> = {};
 
     for (const column of columns) {
       if (row[column] === null) {
-        initialNulls.add(column);
+        initialModes[column] = 'Null';
+      } else if (isJsonDocument(row[column])) {
+        initialValues[column] = JSON.stringify(row[column]);
       } else {
         initialValues[column] = String(row[column]);
       }
     }
 
     setValues(initialValues);
-    setNullColumns(initialNulls);
+    setModes(initialModes);
   }, [row, columns]);
 
   const handleSubmit = async (e: React.FormEvent) => {
@@ -48,20 +62,21 @@ export default function EditRowModal({
     setError(null);
 
     try {
-      const data: Record<string, any> = {};
+      const data: Record<string, CellValue> = {};
       for (const column of columns) {
-        if (nullColumns.has(column)) {
-          data[column] = null;
-        } else if (values[column] !== undefined) {
-          // Try to parse as number if it looks like one
-          const value = values[column];
-          if (/^-?\d+$/.test(value)) {
-            data[column] = parseInt(value, 10);
-          } else if (/^-?\d+\.\d+$/.test(value)) {
-            data[column] = parseFloat(value);
-          } else {
-            data[column] = value;
-          }
+        const mode = modes[column] ?? 'Value';
+        // JSON columns are sent as structured JSON so the backend casts them
+        // to a JSON document
+        const format = isJsonDocument(row[column])
+          ? 'json'
+          : columnInfo[column]?.allowed_values
+            ? 'text'
+            : 'auto';
+        try {
+          data[column] = toCellValue(mode, values[column] ?? '', format);
+        } catch {
+          setError(`${column} is not valid JSON`);
+          return;
         }
       }
 
@@ -87,7 +102,7 @@ export default function EditRowModal({
       onSuccess();
       onClose();
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to update row');
+      setError(errorMessage(err, 'Failed to update row'));
     } finally {
       setLoading(false);
     }
@@ -95,28 +110,15 @@ export default function EditRowModal({
 
   const handleValueChange = (column: string, value: string) => {
     setValues({ ...values, [column]: value });
-    // Remove from null set if value is entered
-    if (value) {
-      const newNullColumns = new Set(nullColumns);
-      newNullColumns.delete(column);
-      setNullColumns(newNullColumns);
-    }
   };
 
-  const toggleNull = (column: string) => {
-    const newNullColumns = new Set(nullColumns);
-    if (nullColumns.has(column)) {
-      newNullColumns.delete(column);
-    } else {
-      newNullColumns.add(column);
-      // Clear value when setting to NULL
-      const newValues = { ...values };
-      delete newValues[column];
-      setValues(newValues);
-    }
-    setNullColumns(newNullColumns);
+  const setMode = (column: string, mode: FieldMode) => {
+    setModes({ ...modes, [column]: mode });
   };
 
+  const hasText = (column: string) =>
+    (modes[column] ?? 'Value') === 'Value' || modes[column] === 'Expression';
+
   return (
     <div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-[60] p-4">
       <div className="bg-white rounded-lg shadow-xl max-w-2xl w-full max-h-[90vh] overflow-hidden flex flex-col">
@@ -149,25 +151,29 @@ export default function EditRowModal({
                   <label className="block text-sm font-medium text-gray-700 mb-1">
                     {column}
                   </label>
-                  <input
-                    type="text"
+                  <FieldInput
+                    column={column}
                     value={values[column] || ''}
-                    onChange={(e) => handleValueChange(column, e.target.value)}
-                    disabled={nullColumns.has(column)}
+                    onChange={(value) => handleValueChange(column, value)}
+                    disabled={!hasText(column)}
                     className="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 disabled:bg-gray-100 disabled:text-gray-500"
-                    placeholder="Enter value..."
+                    placeholder={modes[column] === 'Expression' ? 'SQL expression, e.g. NOW()' : 'Enter value...'}
+                    schema={modes[column] === 'Expression' ? undefined : columnInfo[column]}
                   />
                 </div>
-                <div className="pt-7">
-                  <label className="flex items-center gap-2 cursor-pointer">
-                    <input
-                      type="checkbox"
-                      checked={nullColumns.has(column)}
-                      onChange={() => toggleNull(column)}
-                      className="rounded border-gray-300"
-                    />
-                    <span className="text-sm text-gray-600">NULL</span>
-                  </label>
+                <div className="pt-6">
+                  <select
+                    value={modes[column] ?? 'Value'}
+                    onChange={(e) => setMode(column, e.target.value as FieldMode)}
+                    className="px-2 py-2 border border-gray-300 rounded-md text-sm text-gray-600"
+                    aria-label={`How ${column} is written`}
+                  >
+                    {FIELD_MODES.map((mode) => (
+                      <option key={mode} value={mode}>
+                        {FIELD_MODE_LABELS[mode]}
+                      </option>
+                    ))}
+                  </select>
                 </div>
               </div>
             ))}
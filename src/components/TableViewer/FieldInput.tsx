@@ -0,0 +1,78 @@
+import type { ColumnSchema } from '../../types/schema';
+
+interface FieldInputProps {
+  column: string;
+  value: string;
+  onChange: (value: string) => void;
+  disabled: boolean;
+  placeholder: string;
+  className: string;
+  schema?: ColumnSchema;
+}
+
+/**
+ * Text field for a row editor; ENUM columns get a dropdown of their values
+ * and SET columns a multi-select whose choices are joined with commas.
+ */
+export default function FieldInput({
+  column,
+  value,
+  onChange,
+  disabled,
+  placeholder,
+  className,
+  schema,
+}: FieldInputProps) {
+  const allowed = schema?.allowed_values;
+
+  if (allowed && !disabled) {
+    if (schema?.data_type.toLowerCase() === 'set') {
+      return (
+        <select
+          multiple
+          value={value.split(',').filter(Boolean)}
+          onChange={(e) =>
+            onChange(Array.from(e.target.selectedOptions, (option) => option.value).join(','))
+          }
+          className={className}
+          aria-label={column}
+        >
+          {allowed.map((option) => (
+            <option key={option} value={option}>
+              {option}
+            </option>
+          ))}
+        </select>
+      );
+    }
+
+    return (
+      <select
+        value={value}
+        onChange={(e) => onChange(e.target.value)}
+        className={className}
+        aria-label={column}
+      >
+        {!allowed.includes(value) && (
+          <option value={value}>{value || 'Choose a value...'}</option>
+        )}
+        {allowed.map((option) => (
+          <option key={option} value={option}>
+            {option}
+          </option>
+        ))}
+      </select>
+    );
+  }
+
+  return (
+    <input
+      type="text"
+      value={value}
+      onChange={(e) => onChange(e.target.value)}
+      disabled={disabled}
+      className={className}
+      placeholder={placeholder}
+    />
+  );
+}
@@ -2,10 +2,12 @@ import { useEffect, useState, useCallback } from 'react';
 import { tableApi } from '../../services/tauriApi';
 import { useTabStore } from '../../store/tabStore';
 import type { TableData, TableFilter } from '../../types/table';
-import { SortOrder } from '../../types/table';
+import { RowCountMode, SortOrder } from '../../types/table';
 import AddRowModal from './AddRowModal';
 import EditRowModal from './EditRowModal';
 import DeleteConfirmModal from './DeleteConfirmModal';
+import { errorMessage } from '../../services/errors';
+import { formatCellValue } from '../../services/cellValue';
 
 interface TableDataViewerProps {
   connectionId: string;
@@ -28,6 +30,7 @@ export default function TableDataViewer({
   const [sortBy, setSortBy] = useState<string | undefined>(undefined);
   const [sortOrder, setSortOrder] = useState<SortOrder | undefined>(undefined);
   const [filters] = useState<TableFilter[]>([]); // TODO: Add filter UI later
+  const [rowCount, setRowCount] = useState<RowCountMode>(RowCountMode.Auto);
 
   const [selectedRows, setSelectedRows] = useState<Set<number>>(new Set());
   const [showAddModal, setShowAddModal] = useState(false);
@@ -47,20 +50,42 @@ export default function TableDataViewer({
         filters: filters.length > 0 ? filters : undefined,
         sort_by: sortBy,
         sort_order: sortOrder,
+        row_count: rowCount,
       });
       setData(result);
       setSelectedRows(new Set());
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to load table data');
+      setError(errorMessage(err, 'Failed to load table data'));
     } finally {
       setLoading(false);
     }
-  }, [connectionId, database, table, page, pageSize, filters, sortBy, sortOrder]);
+  }, [connectionId, database, table, page, pageSize, filters, sortBy, sortOrder, rowCount]);
 
   useEffect(() => {
     loadTableData();
   }, [loadTableData]);
 
+  // The exact count arrives after the page; ignore counts of older pages
+  const requestId = data?.request_id;
+  useEffect(() => {
+    if (!requestId) return;
+    const unlisten = tableApi.onRowCount((count) => {
+      if (count.request_id !== requestId) return;
+      setData((current) =>
+        current && current.request_id === requestId
+          ? {
+              ...current,
+              total_rows: count.total_rows ?? current.total_rows,
+              total_rows_pending: false,
+            }
+          : current
+      );
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [requestId]);
+
   const handleSort = (column: string) => {
     if (sortBy !== column) {
       setSortBy(column);
@@ -101,6 +126,10 @@ export default function TableDataViewer({
   };
 
   const totalPages = data ? Math.ceil(data.total_rows / pageSize) : 0;
+  // Until the count arrives, a full page means there may be another one
+  const hasNextPage = data?.total_rows_pending
+    ? data.rows.length === pageSize
+    : page < totalPages - 1;
 
   return (
     <>
@@ -114,7 +143,23 @@ export default function TableDataViewer({
                 {database}.{table}
                 {data && (
                   <span className="ml-2 text-gray-500">
-                    ({data.total_rows.toLocaleString()} {data.total_rows === 1 ? 'row' : 'rows'})
+                    {data.total_rows_pending ? (
+                      '(counting rows...)'
+                    ) : (
+                      <>
+                        ({data.total_rows_estimated && '~'}
+                        {data.total_rows.toLocaleString()} {data.total_rows === 1 ? 'row' : 'rows'})
+                      </>
+                    )}
+                    {data.total_rows_estimated && (
+                      <button
+                        onClick={() => setRowCount(RowCountMode.Exact)}
+                        className="ml-2 text-blue-600 hover:text-blue-800"
+                        title="The row count is an estimate from table statistics"
+                      >
+                        Count exactly
+                      </button>
+                    )}
                   </span>
                 )}
               </p>
@@ -242,7 +287,7 @@ export default function TableDataViewer({
                             {row[column] === null ? (
                               <span className="text-gray-400 italic">NULL</span>
                             ) : (
-                              String(row[column])
+                              formatCellValue(row[column])
                             )}
                           </td>
                         ))}
@@ -297,7 +342,15 @@ export default function TableDataViewer({
               </select>
 
               <span className="text-sm text-gray-600">
-                Showing {page * pageSize + 1} - {Math.min((page + 1) * pageSize, data.total_rows)} of {data.total_rows.toLocaleString()}
+                {data.total_rows_pending ? (
+                  <>
+                    Showing {page * pageSize + 1} - {page * pageSize + data.rows.length}
+                  </>
+                ) : (
+                  <>
+                    Showing {page * pageSize + 1} - {Math.min((page + 1) * pageSize, data.total_rows)} of {data.total_rows_estimated && '~'}{data.total_rows.toLocaleString()}
+                  </>
+                )}
               </span>
             </div>
 
@@ -317,18 +370,19 @@ export default function TableDataViewer({
                 Previous
               </button>
               <span className="text-sm text-gray-600">
-                Page {page + 1} of {totalPages}
+                Page {page + 1}
+                {!data.total_rows_pending && <> of {totalPages}</>}
               </span>
               <button
                 onClick={() => setPage(page + 1)}
-                disabled={page >= totalPages - 1}
+                disabled={!hasNextPage}
                 className="px-3 py-1.5 border border-gray-300 rounded-md text-sm disabled:opacity-50 disabled:cursor-not-allowed hover:bg-gray-100"
               >
                 Next
               </button>
               <button
                 onClick={() => setPage(totalPages - 1)}
-                disabled={page >= totalPages - 1}
+                disabled={data.total_rows_pending || page >= totalPages - 1}
                 className="px-3 py-1.5 border border-gray-300 rounded-md text-sm disabled:opacity-50 disabled:cursor-not-allowed hover:bg-gray-100"
               >
                 Last
@@ -1,6 +1,7 @@
 import { useEffect, useState, useCallback } from 'react';
 import { tableApi } from '../../services/tauriApi';
 import type { TableSchema } from '../../types/schema';
+import { errorMessage } from '../../services/errors';
 
 interface TableStructureViewProps {
   connectionId: string;
@@ -24,7 +25,7 @@ export default function TableStructureView({
       const data = await tableApi.getTableStructure(connectionId, database, table);
       setSchema(data);
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to load table structure');
+      setError(errorMessage(err, 'Failed to load table structure'));
     } finally {
       setLoading(false);
     }
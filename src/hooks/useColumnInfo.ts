@@ -0,0 +1,33 @@
+import { useEffect, useState } from 'react';
+import { tableApi } from '../services/tauriApi';
+import type { ColumnSchema } from '../types/schema';
+
+/**
+ * Column definitions of a table by name, for row editors. Empty until loaded,
+ * and stays empty if the structure can't be read; editors then fall back to
+ * plain text fields.
+ */
+export function useColumnInfo(
+  connectionId: string,
+  database: string,
+  table: string
+): Record<string, ColumnSchema> {
+  const [columnInfo, setColumnInfo] = useState<Record<string, ColumnSchema>>({});
+
+  useEffect(() => {
+    let cancelled = false;
+    tableApi
+      .getTableStructure(connectionId, database, table)
+      .then((schema) => {
+        if (!cancelled) {
+          setColumnInfo(Object.fromEntries(schema.columns.map((c) => [c.name, c])));
+        }
+      })
+      .catch(() => {});
+    return () => {
+      cancelled = true;
+    };
+  }, [connectionId, database, table]);
+
+  return columnInfo;
+}
@@ -0,0 +1,78 @@
+import type { BlobValue, CellValue } from '../types/table';
+
+export function isBlobValue(value: unknown): value is BlobValue {
+  return (
+    typeof value === 'object' &&
+    value !== null &&
+    (value as { type?: unknown }).type === 'blob' &&
+    'size' in value
+  );
+}
+
+/** Whether a cell holds a JSON object or array, as JSON columns are returned */
+export function isJsonDocument(value: unknown): value is object {
+  return typeof value === 'object' && value !== null && !isBlobValue(value);
+}
+
+/** Text shown for a non-NULL cell in a results grid */
+export function formatCellValue(value: unknown): string {
+  if (isBlobValue(value)) {
+    const kind = value.content_type ?? 'BLOB';
+    return `${kind} (${value.size} bytes)`;
+  }
+  if (typeof value === 'object') {
+    return JSON.stringify(value);
+  }
+  return String(value);
+}
+
+/** How a row editor field is written */
+export type FieldMode = 'Value' | 'Null' | 'Default' | 'Expression';
+
+export const FIELD_MODES: FieldMode[] = ['Value', 'Null', 'Default', 'Expression'];
+
+export const FIELD_MODE_LABELS: Record<FieldMode, string> = {
+  Value: 'Value',
+  Null: 'NULL',
+  Default: 'DEFAULT',
+  Expression: 'Expression',
+};
+
+/**
+ * How a field's text is read: `auto` sends number-like text as a number,
+ * `json` parses it and `text` always sends a string, e.g. for ENUM values,
+ * which MySQL would read as positions if sent as numbers.
+ */
+export type FieldFormat = 'auto' | 'json' | 'text';
+
+/**
+ * Value sent for a row editor field. Throws a SyntaxError when a `json` field
+ * isn't valid JSON.
+ */
+export function toCellValue(
+  mode: FieldMode,
+  text: string,
+  format: FieldFormat = 'auto'
+): CellValue {
+  switch (mode) {
+    case 'Null':
+      return { kind: 'Null' };
+    case 'Default':
+      return { kind: 'Default' };
+    case 'Expression':
+      return { kind: 'Expression', value: text };
+  }
+  if (format === 'json') {
+    return { kind: 'Literal', value: JSON.parse(text) };
+  }
+  if (format === 'text') {
+    return { kind: 'Literal', value: text };
+  }
+  if (/^-?\d+$/.test(text)) {
+    return { kind: 'Literal', value: parseInt(text, 10) };
+  }
+  if (/^-?\d+\.\d+$/.test(text)) {
+    return { kind: 'Literal', value: parseFloat(text) };
+  }
+  return { kind: 'Literal', value: text };
+}
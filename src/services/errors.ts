@@ -0,0 +1,25 @@
+import type { AppError } from '../types/error';
+
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    'code' in error &&
+    'category' in error &&
+    'message' in error
+  );
+}
+
+/** Human-readable message for anything a command or the UI may throw */
+export function errorMessage(error: unknown, fallback?: string): string {
+  if (isAppError(error)) {
+    return error.detail ? `${error.message}\n${error.detail}` : error.message;
+  }
+  if (error instanceof Error) {
+    return error.message;
+  }
+  if (typeof error === 'string') {
+    return error;
+  }
+  return fallback ?? String(error);
+}
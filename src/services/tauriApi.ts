@@ -1,21 +1,87 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { Connection } from '../types/connection';
-import type { AutocompleteData, Schema, TableSchema } from '../types/schema';
-import type { QueryRequest, QueryResult } from '../types/query';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type {
+  Connection,
+  ConnectionGroup,
+  ConnectionSort,
+  ConnectionStatusEvent,
+  ConnectionTree,
+  ServerInfo,
+  StoreStatus,
+} from '../types/connection';
+import type {
+  AutocompleteData,
+  AutocompleteUpdate,
+  ColumnMatch,
+  Completion,
+  ResolvedReference,
+  Schema,
+  TableSchema,
+} from '../types/schema';
+import type {
+  DiffRequest,
+  ExportFormat,
+  ExportRequest,
+  MaskedCell,
+  ProcedureParameter,
+  ProcedureResult,
+  QueryRequest,
+  QueryResult,
+  QuerySearchMatch,
+  QueryStats,
+  QueuePosition,
+  ResultDiff,
+  ResultViewRequest,
+  SlowQuery,
+  TableCompareRequest,
+  TableDataDiff,
+  TimeRange,
+  ValidationResult,
+} from '../types/query';
+import type { AuditEntry } from '../types/audit';
+import type { ScheduleRequest, ScheduledQuery, ScheduledResult } from '../types/schedule';
+import type { TaskInfo } from '../types/task';
+import type { LogEntry, LogLevel } from '../types/log';
+import type { AppSettings } from '../types/settings';
+import type { PinnedTable, RecentObject, RecentObjectKind, Workspace } from '../types/workspace';
 import type {
   TableData,
   TableDataRequest,
   InsertRowRequest,
   UpdateRowRequest,
   DeleteRowRequest,
+  RowField,
+  ColumnProfile,
+  SeedTableRequest,
+  CopyTableRequest,
+  JsonExportRequest,
+  JsonImportReport,
+  JsonImportRequest,
+  CellFileRequest,
+  TableRowCount,
+  TableRowTotal,
+  QueryTemplateKind,
+  SortOrder,
 } from '../types/table';
 
 interface ConnectionApi {
   save(connection: Connection): Promise<void>;
   load(id: string): Promise<Connection | null>;
-  list(): Promise<Connection[]>;
+  list(sort?: ConnectionSort): Promise<ConnectionTree>;
   delete(id: string): Promise<void>;
+  /** Roll back open sessions and close the connection's pool and SSH tunnel */
+  disconnect(connectionId: string): Promise<void>;
+  disconnectAll(): Promise<void>;
+  /** A cached connection stopped responding and is being reopened */
+  onLost(handler: (event: ConnectionStatusEvent) => void): Promise<UnlistenFn>;
+  onRestored(handler: (event: ConnectionStatusEvent) => void): Promise<UnlistenFn>;
+  duplicate(id: string, newName: string): Promise<Connection>;
   test(connection: Connection): Promise<string>;
+  resolveClientConfig(connection: Connection): Promise<Connection>;
+  createGroup(name: string): Promise<ConnectionGroup>;
+  renameGroup(id: string, name: string): Promise<void>;
+  deleteGroup(id: string): Promise<void>;
+  moveToGroup(connectionId: string, groupId: string | null): Promise<void>;
 }
 
 export const connectionApi: ConnectionApi = {
@@ -27,22 +93,113 @@ export const connectionApi: ConnectionApi = {
     return invoke('load_connection', { id });
   },
 
-  list(): Promise<Connection[]> {
-    return invoke('list_connections');
+  list(sort?: ConnectionSort): Promise<ConnectionTree> {
+    return invoke('list_connections', { sort });
   },
 
   delete(id: string): Promise<void> {
     return invoke('delete_connection', { id });
   },
 
+  disconnect(connectionId: string): Promise<void> {
+    return invoke('disconnect', { connectionId });
+  },
+
+  disconnectAll(): Promise<void> {
+    return invoke('disconnect_all');
+  },
+
+  onLost(handler: (event: ConnectionStatusEvent) => void): Promise<UnlistenFn> {
+    return listen<ConnectionStatusEvent>('connection://lost', (event) => handler(event.payload));
+  },
+
+  onRestored(handler: (event: ConnectionStatusEvent) => void): Promise<UnlistenFn> {
+    return listen<ConnectionStatusEvent>('connection://restored', (event) => handler(event.payload));
+  },
+
+  duplicate(id: string, newName: string): Promise<Connection> {
+    return invoke('duplicate_connection', { id, newName });
+  },
+
   test(connection: Connection): Promise<string> {
     return invoke('test_connection', { connection });
   },
+
+  resolveClientConfig(connection: Connection): Promise<Connection> {
+    return invoke('resolve_client_config', { connection });
+  },
+
+  createGroup(name: string): Promise<ConnectionGroup> {
+    return invoke('create_connection_group', { name });
+  },
+
+  renameGroup(id: string, name: string): Promise<void> {
+    return invoke('rename_connection_group', { id, name });
+  },
+
+  deleteGroup(id: string): Promise<void> {
+    return invoke('delete_connection_group', { id });
+  },
+
+  moveToGroup(connectionId: string, groupId: string | null): Promise<void> {
+    return invoke('move_connection_to_group', { connectionId, groupId });
+  },
+};
+
+/** Master password protection of the stored connection passwords */
+interface StoreApi {
+  status(): Promise<StoreStatus>;
+  setMasterPassword(password: string): Promise<void>;
+  /**
+   * Re-encrypt stored passwords with a new master password, or a random key when
+   * omitted. Resolves to the path the previous store file was backed up to.
+   */
+  rotateKey(password?: string): Promise<string>;
+  /** Rejects with code `WRONG_PASSWORD` for a wrong password */
+  unlock(password: string): Promise<void>;
+  /** Resolves to false when there is no master password to lock with */
+  lock(): Promise<boolean>;
+  /** Fired when the store locks itself after inactivity */
+  onLocked(handler: () => void): Promise<UnlistenFn>;
+}
+
+export const storeApi: StoreApi = {
+  status(): Promise<StoreStatus> {
+    return invoke('get_store_status');
+  },
+
+  setMasterPassword(password: string): Promise<void> {
+    return invoke('set_master_password', { password });
+  },
+
+  rotateKey(password?: string): Promise<string> {
+    return invoke('rotate_encryption_key', { password: password ?? null });
+  },
+
+  unlock(password: string): Promise<void> {
+    return invoke('unlock_store', { password });
+  },
+
+  lock(): Promise<boolean> {
+    return invoke('lock_store');
+  },
+
+  onLocked(handler: () => void): Promise<UnlistenFn> {
+    return listen('store://locked', () => handler());
+  },
 };
 
 interface SchemaApi {
   getSchema(connectionId: string): Promise<Schema>;
+  /** Columns across every database on the server; `*` and `?` are wildcards */
+  findColumns(connectionId: string, pattern: string): Promise<ColumnMatch[]>;
   getAutocompleteData(connectionId: string, database: string): Promise<AutocompleteData>;
+  /** Re-reads only the tables that changed since the data was cached */
+  refreshAutocompleteData(connectionId: string, database: string): Promise<AutocompleteData>;
+  onAutocompleteUpdated(handler: (update: AutocompleteUpdate) => void): Promise<UnlistenFn>;
+  /** Ranked suggestions for the word at `cursorOffset` (in characters) */
+  getCompletions(sql: string, cursorOffset: number, connectionId: string, database: string): Promise<Completion[]>;
+  resolveReference(connectionId: string, database: string, reference: string): Promise<ResolvedReference | null>;
 }
 
 export const schemaApi: SchemaApi = {
@@ -50,37 +207,270 @@ export const schemaApi: SchemaApi = {
     return invoke('get_schema', { connectionId });
   },
 
+  findColumns(connectionId: string, pattern: string): Promise<ColumnMatch[]> {
+    return invoke('find_columns', { connectionId, pattern });
+  },
+
   getAutocompleteData(connectionId: string, database: string): Promise<AutocompleteData> {
     return invoke('get_autocomplete_data', { connectionId, database });
   },
+
+  refreshAutocompleteData(connectionId: string, database: string): Promise<AutocompleteData> {
+    return invoke('refresh_autocomplete_data', { connectionId, database });
+  },
+
+  onAutocompleteUpdated(handler: (update: AutocompleteUpdate) => void): Promise<UnlistenFn> {
+    return listen<AutocompleteUpdate>('autocomplete://updated', (event) => handler(event.payload));
+  },
+
+  getCompletions(sql: string, cursorOffset: number, connectionId: string, database: string): Promise<Completion[]> {
+    return invoke('get_completions', { sql, cursorOffset, connectionId, database });
+  },
+
+  resolveReference(connectionId: string, database: string, reference: string): Promise<ResolvedReference | null> {
+    return invoke('resolve_reference', { connectionId, database, reference });
+  },
 };
 
 interface QueryApi {
   execute(request: QueryRequest): Promise<QueryResult>;
+  /** Place in line of statements waiting for one of their connection's slots */
+  onQueuePosition(handler: (position: QueuePosition) => void): Promise<UnlistenFn>;
+  viewResult(request: ResultViewRequest): Promise<QueryResult>;
+  /** Sort a cached result, spilled rows included; `null` restores the fetched order */
+  sortResult(resultId: string, column: string | null, order: SortOrder): Promise<QueryResult>;
+  /** Keep rows matching a SQL expression, e.g. `total > 100`; `''` clears the filter */
+  filterResult(resultId: string, expression: string): Promise<QueryResult>;
+  fetchResultPage(resultId: string, page: number): Promise<QueryResult>;
+  releaseResult(resultId: string): Promise<void>;
+  /** The full value of a masked cell; fails with `NEEDS_CONFIRMATION` until `confirmed` */
+  revealMaskedCell(cell: MaskedCell, confirmed: boolean): Promise<any>;
+  diffResults(request: DiffRequest): Promise<ResultDiff>;
+  getProcedureParameters(
+    connectionId: string,
+    database: string,
+    name: string
+  ): Promise<ProcedureParameter[]>;
+  /** Values of IN and INOUT parameters by name; missing ones are NULL */
+  callProcedure(
+    connectionId: string,
+    database: string,
+    name: string,
+    params: Record<string, unknown>,
+    confirmation?: string
+  ): Promise<ProcedureResult>;
+  /** Resolves to the id of the export's background task */
+  exportResult(request: ExportRequest): Promise<string>;
+  /** A Markdown or HTML table of the result, for the clipboard */
+  renderResult(
+    resultId: string,
+    format: ExportFormat,
+    columns?: string[],
+    rowLimit?: number
+  ): Promise<string>;
+  validate(
+    connectionId: string,
+    sql: string,
+    database: string | null,
+    serverCheck: boolean
+  ): Promise<ValidationResult>;
+  /** Slowest and most frequent statements, from this machine's query history */
+  getQueryStats(connectionId: string, timeRange: TimeRange): Promise<QueryStats>;
+  /** History statements containing every word of `text`, best match first; all connections when none is given */
+  searchQueries(
+    text: string,
+    connectionId?: string,
+    dateRange?: TimeRange,
+    limit?: number
+  ): Promise<QuerySearchMatch[]>;
 }
 
 export const queryApi: QueryApi = {
   execute(request: QueryRequest): Promise<QueryResult> {
     return invoke('execute_query', { request });
   },
+
+  onQueuePosition(handler: (position: QueuePosition) => void): Promise<UnlistenFn> {
+    return listen<QueuePosition>('query://queue', (event) => handler(event.payload));
+  },
+
+  viewResult(request: ResultViewRequest): Promise<QueryResult> {
+    return invoke('view_result', { request });
+  },
+
+  sortResult(resultId: string, column: string | null, order: SortOrder): Promise<QueryResult> {
+    return invoke('sort_result', { resultId, column, order });
+  },
+
+  filterResult(resultId: string, expression: string): Promise<QueryResult> {
+    return invoke('filter_result', { resultId, expression });
+  },
+
+  fetchResultPage(resultId: string, page: number): Promise<QueryResult> {
+    return invoke('fetch_result_page', { resultId, page });
+  },
+
+  releaseResult(resultId: string): Promise<void> {
+    return invoke('release_result', { resultId });
+  },
+
+  revealMaskedCell(cell: MaskedCell, confirmed: boolean): Promise<any> {
+    return invoke('reveal_masked_cell', { cell, confirmed });
+  },
+
+  diffResults(request: DiffRequest): Promise<ResultDiff> {
+    return invoke('diff_results', { request });
+  },
+
+  getProcedureParameters(
+    connectionId: string,
+    database: string,
+    name: string
+  ): Promise<ProcedureParameter[]> {
+    return invoke('get_procedure_parameters', { connectionId, database, name });
+  },
+
+  callProcedure(
+    connectionId: string,
+    database: string,
+    name: string,
+    params: Record<string, unknown>,
+    confirmation?: string
+  ): Promise<ProcedureResult> {
+    return invoke('call_procedure', { connectionId, database, name, params, confirmation });
+  },
+
+  exportResult(request: ExportRequest): Promise<string> {
+    return invoke('export_result', { request });
+  },
+
+  renderResult(
+    resultId: string,
+    format: ExportFormat,
+    columns?: string[],
+    rowLimit?: number
+  ): Promise<string> {
+    return invoke('render_result', {
+      resultId,
+      format,
+      columns: columns ?? null,
+      rowLimit: rowLimit ?? null,
+    });
+  },
+
+  validate(
+    connectionId: string,
+    sql: string,
+    database: string | null,
+    serverCheck: boolean
+  ): Promise<ValidationResult> {
+    return invoke('validate_query', { sql, connectionId, database, serverCheck });
+  },
+
+  getQueryStats(connectionId: string, timeRange: TimeRange): Promise<QueryStats> {
+    return invoke('get_query_stats', { connectionId, timeRange });
+  },
+
+  searchQueries(
+    text: string,
+    connectionId?: string,
+    dateRange?: TimeRange,
+    limit?: number
+  ): Promise<QuerySearchMatch[]> {
+    return invoke('search_queries', { text, connectionId, dateRange, limit });
+  },
 };
 
 interface DatabaseApi {
   listDatabases(connectionId: string): Promise<string[]>;
+  /** Schemas within a database; empty where databases don't hold schemas (MySQL) */
+  listSchemas(connectionId: string, database: string): Promise<string[]>;
+  getServerInfo(connectionId: string): Promise<ServerInfo>;
+  /** Statements the server spent the most time on in total, from performance_schema */
+  getSlowQueries(connectionId: string, limit: number): Promise<SlowQuery[]>;
+  createDemoDatabase(connectionId: string, database: string): Promise<void>;
 }
 
 export const databaseApi: DatabaseApi = {
   listDatabases(connectionId: string): Promise<string[]> {
     return invoke('list_databases', { connectionId });
   },
+
+  listSchemas(connectionId: string, database: string): Promise<string[]> {
+    return invoke('list_schemas', { connectionId, database });
+  },
+
+  getServerInfo(connectionId: string): Promise<ServerInfo> {
+    return invoke('get_server_info', { connectionId });
+  },
+
+  getSlowQueries(connectionId: string, limit: number): Promise<SlowQuery[]> {
+    return invoke('get_slow_queries', { connectionId, limit });
+  },
+
+  createDemoDatabase(connectionId: string, database: string): Promise<void> {
+    return invoke('create_demo_database', { connectionId, database });
+  },
 };
 
 interface TableApi {
   getTableStructure(connectionId: string, database: string, table: string): Promise<TableSchema>;
+  /** Estimated row counts in one round trip; tables in `exact` are counted */
+  getTableRowCounts(
+    connectionId: string,
+    database: string,
+    tables: string[],
+    exact?: string[]
+  ): Promise<TableRowTotal[]>;
   getTableData(request: TableDataRequest): Promise<TableData>;
-  insertRow(request: InsertRowRequest): Promise<void>;
-  updateRow(request: UpdateRowRequest): Promise<number>;
-  deleteRows(request: DeleteRowRequest): Promise<number>;
+  /** Exact counts of pages returned with `total_rows_pending` */
+  onRowCount(handler: (count: TableRowCount) => void): Promise<UnlistenFn>;
+  /** `confirmation` is the connection's name, for environments that ask for it */
+  insertRow(request: InsertRowRequest, confirmation?: string): Promise<void>;
+  updateRow(request: UpdateRowRequest, confirmation?: string): Promise<number>;
+  deleteRows(request: DeleteRowRequest, confirmation?: string): Promise<number>;
+  getRow(
+    connectionId: string,
+    database: string,
+    table: string,
+    pkValues: Record<string, any>
+  ): Promise<RowField[] | null>;
+  /** The full value of a cell whose column is marked `truncated` */
+  getCellValue(
+    connectionId: string,
+    database: string,
+    table: string,
+    pkValues: Record<string, any>,
+    column: string
+  ): Promise<RowField | null>;
+  profileColumn(
+    connectionId: string,
+    database: string,
+    table: string,
+    column: string
+  ): Promise<ColumnProfile>;
+  /** Add made-up rows to a table in a background task, resolving to its id */
+  seedTable(request: SeedTableRequest, confirmation?: string): Promise<string>;
+  exportCellToFile(request: CellFileRequest): Promise<number>;
+  updateCellFromFile(request: CellFileRequest, confirmation?: string): Promise<number>;
+  /**
+   * Create the table on the target and copy its rows in a background task,
+   * resolving to the task's id; null when only the structure is copied
+   */
+  copyTable(request: CopyTableRequest, confirmation?: string): Promise<string | null>;
+  /** Write a table's rows to a type-tagged JSON file in a background task, resolving to its id */
+  exportTableJson(request: JsonExportRequest): Promise<string>;
+  /** Insert rows from `exportTableJson`; rows that don't fit the table are reported */
+  importTableJson(request: JsonImportRequest, confirmation?: string): Promise<JsonImportReport>;
+  /** Compare a table's rows on two connections, optionally syncing the target */
+  compareTableData(request: TableCompareRequest): Promise<TableDataDiff>;
+  /** A statement listing the table's columns, with placeholders for values */
+  generateQueryTemplate(
+    connectionId: string,
+    database: string,
+    table: string,
+    kind: QueryTemplateKind
+  ): Promise<string>;
 }
 
 export const tableApi: TableApi = {
@@ -88,19 +478,270 @@ export const tableApi: TableApi = {
     return invoke('get_table_structure', { connectionId, database, table });
   },
 
+  getTableRowCounts(
+    connectionId: string,
+    database: string,
+    tables: string[],
+    exact?: string[]
+  ): Promise<TableRowTotal[]> {
+    return invoke('get_table_row_counts', {
+      connectionId,
+      database,
+      tables,
+      exact: exact ?? null,
+    });
+  },
+
   getTableData(request: TableDataRequest): Promise<TableData> {
     return invoke('get_table_data', { request });
   },
 
-  insertRow(request: InsertRowRequest): Promise<void> {
-    return invoke('insert_table_row', { request });
+  onRowCount(handler: (count: TableRowCount) => void): Promise<UnlistenFn> {
+    return listen<TableRowCount>('table-data://count', (event) => handler(event.payload));
+  },
+
+  insertRow(request: InsertRowRequest, confirmation?: string): Promise<void> {
+    return invoke('insert_table_row', { request, confirmation });
+  },
+
+  updateRow(request: UpdateRowRequest, confirmation?: string): Promise<number> {
+    return invoke('update_table_row', { request, confirmation });
+  },
+
+  deleteRows(request: DeleteRowRequest, confirmation?: string): Promise<number> {
+    return invoke('delete_table_rows', { request, confirmation });
+  },
+
+  getRow(
+    connectionId: string,
+    database: string,
+    table: string,
+    pkValues: Record<string, any>
+  ): Promise<RowField[] | null> {
+    return invoke('get_row', { connectionId, database, table, pkValues });
+  },
+
+  getCellValue(
+    connectionId: string,
+    database: string,
+    table: string,
+    pkValues: Record<string, any>,
+    column: string
+  ): Promise<RowField | null> {
+    return invoke('get_cell_value', { connectionId, database, table, pkValues, column });
+  },
+
+  profileColumn(
+    connectionId: string,
+    database: string,
+    table: string,
+    column: string
+  ): Promise<ColumnProfile> {
+    return invoke('profile_column', { connectionId, database, table, column });
+  },
+
+  seedTable(request: SeedTableRequest, confirmation?: string): Promise<string> {
+    return invoke('seed_table', { request, confirmation });
+  },
+
+  exportCellToFile(request: CellFileRequest): Promise<number> {
+    return invoke('export_cell_to_file', { request });
+  },
+
+  updateCellFromFile(request: CellFileRequest, confirmation?: string): Promise<number> {
+    return invoke('update_cell_from_file', { request, confirmation });
+  },
+
+  copyTable(request: CopyTableRequest, confirmation?: string): Promise<string | null> {
+    return invoke('copy_table', { request, confirmation });
+  },
+
+  exportTableJson(request: JsonExportRequest): Promise<string> {
+    return invoke('export_table_json', { request });
+  },
+
+  importTableJson(request: JsonImportRequest, confirmation?: string): Promise<JsonImportReport> {
+    return invoke('import_table_json', { request, confirmation });
+  },
+
+  compareTableData(request: TableCompareRequest): Promise<TableDataDiff> {
+    return invoke('compare_table_data', { request });
+  },
+
+  generateQueryTemplate(
+    connectionId: string,
+    database: string,
+    table: string,
+    kind: QueryTemplateKind
+  ): Promise<string> {
+    return invoke('generate_query_template', { connectionId, database, table, kind });
+  },
+};
+
+interface SettingsApi {
+  get(): Promise<AppSettings>;
+  update(settings: AppSettings): Promise<void>;
+}
+
+export const settingsApi: SettingsApi = {
+  get(): Promise<AppSettings> {
+    return invoke('get_settings');
+  },
+
+  update(settings: AppSettings): Promise<void> {
+    return invoke('update_settings', { settings });
+  },
+};
+
+/** Whole-profile archives: connections, history, settings and workspaces */
+interface BackupApi {
+  backup(path: string, passphrase: string): Promise<void>;
+  /** Replaces all app data; resolves to where the replaced data was backed up */
+  restore(path: string, passphrase: string): Promise<string>;
+}
+
+export const backupApi: BackupApi = {
+  backup(path: string, passphrase: string): Promise<void> {
+    return invoke('backup_app_data', { path, passphrase });
+  },
+
+  restore(path: string, passphrase: string): Promise<string> {
+    return invoke('restore_app_data', { path, passphrase });
+  },
+};
+
+interface WindowApi {
+  /**
+   * Open another window sharing connections and pools. A connection's window opens
+   * with `?connection=<id>` and is focused instead of duplicated. Resolves to its label.
+   */
+  open(connectionId?: string): Promise<string>;
+}
+
+export const windowApi: WindowApi = {
+  open(connectionId?: string): Promise<string> {
+    return invoke('new_window', { connectionId: connectionId ?? null });
+  },
+};
+
+interface LogsApi {
+  /** Newest entries first; all levels when `level` is omitted */
+  get(limit: number, level?: LogLevel): Promise<LogEntry[]>;
+  setLevel(level: LogLevel): Promise<void>;
+}
+
+export const logsApi: LogsApi = {
+  get(limit: number, level?: LogLevel): Promise<LogEntry[]> {
+    return invoke('get_app_logs', { level: level ?? null, limit });
+  },
+
+  setLevel(level: LogLevel): Promise<void> {
+    return invoke('set_log_level', { level });
+  },
+};
+
+interface AuditApi {
+  /** Newest entries first; every connection when `connectionId` is omitted */
+  list(limit: number, connectionId?: string): Promise<AuditEntry[]>;
+}
+
+export const auditApi: AuditApi = {
+  list(limit: number, connectionId?: string): Promise<AuditEntry[]> {
+    return invoke('get_audit_log', { connectionId: connectionId ?? null, limit });
+  },
+};
+
+interface WorkspaceApi {
+  /** Workspaces are kept per window; the calling window is implied */
+  save(workspace: Workspace): Promise<void>;
+  load(connectionId: string): Promise<Workspace | null>;
+  pinTable(connectionId: string, database: string, table: string): Promise<void>;
+  unpinTable(connectionId: string, database: string, table: string): Promise<void>;
+  listPinnedTables(connectionId: string): Promise<PinnedTable[]>;
+  /** Note that an object was opened; `name` is omitted for a database */
+  recordRecentObject(connectionId: string, kind: RecentObjectKind, database: string, name?: string): Promise<void>;
+  /** Latest first; 20 when no limit is given */
+  getRecentObjects(connectionId: string, limit?: number): Promise<RecentObject[]>;
+  clearRecentObjects(connectionId: string): Promise<void>;
+}
+
+export const workspaceApi: WorkspaceApi = {
+  save(workspace: Workspace): Promise<void> {
+    return invoke('save_workspace', { workspace });
+  },
+
+  load(connectionId: string): Promise<Workspace | null> {
+    return invoke('load_workspace', { connectionId });
+  },
+
+  pinTable(connectionId: string, database: string, table: string): Promise<void> {
+    return invoke('pin_table', { connectionId, database, table });
+  },
+
+  unpinTable(connectionId: string, database: string, table: string): Promise<void> {
+    return invoke('unpin_table', { connectionId, database, table });
+  },
+
+  listPinnedTables(connectionId: string): Promise<PinnedTable[]> {
+    return invoke('list_pinned_tables', { connectionId });
+  },
+
+  recordRecentObject(connectionId: string, kind: RecentObjectKind, database: string, name?: string): Promise<void> {
+    return invoke('record_recent_object', { connectionId, kind, database, name });
+  },
+
+  getRecentObjects(connectionId: string, limit?: number): Promise<RecentObject[]> {
+    return invoke('get_recent_objects', { connectionId, limit });
+  },
+
+  clearRecentObjects(connectionId: string): Promise<void> {
+    return invoke('clear_recent_objects', { connectionId });
+  },
+};
+
+interface ScheduleApi {
+  schedule(request: ScheduleRequest): Promise<ScheduledQuery>;
+  unschedule(scheduleId: string): Promise<void>;
+  /** Every connection's schedules when `connectionId` is omitted */
+  list(connectionId?: string): Promise<ScheduledQuery[]>;
+  onResult(handler: (result: ScheduledResult) => void): Promise<UnlistenFn>;
+}
+
+export const scheduleApi: ScheduleApi = {
+  schedule(request: ScheduleRequest): Promise<ScheduledQuery> {
+    return invoke('schedule_query', { request });
+  },
+
+  unschedule(scheduleId: string): Promise<void> {
+    return invoke('unschedule_query', { scheduleId });
+  },
+
+  list(connectionId?: string): Promise<ScheduledQuery[]> {
+    return invoke('list_scheduled_queries', { connectionId: connectionId ?? null });
+  },
+
+  onResult(handler: (result: ScheduledResult) => void): Promise<UnlistenFn> {
+    return listen<ScheduledResult>('schedule://result', (event) => handler(event.payload));
+  },
+};
+
+interface TaskApi {
+  /** Running and recently finished background tasks, newest first */
+  list(): Promise<TaskInfo[]>;
+  cancel(taskId: string): Promise<void>;
+  onProgress(handler: (task: TaskInfo) => void): Promise<UnlistenFn>;
+}
+
+export const taskApi: TaskApi = {
+  list(): Promise<TaskInfo[]> {
+    return invoke('list_tasks');
   },
 
-  updateRow(request: UpdateRowRequest): Promise<number> {
-    return invoke('update_table_row', { request });
+  cancel(taskId: string): Promise<void> {
+    return invoke('cancel_task', { taskId });
   },
 
-  deleteRows(request: DeleteRowRequest): Promise<number> {
-    return invoke('delete_table_rows', { request });
+  onProgress(handler: (task: TaskInfo) => void): Promise<UnlistenFn> {
+    return listen<TaskInfo>('task://progress', (event) => handler(event.payload));
   },
 };
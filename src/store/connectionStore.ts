@@ -1,9 +1,11 @@
 import { create } from 'zustand';
-import type { Connection } from '../types/connection';
+import type { Connection, ConnectionGroup } from '../types/connection';
 import { connectionApi } from '../services/tauriApi';
+import { errorMessage } from '../services/errors';
 
 interface ConnectionState {
   connections: Connection[];
+  groups: ConnectionGroup[];
   activeConnectionId: string | null;
   selectedDatabase: string | null;
   isLoading: boolean;
@@ -22,15 +24,9 @@ interface ConnectionActions {
 
 type ConnectionStore = ConnectionState & ConnectionActions;
 
-function toErrorMessage(error: unknown): string {
-  if (error instanceof Error) {
-    return error.message;
-  }
-  return String(error);
-}
-
 export const useConnectionStore = create<ConnectionStore>((set, get) => ({
   connections: [],
+  groups: [],
   activeConnectionId: null,
   selectedDatabase: null,
   isLoading: false,
@@ -39,10 +35,15 @@ export const useConnectionStore = create<ConnectionStore>((set, get) => ({
   loadConnections: async () => {
     set({ isLoading: true, error: null });
     try {
-      const connections = await connectionApi.list();
-      set({ connections, isLoading: false });
+      const tree = await connectionApi.list();
+      const connections = [
+        ...tree.groups.flatMap((node) => node.connections),
+        ...tree.ungrouped,
+      ];
+      const groups = tree.groups.map((node) => node.group);
+      set({ connections, groups, isLoading: false });
     } catch (error) {
-      set({ error: toErrorMessage(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
     }
   },
 
@@ -52,7 +53,7 @@ export const useConnectionStore = create<ConnectionStore>((set, get) => ({
       await connectionApi.save(connection);
       await get().loadConnections();
     } catch (error) {
-      set({ error: toErrorMessage(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
       throw error;
     }
   },
@@ -70,7 +71,7 @@ export const useConnectionStore = create<ConnectionStore>((set, get) => ({
         activeConnectionId: activeConnectionId === id ? null : activeConnectionId,
       });
     } catch (error) {
-      set({ error: toErrorMessage(error), isLoading: false });
+      set({ error: errorMessage(error), isLoading: false });
       throw error;
     }
   },
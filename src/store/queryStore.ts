@@ -2,20 +2,45 @@ import { create } from 'zustand';
 import type { QueryResult, QueryHistoryEntry } from '../types/query';
 import type { AutocompleteData } from '../types/schema';
 import { queryApi, schemaApi } from '../services/tauriApi';
+import { errorMessage, isAppError } from '../services/errors';
+
+/** A statement the backend refused to run until the user confirms it */
+export interface PendingConfirmation {
+  connectionId: string;
+  sql: string;
+  database?: string;
+  message: string;
+}
 
 interface QueryState {
   currentResult: QueryResult | null;
+  /** Page of `currentResult` shown when the backend split it into pages */
+  currentPage: number;
   isExecuting: boolean;
   error: string | null;
   history: QueryHistoryEntry[];
   autocompleteData: AutocompleteData | null;
+  autocompleteConnectionId: string | null;
   currentQueryId: string | null;
   cancelledQueries: Set<string>;
+  pendingConfirmation: PendingConfirmation | null;
 }
 
 interface QueryActions {
-  executeQuery: (connectionId: string, sql: string, database?: string, page?: number, pageSize?: number) => Promise<void>;
+  executeQuery: (
+    connectionId: string,
+    sql: string,
+    database?: string,
+    page?: number,
+    pageSize?: number,
+    forceRefresh?: boolean,
+    confirmed?: boolean
+  ) => Promise<void>;
+  /** Run the pending statement after the user confirmed it */
+  confirmPending: () => Promise<void>;
+  cancelPending: () => void;
   cancelQuery: () => void;
+  loadResultPage: (page: number) => Promise<void>;
   loadAutocompleteData: (connectionId: string, database: string) => Promise<void>;
   clearResult: () => void;
   clearError: () => void;
@@ -23,23 +48,36 @@ interface QueryActions {
 
 type QueryStore = QueryState & QueryActions;
 
-function toErrorMessage(error: unknown): string {
-  if (error instanceof Error) {
-    return error.message;
+/** Let the backend free a cached result the UI no longer shows */
+function releaseResult(result: QueryResult | null) {
+  if (result?.result_id) {
+    queryApi.releaseResult(result.result_id).catch((error) => {
+      console.error('Failed to release result:', error);
+    });
   }
-  return String(error);
 }
 
 export const useQueryStore = create<QueryStore>((set, get) => ({
   currentResult: null,
+  currentPage: 0,
   isExecuting: false,
   error: null,
   history: [],
   autocompleteData: null,
+  autocompleteConnectionId: null,
   currentQueryId: null,
   cancelledQueries: new Set<string>(),
-
-  executeQuery: async (connectionId: string, sql: string, database?: string, page?: number, pageSize?: number) => {
+  pendingConfirmation: null,
+
+  executeQuery: async (
+    connectionId: string,
+    sql: string,
+    database?: string,
+    page?: number,
+    pageSize?: number,
+    forceRefresh?: boolean,
+    confirmed?: boolean
+  ) => {
     const queryId = crypto.randomUUID();
     set({ isExecuting: true, error: null, currentQueryId: queryId });
     const startTime = Date.now();
@@ -51,6 +89,8 @@ export const useQueryStore = create<QueryStore>((set, get) => ({
         database,
         page,
         page_size: pageSize,
+        force_refresh: forceRefresh,
+        confirmed,
       });
 
       // Check if this query was cancelled while executing
@@ -67,7 +107,8 @@ export const useQueryStore = create<QueryStore>((set, get) => ({
         return;
       }
 
-      set({ currentResult: result, isExecuting: false, currentQueryId: null });
+      releaseResult(get().currentResult);
+      set({ currentResult: result, currentPage: 0, isExecuting: false, currentQueryId: null });
 
       // Add to history
       const historyEntry: QueryHistoryEntry = {
@@ -96,8 +137,18 @@ export const useQueryStore = create<QueryStore>((set, get) => ({
         return;
       }
 
-      const errorMessage = toErrorMessage(error);
-      set({ error: errorMessage, isExecuting: false, currentResult: null, currentQueryId: null });
+      // Nothing ran; ask before running it for real
+      if (isAppError(error) && error.code === 'NEEDS_CONFIRMATION') {
+        set({
+          isExecuting: false,
+          currentQueryId: null,
+          pendingConfirmation: { connectionId, sql, database, message: error.message },
+        });
+        return;
+      }
+
+      const message = errorMessage(error);
+      set({ error: message, isExecuting: false, currentResult: null, currentQueryId: null });
 
       // Add failed query to history
       const historyEntry: QueryHistoryEntry = {
@@ -107,7 +158,7 @@ export const useQueryStore = create<QueryStore>((set, get) => ({
         executed_at: startTime,
         execution_time_ms: Date.now() - startTime,
         success: false,
-        error_message: errorMessage,
+        error_message: message,
       };
 
       set((state) => ({
@@ -116,6 +167,25 @@ export const useQueryStore = create<QueryStore>((set, get) => ({
     }
   },
 
+  confirmPending: async () => {
+    const pending = get().pendingConfirmation;
+    if (!pending) return;
+    set({ pendingConfirmation: null });
+    await get().executeQuery(
+      pending.connectionId,
+      pending.sql,
+      pending.database,
+      undefined,
+      undefined,
+      false,
+      true
+    );
+  },
+
+  cancelPending: () => {
+    set({ pendingConfirmation: null });
+  },
+
   cancelQuery: () => {
     const state = get();
     if (state.currentQueryId) {
@@ -130,17 +200,35 @@ export const useQueryStore = create<QueryStore>((set, get) => ({
     }
   },
 
+  loadResultPage: async (page: number) => {
+    const current = get().currentResult;
+    if (!current?.result_id || !current.page_size) {
+      return;
+    }
+
+    try {
+      const result = await queryApi.fetchResultPage(current.result_id, page);
+      // Ignore the page if another query replaced the result meanwhile
+      if (get().currentResult?.result_id === current.result_id) {
+        set({ currentResult: result, currentPage: page });
+      }
+    } catch (error) {
+      set({ error: errorMessage(error) });
+    }
+  },
+
   loadAutocompleteData: async (connectionId: string, database: string) => {
     try {
       const data = await schemaApi.getAutocompleteData(connectionId, database);
-      set({ autocompleteData: data });
+      set({ autocompleteData: data, autocompleteConnectionId: connectionId });
     } catch (error) {
       console.error('Failed to load autocomplete data:', error);
     }
   },
 
   clearResult: () => {
-    set({ currentResult: null });
+    releaseResult(get().currentResult);
+    set({ currentResult: null, currentPage: 0 });
   },
 
   clearError: () => {
@@ -18,6 +18,7 @@ vi.mock('monaco-editor', () => ({
       Field: 0,
       Class: 1,
       Keyword: 2,
+      Function: 3,
     },
   },
   KeyCode: {
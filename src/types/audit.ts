@@ -0,0 +1,13 @@
+export interface AuditEntry {
+  id: string;
+  connection_id: string;
+  database: string | null;
+  /** Backend command that issued the statement, e.g. `update_table_row` */
+  command: string;
+  sql: string;
+  /** Null when the statement failed */
+  rows_affected: number | null;
+  success: boolean;
+  error_message: string | null;
+  executed_at: number;
+}
@@ -1,5 +1,12 @@
 export type DatabaseType = 'MySQL' | 'PostgreSQL';
 
+/** Staging and Prod connections follow the guardrails in the settings */
+export type Environment = 'Dev' | 'Staging' | 'Prod';
+
+/**
+ * Server, credential, socket, SSH and TLS fields may hold `${NAME}`
+ * placeholders, filled from the environment when the connection opens.
+ */
 export interface Connection {
   id: string;
   name: string;
@@ -12,6 +19,75 @@ export interface Connection {
   database?: string | null;
   ssh_config?: SSHConfig | null;
   ssl_config?: SSLConfig | null;
+  group_id?: string | null;
+  read_only?: boolean;
+  environment?: Environment;
+  options?: ConnectionOptions;
+  /** Unix time in milliseconds */
+  last_used_at?: number | null;
+  use_count?: number;
+}
+
+export type ConnectionSort = 'Name' | 'Recent';
+
+export interface ConnectionOptions {
+  max_rows: number | null;
+  /** Megabytes of a result kept in memory, the rest is paged from disk; unlimited when null */
+  max_result_memory_mb?: number | null;
+  statement_timeout_ms: number | null;
+  connect_timeout_secs: number;
+  pool_size: number;
+  /** Statements run at once from the query editor, more wait in line; unlimited when null */
+  max_concurrent_queries?: number | null;
+  /** Session time zone, e.g. `+00:00` or `Europe/Berlin`; the server's when null */
+  time_zone?: string | null;
+  /** Variables set on every session, e.g. `{ sql_mode: 'ANSI_QUOTES' }` */
+  session_variables?: Record<string, string>;
+  /** Statements run on every new session, e.g. `SET ROLE reporting` */
+  init_sql?: string | null;
+  /** Local socket to connect through instead of TCP (a directory for PostgreSQL) */
+  socket_path?: string | null;
+  /** Fill the user, password and server left empty from ~/.my.cnf or ~/.pgpass */
+  use_client_config?: boolean;
+  /** Read the password from a secret store at connect time instead of storing it */
+  password_secret?: SecretRef | null;
+  /** Columns masked in results: patterns like `*password*` or `users.ssn`, `*` and `?` wildcards */
+  masked_columns?: string[];
+}
+
+/** A Vault secret; `path` is below `/v1/`, e.g. `secret/data/prod/db` */
+export type SecretRef = {
+  Vault: { path: string; key: string; username_key?: string | null };
+};
+
+export interface ServerInfo {
+  version: string;
+  /** `SYSTEM` means the server host's zone */
+  server_time_zone: string;
+  system_time_zone: string;
+  session_time_zone: string;
+  utc_offset_minutes: number;
+}
+
+export interface ConnectionGroup {
+  id: string;
+  name: string;
+}
+
+export interface ConnectionGroupNode {
+  group: ConnectionGroup;
+  connections: Connection[];
+}
+
+export interface ConnectionTree {
+  groups: ConnectionGroupNode[];
+  ungrouped: Connection[];
+}
+
+/** While `locked`, commands reading credentials fail with code `STORE_LOCKED` */
+export interface StoreStatus {
+  has_master_password: boolean;
+  locked: boolean;
 }
 
 export interface SSHConfig {
@@ -19,6 +95,19 @@ export interface SSHConfig {
   port: number;
   username: string;
   auth: SSHAuth;
+  use_ssh_config?: boolean;
+  /** Jump hosts in OpenSSH `ProxyJump` syntax; ignored when `jump_hosts` is set */
+  proxy_jump?: string | null;
+  /** Hosts tunneled through in order before `host` */
+  jump_hosts?: SSHHop[];
+}
+
+export interface SSHHop {
+  host: string;
+  port: number;
+  /** The local user when empty */
+  username: string;
+  auth: SSHAuth;
 }
 
 export type SSHAuth =
@@ -32,3 +121,17 @@ export interface SSLConfig {
   client_key?: string | null;
   verify: boolean;
 }
+
+export type ConnectionStatus = 'Connected' | 'Reconnecting' | 'Disconnected';
+
+/** Why a cached connection stopped responding */
+export type LostReason = 'TunnelDied' | 'ServerGone' | 'AuthExpired' | 'Timeout' | 'Other';
+
+/** Payload of the `connection://status`, `connection://lost` and `connection://restored` events */
+export interface ConnectionStatusEvent {
+  connection_id: string;
+  status: ConnectionStatus;
+  error?: string | null;
+  /** Set when the connection was lost */
+  reason?: LostReason | null;
+}
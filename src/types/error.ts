@@ -0,0 +1,37 @@
+import type { DestructiveRisk } from './query';
+
+export enum ErrorCategory {
+  Auth = 'Auth',
+  Network = 'Network',
+  Syntax = 'Syntax',
+  Constraint = 'Constraint',
+  Timeout = 'Timeout',
+  Permission = 'Permission',
+  NotFound = 'NotFound',
+  Validation = 'Validation',
+  /** The data changed since it was read */
+  Conflict = 'Conflict',
+  Internal = 'Internal',
+}
+
+/**
+ * Error value rejected by every backend command. Writes to a guarded
+ * environment fail with `NEEDS_TYPED_CONFIRMATION` until the connection's
+ * name is passed as `confirmation`, and statements its policy forbids fail
+ * with `GUARDRAIL`.
+ */
+export interface AppError {
+  /** Stable identifier, e.g. `DATABASE_SERVER` or `NOT_FOUND` */
+  code: string;
+  category: ErrorCategory;
+  message: string;
+  sqlstate: string | null;
+  /** Server error number, e.g. 1062 for a MySQL duplicate key */
+  server_code: number | null;
+  /** Byte offset into the statement where the error was found */
+  position: number | null;
+  /** Extra context, such as a GRANT statement that would fix the problem */
+  detail: string | null;
+  /** Why the statement needs confirmation; set when `code` is `NEEDS_CONFIRMATION` */
+  risk: DestructiveRisk | null;
+}
@@ -0,0 +1,17 @@
+export enum LogLevel {
+  Error = 'Error',
+  Warn = 'Warn',
+  Info = 'Info',
+  Debug = 'Debug',
+  Trace = 'Trace',
+}
+
+export interface LogEntry {
+  /** RFC 3339 timestamp */
+  timestamp: string;
+  level: LogLevel;
+  /** Backend module that wrote the entry */
+  target: string;
+  message: string;
+  fields: Record<string, unknown>;
+}
@@ -1,9 +1,31 @@
+import type { SortOrder, TableFilter } from './table';
+
 export interface QueryRequest {
   connection_id: string;
   sql: string;
   database?: string;
   page?: number;
   page_size?: number;
+  params?: unknown[];
+  session_id?: string;
+  /** Run the query even when a cached result is available */
+  force_refresh?: boolean;
+  /** The user agreed to run a statement that may lose data */
+  confirmed?: boolean;
+  /** The connection's name as typed, when it fails with `NEEDS_TYPED_CONFIRMATION` */
+  confirmation?: string;
+  /** Chosen by the caller to match `query://queue` events to this request */
+  request_id?: string;
+}
+
+/** Why a statement needs the user's confirmation before it runs */
+export enum DestructiveRisk {
+  UpdateWithoutWhere = 'UpdateWithoutWhere',
+  DeleteWithoutWhere = 'DeleteWithoutWhere',
+  Drop = 'Drop',
+  Truncate = 'Truncate',
+  RevealMasked = 'RevealMasked',
+  SyncTable = 'SyncTable',
 }
 
 export interface QueryResult {
@@ -11,6 +33,159 @@ export interface QueryResult {
   rows: Array<Array<any>>;
   total_rows: number;
   execution_time_ms: number;
+  result_id?: string | null;
+  truncated?: boolean;
+  /** Set when `rows` is only the first page; fetch the rest with `fetchResultPage` */
+  page_size?: number | null;
+  /** Rows changed by an INSERT, UPDATE or DELETE */
+  rows_affected?: number;
+  /** Type of each column, in `columns` order; empty when there are no rows */
+  column_types?: ColumnTypeInfo[];
+  /** Served from the query cache instead of running the query */
+  cached?: boolean;
+  /** When the cached result was fetched, in milliseconds since the epoch */
+  cached_at?: number | null;
+  /** The LIMIT appended to an unbounded SELECT, when one was */
+  auto_limit?: number | null;
+  /** A SELECT without a LIMIT that was run as written */
+  unbounded?: boolean;
+  /** A page of a paginated query was full and more rows follow it */
+  has_more?: boolean;
+  /** An INSERT, UPDATE, DELETE or REPLACE, described by the fields below instead of rows */
+  dml?: boolean;
+  /** AUTO_INCREMENT value generated by an INSERT */
+  last_insert_id?: number | null;
+  /** Warnings the server reported for an INSERT, UPDATE or DELETE */
+  warnings?: QueryWarning[];
+  /**
+   * Result sets after the first, from a script or a stored procedure call.
+   * `rows_affected` above counts the rows of every set.
+   */
+  more_results?: ResultSet[];
+}
+
+/** One of several result sets returned by a single execution */
+export interface ResultSet {
+  columns: string[];
+  column_types: ColumnTypeInfo[];
+  rows: Array<Array<any>>;
+  total_rows: number;
+  rows_affected: number;
+  /** Page through the full set with `fetchResultPage` */
+  result_id?: string | null;
+  page_size?: number | null;
+}
+
+/** A note or warning the server reported for a statement, from SHOW WARNINGS */
+export interface QueryWarning {
+  /** Note, Warning or Error */
+  level: string;
+  code: number;
+  message: string;
+}
+
+export type ParameterMode = 'In' | 'Out' | 'InOut';
+
+export interface ProcedureParameter {
+  name: string;
+  mode: ParameterMode;
+  /** Declared type, e.g. `decimal(10,2)` */
+  data_type: string;
+}
+
+/** Value of an OUT or INOUT parameter after a call */
+export interface ProcedureOutput {
+  name: string;
+  data_type: string;
+  value: unknown;
+}
+
+export interface ProcedureResult {
+  /** The procedure's result sets */
+  result: QueryResult;
+  /** One entry per OUT and INOUT parameter, in declaration order */
+  outputs: ProcedureOutput[];
+}
+
+export enum ValueKind {
+  Boolean = 'Boolean',
+  Integer = 'Integer',
+  Float = 'Float',
+  /** Sent as a string to keep its precision */
+  Decimal = 'Decimal',
+  Text = 'Text',
+  Enum = 'Enum',
+  Json = 'Json',
+  Binary = 'Binary',
+  Date = 'Date',
+  Time = 'Time',
+  DateTime = 'DateTime',
+  Year = 'Year',
+  Bit = 'Bit',
+  Geometry = 'Geometry',
+  Other = 'Other',
+}
+
+export interface ColumnTypeInfo {
+  name: string;
+  type_name: string;
+  kind: ValueKind;
+  /** Some values were cut to `max_cell_length`; `getCellValue` fetches one in full */
+  truncated?: boolean;
+  /** Values are hidden by the connection's masking rules; `revealMaskedCell` shows one */
+  masked?: boolean;
+}
+
+export enum ValidationSource {
+  Parser = 'Parser',
+  Server = 'Server',
+}
+
+/** A problem found in a statement without running it */
+export interface ValidationIssue {
+  message: string;
+  source: ValidationSource;
+  /** Byte offset into the validated SQL */
+  position: number | null;
+  /** 1-based line and column of `position`, counted in characters */
+  line: number | null;
+  column: number | null;
+  statement_start: number;
+  statement_end: number;
+  server_code: number | null;
+}
+
+export interface ValidationResult {
+  valid: boolean;
+  issues: ValidationIssue[];
+}
+
+export interface ResultSort {
+  column: string;
+  order: SortOrder;
+}
+
+/** Sort/filter a cached result without re-running the query */
+export interface ResultViewRequest {
+  result_id: string;
+  filters?: TableFilter[];
+  sort?: ResultSort[];
+  /** SQL expression like `filterResult` takes, e.g. `total > 100` */
+  expression?: string;
+  offset?: number;
+  limit: number;
+}
+
+export type ExportFormat = 'Parquet' | 'Markdown' | 'Html';
+
+/** Writes the rows of a cached result, not just the loaded pages */
+export interface ExportRequest {
+  result_id: string;
+  path: string;
+  format: ExportFormat;
+  /** Columns to export, in this order; all of them when empty */
+  columns?: string[];
+  row_limit?: number | null;
 }
 
 export interface QueryHistoryEntry {
@@ -22,3 +197,186 @@ export interface QueryHistoryEntry {
   success: boolean;
   error_message?: string;
 }
+
+/** A statement from the history matching `searchQueries` */
+export interface QuerySearchMatch {
+  /** The latest execution of the statement */
+  entry: QueryHistoryEntry;
+  /** The statement around the matched words, which are wrapped in « and » */
+  snippet: string;
+  /** Higher is a better match */
+  score: number;
+}
+
+/** Milliseconds since the epoch; a missing end is open */
+export interface TimeRange {
+  start?: number | null;
+  end?: number | null;
+}
+
+/** A statement normalized by the server, with its totals, from `getSlowQueries` */
+export interface SlowQuery {
+  database: string | null;
+  digest: string | null;
+  /** The statement with its literals replaced by `?` */
+  digest_text: string;
+  calls: number;
+  total_latency_ms: number;
+  mean_latency_ms: number;
+  max_latency_ms: number;
+  rows_examined: number;
+  rows_sent: number;
+  first_seen: string | null;
+  last_seen: string | null;
+}
+
+/** Recorded executions of one statement fingerprint against one database */
+export interface QueryStat {
+  fingerprint: string;
+  database: string | null;
+  executions: number;
+  failures: number;
+  total_time_ms: number;
+  average_time_ms: number;
+  max_time_ms: number;
+  last_executed_at: number;
+}
+
+export interface TableTime {
+  database: string | null;
+  table: string;
+  executions: number;
+  total_time_ms: number;
+}
+
+export interface QueryStats {
+  executions: number;
+  failures: number;
+  /** Share of executions that failed, from 0 to 1 */
+  error_rate: number;
+  total_time_ms: number;
+  slowest: QueryStat[];
+  most_frequent: QueryStat[];
+  /** A statement joining tables counts fully for each */
+  time_by_table: TableTime[];
+}
+
+export interface WatchdogConfig {
+  enabled: boolean;
+  threshold_secs: number;
+  auto_rollback: boolean;
+}
+
+/** Payload of the `session://idle-transaction` event */
+export interface IdleTransactionWarning {
+  session_id: string;
+  connection_id: string;
+  open_for_ms: number;
+  statements_in_transaction: string[];
+  rolled_back: boolean;
+}
+
+/** A statement's place in line for its connection, 0 once it starts */
+export interface QueuePosition {
+  connection_id: string;
+  request_id: string | null;
+  position: number;
+}
+
+/** One of the two queries compared by `diffResults` */
+export interface DiffSide {
+  connection_id: string;
+  database?: string | null;
+  sql: string;
+  params?: unknown[];
+}
+
+export interface DiffRequest {
+  left: DiffSide;
+  right: DiffSide;
+  /** Columns identifying a row on both sides; every column when empty */
+  key_columns?: string[];
+}
+
+/** A cell hidden by masking rules, to reveal */
+export type MaskedCell =
+  | { source: 'Result'; result_id: string; row: number; column: string }
+  | {
+      source: 'Table';
+      connection_id: string;
+      database: string;
+      table: string;
+      primary_key: Record<string, any>;
+      column: string;
+    };
+
+/** A row found on both sides with different values */
+export interface RowChange {
+  /** Values of the key columns */
+  key: unknown[];
+  left: unknown[];
+  right: unknown[];
+  changed_columns: string[];
+}
+
+/** Row-level differences between two results; rows hold the values of `columns` */
+export interface ResultDiff {
+  columns: string[];
+  key_columns: string[];
+  left_only_columns: string[];
+  right_only_columns: string[];
+  /** Rows only the right side has */
+  added: unknown[][];
+  /** Rows only the left side has */
+  removed: unknown[][];
+  changed: RowChange[];
+  unchanged: number;
+  /** A side hit the row limit, so rows past it weren't compared */
+  truncated: boolean;
+}
+
+/** A table's database on one of the connections `compareTableData` reads */
+export interface TableLocation {
+  connection_id: string;
+  database: string;
+}
+
+/** Report only, also return sync statements, or run them on the target */
+export type SyncMode = 'Report' | 'Generate' | 'Apply';
+
+export interface TableCompareRequest {
+  source: TableLocation;
+  target: TableLocation;
+  table: string;
+  /** Columns identifying a row on both sides; the source's primary key when empty */
+  key_columns?: string[];
+  mode?: SyncMode;
+  /** Required to apply; fails with `NEEDS_CONFIRMATION` until set */
+  confirmed?: boolean;
+  /** The target connection's name, when it fails with `NEEDS_TYPED_CONFIRMATION` */
+  confirmation?: string;
+}
+
+/** Changes that bring the target table in line with the source; binary values as hex */
+export interface TableDataDiff {
+  columns: string[];
+  key_columns: string[];
+  source_only_columns: string[];
+  target_only_columns: string[];
+  /** Source rows the target lacks */
+  inserted: unknown[][];
+  /** `left` holds the source's values, `right` the target's */
+  updated: RowChange[];
+  /** Target rows the source lacks */
+  deleted: unknown[][];
+  inserted_count: number;
+  updated_count: number;
+  deleted_count: number;
+  unchanged: number;
+  /** Only the first rows of each kind are listed; the counts cover all */
+  truncated: boolean;
+  /** Statements syncing the target, in `Generate` mode */
+  statements: string[];
+  /** Rows changed on the target, in `Apply` mode */
+  rows_affected: number | null;
+}
@@ -0,0 +1,35 @@
+import type { QueryResult } from './query';
+
+/** `OnChange` only delivers runs whose rows differ from the previous run */
+export type ScheduleDelivery = 'EveryRun' | 'OnChange';
+
+export interface ScheduleRequest {
+  connection_id: string;
+  database: string | null;
+  sql: string;
+  params?: unknown[];
+  /** At least 5 seconds */
+  interval_secs: number;
+  delivery?: ScheduleDelivery;
+}
+
+export interface ScheduledQuery {
+  id: string;
+  connection_id: string;
+  database: string | null;
+  sql: string;
+  params: unknown[];
+  interval_secs: number;
+  delivery: ScheduleDelivery;
+  last_run_at: number | null;
+  last_error: string | null;
+}
+
+export interface ScheduledResult {
+  schedule_id: string;
+  ran_at: number;
+  /** Null when the run failed */
+  result: QueryResult | null;
+  error: string | null;
+  changed: boolean;
+}
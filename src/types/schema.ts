@@ -1,7 +1,56 @@
+export interface FunctionInfo {
+  name: string;
+  /** e.g. `DATE_FORMAT(date, format)` */
+  signature: string;
+  description: string;
+}
+
 export interface AutocompleteData {
   tables: string[];
   columns_by_table: Record<string, string[]>;
   keywords: string[];
+  databases?: string[];
+  functions?: FunctionInfo[];
+}
+
+/** Payload of `autocomplete://updated`: tables that changed since the last load */
+export interface AutocompleteUpdate {
+  connection_id: string;
+  database: string;
+  added: string[];
+  changed: string[];
+  removed: string[];
+}
+
+export enum CompletionKind {
+  Column = 'Column',
+  Table = 'Table',
+  Alias = 'Alias',
+  Database = 'Database',
+  Keyword = 'Keyword',
+  Function = 'Function',
+}
+
+export interface Completion {
+  label: string;
+  kind: CompletionKind;
+  /** Where the suggestion comes from, e.g. `u: varchar(255)` for a column */
+  detail: string | null;
+}
+
+export interface ResolvedReference {
+  database: string;
+  table: string;
+  column?: string | null;
+}
+
+/** A column found by `findColumns` */
+export interface ColumnMatch {
+  database: string;
+  table: string;
+  column: string;
+  /** Full type, e.g. `int unsigned` or `varchar(255)` */
+  column_type: string;
 }
 
 export interface ColumnSchema {
@@ -10,6 +59,13 @@ export interface ColumnSchema {
   is_nullable: boolean;
   default_value?: string;
   max_length?: number;
+  /** Filled in by the database when left out of an insert */
+  is_auto_increment?: boolean;
+  /** Computed from other columns; can't be written */
+  is_generated?: boolean;
+  extra?: string;
+  /** Values an ENUM or SET column accepts */
+  allowed_values?: string[] | null;
 }
 
 export interface ForeignKey {
@@ -20,6 +76,8 @@ export interface ForeignKey {
 
 export interface TableSchema {
   name: string;
+  /** Schema the table is in, on servers whose databases hold schemas */
+  schema?: string | null;
   columns: ColumnSchema[];
   primary_keys: string[];
   foreign_keys: ForeignKey[];
@@ -27,4 +85,6 @@ export interface TableSchema {
 
 export interface Schema {
   tables: TableSchema[];
+  /** Schemas unqualified table names resolve to, in order; empty on MySQL */
+  search_path: string[];
 }
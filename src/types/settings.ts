@@ -0,0 +1,85 @@
+import type { LogLevel } from './log';
+
+export enum Theme {
+  System = 'System',
+  Light = 'Light',
+  Dark = 'Dark',
+}
+
+export interface EditorSettings {
+  font_size: number;
+  tab_size: number;
+  word_wrap: boolean;
+  autocomplete: boolean;
+}
+
+export interface ConfirmSettings {
+  delete_rows: boolean;
+  drop_objects: boolean;
+  truncate_tables: boolean;
+}
+
+export interface AuditSettings {
+  enabled: boolean;
+  /** Days of audit entries to keep; null keeps them forever */
+  retention_days: number | null;
+}
+
+/** What to do with a SELECT that could return a whole table */
+export enum LimitGuardMode {
+  Off = 'Off',
+  /** Run it as written, flagging the result */
+  Warn = 'Warn',
+  /** Append `LIMIT default_limit` */
+  Append = 'Append',
+}
+
+export interface LimitGuardSettings {
+  mode: LimitGuardMode;
+  default_limit: number;
+}
+
+/** Restrictions on every connection of an environment */
+export interface EnvironmentPolicy {
+  read_only: boolean;
+  /** Reject CREATE, ALTER, DROP and other schema changes */
+  block_ddl: boolean;
+  /** Append the limit guard's LIMIT whatever its mode */
+  force_limit: boolean;
+  /** Writes need the connection's name typed as confirmation */
+  confirm_writes: boolean;
+}
+
+/** Dev connections are unrestricted */
+export interface GuardrailSettings {
+  staging: EnvironmentPolicy;
+  prod: EnvironmentPolicy;
+}
+
+/**
+ * Vault server for connection secrets; empty values fall back to VAULT_ADDR etc.
+ * The token comes from VAULT_TOKEN or ~/.vault-token, never from the settings.
+ */
+export interface VaultSettings {
+  address: string | null;
+  namespace: string | null;
+}
+
+export interface AppSettings {
+  editor: EditorSettings;
+  default_page_size: number;
+  /** Text and JSON cells in listings are cut to this many characters; null shows them in full */
+  max_cell_length: number | null;
+  confirm: ConfirmSettings;
+  theme: Theme;
+  /** Days of query history to keep; null keeps it forever */
+  history_retention_days: number | null;
+  /** Least severe level written to the application log */
+  log_level: LogLevel;
+  audit: AuditSettings;
+  limit_guard: LimitGuardSettings;
+  guardrails: GuardrailSettings;
+  vault: VaultSettings;
+  /** Idle minutes before a master-password store locks; null never locks */
+  auto_lock_minutes: number | null;
+}
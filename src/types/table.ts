@@ -1,18 +1,54 @@
+import type { ColumnTypeInfo } from './query';
+
 export interface TableData {
   columns: string[];
   rows: Record<string, any>[];
   total_rows: number;
+  column_types?: ColumnTypeInfo[];
+  /** `total_rows` is the database's estimate rather than a count */
+  total_rows_estimated?: boolean;
+  /** The exact count is still running and arrives in a `TableRowCount` event */
+  total_rows_pending?: boolean;
+  request_id?: string | null;
+}
+
+/** Payload of the `table-data://count` event */
+/** Rows of a table for the sidebar */
+export interface TableRowTotal {
+  table: string;
+  /** null when the server keeps no statistics for it, e.g. for a view */
+  rows: number | null;
+  /** Whether `rows` was counted rather than estimated */
+  exact: boolean;
+  /** Known to hold no rows */
+  empty: boolean;
+}
+
+export interface TableRowCount {
+  request_id: string;
+  total_rows: number | null;
+  error: string | null;
+}
+
+/** How `total_rows` is counted; Auto estimates for very large tables */
+export enum RowCountMode {
+  Auto = 'Auto',
+  Exact = 'Exact',
+  Estimate = 'Estimate',
 }
 
 export interface TableDataRequest {
   connection_id: string;
   database: string;
+  /** Schema of the table within `database`, where databases hold schemas */
+  schema?: string;
   table: string;
   page: number;
   page_size: number;
   filters?: TableFilter[];
   sort_by?: string;
   sort_order?: SortOrder;
+  row_count?: RowCountMode;
 }
 
 export interface TableFilter {
@@ -41,19 +77,32 @@ export enum SortOrder {
   Desc = 'Desc',
 }
 
+/** A value written to a column by an insert or update; an Expression is raw SQL such as `NOW()` */
+export type CellValue =
+  | { kind: 'Null' }
+  | { kind: 'Default' }
+  | { kind: 'Expression'; value: string }
+  | { kind: 'Literal'; value: any };
+
 export interface InsertRowRequest {
   connection_id: string;
   database: string;
   table: string;
-  data: Record<string, any>;
+  data: Record<string, CellValue>;
 }
 
 export interface UpdateRowRequest {
   connection_id: string;
   database: string;
   table: string;
-  data: Record<string, any>;
+  data: Record<string, CellValue>;
   where_clause: Record<string, any>;
+  /**
+   * The row's values as they were read; the edit fails with `ROW_CONFLICT`
+   * when they no longer match. Cut, masked, binary and FLOAT or DOUBLE values
+   * are left out of the check.
+   */
+  expected_values?: Record<string, any> | null;
 }
 
 export interface DeleteRowRequest {
@@ -61,4 +110,128 @@ export interface DeleteRowRequest {
   database: string;
   table: string;
   where_clause: Record<string, any>;
+  /** See `UpdateRowRequest.expected_values` */
+  expected_values?: Record<string, any> | null;
+}
+
+/**
+ * A binary value in results. `content_type` is set for recognised images,
+ * and `data` (base64) when the image is small enough to preview.
+ */
+export interface BlobValue {
+  type: 'blob';
+  size: number;
+  /** Hex of the first bytes */
+  preview_hex: string;
+  content_type?: string;
+  data?: string;
+}
+
+/** A cell located by its row's primary key, and the file it's written to or read from */
+export interface CellFileRequest {
+  connection_id: string;
+  database: string;
+  table: string;
+  column: string;
+  primary_key: Record<string, any>;
+  path: string;
+}
+
+/**
+ * A column of a single fetched row, in full. JSON columns hold parsed JSON;
+ * binary columns hold text, or a `BlobValue` with all of its data.
+ */
+/** Fill a table with made-up rows */
+export interface SeedTableRequest {
+  connection_id: string;
+  database: string;
+  table: string;
+  row_count: number;
+  /** Rows per INSERT statement */
+  batch_size?: number;
+}
+
+/** What `copyTable` copies; `DataOnly` fills a table that already exists */
+export type CopyContent = 'StructureAndData' | 'StructureOnly' | 'DataOnly';
+
+export interface CopyTableOptions {
+  content?: CopyContent;
+  /** Name of the copy; the source table's when null */
+  target_table?: string | null;
+  /** Rows per INSERT statement */
+  batch_size?: number | null;
+}
+
+export interface CopyTableRequest {
+  source_connection_id: string;
+  source_database: string;
+  table: string;
+  target_connection_id: string;
+  target_database: string;
+  options?: CopyTableOptions;
+}
+
+/** `Ndjson` puts the header on the first line and each row on its own line */
+export type JsonLayout = 'Document' | 'Ndjson';
+
+export interface JsonExportRequest {
+  connection_id: string;
+  database: string;
+  table: string;
+  path: string;
+  layout?: JsonLayout;
+}
+
+export interface JsonImportRequest {
+  connection_id: string;
+  database: string;
+  table: string;
+  path: string;
+  /** Rows per INSERT statement */
+  batch_size?: number | null;
 }
+
+/** A row of the file that wasn't imported; `row` counts from 1 */
+export interface JsonRowError {
+  row: number;
+  column: string | null;
+  message: string;
+}
+
+export interface JsonImportReport {
+  imported: number;
+  failed: number;
+  /** Errors of the first failed rows */
+  errors: JsonRowError[];
+}
+
+/** A value of a column and the number of rows holding it */
+export interface ValueFrequency {
+  value: any;
+  count: number;
+}
+
+/** Statistics of the values in one column of a table */
+export interface ColumnProfile {
+  column: string;
+  total_rows: number;
+  null_count: number;
+  distinct_count: number;
+  min: any;
+  max: any;
+  /** Average length in characters of the non-NULL values as text */
+  avg_length: number | null;
+  /** Most common values, most frequent first */
+  top_values: ValueFrequency[];
+}
+
+export interface RowField {
+  name: string;
+  type_name: string;
+  value: any;
+  /** Size of a text or binary value in bytes */
+  size: number | null;
+}
+
+/** Statement `generateQueryTemplate` writes for a table */
+export type QueryTemplateKind = 'Select' | 'Insert' | 'Update' | 'Delete';
@@ -0,0 +1,20 @@
+export type TaskKind = 'Export' | 'Seed' | 'Copy';
+
+export type TaskStatus = 'Running' | 'Completed' | 'Failed' | 'Cancelled';
+
+/** A background task, as listed by `list_tasks` and pushed in `task://progress` events */
+export interface TaskInfo {
+  id: string;
+  kind: TaskKind;
+  /** What the task works on, e.g. the file an export writes */
+  description: string;
+  status: TaskStatus;
+  rows_processed: number;
+  total_rows: number | null;
+  bytes_processed: number;
+  /** Estimated time left, once enough progress was made to tell */
+  eta_ms: number | null;
+  started_at: number;
+  finished_at: number | null;
+  error: string | null;
+}
@@ -0,0 +1,55 @@
+import type { SortOrder, TableFilter } from './table';
+
+export enum TabKind {
+  Query = 'Query',
+  TableData = 'TableData',
+  TableStructure = 'TableStructure',
+}
+
+export interface TableBrowserState {
+  page: number;
+  page_size: number;
+  sort_by?: string | null;
+  sort_order?: SortOrder | null;
+  filters?: TableFilter[];
+}
+
+export interface WorkspaceTab {
+  id: string;
+  title: string;
+  kind: TabKind;
+  database?: string | null;
+  query_text?: string | null;
+  table?: string | null;
+  table_state?: TableBrowserState | null;
+  scroll_top?: number;
+}
+
+/** Open tabs of a connection, restored on the next start */
+export interface Workspace {
+  connection_id: string;
+  tabs: WorkspaceTab[];
+  active_tab_id?: string | null;
+}
+
+/** A table pinned to the top of a connection's sidebar */
+export interface PinnedTable {
+  connection_id: string;
+  database: string;
+  table: string;
+  pinned_at: number;
+}
+
+export type RecentObjectKind = 'Database' | 'Table' | 'View';
+
+/** A database, table or view recently opened on a connection */
+export interface RecentObject {
+  connection_id: string;
+  kind: RecentObjectKind;
+  database: string;
+  /** The table or view; null for a database */
+  name: string | null;
+  /** Milliseconds since the epoch */
+  opened_at: number;
+  open_count: number;
+}